@@ -0,0 +1,113 @@
+//! Scheduler baselines: sequential `step` against `step_parallel` on
+//! cascades of varying fan-out, and the cost of `frontier()` on a large
+//! graph.
+//!
+//! Run with: `cargo bench -p ultimate-engine --bench scheduler`
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+use ultimate_engine::causal::event::{Event, EventPayload};
+use ultimate_engine::causal::graph::CausalGraph;
+use ultimate_engine::causal::scheduler::Scheduler;
+use ultimate_engine::rules::RuleSet;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::position::BlockPos;
+use ultimate_engine::world::{World, WorldView};
+
+/// At most how many events each fan-out cascade runs, so the fan-outs
+/// differ in wavefront width rather than total work.
+const CASCADE_EVENTS: u64 = 20_000;
+
+/// Each notify at depth `y` spawns `K` notifies at depth `y + 1`, spread
+/// along x so every node has its own position (and chunk, further down).
+fn fan<const K: i64>(_world: &WorldView, payload: &EventPayload) -> Vec<Event> {
+    let EventPayload::BlockNotify { pos, .. } = payload else { return vec![] };
+    if pos.y >= depth(K) {
+        return vec![];
+    }
+    (0..K)
+        .map(|j| Event {
+            payload: EventPayload::BlockNotify { pos: BlockPos::new(pos.x * K + j, pos.y + 1, pos.z), from: None },
+        })
+        .collect()
+}
+
+/// The deepest `k`-ary tree holding at most `CASCADE_EVENTS` nodes.
+fn depth(k: i64) -> i64 {
+    let (mut nodes, mut level, mut depth) = (1u64, 1u64, 0);
+    loop {
+        level *= k as u64;
+        if nodes + level > CASCADE_EVENTS {
+            return depth;
+        }
+        nodes += level;
+        depth += 1;
+    }
+}
+
+fn fan_rules(k: i64) -> RuleSet {
+    let mut rules = RuleSet::new();
+    rules.add(match k {
+        2 => fan::<2>,
+        8 => fan::<8>,
+        32 => fan::<32>,
+        _ => fan::<128>,
+    });
+    rules
+}
+
+fn root() -> CausalGraph {
+    let mut graph = CausalGraph::with_pruning();
+    graph.insert_root(Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 0, 0), from: None } });
+    graph
+}
+
+fn step_vs_parallel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cascade");
+    let scheduler = Scheduler::new();
+    let world = World::new();
+    for k in [2, 8, 32, 128] {
+        let rules = fan_rules(k);
+        let events = scheduler.run_until_quiet(&world, &mut root(), &rules, usize::MAX);
+        group.throughput(Throughput::Elements(events as u64));
+        group.bench_with_input(BenchmarkId::new("step", k), &rules, |b, rules| {
+            b.iter(|| scheduler.run_until_quiet(&world, &mut root(), rules, usize::MAX));
+        });
+        group.bench_with_input(BenchmarkId::new("step_parallel", k), &rules, |b, rules| {
+            b.iter(|| scheduler.run_until_quiet_parallel(&world, &mut root(), rules, usize::MAX));
+        });
+    }
+    group.finish();
+}
+
+/// A chain of `executed` executed events with `pending` unexecuted roots
+/// beside it — the shape of an unpruned graph late in a long cascade.
+fn large_graph(executed: usize, pending: usize) -> CausalGraph {
+    let mut graph = CausalGraph::new();
+    let mut parent = None;
+    for i in 0..executed {
+        let event = Event { payload: EventPayload::BlockSet { pos: BlockPos::new(i as i64, 0, 0), old: BlockId::AIR, new: BlockId::new(1) } };
+        let id = graph.insert(event, parent.into_iter().collect());
+        graph.drain_ready(1);
+        graph.mark_executed(id);
+        parent = Some(id);
+    }
+    for i in 0..pending {
+        graph.insert_root(Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(i as i64, 1, 0), from: None } });
+    }
+    graph
+}
+
+fn frontier(c: &mut Criterion) {
+    let mut group = c.benchmark_group("frontier");
+    for executed in [1_000, 10_000, 100_000] {
+        let graph = large_graph(executed, 64);
+        group.bench_with_input(BenchmarkId::from_parameter(executed), &graph, |b, graph| {
+            b.iter(|| graph.frontier());
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step_vs_parallel, frontier);
+criterion_main!(benches);
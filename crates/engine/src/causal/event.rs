@@ -1,5 +1,5 @@
 use crate::world::block::BlockId;
-use crate::world::position::{BlockPos, ChunkPos};
+use crate::world::position::{BlockPos, ChunkPos, Direction};
 use slotmap::new_key_type;
 
 new_key_type! {
@@ -39,8 +39,11 @@ pub enum EventPayload {
         new: BlockId,
     },
 
-    /// A block's neighbors should be re-evaluated (after a nearby change).
-    BlockNotify { pos: BlockPos },
+    /// A block should be re-evaluated (after a nearby change). `from` is
+    /// the side of `pos` the change happened on, for rules that care which
+    /// way they were poked (observers, pistons, torches); `None` when the
+    /// sender didn't say.
+    BlockNotify { pos: BlockPos, from: Option<Direction> },
 
     /// A light value was set at a position.
     LightSet {
@@ -60,15 +63,26 @@ pub enum EventPayload {
 
     /// A position's light should be recalculated (a neighbor's light changed).
     LightNotify { pos: BlockPos },
+
+    /// An event defined outside the engine (entities, weather, redstone...).
+    /// The engine never interprets it: applying it writes nothing, and only
+    /// rules that recognise `kind` react to it. `pos` anchors it for
+    /// chunk routing and deferral; `data` is the kind's own encoding.
+    Custom {
+        kind: &'static str,
+        pos: BlockPos,
+        data: std::sync::Arc<[u8]>,
+    },
 }
 
 impl Event {
     pub fn positions(&self) -> Vec<BlockPos> {
         match &self.payload {
             EventPayload::BlockSet { pos, .. }
-            | EventPayload::BlockNotify { pos }
+            | EventPayload::BlockNotify { pos, .. }
             | EventPayload::LightSet { pos, .. }
-            | EventPayload::LightNotify { pos } => vec![*pos],
+            | EventPayload::LightNotify { pos }
+            | EventPayload::Custom { pos, .. } => vec![*pos],
             EventPayload::LightBatch { changes } => changes.iter().map(|c| c.pos).collect(),
         }
     }
@@ -77,9 +91,10 @@ impl Event {
     pub fn chunk(&self) -> ChunkPos {
         match &self.payload {
             EventPayload::BlockSet { pos, .. }
-            | EventPayload::BlockNotify { pos }
+            | EventPayload::BlockNotify { pos, .. }
             | EventPayload::LightSet { pos, .. }
-            | EventPayload::LightNotify { pos } => pos.chunk(),
+            | EventPayload::LightNotify { pos }
+            | EventPayload::Custom { pos, .. } => pos.chunk(),
             // A light flood spans chunks; its origin cell anchors it.
             EventPayload::LightBatch { changes } => changes
                 .first()
@@ -92,10 +107,11 @@ impl Event {
 /// Identity for an *idempotent* event that can be coalesced with other
 /// pending events of the same identity. Only returned for events whose
 /// semantics are "re-evaluate this position" — never for writes, whose
-/// identity depends on their value fields.
+/// identity depends on their value fields. Notifies from different sides
+/// stay apart so a directional rule sees each one.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum DedupKey {
-    BlockNotify(BlockPos),
+    BlockNotify(BlockPos, Option<Direction>),
     LightNotify(BlockPos),
 }
 
@@ -103,14 +119,15 @@ impl EventPayload {
     /// Returns a dedup key if this event can be coalesced with pending events
     /// of the same identity (idempotent re-evaluate-this-position events).
     /// Returns `None` for events whose identity depends on their payload
-    /// values (e.g., `BlockSet`, `LightSet`).
+    /// values (e.g., `BlockSet`, `LightSet`, `Custom`).
     pub fn dedup_key(&self) -> Option<DedupKey> {
         match self {
-            EventPayload::BlockNotify { pos } => Some(DedupKey::BlockNotify(*pos)),
+            EventPayload::BlockNotify { pos, from } => Some(DedupKey::BlockNotify(*pos, *from)),
             EventPayload::LightNotify { pos } => Some(DedupKey::LightNotify(*pos)),
             EventPayload::BlockSet { .. }
             | EventPayload::LightSet { .. }
-            | EventPayload::LightBatch { .. } => None,
+            | EventPayload::LightBatch { .. }
+            | EventPayload::Custom { .. } => None,
         }
     }
 }
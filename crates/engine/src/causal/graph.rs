@@ -1,9 +1,41 @@
 use super::event::{DedupKey, Event, EventId, EventPayload};
+use crate::world::position::{BlockPos, ChunkPos};
 use slotmap::SlotMap;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Which recently inserted events a graph remembers for snapshots
+/// ([`CausalGraph::recent_node_ids`], [`CausalGraph::recent_since`]).
+/// An event leaves the window when either limit is hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecentWindow {
+    /// The most recent events to keep; 0 turns tracking off.
+    pub max_nodes: usize,
+    /// Forget events older than this. Costs a clock read per insert, so
+    /// off unless set.
+    pub max_age: Option<Duration>,
+}
+
+impl Default for RecentWindow {
+    fn default() -> Self {
+        Self { max_nodes: 200, max_age: None }
+    }
+}
+
+/// One event in the recent window.
+#[derive(Debug, Clone, Copy)]
+struct Recent {
+    id: EventId,
+    /// `inserted_total` just after this event's insert.
+    seq: u64,
+    /// Insert time, when the window has a `max_age`.
+    at: Option<Instant>,
+}
 
-/// Maximum number of recent event IDs retained for dashboard snapshots.
-const MAX_RECENT: usize = 200;
+/// Deferred events held per unloaded chunk; later ones are dropped. A
+/// cascade lapping at a never-loaded border would otherwise grow the
+/// queue without bound.
+const MAX_DEFERRED_PER_CHUNK: usize = 4096;
 
 /// A node in the causal DAG.
 #[derive(Debug)]
@@ -21,6 +53,23 @@ pub struct EventNode {
     /// end-to-end.
     pub priority: u8,
     dedup_key: Option<DedupKey>,
+    /// Parent edges whose parent hasn't executed yet; the node is ready
+    /// at zero.
+    pending_parents: u32,
+    /// Whether the node sits in a ready lane, so it is queued once.
+    queued: bool,
+}
+
+/// An executed event moved out of the graph by
+/// [`CausalGraph::prune_executed`]: the event itself and its causal edges,
+/// without the bookkeeping a live node carries.
+#[derive(Debug, Clone)]
+pub struct ArchivedEvent {
+    pub event: Event,
+    /// Indices of this event's parents within the same archive. Parents
+    /// reaped by automatic pruning, or archived before the last
+    /// [`take_archive`](CausalGraph::take_archive), are omitted.
+    pub parents: Vec<u32>,
 }
 
 /// The causal graph: an append-only DAG of events.
@@ -37,7 +86,8 @@ pub struct EventNode {
 /// the existing event's parent set; no new node is created. This collapses
 /// the many-to-one fan-in common to neighbor-notification rules (a single
 /// position getting `BlockNotify`'d from each of its six neighbors becomes
-/// one notify event with six parents, not six duplicate events).
+/// one notify event with six parents, not six duplicate events). Directed
+/// notifies only coalesce with ones from the same side.
 ///
 /// Non-idempotent events (`BlockSet`, `LightSet`) whose identity depends
 /// on their value fields are never coalesced.
@@ -58,12 +108,22 @@ pub struct EventNode {
 /// graph also keeps a [`write_log`](CausalGraph::write_log): an
 /// execution-ordered list of effective world writes appended by the
 /// scheduler via [`log_write`](CausalGraph::log_write).
+///
+/// A graph built with plain `new()` can instead be compacted on demand
+/// with [`prune_executed`](CausalGraph::prune_executed), which keeps the
+/// most recently executed nodes live and moves older ones into a compact
+/// [`ArchivedEvent`] list with index edges.
 pub struct CausalGraph {
     nodes: SlotMap<EventId, EventNode>,
-    /// Ring buffer of the most recently inserted event IDs (for dashboard snapshots).
-    recent_ids: VecDeque<EventId>,
+    /// The most recently inserted events, oldest first (for dashboard
+    /// snapshots), bounded by `recent_window`.
+    recent: VecDeque<Recent>,
+    recent_window: RecentWindow,
     /// Incrementally-maintained ready queues: events whose parents are all
-    /// executed but which have not been executed themselves. Two lanes —
+    /// executed but which have not been executed themselves, fed by the
+    /// nodes' pending-parent counters so nothing rescans the graph. Entries
+    /// executed without being popped (callers that run `frontier()`
+    /// directly) linger until the next pop discards them. Two lanes —
     /// `drain_ready` empties the priority lane before touching the normal
     /// lane, so player-initiated cascades cut ahead of background physics
     /// (Phase 6d priority-aware draining).
@@ -95,13 +155,28 @@ pub struct CausalGraph {
     /// the peak causal wavefront width; without, it ends equal to
     /// `inserted_total` minus dedup merges.
     peak_len: usize,
+    /// Events parked because their chunk was unloaded (or inactive) when
+    /// they were produced, with their inherited priority. Re-enter as roots
+    /// once the chunk is present (see `Scheduler::with_unloaded_deferral`
+    /// and `Scheduler::with_activity`).
+    deferred: HashMap<ChunkPos, Vec<(Event, u8)>>,
+    deferred_dropped: u64,
+    /// Execution order of live executed nodes, oldest first, for
+    /// `prune_executed`. Not kept under automatic pruning.
+    executed_order: VecDeque<EventId>,
+    /// Nodes compacted out by `prune_executed`, in execution order.
+    archive: Vec<ArchivedEvent>,
+    /// Archive index of each archived node, for resolving later
+    /// archived children's parent edges. Cleared by `take_archive`.
+    archive_ids: HashMap<EventId, u32>,
 }
 
 impl CausalGraph {
     pub fn new() -> Self {
         Self {
             nodes: SlotMap::with_key(),
-            recent_ids: VecDeque::with_capacity(MAX_RECENT),
+            recent: VecDeque::new(),
+            recent_window: RecentWindow::default(),
             ready_high: VecDeque::new(),
             ready_norm: VecDeque::new(),
             pending: HashMap::new(),
@@ -113,6 +188,11 @@ impl CausalGraph {
             same_chunk_edges: 0,
             cross_chunk_edges: 0,
             peak_len: 0,
+            deferred: HashMap::new(),
+            deferred_dropped: 0,
+            executed_order: VecDeque::new(),
+            archive: Vec::new(),
+            archive_ids: HashMap::new(),
         }
     }
 
@@ -126,6 +206,17 @@ impl CausalGraph {
         g
     }
 
+    /// Replace the recent-event window (default: the last 200 events).
+    pub fn with_recent_window(mut self, window: RecentWindow) -> Self {
+        self.recent_window = window;
+        self.recent.clear();
+        self
+    }
+
+    pub fn recent_window(&self) -> RecentWindow {
+        self.recent_window
+    }
+
     /// Is `id` executed? Missing nodes count as executed: ids never leave
     /// the graph except by reaping, and only executed nodes are reaped.
     fn is_executed(&self, id: EventId) -> bool {
@@ -159,9 +250,13 @@ impl CausalGraph {
                         .map(|n| n.event.chunk());
                     for &parent_id in &parents {
                         let mut added = false;
+                        let parent_done = self.is_executed(parent_id);
                         if let Some(existing) = self.nodes.get_mut(existing_id) {
                             if !existing.parents.contains(&parent_id) {
                                 existing.parents.push(parent_id);
+                                if !parent_done {
+                                    existing.pending_parents += 1;
+                                }
                                 added = true;
                             }
                             // Escalate: a priority cascade merging into a
@@ -189,7 +284,7 @@ impl CausalGraph {
             }
         }
 
-        let all_parents_done = parents.iter().all(|p| self.is_executed(*p));
+        let pending_parents = parents.iter().filter(|p| !self.is_executed(**p)).count() as u32;
 
         self.inserted_total += 1;
         let child_chunk = Some(event.chunk());
@@ -200,6 +295,8 @@ impl CausalGraph {
             executed: false,
             priority,
             dedup_key,
+            pending_parents,
+            queued: false,
         });
         self.peak_len = self.peak_len.max(self.nodes.len());
 
@@ -210,18 +307,14 @@ impl CausalGraph {
             self.count_edge_locality(parent_id, child_chunk);
         }
 
-        // Track for dashboard snapshots.
-        self.recent_ids.push_back(id);
-        if self.recent_ids.len() > MAX_RECENT {
-            self.recent_ids.pop_front();
-        }
+        self.track_recent(id);
 
         if let Some(key) = dedup_key {
             self.pending.insert(key, id);
         }
 
-        if all_parents_done {
-            self.push_ready(id, priority);
+        if pending_parents == 0 {
+            self.push_ready(id);
         }
 
         id
@@ -235,97 +328,123 @@ impl CausalGraph {
         self.insert_with_priority(event, Vec::new(), priority)
     }
 
+    /// Add a new node to the recent window, dropping what falls out.
+    fn track_recent(&mut self, id: EventId) {
+        let window = self.recent_window;
+        if window.max_nodes == 0 {
+            return;
+        }
+        let at = window.max_age.map(|_| Instant::now());
+        self.recent.push_back(Recent { id, seq: self.inserted_total, at });
+        if self.recent.len() > window.max_nodes {
+            self.recent.pop_front();
+        }
+        if let (Some(max_age), Some(now)) = (window.max_age, at) {
+            while self.recent.front().is_some_and(|r| r.at.is_some_and(|t| now - t > max_age)) {
+                self.recent.pop_front();
+            }
+        }
+    }
+
+    /// The window's entries that haven't aged out by now.
+    fn recent_live(&self) -> impl Iterator<Item = &Recent> + '_ {
+        let cutoff = self.recent_window.max_age.and_then(|age| Instant::now().checked_sub(age));
+        self.recent.iter().filter(move |r| match (cutoff, r.at) {
+            (Some(cutoff), Some(at)) => at >= cutoff,
+            _ => true,
+        })
+    }
+
+    /// Queue `id` in its priority's lane unless it is already queued.
     #[inline]
-    fn push_ready(&mut self, id: EventId, priority: u8) {
-        if priority > 0 {
+    fn push_ready(&mut self, id: EventId) {
+        let Some(node) = self.nodes.get_mut(id) else { return };
+        if node.queued {
+            return;
+        }
+        node.queued = true;
+        if node.priority > 0 {
             self.ready_high.push_back(id);
         } else {
             self.ready_norm.push_back(id);
         }
     }
 
-    /// Drain up to `limit` ready events from the incremental queue.
-    ///
-    /// Re-checks `parents.all(executed)` at pop time because dedup merges
-    /// can add unfinished parents to an already-ready event, regressing
-    /// readiness. Skipped events are dropped from the queue; when their
-    /// remaining parents eventually execute, `mark_executed` will re-enqueue
-    /// them.
+    /// Drain up to `limit` ready events from the incremental queue, in
+    /// [`pop_frontier`](Self::pop_frontier) order.
     pub fn drain_ready(&mut self, limit: usize) -> Vec<EventId> {
         let mut batch = Vec::new();
         while batch.len() < limit {
+            match self.pop_frontier() {
+                Some(id) => batch.push(id),
+                None => break,
+            }
+        }
+        batch
+    }
+
+    /// Take the next ready event off the incremental queue, or `None` when
+    /// nothing is ready.
+    ///
+    /// Readiness is re-checked at pop time because dedup merges can add
+    /// unfinished parents to an already-queued event. Such an event is
+    /// dropped from the queue; `mark_executed` re-enqueues it when its
+    /// pending-parent count returns to zero.
+    pub fn pop_frontier(&mut self) -> Option<EventId> {
+        loop {
             // Priority lane first: player cascades cut ahead of background
             // physics among spacelike-separated (causally unordered) events.
-            let id = match self.ready_high.pop_front().or_else(|| self.ready_norm.pop_front()) {
-                Some(id) => id,
-                None => break,
-            };
-            let ready = match self.nodes.get(id) {
-                Some(node) => {
-                    !node.executed
-                        && node.parents.iter().all(|p|
-                            self.nodes.get(*p).is_none_or(|n| n.executed)
-                        )
-                }
-                None => false,
-            };
-            if !ready {
+            let id = self.ready_high.pop_front().or_else(|| self.ready_norm.pop_front())?;
+            let Some(node) = self.nodes.get_mut(id) else { continue };
+            node.queued = false;
+            if node.executed || node.pending_parents > 0 {
                 continue;
             }
             // Clear from pending: once an event is about to execute, new
             // inserts with the same key must create a fresh event (not merge
             // into this one, which is mid-flight).
-            if let Some(node) = self.nodes.get(id) {
-                if let Some(key) = node.dedup_key {
-                    if self.pending.get(&key) == Some(&id) {
-                        self.pending.remove(&key);
-                    }
-                }
+            if let Some(key) = node.dedup_key
+                && self.pending.get(&key) == Some(&id)
+            {
+                self.pending.remove(&key);
             }
-            batch.push(id);
+            return Some(id);
         }
-        batch
     }
 
-    /// The "frontier": all events whose parents have all been executed,
-    /// but which have not been executed themselves.  Full scan — kept for
-    /// tests and debugging; the scheduler uses `drain_ready` instead.
+    /// The "frontier": all queued events whose parents have all been
+    /// executed but which have not been executed themselves, priority lane
+    /// first. Reads the ready queues rather than scanning the graph; events
+    /// already handed out by `drain_ready`/`pop_frontier` are not included.
     pub fn frontier(&self) -> Vec<EventId> {
-        self.nodes
+        self.ready_high
             .iter()
-            .filter(|(_, node)| {
-                !node.executed
-                    && node
-                        .parents
-                        .iter()
-                        .all(|p| self.nodes.get(*p).is_none_or(|n| n.executed))
-            })
-            .map(|(id, _)| id)
+            .chain(&self.ready_norm)
+            .copied()
+            .filter(|&id| self.nodes.get(id).is_some_and(|n| !n.executed && n.pending_parents == 0))
             .collect()
     }
 
     pub fn mark_executed(&mut self, id: EventId) {
         let (children, parents) = match self.nodes.get_mut(id) {
-            Some(node) => {
-                if !node.executed {
-                    node.executed = true;
-                    self.executed_total += 1;
+            Some(node) if !node.executed => {
+                node.executed = true;
+                self.executed_total += 1;
+                if !self.prune {
+                    self.executed_order.push_back(id);
                 }
                 (node.children.clone(), node.parents.clone())
             }
-            None => return,
+            _ => return,
         };
 
+        // One decrement per edge: `children` repeats a child exactly as
+        // often as that child lists this node among its parents.
         for child_id in children {
-            if let Some(child) = self.nodes.get(child_id) {
-                if !child.executed
-                    && child.parents.iter().all(|p|
-                        self.nodes.get(*p).is_none_or(|n| n.executed)
-                    )
-                {
-                    let prio = child.priority;
-                    self.push_ready(child_id, prio);
-                }
+            let Some(child) = self.nodes.get_mut(child_id) else { continue };
+            child.pending_parents = child.pending_parents.saturating_sub(1);
+            if child.pending_parents == 0 && !child.executed {
+                self.push_ready(child_id);
             }
         }
 
@@ -349,32 +468,103 @@ impl CausalGraph {
         }
     }
 
+    /// Drop every event that hasn't executed, returning how many. What
+    /// already executed stays applied; nothing queued behind it runs.
+    /// Deferred events are kept — they wait on a chunk, not on a cascade.
+    /// This is how a runaway cascade is aborted.
+    pub fn abandon_pending(&mut self) -> usize {
+        let unexecuted: Vec<EventId> = self.nodes.iter().filter(|(_, n)| !n.executed).map(|(id, _)| id).collect();
+        for &id in &unexecuted {
+            self.nodes.remove(id);
+        }
+        self.ready_high.clear();
+        self.ready_norm.clear();
+        self.pending.clear();
+        // Executed nodes waiting on the dropped children are done now.
+        if self.prune {
+            for id in self.nodes.keys().collect::<Vec<_>>() {
+                self.try_reap(id);
+            }
+        }
+        unexecuted.len()
+    }
+
     /// Reap `id` if it is executed and all of its children are executed.
     /// Children of a reaped node hold a dangling parent id, which readiness
     /// checks treat as executed — valid precisely because the reap
     /// condition guarantees no unexecuted child exists.
     fn try_reap(&mut self, id: EventId) {
-        let reapable = match self.nodes.get(id) {
-            Some(node) => {
-                node.executed && node.children.iter().all(|c| self.is_executed(*c))
-            }
-            None => false,
-        };
-        if !reapable {
-            return;
+        if self.is_reapable(id) {
+            self.remove_node(id);
         }
-        let node = self.nodes.remove(id).expect("checked above");
+    }
+
+    fn is_reapable(&self, id: EventId) -> bool {
+        self.nodes
+            .get(id)
+            .is_some_and(|node| node.executed && node.children.iter().all(|c| self.is_executed(*c)))
+    }
+
+    fn remove_node(&mut self, id: EventId) -> EventNode {
+        let node = self.nodes.remove(id).expect("caller checked the node exists");
         if let Some(key) = node.dedup_key {
             if self.pending.get(&key) == Some(&id) {
                 self.pending.remove(&key);
             }
         }
         self.reaped_total += 1;
+        node
+    }
+
+    /// Compact a graph without automatic pruning: every executed node
+    /// except the `keep_last_n` most recently executed is moved into the
+    /// [`archive`](Self::archive), provided all its children have executed
+    /// too (the same condition automatic pruning uses). Nodes still waiting
+    /// on children stay live and are reconsidered on the next call.
+    /// Returns the number of nodes archived.
+    ///
+    /// Long-running simulation cascades on an inspectable graph call this
+    /// periodically so memory tracks `keep_last_n` plus the wavefront,
+    /// while the archive keeps what ran for the dashboard or a journal.
+    pub fn prune_executed(&mut self, keep_last_n: usize) -> usize {
+        let excess = self.executed_order.len().saturating_sub(keep_last_n);
+        let candidates: Vec<EventId> = self.executed_order.drain(..excess).collect();
+        let mut waiting = Vec::new();
+        let mut archived = 0;
+        // Execution order: a node's archived parents already have indices.
+        for id in candidates {
+            if self.is_reapable(id) {
+                let node = self.remove_node(id);
+                let parents = node.parents.iter().filter_map(|p| self.archive_ids.get(p).copied()).collect();
+                self.archive_ids.insert(id, self.archive.len() as u32);
+                self.archive.push(ArchivedEvent { event: node.event, parents });
+                archived += 1;
+            } else if self.nodes.contains_key(id) {
+                waiting.push(id);
+            }
+        }
+        for id in waiting.into_iter().rev() {
+            self.executed_order.push_front(id);
+        }
+        archived
+    }
+
+    /// Events compacted out by [`prune_executed`](Self::prune_executed),
+    /// in execution order.
+    pub fn archive(&self) -> &[ArchivedEvent] {
+        &self.archive
+    }
+
+    /// Drain the archive. Parent edges from later archived events to
+    /// anything drained here are dropped.
+    pub fn take_archive(&mut self) -> Vec<ArchivedEvent> {
+        self.archive_ids.clear();
+        std::mem::take(&mut self.archive)
     }
 
     /// Append an *effective* world write to the execution-ordered log.
     /// Only write payloads (`BlockSet`, `LightSet`) are retained; notify
-    /// events are ignored.
+    /// and custom events are ignored.
     pub fn log_write(&mut self, payload: &EventPayload) {
         match payload {
             EventPayload::BlockSet { .. }
@@ -382,7 +572,9 @@ impl CausalGraph {
             | EventPayload::LightBatch { .. } => {
                 self.write_log.push(payload.clone());
             }
-            EventPayload::BlockNotify { .. } | EventPayload::LightNotify { .. } => {}
+            EventPayload::BlockNotify { .. }
+            | EventPayload::LightNotify { .. }
+            | EventPayload::Custom { .. } => {}
         }
     }
 
@@ -392,6 +584,58 @@ impl CausalGraph {
         &self.write_log
     }
 
+    /// Park `event` until its chunk loads. It loses its causal parents —
+    /// which have executed by the time a consequent exists — and comes back
+    /// as a root at `priority` via [`release_deferred`](Self::release_deferred).
+    pub fn defer(&mut self, event: Event, priority: u8) {
+        let queue = self.deferred.entry(event.chunk()).or_default();
+        if queue.len() < MAX_DEFERRED_PER_CHUNK {
+            queue.push((event, priority));
+        } else {
+            self.deferred_dropped += 1;
+        }
+    }
+
+    /// Insert, as roots, every deferred event whose chunk `is_loaded` now
+    /// reports present. Returns how many were released.
+    pub fn release_deferred(&mut self, is_loaded: impl Fn(ChunkPos) -> bool) -> usize {
+        if self.deferred.is_empty() {
+            return 0;
+        }
+        let ready: Vec<ChunkPos> = self.deferred.keys().copied().filter(|&c| is_loaded(c)).collect();
+        let mut released = 0;
+        for chunk in ready {
+            for (event, priority) in self.deferred.remove(&chunk).unwrap_or_default() {
+                self.insert_root_with_priority(event, priority);
+                released += 1;
+            }
+        }
+        released
+    }
+
+    /// Remove every parked event, by chunk, for a caller that keeps them
+    /// elsewhere (a store that outlives the graph, say) and re-inserts
+    /// them as roots itself.
+    pub fn take_deferred(&mut self) -> HashMap<ChunkPos, Vec<(Event, u8)>> {
+        std::mem::take(&mut self.deferred)
+    }
+
+    /// Events currently parked waiting for their chunk to load.
+    pub fn deferred_len(&self) -> usize {
+        self.deferred.values().map(Vec::len).sum()
+    }
+
+    /// Chunks with events parked on them.
+    pub fn deferred_chunks(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.deferred.keys().copied()
+    }
+
+    /// Lifetime number of events dropped because their chunk's deferral
+    /// queue was full.
+    pub fn deferred_dropped(&self) -> u64 {
+        self.deferred_dropped
+    }
+
     /// Drain the write log, returning everything logged since the last
     /// drain. A long-lived graph (the shared physics graph) must consume
     /// its log per processing batch or it grows without bound.
@@ -463,9 +707,61 @@ impl CausalGraph {
         self.nodes.keys().collect()
     }
 
-    /// Iterator over the most recently inserted event IDs (for dashboard snapshots).
+    /// The event IDs in the recent window, oldest first (for dashboard
+    /// snapshots). Reaped events stay listed; `get` returns `None` for
+    /// them.
     pub fn recent_node_ids(&self) -> impl Iterator<Item = EventId> + '_ {
-        self.recent_ids.iter().copied()
+        self.recent_live().map(|r| r.id)
+    }
+
+    /// Position for [`recent_since`](Self::recent_since): pass the value
+    /// from the previous snapshot to get only what was inserted after it.
+    pub fn recent_cursor(&self) -> u64 {
+        self.inserted_total
+    }
+
+    /// Recent-window events inserted after `cursor` (a previous
+    /// [`recent_cursor`](Self::recent_cursor)), oldest first. Events that
+    /// already left the window are gone.
+    pub fn recent_since(&self, cursor: u64) -> impl Iterator<Item = EventId> + '_ {
+        self.recent_live().filter(move |r| r.seq > cursor).map(|r| r.id)
+    }
+
+    /// Every live event `id` transitively depends on, nearest first
+    /// (breadth-first over parent edges). Reaped or archived ancestors, and
+    /// anything only reachable through them, are not included.
+    pub fn ancestors(&self, id: EventId) -> Vec<EventId> {
+        self.walk(id, |node| &node.parents)
+    }
+
+    /// Every live event `id` transitively caused, nearest first — its
+    /// blast radius so far.
+    pub fn descendants(&self, id: EventId) -> Vec<EventId> {
+        self.walk(id, |node| &node.children)
+    }
+
+    /// Breadth-first over `edges`, excluding `start` itself.
+    fn walk(&self, start: EventId, edges: impl Fn(&EventNode) -> &Vec<EventId>) -> Vec<EventId> {
+        let mut seen: HashSet<EventId> = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+        let mut out = Vec::new();
+        while let Some(id) = queue.pop_front() {
+            let Some(node) = self.nodes.get(id) else { continue };
+            for &next in edges(node) {
+                if self.nodes.contains_key(next) && seen.insert(next) {
+                    out.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        out
+    }
+
+    /// Every block position a live event touches, executed or not.
+    /// Combine with [`descendants`](Self::descendants) for one event's
+    /// footprint.
+    pub fn affected_positions(&self) -> HashSet<BlockPos> {
+        self.nodes.values().flat_map(|node| node.event.positions()).collect()
     }
 
     /// Export the graph in Graphviz DOT format.
@@ -480,8 +776,11 @@ impl CausalGraph {
                     format!("Set ({},{},{})\\n-> {:?}", pos.x, pos.y, pos.z, new),
                     "#d4edda",
                 ),
-                EventPayload::BlockNotify { pos } => (
-                    format!("Notify ({},{},{})", pos.x, pos.y, pos.z),
+                EventPayload::BlockNotify { pos, from } => (
+                    match from {
+                        Some(dir) => format!("Notify ({},{},{})\\nfrom {:?}", pos.x, pos.y, pos.z, dir),
+                        None => format!("Notify ({},{},{})", pos.x, pos.y, pos.z),
+                    },
                     "#fff3cd",
                 ),
                 EventPayload::LightSet { pos, light_type, new, .. } => (
@@ -496,6 +795,10 @@ impl CausalGraph {
                     format!("LightBatch ({} cells)", changes.len()),
                     "#cce5ff",
                 ),
+                EventPayload::Custom { kind, pos, data } => (
+                    format!("{} ({},{},{})\\n{} bytes", kind, pos.x, pos.y, pos.z, data.len()),
+                    "#f5d0fe",
+                ),
             };
             let fill = if node.executed { color } else { "#f8f9fa" };
             out.push_str(&format!(
@@ -1,38 +1,163 @@
 use super::event::{Event, EventId, EventPayload};
 use super::graph::CausalGraph;
 use crate::rules::RuleSet;
-use crate::world::World;
+use crate::world::{World, WorldView};
+use crate::world::block::BlockId;
+use crate::world::position::{BlockPos, ChunkPos};
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "parallel")]
+use std::time::Instant;
+
+/// Waves smaller than this run `step_stealing`'s queues on the calling
+/// thread: below it, waking pool threads costs more than the events do.
+const INLINE_WAVE: usize = 64;
+
+/// Whether a chunk may simulate; see [`Scheduler::with_activity`].
+pub type ChunkActivity = Arc<dyn Fn(ChunkPos) -> bool + Send + Sync>;
 
 /// Drains the causal frontier, applying events to the world and generating
 /// consequent events via the rule set.
 ///
-/// Provides both sequential (`step`) and parallel (`step_parallel`) execution.
+/// Provides sequential (`step`) and parallel (`step_parallel`,
+/// `step_stealing`) execution. Without the `parallel` feature the parallel
+/// steps still group by chunk but run the groups one after another, so
+/// callers need no cfg of their own.
 pub struct Scheduler {
     pub max_events_per_step: usize,
+    /// Pool that `step_parallel` fans out on. `None` = rayon's global pool.
+    #[cfg(feature = "parallel")]
+    pool: Option<Arc<rayon::ThreadPool>>,
+    /// Accumulates nanoseconds spent executing chunk groups across all
+    /// pool threads (divide by threads × wall time for utilization).
+    #[cfg(feature = "parallel")]
+    busy_ns: Option<Arc<AtomicU64>>,
+    /// Counts `BlockSet`s dropped because the cell no longer held their
+    /// `old` value when they ran.
+    conflicts: Option<Arc<AtomicU64>>,
+    /// Run `step_parallel` groups in chunk order rather than hash order.
+    deterministic: bool,
+    /// Park consequents aimed at unloaded chunks instead of executing them.
+    defer_unloaded: bool,
+    /// Park consequents aimed at chunks this reports inactive.
+    active: Option<ChunkActivity>,
 }
 
 impl Scheduler {
     pub fn new() -> Self {
         Self {
             max_events_per_step: 10_000,
+            #[cfg(feature = "parallel")]
+            pool: None,
+            #[cfg(feature = "parallel")]
+            busy_ns: None,
+            conflicts: None,
+            deterministic: false,
+            defer_unloaded: false,
+            active: None,
+        }
+    }
+
+    /// Run parallel steps on a dedicated pool instead of the global one,
+    /// so cascades can't oversubscribe cores shared with other runtimes.
+    #[cfg(feature = "parallel")]
+    pub fn with_pool(mut self, pool: Arc<rayon::ThreadPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Add the time spent executing each parallel chunk group to `counter`.
+    #[cfg(feature = "parallel")]
+    pub fn with_busy_counter(mut self, counter: Arc<AtomicU64>) -> Self {
+        self.busy_ns = Some(counter);
+        self
+    }
+
+    /// Count stale `BlockSet`s (see [`apply_event`](Self::apply_event)) in
+    /// `counter`.
+    pub fn with_conflict_counter(mut self, counter: Arc<AtomicU64>) -> Self {
+        self.conflicts = Some(counter);
+        self
+    }
+
+    /// Order `step_parallel`'s chunk groups by chunk position (events
+    /// within a group keep their ready-queue order) instead of hash-map
+    /// order, which varies run to run. Consequents are then inserted in
+    /// the same order every run, so the whole cascade replays identically
+    /// given the same roots — for benchmarks and invariance tests that
+    /// compare runs across machines. Groups still execute concurrently;
+    /// only the grouping and gather order are fixed.
+    pub fn with_deterministic_order(mut self) -> Self {
+        self.deterministic = true;
+        self
+    }
+
+    /// Border-safe mode. `World::get_block` reads unloaded chunks as air,
+    /// so at a load boundary fluids "flow into" and blocks "fall into"
+    /// space that isn't there — and the write materializes an empty chunk
+    /// that generation then skips. With this set, a `BlockSet` or
+    /// `BlockNotify` consequent whose chunk isn't loaded is parked in the
+    /// graph (`CausalGraph::defer`) and re-enters as a root at the start
+    /// of the first step after the chunk loads. A parked `BlockSet` keeps
+    /// its observed `old`, so if the loaded terrain differs the
+    /// stale-precondition guard drops it.
+    pub fn with_unloaded_deferral(mut self) -> Self {
+        self.defer_unloaded = true;
+        self
+    }
+
+    /// Simulation distance. Consequent `BlockSet`s and `BlockNotify`s
+    /// aimed at a chunk `active` rejects are parked exactly as border-safe
+    /// mode parks them for unloaded chunks, and released once the chunk
+    /// is active (and, under border-safe mode, loaded). Roots always run:
+    /// only cascades stop at the edge of the simulated area.
+    pub fn with_activity(mut self, active: ChunkActivity) -> Self {
+        self.active = Some(active);
+        self
+    }
+
+    /// Whether consequents aimed at `chunk` may run now.
+    fn runnable(&self, world: &World, chunk: ChunkPos) -> bool {
+        (!self.defer_unloaded || world.has_chunk(chunk)) && self.active.as_ref().is_none_or(|active| active(chunk))
+    }
+
+    /// Release deferred events whose chunks have loaded (or become active)
+    /// since the last step.
+    fn release_loaded(&self, world: &World, graph: &mut CausalGraph) {
+        if self.defer_unloaded || self.active.is_some() {
+            graph.release_deferred(|chunk| self.runnable(world, chunk));
+        }
+    }
+
+    /// Insert `event` as a consequent of `parent`, or park it if it targets
+    /// an unloaded chunk under border-safe mode or an inactive one.
+    fn admit(&self, world: &World, graph: &mut CausalGraph, event: Event, parent: EventId, priority: u8) {
+        let border = matches!(event.payload, EventPayload::BlockSet { .. } | EventPayload::BlockNotify { .. })
+            && !self.runnable(world, event.chunk());
+        if border {
+            graph.defer(event, priority);
+        } else {
+            graph.insert(event, vec![parent]);
         }
     }
 
     // ── Sequential execution ────────────────────────────────────────────
 
     pub fn step(&self, world: &World, graph: &mut CausalGraph, rules: &RuleSet) -> usize {
+        self.release_loaded(world, graph);
         let batch = graph.drain_ready(self.max_events_per_step);
         let mut executed = 0;
 
         for id in batch {
-            let event = match graph.get(id) {
-                Some(node) => node.event.clone(),
+            let (event, priority) = match graph.get(id) {
+                Some(node) => (node.event.clone(), node.priority),
                 None => continue,
             };
 
-            let effective = apply_event(world, &event.payload);
+            let effective = self.apply_event(world, &event.payload);
             graph.mark_executed(id);
             executed += 1;
 
@@ -40,9 +165,9 @@ impl Scheduler {
                 graph.log_write(&event.payload);
             }
             if effective {
-                let consequents = rules.evaluate(world, &event.payload);
+                let consequents = rules.evaluate(&world.view(), &event.payload);
                 for new_event in consequents {
-                    graph.insert(new_event, vec![id]);
+                    self.admit(world, graph, new_event, id, priority);
                 }
             }
             // All consequents are in; a pruning graph may now reap this
@@ -74,6 +199,7 @@ impl Scheduler {
         rules: &RuleSet,
         route: &mut dyn FnMut(&Event, u8) -> bool,
     ) -> usize {
+        self.release_loaded(world, graph);
         let batch = graph.drain_ready(self.max_events_per_step);
         let mut executed = 0;
 
@@ -83,7 +209,7 @@ impl Scheduler {
                 None => continue,
             };
 
-            let effective = apply_event(world, &event.payload);
+            let effective = self.apply_event(world, &event.payload);
             graph.mark_executed(id);
             executed += 1;
 
@@ -91,10 +217,10 @@ impl Scheduler {
                 graph.log_write(&event.payload);
             }
             if effective {
-                let consequents = rules.evaluate(world, &event.payload);
+                let consequents = rules.evaluate(&world.view(), &event.payload);
                 for new_event in consequents {
                     if route(&new_event, priority) {
-                        graph.insert(new_event, vec![id]);
+                        self.admit(world, graph, new_event, id, priority);
                     }
                 }
             }
@@ -125,6 +251,7 @@ impl Scheduler {
     // ── Parallel execution (snapshot-scatter-gather) ────────────────────
 
     pub fn step_parallel(&self, world: &World, graph: &mut CausalGraph, rules: &RuleSet) -> usize {
+        self.release_loaded(world, graph);
         let batch = graph.drain_ready(self.max_events_per_step);
         if batch.is_empty() {
             return 0;
@@ -142,36 +269,25 @@ impl Scheduler {
                 .or_default()
                 .push((id, event));
         }
-        let groups: Vec<Vec<(EventId, Event)>> = chunk_groups.into_values().collect();
-
-        let results: Vec<Vec<(EventId, Event, bool, Vec<Event>)>> = groups
-            .into_par_iter()
-            .map(|group| {
-                group
-                    .into_iter()
-                    .map(|(id, event)| {
-                        let effective = apply_event(world, &event.payload);
-                        let consequents = if effective {
-                            rules.evaluate(world, &event.payload)
-                        } else {
-                            Vec::new()
-                        };
-                        (id, event, effective, consequents)
-                    })
-                    .collect()
-            })
-            .collect();
+        let mut groups: Vec<_> = chunk_groups.into_iter().collect();
+        if self.deterministic {
+            groups.sort_unstable_by_key(|(chunk, _)| *chunk);
+        }
+        let groups: Vec<Vec<(EventId, Event)>> = groups.into_iter().map(|(_, group)| group).collect();
+
+        let results = self.scatter(world, rules, groups);
 
         let mut executed = 0;
         for group_results in results {
             for (id, event, effective, consequents) in group_results {
+                let priority = graph.get(id).map_or(0, |node| node.priority);
                 graph.mark_executed(id);
                 executed += 1;
                 if should_log(&event.payload, effective) {
                     graph.log_write(&event.payload);
                 }
                 for new_event in consequents {
-                    graph.insert(new_event, vec![id]);
+                    self.admit(world, graph, new_event, id, priority);
                 }
                 graph.finish(id);
             }
@@ -180,6 +296,51 @@ impl Scheduler {
         executed
     }
 
+    /// Execute each chunk group against the world, in parallel across
+    /// groups, returning every event with its effectiveness and consequents.
+    /// All groups' writes land before any rule runs (see [`WorldView`]).
+    #[cfg(feature = "parallel")]
+    fn scatter(&self, world: &World, rules: &RuleSet, groups: Vec<Vec<(EventId, Event)>>) -> Vec<Vec<Executed>> {
+        let busy_ns = self.busy_ns.as_deref();
+        let view = world.view();
+        let scatter = || -> Vec<Vec<Executed>> {
+            let mut results: Vec<Vec<Executed>> = groups
+                .into_par_iter()
+                .map(|mut group| {
+                    timed(busy_ns, || {
+                        let mut out = Vec::with_capacity(group.len());
+                        self.apply_group(world, &mut group, &mut out);
+                        out
+                    })
+                })
+                .collect();
+            results
+                .par_iter_mut()
+                .for_each(|out| timed(busy_ns, || evaluate_group(&view, rules, out)));
+            results
+        };
+        match &self.pool {
+            Some(pool) => pool.install(scatter),
+            None => scatter(),
+        }
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn scatter(&self, world: &World, rules: &RuleSet, groups: Vec<Vec<(EventId, Event)>>) -> Vec<Vec<Executed>> {
+        let mut results: Vec<Vec<Executed>> = groups
+            .into_iter()
+            .map(|mut group| {
+                let mut out = Vec::with_capacity(group.len());
+                self.apply_group(world, &mut group, &mut out);
+                out
+            })
+            .collect();
+        for out in &mut results {
+            evaluate_group(&world.view(), rules, out);
+        }
+        results
+    }
+
     pub fn run_until_quiet_parallel(
         &self,
         world: &World,
@@ -197,6 +358,151 @@ impl Scheduler {
         }
         total
     }
+
+    // ── Parallel execution over persistent per-chunk queues ─────────────
+
+    /// Like `step_parallel`, but the per-chunk queues live in `queues` and
+    /// are reused wave after wave instead of being rebuilt, and small waves
+    /// skip the pool entirely. Queues are handed to the pool's threads with
+    /// rayon's work stealing, so one busy chunk doesn't idle the others.
+    ///
+    /// Queues are numbered in the order their chunk first appears in the
+    /// ready batch, and consequents are gathered in that order, so results
+    /// are reproducible without `with_deterministic_order`.
+    pub fn step_stealing(
+        &self,
+        world: &World,
+        graph: &mut CausalGraph,
+        rules: &RuleSet,
+        queues: &mut StealQueues,
+    ) -> usize {
+        self.release_loaded(world, graph);
+        let batch = graph.drain_ready(self.max_events_per_step);
+        if batch.is_empty() {
+            return 0;
+        }
+        queues.fill(graph, &batch);
+        let inline = batch.len() < INLINE_WAVE || queues.used == 1;
+        self.drain_queues(world, rules, queues, inline);
+
+        let mut executed = 0;
+        for results in &mut queues.results[..queues.used] {
+            for (id, event, effective, consequents) in results.drain(..) {
+                let priority = graph.get(id).map_or(0, |node| node.priority);
+                graph.mark_executed(id);
+                executed += 1;
+                if should_log(&event.payload, effective) {
+                    graph.log_write(&event.payload);
+                }
+                for new_event in consequents {
+                    self.admit(world, graph, new_event, id, priority);
+                }
+                graph.finish(id);
+            }
+        }
+
+        executed
+    }
+
+    /// Execute every filled queue, leaving each one's events in the
+    /// matching results slot. As in `scatter`, every queue's writes land
+    /// before any rule runs.
+    #[cfg(feature = "parallel")]
+    fn drain_queues(&self, world: &World, rules: &RuleSet, queues: &mut StealQueues, inline: bool) {
+        let used = queues.used;
+        let view = world.view();
+        let (queues, results) = (&mut queues.queues[..used], &mut queues.results[..used]);
+        if inline {
+            for (queue, results) in queues.iter_mut().zip(results.iter_mut()) {
+                self.apply_group(world, queue, results);
+            }
+            for results in results.iter_mut() {
+                evaluate_group(&view, rules, results);
+            }
+            return;
+        }
+        let busy_ns = self.busy_ns.as_deref();
+        let mut run = || {
+            queues.par_iter_mut().zip(results.par_iter_mut()).for_each(|(queue, results)| {
+                timed(busy_ns, || self.apply_group(world, queue, results));
+            });
+            results
+                .par_iter_mut()
+                .for_each(|results| timed(busy_ns, || evaluate_group(&view, rules, results)));
+        };
+        match &self.pool {
+            Some(pool) => pool.install(run),
+            None => run(),
+        }
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    fn drain_queues(&self, world: &World, rules: &RuleSet, queues: &mut StealQueues, _inline: bool) {
+        let used = queues.used;
+        for (queue, results) in queues.queues[..used].iter_mut().zip(&mut queues.results) {
+            self.apply_group(world, queue, results);
+        }
+        for results in &mut queues.results[..used] {
+            evaluate_group(&world.view(), rules, results);
+        }
+    }
+
+    pub fn run_until_quiet_stealing(
+        &self,
+        world: &World,
+        graph: &mut CausalGraph,
+        rules: &RuleSet,
+        max_steps: usize,
+    ) -> usize {
+        let mut queues = StealQueues::default();
+        let mut total = 0;
+        for _ in 0..max_steps {
+            let n = self.step_stealing(world, graph, rules, &mut queues);
+            if n == 0 {
+                break;
+            }
+            total += n;
+        }
+        total
+    }
+}
+
+/// Per-chunk event queues for [`Scheduler::step_stealing`], kept by the
+/// caller across steps so their allocations are reused. Slots are
+/// recycled each wave; a slot's vectors keep their capacity.
+#[derive(Default)]
+pub struct StealQueues {
+    slot_of: HashMap<ChunkPos, usize>,
+    queues: Vec<Vec<(EventId, Event)>>,
+    results: Vec<Vec<Executed>>,
+    /// Slots filled by the current wave.
+    used: usize,
+}
+
+impl StealQueues {
+    /// Distribute `batch` over per-chunk slots, in first-appearance order.
+    fn fill(&mut self, graph: &CausalGraph, batch: &[EventId]) {
+        self.slot_of.clear();
+        self.used = 0;
+        for &id in batch {
+            let Some(node) = graph.get(id) else { continue };
+            let used = &mut self.used;
+            let slot = *self.slot_of.entry(node.event.chunk()).or_insert_with(|| {
+                *used += 1;
+                *used - 1
+            });
+            if slot == self.queues.len() {
+                self.queues.push(Vec::new());
+                self.results.push(Vec::new());
+            }
+            self.queues[slot].push((id, node.event.clone()));
+        }
+    }
+
+    /// Slots allocated so far: the widest wave seen, in chunks.
+    pub fn capacity(&self) -> usize {
+        self.queues.len()
+    }
 }
 
 impl Default for Scheduler {
@@ -205,6 +511,10 @@ impl Default for Scheduler {
     }
 }
 
+/// An event run during the scatter phase: whether its write was effective,
+/// and the consequents its rules produced.
+type Executed = (EventId, Event, bool, Vec<Event>);
+
 /// Should this executed event land in the graph's write log?
 ///
 /// Effective `BlockSet`s, always. `LightSet`s regardless of apply
@@ -219,47 +529,121 @@ fn should_log(payload: &EventPayload, effective: bool) -> bool {
     }
 }
 
-/// Apply the event's write to the world.  Returns `true` when the write was
-/// effective (the value actually changed) so that the scheduler can skip rule
-/// evaluation for redundant / duplicate writes.
-fn apply_event(world: &World, payload: &EventPayload) -> bool {
-    match payload {
-        EventPayload::BlockSet { pos, old, new } => {
-            // Stale-precondition guard: the rule that emitted this event
-            // observed `old` at `pos`. If a causally-unrelated event has
-            // since changed the cell, this write is based on stale state —
-            // skip it (and its consequents) rather than clobber the newer
-            // value. Prevents e.g. block duplication when two cascades
-            // race to move different blocks into the same cell.
-            let current = world.get_block(*pos);
-            if current != *old || old == new {
-                return false;
-            }
-            world.set_block(*pos, *new);
-            true
+/// Add the time `f` takes to `busy_ns`, if set.
+#[cfg(feature = "parallel")]
+fn timed<R>(busy_ns: Option<&AtomicU64>, f: impl FnOnce() -> R) -> R {
+    let started = busy_ns.map(|_| Instant::now());
+    let result = f();
+    if let (Some(counter), Some(t)) = (busy_ns, started) {
+        counter.fetch_add(t.elapsed().as_nanos() as u64, Ordering::Relaxed);
+    }
+    result
+}
+
+/// Second half of a parallel wave: run rules for every effective event
+/// `apply_group` left in `out`, filling in its consequents.
+fn evaluate_group(view: &WorldView, rules: &RuleSet, out: &mut [Executed]) {
+    for (_, event, effective, consequents) in out {
+        if *effective {
+            *consequents = rules.evaluate(view, &event.payload);
         }
-        EventPayload::BlockNotify { .. } => true,
-        EventPayload::LightSet {
-            pos,
-            light_type,
-            new,
-            ..
-        } => {
-            let current = match light_type {
-                super::event::LightType::Sky => world.get_sky_light(*pos),
-                super::event::LightType::Block => world.get_block_light(*pos),
+    }
+}
+
+impl Scheduler {
+    /// First half of a parallel wave: apply a chunk group's (or stealing
+    /// queue's) writes, draining `group` into `out` with no consequents
+    /// yet. Its `BlockSet`s go in as one batched compare-and-set. Events
+    /// of one wave are all ready at once, so none caused another and
+    /// applying them all before any rule runs is as valid as interleaving.
+    fn apply_group(&self, world: &World, group: &mut Vec<(EventId, Event)>, out: &mut Vec<Executed>) {
+        let writes: Vec<(BlockPos, BlockId, BlockId)> = group
+            .iter()
+            .filter_map(|(_, event)| match event.payload {
+                EventPayload::BlockSet { pos, old, new } if old != new => Some((pos, old, new)),
+                _ => None,
+            })
+            .collect();
+        if writes.len() < 2 {
+            out.extend(group.drain(..).map(|(id, event)| {
+                let effective = self.apply_event(world, &event.payload);
+                (id, event, effective, Vec::new())
+            }));
+            return;
+        }
+        let mut applied = world.compare_and_set_batch(&writes).into_iter();
+        for (id, event) in group.drain(..) {
+            let effective = match event.payload {
+                EventPayload::BlockSet { pos, old, new } if old != new => {
+                    let ok = applied.next().unwrap_or(false);
+                    if !ok {
+                        self.stale(pos, old, new, world.get_block(pos));
+                    }
+                    ok
+                }
+                _ => self.apply_event(world, &event.payload),
             };
-            if *new == current {
-                return false;
+            out.push((id, event, effective, Vec::new()));
+        }
+    }
+
+    /// Count and trace a `BlockSet` skipped because `pos` held `current`
+    /// rather than its `old`.
+    fn stale(&self, pos: BlockPos, old: BlockId, new: BlockId, current: BlockId) {
+        if let Some(conflicts) = &self.conflicts {
+            conflicts.fetch_add(1, Ordering::Relaxed);
+        }
+        tracing::trace!(?pos, ?old, ?new, ?current, "stale BlockSet skipped");
+    }
+
+    /// Apply the event's write to the world.  Returns `true` when the write was
+    /// effective (the value actually changed) so that the scheduler can skip rule
+    /// evaluation for redundant / duplicate writes.
+    fn apply_event(&self, world: &World, payload: &EventPayload) -> bool {
+        match payload {
+            EventPayload::BlockSet { pos, old, new } => {
+                if old == new {
+                    return false;
+                }
+                // Stale-precondition guard: the rule that emitted this event
+                // observed `old` at `pos`. If a causally-unrelated event has
+                // since changed the cell, this write is based on stale state —
+                // skip it (and its consequents) rather than clobber the newer
+                // value. Prevents e.g. block duplication when two cascades
+                // race to move different blocks into the same cell. The check
+                // and the write are one compare-and-set, so two parallel
+                // groups writing the same cell can't both pass it.
+                if let Err(current) = world.set_block_if(*pos, *new, |current| current == *old) {
+                    self.stale(*pos, *old, *new, current);
+                    return false;
+                }
+                true
             }
-            match light_type {
-                super::event::LightType::Sky => world.set_sky_light(*pos, *new),
-                super::event::LightType::Block => world.set_block_light(*pos, *new),
+            EventPayload::BlockNotify { .. } => true,
+            EventPayload::LightSet {
+                pos,
+                light_type,
+                new,
+                ..
+            } => {
+                let current = match light_type {
+                    super::event::LightType::Sky => world.get_sky_light(*pos),
+                    super::event::LightType::Block => world.get_block_light(*pos),
+                };
+                if *new == current {
+                    return false;
+                }
+                match light_type {
+                    super::event::LightType::Sky => world.set_sky_light(*pos, *new),
+                    super::event::LightType::Block => world.set_block_light(*pos, *new),
+                }
+                true
             }
-            true
+            EventPayload::LightNotify { .. } => true,
+            // Reporting-only: the light rule's BFS already wrote light storage.
+            EventPayload::LightBatch { .. } => true,
+            // Opaque to the engine; rules that know the kind give it meaning.
+            EventPayload::Custom { .. } => true,
         }
-        EventPayload::LightNotify { .. } => true,
-        // Reporting-only: the light rule's BFS already wrote light storage.
-        EventPayload::LightBatch { .. } => true,
     }
 }
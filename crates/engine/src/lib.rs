@@ -1,3 +1,4 @@
 pub mod causal;
+pub mod pathfinding;
 pub mod rules;
 pub mod world;
@@ -0,0 +1,355 @@
+//! A* pathfinding over the block world, for mobs.
+//!
+//! The engine doesn't know what blocks are, so callers say how each one
+//! is walked with a [`Cell`] classifier in [`Limits`]. A mob is one block
+//! wide and two tall; it stands in a cell whose own and upper blocks are
+//! passable, on top of a [`Cell::Solid`] block. From there it can step to
+//! any of the eight neighbouring columns on the same level (diagonally
+//! only past two clear corners), jump up one block, or walk off an edge
+//! and drop up to [`Limits::max_fall`] blocks. Jumps, drops and doors cost
+//! extra, so the cheapest path prefers flat, open ground. Unloaded chunks
+//! are walls.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::world::World;
+use crate::world::block::BlockId;
+use crate::world::position::BlockPos;
+
+/// How a block is walked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    /// Nothing in the way (air, flowers, open doors).
+    Open,
+    /// Stood on, never walked through.
+    Solid,
+    /// Stands in the way, and too tall to stand on: fences and walls.
+    Tall,
+    /// A closed door: walked through only by mobs that open doors.
+    Door,
+    /// Never entered or stood on: lava, fire, cactus.
+    Hazard,
+}
+
+/// Cost of a step to a side neighbour; the other costs are in the same
+/// units.
+const STEP: u32 = 10;
+/// A diagonal step (√2 · STEP).
+const DIAGONAL: u32 = 14;
+/// Added for jumping up a block.
+const JUMP: u32 = 10;
+/// Added per block dropped.
+const FALL: u32 = 5;
+/// Added for passing a door.
+const DOOR: u32 = 10;
+
+/// How far and how a search may go.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// Nodes expanded before giving up with the best partial path.
+    pub max_nodes: usize,
+    /// Deepest drop taken.
+    pub max_fall: u32,
+    /// Whether closed doors may be walked through (villagers do, zombies
+    /// don't).
+    pub open_doors: bool,
+    /// The caller's idea of each block.
+    pub classify: fn(BlockId) -> Cell,
+}
+
+impl Limits {
+    /// Vanilla-like defaults: a few hundred nodes (paths of a few dozen
+    /// blocks), drops of up to three blocks, doors shut.
+    pub fn new(classify: fn(BlockId) -> Cell) -> Self {
+        Self { max_nodes: 500, max_fall: 3, open_doors: false, classify }
+    }
+}
+
+/// A found path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    /// Feet positions to stand in, in order, not including the start.
+    pub steps: Vec<BlockPos>,
+    /// Whether the last step is the goal. Otherwise the search ran out of
+    /// nodes or ground, and the path leads as close as it got.
+    pub complete: bool,
+}
+
+/// Find the cheapest walk from feet position `from` to `to` in `world`.
+/// `None` when `from` isn't somewhere a mob can stand, or no step leads
+/// anywhere closer.
+pub fn find_path(world: &World, from: BlockPos, to: BlockPos, limits: &Limits) -> Option<Path> {
+    let terrain = Terrain { world, limits };
+    if !terrain.stands(from) {
+        return None;
+    }
+    if from == to {
+        return Some(Path { steps: Vec::new(), complete: true });
+    }
+
+    // Best known cost to each node and the node it was reached from.
+    let mut best: HashMap<BlockPos, (u32, BlockPos)> = HashMap::new();
+    best.insert(from, (0, from));
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((estimate(from, to), 0, key(from))));
+    let mut closest = (estimate(from, to), from);
+    let mut expanded = 0;
+
+    while let Some(Reverse((_, cost, node))) = open.pop() {
+        let node = unkey(node);
+        if best.get(&node).is_some_and(|&(known, _)| known < cost) {
+            continue;
+        }
+        if node == to {
+            return Some(trace(&best, from, to, true));
+        }
+        expanded += 1;
+        if expanded > limits.max_nodes {
+            break;
+        }
+        for (next, step) in terrain.moves(node) {
+            let cost = cost + step;
+            if best.get(&next).is_some_and(|&(known, _)| known <= cost) {
+                continue;
+            }
+            best.insert(next, (cost, node));
+            let h = estimate(next, to);
+            if h < closest.0 {
+                closest = (h, next);
+            }
+            open.push(Reverse((cost + h, cost, key(next))));
+        }
+    }
+    (closest.1 != from).then(|| trace(&best, from, closest.1, false))
+}
+
+/// Heap key for a position: ordered, so ties between equal costs break
+/// the same way every run.
+fn key(pos: BlockPos) -> (i64, i64, i64) {
+    (pos.x, pos.y, pos.z)
+}
+
+fn unkey((x, y, z): (i64, i64, i64)) -> BlockPos {
+    BlockPos::new(x, y, z)
+}
+
+/// A lower bound on the cost from `a` to `b`: octile distance across,
+/// and the cheapest vertical cost (a drop) for the height difference.
+fn estimate(a: BlockPos, b: BlockPos) -> u32 {
+    let (dx, dz) = ((a.x - b.x).unsigned_abs(), (a.z - b.z).unsigned_abs());
+    let (long, short) = (dx.max(dz), dx.min(dz));
+    let across = short * DIAGONAL as u64 + (long - short) * STEP as u64;
+    let vertical = (a.y - b.y).unsigned_abs() * FALL as u64;
+    (across + vertical).min(u32::MAX as u64) as u32
+}
+
+/// The steps from `from` to `to`, following parents back.
+fn trace(best: &HashMap<BlockPos, (u32, BlockPos)>, from: BlockPos, to: BlockPos, complete: bool) -> Path {
+    let mut steps = vec![to];
+    let mut at = to;
+    while let Some(&(_, parent)) = best.get(&at) {
+        if parent == from {
+            break;
+        }
+        steps.push(parent);
+        at = parent;
+    }
+    steps.reverse();
+    Path { steps, complete }
+}
+
+struct Terrain<'a> {
+    world: &'a World,
+    limits: &'a Limits,
+}
+
+impl Terrain<'_> {
+    fn cell(&self, pos: BlockPos) -> Cell {
+        if !self.world.has_chunk(pos.chunk()) {
+            return Cell::Tall;
+        }
+        (self.limits.classify)(self.world.get_block(pos))
+    }
+
+    /// Whether a mob's body fits with its feet at `pos`, and what passing
+    /// costs (doors).
+    fn fits(&self, pos: BlockPos) -> Option<u32> {
+        let mut cost = 0;
+        for part in [pos, pos.offset(0, 1, 0)] {
+            match self.cell(part) {
+                Cell::Open => {}
+                Cell::Door if self.limits.open_doors => cost = DOOR,
+                _ => return None,
+            }
+        }
+        Some(cost)
+    }
+
+    /// Whether a mob can stand with its feet at `pos`.
+    fn stands(&self, pos: BlockPos) -> bool {
+        self.fits(pos).is_some() && self.cell(pos.offset(0, -1, 0)) == Cell::Solid
+    }
+
+    /// Every move out of `pos`, with its cost.
+    fn moves(&self, pos: BlockPos) -> Vec<(BlockPos, u32)> {
+        const SIDES: [(i64, i64); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+        const CORNERS: [(i64, i64); 4] = [(1, 1), (1, -1), (-1, 1), (-1, -1)];
+        let mut moves = Vec::with_capacity(8);
+        for (dx, dz) in SIDES {
+            let side = pos.offset(dx, 0, dz);
+            if let Some(door) = self.fits(side) {
+                if self.stands(side) {
+                    moves.push((side, STEP + door));
+                } else if let Some(landing) = self.drop(side) {
+                    moves.push((landing, STEP + door + FALL * (side.y - landing.y) as u32));
+                }
+            } else {
+                // Jump: room overhead here, and the block ahead is
+                // ground to land on.
+                let up = side.offset(0, 1, 0);
+                if self.cell(pos.offset(0, 2, 0)) == Cell::Open && self.stands(up) {
+                    moves.push((up, STEP + JUMP + self.fits(up).unwrap_or(0)));
+                }
+            }
+        }
+        for (dx, dz) in CORNERS {
+            let corner = pos.offset(dx, 0, dz);
+            let clear = |p: BlockPos| self.fits(p) == Some(0);
+            if clear(pos.offset(dx, 0, 0)) && clear(pos.offset(0, 0, dz)) && clear(corner) && self.stands(corner) {
+                moves.push((corner, DIAGONAL));
+            }
+        }
+        moves
+    }
+
+    /// Where a mob walking off into open column `pos` lands, if within
+    /// [`Limits::max_fall`] and not into a hazard.
+    fn drop(&self, pos: BlockPos) -> Option<BlockPos> {
+        let mut at = pos;
+        for _ in 0..self.limits.max_fall {
+            match self.cell(at.offset(0, -1, 0)) {
+                Cell::Solid => return Some(at),
+                Cell::Open => at = at.offset(0, -1, 0),
+                _ => return None,
+            }
+        }
+        self.stands(at).then_some(at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::chunk::Chunk;
+    use crate::world::position::ChunkPos;
+
+    const STONE: BlockId = BlockId(1);
+    const FENCE: BlockId = BlockId(2);
+    const DOOR_BLOCK: BlockId = BlockId(3);
+    const LAVA: BlockId = BlockId(4);
+
+    fn classify(block: BlockId) -> Cell {
+        match block {
+            BlockId::AIR => Cell::Open,
+            FENCE => Cell::Tall,
+            DOOR_BLOCK => Cell::Door,
+            LAVA => Cell::Hazard,
+            _ => Cell::Solid,
+        }
+    }
+
+    /// One chunk with a stone floor at y=0.
+    fn floor() -> World {
+        let world = World::new();
+        world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+        for x in 0..16 {
+            for z in 0..16 {
+                world.set_block(BlockPos::new(x, 0, z), STONE);
+            }
+        }
+        world
+    }
+
+    fn wall(world: &World, x: i64, height: i64, except_z: Option<i64>, block: BlockId) {
+        for z in 0..16 {
+            for y in 1..=height {
+                if Some(z) != except_z {
+                    world.set_block(BlockPos::new(x, y, z), block);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn walks_straight_and_diagonally_on_open_ground() {
+        let world = floor();
+        let limits = Limits::new(classify);
+        let path = find_path(&world, BlockPos::new(1, 1, 1), BlockPos::new(5, 1, 1), &limits).unwrap();
+        assert!(path.complete);
+        assert_eq!(path.steps.len(), 4);
+        assert_eq!(path.steps.last(), Some(&BlockPos::new(5, 1, 1)));
+
+        let path = find_path(&world, BlockPos::new(1, 1, 1), BlockPos::new(4, 1, 4), &limits).unwrap();
+        assert_eq!(path.steps.len(), 3, "three diagonal steps");
+        assert!(find_path(&world, BlockPos::new(1, 5, 1), BlockPos::new(4, 1, 4), &limits).is_none(), "in mid-air");
+    }
+
+    #[test]
+    fn climbs_steps_and_goes_round_fences() {
+        let world = floor();
+        let limits = Limits::new(classify);
+        // A one-high wall is jumped.
+        wall(&world, 4, 1, None, STONE);
+        let path = find_path(&world, BlockPos::new(1, 1, 3), BlockPos::new(7, 1, 3), &limits).unwrap();
+        assert!(path.complete);
+        assert!(path.steps.contains(&BlockPos::new(4, 2, 3)), "over the top: {:?}", path.steps);
+
+        // A fence isn't; with a gap at z=12 the path goes through it.
+        wall(&world, 4, 1, Some(12), FENCE);
+        world.set_block(BlockPos::new(4, 1, 12), BlockId::AIR);
+        let path = find_path(&world, BlockPos::new(1, 1, 3), BlockPos::new(7, 1, 3), &limits).unwrap();
+        assert!(path.steps.contains(&BlockPos::new(4, 1, 12)), "through the gap: {:?}", path.steps);
+
+        // No gap: the best it can do is the near side.
+        world.set_block(BlockPos::new(4, 1, 12), FENCE);
+        let path = find_path(&world, BlockPos::new(1, 1, 3), BlockPos::new(7, 1, 3), &limits).unwrap();
+        assert!(!path.complete);
+        assert_eq!(path.steps.last(), Some(&BlockPos::new(3, 1, 3)));
+    }
+
+    #[test]
+    fn doors_and_drops() {
+        let world = floor();
+        wall(&world, 4, 3, Some(8), STONE);
+        world.set_block(BlockPos::new(4, 1, 8), DOOR_BLOCK);
+        world.set_block(BlockPos::new(4, 2, 8), DOOR_BLOCK);
+        let (from, to) = (BlockPos::new(1, 1, 8), BlockPos::new(7, 1, 8));
+        let shut = Limits::new(classify);
+        assert!(!find_path(&world, from, to, &shut).unwrap().complete, "zombies stay out");
+        let opens = Limits { open_doors: true, ..shut };
+        assert!(find_path(&world, from, to, &opens).unwrap().complete, "villagers walk in");
+
+        // A ledge three blocks up: dropped from, never climbed.
+        let world = floor();
+        for x in 0..4 {
+            for z in 0..16 {
+                for y in 1..=3 {
+                    world.set_block(BlockPos::new(x, y, z), STONE);
+                }
+            }
+        }
+        let (top, bottom) = (BlockPos::new(2, 4, 5), BlockPos::new(8, 1, 5));
+        assert!(find_path(&world, top, bottom, &shut).unwrap().complete);
+        assert!(!find_path(&world, bottom, top, &shut).unwrap().complete);
+        let timid = Limits { max_fall: 2, ..shut };
+        assert!(!find_path(&world, top, bottom, &timid).unwrap().complete, "too far down");
+
+        // Lava is never a landing.
+        for z in 0..16 {
+            world.set_block(BlockPos::new(4, 0, z), LAVA);
+        }
+        let path = find_path(&world, top, bottom, &shut);
+        assert!(path.is_none_or(|p| !p.complete));
+    }
+}
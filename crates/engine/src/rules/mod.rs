@@ -1,32 +1,73 @@
+mod profile;
+
+pub use profile::{RULE_HIST_BOUNDS_NS, RuleProfile, RuleSample, RuleStats};
+
+use std::sync::Arc;
+use std::time::Instant;
+
 use crate::causal::event::{Event, EventPayload};
-use crate::world::World;
+use crate::world::WorldView;
 
-/// A rule function: given the current world state and an event that just
+/// A rule function: given a view of the world and an event that just
 /// occurred, produce zero or more consequent events.
 ///
+/// The view is read-only for blocks (see [`WorldView`]), so a rule's only
+/// way to change a block is a consequent `BlockSet`.
+///
 /// Rules must be **local**: they only read blocks in a bounded neighborhood
 /// of the event's position. This locality is what makes causal independence
 /// (and therefore parallelism) possible.
-pub type RuleFn = fn(&World, &EventPayload) -> Vec<Event>;
+pub type RuleFn = fn(&WorldView, &EventPayload) -> Vec<Event>;
 
 /// An ordered collection of rules. When an event is executed, every rule
 /// is consulted; their outputs are merged into the causal graph as children
 /// of the triggering event.
 pub struct RuleSet {
     rules: Vec<RuleFn>,
+    names: Vec<&'static str>,
+    profile: Option<Arc<RuleProfile>>,
+    /// Parallel to `rules` while a profile is attached.
+    stats: Vec<Arc<RuleStats>>,
 }
 
 impl RuleSet {
     pub fn new() -> Self {
-        Self { rules: Vec::new() }
+        Self { rules: Vec::new(), names: Vec::new(), profile: None, stats: Vec::new() }
     }
 
     pub fn add(&mut self, rule: RuleFn) {
+        self.add_named("rule", rule);
+    }
+
+    /// Add a rule under the name its [`RuleProfile`] counters use.
+    pub fn add_named(&mut self, name: &'static str, rule: RuleFn) {
         self.rules.push(rule);
+        self.names.push(name);
+        if let Some(profile) = &self.profile {
+            self.stats.push(profile.stats(name));
+        }
     }
 
-    pub fn evaluate(&self, world: &World, payload: &EventPayload) -> Vec<Event> {
+    /// Time rules into `profile`, sampled at its rate.
+    pub fn with_profile(mut self, profile: Arc<RuleProfile>) -> Self {
+        self.stats = self.names.iter().map(|name| profile.stats(name)).collect();
+        self.profile = Some(profile);
+        self
+    }
+
+    pub fn evaluate(&self, world: &WorldView, payload: &EventPayload) -> Vec<Event> {
         let mut out = Vec::new();
+        if let Some(profile) = &self.profile
+            && profile.should_sample()
+        {
+            for (rule, stats) in self.rules.iter().zip(&self.stats) {
+                let started = Instant::now();
+                let events = rule(world, payload);
+                stats.record(started.elapsed(), events.len());
+                out.extend(events);
+            }
+            return out;
+        }
         for rule in &self.rules {
             out.extend(rule(world, payload));
         }
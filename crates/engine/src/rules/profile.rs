@@ -0,0 +1,129 @@
+//! Sampled per-rule timing.
+//!
+//! A [`RuleProfile`] is shared by every [`RuleSet`](super::RuleSet)
+//! attached to it (one per physics worker, rebuilt when rules change), so
+//! counters accumulate per rule *name* across sets. One evaluation in
+//! `sample_every` per thread is timed rule by rule; the rest run untimed,
+//! which keeps the cost off the hot path.
+
+use std::cell::Cell;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering::Relaxed};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Upper bounds (ns) of the first four [`RuleStats`] histogram buckets;
+/// the fifth takes the rest.
+pub const RULE_HIST_BOUNDS_NS: [u64; 4] = [250, 1_000, 10_000, 100_000];
+
+/// Counters for one rule. Only sampled evaluations are counted.
+pub struct RuleStats {
+    pub name: &'static str,
+    samples: AtomicU64,
+    events: AtomicU64,
+    ns_sum: AtomicU64,
+    hist: [AtomicU64; 5],
+}
+
+/// A point-in-time copy of one rule's [`RuleStats`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleSample {
+    pub name: &'static str,
+    /// Timed evaluations.
+    pub samples: u64,
+    /// Consequent events those evaluations produced.
+    pub events: u64,
+    pub ns_sum: u64,
+    /// Bucketed by [`RULE_HIST_BOUNDS_NS`].
+    pub hist: [u64; 5],
+}
+
+impl RuleStats {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            samples: AtomicU64::new(0),
+            events: AtomicU64::new(0),
+            ns_sum: AtomicU64::new(0),
+            hist: Default::default(),
+        }
+    }
+
+    pub fn record(&self, duration: Duration, events: usize) {
+        let ns = duration.as_nanos() as u64;
+        self.samples.fetch_add(1, Relaxed);
+        self.events.fetch_add(events as u64, Relaxed);
+        self.ns_sum.fetch_add(ns, Relaxed);
+        let bucket = RULE_HIST_BOUNDS_NS.iter().position(|&bound| ns < bound).unwrap_or(4);
+        self.hist[bucket].fetch_add(1, Relaxed);
+    }
+
+    pub fn sample(&self) -> RuleSample {
+        RuleSample {
+            name: self.name,
+            samples: self.samples.load(Relaxed),
+            events: self.events.load(Relaxed),
+            ns_sum: self.ns_sum.load(Relaxed),
+            hist: std::array::from_fn(|i| self.hist[i].load(Relaxed)),
+        }
+    }
+}
+
+/// Per-rule counters plus the sampling rate.
+pub struct RuleProfile {
+    /// Time one evaluation in this many; 0 turns profiling off.
+    sample_every: AtomicU32,
+    rules: Mutex<Vec<Arc<RuleStats>>>,
+}
+
+thread_local! {
+    /// Evaluations left on this thread until the next timed one.
+    static UNTIL_SAMPLE: Cell<u32> = const { Cell::new(0) };
+}
+
+impl RuleProfile {
+    pub fn new(sample_every: u32) -> Self {
+        Self { sample_every: AtomicU32::new(sample_every), rules: Mutex::new(Vec::new()) }
+    }
+
+    pub fn set_sample_every(&self, n: u32) {
+        self.sample_every.store(n, Relaxed);
+    }
+
+    pub fn sample_every(&self) -> u32 {
+        self.sample_every.load(Relaxed)
+    }
+
+    /// The counters for `name`, created on first use.
+    pub fn stats(&self, name: &'static str) -> Arc<RuleStats> {
+        let mut rules = self.rules.lock().expect("rule profile poisoned");
+        if let Some(stats) = rules.iter().find(|s| s.name == name) {
+            return Arc::clone(stats);
+        }
+        let stats = Arc::new(RuleStats::new(name));
+        rules.push(Arc::clone(&stats));
+        stats
+    }
+
+    /// Every rule seen so far, in first-seen order.
+    pub fn snapshot(&self) -> Vec<RuleSample> {
+        self.rules.lock().expect("rule profile poisoned").iter().map(|s| s.sample()).collect()
+    }
+
+    /// Whether the calling thread should time this evaluation.
+    pub(crate) fn should_sample(&self) -> bool {
+        let every = self.sample_every();
+        if every == 0 {
+            return false;
+        }
+        UNTIL_SAMPLE.with(|left| match left.get() {
+            0 => {
+                left.set(every - 1);
+                true
+            }
+            n => {
+                left.set(n.min(every) - 1);
+                false
+            }
+        })
+    }
+}
@@ -24,9 +24,9 @@ const NIBBLE_LEN: usize = SECTION_VOLUME / 2;
 /// Indices pack little-endian into `u64` words with no index spanning two
 /// words — the same convention as the MC wire format, so serialization
 /// can walk indices cheaply. The palette only grows (an overwritten
-/// block's entry may linger, slightly widening `bits` until a future
-/// compaction pass); `get`/`set` stay O(1) plus a short palette scan on
-/// novel blocks.
+/// block's entry may linger, slightly widening `bits` until `compact`
+/// drops it); `get`/`set` stay O(1) plus a short palette scan on novel
+/// blocks.
 ///
 /// Cell order is XZY (`y*256 + z*16 + x`) for cache-friendly vertical
 /// scans (gravity, lighting). A section that is entirely air is never
@@ -252,6 +252,55 @@ impl ChunkSection {
         &self.palette
     }
 
+    /// Drop palette entries no cell references any more and re-pack at
+    /// the narrowest width the survivors need (down to uniform). Returns
+    /// the heap bytes reclaimed. O(4096); meant for occasional passes
+    /// (save, unload), not the write path.
+    pub fn compact(&mut self) -> usize {
+        if self.bits == 0 {
+            return 0;
+        }
+        let before = self.memory_bytes();
+        let mut used = vec![false; self.palette.len()];
+        for cell in 0..SECTION_VOLUME {
+            used[self.read_index(cell)] = true;
+        }
+        let live = used.iter().filter(|u| **u).count();
+        let new_bits = match live {
+            1 => 0,
+            2..=16 => 4,
+            17..=256 => 8,
+            _ => 16,
+        };
+        if live == self.palette.len() && new_bits == self.bits {
+            return 0;
+        }
+
+        // Old index → new index, keeping surviving entries in order.
+        let mut remap = vec![0usize; self.palette.len()];
+        let mut palette = Vec::with_capacity(live);
+        for (i, block) in self.palette.iter().enumerate() {
+            if used[i] {
+                remap[i] = palette.len();
+                palette.push(*block);
+            }
+        }
+
+        let mut data = Vec::new();
+        if new_bits > 0 {
+            let per_word = 64 / new_bits as usize;
+            data = vec![0u64; SECTION_VOLUME.div_ceil(per_word)];
+            for cell in 0..SECTION_VOLUME {
+                let value = remap[self.read_index(cell)] as u64;
+                data[cell / per_word] |= value << ((cell % per_word) * new_bits as usize);
+            }
+        }
+        self.palette = palette;
+        self.bits = new_bits;
+        self.data = data;
+        before - self.memory_bytes()
+    }
+
     /// Heap bytes used by this section's block storage (palette + packed
     /// indices). A raw array would be 8192 bytes; uniform sections are ~2,
     /// 4-bit sections ~2050.
@@ -263,6 +312,7 @@ impl ChunkSection {
 /// A column of chunk sections, keyed by section index (y >> 4).
 ///
 /// Only non-empty sections are stored (sparse).
+#[derive(Clone)]
 pub struct Chunk {
     sections: HashMap<i32, ChunkSection>,
     light: HashMap<i32, LightSection>,
@@ -357,6 +407,12 @@ impl Chunk {
         self.sections.get(&section_idx)
     }
 
+    /// Compact every section's palette (see `ChunkSection::compact`).
+    /// Returns the heap bytes reclaimed.
+    pub fn compact(&mut self) -> usize {
+        self.sections.values_mut().map(ChunkSection::compact).sum()
+    }
+
     /// Iterate over all non-empty sections as (section_index, section).
     pub fn sections(&self) -> impl Iterator<Item = (&i32, &ChunkSection)> {
         self.sections.iter()
@@ -366,6 +422,61 @@ impl Chunk {
     pub fn light_sections(&self) -> impl Iterator<Item = (&i32, &LightSection)> {
         self.light.iter()
     }
+
+    /// 64-bit FNV-1a over the block contents, section by section in
+    /// index order. Independent of palette layout and stable across runs
+    /// and processes, so a hash recorded by one server can be checked by
+    /// another. Light is not included.
+    pub fn content_hash(&self) -> u64 {
+        let mut indices: Vec<i32> = self
+            .sections
+            .iter()
+            .filter(|(_, s)| !s.is_empty())
+            .map(|(&idx, _)| idx)
+            .collect();
+        indices.sort_unstable();
+        let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for &b in bytes {
+                h = (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        };
+        for idx in indices {
+            feed(&idx.to_le_bytes());
+            let section = &self.sections[&idx];
+            for cell in 0..SECTION_VOLUME {
+                feed(&section.get_by_index(cell).0.to_le_bytes());
+            }
+        }
+        h
+    }
+
+    /// Every cell whose block differs from `other`, as
+    /// `(pos, ours, theirs)` in section, then cell-index order. Missing
+    /// sections compare as air.
+    pub fn diff(&self, other: &Chunk) -> Vec<(LocalBlockPos, BlockId, BlockId)> {
+        let mut indices: Vec<i32> = self.sections.keys().chain(other.sections.keys()).copied().collect();
+        indices.sort_unstable();
+        indices.dedup();
+        let mut out = Vec::new();
+        for idx in indices {
+            let ours = self.sections.get(&idx);
+            let theirs = other.sections.get(&idx);
+            for cell in 0..SECTION_VOLUME {
+                let a = ours.map_or(BlockId::AIR, |s| s.get_by_index(cell));
+                let b = theirs.map_or(BlockId::AIR, |s| s.get_by_index(cell));
+                if a != b {
+                    let pos = LocalBlockPos {
+                        x: (cell % SECTION_SIZE) as u8,
+                        y: idx as i64 * SECTION_SIZE as i64 + (cell / (SECTION_SIZE * SECTION_SIZE)) as i64,
+                        z: (cell / SECTION_SIZE % SECTION_SIZE) as u8,
+                    };
+                    out.push((pos, a, b));
+                }
+            }
+        }
+        out
+    }
 }
 
 impl Default for Chunk {
@@ -459,4 +570,54 @@ mod tests {
             s.memory_bytes(),
         );
     }
+
+    #[test]
+    fn compact_drops_stale_entries_and_narrows() {
+        let mut s = ChunkSection::new_filled(BlockId::new(1));
+        // Churn through 20 blocks at one cell: palette grows past 16 → 8-bit.
+        for i in 2..22u16 {
+            s.set(0, 0, 0, BlockId::new(i));
+        }
+        s.set(5, 5, 5, BlockId::new(3));
+        assert_eq!(s.palette().len(), 21);
+        let wide = s.memory_bytes();
+
+        let reclaimed = s.compact();
+        assert_eq!(s.palette(), &[BlockId::new(1), BlockId::new(3), BlockId::new(21)]);
+        assert_eq!(reclaimed, wide - s.memory_bytes());
+        assert!(s.memory_bytes() < wide / 2 + 16, "should be back to 4-bit indices");
+        assert_eq!(s.get(0, 0, 0), BlockId::new(21));
+        assert_eq!(s.get(5, 5, 5), BlockId::new(3));
+        assert_eq!(s.get(9, 9, 9), BlockId::new(1));
+        assert_eq!(s.compact(), 0, "already compact");
+
+        // Overwriting the odd cells back collapses to uniform.
+        s.set(0, 0, 0, BlockId::new(1));
+        s.set(5, 5, 5, BlockId::new(1));
+        s.compact();
+        assert!(s.memory_bytes() < 16);
+        assert_eq!(s.get(0, 0, 0), BlockId::new(1));
+        assert_eq!(s.non_air_count(), 4096);
+    }
+
+    #[test]
+    fn content_hash_tracks_blocks_not_layout() {
+        let pos = |x, y, z| LocalBlockPos { x, y, z };
+        let mut a = Chunk::new();
+        a.set_block(pos(1, 5, 1), BlockId::new(3));
+        a.set_block(pos(2, 70, 9), BlockId::new(4));
+        // Same blocks, different write order and a stale palette entry.
+        let mut b = Chunk::new();
+        b.set_block(pos(2, 70, 9), BlockId::new(9));
+        b.set_block(pos(2, 70, 9), BlockId::new(4));
+        b.set_block(pos(1, 5, 1), BlockId::new(3));
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        b.set_block(pos(1, 5, 1), BlockId::new(5));
+        assert_ne!(a.content_hash(), b.content_hash());
+        // Air-only content hashes like an empty chunk.
+        b.set_block(pos(1, 5, 1), BlockId::AIR);
+        b.set_block(pos(2, 70, 9), BlockId::AIR);
+        assert_eq!(b.content_hash(), Chunk::new().content_hash());
+    }
 }
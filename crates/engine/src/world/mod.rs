@@ -1,33 +1,81 @@
 pub mod block;
 pub mod chunk;
+pub mod observer;
 pub mod position;
+pub mod storage;
+mod view;
+
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use block::BlockId;
 use chunk::Chunk;
-use dashmap::{DashMap, DashSet};
+use observer::{DirtyTracker, SkyLitTracker, WorldObserver};
 use position::{BlockPos, ChunkPos};
+use storage::Map;
+
+pub use view::{LightChunk, WorldView};
 
-/// The entire block world. Thread-safe, lock-sharded by chunk.
+/// The entire block world. Thread-safe, lock-sharded by chunk (with the
+/// default `parallel` feature; see `storage`).
 ///
 /// This is the spatial substrate -- the fixed 3D lattice. Time and causality
 /// live in `causal::Graph`, not here.
 pub struct World {
-    chunks: DashMap<ChunkPos, Chunk>,
-    /// Chunks that have been modified since the last save.
-    dirty: DashSet<ChunkPos>,
+    chunks: Map<ChunkPos, Chunk>,
+    /// Chunks (and their sections) modified since the last save.
+    dirty: DirtyTracker,
     /// Chunks whose sky light has already been initialized.
-    sky_lit: DashSet<ChunkPos>,
+    sky_lit: SkyLitTracker,
+    /// Observers registered via `add_observer`. `has_observers` lets the
+    /// write path skip the lock entirely while the list is empty.
+    observers: RwLock<Vec<Box<dyn WorldObserver>>>,
+    has_observers: AtomicBool,
 }
 
 impl World {
     pub fn new() -> Self {
         Self {
-            chunks: DashMap::new(),
-            dirty: DashSet::new(),
-            sky_lit: DashSet::new(),
+            chunks: Map::new(),
+            dirty: DirtyTracker::default(),
+            sky_lit: SkyLitTracker::default(),
+            observers: RwLock::new(Vec::new()),
+            has_observers: AtomicBool::new(false),
         }
     }
 
+    /// Register an observer for block writes and chunk insert/evict. It
+    /// sees every change made after this call; there is no way to remove
+    /// one, observers live as long as the world.
+    pub fn add_observer(&self, observer: Box<dyn WorldObserver>) {
+        self.observers.write().unwrap().push(observer);
+        self.has_observers.store(true, Ordering::Release);
+    }
+
+    /// Fan a change out to the built-in trackers, then to registered
+    /// observers.
+    fn notify(&self, f: impl Fn(&dyn WorldObserver)) {
+        f(&self.dirty);
+        f(&self.sky_lit);
+        if self.has_observers.load(Ordering::Acquire) {
+            for observer in self.observers.read().unwrap().iter() {
+                f(observer.as_ref());
+            }
+        }
+    }
+
+    /// Write a block and report it to observers. The chunk guard is dropped
+    /// before notifying so observers can read the world.
+    fn write_block(&self, pos: BlockPos, block: BlockId, tracked: bool) {
+        let old = {
+            let mut chunk = self.chunks.entry(pos.chunk()).or_default();
+            let old = chunk.get_block(pos.local());
+            chunk.set_block(pos.local(), block);
+            old
+        };
+        self.notify(|o| o.block_set(pos, old, block, tracked));
+    }
+
     /// Read a block at an absolute position. Returns AIR for unloaded chunks.
     pub fn get_block(&self, pos: BlockPos) -> BlockId {
         match self.chunks.get(&pos.chunk()) {
@@ -42,12 +90,88 @@ impl World {
     /// Takes `&self` (not `&mut self`) because `DashMap` provides interior
     /// mutability via per-shard locking.
     pub fn set_block(&self, pos: BlockPos, block: BlockId) {
-        let chunk_pos = pos.chunk();
-        self.chunks
-            .entry(chunk_pos)
-            .or_default()
-            .set_block(pos.local(), block);
-        self.dirty.insert(chunk_pos);
+        self.write_block(pos, block, true);
+    }
+
+    /// Write `new` at `pos` if the block there satisfies `cond`, with the
+    /// read and the write under one chunk lock so no other writer can get
+    /// in between. Returns the block that was there, `Err` when `cond`
+    /// rejected it (nothing written). Marks the chunk dirty like
+    /// [`set_block`](Self::set_block).
+    pub fn set_block_if(&self, pos: BlockPos, new: BlockId, cond: impl Fn(BlockId) -> bool) -> Result<BlockId, BlockId> {
+        let old = {
+            // Don't materialize a missing chunk for a write that won't
+            // happen.
+            let mut chunk = match self.chunks.get_mut(&pos.chunk()) {
+                Some(chunk) => chunk,
+                None if cond(BlockId::AIR) => self.chunks.entry(pos.chunk()).or_default(),
+                None => return Err(BlockId::AIR),
+            };
+            let old = chunk.get_block(pos.local());
+            if !cond(old) {
+                return Err(old);
+            }
+            chunk.set_block(pos.local(), new);
+            old
+        };
+        self.notify(|o| o.block_set(pos, old, new, true));
+        Ok(old)
+    }
+
+    /// Write `new` at `pos` only if it still holds `expected`. Returns
+    /// whether it wrote. The atomic form of "read, check, `set_block`".
+    pub fn compare_and_set(&self, pos: BlockPos, expected: BlockId, new: BlockId) -> bool {
+        self.set_block_if(pos, new, |current| current == expected).is_ok()
+    }
+
+    /// Write many blocks, taking each chunk's lock once and telling
+    /// observers once per batch rather than once per block. Writes to the
+    /// same block land in slice order. Creates chunks as needed and marks
+    /// them dirty, like [`set_block`](Self::set_block).
+    pub fn apply_batch(&self, writes: &[(BlockPos, BlockId)]) {
+        let writes: Vec<_> = writes.iter().map(|&(pos, new)| (pos, None, new)).collect();
+        self.write_batch(&writes);
+    }
+
+    /// [`compare_and_set`](Self::compare_and_set) for each `(pos,
+    /// expected, new)`, batched like [`apply_batch`](Self::apply_batch).
+    /// Each write checks the block as left by the writes before it.
+    /// Returns which writes happened, in slice order.
+    pub fn compare_and_set_batch(&self, writes: &[(BlockPos, BlockId, BlockId)]) -> Vec<bool> {
+        let writes: Vec<_> = writes.iter().map(|&(pos, expected, new)| (pos, Some(expected), new)).collect();
+        self.write_batch(&writes)
+    }
+
+    /// `(pos, expected, new)` writes, `None` meaning unconditional.
+    fn write_batch(&self, writes: &[(BlockPos, Option<BlockId>, BlockId)]) -> Vec<bool> {
+        let mut done = vec![false; writes.len()];
+        let mut order: Vec<usize> = (0..writes.len()).collect();
+        order.sort_by_key(|&i| writes[i].0.chunk());
+        let mut applied = Vec::with_capacity(writes.len());
+        for run in order.chunk_by(|&a, &b| writes[a].0.chunk() == writes[b].0.chunk()) {
+            let pos = writes[run[0]].0.chunk();
+            // As in `set_block_if`: only materialize a missing chunk when
+            // some write in it can go ahead.
+            let may_create = run.iter().any(|&i| writes[i].1.is_none_or(|e| e == BlockId::AIR));
+            let mut chunk = match self.chunks.get_mut(&pos) {
+                Some(chunk) => chunk,
+                None if may_create => self.chunks.entry(pos).or_default(),
+                None => continue,
+            };
+            for &i in run {
+                let (pos, expected, new) = writes[i];
+                let old = chunk.get_block(pos.local());
+                if expected.is_none_or(|e| e == old) {
+                    chunk.set_block(pos.local(), new);
+                    applied.push((pos, old, new));
+                    done[i] = true;
+                }
+            }
+        }
+        if !applied.is_empty() {
+            self.notify(|o| o.blocks_set(&applied, true));
+        }
+        done
     }
 
     /// Write a block WITHOUT marking the chunk dirty. For world generation
@@ -55,10 +179,7 @@ impl World {
     /// part of procedural terrain, not a gameplay modification, so it must
     /// not cause the chunk to be persisted.
     pub fn set_block_untracked(&self, pos: BlockPos, block: BlockId) {
-        self.chunks
-            .entry(pos.chunk())
-            .or_default()
-            .set_block(pos.local(), block);
+        self.write_block(pos, block, false);
     }
 
     pub fn has_chunk(&self, pos: ChunkPos) -> bool {
@@ -68,6 +189,19 @@ impl World {
     /// Insert a chunk without marking it dirty (used for generation/loading).
     pub fn insert_chunk(&self, pos: ChunkPos, chunk: Chunk) {
         self.chunks.insert(pos, chunk);
+        self.notify(|o| o.chunk_inserted(pos));
+    }
+
+    /// Insert a generated chunk unless one is already there: loaded from
+    /// disk, or generated (and perhaps edited) by another thread since the
+    /// caller checked. Returns whether it was inserted; observers hear only
+    /// of chunks that were.
+    pub fn insert_chunk_if_absent(&self, pos: ChunkPos, chunk: Chunk) -> bool {
+        let inserted = storage::insert_if_absent(&self.chunks, pos, chunk);
+        if inserted {
+            self.notify(|o| o.chunk_inserted(pos));
+        }
+        inserted
     }
 
     /// Remove a chunk entirely (Phase 6c eviction). Also clears its
@@ -75,52 +209,62 @@ impl World {
     /// Callers are responsible for ensuring the chunk is reproducible
     /// (procedural baseline + persisted delta) before evicting.
     pub fn remove_chunk(&self, pos: ChunkPos) -> bool {
-        self.sky_lit.remove(&pos);
-        self.chunks.remove(&pos).is_some()
+        let removed = self.chunks.remove(&pos).is_some();
+        if removed {
+            self.notify(|o| o.chunk_removed(pos));
+        }
+        removed
     }
 
     /// Whether this chunk has unsaved modifications.
     pub fn is_dirty(&self, pos: ChunkPos) -> bool {
-        self.dirty.contains(&pos)
+        self.dirty.chunks.contains_key(&pos)
+    }
+
+    /// The sections of this chunk with unsaved modifications, sorted by
+    /// section index. Empty for a clean chunk.
+    pub fn dirty_sections(&self, pos: ChunkPos) -> Vec<i32> {
+        self.dirty.chunks.get(&pos).map(|s| s.clone()).unwrap_or_default()
     }
 
     pub fn chunk_count(&self) -> usize {
         self.chunks.len()
     }
 
-    /// Iterate over all chunks. Each entry is a DashMap ref that derefs to
+    /// Iterate over all chunks. Each entry is a storage ref that derefs to
     /// `(ChunkPos, Chunk)`. Use `*entry.key()` and `&*entry` (value).
-    pub fn iter_chunks(&self) -> dashmap::iter::Iter<'_, ChunkPos, Chunk> {
+    pub fn iter_chunks(&self) -> storage::Iter<'_, ChunkPos, Chunk> {
         self.chunks.iter()
     }
 
     /// Drain and return all chunk positions that have been modified since the
     /// last call. After this returns, the dirty set is empty.
     pub fn take_dirty_chunks(&self) -> Vec<ChunkPos> {
-        let mut dirty = Vec::new();
-        // Collect then remove; a tiny race (chunk dirtied between collect and
-        // remove) just means it'll be re-saved next time -- always safe.
-        for entry in self.dirty.iter() {
-            dirty.push(*entry);
-        }
-        for pos in &dirty {
-            self.dirty.remove(pos);
-        }
-        dirty
+        self.take_dirty_sections().into_iter().map(|(pos, _)| pos).collect()
+    }
+
+    /// Like [`take_dirty_chunks`](Self::take_dirty_chunks), with the dirty
+    /// section indices of each chunk (sorted), so a save can rewrite just
+    /// those.
+    pub fn take_dirty_sections(&self) -> Vec<(ChunkPos, Vec<i32>)> {
+        let positions: Vec<ChunkPos> = self.dirty.chunks.iter().map(|entry| *entry.key()).collect();
+        // Remove each entry whole: a section dirtied after the collect
+        // lands in a fresh entry and is saved next time.
+        positions.into_iter().filter_map(|pos| self.dirty.chunks.remove(&pos)).collect()
     }
 
     /// Number of chunks currently marked dirty.
     pub fn dirty_count(&self) -> usize {
-        self.dirty.len()
+        self.dirty.chunks.len()
     }
 
     /// Get a reference to a single chunk by position, if present.
-    pub fn get_chunk(&self, pos: &ChunkPos) -> Option<dashmap::mapref::one::Ref<'_, ChunkPos, Chunk>> {
+    pub fn get_chunk(&self, pos: &ChunkPos) -> Option<storage::Ref<'_, ChunkPos, Chunk>> {
         self.chunks.get(pos)
     }
 
     /// Get a mutable reference to a single chunk by position, if present.
-    pub fn get_chunk_mut(&self, pos: &ChunkPos) -> Option<dashmap::mapref::one::RefMut<'_, ChunkPos, Chunk>> {
+    pub fn get_chunk_mut(&self, pos: &ChunkPos) -> Option<storage::RefMut<'_, ChunkPos, Chunk>> {
         self.chunks.get_mut(pos)
     }
 
@@ -180,12 +324,73 @@ impl World {
 
     /// Returns `true` if this chunk already has sky light initialized.
     pub fn is_sky_lit(&self, pos: &ChunkPos) -> bool {
-        self.sky_lit.contains(pos)
+        self.sky_lit.chunks.contains(pos)
     }
 
     /// Mark a chunk as having its sky light initialized.
     pub fn mark_sky_lit(&self, pos: ChunkPos) {
-        self.sky_lit.insert(pos);
+        self.sky_lit.chunks.insert(pos);
+    }
+
+    /// Hash of the block contents of every chunk, in chunk order (FNV-1a
+    /// over each chunk's position and [`Chunk::content_hash`]). All-air
+    /// chunks are skipped, so two worlds hash equal exactly when
+    /// [`World::diff`] between them is empty (modulo collisions).
+    pub fn content_hash(&self) -> u64 {
+        let empty = Chunk::new().content_hash();
+        let mut chunks: Vec<(ChunkPos, u64)> = self
+            .iter_chunks()
+            .map(|entry| (*entry.key(), entry.value().content_hash()))
+            .filter(|&(_, h)| h != empty)
+            .collect();
+        chunks.sort_unstable_by_key(|&(pos, _)| pos);
+        let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+        for (pos, chunk_hash) in chunks {
+            for b in pos.x.to_le_bytes().into_iter().chain(pos.z.to_le_bytes()).chain(chunk_hash.to_le_bytes()) {
+                h = (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        }
+        h
+    }
+
+    /// Every block that differs between `self` and `other`, as
+    /// `(pos, ours, theirs)`, sorted by chunk then by section cell. A
+    /// chunk loaded on one side only compares against air. Chunks are
+    /// compared in parallel with the `parallel` feature.
+    pub fn diff(&self, other: &World) -> Vec<(BlockPos, BlockId, BlockId)> {
+        let mut positions: Vec<ChunkPos> =
+            self.iter_chunks().map(|e| *e.key()).chain(other.iter_chunks().map(|e| *e.key())).collect();
+        positions.sort_unstable();
+        positions.dedup();
+
+        let empty = Chunk::new();
+        let diff_chunk = |pos: &ChunkPos| -> Vec<(BlockPos, BlockId, BlockId)> {
+            let ours = self.get_chunk(pos);
+            let theirs = other.get_chunk(pos);
+            let a = ours.as_deref().unwrap_or(&empty);
+            let b = theirs.as_deref().unwrap_or(&empty);
+            a.diff(b)
+                .into_iter()
+                .map(|(local, x, y)| {
+                    let world_pos = BlockPos::new(
+                        pos.x as i64 * 16 + local.x as i64,
+                        local.y,
+                        pos.z as i64 * 16 + local.z as i64,
+                    );
+                    (world_pos, x, y)
+                })
+                .collect()
+        };
+
+        #[cfg(feature = "parallel")]
+        let per_chunk: Vec<Vec<_>> = {
+            use rayon::prelude::*;
+            positions.par_iter().map(diff_chunk).collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let per_chunk: Vec<Vec<_>> = positions.iter().map(diff_chunk).collect();
+
+        per_chunk.into_iter().flatten().collect()
     }
 }
 
@@ -213,4 +418,158 @@ mod tests {
         assert_eq!(world.dirty_count(), 1);
         assert_eq!(world.take_dirty_chunks(), vec![pos_b.chunk()]);
     }
+
+    #[test]
+    fn compare_and_set_writes_only_over_the_expected_block() {
+        let world = World::new();
+        let pos = BlockPos::new(3, 64, 3);
+        assert!(!world.compare_and_set(pos, BlockId::new(5), BlockId::new(1)));
+        assert!(!world.has_chunk(pos.chunk()), "a refused write must not create the chunk");
+
+        assert!(world.compare_and_set(pos, BlockId::AIR, BlockId::new(1)));
+        assert_eq!(world.set_block_if(pos, BlockId::new(2), |b| b == BlockId::AIR), Err(BlockId::new(1)));
+        assert_eq!(world.set_block_if(pos, BlockId::new(2), |b| b != BlockId::AIR), Ok(BlockId::new(1)));
+        assert_eq!(world.get_block(pos), BlockId::new(2));
+        assert!(world.is_dirty(pos.chunk()));
+    }
+
+    #[test]
+    fn generated_chunk_never_replaces_one_already_there() {
+        let world = World::new();
+        let pos = ChunkPos::new(2, -1);
+        assert!(world.insert_chunk_if_absent(pos, Chunk::new()));
+        world.set_block(BlockPos::new(33, 5, -10), BlockId::new(4));
+
+        // A generator that checked before the edit finishes late.
+        assert!(!world.insert_chunk_if_absent(pos, Chunk::new()));
+        assert_eq!(world.get_block(BlockPos::new(33, 5, -10)), BlockId::new(4));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn compare_and_set_has_one_winner_across_threads() {
+        let world = World::new();
+        let pos = BlockPos::new(0, 0, 0);
+        let world = &world;
+        let wins: usize = std::thread::scope(|scope| {
+            let handles: Vec<_> = (1..=8u16)
+                .map(|i| scope.spawn(move || world.compare_and_set(pos, BlockId::AIR, BlockId::new(i)) as usize))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+        assert_eq!(wins, 1);
+        assert_ne!(world.get_block(pos), BlockId::AIR);
+    }
+
+    #[test]
+    fn batches_write_in_order_and_report_once() {
+        let world = World::new();
+        let blocks = std::sync::Arc::new(observer::DirtyBlocks::new(16));
+        world.add_observer(Box::new(std::sync::Arc::clone(&blocks)));
+        let a = BlockPos::new(1, 1, 1);
+        let b = BlockPos::new(40, 1, 1);
+        world.apply_batch(&[(a, BlockId::new(1)), (b, BlockId::new(2)), (a, BlockId::new(3))]);
+        assert_eq!((world.get_block(a), world.get_block(b)), (BlockId::new(3), BlockId::new(2)));
+        assert_eq!(world.take_dirty_chunks().len(), 2);
+        assert_eq!(blocks.drain().blocks.len(), 3);
+
+        let far = BlockPos::new(500, 1, 500);
+        let done = world.compare_and_set_batch(&[
+            (a, BlockId::new(3), BlockId::new(4)),
+            (a, BlockId::new(3), BlockId::new(5)),
+            (b, BlockId::new(2), BlockId::AIR),
+            (far, BlockId::new(9), BlockId::new(1)),
+        ]);
+        assert_eq!(done, vec![true, false, true, false], "the second write sees the first");
+        assert_eq!(world.get_block(a), BlockId::new(4));
+        assert!(!world.has_chunk(far.chunk()), "no chunk made for refused writes");
+    }
+
+    #[test]
+    fn dirty_tracking_is_per_section_and_blocks_drain_per_tick() {
+        let world = World::new();
+        let blocks = std::sync::Arc::new(observer::DirtyBlocks::new(3));
+        world.add_observer(Box::new(std::sync::Arc::clone(&blocks)));
+
+        world.set_block(BlockPos::new(1, 70, 1), BlockId::new(1));
+        world.set_block(BlockPos::new(2, -60, 2), BlockId::new(1));
+        world.set_block(BlockPos::new(3, 64, 3), BlockId::new(1));
+        world.set_block_untracked(BlockPos::new(4, 200, 4), BlockId::new(1));
+        assert_eq!(world.dirty_sections(ChunkPos::new(0, 0)), vec![-4, 4]);
+        assert_eq!(
+            blocks.drain().blocks,
+            vec![BlockPos::new(1, 70, 1), BlockPos::new(2, -60, 2), BlockPos::new(3, 64, 3)],
+        );
+
+        for x in 0..4 {
+            world.set_block(BlockPos::new(x, 0, 0), BlockId::new(2));
+        }
+        let drained = blocks.drain();
+        assert!(drained.overflowed && drained.blocks.len() == 3, "capped");
+        assert_eq!(blocks.drain(), observer::DirtyBlockList::default());
+
+        assert_eq!(world.take_dirty_sections(), vec![(ChunkPos::new(0, 0), vec![-4, 0, 4])]);
+        assert!(!world.is_dirty(ChunkPos::new(0, 0)));
+        assert!(world.dirty_sections(ChunkPos::new(0, 0)).is_empty());
+    }
+
+    #[test]
+    fn observers_see_writes_and_chunk_lifecycle() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Default)]
+        struct Log(Mutex<Vec<String>>);
+        struct Recorder(Arc<Log>);
+        impl WorldObserver for Recorder {
+            fn block_set(&self, pos: BlockPos, old: BlockId, new: BlockId, tracked: bool) {
+                self.0.0.lock().unwrap().push(format!("set {} {} {}->{} {}", pos.x, pos.y, old.0, new.0, tracked));
+            }
+            fn chunk_inserted(&self, pos: ChunkPos) {
+                self.0.0.lock().unwrap().push(format!("insert {} {}", pos.x, pos.z));
+            }
+            fn chunk_removed(&self, pos: ChunkPos) {
+                self.0.0.lock().unwrap().push(format!("remove {} {}", pos.x, pos.z));
+            }
+        }
+
+        let world = World::new();
+        let log = Arc::new(Log::default());
+        world.add_observer(Box::new(Recorder(log.clone())));
+
+        world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+        world.set_block(BlockPos::new(1, 2, 3), BlockId::new(5));
+        world.set_block_untracked(BlockPos::new(1, 2, 3), BlockId::new(6));
+        world.mark_sky_lit(ChunkPos::new(0, 0));
+        assert!(world.remove_chunk(ChunkPos::new(0, 0)));
+        assert!(!world.remove_chunk(ChunkPos::new(0, 0)), "second evict is a no-op");
+
+        assert_eq!(
+            *log.0.lock().unwrap(),
+            ["insert 0 0", "set 1 2 0->5 true", "set 1 2 5->6 false", "remove 0 0"]
+        );
+        assert!(!world.is_sky_lit(&ChunkPos::new(0, 0)), "eviction forgets sky light");
+    }
+
+    #[test]
+    fn diff_and_content_hash_agree() {
+        let a = World::new();
+        let b = World::new();
+        a.set_block(BlockPos::new(1, -3, 2), BlockId::new(4));
+        b.set_block(BlockPos::new(1, -3, 2), BlockId::new(4));
+        // An all-air chunk on one side only is not a difference.
+        b.set_block(BlockPos::new(100, 5, 100), BlockId::AIR);
+        assert!(a.diff(&b).is_empty());
+        assert_eq!(a.content_hash(), b.content_hash());
+
+        b.set_block(BlockPos::new(-20, 70, 33), BlockId::new(9));
+        a.set_block(BlockPos::new(1, -3, 2), BlockId::new(5));
+        assert_eq!(
+            a.diff(&b),
+            [
+                (BlockPos::new(-20, 70, 33), BlockId::AIR, BlockId::new(9)),
+                (BlockPos::new(1, -3, 2), BlockId::new(5), BlockId::new(4)),
+            ]
+        );
+        assert_ne!(a.content_hash(), b.content_hash());
+    }
 }
@@ -0,0 +1,153 @@
+//! Change notifications for the block world.
+//!
+//! Subsystems that derive state from the world (dirty tracking, light
+//! bookkeeping, caches keyed by chunk) implement [`WorldObserver`] and are
+//! told about each write as it lands, instead of scanning chunks to find
+//! out what changed.
+
+use std::sync::{Arc, Mutex};
+
+use super::block::BlockId;
+use super::position::{BlockPos, ChunkPos};
+use super::storage::{Map, Set, Shared};
+
+/// Receives callbacks for every mutation of a [`World`](super::World).
+///
+/// Callbacks run synchronously on the writing thread, after the chunk lock
+/// has been released, so an observer may read the world but should stay
+/// cheap: `block_set` sits on the physics hot path.
+pub trait WorldObserver: Shared {
+    /// A block was written. `tracked` is `false` for generation writes made
+    /// through `set_block_untracked`.
+    fn block_set(&self, _pos: BlockPos, _old: BlockId, _new: BlockId, _tracked: bool) {}
+
+    /// Several blocks were written by one `World::apply_batch`, as
+    /// `(pos, old, new)`, grouped by chunk. Override to take locks once
+    /// per batch instead of once per block.
+    fn blocks_set(&self, writes: &[(BlockPos, BlockId, BlockId)], tracked: bool) {
+        for &(pos, old, new) in writes {
+            self.block_set(pos, old, new, tracked);
+        }
+    }
+
+    /// A whole chunk was inserted (generation or load), replacing any
+    /// chunk previously at `pos`.
+    fn chunk_inserted(&self, _pos: ChunkPos) {}
+
+    /// A chunk was evicted from memory.
+    fn chunk_removed(&self, _pos: ChunkPos) {}
+}
+
+/// Lets a consumer keep its own handle to an observer it registers.
+impl<T: WorldObserver + ?Sized> WorldObserver for Arc<T> {
+    fn block_set(&self, pos: BlockPos, old: BlockId, new: BlockId, tracked: bool) {
+        (**self).block_set(pos, old, new, tracked);
+    }
+
+    fn blocks_set(&self, writes: &[(BlockPos, BlockId, BlockId)], tracked: bool) {
+        (**self).blocks_set(writes, tracked);
+    }
+
+    fn chunk_inserted(&self, pos: ChunkPos) {
+        (**self).chunk_inserted(pos);
+    }
+
+    fn chunk_removed(&self, pos: ChunkPos) {
+        (**self).chunk_removed(pos);
+    }
+}
+
+/// Chunks with gameplay modifications since the last save, and which of
+/// their sections (by section index, sorted) were touched.
+#[derive(Default)]
+pub(crate) struct DirtyTracker {
+    pub(crate) chunks: Map<ChunkPos, Vec<i32>>,
+}
+
+impl WorldObserver for DirtyTracker {
+    fn block_set(&self, pos: BlockPos, old: BlockId, new: BlockId, tracked: bool) {
+        self.blocks_set(&[(pos, old, new)], tracked);
+    }
+
+    fn blocks_set(&self, writes: &[(BlockPos, BlockId, BlockId)], tracked: bool) {
+        if !tracked {
+            return;
+        }
+        for run in writes.chunk_by(|a, b| a.0.chunk() == b.0.chunk()) {
+            let mut sections = self.chunks.entry(run[0].0.chunk()).or_default();
+            for (pos, _, _) in run {
+                let section = pos.local().section_index();
+                if let Err(at) = sections.binary_search(&section) {
+                    sections.insert(at, section);
+                }
+            }
+        }
+    }
+}
+
+/// Tracked block writes since the last [`drain`](Self::drain), for
+/// consumers that sync per tick (network, mirrors) and want the blocks
+/// rather than whole chunks. Register a clone with
+/// [`World::add_observer`](super::World::add_observer). Past `cap`
+/// positions it stops recording and reports an overflow, and the consumer
+/// falls back to the chunks it cares about.
+pub struct DirtyBlocks {
+    cap: usize,
+    pending: Mutex<DirtyBlockList>,
+}
+
+/// What [`DirtyBlocks::drain`] returns.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DirtyBlockList {
+    /// In write order; a block written twice appears twice.
+    pub blocks: Vec<BlockPos>,
+    /// More than `cap` writes happened and some were dropped.
+    pub overflowed: bool,
+}
+
+impl DirtyBlocks {
+    pub fn new(cap: usize) -> Self {
+        Self { cap, pending: Mutex::new(DirtyBlockList::default()) }
+    }
+
+    /// Take everything recorded so far.
+    pub fn drain(&self) -> DirtyBlockList {
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+}
+
+impl WorldObserver for DirtyBlocks {
+    fn block_set(&self, pos: BlockPos, old: BlockId, new: BlockId, tracked: bool) {
+        self.blocks_set(&[(pos, old, new)], tracked);
+    }
+
+    fn blocks_set(&self, writes: &[(BlockPos, BlockId, BlockId)], tracked: bool) {
+        if !tracked {
+            return;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        for &(pos, old, new) in writes {
+            if old == new {
+                continue;
+            }
+            if pending.blocks.len() < self.cap {
+                pending.blocks.push(pos);
+            } else {
+                pending.overflowed = true;
+            }
+        }
+    }
+}
+
+/// Chunks whose sky light has been initialized. Forgets evicted chunks so
+/// a regenerated copy is relit.
+#[derive(Default)]
+pub(crate) struct SkyLitTracker {
+    pub(crate) chunks: Set<ChunkPos>,
+}
+
+impl WorldObserver for SkyLitTracker {
+    fn chunk_removed(&self, pos: ChunkPos) {
+        self.chunks.remove(&pos);
+    }
+}
@@ -28,6 +28,16 @@ impl BlockPos {
         }
     }
 
+    pub const fn offset(&self, dx: i64, dy: i64, dz: i64) -> BlockPos {
+        Self::new(self.x + dx, self.y + dy, self.z + dz)
+    }
+
+    /// The neighbor one step toward `dir`.
+    pub const fn relative(&self, dir: Direction) -> BlockPos {
+        let [dx, dy, dz] = dir.offset();
+        self.offset(dx, dy, dz)
+    }
+
     /// The six cardinal neighbors.
     pub const fn neighbors(&self) -> [BlockPos; 6] {
         [
@@ -39,10 +49,98 @@ impl BlockPos {
             Self::new(self.x, self.y, self.z - 1),
         ]
     }
+
+    /// The six cardinal neighbors with the direction each lies in, in
+    /// [`Direction::ALL`] order.
+    pub fn directions(&self) -> [(Direction, BlockPos); 6] {
+        Direction::ALL.map(|dir| (dir, self.relative(dir)))
+    }
+
+    /// The four horizontal neighbors, in [`Direction::HORIZONTAL`] order.
+    pub fn horizontal_neighbors(&self) -> [BlockPos; 4] {
+        Direction::HORIZONTAL.map(|dir| self.relative(dir))
+    }
+
+    /// Blocks along the axes between the two (taxicab distance).
+    pub const fn manhattan(&self, other: BlockPos) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
+    }
+
+    /// The largest per-axis difference: the radius of the smallest cube
+    /// around `self` that holds `other`.
+    pub const fn chebyshev(&self, other: BlockPos) -> i64 {
+        let (dx, dy, dz) = ((self.x - other.x).abs(), (self.y - other.y).abs(), (self.z - other.z).abs());
+        let xz = if dx > dz { dx } else { dz };
+        if xz > dy { xz } else { dy }
+    }
+
+    /// Every block in the box with corners `a` and `b` (inclusive, either
+    /// order): x fastest, then z, then y.
+    pub fn iter_cuboid(a: BlockPos, b: BlockPos) -> impl Iterator<Item = BlockPos> {
+        let (min, max) = (
+            Self::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            Self::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        );
+        (min.y..=max.y).flat_map(move |y| {
+            (min.z..=max.z).flat_map(move |z| (min.x..=max.x).map(move |x| Self::new(x, y, z)))
+        })
+    }
 }
 
-/// Chunk column position (each chunk is 16x16 blocks horizontally).
+/// One of the six axis-aligned directions. North is -Z and east +X, as
+/// in Minecraft.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Down,
+    Up,
+    North,
+    South,
+    West,
+    East,
+}
+
+impl Direction {
+    /// In Minecraft's 3D data-value order (down, up, north, south, west,
+    /// east).
+    pub const ALL: [Direction; 6] = [
+        Direction::Down,
+        Direction::Up,
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ];
+
+    pub const HORIZONTAL: [Direction; 4] =
+        [Direction::North, Direction::South, Direction::West, Direction::East];
+
+    /// Unit step `[dx, dy, dz]`.
+    pub const fn offset(self) -> [i64; 3] {
+        match self {
+            Direction::Down => [0, -1, 0],
+            Direction::Up => [0, 1, 0],
+            Direction::North => [0, 0, -1],
+            Direction::South => [0, 0, 1],
+            Direction::West => [-1, 0, 0],
+            Direction::East => [1, 0, 0],
+        }
+    }
+
+    pub const fn opposite(self) -> Direction {
+        match self {
+            Direction::Down => Direction::Up,
+            Direction::Up => Direction::Down,
+            Direction::North => Direction::South,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+            Direction::East => Direction::West,
+        }
+    }
+}
+
+/// Chunk column position (each chunk is 16x16 blocks horizontally).
+/// Ordered by `x`, then `z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ChunkPos {
     pub x: i32,
     pub z: i32,
@@ -56,6 +154,19 @@ impl ChunkPos {
     pub const fn block_origin(&self, y: i64) -> BlockPos {
         BlockPos::new((self.x as i64) << 4, y, (self.z as i64) << 4)
     }
+
+    /// The largest per-axis difference, in chunks.
+    pub const fn chebyshev(&self, other: ChunkPos) -> i32 {
+        let (dx, dz) = ((self.x - other.x).abs(), (self.z - other.z).abs());
+        if dx > dz { dx } else { dz }
+    }
+
+    /// Every chunk in the square of `radius` chunks around this one
+    /// (`(2r+1)²` of them), z fastest, then x.
+    pub fn chunks_in_radius(&self, radius: i32) -> impl Iterator<Item = ChunkPos> {
+        let center = *self;
+        (-radius..=radius).flat_map(move |dx| (-radius..=radius).map(move |dz| ChunkPos::new(center.x + dx, center.z + dz)))
+    }
 }
 
 /// Block position local to a chunk (x, z in 0..16).
@@ -75,3 +186,46 @@ impl LocalBlockPos {
         (self.y.rem_euclid(16)) as u8
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_directions_and_distances() {
+        let pos = BlockPos::new(3, 64, -2);
+        assert_eq!(pos.offset(1, -2, 3), BlockPos::new(4, 62, 1));
+        assert_eq!(pos.relative(Direction::North), BlockPos::new(3, 64, -3));
+        let around: Vec<BlockPos> = pos.directions().iter().map(|&(_, p)| p).collect();
+        let mut neighbors = pos.neighbors().to_vec();
+        neighbors.sort_by_key(|p| (p.x, p.y, p.z));
+        let mut sorted = around.clone();
+        sorted.sort_by_key(|p| (p.x, p.y, p.z));
+        assert_eq!(sorted, neighbors, "directions cover the same six blocks");
+        assert!(pos.directions().iter().all(|&(dir, p)| p.manhattan(pos) == 1 && pos.relative(dir) == p));
+        assert_eq!(pos.horizontal_neighbors().iter().filter(|p| p.y == pos.y).count(), 4);
+        for dir in Direction::ALL {
+            assert_eq!(dir.opposite().opposite(), dir);
+            assert_eq!(pos.relative(dir).relative(dir.opposite()), pos);
+        }
+
+        let far = BlockPos::new(-1, 70, 0);
+        assert_eq!(pos.manhattan(far), 4 + 6 + 2);
+        assert_eq!(pos.chebyshev(far), 6);
+    }
+
+    #[test]
+    fn cuboids_and_chunk_squares() {
+        let cells: Vec<BlockPos> = BlockPos::iter_cuboid(BlockPos::new(1, 1, 1), BlockPos::new(0, 0, 0)).collect();
+        assert_eq!(cells.len(), 8);
+        assert_eq!(cells[0], BlockPos::new(0, 0, 0));
+        assert_eq!(cells[1], BlockPos::new(1, 0, 0), "x fastest");
+        assert_eq!(cells[7], BlockPos::new(1, 1, 1));
+
+        let center = ChunkPos::new(-3, 5);
+        let square: Vec<ChunkPos> = center.chunks_in_radius(2).collect();
+        assert_eq!(square.len(), 25);
+        assert!(square.iter().all(|c| c.chebyshev(center) <= 2));
+        assert_eq!(center.chunks_in_radius(0).collect::<Vec<_>>(), vec![center]);
+    }
+}
@@ -0,0 +1,258 @@
+//! Chunk-keyed storage behind `World`.
+//!
+//! With the default `parallel` feature this is `dashmap` — lock-sharded so
+//! physics workers, network tasks and autosave can all touch the world at
+//! once. Without it (wasm32, client-side prediction) a `RefCell`-backed map
+//! with the same call surface stands in, so `World` and everything built
+//! on it compiles unchanged for single-threaded targets.
+
+#[cfg(feature = "parallel")]
+pub use dashmap::{DashMap as Map, DashSet as Set};
+#[cfg(feature = "parallel")]
+pub use dashmap::iter::Iter;
+#[cfg(feature = "parallel")]
+pub use dashmap::mapref::one::{Ref, RefMut};
+
+#[cfg(not(feature = "parallel"))]
+pub use local::{Iter, Map, Ref, RefMut, Set, insert_if_absent};
+
+/// Insert `value` at `key` unless something is there already, atomically
+/// with respect to other inserts. Returns whether it was inserted.
+#[cfg(feature = "parallel")]
+pub fn insert_if_absent<K: Eq + std::hash::Hash + Clone, V>(map: &Map<K, V>, key: K, value: V) -> bool {
+    use dashmap::mapref::entry::Entry;
+    match map.entry(key) {
+        Entry::Occupied(_) => false,
+        Entry::Vacant(slot) => {
+            slot.insert(value);
+            true
+        }
+    }
+}
+
+/// `Send + Sync` when the world is shared across threads, nothing
+/// otherwise. Bound on anything `World` holds by trait object.
+#[cfg(feature = "parallel")]
+pub trait Shared: Send + Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Send + Sync + ?Sized> Shared for T {}
+
+#[cfg(not(feature = "parallel"))]
+pub trait Shared {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> Shared for T {}
+
+#[cfg(not(feature = "parallel"))]
+mod local {
+    use std::cell::{self, RefCell};
+    use std::collections::{HashMap, HashSet};
+    use std::hash::Hash;
+    use std::ops::{Deref, DerefMut};
+
+    /// Single-threaded stand-in for `DashMap`. Guards borrow the whole
+    /// map, so holding a `RefMut` while touching another key panics the
+    /// same way a dashmap shard deadlock would hang.
+    pub struct Map<K, V>(RefCell<HashMap<K, V>>);
+
+    impl<K: Eq + Hash + Clone, V> Map<K, V> {
+        pub fn new() -> Self {
+            Self(RefCell::new(HashMap::new()))
+        }
+
+        pub fn get(&self, key: &K) -> Option<Ref<'_, K, V>> {
+            let value = cell::Ref::filter_map(self.0.borrow(), |m| m.get(key)).ok()?;
+            Some(Ref { key: key.clone(), value })
+        }
+
+        pub fn get_mut(&self, key: &K) -> Option<RefMut<'_, K, V>> {
+            let value = cell::RefMut::filter_map(self.0.borrow_mut(), |m| m.get_mut(key)).ok()?;
+            Some(RefMut { key: key.clone(), value })
+        }
+
+        pub fn entry(&self, key: K) -> Entry<'_, K, V> {
+            Entry { map: self, key }
+        }
+
+        pub fn insert(&self, key: K, value: V) -> Option<V> {
+            self.0.borrow_mut().insert(key, value)
+        }
+
+        pub fn remove(&self, key: &K) -> Option<(K, V)> {
+            self.0.borrow_mut().remove_entry(key)
+        }
+
+        pub fn contains_key(&self, key: &K) -> bool {
+            self.0.borrow().contains_key(key)
+        }
+
+        pub fn len(&self) -> usize {
+            self.0.borrow().len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.0.borrow().is_empty()
+        }
+
+        /// Iterate over a snapshot of the keys present at the call; each
+        /// item re-borrows the map, so entries removed mid-iteration are
+        /// skipped rather than panicking.
+        pub fn iter(&self) -> Iter<'_, K, V> {
+            let keys: Vec<K> = self.0.borrow().keys().cloned().collect();
+            Iter { map: self, keys: keys.into_iter() }
+        }
+    }
+
+    pub fn insert_if_absent<K: Eq + Hash + Clone, V>(map: &Map<K, V>, key: K, value: V) -> bool {
+        let mut inner = map.0.borrow_mut();
+        if inner.contains_key(&key) {
+            return false;
+        }
+        inner.insert(key, value);
+        true
+    }
+
+    impl<K: Eq + Hash + Clone, V> Default for Map<K, V> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    pub struct Entry<'a, K, V> {
+        map: &'a Map<K, V>,
+        key: K,
+    }
+
+    impl<'a, K: Eq + Hash + Clone, V: Default> Entry<'a, K, V> {
+        pub fn or_default(self) -> RefMut<'a, K, V> {
+            let key = self.key;
+            let value = cell::RefMut::map(self.map.0.borrow_mut(), |m| {
+                m.entry(key.clone()).or_default()
+            });
+            RefMut { key, value }
+        }
+    }
+
+    pub struct Ref<'a, K, V> {
+        key: K,
+        value: cell::Ref<'a, V>,
+    }
+
+    impl<K, V> Ref<'_, K, V> {
+        pub fn key(&self) -> &K {
+            &self.key
+        }
+
+        pub fn value(&self) -> &V {
+            &self.value
+        }
+    }
+
+    impl<K, V> Deref for Ref<'_, K, V> {
+        type Target = V;
+        fn deref(&self) -> &V {
+            &self.value
+        }
+    }
+
+    pub struct RefMut<'a, K, V> {
+        key: K,
+        value: cell::RefMut<'a, V>,
+    }
+
+    impl<K, V> RefMut<'_, K, V> {
+        pub fn key(&self) -> &K {
+            &self.key
+        }
+
+        pub fn value(&self) -> &V {
+            &self.value
+        }
+
+        pub fn value_mut(&mut self) -> &mut V {
+            &mut self.value
+        }
+    }
+
+    impl<K, V> Deref for RefMut<'_, K, V> {
+        type Target = V;
+        fn deref(&self) -> &V {
+            &self.value
+        }
+    }
+
+    impl<K, V> DerefMut for RefMut<'_, K, V> {
+        fn deref_mut(&mut self) -> &mut V {
+            &mut self.value
+        }
+    }
+
+    pub struct Iter<'a, K, V> {
+        map: &'a Map<K, V>,
+        keys: std::vec::IntoIter<K>,
+    }
+
+    impl<'a, K: Eq + Hash + Clone, V> Iterator for Iter<'a, K, V> {
+        type Item = Ref<'a, K, V>;
+        fn next(&mut self) -> Option<Self::Item> {
+            self.keys.by_ref().find_map(|key| self.map.get(&key))
+        }
+    }
+
+    /// Single-threaded stand-in for `DashSet`.
+    pub struct Set<K>(RefCell<HashSet<K>>);
+
+    impl<K: Eq + Hash + Clone> Set<K> {
+        pub fn new() -> Self {
+            Self(RefCell::new(HashSet::new()))
+        }
+
+        pub fn insert(&self, key: K) -> bool {
+            self.0.borrow_mut().insert(key)
+        }
+
+        pub fn remove(&self, key: &K) -> Option<K> {
+            self.0.borrow_mut().take(key)
+        }
+
+        pub fn contains(&self, key: &K) -> bool {
+            self.0.borrow().contains(key)
+        }
+
+        pub fn len(&self) -> usize {
+            self.0.borrow().len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.0.borrow().is_empty()
+        }
+
+        /// Iterate over a snapshot of the members.
+        pub fn iter(&self) -> impl Iterator<Item = SetRef<K>> {
+            let keys: Vec<K> = self.0.borrow().iter().cloned().collect();
+            keys.into_iter().map(SetRef)
+        }
+    }
+
+    impl<K: Eq + Hash + Clone> Default for Set<K> {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// A set member yielded by `Set::iter`; derefs to the key like
+    /// dashmap's `RefMulti`.
+    pub struct SetRef<K>(K);
+
+    impl<K> SetRef<K> {
+        pub fn key(&self) -> &K {
+            &self.0
+        }
+    }
+
+    impl<K> Deref for SetRef<K> {
+        type Target = K;
+        fn deref(&self) -> &K {
+            &self.0
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use super::World;
+use super::block::BlockId;
+use super::chunk::Chunk;
+use super::position::{BlockPos, ChunkPos, LocalBlockPos};
+use super::storage;
+
+/// What a rule sees of the world: block reads, plus the light storage the
+/// light rule floods synchronously. There is no way to write a block
+/// through a view — rules change blocks only by emitting `BlockSet`s.
+///
+/// That is what makes the parallel steps deterministic. They apply every
+/// write of a wave first and only then evaluate rules, so while any rule
+/// runs the blocks are frozen at the post-wave state, whichever groups
+/// run alongside it. Light is the exception: it is rule-written scratch
+/// state, and floods from neighbouring groups may still interleave.
+#[derive(Clone, Copy)]
+pub struct WorldView<'w> {
+    world: &'w World,
+}
+
+impl World {
+    /// The read view rules are evaluated against.
+    pub fn view(&self) -> WorldView<'_> {
+        WorldView { world: self }
+    }
+}
+
+impl<'w> WorldView<'w> {
+    /// Read a block at an absolute position. Returns AIR for unloaded chunks.
+    #[inline]
+    pub fn get_block(&self, pos: BlockPos) -> BlockId {
+        self.world.get_block(pos)
+    }
+
+    pub fn has_chunk(&self, pos: ChunkPos) -> bool {
+        self.world.has_chunk(pos)
+    }
+
+    /// Get a shared reference to a single chunk by position, if present.
+    pub fn get_chunk(&self, pos: &ChunkPos) -> Option<storage::Ref<'w, ChunkPos, Chunk>> {
+        self.world.get_chunk(pos)
+    }
+
+    // ── Light ───────────────────────────────────────────────────────────
+
+    pub fn is_sky_lit(&self, pos: &ChunkPos) -> bool {
+        self.world.is_sky_lit(pos)
+    }
+
+    pub fn get_sky_light(&self, pos: BlockPos) -> u8 {
+        self.world.get_sky_light(pos)
+    }
+
+    pub fn set_sky_light(&self, pos: BlockPos, val: u8) {
+        self.world.set_sky_light(pos, val);
+    }
+
+    pub fn get_block_light(&self, pos: BlockPos) -> u8 {
+        self.world.get_block_light(pos)
+    }
+
+    pub fn set_block_light(&self, pos: BlockPos, val: u8) {
+        self.world.set_block_light(pos, val);
+    }
+
+    /// Lock one loaded chunk for a run of light reads and writes (BFS hot
+    /// loops), without exposing its blocks for writing.
+    pub fn light_chunk(&self, pos: &ChunkPos) -> Option<LightChunk<'w>> {
+        self.world.get_chunk_mut(pos).map(LightChunk)
+    }
+}
+
+/// A locked chunk whose light may be written and whose blocks may only be
+/// read. See [`WorldView::light_chunk`].
+pub struct LightChunk<'w>(storage::RefMut<'w, ChunkPos, Chunk>);
+
+impl LightChunk<'_> {
+    #[inline]
+    pub fn get_block(&self, pos: LocalBlockPos) -> BlockId {
+        self.0.get_block(pos)
+    }
+
+    #[inline]
+    pub fn get_sky_light(&self, pos: LocalBlockPos) -> u8 {
+        self.0.get_sky_light(pos)
+    }
+
+    #[inline]
+    pub fn set_sky_light(&mut self, pos: LocalBlockPos, val: u8) {
+        self.0.set_sky_light(pos, val);
+    }
+
+    #[inline]
+    pub fn get_block_light(&self, pos: LocalBlockPos) -> u8 {
+        self.0.get_block_light(pos)
+    }
+
+    #[inline]
+    pub fn set_block_light(&mut self, pos: LocalBlockPos, val: u8) {
+        self.0.set_block_light(pos, val);
+    }
+}
@@ -7,8 +7,8 @@ use ultimate_engine::causal::scheduler::Scheduler;
 use ultimate_engine::rules::RuleSet;
 use ultimate_engine::world::block::BlockId;
 use ultimate_engine::world::chunk::{Chunk, SECTION_SIZE};
-use ultimate_engine::world::position::{BlockPos, ChunkPos, LocalBlockPos};
-use ultimate_engine::world::World;
+use ultimate_engine::world::position::{BlockPos, ChunkPos, Direction, LocalBlockPos};
+use ultimate_engine::world::{World, WorldView};
 
 // ---------------------------------------------------------------------------
 // CausalGraph unit tests
@@ -20,6 +20,7 @@ fn graph_insert_and_retrieve() {
     let id = g.insert_root(Event {
         payload: EventPayload::BlockNotify {
             pos: BlockPos::new(0, 0, 0),
+            from: None,
         },
     });
     assert_eq!(g.len(), 1);
@@ -31,10 +32,10 @@ fn graph_insert_and_retrieve() {
 fn graph_frontier_roots_only() {
     let mut g = CausalGraph::new();
     let a = g.insert_root(Event {
-        payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 0, 0) },
+        payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 0, 0), from: None },
     });
     let b = g.insert_root(Event {
-        payload: EventPayload::BlockNotify { pos: BlockPos::new(1, 0, 0) },
+        payload: EventPayload::BlockNotify { pos: BlockPos::new(1, 0, 0), from: None },
     });
 
     let frontier = g.frontier();
@@ -47,12 +48,12 @@ fn graph_frontier_roots_only() {
 fn graph_frontier_respects_dependencies() {
     let mut g = CausalGraph::new();
     let a = g.insert_root(Event {
-        payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 0, 0) },
+        payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 0, 0), from: None },
     });
     // b depends on a
     let b = g.insert(
         Event {
-            payload: EventPayload::BlockNotify { pos: BlockPos::new(1, 0, 0) },
+            payload: EventPayload::BlockNotify { pos: BlockPos::new(1, 0, 0), from: None },
         },
         vec![a],
     );
@@ -74,18 +75,18 @@ fn graph_diamond_dependency() {
     // A diamond: root -> {left, right} -> join
     let mut g = CausalGraph::new();
     let root = g.insert_root(Event {
-        payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 0, 0) },
+        payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 0, 0), from: None },
     });
     let left = g.insert(
-        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(1, 0, 0) } },
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(1, 0, 0), from: None } },
         vec![root],
     );
     let right = g.insert(
-        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(2, 0, 0) } },
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(2, 0, 0), from: None } },
         vec![root],
     );
     let join = g.insert(
-        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(3, 0, 0) } },
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(3, 0, 0), from: None } },
         vec![left, right],
     );
 
@@ -128,7 +129,7 @@ fn dot_export_is_valid() {
     });
     let _b = g.insert(
         Event {
-            payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 4, 0) },
+            payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 4, 0), from: None },
         },
         vec![a],
     );
@@ -167,11 +168,11 @@ fn dedup_notifies_at_same_position_coalesce() {
 
     // Two BlockNotify at the same pos — should coalesce into one node.
     let n1 = g.insert(
-        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5) } },
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5), from: None } },
         vec![a],
     );
     let n2 = g.insert(
-        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5) } },
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5), from: None } },
         vec![b],
     );
 
@@ -185,6 +186,23 @@ fn dedup_notifies_at_same_position_coalesce() {
     assert!(merged.parents.contains(&b));
 }
 
+#[test]
+fn dedup_directed_notifies_coalesce_per_side() {
+    let mut g = CausalGraph::new();
+    let root = g.insert_root(Event {
+        payload: EventPayload::BlockSet { pos: BlockPos::new(0, 0, 0), old: BlockId::AIR, new: BlockId::new(1) },
+    });
+    let notify = |from| Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5), from } };
+
+    let up = g.insert(notify(Some(Direction::Up)), vec![root]);
+    let east = g.insert(notify(Some(Direction::East)), vec![root]);
+    let plain = g.insert(notify(None), vec![root]);
+    assert_ne!(up, east, "notifies from different sides stay apart");
+    assert_ne!(up, plain);
+    assert_eq!(g.insert(notify(Some(Direction::Up)), vec![root]), up, "same side coalesces");
+    assert_eq!(g.len(), 4);
+}
+
 #[test]
 fn dedup_different_positions_do_not_coalesce() {
     let mut g = CausalGraph::new();
@@ -196,11 +214,11 @@ fn dedup_different_positions_do_not_coalesce() {
         },
     });
     let n1 = g.insert(
-        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(1, 0, 0) } },
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(1, 0, 0), from: None } },
         vec![a],
     );
     let n2 = g.insert(
-        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(2, 0, 0) } },
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(2, 0, 0), from: None } },
         vec![a],
     );
     assert_ne!(n1, n2);
@@ -243,7 +261,7 @@ fn dedup_waits_for_merged_parents() {
     });
     // First notify depends on `early` (which is a root, ready).
     let n = g.insert(
-        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5) } },
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5), from: None } },
         vec![early],
     );
     // Execute `early` so the notify becomes ready.
@@ -259,7 +277,7 @@ fn dedup_waits_for_merged_parents() {
         },
     });
     let n2 = g.insert(
-        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5) } },
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5), from: None } },
         vec![late],
     );
     assert_eq!(n, n2);
@@ -291,7 +309,7 @@ fn dedup_after_pop_creates_fresh_event() {
     g.mark_executed(a);
 
     let n1 = g.insert(
-        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5) } },
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5), from: None } },
         vec![a],
     );
     let batch = g.drain_ready(10);
@@ -299,11 +317,45 @@ fn dedup_after_pop_creates_fresh_event() {
 
     // Post-pop: a new notify at the same pos gets a fresh id.
     let n2 = g.insert_root(Event {
-        payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5) },
+        payload: EventPayload::BlockNotify { pos: BlockPos::new(5, 5, 5), from: None },
     });
     assert_ne!(n1, n2);
 }
 
+#[test]
+fn pop_frontier_counts_down_pending_parents() {
+    // A join with two parents becomes ready only after both execute, and
+    // is queued once even though each parent's execution touches it.
+    let mut g = CausalGraph::new();
+    let left = g.insert_root(Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 0, 0), from: None } });
+    let right = g.insert_root(Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(1, 0, 0), from: None } });
+    let join = g.insert(
+        Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(2, 0, 0), from: None } },
+        vec![left, right, left],
+    );
+
+    assert_eq!(g.pop_frontier(), Some(left));
+    g.mark_executed(left);
+    g.mark_executed(left);
+    assert_eq!(g.frontier(), vec![right], "join still waits on right");
+
+    assert_eq!(g.pop_frontier(), Some(right));
+    g.mark_executed(right);
+    assert_eq!(g.frontier(), vec![join]);
+    assert_eq!(g.pop_frontier(), Some(join));
+    assert_eq!(g.pop_frontier(), None);
+}
+
+#[test]
+fn frontier_skips_events_executed_without_popping() {
+    let mut g = CausalGraph::new();
+    let a = g.insert_root(Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(0, 0, 0), from: None } });
+    let b = g.insert_root(Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(1, 0, 0), from: None } });
+    g.mark_executed(a);
+    assert_eq!(g.frontier(), vec![b]);
+    assert_eq!(g.drain_ready(10), vec![b]);
+}
+
 // ---------------------------------------------------------------------------
 // Quiescence test (empty RuleSet -- no rules means no consequents)
 // ---------------------------------------------------------------------------
@@ -314,10 +366,55 @@ fn dedup_after_pop_creates_fresh_event() {
 
 fn notify_at(x: i64) -> Event {
     Event {
-        payload: EventPayload::BlockNotify { pos: BlockPos::new(x, 0, 0) },
+        payload: EventPayload::BlockNotify { pos: BlockPos::new(x, 0, 0), from: None },
     }
 }
 
+#[test]
+fn ancestry_and_blast_radius() {
+    // a -> b -> d, a -> c -> d, plus an unrelated e.
+    let mut g = CausalGraph::new();
+    let a = g.insert_root(notify_at(0));
+    let b = g.insert(notify_at(1), vec![a]);
+    let c = g.insert(notify_at(2), vec![a]);
+    let d = g.insert(notify_at(3), vec![b, c]);
+    let e = g.insert_root(notify_at(9));
+
+    assert_eq!(g.ancestors(d), vec![b, c, a], "nearest first, a once");
+    assert_eq!(g.descendants(a), vec![b, c, d]);
+    assert!(g.ancestors(a).is_empty() && g.descendants(e).is_empty());
+
+    let positions = g.affected_positions();
+    assert_eq!(positions.len(), 5);
+    assert!(positions.contains(&BlockPos::new(9, 0, 0)));
+}
+
+#[test]
+fn recent_window_bounds_and_cursors() {
+    use std::time::Duration;
+    use ultimate_engine::causal::graph::RecentWindow;
+
+    let mut g = CausalGraph::new().with_recent_window(RecentWindow { max_nodes: 3, max_age: None });
+    let ids: Vec<_> = (0..5).map(|x| g.insert_root(notify_at(x))).collect();
+    assert_eq!(g.recent_node_ids().collect::<Vec<_>>(), ids[2..], "the last three");
+
+    let cursor = g.recent_cursor();
+    assert_eq!(g.recent_since(cursor).count(), 0);
+    let newer = g.insert_root(notify_at(5));
+    assert_eq!(g.recent_since(cursor).collect::<Vec<_>>(), vec![newer]);
+
+    let mut aged = CausalGraph::new()
+        .with_recent_window(RecentWindow { max_nodes: 100, max_age: Some(Duration::from_millis(20)) });
+    aged.insert_root(notify_at(0));
+    std::thread::sleep(Duration::from_millis(40));
+    assert_eq!(aged.recent_node_ids().count(), 0, "aged out on read");
+    let fresh = aged.insert_root(notify_at(1));
+    assert_eq!(aged.recent_since(0).collect::<Vec<_>>(), vec![fresh]);
+
+    let off = CausalGraph::new().with_recent_window(RecentWindow { max_nodes: 0, max_age: None });
+    assert_eq!(off.recent_node_ids().count(), 0);
+}
+
 #[test]
 fn pruning_reaps_chain_behind_the_wavefront() {
     // Simulates the scheduler's per-event lifecycle on a chain A -> B -> C:
@@ -428,6 +525,55 @@ fn pruned_scheduler_run_leaves_empty_graph() {
     assert_eq!(world.get_block(BlockPos::new(4, 5, 4)), BlockId::new(8));
 }
 
+#[test]
+fn prune_executed_archives_all_but_the_latest() {
+    let world = World::new();
+    world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+    let mut graph = CausalGraph::new();
+    let mut rules = RuleSet::new();
+    rules.add(spread_east);
+
+    // A 9-event chain, x = 12..=20.
+    spread_from(&mut graph, 12);
+    Scheduler::new().run_until_quiet(&world, &mut graph, &rules, 100);
+    assert_eq!(graph.len(), 9);
+
+    assert_eq!(graph.prune_executed(3), 6);
+    assert_eq!(graph.len(), 3);
+    let archive = graph.archive();
+    assert!(archive[0].parents.is_empty());
+    for (i, entry) in archive.iter().enumerate().skip(1) {
+        assert_eq!(entry.parents, vec![i as u32 - 1], "chain edges become indices");
+    }
+    match archive[5].event.payload {
+        EventPayload::BlockSet { pos, .. } => assert_eq!(pos.x, 17),
+        ref other => panic!("unexpected {other:?}"),
+    }
+
+    // Edges into drained entries are dropped.
+    assert_eq!(graph.take_archive().len(), 6);
+    assert_eq!(graph.prune_executed(0), 3);
+    assert!(graph.archive()[0].parents.is_empty());
+    assert_eq!(graph.archive()[2].parents, vec![1]);
+    assert!(graph.is_empty());
+    assert_eq!(graph.reaped_total(), 9);
+}
+
+#[test]
+fn prune_executed_keeps_nodes_with_pending_children() {
+    let mut graph = CausalGraph::new();
+    let root = graph.insert_root(notify_at(0));
+    let child = graph.insert(notify_at(1), vec![root]);
+    graph.mark_executed(root);
+
+    assert_eq!(graph.prune_executed(0), 0, "root still has a pending child");
+    assert_eq!(graph.len(), 2);
+
+    graph.mark_executed(child);
+    assert_eq!(graph.prune_executed(0), 2);
+    assert_eq!(graph.archive()[1].parents, vec![0]);
+}
+
 // ---------------------------------------------------------------------------
 // Priority lanes: high-priority events drain before background events
 // among ready (spacelike-separated) work; children inherit priority.
@@ -538,7 +684,8 @@ fn stale_block_set_is_skipped_and_unlogged() {
 
     let mut graph = CausalGraph::new();
     let rules = RuleSet::new();
-    let scheduler = Scheduler::new();
+    let conflicts = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let scheduler = Scheduler::new().with_conflict_counter(std::sync::Arc::clone(&conflicts));
 
     let pos = BlockPos::new(5, 5, 5);
     graph.insert_root(Event {
@@ -557,6 +704,34 @@ fn stale_block_set_is_skipped_and_unlogged() {
         EventPayload::BlockSet { new, .. } => assert_eq!(*new, BlockId::new(1)),
         other => panic!("unexpected log entry {other:?}"),
     }
+    assert_eq!(conflicts.load(std::sync::atomic::Ordering::Relaxed), 1, "the stale write is counted");
+}
+
+#[test]
+fn parallel_step_batches_a_chunks_writes_and_still_drops_stale_ones() {
+    let world = World::new();
+    world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+    let mut graph = CausalGraph::new();
+    let conflicts = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let scheduler = Scheduler::new().with_conflict_counter(std::sync::Arc::clone(&conflicts));
+
+    let contested = BlockPos::new(5, 5, 5);
+    for new in [1, 2] {
+        graph.insert_root(Event {
+            payload: EventPayload::BlockSet { pos: contested, old: BlockId::AIR, new: BlockId::new(new) },
+        });
+    }
+    for x in 0..8 {
+        graph.insert_root(Event {
+            payload: EventPayload::BlockSet { pos: BlockPos::new(x, 1, 0), old: BlockId::AIR, new: BlockId::new(3) },
+        });
+    }
+
+    scheduler.run_until_quiet_parallel(&world, &mut graph, &RuleSet::new(), 10);
+    assert_eq!(world.get_block(contested), BlockId::new(1), "first in queue order wins");
+    assert!((0..8).all(|x| world.get_block(BlockPos::new(x, 1, 0)) == BlockId::new(3)));
+    assert_eq!(graph.write_log().len(), 9);
+    assert_eq!(conflicts.load(std::sync::atomic::Ordering::Relaxed), 1);
 }
 
 #[test]
@@ -614,3 +789,430 @@ fn empty_graph_is_quiescent() {
     let total = scheduler.run_until_quiet(&world, &mut graph, &rules, 100);
     assert_eq!(total, 0);
 }
+
+#[test]
+fn deterministic_parallel_step_gathers_in_chunk_order() {
+    let scheduler = Scheduler::new().with_deterministic_order();
+    // Roots inserted in scrambled chunk order; the write log must come
+    // out sorted by chunk, identically on every run.
+    let chunks = [(3i64, 1i64), (-2, 0), (0, 5), (0, -1), (7, 7), (-2, -3), (1, 1), (4, 0)];
+    let run = || {
+        let world = World::new();
+        let mut graph = CausalGraph::new();
+        for &(cx, cz) in &chunks {
+            for i in 0..3i64 {
+                graph.insert_root(Event {
+                    payload: EventPayload::BlockSet {
+                        pos: BlockPos::new(cx * 16 + i, 5, cz * 16),
+                        old: BlockId::AIR,
+                        new: BlockId::new(1),
+                    },
+                });
+            }
+        }
+        scheduler.run_until_quiet_parallel(&world, &mut graph, &RuleSet::new(), 10);
+        graph
+            .take_write_log()
+            .into_iter()
+            .map(|p| match p {
+                EventPayload::BlockSet { pos, .. } => pos,
+                other => panic!("unexpected log entry {other:?}"),
+            })
+            .collect::<Vec<BlockPos>>()
+    };
+
+    let first = run();
+    let mut sorted = first.clone();
+    sorted.sort_by_key(|pos| (pos.chunk(), pos.x));
+    assert_eq!(first, sorted, "groups gather in chunk order, insertion order within");
+    for _ in 0..5 {
+        assert_eq!(run(), first);
+    }
+}
+
+/// Toy drip rule: block 7 copies itself one cell down until y = 0.
+fn drip(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
+    match payload {
+        EventPayload::BlockSet { pos, new, .. } if *new == BlockId::new(7) && pos.y > 0 => {
+            let below = BlockPos::new(pos.x, pos.y - 1, pos.z);
+            vec![Event {
+                payload: EventPayload::BlockSet { pos: below, old: world.get_block(below), new: *new },
+            }]
+        }
+        _ => vec![],
+    }
+}
+
+#[test]
+fn stealing_step_matches_sequential_and_reuses_queues() {
+    use ultimate_engine::causal::scheduler::StealQueues;
+
+    let mut rules = RuleSet::new();
+    rules.add(drip);
+    // 8 chunks × 12 columns: wide enough that waves go to the pool.
+    let seed = || {
+        let mut graph = CausalGraph::new();
+        for cx in 0..8i64 {
+            for i in 0..12i64 {
+                graph.insert_root(Event {
+                    payload: EventPayload::BlockSet {
+                        pos: BlockPos::new(cx * 16 + i, 6, -cx * 16),
+                        old: BlockId::AIR,
+                        new: BlockId::new(7),
+                    },
+                });
+            }
+        }
+        graph
+    };
+    let scheduler = Scheduler::new();
+
+    let seq_world = World::new();
+    let mut seq_graph = seed();
+    let seq_total = scheduler.run_until_quiet(&seq_world, &mut seq_graph, &rules, 100);
+
+    let world = World::new();
+    let mut graph = seed();
+    let mut queues = StealQueues::default();
+    let mut total = 0;
+    loop {
+        let n = scheduler.step_stealing(&world, &mut graph, &rules, &mut queues);
+        if n == 0 {
+            break;
+        }
+        total += n;
+    }
+
+    assert_eq!(total, seq_total);
+    assert_eq!(graph.write_log().len(), seq_graph.write_log().len());
+    assert_eq!(world.diff(&seq_world), []);
+    assert_eq!(world.content_hash(), seq_world.content_hash());
+    assert_eq!(queues.capacity(), 8, "one queue per chunk, reused every wave");
+}
+
+// ---------------------------------------------------------------------------
+// Border-safe deferral at unloaded chunks.
+// ---------------------------------------------------------------------------
+
+/// Toy spread rule: a write of block 7 copies itself one cell east, up to
+/// x = 20 — crossing the chunk border at x = 16.
+fn spread_east(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
+    match payload {
+        EventPayload::BlockSet { pos, new, .. } if *new == BlockId::new(7) && pos.x < 20 => {
+            let next = BlockPos::new(pos.x + 1, pos.y, pos.z);
+            vec![Event {
+                payload: EventPayload::BlockSet { pos: next, old: world.get_block(next), new: *new },
+            }]
+        }
+        _ => vec![],
+    }
+}
+
+fn spread_from(graph: &mut CausalGraph, x: i64) {
+    graph.insert_root(Event {
+        payload: EventPayload::BlockSet {
+            pos: BlockPos::new(x, 5, 0),
+            old: BlockId::AIR,
+            new: BlockId::new(7),
+        },
+    });
+}
+
+#[test]
+fn unloaded_chunk_events_wait_for_load() {
+    let world = World::new();
+    world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+    let mut graph = CausalGraph::new();
+    let mut rules = RuleSet::new();
+    rules.add(spread_east);
+    let scheduler = Scheduler::new().with_unloaded_deferral();
+
+    spread_from(&mut graph, 12);
+    scheduler.run_until_quiet(&world, &mut graph, &rules, 100);
+
+    // Spread stops at the border; nothing reads the void as air.
+    assert_eq!(world.get_block(BlockPos::new(15, 5, 0)), BlockId::new(7));
+    assert!(!world.has_chunk(ChunkPos::new(1, 0)), "no empty chunk materialized");
+    assert_eq!(graph.deferred_len(), 1);
+
+    world.insert_chunk(ChunkPos::new(1, 0), Chunk::new());
+    scheduler.run_until_quiet(&world, &mut graph, &rules, 100);
+    assert_eq!(graph.deferred_len(), 0);
+    for x in 16..=20 {
+        assert_eq!(world.get_block(BlockPos::new(x, 5, 0)), BlockId::new(7), "x={x}");
+    }
+}
+
+#[test]
+fn taken_deferrals_leave_nothing_to_release() {
+    let world = World::new();
+    world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+    let mut graph = CausalGraph::new();
+    let mut rules = RuleSet::new();
+    rules.add(spread_east);
+    let scheduler = Scheduler::new().with_unloaded_deferral();
+
+    spread_from(&mut graph, 12);
+    scheduler.run_until_quiet(&world, &mut graph, &rules, 100);
+    let taken = graph.take_deferred();
+    assert_eq!(taken.len(), 1);
+    assert_eq!(taken[&ChunkPos::new(1, 0)].len(), 1);
+    assert_eq!(graph.deferred_len(), 0);
+
+    world.insert_chunk(ChunkPos::new(1, 0), Chunk::new());
+    assert_eq!(scheduler.run_until_quiet(&world, &mut graph, &rules, 100), 0);
+    assert_eq!(world.get_block(BlockPos::new(16, 5, 0)), BlockId::AIR);
+}
+
+#[test]
+fn abandoned_cascade_keeps_its_writes_and_stops() {
+    let world = World::new();
+    world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+    let mut graph = CausalGraph::with_pruning();
+    let mut rules = RuleSet::new();
+    rules.add(spread_east);
+    let scheduler = Scheduler::new();
+
+    spread_from(&mut graph, 0);
+    scheduler.step(&world, &mut graph, &rules);
+    scheduler.step(&world, &mut graph, &rules);
+    assert_eq!(graph.abandon_pending(), 1, "the write to x = 2 was queued");
+    assert!(graph.is_empty(), "executed nodes reaped once their children are gone");
+
+    assert_eq!(scheduler.run_until_quiet(&world, &mut graph, &rules, 100), 0);
+    assert_eq!(world.get_block(BlockPos::new(1, 5, 0)), BlockId::new(7));
+    assert_eq!(world.get_block(BlockPos::new(2, 5, 0)), BlockId::AIR);
+}
+
+#[test]
+fn deferred_write_is_stale_against_loaded_terrain() {
+    let world = World::new();
+    world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+    let mut graph = CausalGraph::new();
+    let mut rules = RuleSet::new();
+    rules.add(spread_east);
+    let scheduler = Scheduler::new().with_unloaded_deferral();
+
+    spread_from(&mut graph, 14);
+    scheduler.run_until_quiet(&world, &mut graph, &rules, 100);
+
+    // The chunk loads with stone where the parked write expected air.
+    let mut chunk = Chunk::new();
+    chunk.set_block(LocalBlockPos { x: 0, y: 5, z: 0 }, BlockId::new(1));
+    world.insert_chunk(ChunkPos::new(1, 0), chunk);
+    scheduler.run_until_quiet(&world, &mut graph, &rules, 100);
+
+    assert_eq!(world.get_block(BlockPos::new(16, 5, 0)), BlockId::new(1));
+    assert_eq!(world.get_block(BlockPos::new(17, 5, 0)), BlockId::AIR);
+    assert_eq!(graph.deferred_len(), 0);
+}
+
+#[test]
+fn inactive_chunks_park_cascades_until_activated() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let world = World::new();
+    world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+    world.insert_chunk(ChunkPos::new(1, 0), Chunk::new());
+    let mut graph = CausalGraph::new();
+    let mut rules = RuleSet::new();
+    rules.add(spread_east);
+    let active = Arc::new(AtomicBool::new(false));
+    let gate = Arc::clone(&active);
+    let scheduler = Scheduler::new()
+        .with_unloaded_deferral()
+        .with_activity(Arc::new(move |c: ChunkPos| c.x == 0 || gate.load(Ordering::SeqCst)));
+
+    spread_from(&mut graph, 12);
+    scheduler.run_until_quiet(&world, &mut graph, &rules, 100);
+
+    // Loaded but outside simulation range: the spread waits at its edge.
+    assert_eq!(world.get_block(BlockPos::new(15, 5, 0)), BlockId::new(7));
+    assert_eq!(world.get_block(BlockPos::new(16, 5, 0)), BlockId::AIR);
+    assert_eq!(graph.deferred_len(), 1);
+
+    active.store(true, Ordering::SeqCst);
+    scheduler.run_until_quiet(&world, &mut graph, &rules, 100);
+    assert_eq!(graph.deferred_len(), 0);
+    assert_eq!(world.get_block(BlockPos::new(20, 5, 0)), BlockId::new(7));
+}
+
+#[test]
+fn without_deferral_cascades_run_into_unloaded_chunks() {
+    let world = World::new();
+    world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+    let mut graph = CausalGraph::new();
+    let mut rules = RuleSet::new();
+    rules.add(spread_east);
+
+    spread_from(&mut graph, 12);
+    Scheduler::new().run_until_quiet(&world, &mut graph, &rules, 100);
+
+    // Default behaviour: unloaded reads as air and the write creates it.
+    assert!(world.has_chunk(ChunkPos::new(1, 0)));
+    assert_eq!(world.get_block(BlockPos::new(20, 5, 0)), BlockId::new(7));
+}
+
+// ---------------------------------------------------------------------------
+// Rules read a frozen world in parallel waves.
+// ---------------------------------------------------------------------------
+
+/// Toy rule reading across the x = 16 chunk border: a write of 7 at x = 15
+/// or 16 marks the cell above with 8 if its mirror cell across the border
+/// already holds 7, else 9.
+fn mark_pair(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
+    match payload {
+        EventPayload::BlockSet { pos, new, .. } if *new == BlockId::new(7) => {
+            let mirror = BlockPos::new(31 - pos.x, pos.y, pos.z);
+            let mark = if world.get_block(mirror) == BlockId::new(7) { 8 } else { 9 };
+            vec![Event {
+                payload: EventPayload::BlockSet { pos: pos.offset(0, 1, 0), old: BlockId::AIR, new: BlockId::new(mark) },
+            }]
+        }
+        _ => vec![],
+    }
+}
+
+#[test]
+fn parallel_rules_see_the_whole_wave_written() {
+    let mut rules = RuleSet::new();
+    rules.add(mark_pair);
+    let marks = |world: &World| [15, 16].map(|x| world.get_block(BlockPos::new(x, 6, 0)));
+
+    for round in 0..50 {
+        for stealing in [false, true] {
+            let world = World::new();
+            let mut graph = CausalGraph::new();
+            spread_from(&mut graph, 15);
+            spread_from(&mut graph, 16);
+            let scheduler = Scheduler::new();
+            if stealing {
+                scheduler.run_until_quiet_stealing(&world, &mut graph, &rules, 10);
+            } else {
+                scheduler.run_until_quiet_parallel(&world, &mut graph, &rules, 10);
+            }
+            // Both groups' writes land before either rule reads, whatever
+            // the thread timing.
+            assert_eq!(marks(&world), [BlockId::new(8); 2], "round {round}, stealing {stealing}");
+        }
+    }
+
+    // Sequential steps interleave: the first write's rule runs alone.
+    let world = World::new();
+    let mut graph = CausalGraph::new();
+    spread_from(&mut graph, 15);
+    spread_from(&mut graph, 16);
+    Scheduler::new().run_until_quiet(&world, &mut graph, &rules, 10);
+    assert_eq!(marks(&world), [BlockId::new(9), BlockId::new(8)]);
+}
+
+// ---------------------------------------------------------------------------
+// Custom payloads: the engine routes them, rules give them meaning.
+// ---------------------------------------------------------------------------
+
+/// Toy game-layer event: lightning strikes turn the struck block into the
+/// block id carried in `data`.
+fn lightning(_world: &WorldView, payload: &EventPayload) -> Vec<Event> {
+    match payload {
+        EventPayload::Custom { kind: "lightning", pos, data } => vec![Event {
+            payload: EventPayload::BlockSet { pos: *pos, old: BlockId::AIR, new: BlockId::new(data[0] as u16) },
+        }],
+        _ => vec![],
+    }
+}
+
+#[test]
+fn custom_events_reach_rules_without_writing_the_world() {
+    let world = World::new();
+    world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+    let mut graph = CausalGraph::new();
+    let mut rules = RuleSet::new();
+    rules.add(lightning);
+    let strike = |kind| Event {
+        payload: EventPayload::Custom { kind, pos: BlockPos::new(2, 5, 3), data: vec![9].into() },
+    };
+    assert_eq!(strike("lightning").chunk(), ChunkPos::new(0, 0));
+    assert!(strike("lightning").payload.dedup_key().is_none());
+
+    graph.insert_root(strike("thunder")); // no rule knows this kind
+    graph.insert_root(strike("lightning"));
+    let total = Scheduler::new().run_until_quiet(&world, &mut graph, &rules, 100);
+
+    assert_eq!(total, 3);
+    assert_eq!(world.get_block(BlockPos::new(2, 5, 3)), BlockId::new(9));
+    // Only the rule's world write is logged, not the custom events.
+    assert_eq!(graph.write_log().len(), 1);
+    assert!(graph.to_dot().contains("lightning (2,5,3)"));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_step_runs_on_dedicated_pool() {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    let world = World::new();
+    let mut graph = CausalGraph::new();
+    let rules = RuleSet::new();
+    let pool = Arc::new(
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .thread_name(|i| format!("test-cascade-{i}"))
+            .build()
+            .unwrap(),
+    );
+    let busy = Arc::new(AtomicU64::new(0));
+    let scheduler = Scheduler::new()
+        .with_pool(Arc::clone(&pool))
+        .with_busy_counter(Arc::clone(&busy));
+
+    // Roots spread over several chunks so the scatter has real groups.
+    for cx in 0..4i64 {
+        graph.insert_root(Event {
+            payload: EventPayload::BlockSet {
+                pos: BlockPos::new(cx * 16 + 1, 5, 1),
+                old: BlockId::AIR,
+                new: BlockId::new(3),
+            },
+        });
+    }
+
+    let total = scheduler.run_until_quiet_parallel(&world, &mut graph, &rules, 10);
+    assert_eq!(total, 4);
+    for cx in 0..4i64 {
+        assert_eq!(world.get_block(BlockPos::new(cx * 16 + 1, 5, 1)), BlockId::new(3));
+    }
+    assert!(busy.load(Ordering::Relaxed) > 0, "group execution time is recorded");
+}
+
+#[test]
+fn rule_profile_samples_per_rule() {
+    use ultimate_engine::rules::RuleProfile;
+
+    let world = World::new();
+    world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+    let profile = std::sync::Arc::new(RuleProfile::new(1));
+    let mut rules = RuleSet::new().with_profile(std::sync::Arc::clone(&profile));
+    rules.add_named("spread_east", spread_east);
+    rules.add_named("noop", |_, _| vec![]);
+
+    let mut graph = CausalGraph::new();
+    spread_from(&mut graph, 12);
+    Scheduler::new().run_until_quiet(&world, &mut graph, &rules, 100);
+
+    let samples = profile.snapshot();
+    assert_eq!(samples.iter().map(|s| s.name).collect::<Vec<_>>(), ["spread_east", "noop"]);
+    assert_eq!((samples[0].samples, samples[0].events), (9, 8), "x = 12..=20, the last spawns nothing");
+    assert_eq!(samples[0].hist.iter().sum::<u64>(), 9);
+    assert_eq!((samples[1].samples, samples[1].events), (9, 0));
+
+    // A rebuilt set shares the counters; sampling off leaves them alone.
+    profile.set_sample_every(0);
+    let mut rebuilt = RuleSet::new().with_profile(std::sync::Arc::clone(&profile));
+    rebuilt.add_named("spread_east", spread_east);
+    spread_from(&mut graph, 0);
+    Scheduler::new().run_until_quiet(&world, &mut graph, &rebuilt, 100);
+    assert_eq!(profile.snapshot()[0].samples, 9);
+    assert_eq!(world.get_block(BlockPos::new(11, 5, 0)), BlockId::new(7));
+}
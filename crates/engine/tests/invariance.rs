@@ -0,0 +1,226 @@
+//! Property test for causal invariance: random worlds and random root
+//! sets must settle to the same world under every frontier order and
+//! every scheduler.
+//!
+//! The toy rule set is a signal field that relaxes to a unique fixed point:
+//! sources emit level 5, each step away loses one level, stone blocks it.
+//! Every cell's value is fully determined by the stone and source layout,
+//! so any difference between runs is an engine bug (a lost notify, a write
+//! applied out of causal order, a rule reading a half-written wave) rather
+//! than a legitimately racy rule.
+
+use proptest::prelude::*;
+
+use ultimate_engine::causal::event::{Event, EventId, EventPayload};
+use ultimate_engine::causal::graph::CausalGraph;
+use ultimate_engine::causal::scheduler::{Scheduler, StealQueues};
+use ultimate_engine::rules::RuleSet;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::position::BlockPos;
+use ultimate_engine::world::{World, WorldView};
+
+const STONE: BlockId = BlockId::new(1);
+const SOURCE: BlockId = BlockId::new(2);
+/// Level `n` (1..=4) is stored as block `LEVEL_BASE + n`; level 0 is air.
+const LEVEL_BASE: u16 = 10;
+const SOURCE_LEVEL: u16 = 5;
+
+/// Roots and walls land in a box straddling the corner of four chunks, so
+/// groups read across chunk borders.
+const BOX_XZ: std::ops::Range<i64> = 8..24;
+const BOX_Y: std::ops::Range<i64> = 0..6;
+/// How far a source's signal reaches outside the box.
+const REACH: i64 = SOURCE_LEVEL as i64 - 1;
+
+fn level(block: BlockId) -> u16 {
+    match block {
+        SOURCE => SOURCE_LEVEL,
+        b if b.0 > LEVEL_BASE && b.0 < LEVEL_BASE + SOURCE_LEVEL => b.0 - LEVEL_BASE,
+        _ => 0,
+    }
+}
+
+/// What `pos` should hold given its neighbours, or `None` for stone and
+/// sources, which never change on their own.
+fn desired(world: &WorldView, pos: BlockPos) -> Option<BlockId> {
+    let here = world.get_block(pos);
+    if here == STONE || here == SOURCE {
+        return None;
+    }
+    let best = pos.neighbors().into_iter().map(|n| level(world.get_block(n))).max().unwrap_or(0);
+    Some(match best.saturating_sub(1) {
+        0 => BlockId::AIR,
+        l => BlockId::new(LEVEL_BASE + l),
+    })
+}
+
+/// A change re-checks the cell itself (its own write may have lost a race)
+/// and every neighbour whose level depends on it.
+fn signal(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
+    match payload {
+        EventPayload::BlockSet { pos, .. } => std::iter::once(*pos)
+            .chain(pos.neighbors())
+            .map(|pos| Event { payload: EventPayload::BlockNotify { pos, from: None } })
+            .collect(),
+        EventPayload::BlockNotify { pos, .. } => {
+            let current = world.get_block(*pos);
+            match desired(world, *pos) {
+                Some(new) if new != current => {
+                    vec![Event { payload: EventPayload::BlockSet { pos: *pos, old: current, new } }]
+                }
+                _ => vec![],
+            }
+        }
+        _ => vec![],
+    }
+}
+
+fn rules() -> RuleSet {
+    let mut rules = RuleSet::new();
+    rules.add(signal);
+    rules
+}
+
+/// A block placed in the box: x, y, z and 0 = air, 1 = stone, 2 = source.
+type Placement = (i64, i64, i64, u8);
+
+fn placement() -> impl Strategy<Value = Placement> {
+    (BOX_XZ, BOX_Y, BOX_XZ, 0..3u8)
+}
+
+fn kind(k: u8) -> BlockId {
+    [BlockId::AIR, STONE, SOURCE][k as usize]
+}
+
+/// Lay out `walls`, then settle the field so every run starts from the
+/// same fixed point.
+fn settled_world(walls: &[Placement]) -> World {
+    let world = World::new();
+    let mut seeds = CausalGraph::new();
+    for &(x, y, z, k) in walls {
+        let pos = BlockPos::new(x, y, z);
+        world.set_block(pos, kind(k));
+        for pos in std::iter::once(pos).chain(pos.neighbors()) {
+            seeds.insert_root(Event { payload: EventPayload::BlockNotify { pos, from: None } });
+        }
+    }
+    Scheduler::new().run_until_quiet(&world, &mut seeds, &rules(), 10_000);
+    world
+}
+
+fn copy(world: &World) -> World {
+    let copy = World::new();
+    for entry in world.iter_chunks() {
+        copy.insert_chunk(*entry.key(), entry.value().clone());
+    }
+    copy
+}
+
+/// Roots for `edits`, one per cell (the last edit of a cell wins), each
+/// expecting what the cell holds in `world`.
+fn roots(world: &World, edits: &[Placement]) -> CausalGraph {
+    let mut last = std::collections::BTreeMap::new();
+    for &(x, y, z, k) in edits {
+        last.insert((x, y, z), kind(k));
+    }
+    let mut graph = CausalGraph::new();
+    for ((x, y, z), new) in last {
+        let pos = BlockPos::new(x, y, z);
+        let old = world.get_block(pos);
+        if old != new {
+            graph.insert_root(Event { payload: EventPayload::BlockSet { pos, old, new } });
+        }
+    }
+    graph
+}
+
+/// SplitMix64, for frontier shuffles reproducible from a proptest seed.
+fn splitmix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Sequential execution that shuffles every wave before running it one
+/// event at a time, with the scheduler's stale-precondition semantics.
+fn run_shuffled(world: &World, graph: &mut CausalGraph, rules: &RuleSet, mut seed: u64) -> usize {
+    let mut total = 0;
+    loop {
+        let mut wave: Vec<EventId> = graph.drain_ready(usize::MAX);
+        if wave.is_empty() {
+            return total;
+        }
+        for i in (1..wave.len()).rev() {
+            wave.swap(i, (splitmix(&mut seed) % (i as u64 + 1)) as usize);
+        }
+        for id in wave {
+            let Some(event) = graph.get(id).map(|node| node.event.clone()) else { continue };
+            let effective = match event.payload {
+                EventPayload::BlockSet { pos, old, new } => old != new && world.compare_and_set(pos, old, new),
+                _ => true,
+            };
+            graph.mark_executed(id);
+            total += 1;
+            if effective {
+                for consequent in rules.evaluate(&world.view(), &event.payload) {
+                    graph.insert(consequent, vec![id]);
+                }
+            }
+            graph.finish(id);
+        }
+    }
+}
+
+/// Every cell around the box holds exactly what its neighbours imply.
+fn assert_settled(world: &World) -> Result<(), TestCaseError> {
+    let view = world.view();
+    for x in BOX_XZ.start - REACH..BOX_XZ.end + REACH {
+        for y in BOX_Y.start - REACH..BOX_Y.end + REACH {
+            for z in BOX_XZ.start - REACH..BOX_XZ.end + REACH {
+                let pos = BlockPos::new(x, y, z);
+                if let Some(want) = desired(&view, pos) {
+                    prop_assert_eq!(world.get_block(pos), want, "unsettled at {:?}", pos);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn every_order_and_scheduler_settles_the_same(
+        walls in prop::collection::vec(placement(), 0..48),
+        edits in prop::collection::vec(placement(), 1..24),
+        seeds in prop::collection::vec(any::<u64>(), 3),
+    ) {
+        let start = settled_world(&walls);
+        let fresh = || copy(&start);
+        let rules = rules();
+
+        let reference = fresh();
+        Scheduler::new().run_until_quiet(&reference, &mut roots(&start, &edits), &rules, 10_000);
+        assert_settled(&reference)?;
+
+        for &seed in &seeds {
+            let world = fresh();
+            run_shuffled(&world, &mut roots(&start, &edits), &rules, seed);
+            prop_assert_eq!(world.diff(&reference), vec![], "shuffled with seed {:#x}", seed);
+        }
+
+        let parallel = fresh();
+        Scheduler::new().run_until_quiet_parallel(&parallel, &mut roots(&start, &edits), &rules, 10_000);
+        prop_assert_eq!(parallel.diff(&reference), vec![], "step_parallel");
+
+        let stealing = fresh();
+        let mut graph = roots(&start, &edits);
+        let mut queues = StealQueues::default();
+        let scheduler = Scheduler::new();
+        while scheduler.step_stealing(&stealing, &mut graph, &rules, &mut queues) > 0 {}
+        prop_assert_eq!(stealing.diff(&reference), vec![], "step_stealing");
+    }
+}
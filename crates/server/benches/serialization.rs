@@ -0,0 +1,53 @@
+//! Serialization baselines: `LevelChunkWithLight` packet encoding and
+//! Anvil region-file save throughput, on terrain from the built-in
+//! `noise` preset.
+//!
+//! Run with: `cargo bench -p ultimate-server --bench serialization`
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+
+use ultimate_engine::world::World;
+use ultimate_engine::world::position::ChunkPos;
+use ultimate_server::net::connection::encode_chunk;
+use ultimate_server::persistence;
+use ultimate_server::worldgen::preset;
+
+const R: i32 = 4; // 9x9 chunks
+
+fn chunk_packets(c: &mut Criterion) {
+    let wg = preset::load("noise", 0xC0FFEE).expect("builtin noise preset");
+    let world = World::new();
+    wg.ensure_generated(&world, 0, 0);
+
+    let mut group = c.benchmark_group("chunk_packet");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("encode", |b| {
+        b.iter(|| encode_chunk(&world, wg.as_ref(), 0, 0).expect("encode"));
+    });
+    group.finish();
+}
+
+fn anvil_save(c: &mut Criterion) {
+    let wg = preset::load("noise", 0xC0FFEE).expect("builtin noise preset");
+    let world = World::new();
+    let positions: Vec<ChunkPos> = ChunkPos::new(0, 0).chunks_in_radius(R).collect();
+    for pos in &positions {
+        wg.ensure_generated(&world, pos.x, pos.z);
+    }
+    let dir = std::env::temp_dir().join("ultimate_mc_bench_anvil");
+
+    let mut group = c.benchmark_group("anvil");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(positions.len() as u64));
+    group.bench_function("save_full_chunks", |b| {
+        b.iter(|| {
+            let _ = std::fs::remove_dir_all(&dir);
+            persistence::save_full_chunks(&world, &dir, 0, &positions).expect("save")
+        });
+    });
+    group.finish();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+criterion_group!(benches, chunk_packets, anvil_save);
+criterion_main!(benches);
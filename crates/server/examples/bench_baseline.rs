@@ -199,7 +199,7 @@ fn run_scenario(
     rules: &RuleSet,
     build_roots: impl Fn(&mut CausalGraph),
 ) -> ScenarioReport {
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new().with_deterministic_order();
 
     // Sequential.
     let world_seq = flat_world(R);
@@ -2,7 +2,8 @@
 //!
 //! Generates a realistic area with the built-in `noise` preset and
 //! reports block-storage bytes under paletted sections vs the previous
-//! raw `[BlockId; 4096]` (8 KB/section) representation.
+//! raw `[BlockId; 4096]` (8 KB/section) representation, then how much
+//! `Chunk::compact` gives back after edits leave stale palette entries.
 //!
 //! Run with: `cargo run --release --example bench_memory`
 
@@ -61,6 +62,30 @@ fn main() {
         sections - uniform_sections - bits4_or_less,
         max_palette,
     );
+
+    // Churn: bury every surface block under a shifting stack of edits, then
+    // put it back. The palette keeps each intermediate block until compacted.
+    let mut churned = 0usize;
+    let mut reclaimed = 0usize;
+    for cx in -R..R {
+        for cz in -R..R {
+            let pos = ultimate_engine::world::position::ChunkPos::new(cx, cz);
+            let mut chunk = world.get_chunk_mut(&pos).expect("inserted above");
+            let local = ultimate_engine::world::position::LocalBlockPos { x: 0, y: 64, z: 0 };
+            let original = chunk.get_block(local);
+            for id in 1..=20u16 {
+                chunk.set_block(local, ultimate_engine::world::block::BlockId::new(id));
+            }
+            chunk.set_block(local, original);
+            churned += chunk.sections().map(|(_, s)| s.memory_bytes()).sum::<usize>();
+            reclaimed += chunk.compact();
+        }
+    }
+    println!(
+        "  after churn: {:>10} KB  |  compaction reclaimed {} KB",
+        churned / 1024,
+        reclaimed / 1024,
+    );
     println!(
         "  per loaded chunk (blocks only): {:.1} KB → {:.1} KB",
         raw_bytes as f64 / chunks as f64 / 1024.0,
@@ -1,12 +1,16 @@
 //! Benchmark: sequential vs parallel scheduler.
 //!
-//! Drops many sand columns across a grid of chunks and measures time to quiescence.
+//! Drops many sand columns across a grid of chunks and measures time to
+//! quiescence with the sequential scheduler, snapshot-scatter-gather
+//! (`step_parallel`), and work stealing over persistent per-chunk queues
+//! (`step_stealing`). A second section runs many tiny cascades one after
+//! another, where waves are shallow and per-wave overhead dominates.
 //! Run with: `cargo run --release -p ultimate-server --example bench_parallel`
 
 use std::time::Instant;
 use ultimate_engine::causal::event::{Event, EventPayload};
 use ultimate_engine::causal::graph::CausalGraph;
-use ultimate_engine::causal::scheduler::Scheduler;
+use ultimate_engine::causal::scheduler::{Scheduler, StealQueues};
 use ultimate_engine::world::chunk::{Chunk, SECTION_SIZE};
 use ultimate_engine::world::position::{BlockPos, ChunkPos, LocalBlockPos};
 use ultimate_engine::world::World;
@@ -29,7 +33,7 @@ fn main() {
     println!("  {} total sand drops, each from y={} (5-block fall)\n", total_sand, drop_height);
 
     let rules = ultimate_server::rules::standard();
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new().with_deterministic_order();
 
     // --- Sequential ---
     let world_seq = build_world(side);
@@ -51,10 +55,55 @@ fn main() {
 
     println!("  Parallel:   {:>8} events in {:>8.2?}", n_par, dt_par);
 
-    let speedup = dt_seq.as_secs_f64() / dt_par.as_secs_f64();
-    println!("\n  Speedup: {:.2}x", speedup);
+    // --- Work stealing, persistent queues ---
+    let world_steal = build_world(side);
+    let mut graph_steal = build_graph(chunks, side, sand_per_chunk, drop_height);
+
+    let t0 = Instant::now();
+    let n_steal = scheduler.run_until_quiet_stealing(&world_steal, &mut graph_steal, &rules, 10_000);
+    let dt_steal = t0.elapsed();
+
+    println!("  Stealing:   {:>8} events in {:>8.2?}", n_steal, dt_steal);
+
+    println!("\n  Speedup (parallel): {:.2}x", dt_seq.as_secs_f64() / dt_par.as_secs_f64());
+    println!("  Speedup (stealing): {:.2}x", dt_seq.as_secs_f64() / dt_steal.as_secs_f64());
 
     // --- Verify identical ---
+    for (name, world) in [("parallel", &world_par), ("stealing", &world_steal)] {
+        let mismatches = count_mismatches(&world_seq, world, chunks, side, sand_per_chunk, drop_height);
+        if mismatches == 0 {
+            println!("  Verification ({name}): PASS (worlds identical)");
+        } else {
+            println!("  Verification ({name}): FAIL ({} mismatches!)", mismatches);
+        }
+    }
+
+    // --- Shallow waves: one sand block at a time, each to quiescence ---
+    let drops = 2_000;
+    println!("\n  Shallow waves: {} single-block cascades, one after another", drops);
+    let world_par = build_world(side);
+    let t0 = Instant::now();
+    for i in 0..drops {
+        let mut graph = single_drop(i, side, drop_height);
+        scheduler.run_until_quiet_parallel(&world_par, &mut graph, &rules, 10_000);
+    }
+    let dt_par = t0.elapsed();
+    println!("  Parallel:   {:>8.2?} ({:.1} µs/cascade)", dt_par, dt_par.as_secs_f64() * 1e6 / drops as f64);
+
+    let world_steal = build_world(side);
+    let mut queues = StealQueues::default();
+    let t0 = Instant::now();
+    for i in 0..drops {
+        let mut graph = single_drop(i, side, drop_height);
+        while scheduler.step_stealing(&world_steal, &mut graph, &rules, &mut queues) > 0 {}
+    }
+    let dt_steal = t0.elapsed();
+    println!("  Stealing:   {:>8.2?} ({:.1} µs/cascade)", dt_steal, dt_steal.as_secs_f64() * 1e6 / drops as f64);
+    println!("  Speedup:    {:.2}x", dt_par.as_secs_f64() / dt_steal.as_secs_f64());
+}
+
+/// Blocks that differ between two runs over the sand columns' cells.
+fn count_mismatches(a: &World, b: &World, chunks: usize, side: i32, sand_per_chunk: usize, drop_height: i64) -> usize {
     let mut mismatches = 0;
     let spc_side = (sand_per_chunk as f64).sqrt().ceil() as i64;
     let mut chunk_idx = 0;
@@ -67,7 +116,7 @@ fn main() {
                     let z = (cz as i64) * 16 + sz * 4 + 2;
                     for y in 0..=drop_height {
                         let pos = BlockPos::new(x, y, z);
-                        if world_seq.get_block(pos) != world_par.get_block(pos) {
+                        if a.get_block(pos) != b.get_block(pos) {
                             mismatches += 1;
                         }
                     }
@@ -76,12 +125,21 @@ fn main() {
             chunk_idx += 1;
         }
     }
+    mismatches
+}
 
-    if mismatches == 0 {
-        println!("  Verification: PASS (worlds identical)");
-    } else {
-        println!("  Verification: FAIL ({} mismatches!)", mismatches);
-    }
+/// The `i`th single sand drop, spread over the arena so columns don't stack.
+fn single_drop(i: usize, side: i32, drop_height: i64) -> CausalGraph {
+    let span = side as i64 * 16;
+    let mut graph = CausalGraph::new();
+    graph.insert_root(Event {
+        payload: EventPayload::BlockSet {
+            pos: BlockPos::new(i as i64 % span, drop_height, (i as i64 / span) * 3),
+            old: block::AIR,
+            new: block::SAND,
+        },
+    });
+    graph
 }
 
 fn build_world(side: i32) -> World {
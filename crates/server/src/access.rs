@@ -0,0 +1,460 @@
+//! Access control: whitelist, ban list, and operator permission levels.
+//!
+//! Backed by the vanilla JSON files (`whitelist.json`,
+//! `banned-players.json`, `ops.json`) so existing server folders and
+//! tooling work unchanged. Files are re-read whenever their mtime changes
+//! ([`AccessLists::reload_if_changed`], polled from `main`), so an operator
+//! can edit them by hand while the server runs. Commands (`/op`, `/ban`,
+//! `/whitelist`) write through immediately.
+//!
+//! Permission levels follow vanilla: 0 = everyone, 1–4 = operator levels.
+//! Ops may join even when the whitelist is enforced and they are not on it.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const OPS_FILE: &str = "ops.json";
+pub const BANS_FILE: &str = "banned-players.json";
+pub const WHITELIST_FILE: &str = "whitelist.json";
+
+/// Level granted by `/op` (vanilla's default `op-permission-level`).
+pub const DEFAULT_OP_LEVEL: u8 = 4;
+
+/// Current [`PlayerBundle`] format.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// One `ops.json` entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    pub level: u8,
+    #[serde(rename = "bypassesPlayerLimit", default)]
+    pub bypasses_player_limit: bool,
+}
+
+/// One `banned-players.json` entry. `expires` other than `"forever"` is
+/// kept verbatim but not parsed: such bans are treated as permanent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BanEntry {
+    pub uuid: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub created: String,
+    #[serde(default)]
+    pub source: String,
+    #[serde(default = "forever")]
+    pub expires: String,
+    #[serde(default)]
+    pub reason: String,
+}
+
+fn forever() -> String {
+    "forever".into()
+}
+
+/// One `whitelist.json` entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+/// Every player record the server keeps, as one JSON document for moving
+/// between hosts (`ultimate-server admin export-players|import-players`).
+/// Positions, inventories and statistics aren't persisted yet, so today
+/// that is the three access lists; `version` leaves room to add them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerBundle {
+    pub version: u32,
+    #[serde(default)]
+    pub ops: Vec<OpEntry>,
+    #[serde(default)]
+    pub whitelist: Vec<WhitelistEntry>,
+    #[serde(default)]
+    pub banned_players: Vec<BanEntry>,
+}
+
+#[derive(Default)]
+struct Lists {
+    ops: Vec<OpEntry>,
+    bans: Vec<BanEntry>,
+    whitelist: Vec<WhitelistEntry>,
+    /// Last-seen mtimes of the three files, in [`FILES`] order.
+    mtimes: [Option<SystemTime>; 3],
+}
+
+const FILES: [&str; 3] = [OPS_FILE, BANS_FILE, WHITELIST_FILE];
+
+/// Shared access-control state. Reads are brief and lock-free of I/O;
+/// mutations write the affected file while holding the write lock so
+/// concurrent commands can't interleave partial updates.
+pub struct AccessLists {
+    dir: PathBuf,
+    enforce_whitelist: AtomicBool,
+    lists: RwLock<Lists>,
+}
+
+impl AccessLists {
+    /// Load the three files from `dir` (missing files are empty lists).
+    pub fn load(dir: &Path, enforce_whitelist: bool) -> Result<Self> {
+        let access = Self {
+            dir: dir.to_path_buf(),
+            enforce_whitelist: AtomicBool::new(enforce_whitelist),
+            lists: RwLock::new(Lists::default()),
+        };
+        access.reload_if_changed()?;
+        Ok(access)
+    }
+
+    /// Re-read any file whose mtime changed since the last load. Returns
+    /// whether anything was reloaded. A file that fails to parse keeps
+    /// its previous contents (an operator mid-edit must not wipe the ops).
+    pub fn reload_if_changed(&self) -> Result<bool> {
+        let mut lists = self.lists.write().expect("access lists poisoned");
+        let mut reloaded = false;
+        for (i, file) in FILES.iter().enumerate() {
+            let path = self.dir.join(file);
+            let mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            if mtime == lists.mtimes[i] {
+                continue;
+            }
+            lists.mtimes[i] = mtime;
+            reloaded = true;
+            match i {
+                0 => lists.ops = read_list(&path)?,
+                1 => lists.bans = read_list(&path)?,
+                _ => lists.whitelist = read_list(&path)?,
+            }
+            tracing::info!("Loaded {}", path.display());
+        }
+        Ok(reloaded)
+    }
+
+    /// Decide whether a player may log in. `Err` carries the kick message.
+    pub fn check_login(&self, uuid: Uuid, name: &str) -> Result<(), String> {
+        let lists = self.lists.read().expect("access lists poisoned");
+        if let Some(ban) = lists.bans.iter().find(|b| b.uuid == uuid) {
+            return Err(if ban.reason.is_empty() {
+                "You are banned from this server.".into()
+            } else {
+                format!("You are banned from this server.\nReason: {}", ban.reason)
+            });
+        }
+        if self.whitelist_enforced()
+            && !lists.whitelist.iter().any(|w| w.uuid == uuid)
+            && !lists.ops.iter().any(|o| o.uuid == uuid)
+        {
+            tracing::info!("{} ({}) rejected: not whitelisted", name, uuid);
+            return Err("You are not whitelisted on this server!".into());
+        }
+        Ok(())
+    }
+
+    /// Permission level of a player: their op level, or 0.
+    pub fn permission_level(&self, uuid: Uuid) -> u8 {
+        let lists = self.lists.read().expect("access lists poisoned");
+        lists.ops.iter().find(|o| o.uuid == uuid).map_or(0, |o| o.level)
+    }
+
+    pub fn whitelist_enforced(&self) -> bool {
+        self.enforce_whitelist.load(Ordering::Relaxed)
+    }
+
+    /// Toggle whitelist enforcement for this run (not persisted — the
+    /// startup value comes from `access.whitelist` in `server.yaml`).
+    pub fn set_whitelist_enforced(&self, on: bool) {
+        self.enforce_whitelist.store(on, Ordering::Relaxed);
+    }
+
+    /// Grant (or change) op level. Returns `false` if already at `level`.
+    pub fn op(&self, uuid: Uuid, name: &str, level: u8) -> Result<bool> {
+        self.mutate(0, |l| {
+            if let Some(o) = l.ops.iter_mut().find(|o| o.uuid == uuid) {
+                if o.level == level {
+                    return false;
+                }
+                o.level = level;
+            } else {
+                l.ops.push(OpEntry {
+                    uuid,
+                    name: name.to_owned(),
+                    level,
+                    bypasses_player_limit: false,
+                });
+            }
+            true
+        })
+    }
+
+    /// Revoke op. Returns `false` if the player wasn't an op.
+    pub fn deop(&self, uuid: Uuid) -> Result<bool> {
+        self.mutate(0, |l| remove_where(&mut l.ops, |o| o.uuid == uuid))
+    }
+
+    /// Ban a player. Returns `false` if already banned.
+    pub fn ban(&self, uuid: Uuid, name: &str, source: &str, reason: &str) -> Result<bool> {
+        self.mutate(1, |l| {
+            if l.bans.iter().any(|b| b.uuid == uuid) {
+                return false;
+            }
+            l.bans.push(BanEntry {
+                uuid,
+                name: name.to_owned(),
+                created: vanilla_timestamp(SystemTime::now()),
+                source: source.to_owned(),
+                expires: forever(),
+                reason: reason.to_owned(),
+            });
+            true
+        })
+    }
+
+    /// Lift a ban. Returns `false` if the player wasn't banned.
+    pub fn pardon(&self, uuid: Uuid) -> Result<bool> {
+        self.mutate(1, |l| remove_where(&mut l.bans, |b| b.uuid == uuid))
+    }
+
+    /// Add to the whitelist. Returns `false` if already present.
+    pub fn whitelist_add(&self, uuid: Uuid, name: &str) -> Result<bool> {
+        self.mutate(2, |l| {
+            if l.whitelist.iter().any(|w| w.uuid == uuid) {
+                return false;
+            }
+            l.whitelist.push(WhitelistEntry { uuid, name: name.to_owned() });
+            true
+        })
+    }
+
+    /// Remove from the whitelist. Returns `false` if not present.
+    pub fn whitelist_remove(&self, uuid: Uuid) -> Result<bool> {
+        self.mutate(2, |l| remove_where(&mut l.whitelist, |w| w.uuid == uuid))
+    }
+
+    /// Names on the whitelist, in file order.
+    pub fn whitelist_names(&self) -> Vec<String> {
+        let lists = self.lists.read().expect("access lists poisoned");
+        lists.whitelist.iter().map(|w| w.name.clone()).collect()
+    }
+
+    /// Snapshot all lists as a bundle.
+    pub fn export_bundle(&self) -> PlayerBundle {
+        let lists = self.lists.read().expect("access lists poisoned");
+        PlayerBundle {
+            version: BUNDLE_VERSION,
+            ops: lists.ops.clone(),
+            whitelist: lists.whitelist.clone(),
+            banned_players: lists.bans.clone(),
+        }
+    }
+
+    /// Replace all lists with the bundle's and write the files. Bundles
+    /// from a newer format are refused rather than half-imported.
+    pub fn import_bundle(&self, bundle: PlayerBundle) -> Result<()> {
+        if bundle.version > BUNDLE_VERSION {
+            anyhow::bail!(
+                "player bundle version {} is newer than this server's ({})",
+                bundle.version, BUNDLE_VERSION,
+            );
+        }
+        let PlayerBundle { ops, whitelist, banned_players, .. } = bundle;
+        self.mutate(0, |l| {
+            l.ops = ops;
+            true
+        })?;
+        self.mutate(1, |l| {
+            l.bans = banned_players;
+            true
+        })?;
+        self.mutate(2, |l| {
+            l.whitelist = whitelist;
+            true
+        })?;
+        Ok(())
+    }
+
+    /// Apply `f` under the write lock and, if it reports a change, write
+    /// file `idx` back to disk (recording the new mtime so our own write
+    /// doesn't trigger a reload).
+    fn mutate(&self, idx: usize, f: impl FnOnce(&mut Lists) -> bool) -> Result<bool> {
+        let mut lists = self.lists.write().expect("access lists poisoned");
+        if !f(&mut lists) {
+            return Ok(false);
+        }
+        let path = self.dir.join(FILES[idx]);
+        let json = match idx {
+            0 => serde_json::to_string_pretty(&lists.ops)?,
+            1 => serde_json::to_string_pretty(&lists.bans)?,
+            _ => serde_json::to_string_pretty(&lists.whitelist)?,
+        };
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(&path, json).with_context(|| format!("writing {}", path.display()))?;
+        lists.mtimes[idx] = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        Ok(true)
+    }
+}
+
+/// Start a background task polling the files for hand edits, until
+/// `shutdown`.
+pub fn start_reloader(access: std::sync::Arc<AccessLists>, interval_secs: u64, shutdown: crate::shutdown::Shutdown) {
+    if interval_secs == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let _running = shutdown.task();
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.triggered() => break,
+            }
+            if let Err(e) = access.reload_if_changed() {
+                tracing::warn!("Access list reload failed (keeping previous contents): {:#}", e);
+            }
+        }
+    });
+}
+
+fn read_list<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) if text.trim().is_empty() => Ok(Vec::new()),
+        Ok(text) => serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+fn remove_where<T>(list: &mut Vec<T>, pred: impl Fn(&T) -> bool) -> bool {
+    let before = list.len();
+    list.retain(|x| !pred(x));
+    list.len() != before
+}
+
+/// Format a time the way vanilla writes `created`: `2024-01-31 13:05:09 +0000`.
+fn vanilla_timestamp(t: SystemTime) -> String {
+    let secs = t
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Civil-from-days (Howard Hinnant's algorithm), proleptic Gregorian.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} +0000",
+        year, month, day, rem / 3600, rem % 3600 / 60, rem % 60,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_vanilla_timestamp() {
+        let t = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_706_706_309);
+        assert_eq!(vanilla_timestamp(t), "2024-01-31 13:05:09 +0000");
+        assert_eq!(vanilla_timestamp(SystemTime::UNIX_EPOCH), "1970-01-01 00:00:00 +0000");
+    }
+
+    #[test]
+    fn test_ban_and_whitelist_enforcement() {
+        let dir = temp_dir("ultimate_mc_test_access");
+        let access = AccessLists::load(&dir, false).unwrap();
+        let (alice, bob) = (Uuid::from_u128(1), Uuid::from_u128(2));
+
+        assert!(access.check_login(alice, "alice").is_ok());
+        assert!(access.ban(alice, "alice", "admin", "griefing").unwrap());
+        assert!(!access.ban(alice, "alice", "admin", "again").unwrap());
+        assert!(access.check_login(alice, "alice").unwrap_err().contains("griefing"));
+        assert!(access.pardon(alice).unwrap());
+        assert!(access.check_login(alice, "alice").is_ok());
+
+        access.set_whitelist_enforced(true);
+        assert!(access.check_login(bob, "bob").is_err());
+        access.whitelist_add(bob, "bob").unwrap();
+        assert!(access.check_login(bob, "bob").is_ok());
+        // Ops bypass the whitelist.
+        access.op(alice, "alice", DEFAULT_OP_LEVEL).unwrap();
+        assert!(access.check_login(alice, "alice").is_ok());
+        assert_eq!(access.permission_level(alice), 4);
+        assert_eq!(access.permission_level(bob), 0);
+
+        // Everything was written through in vanilla format.
+        let reopened = AccessLists::load(&dir, true).unwrap();
+        assert_eq!(reopened.permission_level(alice), 4);
+        assert_eq!(reopened.whitelist_names(), vec!["bob".to_string()]);
+        let ops = std::fs::read_to_string(dir.join(OPS_FILE)).unwrap();
+        assert!(ops.contains("\"bypassesPlayerLimit\": false"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_player_bundle_round_trip() {
+        let src = AccessLists::load(&temp_dir("ultimate_mc_test_bundle_src"), false).unwrap();
+        let (alice, bob) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        src.op(alice, "alice", 3).unwrap();
+        src.whitelist_add(bob, "bob").unwrap();
+        src.ban(bob, "bob", "admin", "spam").unwrap();
+        let json = serde_json::to_string(&src.export_bundle()).unwrap();
+
+        let dst_dir = temp_dir("ultimate_mc_test_bundle_dst");
+        let dst = AccessLists::load(&dst_dir, false).unwrap();
+        dst.op(bob, "bob", 4).unwrap(); // replaced, not merged
+        dst.import_bundle(serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(dst.export_bundle(), src.export_bundle());
+        assert_eq!(dst.permission_level(bob), 0);
+        // Written through to the vanilla files.
+        let reopened = AccessLists::load(&dst_dir, false).unwrap();
+        assert_eq!(reopened.permission_level(alice), 3);
+        assert!(reopened.check_login(bob, "bob").is_err());
+
+        let newer = PlayerBundle { version: BUNDLE_VERSION + 1, ..src.export_bundle() };
+        assert!(dst.import_bundle(newer).is_err());
+
+        let _ = std::fs::remove_dir_all(&dst_dir);
+        let _ = std::fs::remove_dir_all(std::env::temp_dir().join("ultimate_mc_test_bundle_src"));
+    }
+
+    #[test]
+    fn test_hand_edits_reload() {
+        let dir = temp_dir("ultimate_mc_test_access_reload");
+        let access = AccessLists::load(&dir, false).unwrap();
+        let carol = Uuid::from_u128(3);
+        assert_eq!(access.permission_level(carol), 0);
+
+        let entry = OpEntry { uuid: carol, name: "carol".into(), level: 2, bypasses_player_limit: false };
+        std::fs::write(dir.join(OPS_FILE), serde_json::to_string(&vec![entry]).unwrap()).unwrap();
+        assert!(access.reload_if_changed().unwrap());
+        assert_eq!(access.permission_level(carol), 2);
+
+        // A broken edit is an error and leaves the previous list in place.
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(dir.join(OPS_FILE), "[{ not json").unwrap();
+        assert!(access.reload_if_changed().is_err());
+        assert_eq!(access.permission_level(carol), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
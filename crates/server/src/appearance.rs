@@ -0,0 +1,115 @@
+//! How other players see a player: sneaking, sprinting and the item in
+//! their main hand.
+//!
+//! Each player's [`Appearance`] lives in their
+//! [`PlayerInfo`](crate::player_registry::PlayerInfo). Their connection
+//! updates it from `ServerboundPlayerInput` (sneaking — 1.21.6+ clients
+//! no longer send it as a player command), `ServerboundPlayerCommand`
+//! (sprinting) and whatever changes the held item (`SetCarriedItem`, the
+//! inventory window, wear); the registry tells every connection (see
+//! `PlayerEvent::AppearanceChanged`), and those that have the player
+//! spawned send entity data or equipment. Newcomers get
+//! [`Appearance::spawn_packets`] right after the spawn itself.
+
+use azalea_entity::{EntityDataItem, EntityDataValue, EntityMetadataItems, Pose};
+use azalea_inventory::ItemStack;
+use azalea_inventory::components::EquipmentSlot;
+use azalea_protocol::packets::Packet;
+use azalea_protocol::packets::game::c_set_equipment::EquipmentSlots;
+use azalea_protocol::packets::game::{ClientboundGamePacket, ClientboundSetEntityData, ClientboundSetEquipment};
+use azalea_world::MinecraftEntityId;
+
+/// Entity data index of the shared flags byte.
+const FLAGS_INDEX: u8 = 0;
+
+/// Entity data index of the pose.
+const POSE_INDEX: u8 = 6;
+
+/// Shared flag bits.
+const CROUCHING: u8 = 0x02;
+const SPRINTING: u8 = 0x08;
+
+/// What others see of a player, beyond where they are.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Appearance {
+    pub sneaking: bool,
+    pub sprinting: bool,
+    pub main_hand: ItemStack,
+}
+
+impl Appearance {
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if self.sneaking {
+            flags |= CROUCHING;
+        }
+        if self.sprinting {
+            flags |= SPRINTING;
+        }
+        flags
+    }
+
+    /// Whether sneaking or sprinting differ from `other`'s.
+    pub fn pose_differs(&self, other: &Appearance) -> bool {
+        (self.sneaking, self.sprinting) != (other.sneaking, other.sprinting)
+    }
+
+    /// Entity data for the player `eid`: flags and pose.
+    pub fn data_packet(&self, eid: i32) -> ClientboundGamePacket {
+        let pose = if self.sneaking { Pose::Crouching } else { Pose::Standing };
+        ClientboundSetEntityData {
+            id: MinecraftEntityId(eid),
+            packed_items: EntityMetadataItems(vec![
+                EntityDataItem { index: FLAGS_INDEX, value: EntityDataValue::Byte(self.flags()) },
+                EntityDataItem { index: POSE_INDEX, value: EntityDataValue::Pose(pose) },
+            ]),
+        }
+        .into_variant()
+    }
+
+    /// Equipment for the player `eid`: the main hand.
+    pub fn equipment_packet(&self, eid: i32) -> ClientboundGamePacket {
+        ClientboundSetEquipment {
+            entity_id: MinecraftEntityId(eid),
+            slots: EquipmentSlots { slots: vec![(EquipmentSlot::Mainhand, self.main_hand.clone())] },
+        }
+        .into_variant()
+    }
+
+    /// What to send after spawning the player `eid`: only what differs
+    /// from how a fresh player entity looks.
+    pub fn spawn_packets(&self, eid: i32) -> Vec<ClientboundGamePacket> {
+        let mut packets = Vec::new();
+        if self.flags() != 0 {
+            packets.push(self.data_packet(eid));
+        }
+        if !self.main_hand.is_empty() {
+            packets.push(self.equipment_packet(eid));
+        }
+        packets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use azalea_registry::builtin::ItemKind;
+
+    #[test]
+    fn test_spawn_packets_only_for_differences() {
+        let mut look = Appearance::default();
+        assert!(look.spawn_packets(5).is_empty());
+
+        look.sneaking = true;
+        assert_eq!(look.flags(), CROUCHING);
+        assert!(look.pose_differs(&Appearance::default()));
+        assert_eq!(look.spawn_packets(5).len(), 1);
+
+        look.main_hand = ItemStack::new(ItemKind::DiamondSword, 1);
+        look.sprinting = true;
+        assert_eq!(look.flags(), CROUCHING | SPRINTING);
+        let packets = look.spawn_packets(5);
+        assert_eq!(packets.len(), 2);
+        assert!(matches!(&packets[1], ClientboundGamePacket::SetEquipment(p) if p.entity_id == MinecraftEntityId(5)));
+    }
+}
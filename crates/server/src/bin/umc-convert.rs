@@ -0,0 +1,224 @@
+//! `umc-convert` — offline conversion between Anvil region saves and
+//! compact binary world snapshots (see `ultimate_server::snapshot`).
+//!
+//! ```text
+//! umc-convert to-snapshot <world-dir> <out.umcs> [--config server.yaml] [--seed N]
+//! umc-convert to-region   <in.umcs> <world-dir>  [--config server.yaml] [--seed N]
+//! umc-convert from-vanilla <vanilla-world-dir> <world-dir> [--config server.yaml] [--seed N]
+//! ```
+//!
+//! Region saves are delta-encoded against the world generator, so both
+//! directions need the server's preset and seed (read from `--config`,
+//! defaults when the file is absent). `to-snapshot` materializes every
+//! saved chunk (baseline + delta); `to-region` writes self-contained
+//! full-section chunks stamped with the generator fingerprint. Every
+//! conversion re-reads its output and compares it chunk-by-chunk against
+//! the source before reporting success.
+//!
+//! `from-vanilla` imports a vanilla 1.21 save (see
+//! `ultimate_server::vanilla`): terrain becomes delta chunks against the
+//! configured generator, biomes go to `biomes.dat`, and spawn and level
+//! name carry over into `level.dat`.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use ultimate_engine::world::World;
+use ultimate_server::config::{self, ServerConfig};
+use ultimate_server::worldgen::{self, WorldGen};
+use ultimate_server::{persistence, snapshot, vanilla};
+
+/// Pull a `--key value` flag out of the CLI args.
+fn cli_arg(key: &str) -> Option<String> {
+    std::env::args()
+        .skip_while(|a| a != key)
+        .nth(1)
+}
+
+/// Positional args: everything that is neither a `--flag` nor its value.
+fn positional() -> Vec<String> {
+    let mut out = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(a) = args.next() {
+        if a.starts_with("--") {
+            args.next();
+        } else {
+            out.push(a);
+        }
+    }
+    out
+}
+
+fn main() -> ExitCode {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "info".parse().unwrap()),
+        )
+        .init();
+
+    let args = positional();
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["to-snapshot", world_dir, out] => to_snapshot(Path::new(world_dir), Path::new(out)),
+        ["to-region", input, world_dir] => to_region(Path::new(input), Path::new(world_dir)),
+        ["from-vanilla", src, world_dir] => from_vanilla(Path::new(src), Path::new(world_dir)),
+        _ => {
+            eprintln!("usage: umc-convert to-snapshot <world-dir> <out.umcs> [--config server.yaml] [--seed N]");
+            eprintln!("       umc-convert to-region   <in.umcs> <world-dir>  [--config server.yaml] [--seed N]");
+            eprintln!("       umc-convert from-vanilla <vanilla-world-dir> <world-dir> [--config server.yaml] [--seed N]");
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            tracing::error!("Conversion failed: {:#}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Generator + fingerprint the save was written under, and the seed.
+fn generator() -> Result<(std::sync::Arc<dyn WorldGen>, u64, u32)> {
+    let path: PathBuf = cli_arg("--config").unwrap_or_else(|| "server.yaml".into()).into();
+    let mut cfg = if path.exists() {
+        config::load_or_create(&path)?
+    } else {
+        ServerConfig::default()
+    };
+    if let Some(v) = cli_arg("--seed").and_then(|s| s.parse().ok()) {
+        cfg.world.seed = v;
+    }
+    tracing::info!("Using preset {:?} with seed {:#x}", cfg.world.preset, cfg.world.seed);
+    let generator = worldgen::preset::load(&cfg.world.preset, cfg.world.seed)?;
+    let fp = worldgen::preset::fingerprint(&cfg.world.preset, cfg.world.seed)?;
+    Ok((generator, fp, cfg.world.seed))
+}
+
+/// Log progress roughly every 5% of the work.
+fn progress(label: &'static str) -> impl FnMut(usize, usize) {
+    let mut last_pct = None;
+    move |done, total| {
+        let pct = (done * 100 / total.max(1)) / 5 * 5;
+        if last_pct != Some(pct) {
+            last_pct = Some(pct);
+            tracing::info!("{}: {}/{} chunks ({}%)", label, done, total, pct);
+        }
+    }
+}
+
+/// Re-read check: the converted output must match the source exactly.
+fn validate(source: &World, converted: &World) -> Result<()> {
+    let mismatched = snapshot::diff_worlds(source, converted);
+    if !mismatched.is_empty() {
+        bail!(
+            "validation failed: {} chunks differ after conversion (first: {:?})",
+            mismatched.len(),
+            mismatched[0],
+        );
+    }
+    tracing::info!("Validated: {} chunks identical after re-read", source.chunk_count());
+    Ok(())
+}
+
+fn to_snapshot(world_dir: &Path, out: &Path) -> Result<()> {
+    let start = Instant::now();
+    let (generator, fp, _) = generator()?;
+    let world = World::new();
+    let n = persistence::load_into(&world, world_dir, fp, &*generator, None)
+        .with_context(|| format!("loading {}", world_dir.display()))?;
+    if n == 0 {
+        bail!("no saved chunks found under {}", world_dir.display());
+    }
+
+    snapshot::write(&world, out, &mut progress("Encoding"))?;
+
+    let reread = World::new();
+    snapshot::read_into(&reread, out)?;
+    validate(&world, &reread)?;
+
+    let size = std::fs::metadata(out)?.len();
+    tracing::info!(
+        "Wrote {} chunks to {} ({:.1} KB, {:.2?})",
+        n, out.display(), size as f64 / 1e3, start.elapsed(),
+    );
+    Ok(())
+}
+
+/// Refuse to merge into an existing save: the result would mix two
+/// worlds and the re-read validation could not tell them apart.
+fn ensure_empty(world_dir: &Path) -> Result<()> {
+    let region_dir = world_dir.join("region");
+    if region_dir.is_dir() && std::fs::read_dir(&region_dir)?.next().is_some() {
+        bail!("{} already contains region files; convert into an empty directory", world_dir.display());
+    }
+    Ok(())
+}
+
+fn to_region(input: &Path, world_dir: &Path) -> Result<()> {
+    let start = Instant::now();
+    ensure_empty(world_dir)?;
+    let region_dir = world_dir.join("region");
+    let (generator, fp, _) = generator()?;
+    let world = World::new();
+    let n = snapshot::read_into(&world, input)?;
+    tracing::info!("Read {} chunks from {}", n, input.display());
+
+    persistence::export_full_chunks(&world, world_dir, fp, &mut progress("Exporting"))?;
+
+    let reread = World::new();
+    persistence::load_into(&reread, world_dir, fp, &*generator, None)?;
+    validate(&world, &reread)?;
+
+    tracing::info!(
+        "Wrote {} chunks to {} ({:.2?})",
+        n, region_dir.display(), start.elapsed(),
+    );
+    Ok(())
+}
+
+fn from_vanilla(src: &Path, world_dir: &Path) -> Result<()> {
+    let start = Instant::now();
+    ensure_empty(world_dir)?;
+    let (generator, fp, seed) = generator()?;
+    let world = World::new();
+    let (biomes, stats) = vanilla::import_world(&world, src)
+        .with_context(|| format!("importing {}", src.display()))?;
+    if stats.chunks == 0 {
+        bail!("no complete 1.18+ chunks found under {}", src.display());
+    }
+    tracing::info!(
+        "Read {} chunks from {} ({} incomplete and {} pre-1.18 chunks skipped)",
+        stats.chunks, src.display(), stats.incomplete, stats.legacy,
+    );
+    if stats.block_entities > 0 {
+        tracing::warn!(
+            "Dropped the contents of {} block entities (chests, signs, ...); their blocks were imported",
+            stats.block_entities,
+        );
+    }
+    if stats.unmapped_biomes > 0 {
+        tracing::warn!("{} biome palette entries had no equivalent and became plains", stats.unmapped_biomes);
+    }
+
+    persistence::export_delta_chunks(&world, world_dir, fp, &*generator, &mut progress("Exporting"))?;
+    biomes.save(world_dir)?;
+    let spawn = stats.spawn.unwrap_or([8, generator.spawn_y(8, 8).ceil() as i32, 8]);
+    let mut level = persistence::LevelInfo::new(seed as i64, spawn);
+    if let Some(name) = stats.level_name {
+        level.level_name = name;
+    }
+    persistence::write_level_dat(world_dir, &level)?;
+
+    let reread = World::new();
+    persistence::load_into(&reread, world_dir, fp, &*generator, None)?;
+    validate(&world, &reread)?;
+
+    tracing::info!(
+        "Imported {} chunks ({} with biomes) into {} ({:.2?})",
+        stats.chunks, biomes.len(), world_dir.display(), start.elapsed(),
+    );
+    Ok(())
+}
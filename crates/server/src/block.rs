@@ -3,6 +3,7 @@
 //! BlockId values are MC block state IDs (from azalea-block), so they can be
 //! used directly in protocol chunk data without any mapping layer.
 
+use ultimate_engine::pathfinding::Cell;
 use ultimate_engine::world::block::BlockId;
 
 // ── MC block state IDs (from azalea-block for MC 1.21.11) ────────────────
@@ -15,6 +16,8 @@ pub const DIRT: BlockId = BlockId(10);
 pub const BEDROCK: BlockId = BlockId(85);
 pub const SAND: BlockId = BlockId(118);
 pub const OAK_LOG: BlockId = BlockId(137);    // axis=y
+pub const COBBLESTONE: BlockId = BlockId(14);
+pub const OBSIDIAN: BlockId = BlockId(3168);
 
 // Legacy aliases for engine tests (which use small sequential IDs)
 pub const GRASS: BlockId = GRASS_BLOCK;
@@ -45,8 +48,9 @@ impl FluidKind {
         }
     }
 
-    /// Maximum horizontal spread distance.
-    /// Water: 7 blocks.  Lava: 3 blocks (overworld).
+    /// Maximum horizontal spread distance in vanilla.
+    /// Water: 7 blocks.  Lava: 3 blocks (overworld). `rules.toml` can
+    /// override it.
     pub const fn max_spread(self) -> u8 {
         match self {
             FluidKind::Water => 7,
@@ -54,18 +58,38 @@ impl FluidKind {
         }
     }
 
+    /// Whether flowing fluid between two sources becomes a source itself:
+    /// the `waterSourceConversion` / `lavaSourceConversion` gamerules. By
+    /// default water does (infinite pools) and lava doesn't.
+    pub fn forms_sources(self) -> bool {
+        use crate::gamerules::GameRule;
+        crate::gamerules::enabled(match self {
+            FluidKind::Water => GameRule::WaterSourceConversion,
+            FluidKind::Lava => GameRule::LavaSourceConversion,
+        })
+    }
+
     /// Source block for this fluid (level 0).
     pub const fn source(self) -> BlockId {
         BlockId(self.base_id())
     }
 
-    /// Block ID for this fluid at a given level (0-15, clamped).
-    pub const fn at_level(self, level: u8) -> BlockId {
-        let l = if level > 15 { 15 } else { level };
+    /// Flowing fluid `distance` blocks from whatever feeds it (a source or
+    /// a falling column): vanilla's `level` 1-7, clamped to that range.
+    pub const fn flowing(self, distance: u8) -> BlockId {
+        let l = if distance < 1 { 1 } else if distance > 7 { 7 } else { distance };
         BlockId(self.base_id() + l as u16)
     }
 
-    /// If `id` is this fluid, return its level (0-15). Otherwise `None`.
+    /// Falling fluid: vanilla's `level` 8, the flowing state with the
+    /// falling bit set. Clients draw it as a full column instead of a
+    /// sloped surface.
+    pub const fn falling(self) -> BlockId {
+        BlockId(self.base_id() + 8)
+    }
+
+    /// If `id` is this fluid, return its raw `level` property (0-15).
+    /// Otherwise `None`.
     pub const fn level(self, id: BlockId) -> Option<u8> {
         let base = self.base_id();
         if id.0 >= base && id.0 <= base + 15 {
@@ -75,6 +99,21 @@ impl FluidKind {
         }
     }
 
+    /// Is `id` this fluid with the falling bit set (level 8-15)?
+    pub const fn is_falling(self, id: BlockId) -> bool {
+        matches!(self.level(id), Some(l) if l >= 8)
+    }
+
+    /// If `id` is this fluid, how far it has spread: 0 for sources and
+    /// falling fluid (both feed their neighbours at distance 1), the level
+    /// for flowing fluid. Otherwise `None`.
+    pub const fn distance(self, id: BlockId) -> Option<u8> {
+        match self.level(id) {
+            Some(l) if l >= 8 => Some(0),
+            other => other,
+        }
+    }
+
     /// Does `id` belong to this fluid at any level?
     pub const fn is_match(self, id: BlockId) -> bool {
         let base = self.base_id();
@@ -105,9 +144,9 @@ pub fn water_level(id: BlockId) -> Option<u8> {
     FluidKind::Water.level(id)
 }
 
-/// Create a water block at the given level (0-15).
-pub fn water_at_level(level: u8) -> BlockId {
-    FluidKind::Water.at_level(level)
+/// Create flowing water `distance` blocks from its feed (level 1-7).
+pub fn water_flowing(distance: u8) -> BlockId {
+    FluidKind::Water.flowing(distance)
 }
 
 /// Maximum horizontal spread for water.
@@ -120,9 +159,9 @@ pub fn lava_level(id: BlockId) -> Option<u8> {
     FluidKind::Lava.level(id)
 }
 
-/// Create a lava block at the given level (0-15).
-pub fn lava_at_level(level: u8) -> BlockId {
-    FluidKind::Lava.at_level(level)
+/// Create flowing lava `distance` blocks from its feed (level 1-7).
+pub fn lava_flowing(distance: u8) -> BlockId {
+    FluidKind::Lava.flowing(distance)
 }
 
 /// Maximum horizontal spread for lava.
@@ -132,9 +171,105 @@ pub fn lava_max_spread() -> u8 {
 
 // ── Block property queries ──────────────────────────────────────────────
 
-/// Does this block fall under gravity (like sand/gravel)?
+/// How a block responds to having nothing under it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gravity {
+    /// Stays where it is.
+    Floats,
+    /// Falls through air and fluids.
+    Falls,
+    /// Falls, and becomes this block if it lands in water (concrete
+    /// powder → concrete).
+    Hardens(BlockId),
+}
+
+/// Blocks that fall, with what they harden into in water. Every state of
+/// a listed block falls (all anvil facings, ...).
+const FALLING_BLOCKS: &[(azalea_registry::builtin::BlockKind, Option<azalea_registry::builtin::BlockKind>)] = {
+    use azalea_registry::builtin::BlockKind as B;
+    &[
+        (B::Sand, None),
+        (B::RedSand, None),
+        (B::Gravel, None),
+        (B::SuspiciousSand, None),
+        (B::SuspiciousGravel, None),
+        (B::Anvil, None),
+        (B::ChippedAnvil, None),
+        (B::DamagedAnvil, None),
+        (B::WhiteConcretePowder, Some(B::WhiteConcrete)),
+        (B::OrangeConcretePowder, Some(B::OrangeConcrete)),
+        (B::MagentaConcretePowder, Some(B::MagentaConcrete)),
+        (B::LightBlueConcretePowder, Some(B::LightBlueConcrete)),
+        (B::YellowConcretePowder, Some(B::YellowConcrete)),
+        (B::LimeConcretePowder, Some(B::LimeConcrete)),
+        (B::PinkConcretePowder, Some(B::PinkConcrete)),
+        (B::GrayConcretePowder, Some(B::GrayConcrete)),
+        (B::LightGrayConcretePowder, Some(B::LightGrayConcrete)),
+        (B::CyanConcretePowder, Some(B::CyanConcrete)),
+        (B::PurpleConcretePowder, Some(B::PurpleConcrete)),
+        (B::BlueConcretePowder, Some(B::BlueConcrete)),
+        (B::BrownConcretePowder, Some(B::BrownConcrete)),
+        (B::GreenConcretePowder, Some(B::GreenConcrete)),
+        (B::RedConcretePowder, Some(B::RedConcrete)),
+        (B::BlackConcretePowder, Some(B::BlackConcrete)),
+    ]
+};
+
+static GRAVITY_LUT: std::sync::LazyLock<Box<[Gravity]>> =
+    std::sync::LazyLock::new(|| gravity_table(FALLING_BLOCKS));
+
+/// The vanilla falling blocks as `(name, hardens into)`, without the
+/// `minecraft:` namespace. The defaults of `rules.toml`.
+pub fn vanilla_falling_blocks() -> impl Iterator<Item = (String, Option<String>)> {
+    let bare = |kind: azalea_registry::builtin::BlockKind| {
+        let name = kind.to_string();
+        name.strip_prefix("minecraft:").map(str::to_owned).unwrap_or(name)
+    };
+    FALLING_BLOCKS.iter().map(move |(kind, hardened)| (bare(*kind), hardened.map(bare)))
+}
+
+/// A gravity lookup table over every block state for the given falling
+/// blocks, indexable by `BlockId`.
+pub fn gravity_table(
+    falling: &[(azalea_registry::builtin::BlockKind, Option<azalea_registry::builtin::BlockKind>)],
+) -> Box<[Gravity]> {
+    (0..=azalea_block::BlockState::MAX_STATE)
+        .map(|raw| gravity_uncached(BlockId(raw), falling))
+        .collect()
+}
+
+fn gravity_uncached(
+    id: BlockId,
+    falling: &[(azalea_registry::builtin::BlockKind, Option<azalea_registry::builtin::BlockKind>)],
+) -> Gravity {
+    use azalea_block::BlockState;
+    use azalea_registry::builtin::BlockKind;
+
+    let Ok(state) = BlockState::try_from(id.0 as u32) else {
+        return Gravity::Floats;
+    };
+    let kind = BlockKind::from(state);
+    match falling.iter().find(|(k, _)| *k == kind) {
+        None => Gravity::Floats,
+        Some((_, None)) => Gravity::Falls,
+        Some((_, Some(hardened))) => {
+            let state: u32 = BlockState::from(*hardened).into();
+            Gravity::Hardens(BlockId(state as u16))
+        }
+    }
+}
+
+/// How this block responds to gravity in vanilla. LUT-backed; O(1). The
+/// gravity rule itself uses the list from `rules.toml` (`rules::config`).
+#[inline]
+pub fn gravity(id: BlockId) -> Gravity {
+    GRAVITY_LUT.get(id.0 as usize).copied().unwrap_or(Gravity::Floats)
+}
+
+/// Does this block fall under gravity (sand, gravel, concrete powder,
+/// anvils)?
 pub fn has_gravity(id: BlockId) -> bool {
-    id == SAND
+    gravity(id) != Gravity::Floats
 }
 
 /// Can another block be placed in this space?
@@ -147,6 +282,93 @@ pub fn is_solid(id: BlockId) -> bool {
     !is_replaceable(id)
 }
 
+static PASSABLE_LUT: std::sync::LazyLock<Box<[bool]>> = std::sync::LazyLock::new(|| {
+    (0..=azalea_block::BlockState::MAX_STATE)
+        .map(|raw| passable_uncached(BlockId(raw)))
+        .collect()
+});
+
+/// Can an entity walk through this block? Air and the blocks without a
+/// collision box that commonly cover the ground (plants, torches, rails,
+/// redstone dust). Fluids aren't: mobs keep out of them. LUT-backed; O(1).
+#[inline]
+pub fn is_passable(id: BlockId) -> bool {
+    PASSABLE_LUT.get(id.0 as usize).copied().unwrap_or(false)
+}
+
+fn passable_uncached(id: BlockId) -> bool {
+    use azalea_block::{BlockState, BlockTrait};
+
+    if id == AIR {
+        return true;
+    }
+    let Ok(state) = BlockState::try_from(id.0 as u32) else {
+        return false;
+    };
+    let block: Box<dyn BlockTrait> = Box::<dyn BlockTrait>::from(state);
+    match block.id() {
+        "air" | "cave_air" | "void_air" => true,
+        "dandelion" | "poppy" | "blue_orchid"
+        | "allium" | "azure_bluet"
+        | "red_tulip" | "orange_tulip"
+        | "white_tulip" | "pink_tulip"
+        | "oxeye_daisy" | "cornflower"
+        | "lily_of_the_valley" | "wither_rose"
+        | "sunflower" | "lilac"
+        | "rose_bush" | "peony"
+        | "short_grass" | "tall_grass"
+        | "fern" | "large_fern"
+        | "dead_bush" | "sugar_cane" | "vine" => true,
+        "torch" | "wall_torch" | "soul_torch" | "soul_wall_torch"
+        | "redstone_torch" | "redstone_wall_torch" => true,
+        "rail" | "powered_rail" | "detector_rail" | "activator_rail" => true,
+        "redstone_wire" | "lever" => true,
+        n => n.ends_with("_sapling"),
+    }
+}
+
+static PATH_CELL_LUT: std::sync::LazyLock<Box<[Cell]>> = std::sync::LazyLock::new(|| {
+    (0..=azalea_block::BlockState::MAX_STATE)
+        .map(|raw| path_cell_uncached(BlockId(raw)))
+        .collect()
+});
+
+/// How mobs path through this block (see
+/// [`ultimate_engine::pathfinding`]): passable blocks are open, fluids
+/// and blocks that hurt are avoided, closed doors need a mob that opens
+/// them (iron ones need redstone, so count as walls), and fences and
+/// walls are too tall to jump. LUT-backed; O(1).
+#[inline]
+pub fn path_cell(id: BlockId) -> Cell {
+    PATH_CELL_LUT.get(id.0 as usize).copied().unwrap_or(Cell::Solid)
+}
+
+fn path_cell_uncached(id: BlockId) -> Cell {
+    use azalea_block::{BlockState, BlockTrait};
+
+    if passable_uncached(id) {
+        return Cell::Open;
+    }
+    if is_fluid(id) {
+        return Cell::Hazard;
+    }
+    let Ok(state) = BlockState::try_from(id.0 as u32) else {
+        return Cell::Solid;
+    };
+    let block: Box<dyn BlockTrait> = Box::<dyn BlockTrait>::from(state);
+    let open = || block.property_map().iter().any(|(k, v)| *k == "open" && *v == "true");
+    match block.id() {
+        "fire" | "soul_fire" | "cactus" | "magma_block" | "sweet_berry_bush"
+        | "campfire" | "soul_campfire" | "powder_snow" => Cell::Hazard,
+        n if n.ends_with("_door") && open() => Cell::Open,
+        "iron_door" => Cell::Tall,
+        n if n.ends_with("_door") => Cell::Door,
+        n if n.ends_with("_fence_gate") && open() => Cell::Open,
+        n if n.ends_with("_fence") || n.ends_with("_fence_gate") || n.ends_with("_wall") => Cell::Tall,
+        _ => Cell::Solid,
+    }
+}
+
 // ── Light property queries ──────────────────────────────────────────────
 //
 // The `*_uncached` functions resolve properties through azalea's
@@ -400,6 +622,35 @@ pub fn block_id_from_name(name: &str) -> Option<BlockId> {
     Some(BlockId::new(state as u16))
 }
 
+/// Whether two states are the same block, ignoring properties (every
+/// `oak_stairs` orientation matches `oak_stairs`).
+pub fn same_block(a: BlockId, b: BlockId) -> bool {
+    use azalea_block::BlockState;
+    use azalea_registry::builtin::BlockKind;
+
+    let kind = |id: BlockId| BlockState::try_from(id.0 as u32).ok().map(BlockKind::from);
+    kind(a) == kind(b)
+}
+
+/// The namespaced name of `id`'s block, ignoring properties
+/// (`minecraft:oak_stairs`).
+pub fn kind_name(id: BlockId) -> String {
+    use azalea_block::BlockState;
+    use azalea_registry::builtin::BlockKind;
+
+    BlockState::try_from(id.0 as u32)
+        .map(|state| BlockKind::from(state).to_string())
+        .unwrap_or_else(|_| format!("block#{}", id.0))
+}
+
+/// Every namespaced block name [`block_id_from_name`] accepts, in registry
+/// order.
+pub fn block_names() -> impl Iterator<Item = String> {
+    use azalea_registry::builtin::BlockKind;
+
+    (0u32..).map_while(|i| BlockKind::try_from(i).ok()).map(|kind| kind.to_string())
+}
+
 /// Human-readable name for dashboard display.
 pub fn name(id: BlockId) -> String {
     match id {
@@ -410,6 +661,8 @@ pub fn name(id: BlockId) -> String {
         BEDROCK => "bedrock".into(),
         SAND => "sand".into(),
         OAK_LOG => "oak_log".into(),
+        COBBLESTONE => "cobblestone".into(),
+        OBSIDIAN => "obsidian".into(),
         LEAVES => "oak_leaves".into(),
         _ => {
             if let Some((kind, level)) = fluid_kind(id) {
@@ -419,6 +672,8 @@ pub fn name(id: BlockId) -> String {
                 };
                 if level == 0 {
                     format!("{}(source)", fluid_name)
+                } else if level >= 8 {
+                    format!("{}(falling)", fluid_name)
                 } else {
                     format!("{}(lvl {})", fluid_name, level)
                 }
@@ -0,0 +1,248 @@
+//! Boss bars: titled progress bars across the top of the screen, for any
+//! subsystem to show status with (autosave progress, timers).
+//!
+//! A bar is shown to everyone or to a chosen set of players
+//! ([`Viewers`]); the set is kept by UUID, so a player who reconnects sees
+//! the bars meant for them again. Every change is broadcast as
+//! [`BossBarUpdate`]s naming who should receive them; connections relay
+//! those addressed to them after the snapshot from [`BossBarManager::join`].
+//! Bars live in memory only.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use azalea_chat::FormattedText;
+use azalea_protocol::packets::game::c_boss_event::{
+    AddOperation, BossBarColor, BossBarOverlay, ClientboundBossEvent, Operation, Properties, Style,
+};
+use azalea_protocol::packets::game::ClientboundGamePacket;
+use azalea_protocol::packets::Packet;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// High half of every bar's UUID; the low half counts up.
+const UUID_PREFIX: u64 = 0x626f_7373_6261_7273; // "bossbars"
+
+/// What a bar shows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BossBar {
+    pub title: String,
+    /// Filled fraction, 0.0 to 1.0.
+    pub progress: f32,
+    pub color: BossBarColor,
+    pub overlay: BossBarOverlay,
+}
+
+impl BossBar {
+    /// A plain white bar.
+    pub fn new(title: impl Into<String>, progress: f32) -> Self {
+        Self { title: title.into(), progress, color: BossBarColor::White, overlay: BossBarOverlay::Progress }
+    }
+}
+
+/// Who sees a bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Viewers {
+    Everyone,
+    Players(HashSet<Uuid>),
+}
+
+impl Viewers {
+    fn includes(&self, player: Uuid) -> bool {
+        match self {
+            Viewers::Everyone => true,
+            Viewers::Players(players) => players.contains(&player),
+        }
+    }
+}
+
+/// A packet for some or all players.
+#[derive(Debug, Clone)]
+pub struct BossBarUpdate {
+    /// `None` for everyone.
+    pub to: Option<Arc<[Uuid]>>,
+    pub packet: ClientboundGamePacket,
+}
+
+impl BossBarUpdate {
+    pub fn reaches(&self, player: Uuid) -> bool {
+        self.to.as_ref().is_none_or(|to| to.contains(&player))
+    }
+}
+
+struct Shown {
+    bar: BossBar,
+    viewers: Viewers,
+}
+
+/// Every bar on the server. Changes are broadcast under the same lock
+/// that applies them, so a joining player's snapshot and the updates after
+/// it never disagree.
+pub struct BossBarManager {
+    bars: RwLock<BTreeMap<Uuid, Shown>>,
+    next_id: AtomicU64,
+    tx: broadcast::Sender<BossBarUpdate>,
+}
+
+impl Default for BossBarManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BossBarManager {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(256);
+        Self { bars: RwLock::new(BTreeMap::new()), next_id: AtomicU64::new(1), tx }
+    }
+
+    /// The bars `player` should see now, and every update after.
+    pub fn join(&self, player: Uuid) -> (Vec<ClientboundGamePacket>, broadcast::Receiver<BossBarUpdate>) {
+        let bars = self.bars.read().expect("boss bars poisoned");
+        let packets = bars
+            .iter()
+            .filter(|(_, shown)| shown.viewers.includes(player))
+            .map(|(&id, shown)| add_packet(id, &shown.bar))
+            .collect();
+        (packets, self.tx.subscribe())
+    }
+
+    /// Show a new bar to `viewers`; the returned id changes or removes it.
+    pub fn create(&self, bar: BossBar, viewers: Viewers) -> Uuid {
+        let id = Uuid::from_u64_pair(UUID_PREFIX, self.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut bars = self.bars.write().expect("boss bars poisoned");
+        self.send(audience(&viewers), add_packet(id, &bar));
+        bars.insert(id, Shown { bar, viewers });
+        id
+    }
+
+    /// Take a bar down for everyone who sees it.
+    pub fn remove(&self, id: Uuid) {
+        let mut bars = self.bars.write().expect("boss bars poisoned");
+        if let Some(shown) = bars.remove(&id) {
+            self.send(audience(&shown.viewers), event(id, Operation::Remove));
+        }
+    }
+
+    pub fn set_progress(&self, id: Uuid, progress: f32) {
+        self.update(id, |bar| {
+            bar.progress = progress.clamp(0.0, 1.0);
+            Operation::UpdateProgress(bar.progress)
+        });
+    }
+
+    pub fn set_title(&self, id: Uuid, title: &str) {
+        self.update(id, |bar| {
+            bar.title = title.to_owned();
+            Operation::UpdateName(FormattedText::from(bar.title.clone()))
+        });
+    }
+
+    pub fn set_style(&self, id: Uuid, color: BossBarColor, overlay: BossBarOverlay) {
+        self.update(id, |bar| {
+            (bar.color, bar.overlay) = (color, overlay);
+            Operation::UpdateStyle(Style { color, overlay })
+        });
+    }
+
+    /// Add `player` to a per-player bar's viewers. False for an unknown
+    /// bar or one already shown to everyone.
+    pub fn show(&self, id: Uuid, player: Uuid) -> bool {
+        let mut bars = self.bars.write().expect("boss bars poisoned");
+        let Some(Shown { bar, viewers: Viewers::Players(players) }) = bars.get_mut(&id) else {
+            return false;
+        };
+        if players.insert(player) {
+            self.send(Some(Arc::new([player])), add_packet(id, bar));
+        }
+        true
+    }
+
+    /// Remove `player` from a per-player bar's viewers. False for an
+    /// unknown bar or one shown to everyone.
+    pub fn hide(&self, id: Uuid, player: Uuid) -> bool {
+        let mut bars = self.bars.write().expect("boss bars poisoned");
+        let Some(Shown { viewers: Viewers::Players(players), .. }) = bars.get_mut(&id) else {
+            return false;
+        };
+        if players.remove(&player) {
+            self.send(Some(Arc::new([player])), event(id, Operation::Remove));
+        }
+        true
+    }
+
+    pub fn is_visible_to(&self, id: Uuid, player: Uuid) -> bool {
+        let bars = self.bars.read().expect("boss bars poisoned");
+        bars.get(&id).is_some_and(|shown| shown.viewers.includes(player))
+    }
+
+    fn update(&self, id: Uuid, f: impl FnOnce(&mut BossBar) -> Operation) {
+        let mut bars = self.bars.write().expect("boss bars poisoned");
+        if let Some(shown) = bars.get_mut(&id) {
+            let operation = f(&mut shown.bar);
+            self.send(audience(&shown.viewers), event(id, operation));
+        }
+    }
+
+    fn send(&self, to: Option<Arc<[Uuid]>>, packet: ClientboundGamePacket) {
+        // No receivers (nobody online) is fine.
+        let _ = self.tx.send(BossBarUpdate { to, packet });
+    }
+}
+
+fn audience(viewers: &Viewers) -> Option<Arc<[Uuid]>> {
+    match viewers {
+        Viewers::Everyone => None,
+        Viewers::Players(players) => Some(players.iter().copied().collect()),
+    }
+}
+
+fn event(id: Uuid, operation: Operation) -> ClientboundGamePacket {
+    ClientboundBossEvent { id, operation }.into_variant()
+}
+
+fn add_packet(id: Uuid, bar: &BossBar) -> ClientboundGamePacket {
+    event(
+        id,
+        Operation::Add(AddOperation {
+            name: FormattedText::from(bar.title.clone()),
+            progress: bar.progress,
+            style: Style { color: bar.color, overlay: bar.overlay },
+            properties: Properties { darken_screen: false, play_music: false, create_world_fog: false },
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_updates_reach_only_viewers() {
+        let bars = BossBarManager::new();
+        let (alice, bob) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let (_, mut rx) = bars.join(bob);
+
+        let id = bars.create(BossBar::new("Timer", 1.0), Viewers::Players(HashSet::from([alice])));
+        bars.set_progress(id, 0.5);
+        assert!(bars.is_visible_to(id, alice) && !bars.is_visible_to(id, bob));
+        for _ in 0..2 {
+            let update = rx.try_recv().unwrap();
+            assert!(update.reaches(alice) && !update.reaches(bob));
+        }
+
+        assert!(bars.show(id, bob));
+        let (snapshot, _) = bars.join(bob);
+        assert_eq!(snapshot.len(), 1, "a reconnecting viewer gets the bar again");
+        assert!(rx.try_recv().unwrap().reaches(bob));
+        assert!(bars.hide(id, alice));
+        assert!(bars.join(alice).0.is_empty());
+
+        let everyone = bars.create(BossBar::new("Saving", 0.0), Viewers::Everyone);
+        assert!(!bars.hide(everyone, alice), "everyone's bars aren't per-player");
+        bars.remove(everyone);
+        bars.remove(id);
+        assert!(bars.join(bob).0.is_empty());
+    }
+}
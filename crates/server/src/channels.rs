@@ -0,0 +1,206 @@
+//! Plugin channels: named custom-payload (plugin message) channels for
+//! server subsystems to talk to client mods and proxies over.
+//!
+//! A subsystem [`register`](PluginChannels::register)s a channel with a
+//! handler; connections hand it every payload a client sends on that
+//! channel (in Configuration and Play), and whatever the handler returns
+//! goes back to that client on the same channel. Pushes go through
+//! [`send`](PluginChannels::send) and [`broadcast`](PluginChannels::broadcast),
+//! which connections relay like boss bar updates. Joining clients are told
+//! the registered channel names on `minecraft:register`.
+//!
+//! `minecraft:brand` is built in: we send ours during Configuration and
+//! log the client's.
+
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::sync::{Arc, RwLock};
+
+use azalea_buf::{AzaleaRead, AzaleaWrite};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+pub const BRAND: &str = "minecraft:brand";
+pub const REGISTER: &str = "minecraft:register";
+/// What we call ourselves on `minecraft:brand` (shown on the client's F3
+/// screen).
+pub const SERVER_BRAND: &str = "ultimate-minecraft";
+
+/// A payload a client sent.
+#[derive(Debug, Clone, Copy)]
+pub struct Received<'a> {
+    pub player: Uuid,
+    pub name: &'a str,
+    pub channel: &'a str,
+    pub data: &'a [u8],
+}
+
+/// Called on the connection's task for every payload on its channel; keep
+/// it quick. `Some(data)` is sent back to the sender on the same channel.
+pub type Handler = Arc<dyn Fn(&Received<'_>) -> Option<Vec<u8>> + Send + Sync>;
+
+/// A payload for some or all players.
+#[derive(Debug, Clone)]
+pub struct Outgoing {
+    /// `None` for everyone.
+    pub to: Option<Uuid>,
+    pub channel: String,
+    pub data: Arc<[u8]>,
+}
+
+impl Outgoing {
+    pub fn reaches(&self, player: Uuid) -> bool {
+        self.to.is_none_or(|to| to == player)
+    }
+}
+
+/// Every registered channel.
+pub struct PluginChannels {
+    handlers: RwLock<BTreeMap<String, Handler>>,
+    tx: broadcast::Sender<Outgoing>,
+}
+
+impl Default for PluginChannels {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PluginChannels {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(256);
+        let channels = Self { handlers: RwLock::new(BTreeMap::new()), tx };
+        channels.register(BRAND, |msg| {
+            match decode_string(msg.data) {
+                Some(brand) => tracing::info!("{} is using client brand {:?}", msg.name, brand),
+                None => tracing::debug!("{} sent a malformed brand", msg.name),
+            }
+            None
+        });
+        channels
+    }
+
+    /// Handle payloads on `channel` (a namespaced id such as
+    /// `myplugin:sync`). False if it already has a handler.
+    pub fn register(
+        &self,
+        channel: &str,
+        handler: impl Fn(&Received<'_>) -> Option<Vec<u8>> + Send + Sync + 'static,
+    ) -> bool {
+        let mut handlers = self.handlers.write().expect("plugin channels poisoned");
+        if handlers.contains_key(channel) {
+            return false;
+        }
+        handlers.insert(channel.to_owned(), Arc::new(handler));
+        true
+    }
+
+    /// Stop handling `channel`. False if it had no handler.
+    pub fn unregister(&self, channel: &str) -> bool {
+        self.handlers.write().expect("plugin channels poisoned").remove(channel).is_some()
+    }
+
+    /// Registered channel names, sorted.
+    pub fn names(&self) -> Vec<String> {
+        self.handlers.read().expect("plugin channels poisoned").keys().cloned().collect()
+    }
+
+    /// Run the handler for a payload a client sent. `None` when nothing
+    /// answers (unknown channel or no reply).
+    pub fn dispatch(&self, msg: &Received<'_>) -> Option<Vec<u8>> {
+        // Clone the handler out so it runs without the lock held and can
+        // itself register channels or send.
+        let handler = self.handlers.read().expect("plugin channels poisoned").get(msg.channel).cloned();
+        match handler {
+            Some(handler) => handler(msg),
+            None => {
+                tracing::debug!("{} sent {} bytes on unregistered channel {}", msg.name, msg.data.len(), msg.channel);
+                None
+            }
+        }
+    }
+
+    /// Send `data` on `channel` to the player with `uuid`, if online.
+    pub fn send(&self, uuid: Uuid, channel: &str, data: Vec<u8>) {
+        self.push(Some(uuid), channel, data);
+    }
+
+    /// Send `data` on `channel` to every online player.
+    pub fn broadcast(&self, channel: &str, data: Vec<u8>) {
+        self.push(None, channel, data);
+    }
+
+    fn push(&self, to: Option<Uuid>, channel: &str, data: Vec<u8>) {
+        // No receivers (nobody online) is fine.
+        let _ = self.tx.send(Outgoing { to, channel: channel.to_owned(), data: data.into() });
+    }
+
+    /// The `minecraft:register` payload for a joining player, and every
+    /// push after it.
+    pub fn join(&self) -> (Vec<u8>, broadcast::Receiver<Outgoing>) {
+        (self.names().join("\0").into_bytes(), self.tx.subscribe())
+    }
+}
+
+/// A protocol string (VarInt length, UTF-8), as `minecraft:brand` carries.
+pub fn encode_string(s: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(s.len() + 1);
+    s.to_owned().azalea_write(&mut out).expect("writing to a Vec cannot fail");
+    out
+}
+
+pub fn decode_string(data: &[u8]) -> Option<String> {
+    String::azalea_read(&mut Cursor::new(data)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn received<'a>(channel: &'a str, data: &'a [u8]) -> Received<'a> {
+        Received { player: Uuid::nil(), name: "Alice", channel, data }
+    }
+
+    #[test]
+    fn test_register_dispatch_and_reply() {
+        let channels = PluginChannels::new();
+        assert!(channels.register("test:echo", |msg| Some(msg.data.iter().rev().copied().collect())));
+        assert!(!channels.register("test:echo", |_| None));
+        assert_eq!(channels.names(), vec![BRAND.to_owned(), "test:echo".to_owned()]);
+
+        assert_eq!(channels.dispatch(&received("test:echo", &[1, 2, 3])), Some(vec![3, 2, 1]));
+        assert_eq!(channels.dispatch(&received("test:other", &[1])), None);
+        assert_eq!(channels.dispatch(&received(BRAND, &encode_string("vanilla"))), None);
+
+        assert!(channels.unregister("test:echo"));
+        assert!(!channels.unregister("test:echo"));
+        assert_eq!(channels.dispatch(&received("test:echo", &[1])), None);
+    }
+
+    #[test]
+    fn test_pushes_reach_their_players() {
+        let channels = PluginChannels::new();
+        channels.register("test:sync", |_| None);
+        let (register, mut rx) = channels.join();
+        assert_eq!(register, b"minecraft:brand\0test:sync");
+
+        let alice = Uuid::from_u128(1);
+        let bob = Uuid::from_u128(2);
+        channels.send(alice, "test:sync", vec![7]);
+        channels.broadcast("test:sync", vec![8]);
+
+        let first = rx.try_recv().unwrap();
+        assert!(first.reaches(alice) && !first.reaches(bob));
+        assert_eq!(&*first.data, &[7]);
+        let second = rx.try_recv().unwrap();
+        assert!(second.reaches(alice) && second.reaches(bob));
+    }
+
+    #[test]
+    fn test_brand_strings_round_trip() {
+        let encoded = encode_string(SERVER_BRAND);
+        assert_eq!(encoded[0] as usize, SERVER_BRAND.len());
+        assert_eq!(decode_string(&encoded).as_deref(), Some(SERVER_BRAND));
+        assert_eq!(decode_string(&[5, b'a']), None);
+    }
+}
@@ -42,7 +42,7 @@ use anyhow::{anyhow, Result};
 
 use ultimate_engine::causal::event::{Event, EventPayload, LightCell, LightType};
 use ultimate_engine::world::block::BlockId;
-use ultimate_engine::world::position::{BlockPos, ChunkPos};
+use ultimate_engine::world::position::{BlockPos, ChunkPos, Direction};
 use ultimate_engine::world::World;
 
 use crate::event_bus::{self, ChangeSource, SpatialBus};
@@ -69,49 +69,76 @@ pub fn owner_node(chunk: ChunkPos, total_nodes: u32) -> u32 {
 fn put_i64(buf: &mut Vec<u8>, v: i64) {
     buf.extend_from_slice(&v.to_le_bytes());
 }
-fn put_u16(buf: &mut Vec<u8>, v: u16) {
+pub(crate) fn put_u16(buf: &mut Vec<u8>, v: u16) {
     buf.extend_from_slice(&v.to_le_bytes());
 }
-fn put_pos(buf: &mut Vec<u8>, p: BlockPos) {
+pub(crate) fn put_pos(buf: &mut Vec<u8>, p: BlockPos) {
     put_i64(buf, p.x);
     put_i64(buf, p.y);
     put_i64(buf, p.z);
 }
 
-struct Reader<'a> {
+pub(crate) struct Reader<'a> {
     buf: &'a [u8],
     at: usize,
 }
 impl<'a> Reader<'a> {
-    fn new(buf: &'a [u8]) -> Self {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
         Self { buf, at: 0 }
     }
-    fn u8(&mut self) -> Result<u8> {
+    pub(crate) fn u8(&mut self) -> Result<u8> {
         let v = *self.buf.get(self.at).ok_or_else(|| anyhow!("truncated frame"))?;
         self.at += 1;
         Ok(v)
     }
-    fn u16(&mut self) -> Result<u16> {
+    pub(crate) fn u16(&mut self) -> Result<u16> {
         let s = self.buf.get(self.at..self.at + 2).ok_or_else(|| anyhow!("truncated frame"))?;
         self.at += 2;
         Ok(u16::from_le_bytes(s.try_into().unwrap()))
     }
-    fn u32(&mut self) -> Result<u32> {
+    pub(crate) fn u32(&mut self) -> Result<u32> {
         let s = self.buf.get(self.at..self.at + 4).ok_or_else(|| anyhow!("truncated frame"))?;
         self.at += 4;
         Ok(u32::from_le_bytes(s.try_into().unwrap()))
     }
-    fn u64(&mut self) -> Result<u64> {
+    pub(crate) fn u64(&mut self) -> Result<u64> {
         let s = self.buf.get(self.at..self.at + 8).ok_or_else(|| anyhow!("truncated frame"))?;
         self.at += 8;
         Ok(u64::from_le_bytes(s.try_into().unwrap()))
     }
-    fn i64(&mut self) -> Result<i64> {
+    pub(crate) fn i64(&mut self) -> Result<i64> {
         Ok(self.u64()? as i64)
     }
-    fn pos(&mut self) -> Result<BlockPos> {
+    pub(crate) fn pos(&mut self) -> Result<BlockPos> {
         Ok(BlockPos::new(self.i64()?, self.i64()?, self.i64()?))
     }
+    pub(crate) fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        let s = self.buf.get(self.at..self.at + n).ok_or_else(|| anyhow!("truncated frame"))?;
+        self.at += n;
+        Ok(s)
+    }
+    pub(crate) fn is_done(&self) -> bool {
+        self.at == self.buf.len()
+    }
+}
+
+/// Custom payload kinds are `&'static str`; a decoded kind is interned so
+/// each distinct name is leaked once. Peers run the same rule set, so the
+/// set of kinds on the wire is small and fixed.
+fn intern_kind(name: &str) -> &'static str {
+    static KINDS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+    let mut kinds = KINDS.lock().unwrap();
+    if let Some(kind) = kinds.iter().find(|k| **k == name) {
+        return kind;
+    }
+    let kind: &'static str = Box::leak(name.into());
+    kinds.push(kind);
+    kind
+}
+
+/// Inverse of `dir as u8`; [`Direction::ALL`] is in declaration order.
+fn direction_from_u8(v: u8) -> Result<Direction> {
+    Direction::ALL.get(v as usize).copied().ok_or_else(|| anyhow!("bad direction {v}"))
 }
 
 fn light_type_to_u8(t: LightType) -> u8 {
@@ -128,7 +155,7 @@ fn light_type_from_u8(v: u8) -> Result<LightType> {
     }
 }
 
-fn encode_payload(buf: &mut Vec<u8>, p: &EventPayload) {
+pub(crate) fn encode_payload(buf: &mut Vec<u8>, p: &EventPayload) {
     match p {
         EventPayload::BlockSet { pos, old, new } => {
             buf.push(0);
@@ -136,10 +163,17 @@ fn encode_payload(buf: &mut Vec<u8>, p: &EventPayload) {
             put_u16(buf, old.0);
             put_u16(buf, new.0);
         }
-        EventPayload::BlockNotify { pos } => {
+        EventPayload::BlockNotify { pos, from: None } => {
             buf.push(1);
             put_pos(buf, *pos);
         }
+        // Its own tag so journals written before directed notifies still
+        // decode.
+        EventPayload::BlockNotify { pos, from: Some(dir) } => {
+            buf.push(6);
+            put_pos(buf, *pos);
+            buf.push(*dir as u8);
+        }
         EventPayload::LightSet { pos, light_type, old, new } => {
             buf.push(2);
             put_pos(buf, *pos);
@@ -161,17 +195,25 @@ fn encode_payload(buf: &mut Vec<u8>, p: &EventPayload) {
                 buf.push(c.new);
             }
         }
+        EventPayload::Custom { kind, pos, data } => {
+            buf.push(5);
+            put_u16(buf, kind.len() as u16);
+            buf.extend_from_slice(kind.as_bytes());
+            put_pos(buf, *pos);
+            buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            buf.extend_from_slice(data);
+        }
     }
 }
 
-fn decode_payload(r: &mut Reader) -> Result<EventPayload> {
+pub(crate) fn decode_payload(r: &mut Reader) -> Result<EventPayload> {
     Ok(match r.u8()? {
         0 => EventPayload::BlockSet {
             pos: r.pos()?,
             old: BlockId(r.u16()?),
             new: BlockId(r.u16()?),
         },
-        1 => EventPayload::BlockNotify { pos: r.pos()? },
+        1 => EventPayload::BlockNotify { pos: r.pos()?, from: None },
         2 => EventPayload::LightSet {
             pos: r.pos()?,
             light_type: light_type_from_u8(r.u8()?)?,
@@ -192,6 +234,15 @@ fn decode_payload(r: &mut Reader) -> Result<EventPayload> {
             }
             EventPayload::LightBatch { changes: cells.into() }
         }
+        5 => {
+            let len = r.u16()? as usize;
+            let kind = std::str::from_utf8(r.bytes(len)?).map_err(|_| anyhow!("bad custom kind"))?;
+            let kind = intern_kind(kind);
+            let pos = r.pos()?;
+            let len = r.u32()? as usize;
+            EventPayload::Custom { kind, pos, data: r.bytes(len)?.into() }
+        }
+        6 => EventPayload::BlockNotify { pos: r.pos()?, from: Some(direction_from_u8(r.u8()?)?) },
         other => return Err(anyhow!("bad payload tag {other}")),
     })
 }
@@ -791,7 +842,9 @@ fn apply_replica_writes(world: &World, payloads: &[EventPayload]) {
                     }
                 }
             }
-            EventPayload::BlockNotify { .. } | EventPayload::LightNotify { .. } => {}
+            EventPayload::BlockNotify { .. }
+            | EventPayload::LightNotify { .. }
+            | EventPayload::Custom { .. } => {}
         }
     }
 }
@@ -808,7 +861,8 @@ mod tests {
                 old: BlockId(0),
                 new: BlockId(118),
             },
-            EventPayload::BlockNotify { pos: BlockPos::new(1, -64, -1) },
+            EventPayload::BlockNotify { pos: BlockPos::new(1, -64, -1), from: None },
+            EventPayload::BlockNotify { pos: BlockPos::new(4, 80, 2), from: Some(Direction::East) },
             EventPayload::LightSet {
                 pos: BlockPos::new(0, 0, 0),
                 light_type: LightType::Block,
@@ -833,6 +887,11 @@ mod tests {
                 ]
                 .into(),
             },
+            EventPayload::Custom {
+                kind: "weather",
+                pos: BlockPos::new(-3, 100, 12),
+                data: vec![1, 2, 3].into(),
+            },
         ];
 
         let mut buf = Vec::new();
@@ -845,6 +904,7 @@ mod tests {
             assert_eq!(format!("{expect:?}"), format!("{got:?}"));
         }
         assert_eq!(r.at, buf.len(), "codec must consume exactly what it wrote");
+        assert!(std::ptr::eq(intern_kind("weather"), intern_kind(&String::from("weather"))));
     }
 
     #[test]
@@ -0,0 +1,156 @@
+//! Player-vs-player combat: melee damage, attack cooldown, knockback,
+//! and the health that runs out.
+//!
+//! The attacker's connection validates a hit (PvP on, target in reach,
+//! both in modes that fight) and sends it to the target's connection as
+//! `PlayerEvent::Attacked`; health lives with the player it belongs to,
+//! so only that connection applies the hit, pushes its client back, and
+//! reports the result to everyone (`Hurt`, `Died`). Creative and
+//! spectator players neither deal nor take damage, as in vanilla.
+//!
+//! Damage follows vanilla: the held weapon's base damage, scaled down
+//! when the attacker swings again before the weapon's cooldown has
+//! recovered ([`damage`]); a player hit again within
+//! [`INVULNERABILITY`] only takes what the new hit exceeds the last by.
+
+use std::time::{Duration, Instant};
+
+use azalea_core::game_type::GameMode;
+use azalea_core::position::Vec3;
+use azalea_registry::builtin::ItemKind;
+
+/// Health of a fresh player.
+pub const MAX_HEALTH: f32 = 20.0;
+
+/// Farthest an attack lands from, eye to feet, in blocks (vanilla's
+/// server-side allowance).
+pub const REACH: f64 = 6.0;
+
+/// After a hit, further hits only deal what exceeds it for this long.
+pub const INVULNERABILITY: Duration = Duration::from_millis(500);
+
+/// Horizontal push of a hit, in blocks per tick.
+const KNOCKBACK: f64 = 0.4;
+
+/// Whether a player in `mode` fights at all.
+pub fn fights(mode: GameMode) -> bool {
+    matches!(mode, GameMode::Survival | GameMode::Adventure)
+}
+
+/// Base damage and attack speed (swings per second) of `held`; anything
+/// that isn't a weapon hits like a fist.
+fn weapon(held: ItemKind) -> (f32, f64) {
+    match held {
+        ItemKind::WoodenSword | ItemKind::GoldenSword => (4.0, 1.6),
+        ItemKind::StoneSword => (5.0, 1.6),
+        ItemKind::IronSword => (6.0, 1.6),
+        ItemKind::DiamondSword => (7.0, 1.6),
+        ItemKind::NetheriteSword => (8.0, 1.6),
+        ItemKind::WoodenAxe => (7.0, 0.8),
+        ItemKind::GoldenAxe => (7.0, 1.0),
+        ItemKind::StoneAxe => (9.0, 0.8),
+        ItemKind::IronAxe => (9.0, 0.9),
+        ItemKind::DiamondAxe => (9.0, 1.0),
+        ItemKind::NetheriteAxe => (10.0, 1.0),
+        ItemKind::Trident => (9.0, 1.1),
+        _ => (1.0, 4.0),
+    }
+}
+
+/// Damage of a hit with `held`, `since` the attacker's previous attack
+/// (`None` for a first swing): full once the weapon has recovered, down to
+/// a fifth when swung again at once.
+pub fn damage(held: ItemKind, since: Option<Duration>) -> f32 {
+    let (base, speed) = weapon(held);
+    let recovered = match since {
+        None => 1.0,
+        Some(since) => ((since.as_secs_f64() + 0.025) * speed).clamp(0.0, 1.0) as f32,
+    };
+    base * (0.2 + recovered * recovered * 0.8)
+}
+
+/// Velocity a hit from `from` gives a player standing at `at`: pushed
+/// away horizontally, and up off the ground.
+pub fn knockback(from: [f64; 3], at: [f64; 3], on_ground: bool) -> Vec3 {
+    let (dx, dz) = (at[0] - from[0], at[2] - from[2]);
+    let len = dx.hypot(dz);
+    let (nx, nz) = if len < 1e-4 { (0.0, 0.0) } else { (dx / len, dz / len) };
+    Vec3 { x: nx * KNOCKBACK, y: if on_ground { KNOCKBACK } else { 0.0 }, z: nz * KNOCKBACK }
+}
+
+/// What a hit did.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hit {
+    /// Absorbed by invulnerability or already dead.
+    Ignored,
+    /// Took damage; the health left.
+    Hurt(f32),
+    Killed,
+}
+
+/// A player's health.
+#[derive(Debug, Clone)]
+pub struct Health {
+    pub health: f32,
+    /// The last damaging hit, for invulnerability.
+    last_hurt: Option<(Instant, f32)>,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self { health: MAX_HEALTH, last_hurt: None }
+    }
+}
+
+impl Health {
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0.0
+    }
+
+    /// Take `damage` at `now`.
+    pub fn hit(&mut self, damage: f32, now: Instant) -> Hit {
+        if self.is_dead() {
+            return Hit::Ignored;
+        }
+        let dealt = match self.last_hurt {
+            Some((at, last)) if now.duration_since(at) < INVULNERABILITY => {
+                if damage <= last {
+                    return Hit::Ignored;
+                }
+                damage - last
+            }
+            _ => damage,
+        };
+        self.last_hurt = Some((now, damage));
+        self.health = (self.health - dealt).max(0.0);
+        if self.is_dead() { Hit::Killed } else { Hit::Hurt(self.health) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cooldown_scales_damage() {
+        assert_eq!(damage(ItemKind::DiamondSword, None), 7.0);
+        assert_eq!(damage(ItemKind::DiamondSword, Some(Duration::from_secs(1))), 7.0);
+        let spammed = damage(ItemKind::DiamondSword, Some(Duration::ZERO));
+        assert!(spammed < 2.0, "a spammed sword hits for a fraction: {spammed}");
+        assert_eq!(damage(ItemKind::Stone, None), 1.0, "blocks hit like a fist");
+    }
+
+    #[test]
+    fn test_invulnerability_and_death() {
+        let start = Instant::now();
+        let mut health = Health::default();
+        assert_eq!(health.hit(6.0, start), Hit::Hurt(14.0));
+        assert_eq!(health.hit(4.0, start + Duration::from_millis(100)), Hit::Ignored);
+        assert_eq!(health.hit(8.0, start + Duration::from_millis(200)), Hit::Hurt(12.0), "only the excess");
+        assert_eq!(health.hit(20.0, start + Duration::from_secs(1)), Hit::Killed);
+        assert_eq!(health.hit(1.0, start + Duration::from_secs(2)), Hit::Ignored);
+
+        let push = knockback([0.0, 0.0, 0.0], [3.0, 0.0, 4.0], true);
+        assert!((push.x - 0.24).abs() < 1e-9 && (push.z - 0.32).abs() < 1e-9 && push.y > 0.0);
+    }
+}
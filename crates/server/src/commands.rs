@@ -0,0 +1,1390 @@
+//! Slash-command dispatcher.
+//!
+//! Connections forward every `ChatCommand` packet here and relay the
+//! returned lines to the sender as system chat. Commands are plain async
+//! functions over a borrowed [`CommandContext`]; anything slow (disk I/O,
+//! whole-world scans) runs on the blocking pool ([`crate::pools`]) so the
+//! connection's keep-alives keep flowing.
+//!
+//! Every command declares the permission level it needs and its argument
+//! shapes in [`COMMANDS`]; the dispatcher checks the level against the
+//! sender's op level (see [`crate::access`]) before running anything. The
+//! same table builds the Brigadier tree sent at join ([`command_tree`]) and
+//! answers the client's tab-completion requests ([`complete`]).
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use azalea_protocol::packets::game::c_commands::{
+    BrigadierNodeStub, BrigadierParser, BrigadierString, ClientboundCommands, NodeType,
+};
+use azalea_registry::identifier::Identifier;
+use ultimate_engine::world::World;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::position::BlockPos;
+use uuid::Uuid;
+
+use crate::access::{AccessLists, DEFAULT_OP_LEVEL};
+use crate::config::ServerConfig;
+use crate::edit::EditSession;
+use crate::persistence::WorldStorage;
+use crate::physics::PhysicsHandle;
+use crate::player_registry::PlayerRegistry;
+use crate::pools::Pools;
+use crate::worldborder::WorldBorder;
+
+/// One argument in a command's syntax.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arg {
+    /// A fixed subcommand word.
+    Literal(&'static str),
+    /// A player name; online players are suggested, any name is accepted.
+    Player,
+    /// A block id such as `stone` or `minecraft:stone`.
+    Block,
+    /// Three block coordinates (`~` allowed).
+    Coords,
+    /// One word with no suggestions, shown as `<label>`.
+    Word(&'static str),
+    /// Free text to the end of the line, shown as `<label>`.
+    Text(&'static str),
+}
+
+/// A command: its name, the permission level required to run it, and every
+/// argument list it accepts (optional trailing arguments are spelled out
+/// as separate usages).
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub level: u8,
+    pub usages: &'static [&'static [Arg]],
+}
+
+/// Every command the server understands.
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec { name: "/pos1", level: 2, usages: &[&[], &[Arg::Coords]] },
+    CommandSpec { name: "/pos2", level: 2, usages: &[&[], &[Arg::Coords]] },
+    CommandSpec { name: "/replace", level: 2, usages: &[&[Arg::Block, Arg::Block]] },
+    CommandSpec { name: "/set", level: 2, usages: &[&[Arg::Block]] },
+    CommandSpec { name: "/undo", level: 2, usages: &[&[]] },
+    CommandSpec { name: "ban", level: 3, usages: &[&[Arg::Player], &[Arg::Player, Arg::Text("reason")]] },
+    CommandSpec { name: "claim", level: 0, usages: &[&[Arg::Word("name")]] },
+    CommandSpec { name: "deop", level: 3, usages: &[&[Arg::Player]] },
+    CommandSpec {
+        name: "gamemode",
+        level: 2,
+        usages: &[
+            &[Arg::Literal("adventure")],
+            &[Arg::Literal("adventure"), Arg::Player],
+            &[Arg::Literal("creative")],
+            &[Arg::Literal("creative"), Arg::Player],
+            &[Arg::Literal("spectator")],
+            &[Arg::Literal("spectator"), Arg::Player],
+            &[Arg::Literal("survival")],
+            &[Arg::Literal("survival"), Arg::Player],
+        ],
+    },
+    CommandSpec {
+        name: "gamerule",
+        level: 2,
+        usages: &[
+            &[],
+            &[Arg::Literal("doDaylightCycle")],
+            &[Arg::Literal("doDaylightCycle"), Arg::Word("value")],
+            &[Arg::Literal("doFireTick")],
+            &[Arg::Literal("doFireTick"), Arg::Word("value")],
+            &[Arg::Literal("doMobSpawning")],
+            &[Arg::Literal("doMobSpawning"), Arg::Word("value")],
+            &[Arg::Literal("doWeatherCycle")],
+            &[Arg::Literal("doWeatherCycle"), Arg::Word("value")],
+            &[Arg::Literal("keepInventory")],
+            &[Arg::Literal("keepInventory"), Arg::Word("value")],
+            &[Arg::Literal("lavaSourceConversion")],
+            &[Arg::Literal("lavaSourceConversion"), Arg::Word("value")],
+            &[Arg::Literal("mobGriefing")],
+            &[Arg::Literal("mobGriefing"), Arg::Word("value")],
+            &[Arg::Literal("randomTickSpeed")],
+            &[Arg::Literal("randomTickSpeed"), Arg::Word("value")],
+            &[Arg::Literal("spawnRadius")],
+            &[Arg::Literal("spawnRadius"), Arg::Word("value")],
+            &[Arg::Literal("waterSourceConversion")],
+            &[Arg::Literal("waterSourceConversion"), Arg::Word("value")],
+        ],
+    },
+    CommandSpec { name: "history", level: 2, usages: &[&[Arg::Coords]] },
+    CommandSpec { name: "home", level: 0, usages: &[&[]] },
+    CommandSpec { name: "op", level: 3, usages: &[&[Arg::Player]] },
+    CommandSpec { name: "pardon", level: 3, usages: &[&[Arg::Player]] },
+    CommandSpec { name: "profile", level: 4, usages: &[&[Arg::Literal("start")], &[Arg::Literal("stop")]] },
+    CommandSpec {
+        name: "region",
+        level: 0,
+        usages: &[
+            &[Arg::Literal("list")],
+            &[Arg::Literal("info")],
+            &[Arg::Literal("define"), Arg::Word("name")],
+            &[Arg::Literal("remove"), Arg::Word("name")],
+            &[Arg::Literal("addmember"), Arg::Word("name"), Arg::Player],
+            &[Arg::Literal("removemember"), Arg::Word("name"), Arg::Player],
+        ],
+    },
+    CommandSpec { name: "reloadrules", level: 3, usages: &[&[]] },
+    CommandSpec {
+        name: "schem",
+        level: 2,
+        usages: &[
+            &[Arg::Literal("list")],
+            &[Arg::Literal("save"), Arg::Word("name"), Arg::Coords, Arg::Coords],
+            &[Arg::Literal("paste"), Arg::Word("name")],
+            &[Arg::Literal("paste"), Arg::Word("name"), Arg::Coords],
+        ],
+    },
+    CommandSpec {
+        name: "scoreboard",
+        level: 2,
+        usages: &[
+            &[Arg::Literal("objectives"), Arg::Literal("list")],
+            &[Arg::Literal("objectives"), Arg::Literal("add"), Arg::Word("objective"), Arg::Word("criterion")],
+            &[
+                Arg::Literal("objectives"),
+                Arg::Literal("add"),
+                Arg::Word("objective"),
+                Arg::Word("criterion"),
+                Arg::Text("displayName"),
+            ],
+            &[Arg::Literal("objectives"), Arg::Literal("remove"), Arg::Word("objective")],
+            &[Arg::Literal("objectives"), Arg::Literal("setdisplay"), Arg::Literal("below_name")],
+            &[Arg::Literal("objectives"), Arg::Literal("setdisplay"), Arg::Literal("below_name"), Arg::Word("objective")],
+            &[Arg::Literal("objectives"), Arg::Literal("setdisplay"), Arg::Literal("list")],
+            &[Arg::Literal("objectives"), Arg::Literal("setdisplay"), Arg::Literal("list"), Arg::Word("objective")],
+            &[Arg::Literal("objectives"), Arg::Literal("setdisplay"), Arg::Literal("sidebar")],
+            &[Arg::Literal("objectives"), Arg::Literal("setdisplay"), Arg::Literal("sidebar"), Arg::Word("objective")],
+            &[Arg::Literal("players"), Arg::Literal("list"), Arg::Player],
+            &[Arg::Literal("players"), Arg::Literal("set"), Arg::Player, Arg::Word("objective"), Arg::Word("score")],
+            &[Arg::Literal("players"), Arg::Literal("add"), Arg::Player, Arg::Word("objective"), Arg::Word("score")],
+            &[Arg::Literal("players"), Arg::Literal("reset"), Arg::Player],
+            &[Arg::Literal("players"), Arg::Literal("reset"), Arg::Player, Arg::Word("objective")],
+        ],
+    },
+    CommandSpec { name: "sethome", level: 0, usages: &[&[]] },
+    CommandSpec { name: "spawn", level: 0, usages: &[&[]] },
+    CommandSpec { name: "tpa", level: 0, usages: &[&[Arg::Player]] },
+    CommandSpec { name: "tpaccept", level: 0, usages: &[&[], &[Arg::Player]] },
+    CommandSpec { name: "tps", level: 0, usages: &[&[]] },
+    CommandSpec { name: "trim", level: 4, usages: &[&[]] },
+    CommandSpec {
+        name: "whitelist",
+        level: 3,
+        usages: &[
+            &[Arg::Literal("on")],
+            &[Arg::Literal("off")],
+            &[Arg::Literal("list")],
+            &[Arg::Literal("reload")],
+            &[Arg::Literal("add"), Arg::Player],
+            &[Arg::Literal("remove"), Arg::Player],
+        ],
+    },
+    CommandSpec {
+        name: "worldborder",
+        level: 2,
+        usages: &[
+            &[Arg::Literal("get")],
+            &[Arg::Literal("set"), Arg::Word("distance")],
+            &[Arg::Literal("set"), Arg::Word("distance"), Arg::Word("seconds")],
+            &[Arg::Literal("add"), Arg::Word("distance")],
+            &[Arg::Literal("add"), Arg::Word("distance"), Arg::Word("seconds")],
+            &[Arg::Literal("center"), Arg::Word("x"), Arg::Word("z")],
+        ],
+    },
+];
+
+/// Sender UUID recorded for console commands (RCON) in the audit log.
+/// It grants nothing: a UUID can arrive from the network (a forwarding
+/// proxy names it), so console rights come only from
+/// [`CommandContext::is_console`].
+pub const CONSOLE_UUID: Uuid = Uuid::nil();
+
+/// Commands that act on the sender as a player (their position, home,
+/// selection), which the console can't run.
+const PLAYER_ONLY: &[&str] =
+    &["/pos1", "/pos2", "/replace", "/set", "/undo", "claim", "home", "sethome", "spawn", "tpa", "tpaccept"];
+
+/// Entries `/history` shows.
+const HISTORY_LINES: usize = 10;
+
+/// Server state a command may touch, borrowed from the calling connection.
+pub struct CommandContext<'a> {
+    pub world: &'a Arc<World>,
+    pub storage: &'a Arc<WorldStorage>,
+    pub registry: &'a PlayerRegistry,
+    pub access: &'a AccessLists,
+    pub pools: &'a Pools,
+    pub physics: &'a PhysicsHandle,
+    pub config: &'a ServerConfig,
+    pub border: &'a WorldBorder,
+    /// The sender's region selection and undo history.
+    pub edit: &'a Mutex<EditSession>,
+    /// Name and UUID of the player who issued the command.
+    pub sender: &'a str,
+    pub sender_uuid: Uuid,
+    /// The sender's block position, which `~` coordinates are relative to.
+    pub sender_pos: [i64; 3],
+    /// Set only by [`Console`]: the sender is the console, which runs
+    /// everything at the top permission level and is never a player.
+    pub is_console: bool,
+}
+
+/// Run one command line (without the leading `/`) and return the reply
+/// lines for the sender.
+pub async fn dispatch(ctx: &CommandContext<'_>, line: &str) -> Vec<String> {
+    let mut parts = line.split_whitespace();
+    let Some(name) = parts.next() else {
+        return vec![];
+    };
+    let args: Vec<&str> = parts.collect();
+    tracing::info!("{} issued server command: /{}", ctx.sender, line);
+    ctx.registry.audit.record(ctx.sender_uuid, ctx.sender, crate::audit::Action::Command { line: line.to_owned() });
+
+    let Some(spec) = COMMANDS.iter().find(|c| c.name == name) else {
+        return vec![format!("Unknown command: /{}", name)];
+    };
+    let console = ctx.is_console;
+    if !console && ctx.access.permission_level(ctx.sender_uuid) < spec.level {
+        return vec!["You do not have permission to use this command.".into()];
+    }
+    if console && PLAYER_ONLY.contains(&name) {
+        return vec![format!("/{} can only be run by a player", name)];
+    }
+
+    match name {
+        "trim" => trim(ctx, &args).await,
+        "op" => op(ctx, &args),
+        "deop" => deop(ctx, &args),
+        "ban" => ban(ctx, &args),
+        "claim" => claim(ctx, &args),
+        "gamemode" => gamemode(ctx, &args),
+        "gamerule" => gamerule(ctx, &args),
+        "pardon" => pardon(ctx, &args),
+        "profile" => profile(ctx, &args).await,
+        "region" => region(ctx, &args),
+        "reloadrules" => reloadrules(&args),
+        "whitelist" => whitelist(ctx, &args),
+        "worldborder" => worldborder(ctx, &args),
+        "schem" => schem(ctx, &args).await,
+        "scoreboard" => scoreboard(ctx, &args),
+        "spawn" => spawn(ctx, &args),
+        "sethome" => sethome(ctx, &args),
+        "history" => history(ctx, &args).await,
+        "home" => home(ctx, &args),
+        "tpa" => tpa(ctx, &args),
+        "tpaccept" => tpaccept(ctx, &args),
+        "tps" => tps(ctx, &args),
+        "/pos1" => select(ctx, &args, 1),
+        "/pos2" => select(ctx, &args, 2),
+        "/set" => set(ctx, &args).await,
+        "/replace" => replace(ctx, &args).await,
+        "/undo" => undo(ctx, &args).await,
+        _ => unreachable!("command {name} listed in COMMANDS without a handler"),
+    }
+}
+
+/// Owned handles for running commands from outside a connection (RCON):
+/// everything a [`CommandContext`] borrows, with the console as sender.
+pub struct Console {
+    pub world: Arc<World>,
+    pub storage: Arc<WorldStorage>,
+    pub registry: Arc<PlayerRegistry>,
+    pub access: Arc<AccessLists>,
+    pub pools: Arc<Pools>,
+    pub physics: PhysicsHandle,
+    pub config: Arc<ServerConfig>,
+    pub border: Arc<WorldBorder>,
+}
+
+impl Console {
+    /// Run `line` (leading `/` optional) as the console named `sender`.
+    pub async fn execute(&self, sender: &str, line: &str) -> Vec<String> {
+        let edit = Mutex::new(EditSession::default());
+        let ctx = CommandContext {
+            world: &self.world,
+            storage: &self.storage,
+            registry: &self.registry,
+            access: &self.access,
+            pools: &self.pools,
+            physics: &self.physics,
+            config: &self.config,
+            border: &self.border,
+            edit: &edit,
+            sender,
+            sender_uuid: CONSOLE_UUID,
+            sender_pos: [0, 0, 0],
+            is_console: true,
+        };
+        dispatch(&ctx, line.strip_prefix('/').unwrap_or(line)).await
+    }
+}
+
+/// The Brigadier command tree for a sender at `level`: only commands they
+/// may run, with every non-literal argument asking the server for
+/// suggestions.
+pub fn command_tree(level: u8) -> ClientboundCommands {
+    let mut nodes = vec![node(NodeType::Root)];
+    for spec in COMMANDS.iter().filter(|c| c.level <= level) {
+        let literal = child(&mut nodes, 0, NodeType::Literal { name: spec.name.into() });
+        for usage in spec.usages {
+            let mut at = literal;
+            for arg in *usage {
+                at = child(&mut nodes, at, arg_node(*arg));
+            }
+            nodes[at].is_executable = true;
+        }
+    }
+    ClientboundCommands { entries: nodes, root_index: 0 }
+}
+
+fn node(node_type: NodeType) -> BrigadierNodeStub {
+    BrigadierNodeStub {
+        is_executable: false,
+        children: Vec::new(),
+        redirect_node: None,
+        node_type,
+        is_restricted: false,
+    }
+}
+
+/// Index of `parent`'s child of this type, created if missing, so usages
+/// sharing a prefix share nodes.
+fn child(nodes: &mut Vec<BrigadierNodeStub>, parent: usize, node_type: NodeType) -> usize {
+    if let Some(&existing) = nodes[parent]
+        .children
+        .iter()
+        .find(|&&c| nodes[c as usize].node_type == node_type)
+    {
+        return existing as usize;
+    }
+    nodes.push(node(node_type));
+    let index = nodes.len() - 1;
+    nodes[parent].children.push(index as u32);
+    index
+}
+
+fn arg_node(arg: Arg) -> NodeType {
+    let ask_server = || Some(Identifier::new("minecraft:ask_server"));
+    let (name, parser, suggestions_type) = match arg {
+        Arg::Literal(name) => return NodeType::Literal { name: name.into() },
+        Arg::Player => ("player", BrigadierParser::String(BrigadierString::SingleWord), ask_server()),
+        Arg::Block => ("block", BrigadierParser::Identifier, ask_server()),
+        Arg::Coords => ("pos", BrigadierParser::BlockPos, ask_server()),
+        Arg::Word(label) => (label, BrigadierParser::String(BrigadierString::SingleWord), None),
+        Arg::Text(label) => (label, BrigadierParser::String(BrigadierString::GreedyPhrase), None),
+    };
+    NodeType::Argument { name: name.into(), parser, suggestions_type }
+}
+
+/// Tab completion for `text` exactly as the client sent it (leading `/`
+/// included). Returns the byte offset the suggestions replace from and the
+/// candidates matching what is typed there. `players` are online names and
+/// `pos` the sender's block position, offered for coordinates.
+pub fn complete(text: &str, level: u8, players: &[String], pos: [i64; 3]) -> (usize, Vec<String>) {
+    let offset = usize::from(text.starts_with('/'));
+    let mut words: Vec<(usize, &str)> = Vec::new();
+    let mut start = offset;
+    for word in text[offset..].split(' ') {
+        words.push((start, word));
+        start += word.len() + 1;
+    }
+    let (partial_at, partial) = *words.last().expect("split yields at least one word");
+    let done: Vec<&str> = words[..words.len() - 1].iter().map(|&(_, w)| w).collect();
+
+    let mut out: Vec<String> = Vec::new();
+    let Some((name, args)) = done.split_first() else {
+        out.extend(
+            COMMANDS
+                .iter()
+                .filter(|c| c.level <= level && c.name.starts_with(partial))
+                .map(|c| c.name.to_owned()),
+        );
+        return (partial_at, out);
+    };
+    let Some(spec) = COMMANDS.iter().find(|c| c.name == *name && c.level <= level) else {
+        return (partial_at, out);
+    };
+
+    for usage in spec.usages {
+        // Walk the typed arguments through this usage to find which
+        // argument (and, for coordinates, which component) is being typed.
+        let mut used = 0;
+        let mut expecting = None;
+        for &arg in *usage {
+            let width = if arg == Arg::Coords { 3 } else { 1 };
+            if used + width > args.len() {
+                expecting = Some((arg, args.len() - used));
+                break;
+            }
+            match arg {
+                Arg::Literal(lit) if args[used] != lit => break,
+                Arg::Text(_) => break,
+                _ => used += width,
+            }
+        }
+        let Some((arg, component)) = expecting else { continue };
+        match arg {
+            Arg::Literal(lit) => out.push(lit.to_owned()),
+            Arg::Player => out.extend(players.iter().cloned()),
+            Arg::Block if partial.contains(':') => out.extend(crate::block::block_names()),
+            Arg::Block => out.extend(
+                crate::block::block_names().map(|n| n.trim_start_matches("minecraft:").to_owned()),
+            ),
+            Arg::Coords => {
+                out.push(vec!["~"; 3 - component].join(" "));
+                out.push(pos[component..].iter().map(i64::to_string).collect::<Vec<_>>().join(" "));
+            }
+            Arg::Word(_) | Arg::Text(_) => {}
+        }
+    }
+
+    let lower = partial.to_lowercase();
+    out.retain(|c| c.to_lowercase().starts_with(&lower));
+    out.sort_unstable();
+    out.dedup();
+    (partial_at, out)
+}
+
+/// Resolve a player argument: an online player's real UUID, else the
+/// offline-mode UUID derived from the name (what they'd get on join).
+fn resolve_player(ctx: &CommandContext<'_>, name: &str) -> (Uuid, String) {
+    match ctx.registry.find_by_name(name) {
+        Some(p) => (p.uuid, p.name),
+        None => (crate::net::connection::offline_uuid(name), name.to_owned()),
+    }
+}
+
+/// Resolve three coordinate words, each absolute (`12`) or relative to
+/// `base`, the sender's position (`~`, `~-3`).
+fn parse_coords(words: &[&str], base: [i64; 3]) -> Option<BlockPos> {
+    let [x, y, z] = words else { return None };
+    let axis = |word: &str, base: i64| match word.strip_prefix('~') {
+        Some("") => Some(base),
+        Some(offset) => offset.parse::<i64>().ok().map(|o| base + o),
+        None => word.parse().ok(),
+    };
+    let [bx, by, bz] = base;
+    Some(BlockPos::new(axis(x, bx)?, axis(y, by)?, axis(z, bz)?))
+}
+
+/// Reply for a failed access-list write.
+fn write_failed(e: anyhow::Error) -> Vec<String> {
+    tracing::error!("Access list update failed: {:#}", e);
+    vec![format!("Failed to save: {:#}", e)]
+}
+
+/// `/trim` — save, then delete saved chunks that regenerate identically.
+async fn trim(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    if !args.is_empty() {
+        return vec!["Usage: /trim".into()];
+    }
+    let world = Arc::clone(ctx.world);
+    let storage = Arc::clone(ctx.storage);
+    match ctx.pools.run_blocking(move || storage.trim(&world)).await {
+        Ok(stats) => vec![format!(
+            "Trimmed {} of {} saved chunks ({} regions rewritten, {} deleted): {:.1} MB -> {:.1} MB",
+            stats.removed,
+            stats.scanned,
+            stats.regions_rewritten,
+            stats.regions_deleted,
+            stats.bytes_before as f64 / 1e6,
+            stats.bytes_after as f64 / 1e6,
+        )],
+        Err(e) => {
+            tracing::error!("Trim failed: {:#}", e);
+            vec![format!("Trim failed: {:#}", e)]
+        }
+    }
+}
+
+/// `/profile <start|stop>` — record tracing spans; needs `--profile`.
+async fn profile(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let Some(profiler) = crate::profiling::profiler() else {
+        return vec!["Profiling is off; start the server with --profile".into()];
+    };
+    match args {
+        ["start"] if profiler.start() => vec!["Profiling started; /profile stop to write it out".into()],
+        ["start"] => vec!["Already profiling".into()],
+        ["stop"] => match ctx.pools.run_blocking(move || profiler.stop()).await {
+            Ok(Some(report)) => {
+                let mut lines = vec![format!(
+                    "Recorded {} spans to {} and {}",
+                    report.spans,
+                    report.trace.display(),
+                    report.folded.display(),
+                )];
+                if report.dropped > 0 {
+                    lines.push(format!("{} more spans were dropped past the limit", report.dropped));
+                }
+                lines
+            }
+            Ok(None) => vec!["Not profiling; /profile start first".into()],
+            Err(e) => {
+                tracing::error!("Writing the profile failed: {:#}", e);
+                vec![format!("Writing the profile failed: {:#}", e)]
+            }
+        },
+        _ => vec!["Usage: /profile <start|stop>".into()],
+    }
+}
+
+/// `/op <player>`
+fn op(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let [target] = args else {
+        return vec!["Usage: /op <player>".into()];
+    };
+    let (uuid, name) = resolve_player(ctx, target);
+    match ctx.access.op(uuid, &name, DEFAULT_OP_LEVEL) {
+        Ok(true) => vec![format!("Made {} a server operator", name)],
+        Ok(false) => vec!["Nothing changed. The player already is an operator".into()],
+        Err(e) => write_failed(e),
+    }
+}
+
+/// `/deop <player>`
+fn deop(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let [target] = args else {
+        return vec!["Usage: /deop <player>".into()];
+    };
+    let (uuid, name) = resolve_player(ctx, target);
+    match ctx.access.deop(uuid) {
+        Ok(true) => vec![format!("Made {} no longer a server operator", name)],
+        Ok(false) => vec!["Nothing changed. The player is not an operator".into()],
+        Err(e) => write_failed(e),
+    }
+}
+
+/// `/ban <player> [reason…]` — also disconnects the player if online.
+fn ban(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let Some((target, reason)) = args.split_first() else {
+        return vec!["Usage: /ban <player> [reason]".into()];
+    };
+    let (uuid, name) = resolve_player(ctx, target);
+    let reason = if reason.is_empty() {
+        "Banned by an operator.".to_owned()
+    } else {
+        reason.join(" ")
+    };
+    match ctx.access.ban(uuid, &name, ctx.sender, &reason) {
+        Ok(true) => {
+            ctx.registry.kick(uuid, &format!("You are banned from this server.\nReason: {}", reason));
+            vec![format!("Banned {}: {}", name, reason)]
+        }
+        Ok(false) => vec!["Nothing changed. The player is already banned".into()],
+        Err(e) => write_failed(e),
+    }
+}
+
+/// `/pardon <player>`
+fn pardon(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let [target] = args else {
+        return vec!["Usage: /pardon <player>".into()];
+    };
+    let (uuid, name) = resolve_player(ctx, target);
+    match ctx.access.pardon(uuid) {
+        Ok(true) => vec![format!("Unbanned {}", name)],
+        Ok(false) => vec!["Nothing changed. The player isn't banned".into()],
+        Err(e) => write_failed(e),
+    }
+}
+
+/// `/reloadrules`: re-read `rules.toml`, and the rule scripts if any
+/// changed. Cascades already running finish under the old rules; a file
+/// that doesn't parse leaves them in place.
+fn reloadrules(args: &[&str]) -> Vec<String> {
+    if !args.is_empty() {
+        return vec!["Usage: /reloadrules".into()];
+    }
+    let mut out = match crate::rules::config::reload() {
+        Ok(summary) => {
+            tracing::info!("Rules reloaded: {}", summary);
+            vec![format!("Reloaded rules: {}", summary)]
+        }
+        Err(e) => vec![format!("Rules not reloaded: {:#}", e)],
+    };
+    match crate::rules::scripts::reload_if_changed() {
+        Ok(Some(summary)) => {
+            tracing::info!("Scripts reloaded: {}", summary);
+            out.push(format!("Reloaded scripts: {}", summary));
+        }
+        Ok(None) => {}
+        Err(e) => out.push(format!("Scripts not reloaded: {:#}", e)),
+    }
+    out
+}
+
+/// `/gamemode <survival|creative|adventure|spectator> [player]`
+fn gamemode(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    const USAGE: &str = "Usage: /gamemode <survival|creative|adventure|spectator> [player]";
+    let (mode, target) = match args {
+        [mode] => (mode, None),
+        [mode, player] => (mode, Some(player)),
+        _ => return vec![USAGE.into()],
+    };
+    let Some(mode) = crate::gamemode::parse(mode) else {
+        return vec![USAGE.into()];
+    };
+    let mode_name = crate::gamemode::display_name(mode);
+    match target {
+        None => {
+            ctx.registry.set_game_mode(ctx.sender_uuid, mode);
+            vec![format!("Set own game mode to {}", mode_name)]
+        }
+        Some(name) => match ctx.registry.find_by_name(name) {
+            Some(p) => {
+                ctx.registry.set_game_mode(p.uuid, mode);
+                vec![format!("Set {}'s game mode to {}", p.name, mode_name)]
+            }
+            None => vec![format!("No player was found called {}", name)],
+        },
+    }
+}
+
+/// `/gamerule [rule [value]]`: list every rule, show one or set it (see
+/// [`crate::gamerules`]).
+fn gamerule(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    use crate::gamerules::GameRule;
+    let rules = ctx.storage.level_info().game_rules;
+    let current = |rule: GameRule| rules.get(rule.name()).cloned().unwrap_or_else(|| rule.default_string());
+    let (name, value) = match args {
+        [] => return GameRule::ALL.iter().map(|&rule| format!("{} = {}", rule.name(), current(rule))).collect(),
+        [name] => (name, None),
+        [name, value] => (name, Some(value)),
+        _ => return vec!["Usage: /gamerule [rule [value]]".into()],
+    };
+    let Some(rule) = GameRule::parse(name) else {
+        return vec![format!("Unknown gamerule: {}", name)];
+    };
+    match value {
+        None => vec![format!("Gamerule {} is currently set to: {}", rule.name(), current(rule))],
+        Some(value) => match ctx.storage.set_game_rule(rule, value) {
+            Ok(value) => vec![format!("Gamerule {} is now set to: {}", rule.name(), value)],
+            Err(e) => vec![format!("Invalid value for {}: {:#}", rule.name(), e)],
+        },
+    }
+}
+
+/// `/spawn`: back to the world spawn.
+fn spawn(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    if !args.is_empty() {
+        return vec!["Usage: /spawn".into()];
+    }
+    let [x, y, z] = ctx.storage.level_info().spawn;
+    ctx.registry.teleport(ctx.sender_uuid, [x as f64 + 0.5, y as f64, z as f64 + 0.5]);
+    vec!["Teleporting to spawn".into()]
+}
+
+/// `/sethome`: remember the sender's position for `/home`.
+fn sethome(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    if !args.is_empty() {
+        return vec!["Usage: /sethome".into()];
+    }
+    let [x, y, z] = ctx.sender_pos;
+    let result = crate::playerdata::load(&ctx.storage.dir, ctx.sender_uuid).and_then(|mut data| {
+        data.home = Some([x as f64 + 0.5, y as f64, z as f64 + 0.5]);
+        crate::playerdata::save(&ctx.storage.dir, ctx.sender_uuid, &data)
+    });
+    match result {
+        Ok(()) => vec![format!("Home set to {} {} {}", x, y, z)],
+        Err(e) => {
+            tracing::warn!("saving {}'s home: {:#}", ctx.sender, e);
+            vec!["Could not save your home".into()]
+        }
+    }
+}
+
+/// `/home`: back to where `/sethome` was used.
+fn home(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    if !args.is_empty() {
+        return vec!["Usage: /home".into()];
+    }
+    match crate::playerdata::load(&ctx.storage.dir, ctx.sender_uuid) {
+        Ok(data) => match data.home {
+            Some(home) => {
+                ctx.registry.teleport(ctx.sender_uuid, home);
+                vec!["Teleporting home".into()]
+            }
+            None => vec!["You have no home; set one with /sethome".into()],
+        },
+        Err(e) => {
+            tracing::warn!("loading {}'s home: {:#}", ctx.sender, e);
+            vec!["Could not load your home".into()]
+        }
+    }
+}
+
+/// `/history <x> <y> <z>`: who placed and broke the block there, newest
+/// first, from the audit log.
+async fn history(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let Some(pos) = parse_coords(args, ctx.sender_pos) else {
+        return vec!["Usage: /history <x> <y> <z>".into()];
+    };
+    let Some(dir) = ctx.registry.audit.dir() else {
+        return vec!["The audit log is off (audit.enabled)".into()];
+    };
+    ctx.registry.audit.flush().await;
+    let at = [pos.x, pos.y, pos.z];
+    match ctx.pools.run_blocking(move || crate::audit::history(&dir, at, HISTORY_LINES)).await {
+        Ok(entries) if entries.is_empty() => vec![format!("No recorded changes at {} {} {}", pos.x, pos.y, pos.z)],
+        Ok(entries) => {
+            let now = crate::audit::now_ms();
+            let mut lines = vec![format!("Changes at {} {} {}, newest first:", pos.x, pos.y, pos.z)];
+            lines.extend(entries.iter().map(|e| e.describe(now)));
+            lines
+        }
+        Err(e) => {
+            tracing::error!("Audit log lookup failed: {:#}", e);
+            vec![format!("Lookup failed: {:#}", e)]
+        }
+    }
+}
+
+/// `/tpa <player>`: ask to teleport to another player.
+fn tpa(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let [name] = args else {
+        return vec!["Usage: /tpa <player>".into()];
+    };
+    let Some(target) = ctx.registry.find_by_name(name) else {
+        return vec![format!("No player was found called {}", name)];
+    };
+    if target.uuid == ctx.sender_uuid {
+        return vec!["You can't teleport to yourself".into()];
+    }
+    if !ctx.registry.teleports.request(ctx.sender_uuid, target.uuid, Instant::now()) {
+        return vec![format!("You already asked to teleport to {}", target.name)];
+    }
+    ctx.registry.tell(
+        target.uuid,
+        &format!("{} asks to teleport to you; type /tpaccept to accept", ctx.sender),
+    );
+    vec![format!("Asked to teleport to {}", target.name)]
+}
+
+/// `/tpaccept [player]`: bring the player who asked (the latest, without
+/// a name) to the sender.
+fn tpaccept(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let from = match args {
+        [] => None,
+        [name] => match ctx.registry.find_by_name(name) {
+            Some(p) => Some(p.uuid),
+            None => return vec![format!("No player was found called {}", name)],
+        },
+        _ => return vec!["Usage: /tpaccept [player]".into()],
+    };
+    let Some(from) = ctx.registry.teleports.accept(ctx.sender_uuid, from, Instant::now()) else {
+        return vec!["You have no pending teleport requests".into()];
+    };
+    let players = ctx.registry.snapshot();
+    let (Some(requester), Some(me)) = (
+        players.iter().find(|p| p.uuid == from),
+        players.iter().find(|p| p.uuid == ctx.sender_uuid),
+    ) else {
+        return vec!["That player is no longer online".into()];
+    };
+    ctx.registry.teleport(from, [me.x, me.y, me.z]);
+    ctx.registry.tell(from, &format!("{} accepted your teleport request", ctx.sender));
+    vec![format!("Teleporting {} to you", requester.name)]
+}
+
+/// `/tps`: the server's tick rate, as the dashboard shows it.
+fn tps(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    if !args.is_empty() {
+        return vec!["Usage: /tps".into()];
+    }
+    let stats = ctx.registry.ticks.stats();
+    vec![format!("TPS: {:.1} ({:.2} ms per tick)", stats.tps, stats.mspt)]
+}
+
+/// `/whitelist <on|off|list|reload|add <player>|remove <player>>`
+fn whitelist(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    match args {
+        ["on"] => {
+            ctx.access.set_whitelist_enforced(true);
+            vec!["Whitelist is now turned on".into()]
+        }
+        ["off"] => {
+            ctx.access.set_whitelist_enforced(false);
+            vec!["Whitelist is now turned off".into()]
+        }
+        ["list"] => {
+            let names = ctx.access.whitelist_names();
+            if names.is_empty() {
+                vec!["There are no whitelisted players".into()]
+            } else {
+                vec![format!("There are {} whitelisted player(s): {}", names.len(), names.join(", "))]
+            }
+        }
+        ["reload"] => match ctx.access.reload_if_changed() {
+            Ok(_) => vec!["Reloaded the whitelist".into()],
+            Err(e) => vec![format!("Reload failed: {:#}", e)],
+        },
+        ["add", target] => {
+            let (uuid, name) = resolve_player(ctx, target);
+            match ctx.access.whitelist_add(uuid, &name) {
+                Ok(true) => vec![format!("Added {} to the whitelist", name)],
+                Ok(false) => vec!["Player is already whitelisted".into()],
+                Err(e) => write_failed(e),
+            }
+        }
+        ["remove", target] => {
+            let (uuid, name) = resolve_player(ctx, target);
+            match ctx.access.whitelist_remove(uuid) {
+                Ok(true) => vec![format!("Removed {} from the whitelist", name)],
+                Ok(false) => vec!["Player is not whitelisted".into()],
+                Err(e) => write_failed(e),
+            }
+        }
+        _ => vec!["Usage: /whitelist <on|off|list|reload|add <player>|remove <player>>".into()],
+    }
+}
+
+/// `/worldborder <get|set <distance> [seconds]|add <distance> [seconds]|center <x> <z>>`
+/// (see [`crate::worldborder`]).
+fn worldborder(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let border = ctx.border;
+    let resize = |diameter: f64, seconds: Option<&&str>| {
+        let over = match seconds {
+            None => std::time::Duration::ZERO,
+            Some(s) => match s.parse::<u64>() {
+                Ok(secs) => std::time::Duration::from_secs(secs),
+                Err(_) => return vec![format!("Invalid number of seconds '{}'", s)],
+            },
+        };
+        let diameter = border.set_diameter(diameter, over);
+        if over.is_zero() {
+            vec![format!("Set the world border to {:.1} block(s) wide", diameter)]
+        } else {
+            vec![format!("Moving the world border to {:.1} block(s) wide over {} second(s)", diameter, over.as_secs())]
+        }
+    };
+    match args {
+        ["get"] => {
+            let [x, z] = border.center();
+            let (to, left) = border.target();
+            let mut out = vec![format!("The world border is {:.1} block(s) wide, centered on {:.1}, {:.1}", border.diameter(), x, z)];
+            if !left.is_zero() {
+                out.push(format!("Moving to {:.1} block(s) wide, {} second(s) left", to, left.as_secs()));
+            }
+            out
+        }
+        ["set", distance, seconds @ ..] if seconds.len() <= 1 => match distance.parse::<f64>() {
+            Ok(d) if d.is_finite() => resize(d, seconds.first()),
+            _ => vec![format!("Invalid distance '{}'", distance)],
+        },
+        ["add", distance, seconds @ ..] if seconds.len() <= 1 => match distance.parse::<f64>() {
+            Ok(d) if d.is_finite() => resize(border.target().0 + d, seconds.first()),
+            _ => vec![format!("Invalid distance '{}'", distance)],
+        },
+        ["center", x, z] => {
+            let axis = |word: &str, base: i64| match word.strip_prefix('~') {
+                Some("") => Some(base as f64 + 0.5),
+                Some(offset) => offset.parse::<f64>().ok().map(|o| base as f64 + 0.5 + o),
+                None => word.parse::<f64>().ok(),
+            };
+            let [bx, _, bz] = ctx.sender_pos;
+            match (axis(x, bx), axis(z, bz)) {
+                (Some(x), Some(z)) if x.is_finite() && z.is_finite() => {
+                    border.set_center(x, z);
+                    vec![format!("Set the center of the world border to {:.1}, {:.1}", x, z)]
+                }
+                _ => vec!["Invalid center coordinates".into()],
+            }
+        }
+        _ => vec!["Usage: /worldborder <get|set <distance> [seconds]|add <distance> [seconds]|center <x> <z>>".into()],
+    }
+}
+
+/// Whether the sender builds in and manages every region.
+fn bypasses_claims(ctx: &CommandContext<'_>) -> bool {
+    ctx.is_console || ctx.access.permission_level(ctx.sender_uuid) >= ctx.config.claims.bypass_level
+}
+
+/// Save the regions after a change, replying `done` if that worked.
+fn save_regions(ctx: &CommandContext<'_>, done: String) -> Vec<String> {
+    match ctx.storage.regions.save() {
+        Ok(()) => vec![done],
+        Err(e) => {
+            tracing::error!("Saving regions failed: {:#}", e);
+            vec![format!("Failed to save: {:#}", e)]
+        }
+    }
+}
+
+/// `/claim <name>`: protect the chunk the sender stands in (see
+/// [`crate::regions`]).
+fn claim(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    use crate::regions::Claim;
+    let [name] = args else {
+        return vec!["Usage: /claim <name>".into()];
+    };
+    let regions = &ctx.storage.regions;
+    let limit = ctx.config.claims.per_player;
+    if !bypasses_claims(ctx) && regions.owned_by(ctx.sender_uuid) >= limit {
+        return vec![format!("You can't claim more than {} region(s)", limit)];
+    }
+    let [x, y, z] = ctx.sender_pos;
+    let chunk = BlockPos::new(x, y, z).chunk();
+    let claim = Claim::chunk(chunk, ctx.sender_uuid, ctx.sender);
+    if let Some(other) = regions.overlapping(&claim) {
+        return vec![format!("This chunk overlaps region {}", other)];
+    }
+    if !regions.define(name, claim) {
+        return vec![format!("A region named {} already exists", name)];
+    }
+    save_regions(ctx, format!("Claimed chunk {}, {} as {}", chunk.x, chunk.z, name))
+}
+
+/// `/region list|info|define|remove|addmember|removemember` (see
+/// [`crate::regions`]). Owners manage their own regions; defining one
+/// from the selection, and managing anyone's, takes `claims.bypass_level`.
+fn region(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    use crate::regions::Claim;
+    let regions = &ctx.storage.regions;
+    let bypass = bypasses_claims(ctx);
+    let describe = |name: &str, c: &Claim| {
+        let ([x1, y1, z1], [x2, y2, z2]) = (c.min, c.max);
+        format!("  {} (owner {}): {} {} {} to {} {} {}", name, c.owner_name, x1, y1, z1, x2, y2, z2)
+    };
+    let managed = |name: &str| match regions.get(name) {
+        None => Err(vec![format!("No region named {}", name)]),
+        Some(c) if c.owner != ctx.sender_uuid && !bypass => Err(vec![format!("Region {} belongs to {}", name, c.owner_name)]),
+        Some(c) => Ok(c),
+    };
+    match args {
+        ["list"] => {
+            let all = regions.list();
+            if all.is_empty() {
+                return vec!["There are no regions".into()];
+            }
+            let mut out = vec![format!("There are {} region(s):", all.len())];
+            out.extend(all.iter().map(|(name, c)| describe(name, c)));
+            out
+        }
+        ["info"] => {
+            let [x, y, z] = ctx.sender_pos;
+            let here = regions.at(BlockPos::new(x, y, z));
+            if here.is_empty() {
+                return vec!["You are not in any region".into()];
+            }
+            let mut out = Vec::new();
+            for (name, c) in &here {
+                out.push(describe(name, c));
+                if !c.members.is_empty() {
+                    out.push(format!("    members: {}", c.members.values().cloned().collect::<Vec<_>>().join(", ")));
+                }
+            }
+            out
+        }
+        ["define", name] => {
+            if !bypass {
+                return vec!["You do not have permission to define regions.".into()];
+            }
+            let Some(selection) = ctx.edit.lock().unwrap().selection() else {
+                return vec!["Make a selection with //pos1 and //pos2 first".into()];
+            };
+            let claim = Claim::new(selection.min, selection.max, ctx.sender_uuid, ctx.sender);
+            if !regions.define(name, claim) {
+                return vec![format!("A region named {} already exists", name)];
+            }
+            save_regions(ctx, format!("Defined region {} ({} blocks)", name, selection.volume()))
+        }
+        ["remove", name] => match managed(name) {
+            Err(reply) => reply,
+            Ok(_) => {
+                regions.remove(name);
+                save_regions(ctx, format!("Removed region {}", name))
+            }
+        },
+        [verb @ ("addmember" | "removemember"), name, player] => {
+            if let Err(reply) = managed(name) {
+                return reply;
+            }
+            let add = *verb == "addmember";
+            let (uuid, player) = resolve_player(ctx, player);
+            if !regions.set_member(name, uuid, &player, add) {
+                let already = if add { "already" } else { "not" };
+                return vec![format!("{} is {} a member of {}", player, already, name)];
+            }
+            let done = if add {
+                format!("Added {} to {}", player, name)
+            } else {
+                format!("Removed {} from {}", player, name)
+            };
+            save_regions(ctx, done)
+        }
+        _ => vec!["Usage: /region <list|info|define <name>|remove <name>|addmember <name> <player>|removemember <name> <player>>".into()],
+    }
+}
+
+/// `/scoreboard objectives …` and `/scoreboard players …` (see
+/// [`crate::scoreboard`]).
+fn scoreboard(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    use crate::scoreboard::{Criterion, Slot};
+    let board = &ctx.storage.scoreboard;
+    match args {
+        ["objectives", "list"] => {
+            let objectives = board.objectives();
+            if objectives.is_empty() {
+                return vec!["There are no objectives".into()];
+            }
+            let mut out = vec![format!("There are {} objective(s):", objectives.len())];
+            out.extend(
+                objectives
+                    .into_iter()
+                    .map(|(name, criterion, display)| format!("  {} ({}): {}", name, criterion.name(), display)),
+            );
+            out
+        }
+        ["objectives", "add", name, criterion, display @ ..] => {
+            let Some(criterion) = Criterion::parse(criterion) else {
+                let known: Vec<_> = Criterion::ALL.iter().map(|c| c.name()).collect();
+                return vec![format!("Unknown criterion: {} (one of {})", criterion, known.join(", "))];
+            };
+            let display = if display.is_empty() { name.to_string() } else { display.join(" ") };
+            if board.add_objective(name, criterion, &display) {
+                vec![format!("Created new objective [{}]", display)]
+            } else {
+                vec!["An objective already exists by that name".into()]
+            }
+        }
+        ["objectives", "remove", name] => {
+            if board.remove_objective(name) {
+                vec![format!("Removed objective {}", name)]
+            } else {
+                vec![format!("Unknown scoreboard objective '{}'", name)]
+            }
+        }
+        ["objectives", "setdisplay", slot, objective @ ..] if objective.len() <= 1 => {
+            let Some(slot) = Slot::parse(slot) else {
+                return vec![format!("Unknown display slot: {}", slot)];
+            };
+            match objective.first() {
+                None => {
+                    board.set_display(slot, None);
+                    vec![format!("Cleared any objectives in display slot {}", slot.name())]
+                }
+                Some(name) if board.set_display(slot, Some(name)) => {
+                    vec![format!("Set display slot {} to show objective {}", slot.name(), name)]
+                }
+                Some(name) => vec![format!("Unknown scoreboard objective '{}'", name)],
+            }
+        }
+        ["players", "list", owner] => {
+            let scores = board.scores_of(owner);
+            if scores.is_empty() {
+                return vec![format!("{} has no scores to show", owner)];
+            }
+            let mut out = vec![format!("{} has {} score(s):", owner, scores.len())];
+            out.extend(scores.into_iter().map(|(objective, score)| format!("  {}: {}", objective, score)));
+            out
+        }
+        ["players", op @ ("set" | "add"), owner, objective, score] => {
+            let Ok(score) = score.parse::<i32>() else {
+                return vec![format!("Invalid integer '{}'", score)];
+            };
+            let updated = if *op == "set" {
+                board.set_score(objective, owner, score).then_some(score)
+            } else {
+                board.add_score(objective, owner, score)
+            };
+            match updated {
+                Some(score) => vec![format!("Set [{}] for {} to {}", objective, owner, score)],
+                None => vec![format!("Unknown scoreboard objective '{}'", objective)],
+            }
+        }
+        ["players", "reset", owner, objective @ ..] if objective.len() <= 1 => {
+            let objective = objective.first().copied();
+            if board.reset(owner, objective) {
+                match objective {
+                    Some(objective) => vec![format!("Reset {} for {}", objective, owner)],
+                    None => vec![format!("Reset all scores of {}", owner)],
+                }
+            } else {
+                vec![format!("{} has no scores to reset", owner)]
+            }
+        }
+        _ => vec!["Usage: /scoreboard <objectives <list|add|remove|setdisplay>|players <list|set|add|reset>> ...".into()],
+    }
+}
+
+/// `/schem <list|save <name> <from> <to>|paste <name> [pos]>` — Sponge
+/// schematics under the world directory (see [`crate::schematics`]).
+async fn schem(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    use crate::schematics::{self, Schematic};
+
+    const USAGE: &str = "Usage: /schem <list|save <name> <from> <to>|paste <name> [pos]>";
+    let dir = ctx.storage.dir.clone();
+    match args {
+        ["list"] => match schematics::list(&dir) {
+            Ok(names) if names.is_empty() => vec!["There are no saved schematics".into()],
+            Ok(names) => vec![format!("There are {} schematic(s): {}", names.len(), names.join(", "))],
+            Err(e) => vec![format!("Listing failed: {:#}", e)],
+        },
+        ["save", name, corners @ ..] if corners.len() == 6 => {
+            let (Some(from), Some(to)) = (parse_coords(&corners[..3], ctx.sender_pos), parse_coords(&corners[3..], ctx.sender_pos)) else {
+                return vec![USAGE.into()];
+            };
+            let path = match schematics::schematic_path(&dir, name) {
+                Ok(path) => path,
+                Err(e) => return vec![format!("{:#}", e)],
+            };
+            let world = Arc::clone(ctx.world);
+            let saved = ctx
+                .pools
+                .run_blocking(move || {
+                    let schem = Schematic::copy(&world, from, to)?;
+                    schem.save(&path)?;
+                    anyhow::Ok(schem.size())
+                })
+                .await;
+            match saved {
+                Ok([w, h, l]) => vec![format!("Saved schematic {} ({}x{}x{})", name, w, h, l)],
+                Err(e) => vec![format!("Save failed: {:#}", e)],
+            }
+        }
+        ["paste", name, pos @ ..] if pos.is_empty() || pos.len() == 3 => {
+            let origin = if pos.is_empty() {
+                let [x, y, z] = ctx.sender_pos;
+                BlockPos::new(x, y, z)
+            } else {
+                let Some(origin) = parse_coords(pos, ctx.sender_pos) else {
+                    return vec![USAGE.into()];
+                };
+                origin
+            };
+            let path = match schematics::schematic_path(&dir, name) {
+                Ok(path) if path.exists() => path,
+                Ok(_) => return vec![format!("No schematic called {}", name)],
+                Err(e) => return vec![format!("{:#}", e)],
+            };
+            let world = Arc::clone(ctx.world);
+            let events = ctx
+                .pools
+                .run_blocking(move || Schematic::load(&path)?.paste_events(&world, origin))
+                .await;
+            match events {
+                Ok(events) => {
+                    let changed = events.len();
+                    // One batch of roots: the whole paste is one cascade.
+                    ctx.physics.submit_events(events);
+                    vec![format!(
+                        "Pasted schematic {} at {} {} {} ({} blocks changed)",
+                        name, origin.x, origin.y, origin.z, changed
+                    )]
+                }
+                Err(e) => vec![format!("Paste failed: {:#}", e)],
+            }
+        }
+        _ => vec![USAGE.into()],
+    }
+}
+
+/// `//pos1 [pos]`, `//pos2 [pos]` — set a selection corner, by default
+/// where the sender stands.
+fn select(ctx: &CommandContext<'_>, args: &[&str], corner: u8) -> Vec<String> {
+    let pos = match args {
+        [] => {
+            let [x, y, z] = ctx.sender_pos;
+            BlockPos::new(x, y, z)
+        }
+        _ => match parse_coords(args, ctx.sender_pos) {
+            Some(pos) => pos,
+            None => return vec![format!("Usage: //pos{} [pos]", corner)],
+        },
+    };
+    let mut session = ctx.edit.lock().unwrap();
+    let which = if corner == 1 {
+        session.pos1 = Some(pos);
+        "First"
+    } else {
+        session.pos2 = Some(pos);
+        "Second"
+    };
+    let size = match session.selection() {
+        Some(region) => format!(" ({} blocks)", region.volume()),
+        None => String::new(),
+    };
+    vec![format!("{} position set to {} {} {}{}", which, pos.x, pos.y, pos.z, size)]
+}
+
+/// `//set <block>`
+async fn set(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let [block] = args else {
+        return vec!["Usage: //set <block>".into()];
+    };
+    let Some(new) = crate::block::block_id_from_name(block) else {
+        return vec![format!("Unknown block: {}", block)];
+    };
+    fill_selection(ctx, None, new).await
+}
+
+/// `//replace <from> <to>` — any state of `from` counts as a match.
+async fn replace(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    let [from, to] = args else {
+        return vec!["Usage: //replace <from> <to>".into()];
+    };
+    let Some(mask) = crate::block::block_id_from_name(from) else {
+        return vec![format!("Unknown block: {}", from)];
+    };
+    let Some(new) = crate::block::block_id_from_name(to) else {
+        return vec![format!("Unknown block: {}", to)];
+    };
+    fill_selection(ctx, Some(mask), new).await
+}
+
+/// Run a fill over the sender's selection and submit it as one cascade.
+async fn fill_selection(ctx: &CommandContext<'_>, mask: Option<BlockId>, new: BlockId) -> Vec<String> {
+    let Some(region) = ctx.edit.lock().unwrap().selection() else {
+        return vec!["Make a selection first with //pos1 and //pos2".into()];
+    };
+    let world = Arc::clone(ctx.world);
+    let max_blocks = ctx.config.edit.max_blocks;
+    let filled = ctx
+        .pools
+        .run_blocking(move || crate::edit::fill(&world, region, mask, new, max_blocks))
+        .await;
+    match filled {
+        Ok((events, record)) => {
+            let changed = events.len();
+            ctx.physics.submit_events(events);
+            ctx.edit.lock().unwrap().push_undo(record);
+            vec![format!("{} blocks changed", changed)]
+        }
+        Err(e) => vec![format!("{:#}", e)],
+    }
+}
+
+/// `//undo` — put back what the sender's last `//set` or `//replace`
+/// overwrote.
+async fn undo(ctx: &CommandContext<'_>, args: &[&str]) -> Vec<String> {
+    if !args.is_empty() {
+        return vec!["Usage: //undo".into()];
+    }
+    let Some(record) = ctx.edit.lock().unwrap().pop_undo() else {
+        return vec!["Nothing left to undo".into()];
+    };
+    let world = Arc::clone(ctx.world);
+    let events = ctx.pools.run_blocking(move || crate::edit::undo(&world, &record)).await;
+    let changed = events.len();
+    ctx.physics.submit_events(events);
+    vec![format!("Undid the last edit ({} blocks changed)", changed)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_complete_commands_and_subcommands() {
+        let players = names(&["Alice", "alex", "Bob"]);
+        let at = [10, 64, -3];
+
+        assert_eq!(complete("/", 4, &players, at), (1, names(&["/pos1", "/pos2", "/replace", "/set", "/undo", "ban", "claim", "deop", "gamemode", "gamerule", "history", "home", "op", "pardon", "profile", "region", "reloadrules", "schem", "scoreboard", "sethome", "spawn", "tpa", "tpaccept", "tps", "trim", "whitelist", "worldborder"])));
+        assert_eq!(complete("/tr", 4, &players, at), (1, names(&["trim"])));
+        assert_eq!(complete("/tr", 3, &players, at).1, Vec::<String>::new(), "trim needs level 4");
+        assert_eq!(complete("/whitelist re", 3, &players, at), (11, names(&["reload", "remove"])));
+        assert_eq!(complete("/whitelist add al", 3, &players, at), (15, names(&["Alice", "alex"])));
+        assert_eq!(complete("/ban Bob spam", 3, &players, at).1, Vec::<String>::new());
+        assert_eq!(complete("/gamemode sp", 2, &players, at), (10, names(&["spectator"])));
+        assert_eq!(complete("/scoreboard objectives setdisplay s", 2, &players, at), (34, names(&["sidebar"])));
+        assert_eq!(complete("/gamemode creative B", 2, &players, at), (19, names(&["Bob"])));
+        assert_eq!(complete("/gamerule doM", 2, &players, at), (10, names(&["doMobSpawning"])));
+        assert_eq!(complete("/op ", 0, &players, at).1, Vec::<String>::new(), "no suggestions without permission");
+    }
+
+    #[tokio::test]
+    async fn test_console_rights_never_come_from_a_uuid() {
+        use crate::config::PhysicsConfig;
+        use crate::dashboard::DashboardState;
+        use crate::worldgen::biome::Biome;
+        use crate::worldgen::pipeline::FlatPipeline;
+
+        let dir = std::env::temp_dir().join("ultimate_mc_test_console_rights");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let world = Arc::new(World::new());
+        let dashboard = Arc::new(DashboardState::new(Arc::clone(&world)));
+        let spatial = crate::event_bus::SpatialBus::new();
+        let base: Arc<dyn crate::worldgen::WorldGen> =
+            Arc::new(FlatPipeline { min_y: -64, layers: vec![(crate::block::STONE, 4)], biome: Biome::Plains });
+        let config = Arc::new(ServerConfig::default());
+        let console = Console {
+            world: Arc::clone(&world),
+            storage: Arc::new(WorldStorage::new(dir.clone(), 1, 7, base, crate::persistence::new_delta_store())),
+            registry: Arc::new(PlayerRegistry::new(Arc::clone(&spatial))),
+            access: Arc::new(AccessLists::load(&dir, false).unwrap()),
+            pools: Arc::new(Pools::new(&PhysicsConfig { cascade_threads: 1, blocking_threads: 1, ..Default::default() }, dashboard).unwrap()),
+            physics: crate::physics::start(
+                Arc::clone(&world),
+                crate::rules::standard,
+                spatial,
+                None,
+                crate::physics::PhysicsOptions { workers: 1, ..Default::default() },
+            ),
+            border: Arc::new(WorldBorder::new(&config.border)),
+            config,
+        };
+
+        // A player whose (forwarded) UUID is the console's is still a player.
+        let edit = Mutex::new(EditSession::default());
+        let player = CommandContext {
+            world: &console.world,
+            storage: &console.storage,
+            registry: &console.registry,
+            access: &console.access,
+            pools: &console.pools,
+            physics: &console.physics,
+            config: &console.config,
+            border: &console.border,
+            edit: &edit,
+            sender: "Mallory",
+            sender_uuid: CONSOLE_UUID,
+            sender_pos: [0, 0, 0],
+            is_console: false,
+        };
+        assert_eq!(dispatch(&player, "deop Steve").await, ["You do not have permission to use this command."]);
+        assert!(!bypasses_claims(&player));
+
+        assert_eq!(console.execute("Rcon", "/deop Steve").await, ["Nothing changed. The player is not an operator"]);
+        assert_eq!(console.execute("Rcon", "home").await, ["/home can only be run by a player"]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_parse_coords_absolute_and_relative() {
+        let at = [10, 64, -3];
+        assert_eq!(parse_coords(&["1", "2", "3"], at), Some(BlockPos::new(1, 2, 3)));
+        assert_eq!(parse_coords(&["~", "~-4", "~2"], at), Some(BlockPos::new(10, 60, -1)));
+        assert_eq!(parse_coords(&["~x", "0", "0"], at), None);
+        assert_eq!(parse_coords(&["0", "0"], at), None);
+    }
+
+    #[test]
+    fn test_tree_merges_shared_prefixes() {
+        let tree = command_tree(DEFAULT_OP_LEVEL);
+        let root = &tree.entries[0];
+        assert_eq!(root.children.len(), COMMANDS.len());
+        let ban = tree
+            .entries
+            .iter()
+            .find(|n| n.node_type == NodeType::Literal { name: "ban".into() })
+            .unwrap();
+        assert!(!ban.is_executable);
+        assert_eq!(ban.children.len(), 1, "both ban usages share the <player> node");
+        let player = &tree.entries[ban.children[0] as usize];
+        assert!(player.is_executable);
+        assert_eq!(player.children.len(), 1);
+
+        let non_op = command_tree(0);
+        let level_zero = COMMANDS.iter().filter(|c| c.level == 0).count();
+        assert_eq!(non_op.entries[0].children.len(), level_zero, "non-ops only get level 0 commands");
+    }
+}
@@ -22,6 +22,406 @@ pub struct ServerConfig {
     pub dashboard: DashboardConfig,
     pub physics: PhysicsConfig,
     pub cluster: ClusterConfig,
+    pub access: AccessConfig,
+    pub chat: ChatConfig,
+    pub skins: SkinsConfig,
+    pub edit: EditConfig,
+    pub mobs: MobsConfig,
+    pub combat: CombatConfig,
+    pub border: BorderConfig,
+    pub rcon: RconConfig,
+    pub query: QueryConfig,
+    pub proxy: ProxyConfig,
+    pub tab_list: TabListConfig,
+    pub status: StatusConfig,
+    pub audit: AuditConfig,
+    pub claims: ClaimsConfig,
+    pub scripts: ScriptsConfig,
+    pub crafting: CraftingConfig,
+    pub trading: TradingConfig,
+    pub watchdog: WatchdogConfig,
+}
+
+/// Remote console (see `rcon`): the standard Minecraft RCON protocol, for
+/// hosting panels and admin scripts.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RconConfig {
+    pub enabled: bool,
+    /// `host:port` to listen on.
+    pub bind: String,
+    /// Password clients must log in with. RCON refuses to start while it
+    /// is empty.
+    pub password: String,
+}
+
+impl Default for RconConfig {
+    fn default() -> Self {
+        Self { enabled: false, bind: "0.0.0.0:25575".into(), password: String::new() }
+    }
+}
+
+/// Query protocol (see `query`): MOTD and player list over UDP for
+/// server-list sites and monitoring.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueryConfig {
+    pub enabled: bool,
+    /// `host:port` to listen on (UDP; vanilla shares the game port).
+    pub bind: String,
+}
+
+impl Default for QueryConfig {
+    fn default() -> Self {
+        Self { enabled: false, bind: "0.0.0.0:25565".into() }
+    }
+}
+
+/// The server-list entry (see `motd`). Re-read while running, together
+/// with `network.max_players`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusConfig {
+    /// Legacy codes (`&a`) and MiniMessage-style tags (`<red>`) allowed.
+    pub motd: String,
+    /// 64x64 PNG shown beside the server. Missing file = no icon.
+    pub icon: PathBuf,
+    /// How often to check the config file and icon for edits, in
+    /// seconds. `0` disables live reload.
+    pub reload_interval_secs: u64,
+}
+
+impl Default for StatusConfig {
+    fn default() -> Self {
+        Self {
+            motd: "Ultimate Minecraft - Causal Graph Engine".into(),
+            icon: PathBuf::from("server-icon.png"),
+            reload_interval_secs: 5,
+        }
+    }
+}
+
+/// Per-player action log (see `audit`): block edits, commands, chat and
+/// logins, queried in game with `/history`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    /// Directory holding `audit.jsonl` and its rotated predecessors.
+    pub dir: PathBuf,
+    /// Rotate `audit.jsonl` once it grows past this many megabytes.
+    pub rotate_mb: u64,
+    /// Rotated files to keep; the oldest are deleted. `0` keeps all.
+    pub keep_files: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self { enabled: true, dir: PathBuf::from("audit"), rotate_mb: 16, keep_files: 8 }
+    }
+}
+
+/// Protected regions (see `regions`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClaimsConfig {
+    /// Regions each player may hold through `/claim`. Ops' `/region
+    /// define` doesn't count against it.
+    pub per_player: usize,
+    /// Permission level at which a player may build inside anyone's
+    /// region and manage every region.
+    pub bypass_level: u8,
+}
+
+impl Default for ClaimsConfig {
+    fn default() -> Self {
+        Self { per_player: 3, bypass_level: 2 }
+    }
+}
+
+/// Scripted block rules (see `rules::scripts`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScriptsConfig {
+    pub enabled: bool,
+    /// Directory of `*.rhai` rule scripts. Missing means none.
+    pub dir: PathBuf,
+    /// Rhai operations one hook call may run before it is stopped.
+    pub max_operations: u64,
+    /// Wall-clock microseconds one hook call may run before it is stopped.
+    pub budget_us: u64,
+    /// Seconds between checks of `dir` for edits. `0` = only load at
+    /// startup and on `/reloadrules`.
+    pub reload_interval_secs: u64,
+}
+
+impl Default for ScriptsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: PathBuf::from("scripts"),
+            max_operations: 10_000,
+            budget_us: 500,
+            reload_interval_secs: 2,
+        }
+    }
+}
+
+/// Crafting recipes (see `recipes`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CraftingConfig {
+    /// `data/minecraft` from the vanilla server jar: recipes are read
+    /// from its `recipe/` and item tags from `tags/item/`. Missing means
+    /// nothing can be crafted.
+    pub data_dir: PathBuf,
+    /// Fill the client's recipe book with every loaded recipe on join.
+    pub recipe_book: bool,
+}
+
+impl Default for CraftingConfig {
+    fn default() -> Self {
+        Self { data_dir: PathBuf::from("vanilla/data/minecraft"), recipe_book: true }
+    }
+}
+
+/// Villager trades (see `trading`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TradingConfig {
+    /// JSON trade lists by profession; written with vanilla-like defaults
+    /// when missing.
+    pub file: PathBuf,
+}
+
+impl Default for TradingConfig {
+    fn default() -> Self {
+        Self { file: PathBuf::from("trades.json") }
+    }
+}
+
+/// Budgets for a single physics cascade (see `watchdog`). A cascade that
+/// outgrows either is aborted: its unexecuted events are dropped. `0`
+/// turns a budget off.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WatchdogConfig {
+    /// Events one worker may execute before the cascade settles.
+    pub max_events: u64,
+    /// Milliseconds one worker may spend before the cascade settles.
+    pub max_millis: u64,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self { max_events: 20_000_000, max_millis: 30_000 }
+    }
+}
+
+/// Tab list header and footer (see `tablist`). Both are templates:
+/// `{online}`, `{max}`, `{tps}`, `{time}` and `{day}` are filled in on
+/// every refresh.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TabListConfig {
+    pub header: String,
+    pub footer: String,
+    /// Seconds between refreshes of the placeholders and latencies.
+    pub refresh_secs: u64,
+}
+
+impl Default for TabListConfig {
+    fn default() -> Self {
+        Self {
+            header: "Ultimate Minecraft".into(),
+            footer: "{online}/{max} online - TPS {tps} - {time}".into(),
+            refresh_secs: 5,
+        }
+    }
+}
+
+/// Player info forwarding from a proxy in front of this server (see
+/// `net::proxy`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProxyConfig {
+    pub mode: ProxyMode,
+    /// Shared secret for Velocity modern forwarding (`forwarding.secret`
+    /// on the proxy). Velocity mode refuses every login while it is empty.
+    pub velocity_secret: String,
+    /// Where the proxy connects from. Every player arrives from these, so
+    /// they are exempt from `network.throttle_connections` while `mode`
+    /// isn't `none`.
+    pub addresses: Vec<std::net::IpAddr>,
+}
+
+impl Default for ProxyConfig {
+    fn default() -> Self {
+        Self { mode: ProxyMode::None, velocity_secret: String::new(), addresses: Vec::new() }
+    }
+}
+
+/// How a proxy forwards the player's real UUID, address and skin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    /// Players connect directly.
+    None,
+    /// BungeeCord `ip_forward`: appended to the handshake hostname.
+    Bungeecord,
+    /// Velocity `modern` forwarding: a signed login plugin message.
+    Velocity,
+}
+
+/// Player combat (see `combat`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CombatConfig {
+    /// Whether players can hurt each other. Only players in survival or
+    /// adventure mode fight either way.
+    pub pvp: bool,
+}
+
+impl Default for CombatConfig {
+    fn default() -> Self {
+        Self { pvp: true }
+    }
+}
+
+/// The world border (see `worldborder`). `/worldborder` changes last
+/// until restart.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BorderConfig {
+    pub center_x: f64,
+    pub center_z: f64,
+    /// Side length of the square border, in blocks.
+    pub diameter: f64,
+    /// Damage per second for each block a player is beyond `safe_zone`
+    /// outside the border (survival and adventure; creative players are
+    /// pushed back in instead).
+    pub damage_per_block: f64,
+    pub safe_zone: f64,
+    /// Clients tint the screen within this many blocks of the border.
+    pub warning_blocks: u32,
+    /// Or this many seconds before a shrinking border reaches them.
+    pub warning_time: u32,
+}
+
+impl Default for BorderConfig {
+    fn default() -> Self {
+        Self {
+            center_x: 0.0,
+            center_z: 0.0,
+            diameter: 59_999_968.0,
+            damage_per_block: 0.2,
+            safe_zone: 5.0,
+            warning_blocks: 5,
+            warning_time: 15,
+        }
+    }
+}
+
+/// Mob spawning (see `mobs`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MobsConfig {
+    /// Spawn pigs, cows and villagers on grass, and zombies and skeletons
+    /// in the dark.
+    pub enabled: bool,
+    /// Most mobs alive at once, across the whole world.
+    pub cap: usize,
+}
+
+impl Default for MobsConfig {
+    fn default() -> Self {
+        Self { enabled: true, cap: 40 }
+    }
+}
+
+/// Region editing commands (`//set`, `//replace`; see `edit`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EditConfig {
+    /// Largest selection one operation may touch, in blocks. Every
+    /// changed block is a root event of the operation's cascade.
+    pub max_blocks: usize,
+}
+
+impl Default for EditConfig {
+    fn default() -> Self {
+        Self { max_blocks: 262_144 }
+    }
+}
+
+/// Skins for offline-mode players (see `skins`).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SkinsConfig {
+    /// Directory of `<name>.json` texture properties, checked before
+    /// anything else. `None` = no local skins.
+    pub dir: Option<PathBuf>,
+    /// Look up missing skins from Mojang by player name. Off by default:
+    /// it sends every joining name to Mojang's API.
+    pub mojang: bool,
+    /// Where Mojang lookups (hits and misses) are cached.
+    pub cache_dir: PathBuf,
+    /// How long a cached lookup is trusted, in seconds.
+    pub cache_ttl_secs: u64,
+}
+
+impl Default for SkinsConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            mojang: false,
+            cache_dir: PathBuf::from("skin-cache"),
+            cache_ttl_secs: 86_400,
+        }
+    }
+}
+
+/// Player chat delivery.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChatConfig {
+    /// Relay chat as player messages (sender identity, chat bubbles,
+    /// reportable). When off, or when a recipient hasn't been told about
+    /// the sender via the tab list, chat falls back to `<name> text`
+    /// system messages.
+    pub player_messages: bool,
+    /// Accept chat that arrives without a signature (offline-mode and
+    /// modded clients never sign). When off, such a message disconnects
+    /// its sender as vanilla's `enforce-secure-profile` does. Signatures
+    /// are not verified or forwarded either way.
+    pub accept_unsigned: bool,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        Self { player_messages: true, accept_unsigned: true }
+    }
+}
+
+/// Whitelist / ban list / operators (vanilla-format JSON files).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccessConfig {
+    /// Directory holding `ops.json`, `banned-players.json` and
+    /// `whitelist.json`. Missing files are treated as empty.
+    pub dir: PathBuf,
+    /// Only whitelisted players (and ops) may join. `/whitelist on|off`
+    /// toggles this at runtime without editing the file.
+    pub whitelist: bool,
+    /// How often the files are checked for hand edits, in seconds.
+    /// `0` disables live reload.
+    pub reload_interval_secs: u64,
+}
+
+impl Default for AccessConfig {
+    fn default() -> Self {
+        Self { dir: PathBuf::from("."), whitelist: false, reload_interval_secs: 5 }
+    }
 }
 
 /// Multi-node clustering (Phase 6f). Disabled by default (single node).
@@ -72,11 +472,31 @@ pub struct PhysicsConfig {
     /// per-region event throughput, moves hot regions between workers,
     /// and splits a dominating region into per-chunk ownership.
     pub rebalance: bool,
+    /// Threads in the dedicated rayon pool parallel cascades fan out on,
+    /// kept apart from rayon's global pool and the tokio runtime.
+    /// `0` = auto (half the logical cores, at least one).
+    pub cascade_threads: usize,
+    /// Threads in the pool that runs blocking simulation and persistence
+    /// work (simulation ticks, autosave, `/trim`) off the tokio runtime.
+    /// `0` = auto (two).
+    pub blocking_threads: usize,
+    /// Events a worker executes per step before publishing the step's
+    /// block changes and checking for new work. Huge cascades (a lake
+    /// draining) then reach clients in slices instead of one burst at the
+    /// end, and player actions queued behind them start sooner.
+    pub step_budget: usize,
 }
 
 impl Default for PhysicsConfig {
     fn default() -> Self {
-        Self { workers: 0, pin_workers: false, rebalance: true }
+        Self {
+            workers: 0,
+            pin_workers: false,
+            rebalance: true,
+            cascade_threads: 0,
+            blocking_threads: 0,
+            step_budget: crate::physics::DEFAULT_STEP_BUDGET,
+        }
     }
 }
 
@@ -87,15 +507,17 @@ pub struct NetworkConfig {
     /// `host:port` to bind. Default `0.0.0.0:25565`.
     pub bind: String,
     /// Maximum simultaneous players advertised in the status response.
+    /// Re-read while running, like `status`.
     pub max_players: u32,
     /// Server-side view distance: the maximum number of chunks (Chebyshev)
     /// from the player at which the server will send chunk data. The
     /// client may *render* fewer chunks than this (its own video setting),
     /// but cannot render more — this is the hard upper bound.
     pub view_distance: i32,
-    /// Simulation distance: how far ticking entities/redstone propagate.
-    /// Currently informational (we don't tick yet) but sent in the Login
-    /// packet for protocol compliance.
+    /// Simulation distance, in chunks (Chebyshev): physics cascades only
+    /// run in chunks this close to some player. Rule writes aimed further
+    /// out wait until a player comes near (see `tickets`). Also sent in
+    /// the Login packet. `0` = every loaded chunk simulates.
     pub simulation_distance: i32,
     /// On join and on chunk-boundary crossings, chunks within Chebyshev
     /// distance `immediate_radius` are sent synchronously before the
@@ -106,7 +528,9 @@ pub struct NetworkConfig {
     ///
     /// If `null`, defaults to 2 (clamped to `view_distance`).
     pub immediate_radius: Option<i32>,
-    /// Maximum deferred chunks sent per main-loop iteration.
+    /// Deferred chunks sent per main-loop iteration to start with. The
+    /// rate then adapts to socket backpressure, between 1 and four times
+    /// this (see `net::chunk_order`).
     pub chunks_per_iter: usize,
     /// Admission control for bulk chunk streaming: at most this many
     /// connections drain their deferred chunk queues CONCURRENTLY.
@@ -124,6 +548,23 @@ pub struct NetworkConfig {
     /// rationale; proper AOI entity lifecycle replaces this with
     /// Phase 5 entities. `0` = unlimited.
     pub entity_spawn_cap: usize,
+    /// Radius, in chunks, within which other players are spawned on a
+    /// client and their movement forwarded. Players who walk out of it
+    /// are despawned and respawn when they come back. Clamped to
+    /// `view_distance`.
+    pub entity_view_distance: i32,
+    /// Maximum chunks a single client holds at once. When the view
+    /// square exceeds it, only the nearest chunks are kept (a rough disc)
+    /// and the farthest are forgotten first, bounding both the server's
+    /// per-connection serialization work and a low-memory client's chunk
+    /// cache while flying or spectating. `0` = unlimited.
+    pub max_loaded_chunks: usize,
+    /// Per-IP connection throttling: at most this many connections from
+    /// one address per `throttle_window_secs`; the rest are closed at
+    /// once. Loopback is exempt (local proxies, load tests), and so are
+    /// `proxy.addresses` while forwarding is on. `0` = off.
+    pub throttle_connections: u32,
+    pub throttle_window_secs: u64,
 }
 
 /// World storage and pre-generation.
@@ -134,11 +575,13 @@ pub struct WorldConfig {
     pub dir: PathBuf,
     /// Autosave cadence in seconds.
     pub autosave_interval_secs: u64,
-    /// Worldgen seed. CLI `--seed` overrides this.
+    /// Worldgen seed for new worlds. An existing world keeps the seed in
+    /// its `level.dat`; CLI `--seed` overrides both.
     pub seed: u32,
     /// Number of chunks (radius, Chebyshev) around origin to pre-generate
-    /// at startup so the spawn region is immediate. Beyond this, chunks
-    /// generate lazily as players approach.
+    /// in the background after startup, so the spawn region is ready soon.
+    /// The server accepts players meanwhile; beyond this (or before it's
+    /// done), chunks generate lazily as players approach.
     pub pregenerate_radius: i32,
     /// Worldgen preset: a built-in name (`"noise"`, `"superflat"`) or
     /// a path to a JSON file describing a custom pipeline. See
@@ -161,6 +604,17 @@ pub struct WorldConfig {
 pub struct DashboardConfig {
     /// HTTP port for the dashboard. Bound to localhost only.
     pub port: u16,
+    /// Bearer token for the admin endpoints under `/api/`. Empty disables
+    /// them; the read-only dashboard is unaffected.
+    pub admin_token: String,
+    /// Time one rule evaluation in this many per physics thread for the
+    /// per-rule cost table. 0 disables.
+    pub rule_sample_every: u32,
+    /// Recent causal events each physics worker keeps for the graph view.
+    pub graph_window_nodes: usize,
+    /// Also forget recent events older than this many milliseconds.
+    /// `0` = no age limit.
+    pub graph_window_ms: u64,
 }
 
 // ── Defaults ────────────────────────────────────────────────────────────────
@@ -173,6 +627,24 @@ impl Default for ServerConfig {
             dashboard: DashboardConfig::default(),
             physics: PhysicsConfig::default(),
             cluster: ClusterConfig::default(),
+            access: AccessConfig::default(),
+            chat: ChatConfig::default(),
+            skins: SkinsConfig::default(),
+            edit: EditConfig::default(),
+            mobs: MobsConfig::default(),
+            combat: CombatConfig::default(),
+            border: BorderConfig::default(),
+            rcon: RconConfig::default(),
+            query: QueryConfig::default(),
+            proxy: ProxyConfig::default(),
+            tab_list: TabListConfig::default(),
+            status: StatusConfig::default(),
+            audit: AuditConfig::default(),
+            claims: ClaimsConfig::default(),
+            scripts: ScriptsConfig::default(),
+            crafting: CraftingConfig::default(),
+            trading: TradingConfig::default(),
+            watchdog: WatchdogConfig::default(),
         }
     }
 }
@@ -189,6 +661,10 @@ impl Default for NetworkConfig {
             stream_permits: 256,
             tab_list_cap: 500,
             entity_spawn_cap: 200,
+            entity_view_distance: 6,
+            max_loaded_chunks: 1024,
+            throttle_connections: 10,
+            throttle_window_secs: 10,
         }
     }
 }
@@ -209,7 +685,13 @@ impl Default for WorldConfig {
 
 impl Default for DashboardConfig {
     fn default() -> Self {
-        Self { port: 8000 }
+        Self {
+            port: 8000,
+            admin_token: String::new(),
+            rule_sample_every: 64,
+            graph_window_nodes: 200,
+            graph_window_ms: 0,
+        }
     }
 }
 
@@ -228,13 +710,16 @@ pub const DEFAULT_CONFIG_YAML: &str = r#"# Ultimate Minecraft -- server configur
 network:
   # Address and port to listen on. Use 0.0.0.0 for all interfaces.
   bind: "0.0.0.0:25565"
-  # Maximum simultaneous players (advertised in the status response).
+  # Maximum simultaneous players: advertised in the status response and
+  # enforced at login. Re-read while running.
   max_players: 20
   # Server-side view distance, in chunks (Chebyshev radius). The client
   # may render fewer than this, but cannot render more.
   view_distance: 8
-  # Simulation distance: how far ticking entities/redstone propagate.
-  # Currently informational; sent in the Login packet.
+  # Simulation distance, in chunks: fluids, falling blocks and other
+  # cascades only run this close to a player. Writes further out wait
+  # until someone comes near, as with vanilla's ticking chunks.
+  # 0 = every loaded chunk simulates.
   simulation_distance: 8
   # On join and when the player crosses a chunk boundary, chunks within
   # this radius are sent SYNCHRONOUSLY before the cache-center update;
@@ -242,7 +727,10 @@ network:
   # iteration (keep-alives interleave, so slow initial loads can't
   # silently time the client out). null = 2.
   immediate_radius: null
-  # Deferred-chunk drain rate, per main-loop iteration.
+  # Deferred-chunk drain rate, per main-loop iteration, to start with:
+  # it halves while the client's socket backs up and climbs back (up to
+  # four times this) while it keeps up. Chunks in front of the player
+  # go before those beside and behind them.
   chunks_per_iter: 5
   # At most this many connections bulk-stream chunks concurrently; the
   # rest wait their turn on keep-alives. Prevents a join storm from
@@ -252,15 +740,27 @@ network:
   # Uncapped presence is O(N^2) bytes across all clients. 0 = unlimited.
   tab_list_cap: 500
   entity_spawn_cap: 200
+  # Other players are only spawned (and their movement sent) within this
+  # many chunks; they despawn on leaving it. Clamped to view_distance.
+  entity_view_distance: 6
+  # Per-client cap on loaded chunks. Beyond it only the nearest chunks
+  # are kept and the farthest are unloaded first. 0 = unlimited.
+  max_loaded_chunks: 1024
+  # At most this many connections per IP address per window; the rest
+  # are closed at once. Loopback is exempt, and so are proxy.addresses
+  # while forwarding is on.
+  throttle_connections: 10
+  throttle_window_secs: 10
 
 world:
   # Directory for saved (player-modified) chunks.
   dir: "world"
   # Autosave cadence, seconds.
   autosave_interval_secs: 300
-  # Worldgen seed. Override on the CLI with --seed <u32>.
+  # Worldgen seed for new worlds; an existing world keeps the one in its
+  # level.dat. Override both on the CLI with --seed <u32>.
   seed: 12648430   # 0xC0FFEE
-  # Chunks (radius) to pre-generate at startup so spawn is immediate.
+  # Chunks (radius) to pre-generate in the background after startup.
   pregenerate_radius: 8
   # Worldgen preset. Built-in: "noise" (default, vanilla-ish noise terrain)
   # or "superflat" (flat layered world). Anything else is treated as a
@@ -271,6 +771,167 @@ world:
 dashboard:
   # HTTP port for the live dashboard. Bound to localhost only.
   port: 8000
+  # Token for the admin API (player list, kick, broadcast, save, block
+  # query, event injection). Send as "Authorization: Bearer <token>".
+  # Empty disables the admin API.
+  admin_token: ""
+  # Per-rule cost: time one rule evaluation in this many (per physics
+  # thread). 0 disables.
+  rule_sample_every: 64
+  # Recent causal events each physics worker keeps for the graph view: at
+  # most graph_window_nodes, none older than graph_window_ms (0 = no age
+  # limit).
+  graph_window_nodes: 200
+  graph_window_ms: 0
+
+access:
+  # Directory holding ops.json, banned-players.json and whitelist.json
+  # (vanilla format; edits are picked up live).
+  dir: "."
+  # Only whitelisted players and ops may join. Toggle at runtime with
+  # /whitelist on|off.
+  whitelist: false
+  # Seconds between checks for hand edits to the files. 0 = never.
+  reload_interval_secs: 5
+
+chat:
+  # Relay chat as player messages (sender identity, chat bubbles). When
+  # false, chat is sent as plain "<name> text" system messages.
+  player_messages: true
+  # Accept chat without a signature. false disconnects unsigned senders.
+  accept_unsigned: true
+
+skins:
+  # Directory of <name>.json files holding a signed "textures" property
+  # ({"value": ..., "signature": ...}), checked first. null = none.
+  dir: null
+  # Fetch other skins from Mojang by player name (sends joining names to
+  # Mojang). Results, including misses, are cached in cache_dir.
+  mojang: false
+  cache_dir: "skin-cache"
+  cache_ttl_secs: 86400
+
+edit:
+  # Largest region //set and //replace may touch, in blocks (64^3).
+  max_blocks: 262144
+
+mobs:
+  # Spawn pigs, cows and villagers on grass in loaded chunks; they wander
+  # about. Right-click a villager to trade. Zombies and skeletons spawn in
+  # the dark, hunt players within 16 blocks and burn in daylight.
+  enabled: true
+  # Most mobs alive at once, across the whole world.
+  cap: 40
+
+combat:
+  # Players can hurt each other (only in survival or adventure mode).
+  pvp: true
+
+border:
+  center_x: 0.0
+  center_z: 0.0
+  # Side length of the square border, in blocks (vanilla's default).
+  diameter: 59999968.0
+  # Damage per second per block beyond `safe_zone` outside the border,
+  # for survival and adventure players; creative players are pushed back.
+  damage_per_block: 0.2
+  safe_zone: 5.0
+  # Clients tint the screen this many blocks from the border, or this many
+  # seconds before a shrinking border reaches them.
+  warning_blocks: 5
+  warning_time: 15
+
+rcon:
+  # Remote console: runs commands for hosting panels and admin scripts
+  # at full operator level. Needs a password; sent in plain text, so keep
+  # the port firewalled.
+  enabled: false
+  bind: "0.0.0.0:25575"
+  password: ""
+
+query:
+  # Answer GameSpy4 query requests (MOTD, player names) over UDP, like
+  # vanilla's enable-query. The UDP port may equal the game's TCP port.
+  enabled: false
+  bind: "0.0.0.0:25565"
+
+proxy:
+  # Player info forwarding from a proxy: "none", "bungeecord" (legacy
+  # ip_forward) or "velocity" (modern forwarding). Behind a proxy, firewall
+  # the game port so players cannot bypass it with a forged identity.
+  mode: none
+  # Velocity's forwarding secret; required in velocity mode.
+  velocity_secret: ""
+  # The proxy's IP addresses, if it runs on another host. Every player
+  # connects from them, so they aren't throttled (network.throttle_*).
+  addresses: []
+
+tab_list:
+  # Shown above and below the player list. Placeholders: {online}, {max},
+  # {tps}, {time} (world time of day) and {day}. "\n" starts a new line.
+  header: "Ultimate Minecraft"
+  footer: "{online}/{max} online - TPS {tps} - {time}"
+  # Seconds between refreshes of the placeholders and players' ping.
+  refresh_secs: 5
+
+status:
+  # Server-list description. Format with legacy codes (&a, &l) or tags
+  # (<red>, <bold>, </red>, <reset>, <newline>).
+  motd: "Ultimate Minecraft - Causal Graph Engine"
+  # 64x64 PNG shown beside the server; no file, no icon.
+  icon: "server-icon.png"
+  # Seconds between checks for edits to this file (motd, max_players) and
+  # the icon. 0 = only read at startup.
+  reload_interval_secs: 5
+
+audit:
+  # Log block places and breaks, commands, chat and logins per player as
+  # JSON lines, and answer "/history <x> <y> <z>" from them.
+  enabled: true
+  dir: "audit"
+  # audit.jsonl is renamed to audit-<timestamp>.jsonl past this size.
+  rotate_mb: 16
+  # Rotated files kept; older ones are deleted. 0 = keep all.
+  keep_files: 8
+
+claims:
+  # Chunks each player may protect with "/claim <name>". Ops define larger
+  # regions with "/region define"; members are managed with "/region".
+  per_player: 3
+  # Op level that builds inside anyone's region and manages them all.
+  bypass_level: 2
+
+scripts:
+  # Block rules written in Rhai, one *.rhai file each, defining
+  # on_set(area, before, after) and/or on_notify(area). See rules/scripts.rs.
+  enabled: true
+  dir: "scripts"
+  # Per call: a script that runs longer is stopped and its changes dropped.
+  max_operations: 10000
+  budget_us: 500
+  # Seconds between checks for edited scripts. 0 = startup and
+  # /reloadrules only.
+  reload_interval_secs: 2
+
+crafting:
+  # data/minecraft extracted from the vanilla server jar; recipes come
+  # from its recipe/ folder, ingredient tags from tags/item/.
+  data_dir: "vanilla/data/minecraft"
+  # Show every recipe in the client's recipe book.
+  recipe_book: true
+
+trading:
+  # Villager offers by profession; each villager takes one profession.
+  # Created with defaults when missing. See trading.rs for the format.
+  file: "trades.json"
+
+watchdog:
+  # A physics cascade that runs past either budget before settling is
+  # aborted and logged with its root event and rule timings: a rule that
+  # never reaches quiescence stops here. 0 turns a budget off. Lakes
+  # draining and other legitimate floods stay well under the defaults.
+  max_events: 20000000
+  max_millis: 30000
 "#;
 
 /// Load `path` if it exists, otherwise write the default file there and
@@ -319,9 +980,60 @@ mod tests {
         let defaults = ServerConfig::default();
         assert_eq!(cfg.network.bind, defaults.network.bind);
         assert_eq!(cfg.network.view_distance, defaults.network.view_distance);
+        assert_eq!(cfg.network.max_loaded_chunks, defaults.network.max_loaded_chunks);
+        assert_eq!(cfg.network.entity_view_distance, defaults.network.entity_view_distance);
+        assert_eq!(cfg.network.throttle_connections, defaults.network.throttle_connections);
+        assert_eq!(cfg.network.throttle_window_secs, defaults.network.throttle_window_secs);
         assert_eq!(cfg.world.dir, defaults.world.dir);
         assert_eq!(cfg.world.seed, defaults.world.seed);
         assert_eq!(cfg.dashboard.port, defaults.dashboard.port);
+        assert_eq!(cfg.dashboard.admin_token, defaults.dashboard.admin_token);
+        assert_eq!(cfg.dashboard.rule_sample_every, defaults.dashboard.rule_sample_every);
+        assert_eq!(cfg.dashboard.graph_window_nodes, defaults.dashboard.graph_window_nodes);
+        assert_eq!(cfg.dashboard.graph_window_ms, defaults.dashboard.graph_window_ms);
+        assert_eq!(cfg.access.dir, defaults.access.dir);
+        assert_eq!(cfg.access.whitelist, defaults.access.whitelist);
+        assert_eq!(cfg.chat.player_messages, defaults.chat.player_messages);
+        assert_eq!(cfg.chat.accept_unsigned, defaults.chat.accept_unsigned);
+        assert_eq!(cfg.skins.mojang, defaults.skins.mojang);
+        assert_eq!(cfg.skins.cache_dir, defaults.skins.cache_dir);
+        assert_eq!(cfg.skins.cache_ttl_secs, defaults.skins.cache_ttl_secs);
+        assert_eq!(cfg.edit.max_blocks, defaults.edit.max_blocks);
+        assert_eq!(cfg.mobs.enabled, defaults.mobs.enabled);
+        assert_eq!(cfg.mobs.cap, defaults.mobs.cap);
+        assert_eq!(cfg.combat.pvp, defaults.combat.pvp);
+        assert_eq!(cfg.border.diameter, defaults.border.diameter);
+        assert_eq!(cfg.border.damage_per_block, defaults.border.damage_per_block);
+        assert_eq!(cfg.border.warning_blocks, defaults.border.warning_blocks);
+        assert_eq!(cfg.rcon.enabled, defaults.rcon.enabled);
+        assert_eq!(cfg.rcon.bind, defaults.rcon.bind);
+        assert_eq!(cfg.query.enabled, defaults.query.enabled);
+        assert_eq!(cfg.query.bind, defaults.query.bind);
+        assert_eq!(cfg.proxy.mode, defaults.proxy.mode);
+        assert_eq!(cfg.proxy.velocity_secret, defaults.proxy.velocity_secret);
+        assert_eq!(cfg.proxy.addresses, defaults.proxy.addresses);
+        assert_eq!(cfg.tab_list.header, defaults.tab_list.header);
+        assert_eq!(cfg.tab_list.footer, defaults.tab_list.footer);
+        assert_eq!(cfg.tab_list.refresh_secs, defaults.tab_list.refresh_secs);
+        assert_eq!(cfg.status.motd, defaults.status.motd);
+        assert_eq!(cfg.status.icon, defaults.status.icon);
+        assert_eq!(cfg.status.reload_interval_secs, defaults.status.reload_interval_secs);
+        assert_eq!(cfg.audit.enabled, defaults.audit.enabled);
+        assert_eq!(cfg.audit.dir, defaults.audit.dir);
+        assert_eq!(cfg.audit.rotate_mb, defaults.audit.rotate_mb);
+        assert_eq!(cfg.audit.keep_files, defaults.audit.keep_files);
+        assert_eq!(cfg.claims.per_player, defaults.claims.per_player);
+        assert_eq!(cfg.claims.bypass_level, defaults.claims.bypass_level);
+        assert_eq!(cfg.scripts.enabled, defaults.scripts.enabled);
+        assert_eq!(cfg.scripts.dir, defaults.scripts.dir);
+        assert_eq!(cfg.scripts.max_operations, defaults.scripts.max_operations);
+        assert_eq!(cfg.scripts.budget_us, defaults.scripts.budget_us);
+        assert_eq!(cfg.scripts.reload_interval_secs, defaults.scripts.reload_interval_secs);
+        assert_eq!(cfg.crafting.data_dir, defaults.crafting.data_dir);
+        assert_eq!(cfg.crafting.recipe_book, defaults.crafting.recipe_book);
+        assert_eq!(cfg.trading.file, defaults.trading.file);
+        assert_eq!(cfg.watchdog.max_events, defaults.watchdog.max_events);
+        assert_eq!(cfg.watchdog.max_millis, defaults.watchdog.max_millis);
     }
 
     #[test]
@@ -341,5 +1053,7 @@ mod tests {
         // Unset fields default.
         assert_eq!(cfg.network.bind, NetworkConfig::default().bind);
         assert_eq!(cfg.dashboard.port, DashboardConfig::default().port);
+        assert_eq!(cfg.physics.cascade_threads, 0);
+        assert_eq!(cfg.physics.step_budget, crate::physics::DEFAULT_STEP_BUDGET);
     }
 }
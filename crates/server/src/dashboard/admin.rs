@@ -0,0 +1,331 @@
+//! Authenticated admin endpoints under `/api/`.
+//!
+//! Every request must carry `Authorization: Bearer <dashboard.admin_token>`.
+//! The dashboard starts before the player registry, physics service and
+//! storage exist, so those are attached afterwards ([`DashboardState::attach_admin`]);
+//! until then — and always, when no token is configured — the endpoints
+//! answer 503.
+//!
+//! | method | path                        | body / query                         |
+//! |--------|-----------------------------|--------------------------------------|
+//! | GET    | `/api/players`              |                                      |
+//! | POST   | `/api/players/:name/kick`   | `{"reason"?}`                        |
+//! | POST   | `/api/broadcast`            | `{"message"}`                        |
+//! | POST   | `/api/save`                 |                                      |
+//! | GET    | `/api/block`                | `?x=&y=&z=`                          |
+//! | POST   | `/api/events`               | `{"kind":"set"\|"notify","x","y","z","block"?}` |
+//!
+//! Errors are `{"error": "..."}` with a matching status code.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use ultimate_engine::causal::event::{Event, EventPayload};
+use ultimate_engine::world::position::BlockPos;
+
+use super::DashboardState;
+use crate::persistence::WorldStorage;
+use crate::physics::PhysicsHandle;
+use crate::player_registry::PlayerRegistry;
+use crate::pools::Pools;
+
+/// Server handles the admin endpoints act on.
+pub struct Admin {
+    pub token: String,
+    pub registry: Arc<PlayerRegistry>,
+    pub physics: PhysicsHandle,
+    pub storage: Arc<WorldStorage>,
+    pub pools: Arc<Pools>,
+}
+
+type Shared = Arc<DashboardState>;
+
+/// The admin routes, behind the token check.
+pub fn routes(state: Shared) -> Router<Shared> {
+    Router::new()
+        .route("/api/players", get(list_players))
+        .route("/api/players/:name/kick", post(kick))
+        .route("/api/broadcast", post(broadcast))
+        .route("/api/save", post(save))
+        .route("/api/block", get(query_block))
+        .route("/api/events", post(inject_event))
+        .route_layer(middleware::from_fn_with_state(state, require_token))
+}
+
+fn error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(json!({ "error": message.into() }))).into_response()
+}
+
+/// Reject requests without the configured bearer token.
+async fn require_token(State(state): State<Shared>, request: Request, next: Next) -> Response {
+    let Some(admin) = state.admin() else {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "admin API disabled");
+    };
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match presented {
+        Some(token) if token_matches(token, &admin.token) => next.run(request).await,
+        _ => error(StatusCode::UNAUTHORIZED, "missing or invalid admin token"),
+    }
+}
+
+/// Compare without short-circuiting on the first differing byte.
+pub(crate) fn token_matches(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// The admin handles; the middleware has already checked they exist.
+fn admin(state: &DashboardState) -> &Admin {
+    state.admin().expect("admin routes run behind require_token")
+}
+
+#[derive(Serialize)]
+struct PlayerJson {
+    name: String,
+    uuid: uuid::Uuid,
+    entity_id: i32,
+    pos: [f64; 3],
+}
+
+async fn list_players(State(state): State<Shared>) -> Json<Vec<PlayerJson>> {
+    let mut players: Vec<PlayerJson> = admin(&state)
+        .registry
+        .snapshot()
+        .into_iter()
+        .map(|p| PlayerJson { name: p.name, uuid: p.uuid, entity_id: p.entity_id, pos: [p.x, p.y, p.z] })
+        .collect();
+    players.sort_by(|a, b| a.name.cmp(&b.name));
+    Json(players)
+}
+
+#[derive(Deserialize, Default)]
+struct KickBody {
+    reason: Option<String>,
+}
+
+async fn kick(
+    State(state): State<Shared>,
+    Path(name): Path<String>,
+    body: Option<Json<KickBody>>,
+) -> Response {
+    let registry = &admin(&state).registry;
+    let Some(player) = registry.find_by_name(&name) else {
+        return error(StatusCode::NOT_FOUND, format!("{} is not online", name));
+    };
+    let reason = body.and_then(|Json(b)| b.reason).unwrap_or_else(|| "Kicked by an operator".into());
+    registry.kick(player.uuid, &reason);
+    tracing::info!("Admin API kicked {}: {}", player.name, reason);
+    Json(json!({ "kicked": player.name })).into_response()
+}
+
+#[derive(Deserialize)]
+struct BroadcastBody {
+    message: String,
+}
+
+async fn broadcast(State(state): State<Shared>, Json(body): Json<BroadcastBody>) -> Response {
+    if body.message.trim().is_empty() {
+        return error(StatusCode::BAD_REQUEST, "message is empty");
+    }
+    let admin = admin(&state);
+    admin.registry.announce(&body.message);
+    Json(json!({ "recipients": admin.registry.player_count() })).into_response()
+}
+
+async fn save(State(state): State<Shared>) -> Response {
+    let admin = admin(&state);
+    let (world, storage) = (Arc::clone(&state.world), Arc::clone(&admin.storage));
+    match admin.pools.run_blocking(move || storage.save(&world)).await {
+        Ok(chunks) => {
+            tracing::info!("Admin API save complete: {} chunks", chunks);
+            Json(json!({ "chunks": chunks })).into_response()
+        }
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, format!("save failed: {:#}", e)),
+    }
+}
+
+#[derive(Deserialize)]
+struct BlockQuery {
+    x: i64,
+    y: i64,
+    z: i64,
+}
+
+async fn query_block(State(state): State<Shared>, Query(q): Query<BlockQuery>) -> Json<serde_json::Value> {
+    let pos = BlockPos::new(q.x, q.y, q.z);
+    let id = state.world.get_block(pos);
+    Json(json!({
+        "id": id.0,
+        "name": crate::block::name(id),
+        "loaded": state.world.has_chunk(pos.chunk()),
+    }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum EventKind {
+    Set,
+    Notify,
+}
+
+#[derive(Deserialize)]
+struct EventBody {
+    kind: EventKind,
+    x: i64,
+    y: i64,
+    z: i64,
+    /// Block name for `set` (`stone`, `minecraft:sand`, ...).
+    block: Option<String>,
+}
+
+/// Submit a root event to the physics service, like a simulation layer.
+/// A `set` observes the current block as its `old`, so it applies unless
+/// something else writes the cell first.
+async fn inject_event(State(state): State<Shared>, Json(body): Json<EventBody>) -> Response {
+    let pos = BlockPos::new(body.x, body.y, body.z);
+    let payload = match body.kind {
+        EventKind::Notify => EventPayload::BlockNotify { pos, from: None },
+        EventKind::Set => {
+            let Some(name) = body.block else {
+                return error(StatusCode::BAD_REQUEST, "`set` needs a block");
+            };
+            let Some(new) = crate::block::block_id_from_name(&name) else {
+                return error(StatusCode::BAD_REQUEST, format!("unknown block {}", name));
+            };
+            EventPayload::BlockSet { pos, old: state.world.get_block(pos), new }
+        }
+    };
+    admin(&state).physics.submit_events(vec![Event { payload }]);
+    Json(json!({ "submitted": true })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PhysicsConfig;
+    use crate::worldgen::biome::Biome;
+    use crate::worldgen::pipeline::FlatPipeline;
+    use ultimate_engine::world::World;
+
+    #[test]
+    fn test_token_matches_exactly() {
+        assert!(token_matches("s3cret", "s3cret"));
+        assert!(!token_matches("s3cre", "s3cret"));
+        assert!(!token_matches("s3creT", "s3cret"));
+        assert!(!token_matches("", "s3cret"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_require_token_and_act() {
+        let world = Arc::new(World::new());
+        let state = Arc::new(DashboardState::new(Arc::clone(&world)));
+        let spatial = crate::event_bus::SpatialBus::new();
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_admin_api");
+        let _ = std::fs::remove_dir_all(&tmp);
+        let base: Arc<dyn crate::worldgen::WorldGen> = Arc::new(FlatPipeline {
+            min_y: -64,
+            layers: vec![(crate::block::STONE, 4)],
+            biome: Biome::Plains,
+        });
+        let cfg = PhysicsConfig { cascade_threads: 1, blocking_threads: 1, ..Default::default() };
+        let registry = Arc::new(PlayerRegistry::new(Arc::clone(&spatial)));
+        let mut events = registry.subscribe();
+        state.attach_admin(Admin {
+            token: "s3cret".into(),
+            registry,
+            physics: crate::physics::start(
+                Arc::clone(&world),
+                crate::rules::standard,
+                spatial,
+                None,
+                crate::physics::PhysicsOptions { workers: 1, ..Default::default() },
+            ),
+            storage: Arc::new(WorldStorage::new(
+                tmp.clone(), 1, 7, base, crate::persistence::new_delta_store(),
+            )),
+            pools: Arc::new(Pools::new(&cfg, Arc::clone(&state)).unwrap()),
+        });
+
+        let app = routes(Arc::clone(&state)).with_state(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = reqwest::Client::new();
+        let url = |path: &str| format!("{base_url}{path}");
+
+        let anon = client.get(url("/api/players")).send().await.unwrap();
+        assert_eq!(anon.status(), StatusCode::UNAUTHORIZED);
+        let wrong = client.get(url("/api/players")).bearer_auth("nope").send().await.unwrap();
+        assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+
+        let players = client.get(url("/api/players")).bearer_auth("s3cret").send().await.unwrap();
+        assert_eq!(players.status(), StatusCode::OK);
+        assert_eq!(players.json::<serde_json::Value>().await.unwrap(), json!([]));
+
+        let kick = client.post(url("/api/players/Steve/kick")).bearer_auth("s3cret").send().await.unwrap();
+        assert_eq!(kick.status(), StatusCode::NOT_FOUND);
+
+        let sent = client
+            .post(url("/api/broadcast"))
+            .bearer_auth("s3cret")
+            .json(&json!({ "message": "restarting soon" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(sent.status(), StatusCode::OK);
+        match events.try_recv().unwrap() {
+            crate::player_registry::PlayerEvent::Announcement { message } => {
+                assert_eq!(message, "restarting soon")
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        let set = client
+            .post(url("/api/events"))
+            .bearer_auth("s3cret")
+            .json(&json!({ "kind": "set", "x": 1, "y": 2, "z": 3, "block": "minecraft:stone" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(set.status(), StatusCode::OK);
+        let pos = BlockPos::new(1, 2, 3);
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while world.get_block(pos) != crate::block::STONE && std::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        let block: serde_json::Value = client
+            .get(url("/api/block?x=1&y=2&z=3"))
+            .bearer_auth("s3cret")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(block["name"], "stone");
+
+        let saved: serde_json::Value = client
+            .post(url("/api/save"))
+            .bearer_auth("s3cret")
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(saved["chunks"], 1);
+
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
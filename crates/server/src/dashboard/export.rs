@@ -0,0 +1,183 @@
+//! `/api/graph.dot` and `/api/graph.json`: the retained cascades (see
+//! [`super::graph_store`]) merged into one graph, filtered and capped so a
+//! flood can't turn into a 100 MB response.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::graph_store::CascadeEntry;
+use super::GraphNode;
+
+/// Node cap when the request doesn't give one.
+pub const DEFAULT_LIMIT: usize = 2_000;
+
+/// Node cap a request can't raise past.
+pub const MAX_LIMIT: usize = 20_000;
+
+/// Which nodes to export. All fields optional in the query string.
+#[derive(Debug, Default, Deserialize)]
+pub struct GraphFilter {
+    /// Only this kind (`block_set`, `block_notify`, `light_set`, ...).
+    pub kind: Option<String>,
+    /// Only nodes within `radius` blocks (Chebyshev) of `x,y,z`.
+    pub x: Option<i64>,
+    pub y: Option<i64>,
+    pub z: Option<i64>,
+    pub radius: Option<i64>,
+    /// Node cap, at most [`MAX_LIMIT`].
+    pub limit: Option<usize>,
+}
+
+impl GraphFilter {
+    fn keeps(&self, node: &GraphNode) -> bool {
+        if self.kind.as_ref().is_some_and(|kind| *kind != node.kind) {
+            return false;
+        }
+        if let (Some(x), Some(y), Some(z)) = (self.x, self.y, self.z) {
+            let radius = self.radius.unwrap_or(0);
+            let [nx, ny, nz] = node.pos;
+            return (nx - x).abs() <= radius && (ny - y).abs() <= radius && (nz - z).abs() <= radius;
+        }
+        true
+    }
+
+    fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT)
+    }
+}
+
+/// The merged graph. Node ids are renumbered across cascades; `seq` on
+/// each node says which cascade it came from.
+#[derive(Serialize)]
+pub struct GraphExport {
+    pub nodes: Vec<ExportNode>,
+    pub edges: Vec<[u32; 2]>,
+    /// Nodes that matched the filter, before the cap.
+    pub matched: usize,
+    pub truncated: bool,
+}
+
+#[derive(Serialize)]
+pub struct ExportNode {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub node: GraphNode,
+}
+
+/// Merge `entries` (oldest first, as the store returns them), newest
+/// cascades first until the cap. Edges are kept where both ends are.
+pub fn merge(entries: &[Arc<CascadeEntry>], filter: &GraphFilter) -> GraphExport {
+    let limit = filter.limit();
+    let mut out = GraphExport { nodes: Vec::new(), edges: Vec::new(), matched: 0, truncated: false };
+    for entry in entries.iter().rev() {
+        let mut ids: HashMap<u32, u32> = HashMap::new();
+        for node in entry.graph.nodes.iter().filter(|n| filter.keeps(n)) {
+            out.matched += 1;
+            if out.nodes.len() == limit {
+                out.truncated = true;
+                continue;
+            }
+            let id = out.nodes.len() as u32;
+            ids.insert(node.id, id);
+            out.nodes.push(ExportNode { seq: entry.seq, node: GraphNode { id, ..node.clone() } });
+        }
+        out.edges.extend(
+            entry
+                .graph
+                .edges
+                .iter()
+                .filter_map(|[parent, child]| Some([*ids.get(parent)?, *ids.get(child)?])),
+        );
+    }
+    out
+}
+
+/// Graphviz DOT, one cluster per cascade, colored like
+/// `CausalGraph::to_dot`.
+pub fn to_dot(export: &GraphExport) -> String {
+    let mut out = String::from(
+        "digraph causal {\n  rankdir=BT;\n  node [shape=box, fontname=\"monospace\", fontsize=10];\n",
+    );
+    let mut seq = None;
+    for n in &export.nodes {
+        if seq != Some(n.seq) {
+            if seq.is_some() {
+                out.push_str("  }\n");
+            }
+            seq = Some(n.seq);
+            let _ = writeln!(out, "  subgraph cluster_{} {{\n    label=\"cascade {}\";", n.seq, n.seq);
+        }
+        let color = match n.node.kind.as_str() {
+            "block_set" => "#d4edda",
+            "block_notify" => "#fff3cd",
+            "light_set" => "#cce5ff",
+            "light_notify" => "#e2e3e5",
+            _ => "#f5d0fe",
+        };
+        let fill = if n.node.executed { color } else { "#f8f9fa" };
+        let label = n.node.label.replace('\\', "\\\\").replace('"', "\\\"");
+        let _ = writeln!(out, "    n{} [label=\"{}\", style=filled, fillcolor=\"{}\"];", n.node.id, label, fill);
+    }
+    if seq.is_some() {
+        out.push_str("  }\n");
+    }
+    for [parent, child] in &export.edges {
+        let _ = writeln!(out, "  n{parent} -> n{child};");
+    }
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dashboard::graph_store::GraphSource;
+    use crate::dashboard::GraphSnapshot;
+
+    fn entry(seq: u64, kinds: &[&str]) -> Arc<CascadeEntry> {
+        let nodes = kinds
+            .iter()
+            .enumerate()
+            .map(|(i, kind)| GraphNode {
+                id: i as u32,
+                kind: kind.to_string(),
+                label: format!("\"{kind}\" {i}"),
+                pos: [i as i64, 0, 0],
+                executed: true,
+                depth: i as u32,
+            })
+            .collect();
+        let edges = (1..kinds.len() as u32).map(|i| [i - 1, i]).collect();
+        Arc::new(CascadeEntry { seq, source: GraphSource::Player, worker: 0, graph: GraphSnapshot { nodes, edges } })
+    }
+
+    #[test]
+    fn test_merge_filters_caps_and_renumbers() {
+        let entries = [entry(1, &["block_set", "block_notify"]), entry(2, &["block_set", "block_set", "block_notify"])];
+
+        let all = merge(&entries, &GraphFilter::default());
+        assert_eq!((all.nodes.len(), all.matched, all.truncated), (5, 5, false));
+        assert_eq!(all.nodes[0].seq, 2, "newest cascade first");
+        assert_eq!(all.edges, vec![[0, 1], [1, 2], [3, 4]]);
+
+        let sets = merge(&entries, &GraphFilter { kind: Some("block_set".into()), ..Default::default() });
+        assert_eq!(sets.nodes.len(), 3);
+        assert_eq!(sets.edges, vec![[0, 1]], "edges to filtered-out nodes drop");
+
+        let near = GraphFilter { x: Some(2), y: Some(0), z: Some(0), radius: Some(0), ..Default::default() };
+        assert_eq!(merge(&entries, &near).nodes.len(), 1);
+
+        let capped = merge(&entries, &GraphFilter { limit: Some(4), ..Default::default() });
+        assert_eq!((capped.nodes.len(), capped.matched, capped.truncated), (4, 5, true));
+        let huge = GraphFilter { limit: Some(usize::MAX), ..Default::default() };
+        assert_eq!(huge.limit(), MAX_LIMIT);
+
+        let dot = to_dot(&all);
+        assert_eq!(dot.matches("subgraph cluster_").count(), 2);
+        assert!(dot.contains("n3 -> n4;"));
+        assert!(dot.contains("label=\"\\\"block_set\\\" 0\""), "quotes escaped");
+    }
+}
@@ -0,0 +1,239 @@
+//! Recent cascades from every physics worker, merged for the dashboard.
+//!
+//! Each worker publishes one [`CascadeEntry`] per batch, holding only the
+//! nodes that batch inserted and tagged with what started it. Entries are
+//! numbered; a WebSocket client remembers the last number it saw and is
+//! sent just the newer entries plus the oldest number still retained, so
+//! it can drop what the store has let go.
+//!
+//! Retention is by age and by total node count, whichever bites first, so
+//! one enormous flood can't push every other source off the screen for
+//! long — and a quiet server's view empties out instead of freezing.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::watch;
+
+use super::GraphSnapshot;
+
+/// Entries older than this are dropped.
+const MAX_AGE: Duration = Duration::from_secs(30);
+
+/// Node budget across all retained entries; oldest entries go first.
+const MAX_NODES: usize = 1500;
+
+/// What started a cascade.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphSource {
+    /// A player's block action.
+    Player,
+    /// Root events from a simulation layer (or the admin API).
+    Simulation,
+    /// Consequents forwarded from another partition or node.
+    Forwarded,
+}
+
+/// One worker batch's slice of the causal graph.
+#[derive(Serialize)]
+pub struct CascadeEntry {
+    pub seq: u64,
+    pub source: GraphSource,
+    pub worker: usize,
+    #[serde(flatten)]
+    pub graph: GraphSnapshot,
+}
+
+/// What a client needs to catch up: entries after its last seen `seq`,
+/// and the oldest retained `seq` (anything older should be dropped).
+#[derive(Serialize)]
+pub struct GraphDelta {
+    #[serde(serialize_with = "serialize_shared")]
+    pub added: Vec<Arc<CascadeEntry>>,
+    pub retained_from: u64,
+}
+
+fn serialize_shared<S: serde::Serializer>(entries: &[Arc<CascadeEntry>], s: S) -> Result<S::Ok, S::Error> {
+    s.collect_seq(entries.iter().map(|e| &**e))
+}
+
+pub struct GraphStore {
+    entries: Mutex<Entries>,
+    /// Latest `seq`, for waking WebSocket clients.
+    seq_tx: watch::Sender<u64>,
+}
+
+#[derive(Default)]
+struct Entries {
+    list: VecDeque<(Instant, Arc<CascadeEntry>)>,
+    nodes: usize,
+    next_seq: u64,
+}
+
+impl Entries {
+    fn expire(&mut self, now: Instant) {
+        while let Some((at, entry)) = self.list.front() {
+            let too_old = now.duration_since(*at) > MAX_AGE;
+            // Always keep the newest entry, however large.
+            let over_budget = self.nodes > MAX_NODES && self.list.len() > 1;
+            if !too_old && !over_budget {
+                break;
+            }
+            self.nodes -= entry.graph.nodes.len();
+            self.list.pop_front();
+        }
+    }
+
+    fn retained_from(&self) -> u64 {
+        self.list.front().map_or(self.next_seq, |(_, e)| e.seq)
+    }
+}
+
+impl GraphStore {
+    pub fn new() -> Self {
+        let (seq_tx, _) = watch::channel(0);
+        Self { entries: Mutex::new(Entries { next_seq: 1, ..Default::default() }), seq_tx }
+    }
+
+    /// Record a batch's snapshot. Empty snapshots are ignored.
+    pub fn push(&self, source: GraphSource, worker: usize, graph: GraphSnapshot) {
+        self.push_at(Instant::now(), source, worker, graph);
+    }
+
+    fn push_at(&self, now: Instant, source: GraphSource, worker: usize, graph: GraphSnapshot) {
+        if graph.nodes.is_empty() {
+            return;
+        }
+        let seq = {
+            let mut entries = self.entries.lock().expect("graph store poisoned");
+            let seq = entries.next_seq;
+            entries.next_seq += 1;
+            entries.nodes += graph.nodes.len();
+            entries.list.push_back((now, Arc::new(CascadeEntry { seq, source, worker, graph })));
+            entries.expire(now);
+            seq
+        };
+        let _ = self.seq_tx.send(seq);
+    }
+
+    /// Entries newer than `after` (0 for everything retained).
+    pub fn since(&self, after: u64) -> GraphDelta {
+        self.since_at(Instant::now(), after)
+    }
+
+    fn since_at(&self, now: Instant, after: u64) -> GraphDelta {
+        let mut entries = self.entries.lock().expect("graph store poisoned");
+        entries.expire(now);
+        GraphDelta {
+            added: entries
+                .list
+                .iter()
+                .filter(|(_, e)| e.seq > after)
+                .map(|(_, e)| Arc::clone(e))
+                .collect(),
+            retained_from: entries.retained_from(),
+        }
+    }
+
+    /// The causal chains through `pos` in the `limit` most recent
+    /// retained cascades that touched it, newest first.
+    pub fn chains_at(&self, pos: [i64; 3], limit: usize) -> Vec<CascadeEntry> {
+        let mut entries = self.entries.lock().expect("graph store poisoned");
+        entries.expire(Instant::now());
+        entries
+            .list
+            .iter()
+            .rev()
+            .filter_map(|(_, e)| {
+                let graph = e.graph.chain_at(pos)?;
+                Some(CascadeEntry { seq: e.seq, source: e.source, worker: e.worker, graph })
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Wakes whenever a new entry is pushed.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.seq_tx.subscribe()
+    }
+}
+
+impl Default for GraphStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dashboard::GraphNode;
+
+    fn graph(nodes: usize) -> GraphSnapshot {
+        GraphSnapshot {
+            nodes: (0..nodes as u32)
+                .map(|id| GraphNode {
+                    id,
+                    kind: "block_set".into(),
+                    label: String::new(),
+                    pos: [0, 0, 0],
+                    executed: true,
+                    depth: 0,
+                })
+                .collect(),
+            edges: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_chains_at_follow_edges_both_ways() {
+        // 0 -> 1 -> 2 at x = 0, 1, 2, with a sibling 0 -> 3 and a stray 4.
+        let mut g = graph(5);
+        for (i, node) in g.nodes.iter_mut().enumerate() {
+            node.pos = [i as i64, 0, 0];
+        }
+        g.edges = vec![[0, 1], [1, 2], [0, 3]];
+        let store = GraphStore::new();
+        store.push(GraphSource::Player, 0, g);
+        store.push(GraphSource::Simulation, 1, graph(3));
+
+        let chains = store.chains_at([1, 0, 0], 5);
+        assert_eq!(chains.len(), 1, "only the first cascade touched x = 1");
+        let ids: Vec<u32> = chains[0].graph.nodes.iter().map(|n| n.id).collect();
+        assert_eq!(ids, vec![0, 1, 2], "cause and effect, not the sibling");
+        assert_eq!(chains[0].graph.edges, vec![[0, 1], [1, 2]]);
+        assert_eq!(store.chains_at([0, 0, 0], 5).iter().map(|c| c.seq).collect::<Vec<_>>(), vec![2, 1]);
+        assert!(store.chains_at([7, 7, 7], 5).is_empty());
+    }
+
+    #[test]
+    fn test_sources_merge_and_retire_by_age_and_size() {
+        let store = GraphStore::new();
+        let t0 = Instant::now();
+        store.push_at(t0, GraphSource::Player, 0, graph(10));
+        store.push_at(t0, GraphSource::Simulation, 1, graph(0)); // ignored
+        store.push_at(t0 + Duration::from_secs(5), GraphSource::Simulation, 1, graph(20));
+
+        let all = store.since_at(t0 + Duration::from_secs(6), 0);
+        let tags: Vec<_> = all.added.iter().map(|e| (e.seq, e.source, e.worker)).collect();
+        assert_eq!(tags, vec![(1, GraphSource::Player, 0), (2, GraphSource::Simulation, 1)]);
+        assert_eq!(all.retained_from, 1);
+
+        // Incremental: only what's new since the client's last seq.
+        let delta = store.since_at(t0 + Duration::from_secs(6), 2);
+        assert!(delta.added.is_empty());
+
+        // The player cascade ages out first.
+        let later = store.since_at(t0 + MAX_AGE + Duration::from_secs(1), 2);
+        assert_eq!(later.retained_from, 2);
+
+        // A flood over the node budget evicts older entries, but not itself.
+        store.push_at(t0 + Duration::from_secs(7), GraphSource::Forwarded, 2, graph(MAX_NODES));
+        let flooded = store.since_at(t0 + Duration::from_secs(8), 0);
+        assert_eq!(flooded.added.len(), 1);
+        assert_eq!(flooded.retained_from, 3);
+    }
+}
@@ -0,0 +1,210 @@
+//! Top-down world map tiles for the dashboard.
+//!
+//! One tile per chunk: a 16×16 PNG coloured by the topmost non-air block
+//! of each column and shaded by its height. Tiles are rendered on request
+//! (`/api/map?cx=..&cz=..`); nothing is cached server-side, so a fetch
+//! always reflects the live world.
+//!
+//! Change tracking is a [`WorldObserver`] that marks chunks dirty. The web
+//! server drains the set a few times a second and pushes the chunk list
+//! to browsers, which re-fetch just those tiles.
+
+use std::io::Write;
+use std::sync::Arc;
+
+use azalea_block::{BlockState, BlockTrait};
+use dashmap::DashSet;
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use ultimate_engine::world::World;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::observer::WorldObserver;
+use ultimate_engine::world::position::{BlockPos, ChunkPos};
+
+use crate::block::{self, FluidKind};
+
+/// Pixels per tile edge — one per block column.
+pub const TILE_SIZE: usize = 16;
+
+/// Chunks whose tiles changed since the last drain.
+#[derive(Default)]
+pub struct DirtyTiles(DashSet<ChunkPos>);
+
+impl DirtyTiles {
+    /// Take every dirty chunk, clearing the set.
+    pub fn drain(&self) -> Vec<ChunkPos> {
+        let chunks: Vec<ChunkPos> = self.0.iter().map(|c| *c).collect();
+        for chunk in &chunks {
+            self.0.remove(chunk);
+        }
+        chunks
+    }
+}
+
+/// Registered with the world by `DashboardState::new`.
+pub(crate) struct MapObserver(pub Arc<DirtyTiles>);
+
+impl WorldObserver for MapObserver {
+    fn block_set(&self, pos: BlockPos, old: BlockId, new: BlockId, _tracked: bool) {
+        // Cheap pre-check first: a cascade writes the same chunk many times.
+        let chunk = pos.chunk();
+        if old != new && !self.0.0.contains(&chunk) {
+            self.0.0.insert(chunk);
+        }
+    }
+
+    fn chunk_inserted(&self, pos: ChunkPos) {
+        self.0.0.insert(pos);
+    }
+
+    fn chunk_removed(&self, pos: ChunkPos) {
+        self.0.0.insert(pos);
+    }
+}
+
+/// Render chunk `(cx, cz)` as a PNG tile, or `None` if it isn't loaded.
+pub fn render_tile(world: &World, cx: i32, cz: i32) -> Option<Vec<u8>> {
+    let chunk = world.get_chunk(&ChunkPos::new(cx, cz))?;
+    let mut sections: Vec<_> = chunk.sections().map(|(&idx, s)| (idx, s)).collect();
+    sections.sort_unstable_by_key(|&(idx, _)| std::cmp::Reverse(idx));
+
+    let mut rgb = vec![0u8; TILE_SIZE * TILE_SIZE * 3];
+    for z in 0..TILE_SIZE as u8 {
+        for x in 0..TILE_SIZE as u8 {
+            let top = sections.iter().find_map(|&(idx, section)| {
+                (0..TILE_SIZE as u8).rev().find_map(|y| {
+                    let id = section.get(x, y, z);
+                    (id != BlockId::AIR).then(|| (id, idx as i64 * TILE_SIZE as i64 + y as i64))
+                })
+            });
+            let pixel = match top {
+                Some((id, y)) => shade(color(id), y),
+                None => [0, 0, 0],
+            };
+            let i = (z as usize * TILE_SIZE + x as usize) * 3;
+            rgb[i..i + 3].copy_from_slice(&pixel);
+        }
+    }
+    Some(encode_png(TILE_SIZE as u32, TILE_SIZE as u32, &rgb))
+}
+
+/// Map colour for a block, by fluid kind or by name.
+fn color(id: BlockId) -> [u8; 3] {
+    match block::fluid_kind(id) {
+        Some((FluidKind::Water, _)) => return [56, 92, 212],
+        Some((FluidKind::Lava, _)) => return [224, 96, 16],
+        None => {}
+    }
+    let Ok(state) = BlockState::try_from(id.0 as u32) else {
+        return [255, 0, 255];
+    };
+    let block: Box<dyn BlockTrait> = Box::<dyn BlockTrait>::from(state);
+    let name = block.id();
+    const TABLE: &[(&str, [u8; 3])] = &[
+        ("grass_block", [96, 160, 56]),
+        ("leaves", [48, 112, 40]),
+        ("snow", [240, 244, 250]),
+        ("ice", [160, 188, 240]),
+        ("sand", [218, 204, 148]),
+        ("gravel", [136, 132, 128]),
+        ("clay", [160, 166, 180]),
+        ("dirt", [134, 96, 66]),
+        ("mud", [90, 76, 70]),
+        ("log", [110, 84, 50]),
+        ("wood", [110, 84, 50]),
+        ("planks", [162, 130, 78]),
+        ("grass", [80, 150, 48]),
+        ("fern", [80, 150, 48]),
+        ("flower", [200, 180, 60]),
+        ("water", [56, 92, 212]),
+        ("lava", [224, 96, 16]),
+        ("bedrock", [60, 60, 60]),
+    ];
+    TABLE
+        .iter()
+        .find(|(key, _)| name.contains(key))
+        .map_or([128, 128, 128], |&(_, rgb)| rgb)
+}
+
+/// Brighten high ground and darken low ground around sea level.
+fn shade(rgb: [u8; 3], y: i64) -> [u8; 3] {
+    let factor = (1.0 + (y - 64) as f32 / 128.0).clamp(0.55, 1.35);
+    rgb.map(|c| (c as f32 * factor).min(255.0) as u8)
+}
+
+/// Minimal truecolour PNG: one IDAT, filter type 0 on every row.
+pub fn encode_png(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    debug_assert_eq!(rgb.len(), width as usize * height as usize * 3);
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]); // 8-bit RGB, deflate, no interlace
+
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::fast());
+    for row in rgb.chunks(width as usize * 3) {
+        zlib.write_all(&[0]).and_then(|_| zlib.write_all(row)).expect("writing to a Vec");
+    }
+    let idat = zlib.finish().expect("writing to a Vec");
+
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut out, b"IHDR", &ihdr);
+    png_chunk(&mut out, b"IDAT", &idat);
+    png_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn png_chunk(out: &mut Vec<u8>, tag: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(tag);
+    out.extend_from_slice(data);
+    let mut crc = Crc::new();
+    crc.update(tag);
+    crc.update(data);
+    out.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+    use ultimate_engine::world::chunk::Chunk;
+    use ultimate_engine::world::position::LocalBlockPos;
+
+    #[test]
+    fn test_tile_colours_top_block_and_tracks_changes() {
+        let world = World::new();
+        let dirty = Arc::new(DirtyTiles::default());
+        world.add_observer(Box::new(MapObserver(Arc::clone(&dirty))));
+
+        let mut chunk = Chunk::new();
+        for x in 0..16u8 {
+            for z in 0..16u8 {
+                chunk.set_block(LocalBlockPos { x, y: 64, z }, block::STONE);
+            }
+        }
+        chunk.set_block(LocalBlockPos { x: 3, y: 70, z: 5 }, block::SAND);
+        world.insert_chunk(ChunkPos::new(2, -1), chunk);
+        assert_eq!(dirty.drain(), vec![ChunkPos::new(2, -1)]);
+        assert!(dirty.drain().is_empty());
+        assert!(render_tile(&world, 0, 0).is_none());
+
+        let png = render_tile(&world, 2, -1).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        // IDAT starts after signature (8) + IHDR chunk (25); skip its
+        // length + tag, inflate, and read pixels back out of the rows.
+        let idat_len = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        let mut raw = Vec::new();
+        ZlibDecoder::new(&png[41..41 + idat_len]).read_to_end(&mut raw).unwrap();
+        let pixel = |x: usize, z: usize| {
+            let i = z * (1 + TILE_SIZE * 3) + 1 + x * 3;
+            [raw[i], raw[i + 1], raw[i + 2]]
+        };
+        assert_eq!(pixel(0, 0), shade(color(block::STONE), 64));
+        assert_eq!(pixel(3, 5), shade(color(block::SAND), 70));
+
+        world.set_block(BlockPos::new(2 * 16 + 1, 64, -16 + 1), block::AIR);
+        world.set_block(BlockPos::new(2 * 16 + 1, 64, -16 + 1), block::AIR);
+        assert_eq!(dirty.drain(), vec![ChunkPos::new(2, -1)]);
+    }
+}
@@ -5,16 +5,92 @@
 //! them at its own pace.
 
 use serde::Serialize;
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use ultimate_engine::rules::RuleProfile;
+use ultimate_engine::world::block::BlockId;
+
+use crate::block;
+
+/// What a cascade's root block was, for per-mechanic cost accounting.
+/// A fixed set so each kind's counters stay plain atomics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CascadeKind {
+    /// Falling blocks (sand).
+    Gravity,
+    Water,
+    Lava,
+    /// A block replaced by air — mining, or anything knocking support out.
+    Break,
+    Other,
+}
+
+impl CascadeKind {
+    pub const ALL: [CascadeKind; 5] = [
+        CascadeKind::Gravity,
+        CascadeKind::Water,
+        CascadeKind::Lava,
+        CascadeKind::Break,
+        CascadeKind::Other,
+    ];
+
+    /// Classify by the block a root event placed (or, for a notify, the
+    /// block it re-evaluates).
+    pub fn of(id: BlockId) -> Self {
+        if id == block::AIR {
+            CascadeKind::Break
+        } else if block::has_gravity(id) {
+            CascadeKind::Gravity
+        } else if block::water_level(id).is_some() {
+            CascadeKind::Water
+        } else if block::lava_level(id).is_some() {
+            CascadeKind::Lava
+        } else {
+            CascadeKind::Other
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CascadeKind::Gravity => "gravity",
+            CascadeKind::Water => "water",
+            CascadeKind::Lava => "lava",
+            CascadeKind::Break => "break",
+            CascadeKind::Other => "other",
+        }
+    }
+}
+
+/// Why a connection was turned away before joining.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    /// `network.max_players` were already online.
+    Full,
+    /// Too many connections from its address (see `net::listener`).
+    Throttled,
+}
+
+/// Cumulative counters for one [`CascadeKind`].
+#[derive(Default)]
+struct KindCounters {
+    cascades: AtomicU64,
+    events_sum: AtomicU64,
+    ns_sum: AtomicU64,
+}
 
 /// Atomic performance counters. ~10 ns to update (a handful of `fetch_add`s).
 pub struct Metrics {
     // Monotonic counters
     events_executed: AtomicU64,
     cascades_completed: AtomicU64,
+    /// Batches the watchdog cut short (see `crate::watchdog`).
+    cascades_aborted: AtomicU64,
     cascade_events_sum: AtomicU64,
     cascade_ns_sum: AtomicU64,
+    // Per root block kind, indexed like `CascadeKind::ALL`.
+    kinds: [KindCounters; CascadeKind::ALL.len()],
 
     // Latency histogram buckets (cascade duration)
     hist_under_1us: AtomicU64,
@@ -23,9 +99,41 @@ pub struct Metrics {
     hist_100us_1ms: AtomicU64,
     hist_over_1ms: AtomicU64,
 
+    /// Bus block and light changes connections dropped because the
+    /// client didn't have the chunk.
+    updates_filtered: AtomicU64,
+
+    /// Connections turned away, by [`Rejection`].
+    rejected_full: AtomicU64,
+    rejected_throttled: AtomicU64,
+
     // Gauges
     players_connected: AtomicU64,
 
+    // Thread pools (see `crate::pools`). The cascade counter is shared
+    // with the scheduler, which adds to it from inside the pool.
+    cascade_busy_ns: Arc<AtomicU64>,
+    cascade_threads: AtomicU64,
+    /// `BlockSet`s the scheduler dropped as stale, shared with it like
+    /// `cascade_busy_ns`.
+    block_conflicts: Arc<AtomicU64>,
+    blocking_busy_ns: AtomicU64,
+    blocking_jobs: AtomicU64,
+    blocking_threads: AtomicU64,
+
+    // `--pregen` progress (see `crate::pregen`).
+    pregen_done: AtomicU64,
+    pregen_total: AtomicU64,
+
+    // Tick rate (see `crate::ticks`), as `f64` bits.
+    tps: AtomicU64,
+    mspt: AtomicU64,
+
+    /// Sampled per-rule timing, shared with every physics worker's
+    /// `RuleSet` (`RuleSet::with_profile`). Off until
+    /// [`Metrics::set_rule_sampling`].
+    rule_profile: Arc<RuleProfile>,
+
     started_at: Instant,
 }
 
@@ -34,25 +142,46 @@ impl Metrics {
         Self {
             events_executed: AtomicU64::new(0),
             cascades_completed: AtomicU64::new(0),
+            cascades_aborted: AtomicU64::new(0),
             cascade_events_sum: AtomicU64::new(0),
             cascade_ns_sum: AtomicU64::new(0),
+            kinds: Default::default(),
             hist_under_1us: AtomicU64::new(0),
             hist_1_10us: AtomicU64::new(0),
             hist_10_100us: AtomicU64::new(0),
             hist_100us_1ms: AtomicU64::new(0),
             hist_over_1ms: AtomicU64::new(0),
+            updates_filtered: AtomicU64::new(0),
+            rejected_full: AtomicU64::new(0),
+            rejected_throttled: AtomicU64::new(0),
             players_connected: AtomicU64::new(0),
+            cascade_busy_ns: Arc::new(AtomicU64::new(0)),
+            cascade_threads: AtomicU64::new(0),
+            block_conflicts: Arc::new(AtomicU64::new(0)),
+            blocking_busy_ns: AtomicU64::new(0),
+            blocking_jobs: AtomicU64::new(0),
+            blocking_threads: AtomicU64::new(0),
+            pregen_done: AtomicU64::new(0),
+            pregen_total: AtomicU64::new(0),
+            tps: AtomicU64::new(20f64.to_bits()),
+            mspt: AtomicU64::new(0),
+            rule_profile: Arc::new(RuleProfile::new(0)),
             started_at: Instant::now(),
         }
     }
 
     /// Called after each `run_until_quiet()` completes. Zero-alloc, ~10 ns.
-    pub fn record_cascade(&self, events: u64, duration: Duration) {
+    pub fn record_cascade(&self, kind: CascadeKind, events: u64, duration: Duration) {
+        let ns = duration.as_nanos() as u64;
         self.events_executed.fetch_add(events, Relaxed);
         self.cascades_completed.fetch_add(1, Relaxed);
         self.cascade_events_sum.fetch_add(events, Relaxed);
-        self.cascade_ns_sum
-            .fetch_add(duration.as_nanos() as u64, Relaxed);
+        self.cascade_ns_sum.fetch_add(ns, Relaxed);
+
+        let k = &self.kinds[kind as usize];
+        k.cascades.fetch_add(1, Relaxed);
+        k.events_sum.fetch_add(events, Relaxed);
+        k.ns_sum.fetch_add(ns, Relaxed);
 
         let us = duration.as_micros() as u64;
         match us {
@@ -74,6 +203,24 @@ impl Metrics {
         }
     }
 
+    /// Called when the watchdog aborts a runaway batch.
+    pub fn record_aborted_cascade(&self) {
+        self.cascades_aborted.fetch_add(1, Relaxed);
+    }
+
+    /// Called when a connection drops bus updates outside its client's
+    /// loaded chunks.
+    pub fn record_filtered_updates(&self, count: u64) {
+        self.updates_filtered.fetch_add(count, Relaxed);
+    }
+
+    pub fn record_rejection(&self, reason: Rejection) {
+        match reason {
+            Rejection::Full => self.rejected_full.fetch_add(1, Relaxed),
+            Rejection::Throttled => self.rejected_throttled.fetch_add(1, Relaxed),
+        };
+    }
+
     pub fn player_joined(&self) {
         self.players_connected.fetch_add(1, Relaxed);
     }
@@ -82,6 +229,53 @@ impl Metrics {
         self.players_connected.fetch_sub(1, Relaxed);
     }
 
+    /// Record the configured pool sizes (utilization denominators).
+    pub fn set_pool_threads(&self, cascade: usize, blocking: usize) {
+        self.cascade_threads.store(cascade as u64, Relaxed);
+        self.blocking_threads.store(blocking as u64, Relaxed);
+    }
+
+    /// Counter the scheduler adds cascade-pool busy time to
+    /// (`Scheduler::with_busy_counter`).
+    pub fn cascade_busy_counter(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.cascade_busy_ns)
+    }
+
+    /// Counter the scheduler adds stale `BlockSet`s to
+    /// (`Scheduler::with_conflict_counter`).
+    pub fn block_conflict_counter(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.block_conflicts)
+    }
+
+    /// Called when a job on the blocking pool finishes.
+    pub fn record_blocking_job(&self, duration: Duration) {
+        self.blocking_jobs.fetch_add(1, Relaxed);
+        self.blocking_busy_ns
+            .fetch_add(duration.as_nanos() as u64, Relaxed);
+    }
+
+    /// Record chunk pre-generation progress.
+    pub fn set_pregen_progress(&self, done: usize, total: usize) {
+        self.pregen_done.store(done as u64, Relaxed);
+        self.pregen_total.store(total as u64, Relaxed);
+    }
+
+    /// The tick rate measured over the last window.
+    pub fn set_ticks(&self, stats: crate::ticks::TickStats) {
+        self.tps.store(stats.tps.to_bits(), Relaxed);
+        self.mspt.store(stats.mspt.to_bits(), Relaxed);
+    }
+
+    /// Time one rule evaluation in `every` (per thread); 0 disables.
+    pub fn set_rule_sampling(&self, every: u32) {
+        self.rule_profile.set_sample_every(every);
+    }
+
+    /// Profile to attach to physics `RuleSet`s.
+    pub fn rule_profile(&self) -> Arc<RuleProfile> {
+        Arc::clone(&self.rule_profile)
+    }
+
     /// Read all counters into a serializable snapshot.
     /// Called by the dashboard server (~every 200 ms), never by the hot path.
     pub fn snapshot(&self, chunks_loaded: u64) -> MetricsSnapshot {
@@ -89,10 +283,43 @@ impl Metrics {
             uptime_secs: self.started_at.elapsed().as_secs_f64(),
             events_total: self.events_executed.load(Relaxed),
             cascades_total: self.cascades_completed.load(Relaxed),
+            cascades_aborted: self.cascades_aborted.load(Relaxed),
             cascade_events_sum: self.cascade_events_sum.load(Relaxed),
             cascade_ns_sum: self.cascade_ns_sum.load(Relaxed),
+            kinds: CascadeKind::ALL
+                .iter()
+                .map(|&kind| {
+                    let k = &self.kinds[kind as usize];
+                    KindSnapshot {
+                        kind: kind.label(),
+                        cascades: k.cascades.load(Relaxed),
+                        events_sum: k.events_sum.load(Relaxed),
+                        ns_sum: k.ns_sum.load(Relaxed),
+                    }
+                })
+                .collect(),
             chunks_loaded,
             players: self.players_connected.load(Relaxed),
+            updates_filtered: self.updates_filtered.load(Relaxed),
+            rejected_full: self.rejected_full.load(Relaxed),
+            rejected_throttled: self.rejected_throttled.load(Relaxed),
+            cascade_threads: self.cascade_threads.load(Relaxed),
+            cascade_busy_ns: self.cascade_busy_ns.load(Relaxed),
+            block_conflicts: self.block_conflicts.load(Relaxed),
+            blocking_threads: self.blocking_threads.load(Relaxed),
+            blocking_busy_ns: self.blocking_busy_ns.load(Relaxed),
+            blocking_jobs: self.blocking_jobs.load(Relaxed),
+            pregen_done: self.pregen_done.load(Relaxed),
+            pregen_total: self.pregen_total.load(Relaxed),
+            tps: f64::from_bits(self.tps.load(Relaxed)),
+            mspt: f64::from_bits(self.mspt.load(Relaxed)),
+            rule_sample_every: self.rule_profile.sample_every(),
+            rules: self
+                .rule_profile
+                .snapshot()
+                .into_iter()
+                .map(|r| RuleSnapshot { rule: r.name, samples: r.samples, events: r.events, ns_sum: r.ns_sum, hist: r.hist })
+                .collect(),
             hist: [
                 self.hist_under_1us.load(Relaxed),
                 self.hist_1_10us.load(Relaxed),
@@ -117,10 +344,179 @@ pub struct MetricsSnapshot {
     pub uptime_secs: f64,
     pub events_total: u64,
     pub cascades_total: u64,
+    /// Batches the watchdog aborted for running past their budget.
+    pub cascades_aborted: u64,
     pub cascade_events_sum: u64,
     pub cascade_ns_sum: u64,
+    /// Cascade totals split by root block kind.
+    pub kinds: Vec<KindSnapshot>,
     pub chunks_loaded: u64,
     pub players: u64,
+    /// Block and light updates not forwarded to clients without the chunk.
+    pub updates_filtered: u64,
+    /// Connections refused because the server was full, and closed by
+    /// the per-IP throttle.
+    pub rejected_full: u64,
+    pub rejected_throttled: u64,
+    /// Pool sizes and cumulative busy time; utilization over an interval
+    /// is `Δbusy_ns / (threads × Δt)`.
+    pub cascade_threads: u64,
+    pub cascade_busy_ns: u64,
+    /// `BlockSet`s skipped because their cell changed after they were
+    /// emitted.
+    pub block_conflicts: u64,
+    pub blocking_threads: u64,
+    pub blocking_busy_ns: u64,
+    pub blocking_jobs: u64,
+    /// Chunks generated of those `--pregen` set out to (`0` of `0` when
+    /// not pre-generating).
+    pub pregen_done: u64,
+    pub pregen_total: u64,
+    /// Ticks per second and milliseconds per tick, as `/tps` shows them.
+    pub tps: f64,
+    pub mspt: f64,
+    /// Per-rule cost, from one evaluation in `rule_sample_every`.
+    pub rule_sample_every: u32,
+    pub rules: Vec<RuleSnapshot>,
     /// `[<1μs, 1-10μs, 10-100μs, 100μs-1ms, >1ms]`
     pub hist: [u64; 5],
 }
+
+/// Samples kept by [`History`]: one hour at one per second.
+pub const HISTORY_LEN: usize = 3600;
+
+/// One history sample: the cumulative counters the time-series charts
+/// plot. Rates come from diffing neighbours, as with live snapshots.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct HistoryPoint {
+    pub uptime_secs: f64,
+    pub events_total: u64,
+    pub cascades_total: u64,
+    pub cascade_events_sum: u64,
+    pub cascade_ns_sum: u64,
+    pub players: u64,
+    pub chunks_loaded: u64,
+}
+
+impl From<&MetricsSnapshot> for HistoryPoint {
+    fn from(s: &MetricsSnapshot) -> Self {
+        Self {
+            uptime_secs: s.uptime_secs,
+            events_total: s.events_total,
+            cascades_total: s.cascades_total,
+            cascade_events_sum: s.cascade_events_sum,
+            cascade_ns_sum: s.cascade_ns_sum,
+            players: s.players,
+            chunks_loaded: s.chunks_loaded,
+        }
+    }
+}
+
+/// Ring buffer of per-second samples, so a dashboard that (re)connects
+/// starts with the last hour instead of empty charts. Written once a
+/// second by the dashboard server's sampler, never by the hot path.
+#[derive(Default)]
+pub struct History {
+    points: Mutex<VecDeque<HistoryPoint>>,
+}
+
+impl History {
+    pub fn record(&self, point: HistoryPoint) {
+        let mut points = self.points.lock().expect("metrics history poisoned");
+        if points.len() == HISTORY_LEN {
+            points.pop_front();
+        }
+        points.push_back(point);
+    }
+
+    /// Samples taken strictly after `uptime_secs`, oldest first. Pass
+    /// `f64::NEG_INFINITY` for the whole buffer.
+    pub fn since(&self, uptime_secs: f64) -> Vec<HistoryPoint> {
+        let points = self.points.lock().expect("metrics history poisoned");
+        let start = points.partition_point(|p| p.uptime_secs <= uptime_secs);
+        points.range(start..).copied().collect()
+    }
+}
+
+/// Cumulative cascade totals for one root block kind. Averages are
+/// `events_sum / cascades` and `ns_sum / cascades`.
+#[derive(Clone, Serialize)]
+pub struct KindSnapshot {
+    pub kind: &'static str,
+    pub cascades: u64,
+    pub events_sum: u64,
+    pub ns_sum: u64,
+}
+
+/// Sampled totals for one rule. Averages are `ns_sum / samples` and
+/// `events / samples`.
+#[derive(Clone, Serialize)]
+pub struct RuleSnapshot {
+    pub rule: &'static str,
+    pub samples: u64,
+    pub events: u64,
+    pub ns_sum: u64,
+    /// `[<250ns, 250ns-1μs, 1-10μs, 10-100μs, >100μs]`
+    pub hist: [u64; 5],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cascades_split_by_root_kind() {
+        let m = Metrics::new();
+        assert_eq!(CascadeKind::of(block::SAND), CascadeKind::Gravity);
+        assert_eq!(CascadeKind::of(block::water_flowing(3)), CascadeKind::Water);
+        assert_eq!(CascadeKind::of(block::LAVA), CascadeKind::Lava);
+        assert_eq!(CascadeKind::of(block::AIR), CascadeKind::Break);
+        assert_eq!(CascadeKind::of(block::STONE), CascadeKind::Other);
+
+        m.record_cascade(CascadeKind::Water, 40, Duration::from_micros(30));
+        m.record_cascade(CascadeKind::Water, 20, Duration::from_micros(10));
+        m.record_cascade(CascadeKind::Gravity, 3, Duration::from_micros(2));
+        let snap = m.snapshot(0);
+        assert_eq!((snap.cascades_total, snap.cascade_events_sum), (3, 63));
+        let water = snap.kinds.iter().find(|k| k.kind == "water").unwrap();
+        assert_eq!((water.cascades, water.events_sum, water.ns_sum), (2, 60, 40_000));
+        let lava = snap.kinds.iter().find(|k| k.kind == "lava").unwrap();
+        assert_eq!(lava.cascades, 0);
+    }
+
+    #[test]
+    fn test_rule_profile_reaches_snapshot() {
+        let m = Metrics::new();
+        let stats = m.rule_profile().stats("water_spread");
+        stats.record(Duration::from_nanos(400), 3);
+        stats.record(Duration::from_micros(20), 5);
+        m.set_rule_sampling(16);
+        let snap = m.snapshot(0);
+        assert_eq!(snap.rule_sample_every, 16);
+        let water = &snap.rules[0];
+        assert_eq!((water.rule, water.samples, water.events, water.ns_sum), ("water_spread", 2, 8, 20_400));
+        assert_eq!(water.hist, [0, 1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_history_keeps_last_hour() {
+        let history = History::default();
+        let point = |t: usize| HistoryPoint {
+            uptime_secs: t as f64,
+            events_total: t as u64 * 10,
+            cascades_total: 0,
+            cascade_events_sum: 0,
+            cascade_ns_sum: 0,
+            players: 0,
+            chunks_loaded: 0,
+        };
+        for t in 0..HISTORY_LEN + 5 {
+            history.record(point(t));
+        }
+        let all = history.since(f64::NEG_INFINITY);
+        assert_eq!(all.len(), HISTORY_LEN);
+        assert_eq!(all[0], point(5), "oldest samples fall off the front");
+        let tail = history.since((HISTORY_LEN + 2) as f64);
+        assert_eq!(tail, vec![point(HISTORY_LEN + 3), point(HISTORY_LEN + 4)]);
+    }
+}
@@ -2,53 +2,119 @@
 //!
 //! Design contract with the physics hot path:
 //!   • Metrics: atomic fetch_add (~10 ns, zero-alloc, never blocks).
-//!   • Graph snapshot: published via `tokio::sync::watch` (non-blocking send,
-//!     overwrites previous value — if the dashboard is slow it just sees the
-//!     latest snapshot, never stalling the engine).
+//!   • Graph snapshots: each worker batch adds one entry to a bounded store
+//!     (brief mutex, no awaits) that merges every source; clients are woken
+//!     via `tokio::sync::watch` and pull what they haven't seen, so a slow
+//!     dashboard never stalls the engine.
 //!   • The web server runs on its own tokio tasks and never touches the
-//!     CausalGraph or World directly.
+//!     CausalGraph directly; it reads the World only to render map tiles.
+//!   • Map changes: a world observer marks chunks dirty (one set insert
+//!     per chunk per drain); the web server drains and broadcasts them.
 
+pub mod admin;
+pub mod export;
+pub mod graph_store;
+pub mod map;
 pub mod metrics;
 pub mod server;
 
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
 
 use serde::Serialize;
-use tokio::sync::watch;
+use tokio::sync::{broadcast, watch};
 use ultimate_engine::causal::event::{EventId, EventPayload};
 use ultimate_engine::causal::graph::CausalGraph;
 use ultimate_engine::world::World;
 
-pub use metrics::Metrics;
+pub use graph_store::{CascadeEntry, GraphDelta, GraphSource, GraphStore};
+pub use metrics::{CascadeKind, History, HistoryPoint, Metrics};
 
 // ── Dashboard state (shared between server, connections, and web) ────────
 
 /// Central state shared via `Arc<DashboardState>`.
 pub struct DashboardState {
     pub metrics: Metrics,
+    /// Per-second samples of `metrics` for the last hour.
+    pub history: History,
     pub world: Arc<World>,
-    graph_tx: watch::Sender<GraphSnapshot>,
+    graphs: GraphStore,
+    map_dirty: Arc<map::DirtyTiles>,
+    map_tx: broadcast::Sender<Arc<[[i32; 2]]>>,
+    admin: OnceLock<admin::Admin>,
 }
 
 impl DashboardState {
     pub fn new(world: Arc<World>) -> Self {
-        let (graph_tx, _) = watch::channel(GraphSnapshot::empty());
+        let (map_tx, _) = broadcast::channel(16);
+        let map_dirty = Arc::new(map::DirtyTiles::default());
+        world.add_observer(Box::new(map::MapObserver(Arc::clone(&map_dirty))));
         Self {
             metrics: Metrics::new(),
+            history: History::default(),
             world,
-            graph_tx,
+            graphs: GraphStore::new(),
+            map_dirty,
+            map_tx,
+            admin: OnceLock::new(),
         }
     }
 
-    /// Publish a new graph snapshot. Non-blocking (overwrites previous).
-    pub fn publish_graph(&self, snapshot: GraphSnapshot) {
-        let _ = self.graph_tx.send(snapshot);
+    /// Enable the admin API. Ignored if the token is empty or handles were
+    /// already attached.
+    pub fn attach_admin(&self, admin: admin::Admin) {
+        if !admin.token.is_empty() {
+            let _ = self.admin.set(admin);
+        }
+    }
+
+    /// The admin handles, once attached.
+    pub fn admin(&self) -> Option<&admin::Admin> {
+        self.admin.get()
+    }
+
+    /// Add one batch's graph snapshot, tagged with what started it.
+    pub fn publish_graph(&self, source: GraphSource, worker: usize, snapshot: GraphSnapshot) {
+        self.graphs.push(source, worker, snapshot);
+    }
+
+    /// Wakes on every published snapshot (one receiver per WebSocket client).
+    pub fn subscribe_graph(&self) -> watch::Receiver<u64> {
+        self.graphs.subscribe()
     }
 
-    /// Create a new receiver for graph snapshots (one per WebSocket client).
-    pub fn subscribe_graph(&self) -> watch::Receiver<GraphSnapshot> {
-        self.graph_tx.subscribe()
+    /// Retained snapshots newer than `after` (0 for all of them).
+    pub fn graph_since(&self, after: u64) -> GraphDelta {
+        self.graphs.since(after)
+    }
+
+    /// Causal chains through a block in the most recent cascades.
+    pub fn graph_chains_at(&self, pos: [i64; 3], limit: usize) -> Vec<CascadeEntry> {
+        self.graphs.chains_at(pos, limit)
+    }
+
+    /// Every retained cascade merged into one graph, for export.
+    pub fn graph_export(&self, filter: &export::GraphFilter) -> export::GraphExport {
+        export::merge(&self.graphs.since(0).added, filter)
+    }
+
+    /// Current metrics, with the world's loaded chunk count filled in.
+    pub fn metrics_snapshot(&self) -> metrics::MetricsSnapshot {
+        self.metrics.snapshot(self.world.chunk_count() as u64)
+    }
+
+    /// Broadcast the chunks whose map tiles changed since the last flush.
+    /// Called periodically by the web server.
+    pub fn flush_map_changes(&self) {
+        let chunks = self.map_dirty.drain();
+        if !chunks.is_empty() {
+            let _ = self.map_tx.send(chunks.iter().map(|c| [c.x, c.z]).collect());
+        }
+    }
+
+    /// Create a receiver for dirty map chunks (one per WebSocket client).
+    pub fn subscribe_map(&self) -> broadcast::Receiver<Arc<[[i32; 2]]>> {
+        self.map_tx.subscribe()
     }
 }
 
@@ -64,6 +130,34 @@ impl GraphSnapshot {
     pub fn empty() -> Self {
         Self::default()
     }
+
+    /// The events at `pos`, everything that led to them and everything
+    /// they caused (the snapshot's view of `CausalGraph::ancestors` and
+    /// `descendants`), or `None` if no event here touched `pos`.
+    pub fn chain_at(&self, pos: [i64; 3]) -> Option<GraphSnapshot> {
+        let start: Vec<u32> = self.nodes.iter().filter(|n| n.pos == pos).map(|n| n.id).collect();
+        if start.is_empty() {
+            return None;
+        }
+        let mut keep: HashSet<u32> = start.iter().copied().collect();
+        for up in [true, false] {
+            let mut frontier = start.clone();
+            let mut seen: HashSet<u32> = start.iter().copied().collect();
+            while let Some(id) = frontier.pop() {
+                for &[parent, child] in &self.edges {
+                    let (from, to) = if up { (child, parent) } else { (parent, child) };
+                    if from == id && seen.insert(to) {
+                        frontier.push(to);
+                    }
+                }
+            }
+            keep.extend(seen);
+        }
+        Some(GraphSnapshot {
+            nodes: self.nodes.iter().filter(|n| keep.contains(&n.id)).cloned().collect(),
+            edges: self.edges.iter().filter(|[p, c]| keep.contains(p) && keep.contains(c)).copied().collect(),
+        })
+    }
 }
 
 #[derive(Clone, Serialize)]
@@ -79,10 +173,16 @@ pub struct GraphNode {
 // ── Snapshot builder ─────────────────────────────────────────────────────
 
 /// Build a `GraphSnapshot` from the graph's recent events.
-/// Called on the connection handler's tokio task after each cascade
-/// (~1-10 μs for 200 nodes — negligible vs. the cascade itself).
+/// Called on the physics worker after each batch (~1-10 μs for 200
+/// nodes — negligible vs. the cascade itself).
 pub fn snapshot_graph(graph: &CausalGraph) -> GraphSnapshot {
-    let recent: Vec<EventId> = graph.recent_node_ids().collect();
+    snapshot_since(graph, 0)
+}
+
+/// Like [`snapshot_graph`], limited to events inserted after `cursor`
+/// (`CausalGraph::recent_cursor` before the batch) — just that batch.
+pub fn snapshot_since(graph: &CausalGraph, cursor: u64) -> GraphSnapshot {
+    let recent: Vec<EventId> = graph.recent_since(cursor).collect();
 
     // Map EventId → contiguous index for the snapshot.
     let mut id_map: HashMap<EventId, u32> = HashMap::with_capacity(recent.len());
@@ -112,9 +212,12 @@ pub fn snapshot_graph(graph: &CausalGraph) -> GraphSnapshot {
                     [pos.x, pos.y, pos.z],
                 )
             }
-            EventPayload::BlockNotify { pos } => (
+            EventPayload::BlockNotify { pos, from } => (
                 "block_notify".to_string(),
-                format!("Notify ({},{},{})", pos.x, pos.y, pos.z),
+                match from {
+                    Some(dir) => format!("Notify ({},{},{}) from {:?}", pos.x, pos.y, pos.z, dir),
+                    None => format!("Notify ({},{},{})", pos.x, pos.y, pos.z),
+                },
                 [pos.x, pos.y, pos.z],
             ),
             EventPayload::LightSet { pos, light_type, new, .. } => (
@@ -127,6 +230,11 @@ pub fn snapshot_graph(graph: &CausalGraph) -> GraphSnapshot {
                 format!("LightNotify ({},{},{})", pos.x, pos.y, pos.z),
                 [pos.x, pos.y, pos.z],
             ),
+            EventPayload::Custom { kind, pos, .. } => (
+                "custom".to_string(),
+                format!("{} ({},{},{})", kind, pos.x, pos.y, pos.z),
+                [pos.x, pos.y, pos.z],
+            ),
             EventPayload::LightBatch { changes } => {
                 let anchor = changes
                     .first()
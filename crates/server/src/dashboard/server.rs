@@ -1,25 +1,81 @@
 //! axum web server for the live dashboard.
 //!
-//! Serves a single-page HTML dashboard at `/` and pushes live metrics +
-//! graph snapshots to connected browsers via WebSocket at `/ws`.
+//! Serves a single-page HTML dashboard at `/`, world map tiles at
+//! `/api/map?cx=..&cz=..`, the recent causal chains through a block at
+//! `/api/graph/at?x=..&y=..&z=..`, the merged recent graph as
+//! `/api/graph.dot` / `/api/graph.json` (see [`super::export`]),
+//! token-protected admin endpoints (see
+//! [`super::admin`]), and pushes live metrics, graph snapshots and
+//! dirty map chunks to connected browsers via WebSocket at `/ws`.
 
 use std::sync::Arc;
 use std::time::Duration;
 
 use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
-use axum::extract::State;
-use axum::response::{Html, IntoResponse};
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
 use axum::routing::get;
-use axum::Router;
+use axum::{Json, Router};
+use serde::Deserialize;
 use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
 
-use super::DashboardState;
+use super::export::{GraphExport, GraphFilter};
+use super::{DashboardState, HistoryPoint};
+use crate::shutdown::Shutdown;
+
+/// How often dirty map chunks are gathered and pushed to browsers.
+const MAP_FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Most cascades `/api/graph/at` returns.
+const CHAINS_PER_QUERY: usize = 5;
+
+/// Metrics history sample period.
+const HISTORY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Start the dashboard web server. Runs on its own tasks until `shutdown`.
+pub async fn start(state: Arc<DashboardState>, port: u16, shutdown: Shutdown) {
+    let _running = shutdown.task();
+    let flush_state = Arc::clone(&state);
+    let flush_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(MAP_FLUSH_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = flush_shutdown.triggered() => break,
+            }
+            flush_state.flush_map_changes();
+        }
+    });
+
+    let sample_state = Arc::clone(&state);
+    let sample_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(HISTORY_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = sample_shutdown.triggered() => break,
+            }
+            let snap = sample_state.metrics_snapshot();
+            sample_state.history.record(HistoryPoint::from(&snap));
+        }
+    });
 
-/// Start the dashboard web server. Runs forever on its own tasks.
-pub async fn start(state: Arc<DashboardState>, port: u16) {
     let app = Router::new()
         .route("/", get(index))
-        .route("/ws", get(ws_upgrade))
+        .route("/ws", get({
+            let shutdown = shutdown.clone();
+            move |ws, state| ws_upgrade(ws, state, shutdown)
+        }))
+        .route("/api/map", get(map_tile))
+        .route("/api/metrics/history", get(metrics_history))
+        .route("/api/graph/at", get(graph_at))
+        .route("/api/graph.dot", get(graph_dot))
+        .route("/api/graph.json", get(graph_json))
+        .merge(super::admin::routes(Arc::clone(&state)))
         .with_state(state);
 
     let addr = format!("0.0.0.0:{}", port);
@@ -32,7 +88,11 @@ pub async fn start(state: Arc<DashboardState>, port: u16) {
     };
     tracing::info!("Dashboard listening on http://{}", addr);
 
-    if let Err(e) = axum::serve(listener, app).await {
+    // Stops accepting at shutdown and returns once open sockets close.
+    let serve = axum::serve(listener, app).with_graceful_shutdown(async move {
+        shutdown.triggered().await;
+    });
+    if let Err(e) = serve.await {
         tracing::error!("Dashboard server error: {}", e);
     }
 }
@@ -42,24 +102,109 @@ async fn index() -> Html<&'static str> {
     Html(include_str!("index.html"))
 }
 
+#[derive(Deserialize)]
+struct TileQuery {
+    cx: i32,
+    cz: i32,
+}
+
+/// One chunk's top-down map tile as PNG; 404 if the chunk isn't loaded.
+async fn map_tile(
+    Query(q): Query<TileQuery>,
+    State(state): State<Arc<DashboardState>>,
+) -> Response {
+    match super::map::render_tile(&state.world, q.cx, q.cz) {
+        Some(png) => (
+            [(header::CONTENT_TYPE, "image/png"), (header::CACHE_CONTROL, "no-store")],
+            png,
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// The last hour of per-second metrics samples, oldest first.
+async fn metrics_history(State(state): State<Arc<DashboardState>>) -> Json<Vec<HistoryPoint>> {
+    Json(state.history.since(f64::NEG_INFINITY))
+}
+
+#[derive(Deserialize)]
+struct BlockQuery {
+    x: i64,
+    y: i64,
+    z: i64,
+}
+
+/// Why did this block change: the chain of events through it in each of
+/// the most recent retained cascades that touched it, newest first.
+async fn graph_at(
+    Query(q): Query<BlockQuery>,
+    State(state): State<Arc<DashboardState>>,
+) -> Json<Vec<super::CascadeEntry>> {
+    Json(state.graph_chains_at([q.x, q.y, q.z], CHAINS_PER_QUERY))
+}
+
+/// The retained cascades as Graphviz DOT. Filters as for `graph.json`;
+/// a truncated export says so in an `X-Graph-Truncated` header.
+async fn graph_dot(
+    Query(filter): Query<GraphFilter>,
+    State(state): State<Arc<DashboardState>>,
+) -> Response {
+    let export = state.graph_export(&filter);
+    (
+        [
+            (header::CONTENT_TYPE, "text/vnd.graphviz"),
+            (header::HeaderName::from_static("x-graph-truncated"), if export.truncated { "true" } else { "false" }),
+        ],
+        super::export::to_dot(&export),
+    )
+        .into_response()
+}
+
+/// The retained cascades merged into one graph, filtered by `kind` and
+/// `x,y,z,radius` and capped at `limit` nodes.
+async fn graph_json(
+    Query(filter): Query<GraphFilter>,
+    State(state): State<Arc<DashboardState>>,
+) -> Json<GraphExport> {
+    Json(state.graph_export(&filter))
+}
+
 /// Upgrade an HTTP request to a WebSocket connection.
 async fn ws_upgrade(
     ws: WebSocketUpgrade,
     State(state): State<Arc<DashboardState>>,
+    shutdown: Shutdown,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+    ws.on_upgrade(move |socket| handle_socket(socket, state, shutdown))
 }
 
-/// Push metrics and graph snapshots to a connected browser.
-async fn handle_socket(mut socket: WebSocket, state: Arc<DashboardState>) {
+/// Push metrics, graph snapshots and map changes to a connected browser
+/// until it leaves or the server shuts down.
+async fn handle_socket(mut socket: WebSocket, state: Arc<DashboardState>, shutdown: Shutdown) {
     let mut graph_rx = state.subscribe_graph();
+    // Graph entries already sent; 0 sends everything retained at connect.
+    let mut graph_seen = 0u64;
+    graph_rx.mark_changed();
+    let mut map_rx = state.subscribe_map();
     let mut ticker = tokio::time::interval(Duration::from_millis(200));
+    // History samples already sent; the first tick sends the whole buffer,
+    // later ticks only what the sampler has added since.
+    let mut history_sent = f64::NEG_INFINITY;
 
     loop {
         tokio::select! {
-            // Push metrics every 200 ms.
+            // Push metrics every 200 ms, and any new history samples.
             _ = ticker.tick() => {
-                let snap = state.metrics.snapshot(state.world.chunk_count() as u64);
+                let points = state.history.since(history_sent);
+                if let Some(last) = points.last() {
+                    history_sent = last.uptime_secs;
+                    let msg = serde_json::json!({ "type": "history", "data": points });
+                    if send_json(&mut socket, &msg).await.is_err() {
+                        break;
+                    }
+                }
+                let snap = state.metrics_snapshot();
                 let msg = serde_json::json!({
                     "type": "metrics",
                     "data": snap,
@@ -69,21 +214,42 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<DashboardState>) {
                 }
             }
 
-            // Push graph whenever a new snapshot arrives.
+            // Push new graph entries whenever a worker publishes.
             result = graph_rx.changed() => {
                 if result.is_err() {
                     break; // sender dropped
                 }
-                let graph = graph_rx.borrow_and_update().clone();
+                graph_rx.borrow_and_update();
+                let delta = state.graph_since(graph_seen);
+                if let Some(last) = delta.added.last() {
+                    graph_seen = last.seq;
+                }
                 let msg = serde_json::json!({
-                    "type": "graph",
-                    "data": graph,
+                    "type": "graph_delta",
+                    "data": delta,
                 });
                 if send_json(&mut socket, &msg).await.is_err() {
                     break;
                 }
             }
 
+            // Push dirty map chunks; a lagging client re-fetches everything.
+            result = map_rx.recv() => {
+                let msg = match result {
+                    Ok(chunks) => serde_json::json!({ "type": "map_dirty", "data": &*chunks }),
+                    Err(RecvError::Lagged(_)) => serde_json::json!({ "type": "map_reset" }),
+                    Err(RecvError::Closed) => break,
+                };
+                if send_json(&mut socket, &msg).await.is_err() {
+                    break;
+                }
+            }
+
+            _ = shutdown.triggered() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+
             // Drain any incoming messages (ping/pong, close).
             msg = socket.recv() => {
                 match msg {
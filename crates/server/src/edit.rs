@@ -0,0 +1,198 @@
+//! WorldEdit-style region editing: `//pos1`, `//pos2`, `//set`,
+//! `//replace` and `//undo` (see [`crate::commands`]).
+//!
+//! Each player's connection owns an [`EditSession`] holding their
+//! selection and undo history. An operation reads the selected cuboid,
+//! builds one root `BlockSet` per cell it changes and submits them as one
+//! batch, so the whole edit is a single causal cascade — gravity, fluids
+//! and light react to it like any placement. Undo restores the recorded
+//! blocks the same way; what the cascade did afterwards (sand that fell,
+//! water that spread) is not rolled back.
+
+use std::collections::VecDeque;
+
+use anyhow::{Result, ensure};
+
+use ultimate_engine::causal::event::{Event, EventPayload};
+use ultimate_engine::world::World;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::position::{BlockPos, ChunkPos};
+
+/// Operations kept for `//undo`, per player.
+pub const UNDO_DEPTH: usize = 8;
+
+/// An inclusive cuboid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub min: BlockPos,
+    pub max: BlockPos,
+}
+
+impl Region {
+    /// The cuboid spanned by two opposite corners, in any order.
+    pub fn new(a: BlockPos, b: BlockPos) -> Self {
+        Self {
+            min: BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    /// Number of blocks, saturating for absurd selections.
+    pub fn volume(&self) -> u64 {
+        let extent = |lo: i64, hi: i64| (hi - lo + 1) as u64;
+        extent(self.min.x, self.max.x)
+            .saturating_mul(extent(self.min.y, self.max.y))
+            .saturating_mul(extent(self.min.z, self.max.z))
+    }
+
+    /// Every position, y-major then z then x.
+    pub fn positions(&self) -> impl Iterator<Item = BlockPos> + '_ {
+        BlockPos::iter_cuboid(self.min, self.max)
+    }
+
+    /// Whether every chunk the region touches is loaded. Edits refuse
+    /// unloaded chunks: a write there would create an empty chunk the
+    /// generator never fills, and a read would see air.
+    pub fn is_loaded(&self, world: &World) -> bool {
+        let (lo, hi) = (self.min.chunk(), self.max.chunk());
+        (lo.x..=hi.x).all(|cx| (lo.z..=hi.z).all(|cz| world.has_chunk(ChunkPos::new(cx, cz))))
+    }
+}
+
+/// Blocks an operation overwrote, to put back on undo.
+pub type UndoRecord = Vec<(BlockPos, BlockId)>;
+
+/// One player's selection and undo history.
+#[derive(Debug, Default)]
+pub struct EditSession {
+    pub pos1: Option<BlockPos>,
+    pub pos2: Option<BlockPos>,
+    history: VecDeque<UndoRecord>,
+}
+
+impl EditSession {
+    /// The selected cuboid, once both corners are set.
+    pub fn selection(&self) -> Option<Region> {
+        Some(Region::new(self.pos1?, self.pos2?))
+    }
+
+    /// Remember an applied operation, dropping the oldest beyond
+    /// [`UNDO_DEPTH`].
+    pub fn push_undo(&mut self, record: UndoRecord) {
+        if self.history.len() == UNDO_DEPTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(record);
+    }
+
+    /// The most recent operation, removed from the history.
+    pub fn pop_undo(&mut self) -> Option<UndoRecord> {
+        self.history.pop_back()
+    }
+}
+
+/// Events setting every cell of `region` accepted by `mask` to `new`
+/// (`mask` = `None` matches everything; otherwise any state of the mask's
+/// block matches), and the record undoing them.
+/// Cells already holding `new` are skipped. Fails if the region is larger
+/// than `max_blocks` or not fully loaded.
+pub fn fill(
+    world: &World,
+    region: Region,
+    mask: Option<BlockId>,
+    new: BlockId,
+    max_blocks: usize,
+) -> Result<(Vec<Event>, UndoRecord)> {
+    ensure!(
+        region.volume() <= max_blocks as u64,
+        "the selection is {} blocks, more than the {} allowed",
+        region.volume(),
+        max_blocks
+    );
+    ensure!(region.is_loaded(world), "the selection isn't fully loaded");
+
+    let mut events = Vec::new();
+    let mut undo = Vec::new();
+    for pos in region.positions() {
+        let old = world.get_block(pos);
+        if old != new && mask.is_none_or(|m| crate::block::same_block(m, old)) {
+            events.push(Event { payload: EventPayload::BlockSet { pos, old, new } });
+            undo.push((pos, old));
+        }
+    }
+    Ok((events, undo))
+}
+
+/// Events putting back the blocks in `record`, each observing what is
+/// there now. Cells already back to their recorded block are skipped.
+pub fn undo(world: &World, record: &UndoRecord) -> Vec<Event> {
+    record
+        .iter()
+        .filter_map(|&(pos, new)| {
+            let old = world.get_block(pos);
+            (old != new).then_some(Event { payload: EventPayload::BlockSet { pos, old, new } })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{AIR, SAND, STONE};
+    use ultimate_engine::world::chunk::Chunk;
+
+    /// Apply root events directly, as the physics service would before
+    /// any cascade.
+    fn apply(world: &World, events: &[Event]) {
+        let writes: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event.payload {
+                EventPayload::BlockSet { pos, new, .. } => Some((pos, new)),
+                _ => None,
+            })
+            .collect();
+        world.apply_batch(&writes);
+    }
+
+    #[test]
+    fn test_fill_replace_and_undo() {
+        let world = World::new();
+        world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+        world.set_block(BlockPos::new(1, 1, 1), STONE);
+
+        let mut session = EditSession { pos1: Some(BlockPos::new(2, 2, 2)), ..Default::default() };
+        assert_eq!(session.selection(), None);
+        session.pos2 = Some(BlockPos::new(0, 0, 0));
+        let region = session.selection().unwrap();
+        assert_eq!(region.volume(), 27);
+
+        let (events, record) = fill(&world, region, None, SAND, 1000).unwrap();
+        assert_eq!(events.len(), 27);
+        apply(&world, &events);
+        session.push_undo(record);
+
+        let (events, record) = fill(&world, region, Some(SAND), STONE, 1000).unwrap();
+        assert_eq!(events.len(), 27);
+        apply(&world, &events);
+        session.push_undo(record);
+        assert!(fill(&world, region, Some(SAND), AIR, 1000).unwrap().0.is_empty(), "nothing left to replace");
+
+        apply(&world, &undo(&world, &session.pop_undo().unwrap()));
+        assert_eq!(world.get_block(BlockPos::new(1, 1, 1)), SAND);
+        apply(&world, &undo(&world, &session.pop_undo().unwrap()));
+        assert_eq!(world.get_block(BlockPos::new(1, 1, 1)), STONE);
+        assert_eq!(world.get_block(BlockPos::new(0, 0, 0)), AIR);
+        assert!(session.pop_undo().is_none());
+    }
+
+    #[test]
+    fn test_fill_limits() {
+        let world = World::new();
+        world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+        let region = Region::new(BlockPos::new(0, 0, 0), BlockPos::new(9, 9, 9));
+        assert!(fill(&world, region, None, STONE, 999).is_err(), "over the cap");
+        assert!(fill(&world, region, None, STONE, 1000).is_ok());
+        let unloaded = Region::new(BlockPos::new(0, 0, 0), BlockPos::new(16, 0, 0));
+        assert!(fill(&world, unloaded, None, STONE, 1000).is_err(), "chunk (1, 0) isn't loaded");
+    }
+}
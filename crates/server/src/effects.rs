@@ -0,0 +1,278 @@
+//! Sounds and particles that accompany world changes.
+//!
+//! A client plays the sounds and particles of its own block breaks and
+//! placements itself; everyone else only hears about them through a
+//! [`WorldEffect`] on the spatial bus (`SpatialMsg::Effects`). The
+//! connection that made a change publishes its effects tagged with its
+//! own [`ChangeSource`](crate::event_bus::ChangeSource) so it can skip
+//! them; the physics service publishes the effects of what cascades do
+//! on their own (lava hardening, fluids spreading) for everyone.
+//! Connections send each effect only to players within earshot.
+//!
+//! Digging outside creative takes a while, and the digger's client draws
+//! the cracks itself; bystanders get [`WorldEffect::Cracks`] and the
+//! block's hit sound as each [stage](crack_stage) is reached, and the
+//! cracks cleared when the digging stops.
+
+use azalea_block::BlockState;
+use azalea_core::position::Vec3;
+use azalea_entity::particle::{BlockParticle, Particle};
+use azalea_protocol::packets::game::c_block_destruction::ClientboundBlockDestruction;
+use azalea_protocol::packets::game::c_level_particles::ClientboundLevelParticles;
+use azalea_protocol::packets::game::c_sound::{ClientboundSound, SoundSource};
+use azalea_protocol::packets::game::ClientboundGamePacket;
+use azalea_protocol::packets::Packet;
+use azalea_registry::builtin::{BlockKind, SoundEvent};
+use azalea_world::MinecraftEntityId;
+
+use ultimate_engine::causal::event::EventPayload;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::position::BlockPos;
+
+use crate::block::{self, FluidKind};
+
+/// Particles are drawn up to this many blocks away (vanilla's limit).
+pub const PARTICLE_RANGE: f64 = 32.0;
+
+/// A sound at volume 1.0 carries this far; louder sounds carry further.
+pub const SOUND_RANGE: f64 = 16.0;
+
+/// Volume of the sound of fluid spreading: present, not a roar.
+const FLOW_VOLUME: f32 = 0.3;
+
+/// Crack stages run 0–9; this one (any outside that range) clears them.
+pub const NO_CRACKS: u8 = 10;
+
+/// A sound or particle burst at a block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldEffect {
+    Sound { sound: SoundEvent, pos: BlockPos, volume: f32, pitch: f32 },
+    Particle { particle: Particle, pos: BlockPos, count: u32 },
+    /// The cracks entity `breaker` has dug into a block so far, `stage`
+    /// 0–9, or [`NO_CRACKS`]. Clients draw one set per digger.
+    Cracks { breaker: i32, pos: BlockPos, stage: u8 },
+}
+
+impl WorldEffect {
+    /// The block the effect happens at.
+    pub fn pos(&self) -> BlockPos {
+        match self {
+            WorldEffect::Sound { pos, .. } | WorldEffect::Particle { pos, .. } | WorldEffect::Cracks { pos, .. } => *pos,
+        }
+    }
+
+    /// Whether a player standing at `(x, y, z)` hears or sees it.
+    pub fn reaches(&self, x: f64, y: f64, z: f64) -> bool {
+        let range = match self {
+            WorldEffect::Sound { volume, .. } => SOUND_RANGE * f64::from(volume.max(1.0)),
+            WorldEffect::Particle { .. } | WorldEffect::Cracks { .. } => PARTICLE_RANGE,
+        };
+        let [cx, cy, cz] = center(self.pos());
+        let (dx, dy, dz) = (cx - x, cy - y, cz - z);
+        dx * dx + dy * dy + dz * dz <= range * range
+    }
+
+    /// The packet that plays it.
+    pub fn to_packet(&self) -> ClientboundGamePacket {
+        let [x, y, z] = center(self.pos());
+        match self {
+            WorldEffect::Sound { sound, pos, volume, pitch } => ClientboundSound {
+                sound: azalea_registry::Holder::Reference(*sound),
+                source: SoundSource::Blocks,
+                // Positions are fixed-point eighths of a block.
+                x: (x * 8.0) as i32,
+                y: (y * 8.0) as i32,
+                z: (z * 8.0) as i32,
+                volume: *volume,
+                pitch: *pitch,
+                // The client picks the sound variant from the seed; vary
+                // it by position so a row of breaks doesn't sound cloned.
+                seed: (pos.x as u64).wrapping_mul(31).wrapping_add(pos.y as u64).wrapping_mul(31).wrapping_add(pos.z as u64),
+            }
+            .into_variant(),
+            WorldEffect::Particle { particle, count, .. } => ClientboundLevelParticles {
+                override_limiter: false,
+                always_show: false,
+                pos: Vec3::new(x, y, z),
+                x_dist: 0.25,
+                y_dist: 0.25,
+                z_dist: 0.25,
+                max_speed: 0.0,
+                count: *count,
+                particle: particle.clone(),
+            }
+            .into_variant(),
+            WorldEffect::Cracks { breaker, pos, stage } => ClientboundBlockDestruction {
+                id: MinecraftEntityId(*breaker),
+                pos: azalea_core::position::BlockPos::new(pos.x as i32, pos.y as i32, pos.z as i32),
+                progress: *stage,
+            }
+            .into_variant(),
+        }
+    }
+}
+
+fn center(pos: BlockPos) -> [f64; 3] {
+    [pos.x as f64 + 0.5, pos.y as f64 + 0.5, pos.z as f64 + 0.5]
+}
+
+/// Effects of a player breaking `old` at `pos`: its break sound and a
+/// burst of its particles, or a bucket filling for a fluid.
+pub fn block_broken(pos: BlockPos, old: BlockId) -> Vec<WorldEffect> {
+    if let Some((kind, _)) = block::fluid_kind(old) {
+        let sound = match kind {
+            FluidKind::Water => SoundEvent::ItemBucketFill,
+            FluidKind::Lava => SoundEvent::ItemBucketFillLava,
+        };
+        return vec![WorldEffect::Sound { sound, pos, volume: 1.0, pitch: 1.0 }];
+    }
+    if old == block::AIR {
+        return Vec::new();
+    }
+    let Ok(block_state) = BlockState::try_from(old.0 as u32) else {
+        return Vec::new();
+    };
+    vec![
+        WorldEffect::Sound { sound: block_sound(old, "break"), pos, volume: 1.0, pitch: 0.8 },
+        WorldEffect::Particle { particle: Particle::Block(BlockParticle { block_state }), pos, count: 30 },
+    ]
+}
+
+/// The crack stage (0–9) of a block that takes `ticks` game ticks to dig,
+/// `elapsed` into digging it.
+pub fn crack_stage(elapsed: std::time::Duration, ticks: u32) -> u8 {
+    let progress = elapsed.as_secs_f64() / (ticks.max(1) as f64 * 0.05);
+    (progress * 10.0).min(9.0) as u8
+}
+
+/// Effects of the player with entity ID `breaker` digging `block` at
+/// `pos` as far as `stage`: the cracks and a tap of the block's hit sound.
+pub fn digging(breaker: i32, pos: BlockPos, block: BlockId, stage: u8) -> Vec<WorldEffect> {
+    vec![
+        WorldEffect::Cracks { breaker, pos, stage },
+        WorldEffect::Sound { sound: block_sound(block, "hit"), pos, volume: 0.25, pitch: 0.5 },
+    ]
+}
+
+/// Effects of a player placing `new` at `pos`: its place sound, or a
+/// bucket emptying for a fluid.
+pub fn block_placed(pos: BlockPos, new: BlockId) -> Vec<WorldEffect> {
+    let sound = match block::fluid_kind(new) {
+        Some((FluidKind::Water, _)) => SoundEvent::ItemBucketEmpty,
+        Some((FluidKind::Lava, _)) => SoundEvent::ItemBucketEmptyLava,
+        None if new == block::AIR => return Vec::new(),
+        None => block_sound(new, "place"),
+    };
+    vec![WorldEffect::Sound { sound, pos, volume: 1.0, pitch: 0.8 }]
+}
+
+/// Effects of what a cascade did, from its write log: lava hardening
+/// hisses and smokes where it happened, and fluid that spread makes one
+/// quiet flowing sound per fluid (a flood is one sound, not thousands).
+pub fn from_writes(log: &[EventPayload]) -> Vec<WorldEffect> {
+    let mut effects = Vec::new();
+    let mut flowed = [false; 2];
+    for payload in log {
+        let EventPayload::BlockSet { pos, old, new } = *payload else { continue };
+        if FluidKind::Lava.is_match(old) && [block::STONE, block::COBBLESTONE, block::OBSIDIAN].contains(&new) {
+            effects.push(WorldEffect::Sound { sound: SoundEvent::BlockLavaExtinguish, pos, volume: 0.5, pitch: 2.6 });
+            effects.push(WorldEffect::Particle { particle: Particle::LargeSmoke, pos, count: 8 });
+        } else if old == block::AIR
+            && let Some((kind, level)) = block::fluid_kind(new)
+            && level > 0
+        {
+            let (index, sound) = match kind {
+                FluidKind::Water => (0, SoundEvent::BlockWaterAmbient),
+                FluidKind::Lava => (1, SoundEvent::BlockLavaAmbient),
+            };
+            if !flowed[index] {
+                flowed[index] = true;
+                effects.push(WorldEffect::Sound { sound, pos, volume: FLOW_VOLUME, pitch: 1.0 });
+            }
+        }
+    }
+    effects
+}
+
+/// The `block.<group>.<action>` sound for a block: its own if vanilla has
+/// one (`block.anvil.place`, ...), else its material's, else stone's.
+fn block_sound(id: BlockId, action: &str) -> SoundEvent {
+    /// Name fragments → sound group, first match wins.
+    const GROUPS: &[(&str, &str)] = &[
+        ("sandstone", "stone"),
+        ("concrete_powder", "sand"),
+        ("sand", "sand"),
+        ("gravel", "gravel"),
+        ("dirt", "gravel"),
+        ("grass", "grass"),
+        ("leaves", "grass"),
+        ("glass", "glass"),
+        ("wool", "wool"),
+        ("carpet", "wool"),
+        ("planks", "wood"),
+        ("_log", "wood"),
+        ("_wood", "wood"),
+        ("snow", "snow"),
+    ];
+
+    let full = BlockState::try_from(id.0 as u32).map(|state| BlockKind::from(state).to_string()).unwrap_or_default();
+    let name = full.strip_prefix("minecraft:").unwrap_or(&full);
+    let group = GROUPS.iter().find(|(fragment, _)| name.contains(fragment)).map(|(_, group)| *group);
+    [Some(name), group, Some("stone")]
+        .into_iter()
+        .flatten()
+        .find_map(|group| format!("block.{group}.{action}").parse().ok())
+        .unwrap_or(SoundEvent::BlockStoneBreak)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sounds_by_material() {
+        let id = |name| block::block_id_from_name(name).unwrap();
+        assert_eq!(block_sound(block::STONE, "break"), SoundEvent::BlockStoneBreak);
+        assert_eq!(block_sound(id("oak_planks"), "place"), SoundEvent::BlockWoodPlace);
+        assert_eq!(block_sound(id("red_sand"), "break"), SoundEvent::BlockSandBreak);
+        assert_eq!(block_sound(id("anvil"), "place"), SoundEvent::BlockAnvilPlace, "a block's own sound wins");
+        assert_eq!(block_sound(id("sandstone"), "break"), SoundEvent::BlockStoneBreak);
+
+        assert_eq!(block_broken(BlockPos::new(0, 0, 0), block::AIR), Vec::new());
+        assert_eq!(block_broken(BlockPos::new(0, 0, 0), block::STONE).len(), 2, "sound and particles");
+        assert!(matches!(
+            block_placed(BlockPos::new(0, 0, 0), block::WATER)[..],
+            [WorldEffect::Sound { sound: SoundEvent::ItemBucketEmpty, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_digging_cracks() {
+        use std::time::Duration;
+        assert_eq!(crack_stage(Duration::ZERO, 30), 0);
+        assert_eq!(crack_stage(Duration::from_millis(750), 30), 5, "half way through 1.5 s");
+        assert_eq!(crack_stage(Duration::from_secs(10), 30), 9, "never past the last stage");
+
+        let effects = digging(4, BlockPos::new(1, 2, 3), block::STONE, 5);
+        assert!(matches!(effects[..], [WorldEffect::Cracks { breaker: 4, stage: 5, .. }, WorldEffect::Sound { sound: SoundEvent::BlockStoneHit, .. }]));
+        assert!(matches!(effects[0].to_packet(), ClientboundGamePacket::BlockDestruction(_)));
+    }
+
+    #[test]
+    fn test_cascade_effects() {
+        let set = |x, old, new| EventPayload::BlockSet { pos: BlockPos::new(x, 5, 0), old, new };
+        let log = [
+            set(0, block::AIR, block::water_flowing(1)),
+            set(1, block::AIR, block::water_flowing(2)),
+            set(2, block::lava_flowing(1), block::COBBLESTONE),
+            set(3, block::AIR, block::STONE),
+        ];
+        let effects = from_writes(&log);
+        assert_eq!(effects.len(), 3, "one flow sound, plus a hiss and smoke: {effects:?}");
+        assert!(effects.iter().any(|e| matches!(e, WorldEffect::Particle { particle: Particle::LargeSmoke, .. })));
+
+        let sound = &effects[0];
+        assert!(sound.reaches(0.5, 5.0, 10.0));
+        assert!(!sound.reaches(0.5, 5.0, 40.0));
+    }
+}
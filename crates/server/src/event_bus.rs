@@ -1,17 +1,23 @@
 //! World-change event bus for cross-player and simulation-to-player distribution.
 //!
 //! Every action that modifies the world (player block break/place, ambient simulation)
-//! publishes a [`WorldChangeBatch`] to a shared `tokio::sync::broadcast` channel.
-//! Each connection subscribes and forwards changes to its client -- except changes
-//! it originated itself.
+//! publishes a [`WorldChangeBatch`] on the [`SpatialBus`]. Each connection subscribes
+//! and forwards changes to its client -- except changes it originated itself. Sounds
+//! and particles travel the same way as [`EffectBatch`]es.
+//!
+//! Messages are split into [`Topic`]s. A subscription names the topics it wants
+//! and how many of each may queue up unread (see [`SubscriptionBuilder`]), so a
+//! consumer interested in block changes alone never sees player moves. Chat and
+//! joins stay on the player registry's global channel.
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 
 use ultimate_engine::causal::event::{EventPayload, LightType};
 use ultimate_engine::world::block::BlockId;
 use ultimate_engine::world::position::BlockPos;
 
-/// Recommended capacity for the broadcast channel.
+/// Default queue limit for [`Topic::Blocks`].
 ///
 /// Batches are `Arc`-backed (a slot is ~100 bytes), so a deep buffer is
 /// nearly free. 256 lagged visibly at 100 wandering+digging players once
@@ -51,6 +57,97 @@ pub struct WorldChangeBatch {
     pub light_changes: Arc<[LightChange]>,
 }
 
+/// Sounds and particles from one source (see [`crate::effects`]).
+#[derive(Clone, Debug)]
+pub struct EffectBatch {
+    pub source: ChangeSource,
+    pub effects: Arc<[crate::effects::WorldEffect]>,
+}
+
+/// A non-player entity's state, on the entity channel. Players have
+/// their own path (`PlayerEvent`); this carries mobs (see
+/// [`crate::mobs`]).
+#[derive(Clone, Debug)]
+pub enum EntityEvent {
+    /// Where the entity is now. A connection that hasn't seen it yet
+    /// spawns it from this, so it carries everything a spawn needs.
+    Moved {
+        entity_id: i32,
+        uuid: uuid::Uuid,
+        kind: azalea_registry::builtin::EntityKind,
+        x: f64,
+        y: f64,
+        z: f64,
+        y_rot: f32,
+        x_rot: f32,
+        on_ground: bool,
+    },
+    /// The entity is gone. `x`/`z` is where it was, for routing.
+    Removed { entity_id: i32, x: f64, z: f64 },
+}
+
+impl EntityEvent {
+    pub fn entity_id(&self) -> i32 {
+        match self {
+            EntityEvent::Moved { entity_id, .. } | EntityEvent::Removed { entity_id, .. } => *entity_id,
+        }
+    }
+}
+
+// ── Topics ──────────────────────────────────────────────────────────────────
+
+/// What a [`SpatialMsg`] carries; subscriptions filter on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Topic {
+    /// Block and light changes ([`SpatialMsg::World`]).
+    Blocks,
+    /// Player movement and arm swings ([`SpatialMsg::Move`]).
+    Moves,
+    /// Mobs ([`SpatialMsg::Entity`]).
+    Entities,
+    /// Sounds and particles ([`SpatialMsg::Effects`]).
+    Effects,
+}
+
+impl Topic {
+    pub const ALL: [Topic; 4] = [Topic::Blocks, Topic::Moves, Topic::Entities, Topic::Effects];
+
+    /// Default queue limit per subscriber. Moves and mob updates are
+    /// superseded by the next one, so they drop first; losing block
+    /// changes desyncs a client, so theirs is deepest.
+    pub const fn default_capacity(self) -> usize {
+        match self {
+            Topic::Blocks => BUS_CAPACITY,
+            Topic::Moves => 4096,
+            Topic::Entities => 4096,
+            Topic::Effects => 1024,
+        }
+    }
+}
+
+/// A set of [`Topic`]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Topics(u8);
+
+impl Topics {
+    pub const NONE: Topics = Topics(0);
+    pub const ALL: Topics = Topics(0b1111);
+
+    pub const fn with(self, topic: Topic) -> Topics {
+        Topics(self.0 | 1 << topic as u8)
+    }
+
+    pub const fn contains(self, topic: Topic) -> bool {
+        self.0 & 1 << topic as u8 != 0
+    }
+}
+
+impl From<Topic> for Topics {
+    fn from(topic: Topic) -> Self {
+        Topics::NONE.with(topic)
+    }
+}
+
 // ── Spatial pub/sub (Phase 6f: the 10k-player delivery plane) ───────────────
 
 /// A region key: 4×4 chunks, consistent with physics partitioning and
@@ -67,8 +164,52 @@ pub fn region_of_block(x: i64, z: i64) -> Region {
 pub enum SpatialMsg {
     /// World changes whose positions all fall in the bucket's region.
     World(WorldChangeBatch),
-    /// A player movement (always `PlayerEvent::Moved`).
+    /// A player moving or swinging an arm (`PlayerEvent::Moved` or
+    /// `PlayerEvent::Swung`).
     Move(crate::player_registry::PlayerEvent),
+    /// Sounds and particles whose positions all fall in the bucket's
+    /// region.
+    Effects(EffectBatch),
+    /// A mob spawning, moving or despawning in the bucket's region.
+    Entity(EntityEvent),
+}
+
+impl SpatialMsg {
+    pub fn topic(&self) -> Topic {
+        match self {
+            SpatialMsg::World(_) => Topic::Blocks,
+            SpatialMsg::Move(_) => Topic::Moves,
+            SpatialMsg::Entity(_) => Topic::Entities,
+            SpatialMsg::Effects(_) => Topic::Effects,
+        }
+    }
+}
+
+/// One subscription's end of the bus: its channel, what it wants, and
+/// how much of each topic is queued unread.
+struct Sink {
+    tx: tokio::sync::mpsc::UnboundedSender<Arc<SpatialMsg>>,
+    topics: Topics,
+    capacity: [usize; 4],
+    queued: [AtomicUsize; 4],
+    dropped: AtomicU64,
+}
+
+impl Sink {
+    /// Queue `msg` if wanted and within its topic's limit. False once the
+    /// receiver is gone.
+    fn offer(&self, msg: &Arc<SpatialMsg>) -> bool {
+        let topic = msg.topic() as usize;
+        if !self.topics.contains(msg.topic()) {
+            return !self.tx.is_closed();
+        }
+        if self.queued[topic].load(Ordering::Relaxed) >= self.capacity[topic] {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return !self.tx.is_closed();
+        }
+        self.queued[topic].fetch_add(1, Ordering::Relaxed);
+        self.tx.send(Arc::clone(msg)).is_ok()
+    }
 }
 
 /// Region-bucketed pub/sub: publishers deliver to the subscribers of the
@@ -80,48 +221,57 @@ pub enum SpatialMsg {
 /// Join/leave/chat remain on the global broadcast channel — the tab list
 /// is global and those events are rare.
 pub struct SpatialBus {
-    buckets: dashmap::DashMap<Region, std::collections::HashMap<u64, Tx>>,
-    next_sub: std::sync::atomic::AtomicU64,
+    buckets: dashmap::DashMap<Region, std::collections::HashMap<u64, Arc<Sink>>>,
+    /// Subscribers that see every region (see
+    /// [`SubscriptionBuilder::everywhere`]).
+    everywhere: std::sync::RwLock<std::collections::HashMap<u64, Arc<Sink>>>,
+    next_sub: AtomicU64,
 }
 
-type Tx = tokio::sync::mpsc::UnboundedSender<Arc<SpatialMsg>>;
-
 impl SpatialBus {
     #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Arc<Self> {
         Arc::new(Self {
             buckets: dashmap::DashMap::new(),
-            next_sub: std::sync::atomic::AtomicU64::new(1),
+            everywhere: Default::default(),
+            next_sub: AtomicU64::new(1),
         })
     }
 
-    /// Create a subscriber. It starts with no regions; call
-    /// [`SpatialSubscriber::set_view`] to subscribe an area.
-    pub fn subscribe(
-        self: &Arc<Self>,
-    ) -> (SpatialSubscriber, tokio::sync::mpsc::UnboundedReceiver<Arc<SpatialMsg>>) {
-        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        let id = self
-            .next_sub
-            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        (
-            SpatialSubscriber {
-                id,
-                bus: Arc::clone(self),
-                regions: std::collections::HashSet::new(),
-                tx,
-            },
-            rx,
-        )
+    /// Create a subscriber to every topic with default limits. It starts
+    /// with no regions; call [`SpatialSubscriber::set_view`] to subscribe
+    /// an area.
+    pub fn subscribe(self: &Arc<Self>) -> (SpatialSubscriber, SpatialReceiver) {
+        self.subscriber().build()
+    }
+
+    /// Start a subscription that picks its topics, limits and scope.
+    pub fn subscriber(self: &Arc<Self>) -> SubscriptionBuilder {
+        SubscriptionBuilder {
+            bus: Arc::clone(self),
+            topics: Topics::ALL,
+            capacity: Topic::ALL.map(Topic::default_capacity),
+            everywhere: false,
+        }
     }
 
     fn deliver(&self, region: Region, msg: &Arc<SpatialMsg>) {
+        // Lazily reap subscribers whose receiver died without Drop
+        // (aborted task).
+        let dead: Vec<u64> = {
+            let everywhere = self.everywhere.read().unwrap_or_else(|e| e.into_inner());
+            everywhere.iter().filter(|(_, sink)| !sink.offer(msg)).map(|(&id, _)| id).collect()
+        };
+        if !dead.is_empty() {
+            let mut everywhere = self.everywhere.write().unwrap_or_else(|e| e.into_inner());
+            for id in dead {
+                everywhere.remove(&id);
+            }
+        }
         let Some(mut bucket) = self.buckets.get_mut(&region) else {
             return;
         };
-        // Lazily reap subscribers whose receiver died without Drop
-        // (aborted task).
-        bucket.retain(|_, tx| tx.send(Arc::clone(msg)).is_ok());
+        bucket.retain(|_, sink| sink.offer(msg));
     }
 
     /// Publish a set of world changes, split per region so each bucket's
@@ -163,10 +313,37 @@ impl SpatialBus {
         }
     }
 
-    /// Publish a player movement to its region's subscribers.
+    /// Publish sounds and particles, split per region like world changes.
+    pub fn publish_effects(&self, source: ChangeSource, effects: Vec<crate::effects::WorldEffect>) {
+        let mut per_region: std::collections::HashMap<Region, Vec<crate::effects::WorldEffect>> =
+            std::collections::HashMap::new();
+        for effect in effects {
+            let pos = effect.pos();
+            per_region.entry(region_of_block(pos.x, pos.z)).or_default().push(effect);
+        }
+        for (region, effects) in per_region {
+            let msg = Arc::new(SpatialMsg::Effects(EffectBatch {
+                source: source.clone(),
+                effects: effects.into(),
+            }));
+            self.deliver(region, &msg);
+        }
+    }
+
+    /// Publish a non-player entity's state to its region's subscribers.
+    pub fn publish_entity(&self, event: EntityEvent) {
+        let (x, z) = match &event {
+            EntityEvent::Moved { x, z, .. } | EntityEvent::Removed { x, z, .. } => (*x, *z),
+        };
+        let region = region_of_block(x.floor() as i64, z.floor() as i64);
+        self.deliver(region, &Arc::new(SpatialMsg::Entity(event)));
+    }
+
+    /// Publish a player movement or arm swing to its region's subscribers.
     pub fn publish_move(&self, event: crate::player_registry::PlayerEvent) {
-        let crate::player_registry::PlayerEvent::Moved { x, z, .. } = &event else {
-            debug_assert!(false, "publish_move expects PlayerEvent::Moved");
+        use crate::player_registry::PlayerEvent;
+        let (PlayerEvent::Moved { x, z, .. } | PlayerEvent::Swung { x, z, .. }) = &event else {
+            debug_assert!(false, "publish_move expects PlayerEvent::Moved or Swung");
             return;
         };
         let region = region_of_block(*x as i64, *z as i64);
@@ -175,6 +352,86 @@ impl SpatialBus {
     }
 }
 
+/// Configures a subscription; from [`SpatialBus::subscriber`].
+pub struct SubscriptionBuilder {
+    bus: Arc<SpatialBus>,
+    topics: Topics,
+    capacity: [usize; 4],
+    everywhere: bool,
+}
+
+impl SubscriptionBuilder {
+    /// Receive only these topics (all by default).
+    pub fn topics(mut self, topics: impl Into<Topics>) -> Self {
+        self.topics = topics.into();
+        self
+    }
+
+    /// Keep at most `capacity` unread messages of `topic`; more are
+    /// dropped and counted ([`SpatialReceiver::dropped`]).
+    pub fn capacity(mut self, topic: Topic, capacity: usize) -> Self {
+        self.capacity[topic as usize] = capacity;
+        self
+    }
+
+    /// Receive every region's messages rather than a view's, for
+    /// server-wide consumers such as the dashboard.
+    pub fn everywhere(mut self) -> Self {
+        self.everywhere = true;
+        self
+    }
+
+    pub fn build(self) -> (SpatialSubscriber, SpatialReceiver) {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let sink = Arc::new(Sink {
+            tx,
+            topics: self.topics,
+            capacity: self.capacity,
+            queued: Default::default(),
+            dropped: AtomicU64::new(0),
+        });
+        let id = self.bus.next_sub.fetch_add(1, Ordering::Relaxed);
+        if self.everywhere {
+            let mut everywhere = self.bus.everywhere.write().unwrap_or_else(|e| e.into_inner());
+            everywhere.insert(id, Arc::clone(&sink));
+        }
+        (
+            SpatialSubscriber {
+                id,
+                bus: self.bus,
+                regions: std::collections::HashSet::new(),
+                sink: Arc::clone(&sink),
+            },
+            SpatialReceiver { rx, sink },
+        )
+    }
+}
+
+/// The receiving end of a subscription.
+pub struct SpatialReceiver {
+    rx: tokio::sync::mpsc::UnboundedReceiver<Arc<SpatialMsg>>,
+    sink: Arc<Sink>,
+}
+
+impl SpatialReceiver {
+    pub async fn recv(&mut self) -> Option<Arc<SpatialMsg>> {
+        let msg = self.rx.recv().await?;
+        self.sink.queued[msg.topic() as usize].fetch_sub(1, Ordering::Relaxed);
+        Some(msg)
+    }
+
+    pub fn try_recv(&mut self) -> Result<Arc<SpatialMsg>, tokio::sync::mpsc::error::TryRecvError> {
+        let msg = self.rx.try_recv()?;
+        self.sink.queued[msg.topic() as usize].fetch_sub(1, Ordering::Relaxed);
+        Ok(msg)
+    }
+
+    /// Messages dropped because their topic's queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.sink.dropped.load(Ordering::Relaxed)
+    }
+}
+
 /// A connection's spatial subscription. Re-point it with
 /// [`set_view`](Self::set_view) when the player crosses chunk borders;
 /// dropping it unsubscribes everywhere.
@@ -182,7 +439,7 @@ pub struct SpatialSubscriber {
     id: u64,
     bus: Arc<SpatialBus>,
     regions: std::collections::HashSet<Region>,
-    tx: Tx,
+    sink: Arc<Sink>,
 }
 
 impl SpatialSubscriber {
@@ -211,7 +468,7 @@ impl SpatialSubscriber {
                 .buckets
                 .entry(*region)
                 .or_default()
-                .insert(self.id, self.tx.clone());
+                .insert(self.id, Arc::clone(&self.sink));
         }
         self.regions = wanted;
     }
@@ -219,6 +476,7 @@ impl SpatialSubscriber {
 
 impl Drop for SpatialSubscriber {
     fn drop(&mut self) {
+        self.bus.everywhere.write().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
         for region in &self.regions {
             if let Some(mut bucket) = self.bus.buckets.get_mut(region) {
                 bucket.remove(&self.id);
@@ -252,6 +510,7 @@ mod spatial_tests {
         crate::player_registry::PlayerEvent::Moved {
             conn_id: 1,
             entity_id: 7,
+            uuid: uuid::Uuid::nil(),
             x,
             y: 80.0,
             z,
@@ -288,6 +547,13 @@ mod spatial_tests {
         assert!(matches!(*rx.try_recv().expect("near move"), SpatialMsg::Move(_)));
         bus.publish_move(moved_at(5000.0, 5000.0));
         assert!(rx.try_recv().is_err(), "far move must not be delivered");
+
+        // And arm swings, which ride with moves.
+        for (x, z) in [(12.0, 12.0), (5000.0, 5000.0)] {
+            bus.publish_move(crate::player_registry::PlayerEvent::Swung { conn_id: 1, entity_id: 7, x, z, off_hand: false });
+        }
+        assert!(matches!(*rx.try_recv().expect("near swing"), SpatialMsg::Move(_)));
+        assert!(rx.try_recv().is_err(), "far swing must not be delivered");
     }
 
     #[test]
@@ -328,6 +594,53 @@ mod spatial_tests {
         assert!(rx.try_recv().is_ok(), "new area subscribed");
     }
 
+    #[test]
+    fn effects_are_region_scoped() {
+        let bus = SpatialBus::new();
+        let (mut sub, mut rx) = bus.subscribe();
+        sub.set_view(0, 0, 4);
+
+        let near = crate::effects::block_broken(BlockPos::new(3, 5, 3), crate::block::STONE);
+        let far = crate::effects::block_broken(BlockPos::new(50 * 64, 5, 50 * 64), crate::block::STONE);
+        bus.publish_effects(ChangeSource::Player(9), near.into_iter().chain(far).collect());
+        match &*rx.try_recv().expect("near effects") {
+            SpatialMsg::Effects(b) => {
+                assert_eq!(b.source, ChangeSource::Player(9));
+                assert_eq!(b.effects.len(), 2, "only the near sound and particles");
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn topics_filter_and_capacities_drop() {
+        let bus = SpatialBus::new();
+        let (_blocks, mut blocks_rx) = bus
+            .subscriber()
+            .topics(Topic::Blocks)
+            .capacity(Topic::Blocks, 2)
+            .everywhere()
+            .build();
+
+        bus.publish_move(moved_at(12.0, 12.0));
+        for i in 0..3 {
+            bus.publish_world(
+                ChangeSource::Physics,
+                vec![(BlockPos::new(i * 10_000, 5, 0), BlockId::new(1))],
+                vec![],
+            );
+        }
+        assert!(matches!(*blocks_rx.try_recv().unwrap(), SpatialMsg::World(_)), "moves filtered out");
+        assert!(blocks_rx.try_recv().is_ok(), "any region when everywhere");
+        assert!(blocks_rx.try_recv().is_err());
+        assert_eq!(blocks_rx.dropped(), 1, "third batch over the limit");
+
+        bus.publish_world(ChangeSource::Physics, vec![(BlockPos::new(1, 5, 1), BlockId::new(1))], vec![]);
+        assert!(blocks_rx.try_recv().is_ok(), "reading frees room");
+        assert!(Topics::ALL.contains(Topic::Effects) && !Topics::from(Topic::Moves).contains(Topic::Blocks));
+    }
+
     #[test]
     fn drop_unsubscribes() {
         let bus = SpatialBus::new();
@@ -343,6 +656,10 @@ mod spatial_tests {
         // No panic / no leak: bucket entries were removed on Drop.
         let total: usize = bus.buckets.iter().map(|b| b.len()).sum();
         assert_eq!(total, 0);
+
+        let (everywhere, _rx) = bus.subscriber().everywhere().build();
+        drop(everywhere);
+        assert!(bus.everywhere.read().unwrap().is_empty());
     }
 }
 
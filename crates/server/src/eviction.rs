@@ -36,7 +36,7 @@ pub fn evict_far_chunks(world: &World, keep_centers: &[ChunkPos], keep_radius: i
         .filter(|pos| {
             keep_centers
                 .iter()
-                .all(|c| (pos.x - c.x).abs().max((pos.z - c.z).abs()) > keep_radius)
+                .all(|c| pos.chebyshev(*c) > keep_radius)
         })
         .collect();
 
@@ -54,22 +54,28 @@ pub fn evict_far_chunks(world: &World, keep_centers: &[ChunkPos], keep_radius: i
 
 /// Start the periodic eviction task. `keep_radius` is in chunks;
 /// `spawn_radius` keeps the spawn region resident even with no players.
+/// Stops at `shutdown`.
 pub fn start(
     world: Arc<World>,
     registry: Arc<PlayerRegistry>,
     keep_radius: i32,
     spawn_radius: i32,
     interval_secs: u64,
+    shutdown: crate::shutdown::Shutdown,
 ) {
     if interval_secs == 0 {
         tracing::info!("Chunk eviction disabled (world.eviction_interval_secs = 0)");
         return;
     }
     tokio::spawn(async move {
+        let _running = shutdown.task();
         let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
         interval.tick().await; // skip the immediate first tick
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.triggered() => break,
+            }
 
             // Keep-centers: every player's chunk, plus spawn.
             let mut centers: Vec<ChunkPos> = registry
@@ -0,0 +1,100 @@
+//! Game modes: creative (every player's mode on join), survival,
+//! adventure and spectator. Survival digging takes the block's time with
+//! the held tool and wears it (see [`crate::items::break_ticks`]), and
+//! survival players fight (see [`crate::combat`]); broken blocks don't
+//! drop anything yet.
+//!
+//! Each player's mode lives in their [`PlayerInfo`](crate::player_registry::PlayerInfo);
+//! `/gamemode` changes it through the registry, which tells every
+//! connection (see `PlayerEvent::GameModeChanged`). The player's own
+//! connection switches the client over and sends the mode's abilities;
+//! everyone else updates the tab list and hides or shows the player's
+//! entity — spectators are only visible to other spectators, as in
+//! vanilla. Spectator no-clip is the client's own behaviour once it is in
+//! spectator mode.
+
+use azalea_core::game_type::GameMode;
+use azalea_protocol::packets::game::c_player_abilities::{ClientboundPlayerAbilities, PlayerAbilitiesFlags};
+
+/// The mode players join in.
+pub const DEFAULT: GameMode = GameMode::Creative;
+
+/// Modes `/gamemode` switches between.
+pub const SUPPORTED: [GameMode; 4] = [GameMode::Survival, GameMode::Creative, GameMode::Adventure, GameMode::Spectator];
+
+/// A supported mode by its command name (`creative`, ...).
+pub fn parse(name: &str) -> Option<GameMode> {
+    SUPPORTED.into_iter().find(|mode| mode.name() == name)
+}
+
+/// Display name, as vanilla's `/gamemode` feedback spells it.
+pub fn display_name(mode: GameMode) -> &'static str {
+    match mode {
+        GameMode::Survival => "Survival Mode",
+        GameMode::Creative => "Creative Mode",
+        GameMode::Adventure => "Adventure Mode",
+        GameMode::Spectator => "Spectator Mode",
+    }
+}
+
+/// The abilities packet for `mode`, with vanilla's default speeds.
+pub fn abilities(mode: GameMode) -> ClientboundPlayerAbilities {
+    let flags = match mode {
+        GameMode::Creative => PlayerAbilitiesFlags {
+            invulnerable: true,
+            flying: false,
+            can_fly: true,
+            instant_break: true,
+        },
+        GameMode::Spectator => PlayerAbilitiesFlags {
+            invulnerable: true,
+            flying: true,
+            can_fly: true,
+            instant_break: false,
+        },
+        GameMode::Survival | GameMode::Adventure => PlayerAbilitiesFlags {
+            invulnerable: false,
+            flying: false,
+            can_fly: false,
+            instant_break: false,
+        },
+    };
+    ClientboundPlayerAbilities { flags, flying_speed: 0.05, walking_speed: 0.1 }
+}
+
+/// Whether a player in `mode` may break, place or use items on blocks.
+/// Adventure players can't without `can_break`/`can_place_on` item
+/// components, which we don't support, so they never may.
+pub fn may_edit_blocks(mode: GameMode) -> bool {
+    !mode.is_block_placing_restricted()
+}
+
+/// Whether a player in `target` mode is shown to one in `viewer` mode.
+pub fn visible_to(target: GameMode, viewer: GameMode) -> bool {
+    target != GameMode::Spectator || viewer == GameMode::Spectator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_rules() {
+        assert_eq!(parse("adventure"), Some(GameMode::Adventure));
+        assert_eq!(parse("survival"), Some(GameMode::Survival));
+        assert_eq!(parse("Creative"), None);
+
+        assert!(may_edit_blocks(GameMode::Creative));
+        assert!(may_edit_blocks(GameMode::Survival));
+        assert!(!may_edit_blocks(GameMode::Adventure));
+        assert!(!may_edit_blocks(GameMode::Spectator));
+
+        assert!(visible_to(GameMode::Creative, GameMode::Spectator));
+        assert!(!visible_to(GameMode::Spectator, GameMode::Adventure));
+        assert!(visible_to(GameMode::Spectator, GameMode::Spectator));
+
+        assert!(abilities(GameMode::Spectator).flags.flying);
+        assert!(abilities(GameMode::Creative).flags.instant_break);
+        assert!(!abilities(GameMode::Adventure).flags.can_fly);
+    }
+}
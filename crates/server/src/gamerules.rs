@@ -0,0 +1,188 @@
+//! Game rules: vanilla's per-world switches (`doDaylightCycle`,
+//! `doMobSpawning`, `randomTickSpeed`, ...), set with `/gamerule` and
+//! saved in `level.dat`.
+//!
+//! `level.dat`'s string map (`LevelInfo::game_rules`) is the record;
+//! [`crate::persistence::WorldStorage`] installs it here when the world
+//! opens and on every `/gamerule`, so code with no handle on the world —
+//! physics rules are plain `fn`s — reads a rule with [`enabled`] or [`value`]
+//! off an atomic. One world per process, as with `rules.toml`.
+//!
+//! Consulted so far: `doDaylightCycle` (world time stops advancing),
+//! `doMobSpawning` (the mob layer), `doWeatherCycle` (the weather layer),
+//! and `waterSourceConversion` / `lavaSourceConversion` (fluid rules). The
+//! rest are stored for the layers that will read them.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use anyhow::{Result, bail};
+
+/// Whether a rule holds `true`/`false` or a whole number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Bool,
+    Int,
+}
+
+/// Every rule the server knows, with vanilla's names and defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameRule {
+    DoDaylightCycle,
+    DoFireTick,
+    DoMobSpawning,
+    DoWeatherCycle,
+    KeepInventory,
+    LavaSourceConversion,
+    MobGriefing,
+    RandomTickSpeed,
+    SpawnRadius,
+    WaterSourceConversion,
+}
+
+impl GameRule {
+    pub const ALL: [GameRule; 10] = [
+        GameRule::DoDaylightCycle,
+        GameRule::DoFireTick,
+        GameRule::DoMobSpawning,
+        GameRule::DoWeatherCycle,
+        GameRule::KeepInventory,
+        GameRule::LavaSourceConversion,
+        GameRule::MobGriefing,
+        GameRule::RandomTickSpeed,
+        GameRule::SpawnRadius,
+        GameRule::WaterSourceConversion,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            GameRule::DoDaylightCycle => "doDaylightCycle",
+            GameRule::DoFireTick => "doFireTick",
+            GameRule::DoMobSpawning => "doMobSpawning",
+            GameRule::DoWeatherCycle => "doWeatherCycle",
+            GameRule::KeepInventory => "keepInventory",
+            GameRule::LavaSourceConversion => "lavaSourceConversion",
+            GameRule::MobGriefing => "mobGriefing",
+            GameRule::RandomTickSpeed => "randomTickSpeed",
+            GameRule::SpawnRadius => "spawnRadius",
+            GameRule::WaterSourceConversion => "waterSourceConversion",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<GameRule> {
+        GameRule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+
+    pub const fn kind(self) -> Kind {
+        match self {
+            GameRule::RandomTickSpeed | GameRule::SpawnRadius => Kind::Int,
+            _ => Kind::Bool,
+        }
+    }
+
+    /// Vanilla's default; bools as 0 and 1.
+    const fn default_value(self) -> i64 {
+        match self {
+            GameRule::KeepInventory | GameRule::LavaSourceConversion => 0,
+            GameRule::RandomTickSpeed => 3,
+            GameRule::SpawnRadius => 10,
+            _ => 1,
+        }
+    }
+
+    /// The default as `level.dat` stores it.
+    pub fn default_string(self) -> String {
+        format_value(self.kind(), self.default_value())
+    }
+
+    /// Check `value` for this rule and return it as `level.dat` stores
+    /// it (`true`/`false`, or a number).
+    pub fn normalize(self, value: &str) -> Result<String> {
+        Ok(format_value(self.kind(), parse_value(self.kind(), value)?))
+    }
+}
+
+fn parse_value(kind: Kind, value: &str) -> Result<i64> {
+    match (kind, value) {
+        (Kind::Bool, "true") => Ok(1),
+        (Kind::Bool, "false") => Ok(0),
+        (Kind::Bool, _) => bail!("expected true or false, got {:?}", value),
+        (Kind::Int, _) => value.parse().map_err(|_| anyhow::anyhow!("expected a whole number, got {:?}", value)),
+    }
+}
+
+fn format_value(kind: Kind, value: i64) -> String {
+    match kind {
+        Kind::Bool => (value != 0).to_string(),
+        Kind::Int => value.to_string(),
+    }
+}
+
+/// Current values in [`GameRule::ALL`] order.
+static VALUES: [AtomicI64; GameRule::ALL.len()] = {
+    let mut values = [const { AtomicI64::new(0) }; GameRule::ALL.len()];
+    let mut i = 0;
+    while i < values.len() {
+        values[i] = AtomicI64::new(GameRule::ALL[i].default_value());
+        i += 1;
+    }
+    values
+};
+
+fn slot(rule: GameRule) -> &'static AtomicI64 {
+    &VALUES[rule as usize]
+}
+
+/// Make the rules in `level.dat`'s map current. Missing or malformed
+/// entries take the default.
+pub fn install(rules: &BTreeMap<String, String>) {
+    for rule in GameRule::ALL {
+        let value = match rules.get(rule.name()).map(|v| parse_value(rule.kind(), v)) {
+            Some(Ok(value)) => value,
+            Some(Err(e)) => {
+                tracing::warn!("Ignoring gamerule {}: {:#}", rule.name(), e);
+                rule.default_value()
+            }
+            None => rule.default_value(),
+        };
+        slot(rule).store(value, Ordering::Relaxed);
+    }
+}
+
+/// A boolean rule's current value.
+pub fn enabled(rule: GameRule) -> bool {
+    debug_assert_eq!(rule.kind(), Kind::Bool);
+    slot(rule).load(Ordering::Relaxed) != 0
+}
+
+/// A numeric rule's current value.
+pub fn value(rule: GameRule) -> i64 {
+    debug_assert_eq!(rule.kind(), Kind::Int);
+    slot(rule).load(Ordering::Relaxed)
+}
+
+/// Every rule at its default, as `level.dat` stores them.
+pub fn defaults() -> BTreeMap<String, String> {
+    GameRule::ALL.into_iter().map(|rule| (rule.name().to_owned(), rule.default_string())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_parse_and_normalize() {
+        assert_eq!(GameRule::parse("randomTickSpeed"), Some(GameRule::RandomTickSpeed));
+        assert_eq!(GameRule::parse("doesNotExist"), None);
+        assert_eq!(GameRule::DoFireTick.normalize("false").unwrap(), "false");
+        assert!(GameRule::DoFireTick.normalize("0").is_err());
+        assert_eq!(GameRule::RandomTickSpeed.normalize("20").unwrap(), "20");
+        assert!(GameRule::RandomTickSpeed.normalize("fast").is_err());
+
+        let defaults = defaults();
+        assert_eq!(defaults.len(), GameRule::ALL.len());
+        assert_eq!(defaults["doMobSpawning"], "true");
+        assert_eq!(defaults["lavaSourceConversion"], "false");
+        assert_eq!(defaults["spawnRadius"], "10");
+    }
+}
@@ -0,0 +1,525 @@
+//! A player's inventory window: its slots, the item on the cursor,
+//! and the crafting grid.
+//!
+//! Every slot holds the real [`ItemStack`] the client sent — blocks,
+//! tools, buckets, potions, anything — so nothing the player picks
+//! disappears. Whether the held item places a block is decided only when
+//! it is used ([`Inventory::held_block`]); until then it's just an item.
+//!
+//! Slots are numbered as in the player inventory window, which is what
+//! `SetCreativeModeSlot` and `ContainerClick` address: 0 crafting output,
+//! 1-4 crafting grid, 5-8 armour, 9-35 main inventory, 36-44 hotbar, 45
+//! offhand.
+//!
+//! Outside creative the server owns the window: [`Inventory::click`]
+//! replays the player's clicks (picking up, placing, splitting, shift-
+//! and number-key moves) against its own slots and the item on the
+//! cursor, and keeps the crafting output showing what the grid crafts
+//! (see [`crate::recipes`]). Taking the output consumes one item from
+//! each grid slot. The connection answers every click with the whole
+//! window, so a client that predicted differently is corrected. Items
+//! can't be dropped yet (there are no item entities): clicking outside
+//! the window, dragging and throwing leave everything where it was.
+
+use std::collections::BTreeMap;
+
+use azalea_block::BlockState;
+use azalea_inventory::operations::ClickType;
+use azalea_inventory::ItemStack;
+use azalea_registry::builtin::{BlockKind, ItemKind};
+
+use crate::items::SavedItem;
+use crate::recipes::{self, RecipeBook};
+
+/// Slots in the player inventory window.
+pub const SLOTS: usize = 46;
+
+/// Window slot of hotbar slot 0.
+pub const HOTBAR_START: usize = 36;
+
+/// Hotbar width.
+pub const HOTBAR_SLOTS: usize = 9;
+
+/// Window slot of the crafting output.
+pub const CRAFT_OUTPUT: usize = 0;
+
+/// Window slots of the 2×2 crafting grid, row-major.
+pub const CRAFT_GRID: std::ops::Range<usize> = 1..5;
+
+/// Window slot of the offhand.
+pub const OFFHAND: usize = 45;
+
+/// Main inventory and hotbar: where shift-clicked and returned items go.
+const STORAGE: std::ops::Range<usize> = 9..45;
+
+#[derive(Debug, Clone)]
+pub struct Inventory {
+    slots: Vec<ItemStack>,
+    /// Selected hotbar slot, `0..HOTBAR_SLOTS`.
+    selected: usize,
+    /// The item on the cursor while the window is open.
+    carried: ItemStack,
+    /// Bumped by every server-side change to the window; `ContainerClick`
+    /// and the window updates we send carry it.
+    state_id: u32,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self { slots: vec![ItemStack::Empty; SLOTS], selected: 0, carried: ItemStack::Empty, state_id: 0 }
+    }
+}
+
+/// Vanilla's stack size for `kind`: 1 for tools, armour, filled buckets
+/// and the like, 16 for the few small stacks, 64 for everything else.
+pub fn max_stack(kind: ItemKind) -> i32 {
+    let full = kind.to_string();
+    let name = full.strip_prefix("minecraft:").unwrap_or(&full);
+    const SINGLE: [&str; 14] = [
+        "_sword", "_pickaxe", "_axe", "_shovel", "_hoe", "_helmet", "_chestplate", "_leggings", "_boots",
+        "_bucket", "_boat", "_minecart", "_horse_armor", "shulker_box",
+    ];
+    const SINGLE_NAMES: [&str; 16] = [
+        "bow", "crossbow", "trident", "shield", "elytra", "fishing_rod", "flint_and_steel", "shears", "potion",
+        "splash_potion", "lingering_potion", "saddle", "totem_of_undying", "enchanted_book", "writable_book", "mace",
+    ];
+    const SIXTEEN: [&str; 7] = ["ender_pearl", "snowball", "egg", "bucket", "honey_bottle", "armor_stand", "written_book"];
+    if SIXTEEN.contains(&name) || name.ends_with("_sign") || name.ends_with("_banner") {
+        16
+    } else if SINGLE_NAMES.contains(&name) || SINGLE.iter().any(|suffix| name.ends_with(suffix)) {
+        1
+    } else {
+        64
+    }
+}
+
+/// `stack` with `count` items (empty at 0), keeping its components.
+pub fn with_count(stack: &ItemStack, count: i32) -> ItemStack {
+    match stack {
+        ItemStack::Present(data) if count > 0 => {
+            let mut data = data.clone();
+            data.count = count;
+            ItemStack::Present(data)
+        }
+        _ => ItemStack::Empty,
+    }
+}
+
+/// Whether `a` and `b` are the same item with the same components, so
+/// they can share a slot.
+fn stacks_with(a: &ItemStack, b: &ItemStack) -> bool {
+    match (a, b) {
+        (ItemStack::Present(a), ItemStack::Present(b)) => a.kind == b.kind && a.component_patch == b.component_patch,
+        _ => false,
+    }
+}
+
+impl Inventory {
+    /// Put `item` in window slot `slot`. Returns `false` (and changes
+    /// nothing) for slots outside the window, such as the creative menu's
+    /// "drop" slot.
+    pub fn set_slot(&mut self, slot: usize, item: ItemStack) -> bool {
+        match self.slots.get_mut(slot) {
+            Some(s) => {
+                *s = item;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The item in window slot `slot` (empty outside the window).
+    pub fn slot(&self, slot: usize) -> &ItemStack {
+        const EMPTY: &ItemStack = &ItemStack::Empty;
+        self.slots.get(slot).unwrap_or(EMPTY)
+    }
+
+    /// Select hotbar slot `index`, clamped to the hotbar.
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(HOTBAR_SLOTS - 1);
+    }
+
+    /// The selected hotbar slot.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// The item in the selected hotbar slot.
+    pub fn held(&self) -> &ItemStack {
+        self.slot(HOTBAR_START + self.selected)
+    }
+
+    /// Replace the held item, returning its window slot.
+    pub fn set_held(&mut self, item: ItemStack) -> usize {
+        let slot = HOTBAR_START + self.selected;
+        self.slots[slot] = item;
+        slot
+    }
+
+    /// The block the held item places, in its default state, or `None`
+    /// for empty hands and items that aren't blocks (tools, food, ...).
+    pub fn held_block(&self) -> Option<BlockState> {
+        item_block(self.held().kind()).map(BlockState::from)
+    }
+
+    /// Every window slot, in order.
+    pub fn slots(&self) -> &[ItemStack] {
+        &self.slots
+    }
+
+    pub fn carried(&self) -> &ItemStack {
+        &self.carried
+    }
+
+    pub fn state_id(&self) -> u32 {
+        self.state_id
+    }
+
+    /// Occupied slots as saved with the player (see
+    /// [`crate::playerdata`]). The crafting output isn't saved: it is
+    /// whatever the grid crafts.
+    pub fn save(&self) -> BTreeMap<usize, SavedItem> {
+        self.slots
+            .iter()
+            .enumerate()
+            .skip(CRAFT_OUTPUT + 1)
+            .filter_map(|(slot, stack)| Some((slot, SavedItem::from_stack(stack)?)))
+            .collect()
+    }
+
+    /// Fill the window from [`Inventory::save`]d slots. Items this
+    /// version doesn't know are left out, and so named in the result.
+    pub fn restore(&mut self, recipes: &RecipeBook, saved: &BTreeMap<usize, SavedItem>) -> Vec<String> {
+        let mut lost = Vec::new();
+        for (&slot, item) in saved {
+            match item.to_stack() {
+                Ok(stack) if slot != CRAFT_OUTPUT && slot < SLOTS => self.slots[slot] = stack,
+                Ok(_) => lost.push(format!("{} in slot {}", item.id, slot)),
+                Err(e) => lost.push(e.to_string()),
+            }
+        }
+        self.update_output(recipes);
+        lost
+    }
+
+    /// Apply a `ContainerClick` on the player window: `slot` as the
+    /// packet numbers it (`-999` outside the window), `button` and `kind`
+    /// as vanilla defines them. Unsupported clicks change nothing. The
+    /// caller sends the window back either way.
+    pub fn click(&mut self, recipes: &RecipeBook, slot: i16, button: u8, kind: ClickType) {
+        self.state_id = self.state_id.wrapping_add(1);
+        let Ok(slot) = usize::try_from(slot) else { return };
+        if slot >= SLOTS {
+            return;
+        }
+        match (kind, slot) {
+            (ClickType::Pickup, CRAFT_OUTPUT) => {
+                let output = self.slots[CRAFT_OUTPUT].clone();
+                if !output.is_empty() && self.carry(&output) {
+                    self.consume_grid(recipes);
+                }
+            }
+            (ClickType::Pickup, _) => pickup(&mut self.slots[slot], &mut self.carried, button == 1),
+            (ClickType::QuickMove, CRAFT_OUTPUT) => {
+                // Craft until the grid runs out or the inventory is full.
+                while !self.slots[CRAFT_OUTPUT].is_empty() && self.fits(&self.slots[CRAFT_OUTPUT], STORAGE) {
+                    let output = self.slots[CRAFT_OUTPUT].clone();
+                    self.insert(output, STORAGE);
+                    self.consume_grid(recipes);
+                }
+            }
+            (ClickType::QuickMove, _) => {
+                let target = match slot {
+                    9..36 => 36..45,
+                    36..45 => 9..36,
+                    _ => STORAGE,
+                };
+                let item = std::mem::replace(&mut self.slots[slot], ItemStack::Empty);
+                self.slots[slot] = self.insert(item, target);
+            }
+            // Number keys 1-9 swap with that hotbar slot; F (40) with the
+            // offhand.
+            (ClickType::Swap, _) => {
+                let other = match button {
+                    0..9 => HOTBAR_START + button as usize,
+                    40 => OFFHAND,
+                    _ => return,
+                };
+                if slot == CRAFT_OUTPUT {
+                    if self.slots[other].is_empty() && !self.slots[CRAFT_OUTPUT].is_empty() {
+                        self.slots[other] = self.slots[CRAFT_OUTPUT].clone();
+                        self.consume_grid(recipes);
+                    }
+                } else {
+                    self.slots.swap(slot, other);
+                }
+            }
+            _ => return,
+        }
+        self.update_output(recipes);
+    }
+
+    /// A plain click, with this window's cursor, on a slot of another
+    /// window (a merchant's payment slots; see [`crate::trading`]).
+    pub fn pickup_with(&mut self, slot: &mut ItemStack, one: bool) {
+        self.state_id = self.state_id.wrapping_add(1);
+        pickup(slot, &mut self.carried, one);
+    }
+
+    /// Add all of `item` to the cursor, if it fits there.
+    pub fn carry(&mut self, item: &ItemStack) -> bool {
+        let fits = self.carried.is_empty()
+            || (stacks_with(&self.carried, item) && self.carried.count() + item.count() <= max_stack(item.kind()));
+        if fits {
+            self.carried = with_count(item, self.carried.count() + item.count());
+        }
+        fits
+    }
+
+    /// Put `item` into the main inventory and hotbar, returning what
+    /// didn't fit.
+    pub fn give(&mut self, item: ItemStack) -> ItemStack {
+        self.insert(item, STORAGE)
+    }
+
+    /// Whether all of `item` would fit in the main inventory and hotbar.
+    pub fn has_room(&self, item: &ItemStack) -> bool {
+        self.fits(item, STORAGE)
+    }
+
+    /// Take up to `count` plain `kind` items (no components) out of the
+    /// main inventory and hotbar, returning how many were taken.
+    pub fn take(&mut self, kind: ItemKind, count: i32) -> i32 {
+        let wanted = ItemStack::new(kind, 1);
+        let mut taken = 0;
+        for slot in STORAGE {
+            let current = &mut self.slots[slot];
+            if taken < count && stacks_with(current, &wanted) {
+                let take = (count - taken).min(current.count());
+                *current = with_count(current, current.count() - take);
+                taken += take;
+            }
+        }
+        taken
+    }
+
+    /// Whether all of `item` would fit in `range`.
+    fn fits(&self, item: &ItemStack, range: std::ops::Range<usize>) -> bool {
+        let room: i32 = self.slots[range]
+            .iter()
+            .map(|slot| match slot {
+                ItemStack::Empty => max_stack(item.kind()),
+                _ if stacks_with(slot, item) => max_stack(item.kind()) - slot.count(),
+                _ => 0,
+            })
+            .sum();
+        room >= item.count()
+    }
+
+    /// Put `item` into `range`, topping up matching stacks before filling
+    /// empty slots, and return what didn't fit.
+    fn insert(&mut self, item: ItemStack, range: std::ops::Range<usize>) -> ItemStack {
+        let mut left = item.count();
+        let max = max_stack(item.kind());
+        for empty_pass in [false, true] {
+            for slot in range.clone() {
+                if left == 0 {
+                    return ItemStack::Empty;
+                }
+                let current = &mut self.slots[slot];
+                let usable = if empty_pass { current.is_empty() } else { stacks_with(current, &item) };
+                if usable {
+                    let put = left.min(max - current.count()).max(0);
+                    *current = with_count(&item, current.count() + put);
+                    left -= put;
+                }
+            }
+        }
+        with_count(&item, left)
+    }
+
+    /// Take one item from each grid slot, leaving containers behind.
+    fn consume_grid(&mut self, recipes: &RecipeBook) {
+        for slot in CRAFT_GRID {
+            let item = self.slots[slot].clone();
+            if item.is_empty() {
+                continue;
+            }
+            self.slots[slot] = with_count(&item, item.count() - 1);
+            if let Some(left) = recipes::remainder(item.kind()) {
+                // Into the emptied slot, else the inventory. With no room
+                // it's lost, where vanilla would drop it.
+                let left = ItemStack::new(left, 1);
+                if self.slots[slot].is_empty() {
+                    self.slots[slot] = left;
+                } else {
+                    self.insert(left, STORAGE);
+                }
+            }
+        }
+        self.update_output(recipes);
+    }
+
+    /// Show in the output slot what the crafting grid makes now.
+    pub fn update_output(&mut self, recipes: &RecipeBook) {
+        let grid: Vec<Option<ItemKind>> =
+            self.slots[CRAFT_GRID].iter().map(|item| (!item.is_empty()).then(|| item.kind())).collect();
+        self.slots[CRAFT_OUTPUT] = match recipes.find(&grid, 2) {
+            Some(recipe) => ItemStack::new(recipe.result, recipe.count),
+            None => ItemStack::Empty,
+        };
+    }
+
+    /// The window closed: put the grid and the cursor back into the
+    /// inventory. Whatever doesn't fit stays where it was.
+    pub fn close(&mut self, recipes: &RecipeBook) {
+        self.state_id = self.state_id.wrapping_add(1);
+        for slot in CRAFT_GRID {
+            let item = std::mem::replace(&mut self.slots[slot], ItemStack::Empty);
+            self.slots[slot] = self.insert(item, STORAGE);
+        }
+        let carried = std::mem::replace(&mut self.carried, ItemStack::Empty);
+        self.carried = self.insert(carried, STORAGE);
+        self.update_output(recipes);
+    }
+}
+
+/// A plain left (or, with `one`, right) click on `held` while
+/// `carried` is on the cursor.
+fn pickup(held: &mut ItemStack, carried: &mut ItemStack, one: bool) {
+    if carried.is_empty() {
+        // Pick up all, or the larger half.
+        let take = if one { (held.count() + 1) / 2 } else { held.count() };
+        *carried = with_count(held, take);
+        *held = with_count(held, held.count() - take);
+    } else if held.is_empty() || stacks_with(held, carried) {
+        let room = max_stack(carried.kind()) - held.count();
+        let put = if one { 1 } else { carried.count() }.min(room).max(0);
+        *held = with_count(if held.is_empty() { &*carried } else { &*held }, held.count() + put);
+        *carried = with_count(carried, carried.count() - put);
+    } else {
+        std::mem::swap(held, carried);
+    }
+}
+
+/// The block an item places. Most block items share the block's name;
+/// the ones that don't (seeds, redstone dust, ...) are listed. Water and
+/// lava buckets aren't block items: they pour through
+/// [`crate::item_use::use_bucket`].
+pub fn item_block(item: ItemKind) -> Option<BlockKind> {
+    let special = match item {
+        ItemKind::Air => return None,
+        ItemKind::PowderSnowBucket => BlockKind::PowderSnow,
+        ItemKind::Redstone => BlockKind::RedstoneWire,
+        ItemKind::String => BlockKind::Tripwire,
+        ItemKind::WheatSeeds => BlockKind::Wheat,
+        ItemKind::BeetrootSeeds => BlockKind::Beetroots,
+        ItemKind::MelonSeeds => BlockKind::MelonStem,
+        ItemKind::PumpkinSeeds => BlockKind::PumpkinStem,
+        ItemKind::TorchflowerSeeds => BlockKind::TorchflowerCrop,
+        ItemKind::PitcherPod => BlockKind::PitcherCrop,
+        ItemKind::Carrot => BlockKind::Carrots,
+        ItemKind::Potato => BlockKind::Potatoes,
+        ItemKind::CocoaBeans => BlockKind::Cocoa,
+        ItemKind::SweetBerries => BlockKind::SweetBerryBush,
+        ItemKind::GlowBerries => BlockKind::CaveVines,
+        _ => {
+            // Display gives "minecraft:oak_planks"; FromStr wants "oak_planks".
+            let full = item.to_string();
+            let name = full.strip_prefix("minecraft:").unwrap_or(&full);
+            return name.parse().ok();
+        }
+    };
+    Some(special)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_block_items_are_kept() {
+        let mut inv = Inventory::default();
+        assert!(inv.set_slot(HOTBAR_START, ItemStack::new(ItemKind::DiamondPickaxe, 1)));
+        assert!(inv.set_slot(HOTBAR_START + 1, ItemStack::new(ItemKind::Stone, 64)));
+        assert!(inv.set_slot(9, ItemStack::new(ItemKind::Bucket, 1)));
+        assert!(!inv.set_slot(u16::MAX as usize, ItemStack::new(ItemKind::Stone, 1)), "drop slot");
+
+        assert_eq!(inv.held().kind(), ItemKind::DiamondPickaxe);
+        assert_eq!(inv.held_block(), None, "tools stay items");
+        inv.select(1);
+        assert_eq!(inv.held_block(), Some(BlockState::from(BlockKind::Stone)));
+        assert_eq!(inv.slot(9).kind(), ItemKind::Bucket);
+        inv.select(20);
+        assert_eq!(inv.selected(), HOTBAR_SLOTS - 1);
+        assert!(inv.held().is_empty());
+    }
+
+    #[test]
+    fn test_crafting_in_the_player_grid() {
+        use crate::recipes::Tags;
+
+        let mut book = RecipeBook::default();
+        let sticks = r#"{"type": "minecraft:crafting_shaped", "key": {"P": "minecraft:oak_planks"},
+            "pattern": ["P", "P"], "result": {"id": "minecraft:stick", "count": 4}}"#;
+        book.add("minecraft:stick", sticks, &Tags::default()).unwrap();
+        let mut inv = Inventory::default();
+        inv.set_slot(9, ItemStack::new(ItemKind::OakPlanks, 3));
+
+        // Pick up the planks and right-click one into each slot of the
+        // grid's left column.
+        inv.click(&book, 9, 0, ClickType::Pickup);
+        assert_eq!(inv.carried().count(), 3);
+        inv.click(&book, 1, 1, ClickType::Pickup);
+        inv.click(&book, 3, 1, ClickType::Pickup);
+        assert_eq!((inv.slot(CRAFT_OUTPUT).kind(), inv.slot(CRAFT_OUTPUT).count()), (ItemKind::Stick, 4));
+
+        // The output doesn't stack onto planks on the cursor.
+        inv.click(&book, 0, 0, ClickType::Pickup);
+        assert_eq!((inv.carried().kind(), inv.slot(1).count()), (ItemKind::OakPlanks, 1));
+        inv.click(&book, 9, 0, ClickType::Pickup);
+        assert!(inv.carried().is_empty());
+
+        // Shift-clicking the output crafts into the inventory.
+        inv.click(&book, 0, 0, ClickType::QuickMove);
+        assert_eq!((inv.slot(10).kind(), inv.slot(10).count()), (ItemKind::Stick, 4));
+        assert!(inv.slot(1).is_empty() && inv.slot(3).is_empty() && inv.slot(CRAFT_OUTPUT).is_empty());
+
+        // Closing the window puts the grid back.
+        inv.click(&book, 9, 0, ClickType::Pickup);
+        inv.click(&book, 2, 0, ClickType::Pickup);
+        inv.close(&book);
+        assert!(inv.slot(2).is_empty());
+        assert_eq!((inv.slot(9).kind(), inv.slot(9).count()), (ItemKind::OakPlanks, 1));
+        assert!(inv.state_id() > 0);
+    }
+
+    #[test]
+    fn test_item_block_names() {
+        assert_eq!(item_block(ItemKind::OakPlanks), Some(BlockKind::OakPlanks));
+        assert_eq!(item_block(ItemKind::PowderSnowBucket), Some(BlockKind::PowderSnow));
+        assert_eq!(item_block(ItemKind::WaterBucket), None);
+        assert_eq!(item_block(ItemKind::WheatSeeds), Some(BlockKind::Wheat));
+        assert_eq!(item_block(ItemKind::Redstone), Some(BlockKind::RedstoneWire));
+        assert_eq!(item_block(ItemKind::Bucket), None);
+        assert_eq!(item_block(ItemKind::Apple), None);
+        assert_eq!(item_block(ItemKind::Air), None);
+    }
+
+    #[test]
+    fn test_save_and_restore() {
+        let book = RecipeBook::default();
+        let mut inv = Inventory::default();
+        inv.set_slot(HOTBAR_START, ItemStack::new(ItemKind::DiamondPickaxe, 1));
+        inv.set_slot(OFFHAND, ItemStack::new(ItemKind::Torch, 12));
+        let mut saved = inv.save();
+        assert_eq!(saved.keys().copied().collect::<Vec<_>>(), vec![HOTBAR_START, OFFHAND]);
+
+        saved.insert(9, SavedItem { id: "minecraft:no_such_item".into(), ..saved[&OFFHAND].clone() });
+        let mut back = Inventory::default();
+        assert_eq!(back.restore(&book, &saved).len(), 1);
+        assert_eq!(back.held().kind(), ItemKind::DiamondPickaxe);
+        assert_eq!(back.slot(OFFHAND).count(), 12);
+        assert!(back.slot(9).is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+//! Using a held item on a block, for items that act on what they click
+//! instead of placing something next to it.
+//!
+//! [`use_on`] decides what the clicked block becomes; the connection
+//! submits that as a `BlockSet` to the physics service like any other
+//! player action, so neighbours are notified and clients see the change
+//! through the normal event-bus path.
+//!
+//! Covered so far: cauldrons, filled and emptied with buckets and bottles.
+//! Players are in creative, so the held item is never swapped or used up.
+//! Rain adds water a level at a time ([`rain_fill`], driven by
+//! [`crate::weather`]).
+//!
+//! Buckets also work on fluids in the world ([`use_bucket`]). The client
+//! sends those as a plain "use item" with its look direction, since fluids
+//! aren't targetable blocks; we raycast from the eye like vanilla: an
+//! empty bucket scoops the first fluid source in reach, a filled one pours
+//! a source against the face of the first solid block. Unlike cauldrons,
+//! these swap the held bucket (empty ↔ filled).
+
+use azalea_block::{BlockState, BlockTrait};
+use azalea_inventory::ItemStack;
+use azalea_inventory::components::PotionContents;
+use azalea_registry::builtin::{ItemKind, Potion};
+use ultimate_engine::world::World;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::position::BlockPos;
+
+use crate::block::FluidKind;
+
+use crate::persistence::lookup_block_state;
+
+/// Full level for water and powder snow cauldrons.
+const CAULDRON_MAX: u8 = 3;
+
+/// Eye height of a standing player, where bucket rays start.
+pub const EYE_HEIGHT: f64 = 1.62;
+
+/// How far a creative player reaches, in blocks.
+pub const REACH: f64 = 5.0;
+
+/// What the clicked `target` becomes when `held` is used on it, or `None`
+/// if the item does nothing there (and should be placed as usual).
+pub fn use_on(held: &ItemStack, target: BlockId) -> Option<BlockId> {
+    let ItemStack::Present(item) = held else {
+        return None;
+    };
+    let content = Cauldron::of(target)?;
+    let next = match (item.kind, content) {
+        (ItemKind::WaterBucket, _) => Cauldron::Water(CAULDRON_MAX),
+        (ItemKind::LavaBucket, _) => Cauldron::Lava,
+        (ItemKind::PowderSnowBucket, _) => Cauldron::PowderSnow(CAULDRON_MAX),
+        // Buckets only scoop a full cauldron.
+        (ItemKind::Bucket, Cauldron::Water(CAULDRON_MAX) | Cauldron::Lava | Cauldron::PowderSnow(CAULDRON_MAX)) => {
+            Cauldron::Empty
+        }
+        (ItemKind::GlassBottle, Cauldron::Water(level)) => Cauldron::Water(level - 1),
+        (ItemKind::Potion, Cauldron::Empty) if is_water_bottle(item) => Cauldron::Water(1),
+        (ItemKind::Potion, Cauldron::Water(level)) if level < CAULDRON_MAX && is_water_bottle(item) => {
+            Cauldron::Water(level + 1)
+        }
+        _ => return None,
+    };
+    (next != content).then(|| next.block()).flatten()
+}
+
+/// A bucket used on the world: the cell to set and the bucket the player
+/// holds afterwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BucketUse {
+    pub pos: BlockPos,
+    pub old: BlockId,
+    pub new: BlockId,
+    pub result: ItemKind,
+}
+
+/// Use `held` from `eye` looking along (`y_rot`, `x_rot`), in degrees as
+/// the client sends them. `None` if it isn't a water, lava or empty bucket,
+/// or there's nothing in reach to scoop or pour against.
+pub fn use_bucket(world: &World, held: ItemKind, eye: [f64; 3], y_rot: f32, x_rot: f32) -> Option<BucketUse> {
+    let dir = look_direction(y_rot, x_rot);
+    let fluid = match held {
+        ItemKind::Bucket => {
+            // Flowing fluid is see-through to an empty bucket; anything
+            // else ends the ray, and only a source can be scooped.
+            let is_flowing = |b| crate::block::fluid_kind(b).is_some_and(|(_, level)| level > 0);
+            let pos = raycast(world, eye, dir, REACH, |b| b != BlockId::AIR && !is_flowing(b))?.pos;
+            let old = world.get_block(pos);
+            let (kind, _) = crate::block::fluid_kind(old)?;
+            let result = match kind {
+                FluidKind::Water => ItemKind::WaterBucket,
+                FluidKind::Lava => ItemKind::LavaBucket,
+            };
+            return Some(BucketUse { pos, old, new: BlockId::AIR, result });
+        }
+        ItemKind::WaterBucket => FluidKind::Water,
+        ItemKind::LavaBucket => FluidKind::Lava,
+        _ => return None,
+    };
+    // Filled: fluids don't stop the ray; pour into the cell in front of
+    // the face it hits, if that cell is open.
+    let pos = raycast(world, eye, dir, REACH, |b| !crate::block::is_replaceable(b))?.before?;
+    let old = world.get_block(pos);
+    let new = fluid.source();
+    (crate::block::is_replaceable(old) && old != new).then_some(BucketUse { pos, old, new, result: ItemKind::Bucket })
+}
+
+/// Unit vector a player faces at (`y_rot`, `x_rot`), in degrees as the
+/// client sends them.
+pub(crate) fn look_direction(y_rot: f32, x_rot: f32) -> [f64; 3] {
+    let (yaw, pitch) = (y_rot.to_radians() as f64, x_rot.to_radians() as f64);
+    [-yaw.sin() * pitch.cos(), -pitch.sin(), yaw.cos() * pitch.cos()]
+}
+
+/// The cell a ray stopped at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct RayHit {
+    pub pos: BlockPos,
+    /// The cell the ray came from (the neighbour on the face it entered
+    /// through); `None` if the ray starts inside `pos`.
+    pub before: Option<BlockPos>,
+    /// How far along the ray it entered `pos`, in units of `dir`.
+    pub distance: f64,
+}
+
+/// Walk the cells along a ray (Amanatides–Woo) until `stop` accepts one
+/// within `reach` (in units of `dir`, which is a unit vector for reach in
+/// blocks).
+pub(crate) fn raycast(
+    world: &World,
+    from: [f64; 3],
+    dir: [f64; 3],
+    reach: f64,
+    stop: impl Fn(BlockId) -> bool,
+) -> Option<RayHit> {
+    let mut cell = from.map(|c| c.floor() as i64);
+    let step = dir.map(|d| if d > 0.0 { 1 } else { -1 });
+    // Ray distance to the next boundary on each axis, and between them.
+    let mut next = [0.0; 3];
+    let mut delta = [f64::INFINITY; 3];
+    for axis in 0..3 {
+        if dir[axis] != 0.0 {
+            delta[axis] = (1.0 / dir[axis]).abs();
+            let boundary = if dir[axis] > 0.0 { cell[axis] as f64 + 1.0 } else { cell[axis] as f64 };
+            next[axis] = (boundary - from[axis]) / dir[axis];
+        } else {
+            next[axis] = f64::INFINITY;
+        }
+    }
+    let mut before = None;
+    let mut distance = 0.0;
+    loop {
+        let pos = BlockPos::new(cell[0], cell[1], cell[2]);
+        if stop(world.get_block(pos)) {
+            return Some(RayHit { pos, before, distance });
+        }
+        let axis = (0..3).min_by(|&a, &b| next[a].total_cmp(&next[b])).unwrap();
+        if next[axis] > reach {
+            return None;
+        }
+        before = Some(pos);
+        distance = next[axis];
+        cell[axis] += step[axis];
+        next[axis] += delta[axis];
+    }
+}
+
+fn is_water_bottle(item: &azalea_inventory::ItemStackData) -> bool {
+    item.get_component::<PotionContents>()
+        .is_some_and(|c| c.potion == Some(Potion::Water))
+}
+
+/// What rain falling into `block` makes of it: an empty or partly filled
+/// water cauldron gains a level. `None` for anything else.
+pub fn rain_fill(block: BlockId) -> Option<BlockId> {
+    match Cauldron::of(block)? {
+        Cauldron::Empty => Cauldron::Water(1).block(),
+        Cauldron::Water(level) if level < CAULDRON_MAX => Cauldron::Water(level + 1).block(),
+        _ => None,
+    }
+}
+
+/// A cauldron block state, by contents. Level 0 is the empty cauldron.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cauldron {
+    Empty,
+    Water(u8),
+    Lava,
+    PowderSnow(u8),
+}
+
+impl Cauldron {
+    fn of(id: BlockId) -> Option<Self> {
+        let state = BlockState::try_from(id.0 as u32).ok()?;
+        let block: Box<dyn BlockTrait> = Box::<dyn BlockTrait>::from(state);
+        let level = || {
+            block.property_map().get("level").and_then(|l| l.parse::<u8>().ok())
+        };
+        match block.id() {
+            "cauldron" => Some(Cauldron::Empty),
+            "water_cauldron" => level().map(Cauldron::Water),
+            "lava_cauldron" => Some(Cauldron::Lava),
+            "powder_snow_cauldron" => level().map(Cauldron::PowderSnow),
+            _ => None,
+        }
+    }
+
+    fn block(self) -> Option<BlockId> {
+        let (name, level) = match self {
+            Cauldron::Empty | Cauldron::Water(0) | Cauldron::PowderSnow(0) => ("cauldron", None),
+            Cauldron::Water(l) => ("water_cauldron", Some(l)),
+            Cauldron::Lava => ("lava_cauldron", None),
+            Cauldron::PowderSnow(l) => ("powder_snow_cauldron", Some(l)),
+        };
+        let props: Vec<(String, String)> =
+            level.map(|l| ("level".to_owned(), l.to_string())).into_iter().collect();
+        lookup_block_state(name, &props).map(BlockId)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use azalea_inventory::ItemStackData;
+    use azalea_inventory::DataComponentPatch;
+
+    fn stack(kind: ItemKind) -> ItemStack {
+        ItemStack::Present(ItemStackData { kind, count: 1, component_patch: DataComponentPatch::default() })
+    }
+
+    /// Looking straight down (`x_rot` 90) from 2.5 blocks above `floor`.
+    fn down_from(floor: BlockPos) -> [f64; 3] {
+        [floor.x as f64 + 0.5, floor.y as f64 + 3.5, floor.z as f64 + 0.5]
+    }
+
+    #[test]
+    fn test_buckets_scoop_sources_and_pour_against_faces() {
+        use ultimate_engine::world::chunk::Chunk;
+        use ultimate_engine::world::position::ChunkPos;
+
+        let world = World::new();
+        world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+        let floor = BlockPos::new(3, 10, 3);
+        world.set_block(floor, crate::block::STONE);
+
+        // Pour onto the top face of the floor.
+        let pour = use_bucket(&world, ItemKind::WaterBucket, down_from(floor), 0.0, 90.0).unwrap();
+        assert_eq!(pour.pos, BlockPos::new(3, 11, 3));
+        assert_eq!((pour.old, pour.new, pour.result), (BlockId::AIR, FluidKind::Water.source(), ItemKind::Bucket));
+
+        // Flowing water above the floor can't be scooped and doesn't stop
+        // the ray; a source can.
+        world.set_block(pour.pos, crate::block::water_flowing(3));
+        assert_eq!(use_bucket(&world, ItemKind::Bucket, down_from(floor), 0.0, 90.0), None);
+        world.set_block(pour.pos, FluidKind::Lava.source());
+        let scoop = use_bucket(&world, ItemKind::Bucket, down_from(floor), 0.0, 90.0).unwrap();
+        assert_eq!((scoop.pos, scoop.new, scoop.result), (pour.pos, BlockId::AIR, ItemKind::LavaBucket));
+
+        // Out of reach, or not a bucket.
+        assert_eq!(use_bucket(&world, ItemKind::WaterBucket, down_from(floor), 0.0, -90.0), None);
+        assert_eq!(use_bucket(&world, ItemKind::Stone, down_from(floor), 0.0, 90.0), None);
+    }
+
+    fn water(level: u8) -> BlockId {
+        Cauldron::Water(level).block().unwrap()
+    }
+
+    #[test]
+    fn test_cauldron_bucket_and_bottle_levels() {
+        let empty = Cauldron::Empty.block().unwrap();
+        assert_eq!(Cauldron::of(water(2)), Some(Cauldron::Water(2)));
+
+        assert_eq!(use_on(&stack(ItemKind::WaterBucket), empty), Some(water(3)));
+        assert_eq!(use_on(&stack(ItemKind::Bucket), water(3)), Some(empty));
+        assert_eq!(use_on(&stack(ItemKind::Bucket), water(2)), None, "only full cauldrons scoop");
+        assert_eq!(use_on(&stack(ItemKind::GlassBottle), water(2)), Some(water(1)));
+        assert_eq!(use_on(&stack(ItemKind::GlassBottle), water(1)), Some(empty));
+        assert_eq!(use_on(&stack(ItemKind::GlassBottle), empty), None);
+        assert_eq!(
+            use_on(&stack(ItemKind::LavaBucket), empty),
+            Cauldron::Lava.block(),
+        );
+
+        // Not a cauldron, or nothing held: fall through to placement.
+        assert_eq!(use_on(&stack(ItemKind::WaterBucket), crate::block::STONE), None);
+        assert_eq!(use_on(&ItemStack::Empty, empty), None);
+    }
+
+    #[test]
+    fn test_rain_fills_water_cauldrons() {
+        let empty = Cauldron::Empty.block().unwrap();
+        assert_eq!(rain_fill(empty), Some(water(1)));
+        assert_eq!(rain_fill(water(2)), Some(water(3)));
+        assert_eq!(rain_fill(water(3)), None, "already full");
+        assert_eq!(rain_fill(Cauldron::Lava.block().unwrap()), None);
+        assert_eq!(rain_fill(crate::block::STONE), None);
+    }
+}
@@ -0,0 +1,353 @@
+//! Item data components the server reads and keeps: damage,
+//! enchantments and custom names.
+//!
+//! Inventory slots hold azalea's [`ItemStack`], whose component patch
+//! travels with the item through every container packet as the client
+//! sent it. This module is the server's view into that patch — how worn
+//! a tool is, its enchantment levels, its name — plus [`SavedItem`], the
+//! form inventories take in `playerdata/<uuid>.json` (see
+//! [`crate::playerdata`]). Only those three components are saved; others
+//! (lore, dyes, trims, ...) last until the player logs out.
+//!
+//! Enchantments are a data registry: ids are positions in
+//! [`ENCHANTMENTS`], which is also what the client is sent at
+//! configuration, so both sides agree.
+//!
+//! Mining: [`break_ticks`] is vanilla's dig time for a block and tool,
+//! Efficiency included, and [`worn`] wears a tool by one use, Unbreaking
+//! included. Creative breaks stay instant; the timed path is for the
+//! modes that dig.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use azalea_block::{BlockState, BlockTrait};
+use azalea_chat::FormattedText;
+use azalea_inventory::ItemStack;
+use azalea_inventory::components::{CustomName, Damage, Enchantments};
+use azalea_registry::DataRegistry;
+use azalea_registry::builtin::{BlockKind, ItemKind};
+use azalea_registry::data::Enchantment;
+use serde::{Deserialize, Serialize};
+
+/// Vanilla's enchantments in registry order (alphabetical, as the
+/// vanilla data pack loads them).
+pub const ENCHANTMENTS: [&str; 43] = [
+    "minecraft:aqua_affinity",
+    "minecraft:bane_of_arthropods",
+    "minecraft:binding_curse",
+    "minecraft:blast_protection",
+    "minecraft:breach",
+    "minecraft:channeling",
+    "minecraft:density",
+    "minecraft:depth_strider",
+    "minecraft:efficiency",
+    "minecraft:feather_falling",
+    "minecraft:fire_aspect",
+    "minecraft:fire_protection",
+    "minecraft:flame",
+    "minecraft:fortune",
+    "minecraft:frost_walker",
+    "minecraft:impaling",
+    "minecraft:infinity",
+    "minecraft:knockback",
+    "minecraft:looting",
+    "minecraft:loyalty",
+    "minecraft:luck_of_the_sea",
+    "minecraft:lunge",
+    "minecraft:lure",
+    "minecraft:mending",
+    "minecraft:multishot",
+    "minecraft:piercing",
+    "minecraft:power",
+    "minecraft:projectile_protection",
+    "minecraft:protection",
+    "minecraft:punch",
+    "minecraft:quick_charge",
+    "minecraft:respiration",
+    "minecraft:riptide",
+    "minecraft:sharpness",
+    "minecraft:silk_touch",
+    "minecraft:smite",
+    "minecraft:soul_speed",
+    "minecraft:sweeping_edge",
+    "minecraft:swift_sneak",
+    "minecraft:thorns",
+    "minecraft:unbreaking",
+    "minecraft:vanishing_curse",
+    "minecraft:wind_burst",
+];
+
+/// Registry id of enchantment `name` (`minecraft:` may be left off).
+pub fn enchantment_id(name: &str) -> Option<u32> {
+    let full = if name.contains(':') { name.to_owned() } else { format!("minecraft:{}", name) };
+    ENCHANTMENTS.iter().position(|&e| e == full).map(|i| i as u32)
+}
+
+fn enchantment_name(id: u32) -> Option<&'static str> {
+    ENCHANTMENTS.get(id as usize).copied()
+}
+
+/// How much of its durability `stack` has used.
+pub fn damage(stack: &ItemStack) -> i32 {
+    match stack {
+        ItemStack::Present(data) => data.component_patch.get::<Damage>().map_or(0, |d| d.amount),
+        ItemStack::Empty => 0,
+    }
+}
+
+/// Every enchantment on `stack`, by name, with its level.
+pub fn enchantments(stack: &ItemStack) -> BTreeMap<String, u32> {
+    let ItemStack::Present(data) = stack else { return BTreeMap::new() };
+    let Some(enchantments) = data.component_patch.get::<Enchantments>() else { return BTreeMap::new() };
+    enchantments
+        .levels
+        .iter()
+        .filter_map(|(enchantment, &level)| {
+            enchantment_name(enchantment.protocol_id()).map(|name| (name.to_owned(), level as u32))
+        })
+        .collect()
+}
+
+/// `stack`'s level of enchantment `name`, 0 without it.
+pub fn enchantment_level(stack: &ItemStack, name: &str) -> u32 {
+    let Some(id) = enchantment_id(name) else { return 0 };
+    let ItemStack::Present(data) = stack else { return 0 };
+    data.component_patch
+        .get::<Enchantments>()
+        .and_then(|e| e.levels.iter().find(|(enchantment, _)| enchantment.protocol_id() == id).map(|(_, &l)| l as u32))
+        .unwrap_or(0)
+}
+
+/// The name `stack` was given in an anvil, as plain text.
+pub fn custom_name(stack: &ItemStack) -> Option<String> {
+    match stack {
+        ItemStack::Present(data) => data.component_patch.get::<CustomName>().map(|c| c.name.to_string()),
+        ItemStack::Empty => None,
+    }
+}
+
+/// Uses before a tool or weapon breaks; `None` for items that don't wear.
+pub fn max_damage(kind: ItemKind) -> Option<i32> {
+    let full = kind.to_string();
+    let name = full.strip_prefix("minecraft:").unwrap_or(&full);
+    let fixed = match name {
+        "shears" => Some(238),
+        "flint_and_steel" | "fishing_rod" => Some(64),
+        "bow" => Some(384),
+        "crossbow" => Some(465),
+        "trident" => Some(250),
+        "shield" => Some(336),
+        "elytra" => Some(432),
+        "mace" => Some(500),
+        _ => None,
+    };
+    fixed.or_else(|| {
+        let (tier, _) = tool(name)?;
+        Some(match tier {
+            "wooden" => 59,
+            "stone" => 131,
+            "copper" => 190,
+            "iron" => 250,
+            "golden" => 32,
+            "diamond" => 1561,
+            "netherite" => 2031,
+            _ => return None,
+        })
+    })
+}
+
+/// A tool's tier and kind: `("diamond", "pickaxe")`.
+fn tool(name: &str) -> Option<(&str, &str)> {
+    let (tier, kind) = name.rsplit_once('_')?;
+    matches!(kind, "pickaxe" | "axe" | "shovel" | "hoe" | "sword").then_some((tier, kind))
+}
+
+/// Mining speed of a tier on blocks its tool is for.
+fn tier_speed(tier: &str) -> f32 {
+    match tier {
+        "wooden" => 2.0,
+        "stone" => 4.0,
+        "copper" => 5.0,
+        "iron" => 6.0,
+        "diamond" => 8.0,
+        "netherite" => 9.0,
+        "golden" => 12.0,
+        _ => 1.0,
+    }
+}
+
+/// Whether a `kind` tool (`pickaxe`, ...) is the one for `block`.
+fn tool_for(kind: &str, block: BlockKind) -> bool {
+    use azalea_registry::tags::blocks::{MINEABLE_AXE, MINEABLE_HOE, MINEABLE_PICKAXE, MINEABLE_SHOVEL};
+    match kind {
+        "pickaxe" => MINEABLE_PICKAXE.contains(&block),
+        "axe" => MINEABLE_AXE.contains(&block),
+        "shovel" => MINEABLE_SHOVEL.contains(&block),
+        "hoe" => MINEABLE_HOE.contains(&block),
+        _ => false,
+    }
+}
+
+/// Game ticks a player on the ground takes to break `state` holding
+/// `held`, as vanilla computes it: the block's hardness against the
+/// tool's speed (plus Efficiency's level² + 1), and thirty times slower
+/// again, rather than a hundred, when the block drops with this tool. `0`
+/// breaks instantly; `None` never breaks (bedrock). Tool tiers aren't
+/// checked against the block's `needs_*_tool` tags: any pickaxe is the
+/// right tool for any pickaxe block.
+pub fn break_ticks(state: BlockState, held: &ItemStack) -> Option<u32> {
+    let block = Box::<dyn BlockTrait>::from(state);
+    let behavior = block.behavior();
+    let hardness = behavior.destroy_time;
+    if hardness < 0.0 {
+        return None;
+    }
+    if hardness == 0.0 {
+        return Some(0);
+    }
+
+    let full = held.kind().to_string();
+    let name = full.strip_prefix("minecraft:").unwrap_or(&full);
+    let right_tool = tool(name).filter(|(_, kind)| tool_for(kind, BlockKind::from(state)));
+    let mut speed = right_tool.map_or(1.0, |(tier, _)| tier_speed(tier));
+    let efficiency = enchantment_level(held, "efficiency");
+    if speed > 1.0 && efficiency > 0 {
+        speed += (efficiency * efficiency + 1) as f32;
+    }
+    let harvests = right_tool.is_some() || !behavior.requires_correct_tool_for_drops;
+    let per_tick = speed / hardness / if harvests { 30.0 } else { 100.0 };
+    if per_tick >= 1.0 {
+        return Some(0);
+    }
+    Some((1.0 / per_tick).ceil() as u32)
+}
+
+/// `held` after one use (a block broken, a hit landed). Unbreaking level
+/// `n` spares the item with chance `n / (n + 1)`, decided by `roll`; a
+/// tool used up breaks and leaves the hand empty. Items that don't wear
+/// come back unchanged.
+pub fn worn(held: &ItemStack, roll: u64) -> ItemStack {
+    let Some(max) = max_damage(held.kind()) else { return held.clone() };
+    let unbreaking = enchantment_level(held, "unbreaking") as u64;
+    if !roll.is_multiple_of(unbreaking + 1) {
+        return held.clone();
+    }
+    let damage = damage(held) + 1;
+    if damage >= max {
+        return ItemStack::Empty;
+    }
+    held.clone().with_component(Damage { amount: damage })
+}
+
+/// An item as saved with the player: its kind, count and the
+/// components above.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedItem {
+    /// Namespaced item id (`minecraft:diamond_pickaxe`).
+    pub id: String,
+    pub count: i32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub damage: i32,
+    /// Level by namespaced enchantment id.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub enchantments: BTreeMap<String, u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
+}
+
+impl SavedItem {
+    /// `None` for an empty slot.
+    pub fn from_stack(stack: &ItemStack) -> Option<Self> {
+        if stack.is_empty() {
+            return None;
+        }
+        Some(Self {
+            id: stack.kind().to_string(),
+            count: stack.count(),
+            damage: damage(stack),
+            enchantments: enchantments(stack),
+            name: custom_name(stack),
+        })
+    }
+
+    /// The stack this describes. Fails on an item or enchantment this
+    /// version doesn't have.
+    pub fn to_stack(&self) -> anyhow::Result<ItemStack> {
+        let bare = self.id.strip_prefix("minecraft:").unwrap_or(&self.id);
+        let kind = ItemKind::from_str(bare).map_err(|_| anyhow::anyhow!("unknown item {:?}", self.id))?;
+        let mut stack = ItemStack::new(kind, self.count.max(1));
+        if self.damage > 0 {
+            stack = stack.with_component(Damage { amount: self.damage });
+        }
+        if !self.enchantments.is_empty() {
+            let mut levels = std::collections::HashMap::new();
+            for (name, &level) in &self.enchantments {
+                let id = enchantment_id(name).ok_or_else(|| anyhow::anyhow!("unknown enchantment {:?}", name))?;
+                levels.insert(Enchantment::new_raw(id), level as _);
+            }
+            stack = stack.with_component(Enchantments { levels });
+        }
+        if let Some(name) = &self.name {
+            stack = stack.with_component(CustomName { name: FormattedText::from(name.as_str()) });
+        }
+        Ok(stack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_items_keep_their_components() {
+        let saved = SavedItem {
+            id: "minecraft:diamond_pickaxe".into(),
+            count: 1,
+            damage: 12,
+            enchantments: BTreeMap::from([("minecraft:efficiency".into(), 5), ("minecraft:unbreaking".into(), 3)]),
+            name: Some("Digger".into()),
+        };
+        let stack = saved.to_stack().unwrap();
+        assert_eq!((damage(&stack), enchantment_level(&stack, "efficiency")), (12, 5));
+        assert_eq!(custom_name(&stack).as_deref(), Some("Digger"));
+        assert_eq!(SavedItem::from_stack(&stack), Some(saved));
+        assert_eq!(SavedItem::from_stack(&ItemStack::Empty), None);
+
+        let json = serde_json::to_string(&SavedItem::from_stack(&ItemStack::new(ItemKind::Stone, 3)).unwrap()).unwrap();
+        assert_eq!(json, r#"{"id":"minecraft:stone","count":3}"#);
+        let bad = SavedItem { id: "minecraft:stone".into(), count: 1, damage: 0, enchantments: BTreeMap::from([("sharpnes".into(), 1)]), name: None };
+        assert!(bad.to_stack().is_err());
+    }
+
+    #[test]
+    fn test_efficiency_speeds_digging_and_unbreaking_spares_tools() {
+        let stone = BlockState::from(BlockKind::Stone);
+        let pick = ItemStack::new(ItemKind::IronPickaxe, 1);
+        // Hardness 1.5 at speed 6: 6 / 1.5 / 30 per tick, so 8 ticks.
+        assert_eq!(break_ticks(stone, &pick), Some(8));
+        // By hand stone doesn't drop: 1 / 1.5 / 100, 150 ticks.
+        assert_eq!(break_ticks(stone, &ItemStack::Empty), Some(150));
+        let fast = SavedItem {
+            id: "minecraft:iron_pickaxe".into(), count: 1, damage: 0,
+            enchantments: BTreeMap::from([("minecraft:efficiency".into(), 2)]), name: None,
+        };
+        // Speed 6 + 2² + 1 = 11: 1.5 * 30 / 11, rounded up.
+        assert_eq!(break_ticks(stone, &fast.to_stack().unwrap()), Some(5));
+        assert_eq!(break_ticks(BlockState::from(BlockKind::Bedrock), &pick), None);
+        assert_eq!(break_ticks(BlockState::from(BlockKind::ShortGrass), &ItemStack::Empty), Some(0));
+
+        assert_eq!(damage(&worn(&pick, 7)), 1);
+        let tough = SavedItem { enchantments: BTreeMap::from([("unbreaking".into(), 3)]), ..fast };
+        let tough = tough.to_stack().unwrap();
+        assert_eq!(damage(&worn(&tough, 1)), 0, "spared");
+        assert_eq!(damage(&worn(&tough, 4)), 1, "worn");
+        let nearly_broken = pick.clone().with_component(Damage { amount: 249 });
+        assert!(worn(&nearly_broken, 0).is_empty());
+        assert_eq!(worn(&ItemStack::new(ItemKind::Stone, 5), 0).count(), 5);
+    }
+}
@@ -0,0 +1,443 @@
+//! Root-event journal and deterministic replay.
+//!
+//! `--record <journal>` appends everything a physics run depends on, in
+//! the order it happened: the contents of each chunk as it loads, chunk
+//! evictions, generation writes into already-loaded chunks, and every
+//! root submitted to the physics service (player actions and raw events).
+//! At shutdown, once physics is quiet, it appends a content hash of every
+//! loaded chunk.
+//!
+//! `--replay <journal>` rebuilds the world from those records alone — no
+//! generator, no saves — by feeding the roots through one scheduler,
+//! sequential or parallel (`--parallel`), and checks the result against
+//! the recorded hashes. Confluent rules must land on the same world no
+//! matter how the live server's workers interleaved; a mismatch names the
+//! chunks where execution diverged.
+//!
+//! Layout (little-endian, uncompressed, append-only):
+//!
+//! ```text
+//! magic "UMCJRNL\0" · version u32 · records*
+//! 0 chunk   · len u32 · snapshot chunk record (see `snapshot`)
+//! 1 unload  · cx i32 · cz i32
+//! 2 write   · pos · block u16                     (untracked world write)
+//! 3 action  · pos · old u16 · new u16 · update_stairs u8
+//! 4 events  · count u32 · payload*                (cluster payload codec)
+//! 5 hashes  · count u32 · (cx i32 · cz i32 · hash u64)*
+//! ```
+//!
+//! Scope: a single node. Forwards from cluster peers and writes made
+//! outside the physics service (other than generation) aren't recorded.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex, Weak};
+
+use anyhow::{bail, Context, Result};
+use ultimate_engine::causal::event::Event;
+use ultimate_engine::causal::graph::CausalGraph;
+use ultimate_engine::causal::scheduler::Scheduler;
+use ultimate_engine::world::World;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::chunk::Chunk;
+use ultimate_engine::world::observer::WorldObserver;
+use ultimate_engine::world::position::{BlockPos, ChunkPos};
+
+use crate::cluster::{decode_payload, encode_payload, put_pos, put_u16, Reader};
+use crate::physics::BlockAction;
+
+const MAGIC: &[u8; 8] = b"UMCJRNL\0";
+const VERSION: u32 = 1;
+
+const TAG_CHUNK: u8 = 0;
+const TAG_UNLOAD: u8 = 1;
+const TAG_WRITE: u8 = 2;
+const TAG_ACTION: u8 = 3;
+const TAG_EVENTS: u8 = 4;
+const TAG_HASHES: u8 = 5;
+
+/// One journal record.
+pub enum Record {
+    Chunk(ChunkPos, Chunk),
+    Unload(ChunkPos),
+    Write(BlockPos, BlockId),
+    Action(BlockAction),
+    Events(Vec<Event>),
+    Hashes(Vec<(ChunkPos, u64)>),
+}
+
+/// Appends records to a journal file. Write errors are logged once and
+/// recording stops; the server keeps running.
+pub struct Recorder {
+    out: Mutex<Option<BufWriter<File>>>,
+    world: Weak<World>,
+    /// While [`Recorder::start`] records the chunks already loaded: those
+    /// recorded so far, by the snapshot or the observer, so a chunk that
+    /// loads mid-snapshot is recorded once.
+    snapshot: Mutex<Option<HashSet<ChunkPos>>>,
+}
+
+impl Recorder {
+    /// Create `path`, record every chunk already loaded, and start
+    /// following `world` for loads, evictions and generation writes.
+    pub fn start(path: &Path, world: &Arc<World>) -> Result<Arc<Self>> {
+        let file = File::create(path).with_context(|| format!("creating journal {}", path.display()))?;
+        let mut out = BufWriter::new(file);
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        let recorder = Arc::new(Self {
+            out: Mutex::new(Some(out)),
+            world: Arc::downgrade(world),
+            snapshot: Mutex::new(Some(HashSet::new())),
+        });
+        // Observe first, so a chunk loading while we walk the loaded ones
+        // can't fall between the two.
+        world.add_observer(Box::new(JournalObserver(Arc::clone(&recorder))));
+        let loaded: Vec<ChunkPos> = world.iter_chunks().map(|entry| *entry.key()).collect();
+        for pos in loaded {
+            let mut seen = recorder.snapshot.lock().expect("journal poisoned");
+            if seen.as_mut().is_some_and(|seen| seen.insert(pos))
+                && let Some(chunk) = world.get_chunk(&pos)
+            {
+                recorder.record_chunk(pos, &chunk);
+            }
+        }
+        *recorder.snapshot.lock().expect("journal poisoned") = None;
+        Ok(recorder)
+    }
+
+    fn append(&self, record: &[u8]) {
+        let mut out = self.out.lock().expect("journal poisoned");
+        if let Some(w) = out.as_mut()
+            && let Err(e) = w.write_all(record)
+        {
+            tracing::error!("Journal write failed, recording stopped: {}", e);
+            *out = None;
+        }
+    }
+
+    fn record_chunk(&self, pos: ChunkPos, chunk: &Chunk) {
+        let bytes = crate::snapshot::chunk_to_bytes(pos, chunk);
+        let mut rec = Vec::with_capacity(5 + bytes.len());
+        rec.push(TAG_CHUNK);
+        rec.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        rec.extend_from_slice(&bytes);
+        self.append(&rec);
+    }
+
+    pub fn record_action(&self, a: &BlockAction) {
+        let mut rec = vec![TAG_ACTION];
+        put_pos(&mut rec, a.pos);
+        put_u16(&mut rec, a.old.0);
+        put_u16(&mut rec, a.new.0);
+        rec.push(a.update_stairs as u8);
+        self.append(&rec);
+    }
+
+    pub fn record_events(&self, events: &[Event]) {
+        let mut rec = vec![TAG_EVENTS];
+        rec.extend_from_slice(&(events.len() as u32).to_le_bytes());
+        for event in events {
+            encode_payload(&mut rec, &event.payload);
+        }
+        self.append(&rec);
+    }
+
+    /// Append the content hash of every loaded chunk and flush. Call with
+    /// physics quiet, or the hashes capture a cascade mid-flight.
+    pub fn checkpoint(&self, world: &World) -> Result<usize> {
+        let hashes = world_hashes(world);
+        let mut rec = vec![TAG_HASHES];
+        rec.extend_from_slice(&(hashes.len() as u32).to_le_bytes());
+        for (pos, hash) in &hashes {
+            rec.extend_from_slice(&pos.x.to_le_bytes());
+            rec.extend_from_slice(&pos.z.to_le_bytes());
+            rec.extend_from_slice(&hash.to_le_bytes());
+        }
+        self.append(&rec);
+        let mut out = self.out.lock().expect("journal poisoned");
+        match out.as_mut() {
+            Some(w) => w.flush().context("flushing journal")?,
+            None => bail!("journal recording stopped after an earlier write error"),
+        }
+        Ok(hashes.len())
+    }
+}
+
+struct JournalObserver(Arc<Recorder>);
+
+impl WorldObserver for JournalObserver {
+    fn block_set(&self, pos: BlockPos, _old: BlockId, new: BlockId, tracked: bool) {
+        // Tracked writes come from physics and replay reproduces them;
+        // untracked ones are decorators spilling into loaded chunks.
+        if !tracked {
+            let mut rec = vec![TAG_WRITE];
+            put_pos(&mut rec, pos);
+            put_u16(&mut rec, new.0);
+            self.0.append(&rec);
+        }
+    }
+
+    fn chunk_inserted(&self, pos: ChunkPos) {
+        let Some(world) = self.0.world.upgrade() else { return };
+        let mut seen = self.0.snapshot.lock().expect("journal poisoned");
+        if seen.as_mut().is_some_and(|seen| !seen.insert(pos)) {
+            return;
+        }
+        if let Some(chunk) = world.get_chunk(&pos) {
+            self.0.record_chunk(pos, &chunk);
+        }
+    }
+
+    fn chunk_removed(&self, pos: ChunkPos) {
+        let mut rec = vec![TAG_UNLOAD];
+        rec.extend_from_slice(&pos.x.to_le_bytes());
+        rec.extend_from_slice(&pos.z.to_le_bytes());
+        self.0.append(&rec);
+    }
+}
+
+fn world_hashes(world: &World) -> Vec<(ChunkPos, u64)> {
+    let mut hashes: Vec<(ChunkPos, u64)> =
+        world.iter_chunks().map(|entry| (*entry.key(), entry.value().content_hash())).collect();
+    hashes.sort_unstable_by_key(|(pos, _)| *pos);
+    hashes
+}
+
+/// Read every record of the journal at `path`.
+pub fn read(path: &Path) -> Result<Vec<Record>> {
+    let raw = std::fs::read(path).with_context(|| format!("reading journal {}", path.display()))?;
+    let mut r = Reader::new(&raw);
+    if r.bytes(8)? != MAGIC {
+        bail!("{} is not a replay journal", path.display());
+    }
+    let version = r.u32()?;
+    if version != VERSION {
+        bail!("unsupported journal version {} (expected {})", version, VERSION);
+    }
+    let mut records = Vec::new();
+    while !r.is_done() {
+        records.push(read_record(&mut r).with_context(|| format!("journal record {}", records.len()))?);
+    }
+    Ok(records)
+}
+
+fn read_record(r: &mut Reader) -> Result<Record> {
+    Ok(match r.u8()? {
+        TAG_CHUNK => {
+            let len = r.u32()? as usize;
+            let (pos, chunk) = crate::snapshot::chunk_from_bytes(r.bytes(len)?)?;
+            Record::Chunk(pos, chunk)
+        }
+        TAG_UNLOAD => Record::Unload(ChunkPos::new(r.u32()? as i32, r.u32()? as i32)),
+        TAG_WRITE => Record::Write(r.pos()?, BlockId(r.u16()?)),
+        TAG_ACTION => Record::Action(BlockAction {
+            pos: r.pos()?,
+            old: BlockId(r.u16()?),
+            new: BlockId(r.u16()?),
+            update_stairs: r.u8()? != 0,
+        }),
+        TAG_EVENTS => {
+            let n = r.u32()? as usize;
+            let events = (0..n).map(|_| decode_payload(r).map(|payload| Event { payload }));
+            Record::Events(events.collect::<Result<_>>()?)
+        }
+        TAG_HASHES => {
+            let n = r.u32()? as usize;
+            let mut hashes = Vec::with_capacity(n);
+            for _ in 0..n {
+                hashes.push((ChunkPos::new(r.u32()? as i32, r.u32()? as i32), r.u64()?));
+            }
+            Record::Hashes(hashes)
+        }
+        other => bail!("bad journal record tag {}", other),
+    })
+}
+
+/// Outcome of a replay.
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    /// Actions and raw events fed to the scheduler.
+    pub roots: usize,
+    /// Events executed, consequents included.
+    pub executed: usize,
+    /// Chunk hashes compared.
+    pub checked: usize,
+    /// Chunks whose replayed content differs from the recording, or that
+    /// replay doesn't have loaded.
+    pub mismatched: Vec<ChunkPos>,
+}
+
+/// Replay `records` into a fresh world, returning it with the report.
+///
+/// Roots go into one causal graph with the same priorities the physics
+/// service gives them. Before any chunk load, eviction or checkpoint the
+/// graph runs to quiescence, and stair shapes settle as they do after a
+/// live batch; events aimed at unloaded chunks wait at the border as they
+/// do on the server.
+pub fn replay(records: Vec<Record>, parallel: bool) -> (Arc<World>, ReplayReport) {
+    let world = Arc::new(World::new());
+    let rules = crate::rules::standard();
+    let scheduler = Scheduler::new().with_unloaded_deferral();
+    let mut graph = CausalGraph::with_pruning();
+    let mut stair_hooks: Vec<BlockPos> = Vec::new();
+    let mut report = ReplayReport::default();
+
+    let settle = |graph: &mut CausalGraph, stair_hooks: &mut Vec<BlockPos>, report: &mut ReplayReport| {
+        report.executed += if parallel {
+            scheduler.run_until_quiet_parallel(&world, graph, &rules, usize::MAX)
+        } else {
+            scheduler.run_until_quiet(&world, graph, &rules, usize::MAX)
+        };
+        for pos in stair_hooks.drain(..) {
+            world.apply_batch(&crate::placement::update_adjacent_stair_shapes(&world, pos));
+        }
+    };
+
+    for record in records {
+        match record {
+            Record::Chunk(pos, chunk) => {
+                settle(&mut graph, &mut stair_hooks, &mut report);
+                world.insert_chunk(pos, chunk);
+            }
+            Record::Unload(pos) => {
+                settle(&mut graph, &mut stair_hooks, &mut report);
+                world.remove_chunk(pos);
+            }
+            Record::Write(pos, block) => world.set_block_untracked(pos, block),
+            Record::Action(action) => {
+                crate::physics::insert_action(&mut graph, &action);
+                if action.update_stairs {
+                    stair_hooks.push(action.pos);
+                }
+                report.roots += 1;
+            }
+            Record::Events(events) => {
+                report.roots += events.len();
+                for event in events {
+                    graph.insert_root(event);
+                }
+            }
+            Record::Hashes(expected) => {
+                settle(&mut graph, &mut stair_hooks, &mut report);
+                let actual: HashMap<ChunkPos, u64> = world_hashes(&world).into_iter().collect();
+                report.checked += expected.len();
+                report.mismatched.extend(
+                    expected.iter().filter(|(pos, hash)| actual.get(pos) != Some(hash)).map(|(pos, _)| *pos),
+                );
+            }
+        }
+    }
+    settle(&mut graph, &mut stair_hooks, &mut report);
+    (world, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultimate_engine::causal::event::EventPayload;
+    use ultimate_engine::world::position::LocalBlockPos;
+
+    fn flat_chunk() -> Chunk {
+        let mut chunk = Chunk::new();
+        for x in 0..16u8 {
+            for z in 0..16u8 {
+                for y in 0..4i64 {
+                    chunk.set_block(LocalBlockPos { x, y, z }, crate::block::STONE);
+                }
+            }
+        }
+        chunk
+    }
+
+    #[test]
+    fn test_chunks_loading_during_start_are_recorded_once() {
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_journal_start");
+        let _ = std::fs::create_dir_all(&tmp);
+        let path = tmp.join("run.journal");
+
+        let world = Arc::new(World::new());
+        for x in 0..32 {
+            world.insert_chunk(ChunkPos::new(x, 0), flat_chunk());
+        }
+        let loader = {
+            let world = Arc::clone(&world);
+            std::thread::spawn(move || {
+                for x in 0..32 {
+                    world.insert_chunk(ChunkPos::new(x, 1), flat_chunk());
+                }
+            })
+        };
+        let recorder = Recorder::start(&path, &world).unwrap();
+        loader.join().unwrap();
+        recorder.checkpoint(&world).unwrap();
+
+        let mut counts: HashMap<ChunkPos, usize> = HashMap::new();
+        for record in read(&path).unwrap() {
+            if let Record::Chunk(pos, _) = record {
+                *counts.entry(pos).or_default() += 1;
+            }
+        }
+        assert_eq!(counts.len(), 64, "every chunk is recorded");
+        assert!(counts.values().all(|&n| n == 1), "{counts:?}");
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_recorded_run_replays_to_matching_hashes() {
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_journal");
+        let _ = std::fs::create_dir_all(&tmp);
+        let path = tmp.join("run.journal");
+
+        // "Live" run: record, cascade through the physics service, checkpoint.
+        let world = Arc::new(World::new());
+        world.insert_chunk(ChunkPos::new(0, 0), flat_chunk());
+        let recorder = Recorder::start(&path, &world).unwrap();
+        world.insert_chunk(ChunkPos::new(1, 0), flat_chunk());
+        let physics = crate::physics::start(
+            Arc::clone(&world),
+            crate::rules::standard,
+            crate::event_bus::SpatialBus::new(),
+            None,
+            crate::physics::PhysicsOptions { workers: 2, journal: Some(Arc::clone(&recorder)), ..Default::default() },
+        );
+        physics.submit_action(BlockAction {
+            pos: BlockPos::new(3, 9, 3),
+            old: crate::block::AIR,
+            new: crate::block::SAND,
+            update_stairs: false,
+        });
+        physics.submit_events(vec![Event {
+            payload: EventPayload::BlockSet {
+                pos: BlockPos::new(20, 12, 5),
+                old: crate::block::AIR,
+                new: crate::block::SAND,
+            },
+        }]);
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while (physics.pending() != 0 || world.get_block(BlockPos::new(20, 4, 5)) != crate::block::SAND)
+            && std::time::Instant::now() < deadline
+        {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(recorder.checkpoint(&world).unwrap(), 2);
+
+        for parallel in [false, true] {
+            let (replayed, report) = replay(read(&path).unwrap(), parallel);
+            assert_eq!(report.roots, 2);
+            assert_eq!(report.checked, 2);
+            assert!(report.mismatched.is_empty(), "diverged in {:?}", report.mismatched);
+            assert_eq!(replayed.get_block(BlockPos::new(3, 4, 3)), crate::block::SAND);
+        }
+
+        // A tampered world is caught.
+        let mut records = read(&path).unwrap();
+        records.insert(records.len() - 1, Record::Write(BlockPos::new(30, 1, 1), crate::block::DIRT));
+        let (_, report) = replay(records, false);
+        assert_eq!(report.mismatched, vec![ChunkPos::new(1, 0)]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,14 +1,55 @@
+pub mod access;
+pub mod appearance;
+pub mod audit;
 pub mod block;
+pub mod bossbar;
+pub mod channels;
 pub mod cluster;
+pub mod combat;
+pub mod commands;
 pub mod config;
 pub mod dashboard;
+pub mod edit;
+pub mod effects;
 pub mod event_bus;
 pub mod eviction;
+pub mod gamemode;
+pub mod gamerules;
+pub mod inventory;
+pub mod item_use;
+pub mod items;
+pub mod journal;
+pub mod mobs;
+pub mod motd;
 pub mod net;
 pub mod persistence;
 pub mod physics;
 pub mod placement;
+pub mod playerdata;
 pub mod player_registry;
+pub mod pending;
+pub mod pools;
+pub mod pregen;
+pub mod profiling;
+pub mod projectiles;
+pub mod query;
+pub mod rcon;
+pub mod recipes;
+pub mod regions;
 pub mod rules;
+pub mod schematics;
+pub mod scoreboard;
+pub mod shutdown;
 pub mod simulation;
+pub mod skins;
+pub mod snapshot;
+pub mod tablist;
+pub mod teleport;
+pub mod tickets;
+pub mod ticks;
+pub mod trading;
+pub mod vanilla;
+pub mod watchdog;
+pub mod weather;
+pub mod worldborder;
 pub mod worldgen;
@@ -5,10 +5,16 @@ use ultimate_engine::world::World;
 use ultimate_server::config::{self, ServerConfig};
 use ultimate_server::dashboard::{self, DashboardState};
 use ultimate_server::event_bus::{self};
+use ultimate_server::net::chunk_cache::ChunkCache;
+use ultimate_server::net::chunk_sender::ChunkSender;
 use ultimate_server::persistence;
 use ultimate_server::player_registry::PlayerRegistry;
+use ultimate_server::shutdown::Shutdown;
 use ultimate_server::worldgen::{self, WorldGen};
 
+/// How long shutdown waits for connections and background tasks.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Pull a `--key value` flag out of the CLI args.
 fn cli_arg(key: &str) -> Option<String> {
     std::env::args()
@@ -23,18 +29,43 @@ async fn main() {
         .unwrap_or_else(|| "server.yaml".into())
         .into();
 
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info".parse().unwrap()),
-        )
-        .init();
+    // `--profile`: record spans on demand (`/profile start|stop`).
+    {
+        use tracing_subscriber::prelude::*;
+        let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| "info".parse().unwrap());
+        let profile = std::env::args()
+            .any(|a| a == "--profile")
+            .then(|| ultimate_server::profiling::install("profiles"));
+        tracing_subscriber::registry()
+            .with(tracing_subscriber::fmt::layer().with_filter(filter))
+            .with(profile)
+            .init();
+    }
 
     if demo_mode {
         run_demo();
         return;
     }
 
+    // `--replay <journal>`: rebuild a recorded run offline and check it.
+    if let Some(path) = cli_arg("--replay") {
+        if let Err(e) = run_replay(std::path::Path::new(&path)) {
+            tracing::error!("{:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // `admin <subcommand>`: offline maintenance, no server started.
+    if std::env::args().nth(1).as_deref() == Some("admin") {
+        if let Err(e) = run_admin(&config_path) {
+            tracing::error!("{:#}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     tracing::info!("Ultimate Minecraft -- causal voxel engine server");
 
     // ── Load config (auto-create on first run) ──────────────────────────
@@ -52,9 +83,21 @@ async fn main() {
         cfg.dashboard.port = v;
     }
     if let Some(v) = cli_arg("--world") { cfg.world.dir = v.into(); }
-    if let Some(v) = cli_arg("--seed").and_then(|s| s.parse().ok()) {
-        cfg.world.seed = v;
-    }
+    let seed_forced = match cli_arg("--seed").and_then(|s| s.parse().ok()) {
+        Some(v) => {
+            cfg.world.seed = v;
+            true
+        }
+        None => false,
+    };
+    // An existing world regenerates with the seed in its level.dat.
+    cfg.world.seed = match persistence::resolve_seed(&cfg.world.dir, cfg.world.seed, seed_forced) {
+        Ok(seed) => seed,
+        Err(e) => {
+            tracing::error!("World load failed: {:#}", e);
+            return;
+        }
+    };
 
     let cfg = Arc::new(cfg);
     tracing::info!(
@@ -65,6 +108,25 @@ async fn main() {
         cfg.world.seed,
     );
 
+    // ── Rule configuration (beside the config unless --rules) ───────────
+    let rules_path: PathBuf = cli_arg("--rules")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config_path.with_file_name("rules.toml"));
+    match ultimate_server::rules::config::load(&rules_path) {
+        Ok(summary) => tracing::info!("Rules loaded from {}: {}", rules_path.display(), summary),
+        Err(e) => {
+            tracing::error!("Rules load failed: {:#}", e);
+            return;
+        }
+    }
+    match ultimate_server::rules::scripts::load(&cfg.scripts) {
+        Ok(summary) => tracing::info!("Scripts loaded from {}: {}", cfg.scripts.dir.display(), summary),
+        Err(e) => {
+            tracing::error!("Scripts load failed: {:#}", e);
+            return;
+        }
+    }
+
     // ── Generate base world, then overlay saved modifications ──────────
     let world = Arc::new(World::new());
     // Base generator: pristine procedural pipeline. Persistence diffs
@@ -79,10 +141,20 @@ async fn main() {
     // Live delta store + overlay: every chunk generation re-applies saved
     // edits, which is what makes eviction / lazy regeneration faithful.
     let delta_store = persistence::new_delta_store();
-    let worldgen: Arc<dyn WorldGen> = Arc::new(persistence::DeltaOverlayGen::new(
+    let mut overlay = persistence::DeltaOverlayGen::new(
         Arc::clone(&base_worldgen),
         Arc::clone(&delta_store),
-    ));
+    );
+    // Biomes of a world imported with `umc-convert from-vanilla`.
+    match ultimate_server::vanilla::BiomeOverlay::load(&cfg.world.dir) {
+        Ok(Some(biomes)) => {
+            tracing::info!("Loaded imported biomes for {} chunks", biomes.len());
+            overlay = overlay.with_biomes(Arc::new(biomes));
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Ignoring unreadable imported biomes: {:#}", e),
+    }
+    let worldgen: Arc<dyn WorldGen> = Arc::new(overlay);
     // Fingerprint of (preset content, seed): stamped into saved chunks so
     // stale-generator terrain is detected and regenerated at load.
     let gen_fp = match worldgen::preset::fingerprint(&cfg.world.preset, cfg.world.seed) {
@@ -92,32 +164,90 @@ async fn main() {
             return;
         }
     };
+    let storage = Arc::new(persistence::WorldStorage::new(
+        cfg.world.dir.clone(),
+        gen_fp,
+        cfg.world.seed as i64,
+        Arc::clone(&base_worldgen),
+        Arc::clone(&delta_store),
+    ));
+
+    // Offline maintenance: drop saved chunks that regenerate identically,
+    // then exit without starting the server.
+    if std::env::args().any(|a| a == "--trim") {
+        match persistence::trim_world(&cfg.world.dir, gen_fp, &*base_worldgen, None) {
+            Ok(stats) => tracing::info!(
+                "Trim complete: removed {} of {} saved chunks",
+                stats.removed, stats.scanned,
+            ),
+            Err(e) => tracing::error!("Trim failed: {:#}", e),
+        }
+        return;
+    }
+
     tracing::info!(
-        "Generating world from preset {:?} (seed {:#x})...",
+        "World preset {:?} (seed {:#x}); chunks generate on demand",
         cfg.world.preset, cfg.world.seed,
     );
-    worldgen.pregenerate_radius(&world, cfg.world.pregenerate_radius);
-    tracing::info!(
-        "Base world ready: {} chunks pre-generated; further chunks generated on demand",
-        world.chunk_count(),
-    );
 
-    // Load saved (player-modified) chunks on top of the generated base,
-    // populating the delta store for future regenerations.
+    // Load saved (player-modified) chunks before anything is generated,
+    // populating the delta store for future regenerations. Generation
+    // never replaces a chunk already in the world.
     match persistence::load_into(&world, &cfg.world.dir, gen_fp, &*worldgen, Some(&delta_store)) {
         Ok(0) => tracing::info!("No saved modifications found"),
         Ok(n) => tracing::info!("Loaded {} modified chunks from {}", n, cfg.world.dir.display()),
         Err(e) => tracing::error!("Failed to load saved chunks: {:#}", e),
     }
 
+    // Every long-running task below stops when this is triggered.
+    let shutdown = Shutdown::new();
+
     // Start live dashboard (non-blocking — runs on its own tasks).
     let dashboard = Arc::new(DashboardState::new(Arc::clone(&world)));
+    dashboard.metrics.set_rule_sampling(cfg.dashboard.rule_sample_every);
     let dash = Arc::clone(&dashboard);
     let dashboard_port = cfg.dashboard.port;
+    let dash_shutdown = shutdown.clone();
     tokio::spawn(async move {
-        dashboard::server::start(dash, dashboard_port).await;
+        dashboard::server::start(dash, dashboard_port, dash_shutdown).await;
     });
 
+    // Offline maintenance: generate every chunk within `--pregen <radius>`
+    // chunks of spawn on all cores, save them, then exit. Progress goes
+    // to the log and the dashboard.
+    if let Some(radius) = cli_arg("--pregen") {
+        let Ok(radius) = radius.parse::<i32>() else {
+            tracing::error!("--pregen takes a radius in chunks, not {:?}", radius);
+            return;
+        };
+        let [sx, _, sz] = storage.level_info().spawn;
+        let center = ultimate_engine::world::position::ChunkPos::new(sx >> 4, sz >> 4);
+        tracing::info!("Pre-generating chunks within {} of ({}, {})...", radius, center.x, center.z);
+        let start = std::time::Instant::now();
+        let generated = ultimate_server::pregen::pregenerate(&world, &*worldgen, center, radius, &|done, total| {
+            dashboard.metrics.set_pregen_progress(done, total);
+            if ultimate_server::pregen::crossed_tenth(done, total) {
+                tracing::info!("Pre-generated {}/{} chunks ({}%)", done, total, done * 100 / total);
+            }
+        });
+        tracing::info!("Generated {} chunks in {:.1?}; saving...", generated.len(), start.elapsed());
+        match persistence::save_full_chunks(&world, &cfg.world.dir, gen_fp, &generated) {
+            Ok(n) => tracing::info!("Pre-generation complete: {} chunks saved", n),
+            Err(e) => tracing::error!("Saving pre-generated chunks failed: {:#}", e),
+        }
+        return;
+    }
+
+    // Dedicated cascade and blocking pools, sized so neither competes
+    // with the tokio runtime for every core.
+    let pools = match ultimate_server::pools::Pools::new(&cfg.physics, Arc::clone(&dashboard)) {
+        Ok(p) => Arc::new(p),
+        Err(e) => {
+            tracing::error!("Thread pool setup failed: {:#}", e);
+            return;
+        }
+    };
+
     // Spatial event bus (Phase 6f): world changes and entity moves are
     // delivered per-region to nearby subscribers only.
     let spatial = event_bus::SpatialBus::new();
@@ -161,6 +291,30 @@ async fn main() {
         None
     };
 
+    // `--record <journal>`: log chunk loads and physics roots for replay.
+    let recorder = match cli_arg("--record") {
+        Some(path) => match ultimate_server::journal::Recorder::start(path.as_ref(), &world) {
+            Ok(r) => {
+                tracing::info!("Recording physics journal to {}", path);
+                Some(r)
+            }
+            Err(e) => {
+                tracing::error!("Journal setup failed: {:#}", e);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    // Simulation distance. Tickets are per node, so a cluster (whose
+    // players are spread across nodes) simulates every loaded chunk.
+    let tickets = (cfg.network.simulation_distance > 0 && mesh.is_none()).then(|| {
+        Arc::new(ultimate_server::tickets::SimulationTickets::new(cfg.network.simulation_distance))
+    });
+    if cfg.network.simulation_distance > 0 && mesh.is_some() {
+        tracing::info!("Clustered: network.simulation_distance ignored, every loaded chunk simulates");
+    }
+
     // ── Physics service ──────────────────────────────────────────────────
     // Partition workers own the shared causal graphs; connections and
     // simulation layers submit root events and the spatial bus carries
@@ -177,37 +331,220 @@ async fn main() {
             cluster: mesh.as_ref().map(|m| ultimate_server::physics::ClusterCtx {
                 mesh: Arc::clone(m),
             }),
+            cascade_pool: Some(pools.cascade()),
+            step_budget: cfg.physics.step_budget,
+            watchdog: (&cfg.watchdog).into(),
+            simulation: tickets.clone(),
+            parked: Arc::clone(&storage.pending),
+            journal: recorder.clone(),
+            recent_window: ultimate_engine::causal::graph::RecentWindow {
+                max_nodes: cfg.dashboard.graph_window_nodes,
+                max_age: (cfg.dashboard.graph_window_ms > 0)
+                    .then(|| Duration::from_millis(cfg.dashboard.graph_window_ms)),
+            },
         },
     );
     if let Some(m) = &mesh {
         m.attach(Arc::clone(&world), Arc::clone(&spatial), physics.clone());
     }
 
-    // Ambient simulation layers (empty for now).
-    let sim_layers: Vec<Box<dyn ultimate_server::simulation::SimulationLayer>> = vec![];
-    ultimate_server::simulation::start(Arc::clone(&world), sim_layers, physics.clone());
-
     // Shared player registry for multiplayer visibility.
-    let registry = Arc::new(PlayerRegistry::new(Arc::clone(&spatial)));
+    let mut registry = PlayerRegistry::new(Arc::clone(&spatial));
+    if let Some(tickets) = tickets {
+        registry = registry.with_simulation(tickets);
+    }
+    let registry = Arc::new(registry);
+
+    // Ambient simulation layers.
+    let mut sim_layers: Vec<Box<dyn ultimate_server::simulation::SimulationLayer>> = vec![];
+    if cfg.mobs.enabled {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        let mobs = ultimate_server::mobs::Mobs::new(
+            cfg.mobs.cap, Arc::clone(&registry), Arc::clone(&spatial), seed,
+        );
+        match storage.attach_mobs(Arc::clone(&mobs)) {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("Restored {} saved mobs", n),
+            Err(e) => tracing::warn!("Saved mobs failed to load: {:#}", e),
+        }
+        let clock_storage = Arc::clone(&storage);
+        ultimate_server::mobs::start_ai(
+            Arc::clone(&mobs), Arc::clone(&world), Arc::clone(&pools),
+            move || clock_storage.level_info().time, shutdown.clone(),
+        );
+        sim_layers.push(Box::new(ultimate_server::mobs::MobLayer(mobs)));
+    }
+    let weather_seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    sim_layers.push(Box::new(ultimate_server::weather::WeatherLayer::new(Arc::clone(&registry), weather_seed)));
+    let border = Arc::new(ultimate_server::worldborder::WorldBorder::new(&cfg.border));
+    let recipes = match ultimate_server::recipes::RecipeBook::load(&cfg.crafting.data_dir) {
+        Ok(recipes) => Arc::new(recipes),
+        Err(e) => {
+            tracing::error!("Recipes failed to load: {:#}", e);
+            return;
+        }
+    };
+    let trades = match ultimate_server::trading::Trades::load(&cfg.trading.file) {
+        Ok(trades) => Arc::new(trades),
+        Err(e) => {
+            tracing::error!("Trades failed to load: {:#}", e);
+            return;
+        }
+    };
+    let projectiles = ultimate_server::projectiles::Projectiles::new(Arc::clone(&registry), Arc::clone(&spatial));
+    ultimate_server::projectiles::start(
+        Arc::clone(&projectiles), Arc::clone(&world), physics.clone(), Arc::clone(&pools), shutdown.clone(),
+    );
+    ultimate_server::simulation::start(
+        Arc::clone(&world), sim_layers, physics.clone(), Arc::clone(&pools), shutdown.clone(),
+    );
+
+    // Whitelist / bans / ops, re-read live when edited by hand.
+    let access = match ultimate_server::access::AccessLists::load(&cfg.access.dir, cfg.access.whitelist) {
+        Ok(a) => Arc::new(a),
+        Err(e) => {
+            tracing::error!("Access lists failed to load: {:#}", e);
+            return;
+        }
+    };
+    ultimate_server::access::start_reloader(Arc::clone(&access), cfg.access.reload_interval_secs, shutdown.clone());
+
+    // Server-list entry (MOTD, max players, icon), re-read when edited.
+    let status = Arc::new(ultimate_server::motd::ServerStatus::new(&cfg, &config_path));
+    ultimate_server::motd::start_reloader(Arc::clone(&status), cfg.status.reload_interval_secs, shutdown.clone());
+    ultimate_server::rules::scripts::start_reloader(&cfg.scripts, shutdown.clone());
+    // TPS and MSPT for the tab list, `/tps`, the dashboard and the debug
+    // channel.
+    ultimate_server::ticks::start(
+        Arc::clone(&registry), Arc::clone(&dashboard), Arc::clone(&pools), shutdown.clone(),
+    );
+    ultimate_server::tablist::start(
+        &cfg.tab_list, Arc::clone(&status), Arc::clone(&registry), Arc::clone(&storage), shutdown.clone(),
+    );
+
+    // Per-player action log behind `/history`.
+    if cfg.audit.enabled {
+        if let Err(e) = registry.audit.open(&cfg.audit) {
+            tracing::error!("Audit log failed to open: {:#}", e);
+            return;
+        }
+        tracing::info!("Audit log: {}", cfg.audit.dir.display());
+    }
+
+    // Offline-mode skins (local directory and/or cached Mojang lookups).
+    let skins = match ultimate_server::skins::SkinResolver::new(&cfg.skins) {
+        Ok(s) => Arc::new(s),
+        Err(e) => {
+            tracing::error!("Skin resolver failed to start: {:#}", e);
+            return;
+        }
+    };
+
+    // Generate the spawn area and encode the spawn view in the background,
+    // so startup doesn't wait on them. Players who join first get their
+    // chunks generated on demand by the chunk sender.
+    let chunk_cache = Arc::new(ChunkCache::attach(&world));
+    {
+        let (world, worldgen, cache) = (Arc::clone(&world), Arc::clone(&worldgen), Arc::clone(&chunk_cache));
+        let (radius, view, max_loaded) =
+            (cfg.world.pregenerate_radius, cfg.network.view_distance, cfg.network.max_loaded_chunks);
+        let pools = Arc::clone(&pools);
+        tokio::spawn(async move {
+            let start = std::time::Instant::now();
+            let warmed = pools.run_blocking(move || {
+                worldgen.pregenerate_radius(&world, radius);
+                ultimate_server::net::connection::warm_spawn_chunks(&world, &*worldgen, &cache, view, max_loaded)
+            }).await;
+            match warmed {
+                Ok(n) => tracing::info!(
+                    "Spawn area ready: generated around origin and {} packets warmed in {:.1} ms",
+                    n, start.elapsed().as_secs_f64() * 1000.0,
+                ),
+                Err(e) => tracing::warn!("Spawn chunk warm-up failed: {:#}", e),
+            }
+        });
+    }
+    // Everything else is encoded once per round by the shared sender,
+    // however many players are waiting on it.
+    let chunks = Arc::new(ChunkSender::start(
+        Arc::clone(&world), Arc::clone(&worldgen), chunk_cache, Arc::clone(&pools),
+    ));
+
+    // Admin API on the dashboard, now that its handles exist.
+    dashboard.attach_admin(dashboard::admin::Admin {
+        token: cfg.dashboard.admin_token.clone(),
+        registry: Arc::clone(&registry),
+        physics: physics.clone(),
+        storage: Arc::clone(&storage),
+        pools: Arc::clone(&pools),
+    });
+    if !cfg.dashboard.admin_token.is_empty() {
+        tracing::info!("Dashboard admin API enabled");
+    }
+
+    // Remote console for hosting panels and admin scripts (off by default).
+    let console = Arc::new(ultimate_server::commands::Console {
+        world: Arc::clone(&world),
+        storage: Arc::clone(&storage),
+        registry: Arc::clone(&registry),
+        access: Arc::clone(&access),
+        pools: Arc::clone(&pools),
+        physics: physics.clone(),
+        config: Arc::clone(&cfg),
+        border: Arc::clone(&border),
+    });
+    let rcon_shutdown = shutdown.clone();
+    let rcon_config = cfg.rcon.clone();
+    tokio::spawn(async move {
+        if let Err(e) = ultimate_server::rcon::run(rcon_config, console, rcon_shutdown).await {
+            tracing::error!("RCON failed: {:#}", e);
+        }
+    });
+
+    // Query protocol for server-list sites (off by default).
+    let query_config = Arc::clone(&cfg);
+    let query_registry = Arc::clone(&registry);
+    let query_status = Arc::clone(&status);
+    let query_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        if let Err(e) = ultimate_server::query::run(query_config, query_registry, query_status, query_shutdown).await {
+            tracing::error!("Query failed: {:#}", e);
+        }
+    });
 
     // ── Periodic autosave ────────────────────────────────────────────────
     let save_world_ref = Arc::clone(&world);
-    let save_dir = cfg.world.dir.clone();
-    let save_worldgen = Arc::clone(&base_worldgen); // diff against the BASE
-    let save_deltas = Arc::clone(&delta_store);
+    let save_storage = Arc::clone(&storage); // diffs against the BASE
+    let save_pools = Arc::clone(&pools);
+    let save_registry = Arc::clone(&registry);
     let autosave = Duration::from_secs(cfg.world.autosave_interval_secs);
+    let save_shutdown = shutdown.clone();
     tokio::spawn(async move {
+        // A save in progress at shutdown finishes before the final one.
+        let _running = save_shutdown.task();
         let mut interval = tokio::time::interval(autosave);
         interval.tick().await; // first tick is immediate, skip it
         loop {
-            interval.tick().await;
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = save_shutdown.triggered() => break,
+            }
             tracing::info!("Autosaving...");
-            match persistence::save_world(
-                &save_world_ref, &save_dir, gen_fp, &*save_worldgen, Some(&save_deltas),
-            ) {
+            let bars = &save_registry.boss_bars;
+            let bar = bars.create(
+                ultimate_server::bossbar::BossBar::new("Autosave in progress", 0.0),
+                ultimate_server::bossbar::Viewers::Everyone,
+            );
+            let (world, storage) = (Arc::clone(&save_world_ref), Arc::clone(&save_storage));
+            match save_pools.run_blocking(move || storage.save(&world)).await {
                 Ok(n) => tracing::info!("Autosave complete: {} chunks", n),
                 Err(e) => tracing::error!("Autosave failed: {:#}", e),
             }
+            bars.remove(bar);
         }
     });
 
@@ -223,35 +560,157 @@ async fn main() {
         keep_radius,
         cfg.world.pregenerate_radius,
         cfg.world.eviction_interval_secs,
+        shutdown.clone(),
     );
 
     // ── Start listener with graceful shutdown ────────────────────────────
     tracing::info!("Starting Minecraft 1.21.11 server on {}", cfg.network.bind);
+    if cfg.proxy.mode == ultimate_server::config::ProxyMode::Bungeecord
+        && !ultimate_server::net::proxy::bind_is_loopback(&cfg.network.bind)
+    {
+        tracing::warn!(
+            "BungeeCord forwarding is on and {} is not loopback: its player data is unauthenticated, \
+             so anyone reaching this port directly can claim any UUID. Bind to 127.0.0.1 or firewall \
+             it to the proxy, or use Velocity forwarding",
+            cfg.network.bind,
+        );
+    }
 
-    tokio::select! {
-        result = ultimate_server::net::listener::run(
-            Arc::clone(&world), dashboard, spatial, registry,
-            Arc::clone(&worldgen),
-            Arc::clone(&cfg),
-            physics,
-        ) => {
-            if let Err(e) = result {
-                tracing::error!("Server error: {}", e);
-            }
-        }
-        _ = tokio::signal::ctrl_c() => {
+    let ctrl_c = shutdown.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
             tracing::info!("Ctrl+C received, shutting down...");
+            ctrl_c.trigger("Server closed");
+        }
+    });
+    let ctx = ultimate_server::net::listener::ServerContext {
+        world: Arc::clone(&world),
+        dashboard,
+        spatial,
+        registry: Arc::clone(&registry),
+        worldgen: Arc::clone(&worldgen),
+        config: Arc::clone(&cfg),
+        physics: physics.clone(),
+        storage: Arc::clone(&storage),
+        access,
+        pools,
+        skins,
+        chunks,
+        projectiles,
+        border,
+        status,
+        recipes,
+        trades,
+        shutdown: shutdown.clone(),
+    };
+    if let Err(e) = ultimate_server::net::listener::run(ctx    ).await {
+        tracing::error!("Server error: {}", e);
+    }
+
+    // Disconnect players and stop background tasks (a no-op trigger after
+    // Ctrl+C), giving them a bounded time to finish.
+    shutdown.trigger("Server closed");
+    match shutdown.wait_for_tasks(SHUTDOWN_TIMEOUT).await {
+        0 => tracing::info!("All tasks stopped"),
+        n => tracing::warn!("{} tasks still running after {:?}; saving anyway", n, SHUTDOWN_TIMEOUT),
+    }
+    // Logouts included.
+    registry.audit.flush().await;
+
+    // Close the journal with chunk hashes once in-flight cascades settle.
+    if let Some(recorder) = &recorder {
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        while physics.pending() != 0 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        match recorder.checkpoint(&world) {
+            Ok(n) => tracing::info!("Journal closed with {} chunk hashes", n),
+            Err(e) => tracing::error!("Journal checkpoint failed: {:#}", e),
         }
     }
 
     // ── Save on shutdown ─────────────────────────────────────────────────
     tracing::info!("Saving world before exit...");
-    match persistence::save_world(&world, &cfg.world.dir, gen_fp, &*base_worldgen, None) {
+    match storage.save(&world) {
         Ok(n) => tracing::info!("Shutdown save complete: {} chunks written", n),
         Err(e) => tracing::error!("Shutdown save failed: {:#}", e),
     }
 }
 
+/// `--replay <journal> [--parallel]`: replay a `--record` journal into a
+/// fresh world and fail if any checkpointed chunk hash differs.
+fn run_replay(path: &std::path::Path) -> anyhow::Result<()> {
+    let parallel = std::env::args().any(|a| a == "--parallel");
+    let records = ultimate_server::journal::read(path)?;
+    tracing::info!(
+        "Replaying {} journal records from {} ({})",
+        records.len(),
+        path.display(),
+        if parallel { "parallel" } else { "sequential" },
+    );
+    let (_, report) = ultimate_server::journal::replay(records, parallel);
+    tracing::info!(
+        "Replay done: {} roots, {} events executed, {} chunk hashes checked",
+        report.roots, report.executed, report.checked,
+    );
+    if !report.mismatched.is_empty() {
+        anyhow::bail!(
+            "replay diverged in {} chunk(s): {:?}",
+            report.mismatched.len(),
+            report.mismatched,
+        );
+    }
+    Ok(())
+}
+
+/// `admin export-players <out.json>` / `admin import-players <in.json>`:
+/// move ops, whitelist and bans between hosts as one bundle. Reads the
+/// access directory from the config file (created with defaults if
+/// missing, like a normal start).
+fn run_admin(config_path: &std::path::Path) -> anyhow::Result<()> {
+    use anyhow::Context;
+    use ultimate_server::access::AccessLists;
+
+    let cfg = config::load_or_create(config_path)?;
+    let access = AccessLists::load(&cfg.access.dir, cfg.access.whitelist)?;
+    // Positional args after `admin`, skipping `--flag value` pairs.
+    let mut args = Vec::new();
+    let mut rest = std::env::args().skip(2);
+    while let Some(a) = rest.next() {
+        if a.starts_with("--") {
+            rest.next();
+        } else {
+            args.push(a);
+        }
+    }
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["export-players", out] => {
+            let bundle = access.export_bundle();
+            std::fs::write(out, serde_json::to_string_pretty(&bundle)?)
+                .with_context(|| format!("writing {}", out))?;
+            tracing::info!(
+                "Exported {} ops, {} whitelisted, {} bans to {}",
+                bundle.ops.len(), bundle.whitelist.len(), bundle.banned_players.len(), out,
+            );
+        }
+        ["import-players", input] => {
+            let text = std::fs::read_to_string(input).with_context(|| format!("reading {}", input))?;
+            let bundle: ultimate_server::access::PlayerBundle =
+                serde_json::from_str(&text).with_context(|| format!("parsing {}", input))?;
+            let counts = (bundle.ops.len(), bundle.whitelist.len(), bundle.banned_players.len());
+            access.import_bundle(bundle)?;
+            tracing::info!(
+                "Imported {} ops, {} whitelisted, {} bans into {}",
+                counts.0, counts.1, counts.2, cfg.access.dir.display(),
+            );
+        }
+        _ => anyhow::bail!(
+            "usage: ultimate-server admin export-players <out.json> | import-players <in.json> [--config server.yaml]"
+        ),
+    }
+    Ok(())
+}
+
 /// Original sand-drop demo for testing the causal engine.
 fn run_demo() {
     use ultimate_engine::causal::event::{Event, EventPayload};
@@ -0,0 +1,802 @@
+//! Mobs: pigs, cows and villagers that spawn on grass and wander about,
+//! and zombies and skeletons that spawn in the dark and hunt players.
+//! Villagers trade (see [`crate::trading`]).
+//!
+//! [`MobLayer`] is the simulation layer that spawns them, up to
+//! `mobs.cap`: passive mobs on grass in loaded chunks, hostile ones
+//! where block light is 0 and, by day, the sky's light is dim (caves,
+//! deep shade). [`start_ai`] runs the AI on its own timer. A hostile mob
+//! picks the nearest player it can hurt within [`FOLLOW_RANGE`], walks
+//! there along an A* path ([`ultimate_engine::pathfinding`]) and hits
+//! them when close; skeletons don't shoot yet, they fight up close too.
+//! Caught in daylight under open sky, hostile mobs burn away. Mobs live only here — they never touch the causal
+//! graph — and reach clients as [`EntityEvent`]s on the spatial bus's
+//! entity channel. A connection spawns a mob the first time it hears of
+//! it, so idle mobs re-announce themselves every [`ANNOUNCE_TICKS`]
+//! ticks: that is how players who arrive later see them. Mobs in chunks
+//! that unload go dormant — despawned for clients, kept here — and wake
+//! when the chunk loads again; the world saves them all (see
+//! [`WorldStorage::attach_mobs`](crate::persistence::WorldStorage::attach_mobs)).
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use azalea_registry::builtin::EntityKind;
+
+use ultimate_engine::causal::event::Event;
+use ultimate_engine::pathfinding::{self, Cell, Limits};
+use ultimate_engine::world::World;
+use ultimate_engine::world::position::BlockPos;
+
+use crate::block;
+use crate::event_bus::{EntityEvent, SpatialBus};
+use crate::persistence::SavedEntity;
+use crate::player_registry::PlayerRegistry;
+use crate::pools::Pools;
+use crate::simulation::SimulationLayer;
+
+/// How often the wander AI ticks.
+pub const AI_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often [`MobLayer`] tries to spawn a mob.
+pub const SPAWN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// An idle mob re-announces its position every this many AI ticks.
+pub const ANNOUNCE_TICKS: u64 = 8;
+
+/// Walking speed, in blocks per AI tick (1 block/s).
+const SPEED: f64 = 0.25;
+
+/// A hostile mob's walking speed (1.4 blocks/s).
+const HOSTILE_SPEED: f64 = 0.35;
+
+/// How far hostile mobs notice players, in blocks.
+pub const FOLLOW_RANGE: f64 = 16.0;
+
+/// How close a hostile mob gets to hit.
+const MELEE_REACH: f64 = 1.5;
+
+/// AI ticks between a hostile mob's hits (one second).
+const ATTACK_TICKS: u32 = 4;
+
+/// AI ticks between fresh paths to a target, who may have moved.
+const REPATH_TICKS: u64 = 8;
+
+/// Hit points of every mob.
+const MAX_HEALTH: f32 = 20.0;
+
+/// Health a hostile mob in daylight loses each second.
+const BURN_DAMAGE: f32 = 1.0;
+
+/// Brightest sky light hostile mobs spawn in by day. At night the sky
+/// doesn't count; only block light (torches) keeps them away.
+const DARK_SKY: u8 = 7;
+
+/// Length of a Minecraft day, in ticks.
+const DAY_TICKS: i64 = 24_000;
+
+/// How far a mob wanders from where it stands, per walk.
+const WANDER_RADIUS: f64 = 6.0;
+
+/// One in this many idle ticks starts a new walk.
+const WANDER_CHANCE: u64 = 12;
+
+/// Columns tried per spawn attempt before giving up until the next one.
+const SPAWN_TRIES: usize = 4;
+
+/// Topmost block a surface scan starts from.
+const MAX_Y: i64 = 319;
+
+/// Bottom of the world.
+const MIN_Y: i64 = -64;
+
+/// The kinds of mob that spawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MobKind {
+    Pig,
+    Cow,
+    Villager,
+    Zombie,
+    Skeleton,
+}
+
+impl MobKind {
+    /// Every kind.
+    pub const ALL: [MobKind; 5] = [MobKind::Pig, MobKind::Cow, MobKind::Villager, MobKind::Zombie, MobKind::Skeleton];
+
+    /// Kinds that spawn on grass, each as likely.
+    const PASSIVE: [MobKind; 3] = [MobKind::Pig, MobKind::Cow, MobKind::Villager];
+
+    /// Kinds that spawn in the dark, each as likely.
+    const HOSTILE: [MobKind; 2] = [MobKind::Zombie, MobKind::Skeleton];
+
+    pub fn is_hostile(self) -> bool {
+        Self::HOSTILE.contains(&self)
+    }
+
+    /// As named in death messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            MobKind::Pig => "Pig",
+            MobKind::Cow => "Cow",
+            MobKind::Villager => "Villager",
+            MobKind::Zombie => "Zombie",
+            MobKind::Skeleton => "Skeleton",
+        }
+    }
+
+    /// Damage of one hit on a player.
+    fn attack_damage(self) -> f32 {
+        match self {
+            MobKind::Zombie => 3.0,
+            MobKind::Skeleton => 2.0,
+            _ => 0.0,
+        }
+    }
+
+    fn speed(self) -> f64 {
+        if self.is_hostile() { HOSTILE_SPEED } else { SPEED }
+    }
+
+    pub fn entity_kind(self) -> EntityKind {
+        match self {
+            MobKind::Pig => EntityKind::Pig,
+            MobKind::Cow => EntityKind::Cow,
+            MobKind::Villager => EntityKind::Villager,
+            MobKind::Zombie => EntityKind::Zombie,
+            MobKind::Skeleton => EntityKind::Skeleton,
+        }
+    }
+
+    /// Vanilla's entity id, as saved.
+    pub fn id(self) -> &'static str {
+        match self {
+            MobKind::Pig => "minecraft:pig",
+            MobKind::Cow => "minecraft:cow",
+            MobKind::Villager => "minecraft:villager",
+            MobKind::Zombie => "minecraft:zombie",
+            MobKind::Skeleton => "minecraft:skeleton",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        MobKind::ALL.into_iter().find(|kind| kind.id() == id)
+    }
+}
+
+/// One mob. `x`/`z` are continuous; `y` is always a block's floor.
+#[derive(Debug, Clone)]
+pub struct Mob {
+    pub entity_id: i32,
+    pub uuid: uuid::Uuid,
+    pub kind: MobKind,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub y_rot: f32,
+    /// Where the current walk is heading, if walking.
+    target: Option<(f64, f64)>,
+    /// Its chunk is unloaded: clients were told it is gone, and it stands
+    /// still until the chunk is back.
+    dormant: bool,
+    health: f32,
+    /// A hostile mob's way to its target, next step last.
+    path: Vec<BlockPos>,
+    /// AI ticks until a hostile mob may hit again.
+    cooldown: u32,
+}
+
+impl Mob {
+    fn event(&self, on_ground: bool) -> EntityEvent {
+        EntityEvent::Moved {
+            entity_id: self.entity_id,
+            uuid: self.uuid,
+            kind: self.kind.entity_kind(),
+            x: self.x,
+            y: self.y,
+            z: self.z,
+            y_rot: self.y_rot,
+            x_rot: 0.0,
+            on_ground,
+        }
+    }
+
+    fn feet(&self) -> BlockPos {
+        BlockPos::new(self.x.floor() as i64, self.y.floor() as i64, self.z.floor() as i64)
+    }
+
+    fn new(entity_id: i32, uuid: uuid::Uuid, kind: MobKind, pos: [f64; 3], y_rot: f32) -> Self {
+        Self {
+            entity_id,
+            uuid,
+            kind,
+            x: pos[0],
+            y: pos[1],
+            z: pos[2],
+            y_rot,
+            target: None,
+            dormant: false,
+            health: MAX_HEALTH,
+            path: Vec::new(),
+            cooldown: 0,
+        }
+    }
+}
+
+/// A player hostile mobs may go after.
+#[derive(Debug, Clone, Copy)]
+struct Prey {
+    uuid: uuid::Uuid,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+/// Whether it is day at world time `time` (for burning and spawning).
+pub fn is_day(time: i64) -> bool {
+    !(12_542..23_460).contains(&time.rem_euclid(DAY_TICKS))
+}
+
+/// All mobs, shared by the spawning layer and the AI task.
+pub struct Mobs {
+    mobs: Mutex<Vec<Mob>>,
+    cap: usize,
+    rng: Mutex<Rng>,
+    registry: Arc<PlayerRegistry>,
+    bus: Arc<SpatialBus>,
+    /// World time as of the AI's last tick (see [`start_ai`]).
+    time: AtomicI64,
+}
+
+impl Mobs {
+    /// No mobs yet; at most `cap` at a time. Entity IDs come from the
+    /// player registry so they never collide with players'.
+    pub fn new(cap: usize, registry: Arc<PlayerRegistry>, bus: Arc<SpatialBus>, seed: u64) -> Arc<Self> {
+        Arc::new(Self {
+            mobs: Mutex::new(Vec::new()),
+            cap,
+            rng: Mutex::new(Rng(seed)),
+            registry,
+            bus,
+            time: AtomicI64::new(0),
+        })
+    }
+
+    /// Tell the mobs the world's time of day.
+    pub fn set_time(&self, time: i64) {
+        self.time.store(time, Ordering::Relaxed);
+    }
+
+    /// How many mobs are alive.
+    pub fn len(&self) -> usize {
+        self.mobs.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// A copy of every mob.
+    pub fn snapshot(&self) -> Vec<Mob> {
+        self.mobs.lock().unwrap().clone()
+    }
+
+    /// Try to spawn one mob in a random loaded chunk — half the time a
+    /// hostile one if the column has a dark spot, otherwise a passive one
+    /// on grass — unless the cap is reached or `doMobSpawning` is off.
+    /// Returns whether one spawned.
+    pub fn spawn_tick(&self, world: &World) -> bool {
+        if !crate::gamerules::enabled(crate::gamerules::GameRule::DoMobSpawning)
+            || self.len() >= self.cap
+            || world.chunk_count() == 0
+        {
+            return false;
+        }
+        let night = !is_day(self.time.load(Ordering::Relaxed));
+        let mut rng = self.rng.lock().unwrap();
+        for _ in 0..SPAWN_TRIES {
+            let nth = rng.below(world.chunk_count() as u64) as usize;
+            let Some(chunk) = world.iter_chunks().nth(nth).map(|entry| *entry.key()) else {
+                continue;
+            };
+            let x = chunk.x as i64 * 16 + rng.below(16) as i64;
+            let z = chunk.z as i64 * 16 + rng.below(16) as i64;
+            let dark = match rng.below(2) {
+                0 => dark_spot(world, x, z, rng.next(), night),
+                _ => None,
+            };
+            let (kinds, y): (&[MobKind], _) = match dark {
+                Some(y) => (&MobKind::HOSTILE[..], y),
+                None => match grass_surface(world, x, z) {
+                    Some(y) => (&MobKind::PASSIVE[..], y),
+                    None => continue,
+                },
+            };
+            let kind = kinds[rng.below(kinds.len() as u64) as usize];
+            let mob = Mob::new(
+                self.registry.allocate_entity_id(),
+                uuid::Uuid::from_u64_pair(rng.next(), rng.next()),
+                kind,
+                [x as f64 + 0.5, y as f64, z as f64 + 0.5],
+                rng.below(360) as f32,
+            );
+            self.bus.publish_entity(mob.event(true));
+            self.mobs.lock().unwrap().push(mob);
+            return true;
+        }
+        false
+    }
+
+    /// One AI tick: every mob falls, walks, hunts or idles, and what
+    /// changed is published. Mobs whose chunk unloaded go dormant; dormant
+    /// ones whose chunk is back wake up.
+    pub fn ai_tick(&self, world: &World, tick: u64) {
+        let prey: Vec<Prey> = self
+            .registry
+            .snapshot()
+            .into_iter()
+            .filter(|p| crate::combat::fights(p.game_mode))
+            .map(|p| Prey { uuid: p.uuid, x: p.x, y: p.y, z: p.z })
+            .collect();
+        self.tick_mobs(world, tick, &prey);
+    }
+
+    fn tick_mobs(&self, world: &World, tick: u64, prey: &[Prey]) {
+        let day = is_day(self.time.load(Ordering::Relaxed));
+        let mut rng = self.rng.lock().unwrap();
+        let mut mobs = self.mobs.lock().unwrap();
+        for mob in mobs.iter_mut() {
+            let loaded = world.has_chunk(mob.feet().chunk());
+            if !loaded {
+                if !mob.dormant {
+                    mob.dormant = true;
+                    self.bus.publish_entity(EntityEvent::Removed { entity_id: mob.entity_id, x: mob.x, z: mob.z });
+                }
+                continue;
+            }
+            let woke = std::mem::take(&mut mob.dormant);
+            let moved = if mob.kind.is_hostile() { self.hunt(world, mob, prey, tick, &mut rng) } else { step(world, mob, &mut rng) };
+            if woke || moved.is_some() || (tick + mob.entity_id as u64).is_multiple_of(ANNOUNCE_TICKS) {
+                self.bus.publish_entity(mob.event(moved != Some(Motion::Fell)));
+            }
+            let second = (tick + mob.entity_id as u64).is_multiple_of(4);
+            if mob.kind.is_hostile() && day && second && in_sunlight(world, mob) {
+                mob.health -= BURN_DAMAGE;
+            }
+        }
+        mobs.retain(|mob| {
+            let alive = mob.health > 0.0;
+            if !alive {
+                self.bus.publish_entity(EntityEvent::Removed { entity_id: mob.entity_id, x: mob.x, z: mob.z });
+            }
+            alive
+        });
+    }
+
+    /// A hostile mob's tick: hit the nearest prey in reach, otherwise walk
+    /// its path toward them (finding a new one now and then), or wander
+    /// with no one about.
+    fn hunt(&self, world: &World, mob: &mut Mob, prey: &[Prey], tick: u64, rng: &mut Rng) -> Option<Motion> {
+        mob.cooldown = mob.cooldown.saturating_sub(1);
+        let distance = |p: &Prey| (p.x - mob.x).hypot(p.z - mob.z).hypot(p.y - mob.y);
+        let Some(target) = prey
+            .iter()
+            .filter(|p| distance(p) <= FOLLOW_RANGE)
+            .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+            .copied()
+        else {
+            mob.path.clear();
+            return step(world, mob, rng);
+        };
+
+        let (dx, dz) = (target.x - mob.x, target.z - mob.z);
+        if dx.hypot(dz) <= MELEE_REACH && (target.y - mob.y).abs() < 2.0 {
+            mob.target = None;
+            mob.path.clear();
+            mob.y_rot = (-dx).atan2(dz).to_degrees() as f32;
+            if mob.cooldown == 0 {
+                self.registry.attack(target.uuid, mob.kind.name(), mob.kind.attack_damage(), [mob.x, mob.y, mob.z]);
+                mob.cooldown = ATTACK_TICKS;
+            }
+            return fall(world, mob).then_some(Motion::Fell);
+        }
+
+        if mob.path.is_empty() || (tick + mob.entity_id as u64).is_multiple_of(REPATH_TICKS) {
+            let goal = BlockPos::new(target.x.floor() as i64, target.y.floor() as i64, target.z.floor() as i64);
+            let limits = Limits::new(block::path_cell);
+            mob.path = pathfinding::find_path(world, mob.feet(), goal, &limits)
+                .map(|path| path.steps.into_iter().rev().collect())
+                .unwrap_or_default();
+            mob.target = None;
+        }
+        if mob.target.is_none()
+            && let Some(next) = mob.path.pop()
+        {
+            mob.target = Some((next.x as f64 + 0.5, next.z as f64 + 0.5));
+        }
+        step(world, mob, rng)
+    }
+
+    /// Every mob, as saved.
+    pub fn saved(&self) -> Vec<SavedEntity> {
+        self.mobs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|mob| SavedEntity {
+                id: mob.kind.id().to_string(),
+                uuid: mob.uuid,
+                pos: [mob.x, mob.y, mob.z],
+                rotation: [mob.y_rot, 0.0],
+            })
+            .collect()
+    }
+
+    /// Bring back saved mobs, dormant until the AI sees their chunk
+    /// loaded. They get fresh entity IDs and may exceed the cap; entities
+    /// that aren't mobs are ignored. Returns how many were restored.
+    pub fn restore(&self, saved: &[SavedEntity]) -> usize {
+        let restored: Vec<Mob> = saved
+            .iter()
+            .filter_map(|entity| {
+                let kind = MobKind::from_id(&entity.id)?;
+                let pos = [entity.pos[0], entity.pos[1].floor(), entity.pos[2]];
+                let mut mob = Mob::new(self.registry.allocate_entity_id(), entity.uuid, kind, pos, entity.rotation[0]);
+                mob.dormant = true;
+                Some(mob)
+            })
+            .collect();
+        let count = restored.len();
+        self.mobs.lock().unwrap().extend(restored);
+        count
+    }
+}
+
+/// The simulation layer that spawns mobs. It submits no block events.
+pub struct MobLayer(pub Arc<Mobs>);
+
+impl SimulationLayer for MobLayer {
+    fn name(&self) -> &'static str {
+        "mobs"
+    }
+
+    fn interval(&self) -> Duration {
+        SPAWN_INTERVAL
+    }
+
+    fn generate_events(&self, world: &World) -> Vec<Event> {
+        self.0.spawn_tick(world);
+        Vec::new()
+    }
+}
+
+/// Run the AI every [`AI_INTERVAL`] on the blocking pool until
+/// `shutdown`. `clock` is the world time (day and night).
+pub fn start_ai(
+    mobs: Arc<Mobs>,
+    world: Arc<World>,
+    pools: Arc<Pools>,
+    clock: impl Fn() -> i64 + Send + 'static,
+    shutdown: crate::shutdown::Shutdown,
+) {
+    tokio::spawn(async move {
+        let _running = shutdown.task();
+        let mut interval = tokio::time::interval(AI_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        for tick in 0u64.. {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.triggered() => break,
+            }
+            mobs.set_time(clock());
+            let (mobs, world) = (Arc::clone(&mobs), Arc::clone(&world));
+            pools.run_blocking(move || mobs.ai_tick(&world, tick)).await;
+        }
+    });
+}
+
+/// The feet height of a mob spawning in column (`x`, `z`): above its
+/// topmost block, if that is grass with room for a mob above.
+fn grass_surface(world: &World, x: i64, z: i64) -> Option<i64> {
+    let top = (MIN_Y..=MAX_Y).rev().find(|&y| !block::is_passable(world.get_block(BlockPos::new(x, y, z))))?;
+    let room = |y| block::is_passable(world.get_block(BlockPos::new(x, y, z)));
+    (world.get_block(BlockPos::new(x, top, z)) == block::GRASS_BLOCK && room(top + 1) && room(top + 2))
+        .then_some(top + 1)
+}
+
+/// The feet height of a hostile mob spawning in column (`x`, `z`): the
+/// first dark spot with room for it at or above a height picked by
+/// `roll`. Dark is block light 0, and by day sky light up to
+/// [`DARK_SKY`]; columns whose light isn't computed yet are skipped.
+fn dark_spot(world: &World, x: i64, z: i64, roll: u64, night: bool) -> Option<i64> {
+    let at = |y| BlockPos::new(x, y, z);
+    if !world.is_sky_lit(&at(0).chunk()) {
+        return None;
+    }
+    let top = (MIN_Y..=MAX_Y).rev().find(|&y| !block::is_passable(world.get_block(at(y))))?;
+    let start = MIN_Y + 1 + (roll % (top - MIN_Y + 1) as u64) as i64;
+    (start..=top + 1).find(|&y| {
+        block::path_cell(world.get_block(at(y - 1))) == Cell::Solid
+            && block::is_passable(world.get_block(at(y)))
+            && block::is_passable(world.get_block(at(y + 1)))
+            && world.get_block_light(at(y)) == 0
+            && (night || world.get_sky_light(at(y)) <= DARK_SKY)
+    })
+}
+
+/// Whether `mob` stands in full sunlight (sky light 15 at its head).
+fn in_sunlight(world: &World, mob: &Mob) -> bool {
+    let head = mob.feet().offset(0, 1, 0);
+    world.is_sky_lit(&head.chunk()) && world.get_sky_light(head) >= 15
+}
+
+/// How a mob moved in a step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Walked,
+    Fell,
+}
+
+/// Advance `mob` by one AI tick: fall a block if nothing is under it,
+/// otherwise take a step of its walk (climbing at most one block, never
+/// into fluids or solid blocks) or maybe start one. A blocked walk ends.
+fn step(world: &World, mob: &mut Mob, rng: &mut Rng) -> Option<Motion> {
+    if fall(world, mob) {
+        return Some(Motion::Fell);
+    }
+    let feet = mob.feet();
+
+    let Some((tx, tz)) = mob.target else {
+        if rng.below(WANDER_CHANCE) == 0 {
+            let angle = rng.unit() * std::f64::consts::TAU;
+            let distance = 1.0 + rng.unit() * (WANDER_RADIUS - 1.0);
+            mob.target = Some((mob.x + angle.cos() * distance, mob.z + angle.sin() * distance));
+        }
+        return None;
+    };
+    let speed = mob.kind.speed();
+    let (dx, dz) = (tx - mob.x, tz - mob.z);
+    let distance = (dx * dx + dz * dz).sqrt();
+    if distance < speed {
+        mob.target = None;
+        return None;
+    }
+    let (nx, nz) = (mob.x + dx / distance * speed, mob.z + dz / distance * speed);
+
+    let clear = |y: i64| {
+        let at = |y| block::is_passable(world.get_block(BlockPos::new(nx.floor() as i64, y, nz.floor() as i64)));
+        at(y) && at(y + 1)
+    };
+    let y = if clear(feet.y) {
+        feet.y
+    } else if clear(feet.y + 1) && block::is_solid(world.get_block(BlockPos::new(nx.floor() as i64, feet.y, nz.floor() as i64))) {
+        feet.y + 1
+    } else {
+        mob.target = None;
+        return None;
+    };
+    mob.x = nx;
+    mob.z = nz;
+    mob.y = y as f64;
+    mob.y_rot = (-dx).atan2(dz).to_degrees() as f32;
+    Some(Motion::Walked)
+}
+
+/// Drop `mob` a block if nothing is under it. Returns whether it fell.
+fn fall(world: &World, mob: &mut Mob) -> bool {
+    let feet = mob.feet();
+    let below = BlockPos::new(feet.x, feet.y - 1, feet.z);
+    let falls = feet.y > MIN_Y && block::is_passable(world.get_block(below));
+    if falls {
+        mob.y -= 1.0;
+    }
+    falls
+}
+
+/// splitmix64: small, seedable, good enough for wandering.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..n` (`n` > 0).
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player_registry::PlayerEvent;
+    use ultimate_engine::world::chunk::Chunk;
+    use ultimate_engine::world::position::ChunkPos;
+
+    /// One chunk: stone to y=3, grass at y=4, and a stone wall at x=8.
+    fn meadow() -> World {
+        let world = World::new();
+        world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..4 {
+                    world.set_block(BlockPos::new(x, y, z), block::STONE);
+                }
+                world.set_block(BlockPos::new(x, 4, z), block::GRASS_BLOCK);
+            }
+        }
+        for z in 0..16 {
+            for y in 5..8 {
+                world.set_block(BlockPos::new(8, y, z), block::STONE);
+            }
+        }
+        world
+    }
+
+    fn herd(cap: usize) -> Arc<Mobs> {
+        let bus = SpatialBus::new();
+        Mobs::new(cap, Arc::new(PlayerRegistry::new(Arc::clone(&bus))), bus, 7)
+    }
+
+    #[test]
+    fn test_spawns_on_grass_up_to_cap() {
+        let world = meadow();
+        let mobs = herd(3);
+        for _ in 0..50 {
+            mobs.spawn_tick(&world);
+        }
+        assert_eq!(mobs.len(), 3);
+        for mob in mobs.snapshot() {
+            assert_eq!(mob.y, 5.0, "standing on the grass");
+            assert_ne!(mob.x.floor(), 8.0, "not on the wall");
+        }
+
+        let bare = World::new();
+        bare.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+        assert!(!herd(3).spawn_tick(&bare), "no grass, no mobs");
+    }
+
+    #[test]
+    fn test_wandering_respects_walls_and_unloads() {
+        let world = meadow();
+        let mut rng = Rng(1);
+        let mut mob = Mob::new(1, uuid::Uuid::nil(), MobKind::Pig, [6.5, 5.0, 4.5], 0.0);
+        mob.target = Some((12.0, 4.5));
+        let mut walked = 0;
+        while step(&world, &mut mob, &mut rng) == Some(Motion::Walked) {
+            walked += 1;
+        }
+        assert!(walked > 0);
+        assert!(mob.x < 8.0, "the three-high wall stops it at x={}", mob.x);
+        assert_eq!(mob.target, None, "a blocked walk ends");
+
+        // A one-block step is climbed; a drop is fallen down.
+        world.set_block(BlockPos::new(5, 5, 4), block::STONE);
+        mob.target = Some((3.0, 4.5));
+        while mob.x > 4.0 && step(&world, &mut mob, &mut rng).is_some() {}
+        world.set_block(BlockPos::new(5, 5, 4), block::AIR);
+        assert!(mob.x <= 4.0, "walked over the step");
+        world.set_block(BlockPos::new(mob.x.floor() as i64, 4, 4), block::AIR);
+        assert_eq!(step(&world, &mut mob, &mut rng), Some(Motion::Fell));
+        assert_eq!(mob.y, 4.0);
+
+        let mobs = herd(1);
+        assert!(mobs.spawn_tick(&world));
+        world.remove_chunk(ChunkPos::new(0, 0));
+        mobs.ai_tick(&world, 0);
+        assert!(mobs.snapshot()[0].dormant, "asleep with its chunk");
+        world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+        mobs.ai_tick(&world, 1);
+        assert!(!mobs.snapshot()[0].dormant, "and awake when it is back");
+    }
+
+    #[test]
+    fn test_hostiles_spawn_in_the_dark_and_burn_by_day() {
+        let world = meadow();
+        // A fence rim, above the wall at x=8, keeps them all in the chunk:
+        // nothing spawns on or climbs a fence.
+        let fence = block::block_id_from_name("oak_fence").unwrap();
+        for i in 0..16 {
+            for y in 5..10 {
+                for pos in [(0, i), (15, i), (i, 0), (i, 15)] {
+                    world.set_block(BlockPos::new(pos.0, y, pos.1), fence);
+                }
+            }
+        }
+        let mobs = herd(40);
+        for _ in 0..10 {
+            mobs.spawn_tick(&world);
+        }
+        assert!(mobs.snapshot().iter().all(|mob| !mob.kind.is_hostile()), "no light computed, no hostiles");
+
+        world.mark_sky_lit(ChunkPos::new(0, 0));
+        for _ in 0..100 {
+            mobs.spawn_tick(&world);
+        }
+        let hostile = |mobs: &Mobs| mobs.snapshot().iter().filter(|mob| mob.kind.is_hostile()).count();
+        assert!(hostile(&mobs) > 0, "the unlit meadow is dark");
+
+        // Sunrise: the sky lights the meadow and the hostiles burn away.
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 5..20 {
+                    world.set_sky_light(BlockPos::new(x, y, z), 15);
+                }
+            }
+        }
+        mobs.set_time(1000);
+        for tick in 0..100 {
+            mobs.tick_mobs(&world, tick, &[]);
+        }
+        assert_eq!(hostile(&mobs), 0);
+        assert!(!mobs.snapshot().is_empty(), "the others don't burn");
+    }
+
+    #[test]
+    fn test_zombie_walks_around_a_wall_and_attacks() {
+        let world = meadow();
+        for z in 0..14 {
+            world.set_block(BlockPos::new(4, 5, z), block::STONE);
+            world.set_block(BlockPos::new(4, 6, z), block::STONE);
+        }
+        let mobs = herd(1);
+        let mut events = mobs.registry.subscribe();
+        mobs.mobs.lock().unwrap().push(Mob::new(1, uuid::Uuid::nil(), MobKind::Zombie, [1.5, 5.0, 2.5], 0.0));
+        let player = uuid::Uuid::from_u64_pair(1, 2);
+        let prey = [Prey { uuid: player, x: 6.5, y: 5.0, z: 2.5 }];
+
+        let mut attacked = None;
+        for tick in 0..200 {
+            mobs.tick_mobs(&world, tick, &prey);
+            while let Ok(event) = events.try_recv() {
+                if let PlayerEvent::Attacked { target, attacker, damage, .. } = event {
+                    attacked.get_or_insert((tick, target, attacker, damage));
+                }
+            }
+        }
+        let (tick, target, attacker, damage) = attacked.expect("the zombie reached the player");
+        assert_eq!((target, attacker.as_str(), damage), (player, "Zombie", 3.0));
+        assert!(tick > 20, "went the long way round, around z=14");
+
+        // Out of range, it loses interest.
+        let far = [Prey { uuid: player, x: 1.5, y: 5.0, z: 2.5 + FOLLOW_RANGE + 20.0 }];
+        mobs.tick_mobs(&world, 200, &far);
+        assert!(mobs.snapshot()[0].path.is_empty());
+    }
+
+    #[test]
+    fn test_mobs_survive_a_save() {
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_mob_save");
+        let _ = std::fs::remove_dir_all(&tmp);
+        let world = meadow();
+        let mobs = herd(3);
+        while mobs.spawn_tick(&world) {}
+
+        let written = crate::persistence::save_entities(&tmp, &mobs.saved(), &Default::default()).unwrap();
+        assert_eq!(written, [ChunkPos::new(0, 0)].into());
+        let reloaded = herd(3);
+        assert_eq!(reloaded.restore(&crate::persistence::load_entities(&tmp).unwrap()), 3);
+        let uuids = |mobs: &Mobs| {
+            let mut u: Vec<_> = mobs.snapshot().iter().map(|m| (m.uuid, m.kind, m.x, m.z)).collect();
+            u.sort_by_key(|m| m.0);
+            u
+        };
+        assert_eq!(uuids(&reloaded), uuids(&mobs));
+
+        // A chunk that no longer holds mobs is saved empty.
+        crate::persistence::save_entities(&tmp, &[], &written).unwrap();
+        assert!(crate::persistence::load_entities(&tmp).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
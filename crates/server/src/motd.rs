@@ -0,0 +1,316 @@
+//! What the server list shows: MOTD, max players and favicon.
+//!
+//! The MOTD comes from `status.motd` and may be formatted with legacy
+//! codes (`&a`, `§l`) or MiniMessage-style tags (`<red>`, `<bold>`,
+//! `</red>`, `<reset>`, `<newline>`); [`to_legacy`] turns both into `§`
+//! codes, which the status response and the legacy ping both
+//! understand. The favicon is `status.icon`, a 64×64 PNG sent base64
+//! encoded.
+//!
+//! The config file and the icon are re-read whenever their mtime changes
+//! ([`ServerStatus::reload_if_changed`], polled by [`start_reloader`]), so
+//! an operator can change the MOTD, `network.max_players` or the icon
+//! without a restart. A file that fails to load keeps the previous value.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+
+use crate::config::ServerConfig;
+
+/// Side length vanilla clients expect of the favicon.
+pub const ICON_SIZE: u32 = 64;
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// A snapshot of the server-list entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    /// The MOTD with `§` formatting codes.
+    pub motd: String,
+    pub max_players: u32,
+    /// `data:image/png;base64,...`, if an icon is set.
+    pub favicon: Option<String>,
+}
+
+impl Status {
+    /// The MOTD without formatting, for protocols that can't show it.
+    pub fn plain_motd(&self) -> String {
+        strip_codes(&self.motd)
+    }
+}
+
+struct State {
+    status: Status,
+    icon: PathBuf,
+    config_mtime: Option<SystemTime>,
+    icon_mtime: Option<SystemTime>,
+}
+
+/// The current server-list entry, kept in step with the config file.
+pub struct ServerStatus {
+    config_path: PathBuf,
+    state: RwLock<State>,
+}
+
+impl ServerStatus {
+    /// Start from the already loaded `config` (read from `config_path`)
+    /// and load the icon.
+    pub fn new(config: &ServerConfig, config_path: &Path) -> Self {
+        let status = Self {
+            config_path: config_path.to_path_buf(),
+            state: RwLock::new(State {
+                status: Status {
+                    motd: to_legacy(&config.status.motd),
+                    max_players: config.network.max_players,
+                    favicon: None,
+                },
+                icon: config.status.icon.clone(),
+                config_mtime: mtime(config_path),
+                icon_mtime: None,
+            }),
+        };
+        if let Err(e) = status.reload_if_changed() {
+            tracing::warn!("Server icon not loaded: {:#}", e);
+        }
+        status
+    }
+
+    pub fn current(&self) -> Status {
+        self.state.read().expect("server status poisoned").status.clone()
+    }
+
+    pub fn max_players(&self) -> u32 {
+        self.state.read().expect("server status poisoned").status.max_players
+    }
+
+    /// Re-read the config file and the icon if their mtimes changed.
+    /// Returns whether anything was reloaded.
+    pub fn reload_if_changed(&self) -> Result<bool> {
+        let mut state = self.state.write().expect("server status poisoned");
+        let mut reloaded = false;
+
+        let config_mtime = mtime(&self.config_path);
+        if config_mtime != state.config_mtime {
+            state.config_mtime = config_mtime;
+            let text = std::fs::read_to_string(&self.config_path)
+                .with_context(|| format!("reading {}", self.config_path.display()))?;
+            let config: ServerConfig = serde_yaml::from_str(&text)
+                .with_context(|| format!("parsing {}", self.config_path.display()))?;
+            state.status.motd = to_legacy(&config.status.motd);
+            state.status.max_players = config.network.max_players;
+            if config.status.icon != state.icon {
+                state.icon = config.status.icon;
+                state.icon_mtime = None;
+                state.status.favicon = None;
+            }
+            tracing::info!("Reloaded server list entry from {}", self.config_path.display());
+            reloaded = true;
+        }
+
+        let icon_mtime = mtime(&state.icon);
+        if icon_mtime != state.icon_mtime {
+            state.icon_mtime = icon_mtime;
+            state.status.favicon = None;
+            reloaded = true;
+            if icon_mtime.is_some() {
+                let png = std::fs::read(&state.icon).with_context(|| format!("reading {}", state.icon.display()))?;
+                state.status.favicon = Some(favicon(&png).with_context(|| format!("loading {}", state.icon.display()))?);
+                tracing::info!("Loaded server icon {}", state.icon.display());
+            }
+        }
+        Ok(reloaded)
+    }
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// A favicon data URI for `png`, which must be a 64×64 PNG.
+pub fn favicon(png: &[u8]) -> Result<String> {
+    // The IHDR chunk comes first: length, type, then width and height.
+    if png.len() < 24 || !png.starts_with(PNG_SIGNATURE) || &png[12..16] != b"IHDR" {
+        bail!("not a PNG image");
+    }
+    let width = u32::from_be_bytes(png[16..20].try_into().expect("4 bytes"));
+    let height = u32::from_be_bytes(png[20..24].try_into().expect("4 bytes"));
+    if (width, height) != (ICON_SIZE, ICON_SIZE) {
+        bail!("icon is {}x{}, must be {}x{}", width, height, ICON_SIZE, ICON_SIZE);
+    }
+    Ok(format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png)))
+}
+
+/// Legacy code for a MiniMessage-style tag name.
+fn tag_code(tag: &str) -> Option<char> {
+    Some(match tag {
+        "black" => '0',
+        "dark_blue" => '1',
+        "dark_green" => '2',
+        "dark_aqua" => '3',
+        "dark_red" => '4',
+        "dark_purple" => '5',
+        "gold" => '6',
+        "gray" | "grey" => '7',
+        "dark_gray" | "dark_grey" => '8',
+        "blue" => '9',
+        "green" => 'a',
+        "aqua" => 'b',
+        "red" => 'c',
+        "light_purple" => 'd',
+        "yellow" => 'e',
+        "white" => 'f',
+        "obfuscated" | "obf" => 'k',
+        "bold" | "b" => 'l',
+        "strikethrough" | "st" => 'm',
+        "underlined" | "u" => 'n',
+        "italic" | "i" | "em" => 'o',
+        "reset" => 'r',
+        _ => return None,
+    })
+}
+
+fn is_code(c: char) -> bool {
+    matches!(c.to_ascii_lowercase(), '0'..='9' | 'a'..='f' | 'k'..='o' | 'r')
+}
+
+/// Turn `&x` codes and MiniMessage-style tags into `§` codes. A closing
+/// tag resets all formatting (legacy codes can't undo just one); unknown
+/// tags and `&` not followed by a code are kept as written.
+pub fn to_legacy(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut rest = src;
+    while let Some(c) = rest.chars().next() {
+        if c == '&' && rest[1..].chars().next().is_some_and(is_code) {
+            out.push('§');
+            rest = &rest[1..];
+            continue;
+        }
+        if c == '<'
+            && let Some(end) = rest.find('>')
+        {
+            let tag = rest[1..end].trim().to_ascii_lowercase();
+            let replacement = match tag.strip_prefix('/') {
+                Some(closed) if tag_code(closed).is_some() => Some("§r".to_owned()),
+                Some(_) => None,
+                None if tag == "newline" || tag == "br" => Some("\n".to_owned()),
+                None => tag_code(&tag).map(|code| format!("§{}", code)),
+            };
+            if let Some(replacement) = replacement {
+                out.push_str(&replacement);
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Drop `§` codes.
+pub fn strip_codes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Start a background task polling the config file and icon for edits,
+/// until `shutdown`.
+pub fn start_reloader(status: Arc<ServerStatus>, interval_secs: u64, shutdown: crate::shutdown::Shutdown) {
+    if interval_secs == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let _running = shutdown.task();
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.triggered() => break,
+            }
+            if let Err(e) = status.reload_if_changed() {
+                tracing::warn!("Server list reload failed (keeping previous entry): {:#}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut out = PNG_SIGNATURE.to_vec();
+        out.extend_from_slice(&13u32.to_be_bytes());
+        out.extend_from_slice(b"IHDR");
+        out.extend_from_slice(&width.to_be_bytes());
+        out.extend_from_slice(&height.to_be_bytes());
+        out.extend_from_slice(&[8, 6, 0, 0, 0]);
+        out
+    }
+
+    #[test]
+    fn test_formatting_becomes_legacy_codes() {
+        assert_eq!(to_legacy("&aGreen &lbold"), "§aGreen §lbold");
+        assert_eq!(to_legacy("<red>Hot</red> <BOLD>news"), "§cHot§r §lnews");
+        assert_eq!(to_legacy("one<newline>two"), "one\ntwo");
+        assert_eq!(to_legacy("a & b <3 <unknown> &z"), "a & b <3 <unknown> &z");
+        assert_eq!(to_legacy("§6kept"), "§6kept");
+        assert_eq!(strip_codes(&to_legacy("<gold>Gold</gold> &lrush")), "Gold rush");
+    }
+
+    #[test]
+    fn test_favicon_needs_64px_png() {
+        let uri = favicon(&png(64, 64)).unwrap();
+        assert!(uri.starts_with("data:image/png;base64,iVBORw0KGgo"));
+        assert!(favicon(&png(128, 128)).unwrap_err().to_string().contains("128x128"));
+        assert!(favicon(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn test_reloads_config_and_icon_on_change() {
+        let dir = std::env::temp_dir().join("ultimate_mc_test_motd_reload");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_path = dir.join("server.yaml");
+        let icon = dir.join("server-icon.png");
+        let yaml = |motd: &str, max: u32| {
+            format!("network:\n  max_players: {}\nstatus:\n  motd: \"{}\"\n  icon: {:?}\n", max, motd, icon)
+        };
+        std::fs::write(&config_path, yaml("<red>One", 20)).unwrap();
+        let config: ServerConfig = serde_yaml::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+
+        let status = ServerStatus::new(&config, &config_path);
+        assert_eq!(status.current(), Status { motd: "§cOne".into(), max_players: 20, favicon: None });
+        assert!(!status.reload_if_changed().unwrap());
+
+        std::fs::write(&icon, png(64, 64)).unwrap();
+        // mtimes can be coarse; move them on so each edit is seen as one.
+        std::fs::write(&config_path, yaml("Two", 50)).unwrap();
+        let f = std::fs::File::options().write(true).open(&config_path).unwrap();
+        f.set_modified(SystemTime::now() + std::time::Duration::from_secs(5)).unwrap();
+        assert!(status.reload_if_changed().unwrap());
+        let now = status.current();
+        assert_eq!((now.motd.as_str(), now.max_players), ("Two", 50));
+        assert!(now.favicon.is_some());
+
+        // A broken edit keeps the previous entry.
+        std::fs::write(&config_path, "status: [").unwrap();
+        let f = std::fs::File::options().write(true).open(&config_path).unwrap();
+        f.set_modified(SystemTime::now() + std::time::Duration::from_secs(10)).unwrap();
+        assert!(status.reload_if_changed().is_err());
+        assert_eq!(status.current().motd, "Two");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
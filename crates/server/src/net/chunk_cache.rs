@@ -0,0 +1,107 @@
+//! Pre-encoded chunk packets for the spawn area.
+//!
+//! Every player logs in at spawn, so without a cache the first join after
+//! startup (and each one after) pays for serializing the whole spawn view.
+//! `warm_spawn_chunks` fills this cache before the listener opens; logins
+//! then copy finished `LevelChunkWithLight` bodies instead of re-encoding.
+//!
+//! Entries are only ever dropped, never refilled: a block write evicts its
+//! chunk and the eight around it (light spreads up to 15 blocks, so at
+//! most one chunk over), and replacing or evicting a chunk drops its entry.
+//! Light propagation lands after the `BlockSet` that caused it, so
+//! re-caching on a later miss could capture half-updated light — a chunk
+//! that has changed since startup is simply encoded fresh on every send.
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use ultimate_engine::world::World;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::observer::WorldObserver;
+use ultimate_engine::world::position::{BlockPos, ChunkPos};
+
+type Packets = DashMap<(i32, i32), Arc<[u8]>>;
+
+/// Encoded chunk packets (packet id onward, unframed), keyed by chunk.
+#[derive(Default)]
+pub struct ChunkCache {
+    packets: Arc<Packets>,
+}
+
+impl ChunkCache {
+    /// An empty cache that drops entries as `world` changes.
+    pub fn attach(world: &World) -> Self {
+        let cache = Self::default();
+        world.add_observer(Box::new(Invalidator(Arc::clone(&cache.packets))));
+        cache
+    }
+
+    pub fn get(&self, cx: i32, cz: i32) -> Option<Arc<[u8]>> {
+        self.packets.get(&(cx, cz)).map(|p| Arc::clone(&p))
+    }
+
+    pub fn insert(&self, cx: i32, cz: i32, packet: Vec<u8>) {
+        self.packets.insert((cx, cz), packet.into());
+    }
+
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+}
+
+/// Registered with the world; shares only the map so the world doesn't
+/// keep the cache's owner alive.
+struct Invalidator(Arc<Packets>);
+
+impl WorldObserver for Invalidator {
+    fn block_set(&self, pos: BlockPos, _old: BlockId, _new: BlockId, _tracked: bool) {
+        if self.0.is_empty() {
+            return;
+        }
+        for c in pos.chunk().chunks_in_radius(1) {
+            self.0.remove(&(c.x, c.z));
+        }
+    }
+
+    fn chunk_inserted(&self, pos: ChunkPos) {
+        self.0.remove(&(pos.x, pos.z));
+    }
+
+    fn chunk_removed(&self, pos: ChunkPos) {
+        self.0.remove(&(pos.x, pos.z));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultimate_engine::world::chunk::Chunk;
+
+    #[test]
+    fn test_writes_evict_neighbourhood() {
+        let world = World::new();
+        let cache = ChunkCache::attach(&world);
+        for cx in -2..=2 {
+            for cz in -2..=2 {
+                world.insert_chunk(ChunkPos::new(cx, cz), Chunk::new());
+                cache.insert(cx, cz, vec![cx as u8, cz as u8]);
+            }
+        }
+        assert_eq!(cache.len(), 25);
+
+        // A write in chunk (0, 0) can relight its neighbours.
+        world.set_block(BlockPos::new(5, 64, 5), BlockId::new(1));
+        assert_eq!(cache.len(), 16);
+        assert!(cache.get(1, -1).is_none());
+        assert_eq!(cache.get(2, 0).as_deref(), Some(&[2u8, 0][..]));
+
+        world.insert_chunk(ChunkPos::new(2, 0), Chunk::new());
+        world.remove_chunk(ChunkPos::new(-2, -2));
+        assert!(cache.get(2, 0).is_none() && cache.get(-2, -2).is_none());
+        assert_eq!(cache.len(), 14);
+    }
+}
@@ -0,0 +1,180 @@
+//! Which queued chunks to send next, and how many at a time.
+//!
+//! Deferred chunks used to stream out by Chebyshev distance alone, so a
+//! player sprinting forward waited on the ring behind them before the
+//! terrain ahead arrived. [`ViewOrder`] keeps the send queue sorted by
+//! [`view_score`]: distance, stretched for chunks to the side and more
+//! for those behind, from the rotation the connection tracks. The queue
+//! is re-sorted when the player changes chunk, turns into another
+//! [`SECTORS`] sector, or the queue changes under it.
+//!
+//! [`ChunkPacer`] replaces the fixed per-iteration chunk count. Chunk
+//! packets go straight to the socket, so a write that blocks means the
+//! kernel buffer is full and the client is behind: a pass that takes
+//! longer than [`SLOW_PASS`] halves the count, one under [`FAST_PASS`]
+//! raises it by one, between 1 and [`MAX_FACTOR`] times
+//! `network.chunks_per_iter`.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Rotation sectors; turning into another one re-sorts the queue.
+pub const SECTORS: u8 = 8;
+
+/// Half the angle, in degrees, counted as in view. Vanilla's default
+/// field of view is 70° vertically, wider horizontally.
+const VIEW_HALF_ANGLE: f32 = 60.0;
+
+/// Distance multipliers out of view: beside and behind the player.
+const SIDE_WEIGHT: f32 = 1.5;
+const BEHIND_WEIGHT: f32 = 2.5;
+
+/// Chunks this close go first whichever way the player faces: they are
+/// what the player stands on.
+const NEAR: f32 = 1.5;
+
+/// A drain pass slower than this backs off.
+pub const SLOW_PASS: Duration = Duration::from_millis(50);
+
+/// A drain pass faster than this speeds up.
+pub const FAST_PASS: Duration = Duration::from_millis(10);
+
+/// The most the pacer goes above the configured count.
+pub const MAX_FACTOR: usize = 4;
+
+/// Send order key for the chunk `(dx, dz)` away from the player's,
+/// facing `y_rot` (degrees, 0 = +Z, 90 = -X). Lower goes first.
+pub fn view_score(dx: i32, dz: i32, y_rot: f32) -> f32 {
+    let distance = ((dx * dx + dz * dz) as f32).sqrt();
+    if distance <= NEAR {
+        return distance;
+    }
+    let yaw = y_rot.to_radians();
+    let (look_x, look_z) = (-yaw.sin(), yaw.cos());
+    let cos = (dx as f32 * look_x + dz as f32 * look_z) / distance;
+    let weight = if cos >= VIEW_HALF_ANGLE.to_radians().cos() {
+        1.0
+    } else if cos >= 0.0 {
+        SIDE_WEIGHT
+    } else {
+        BEHIND_WEIGHT
+    };
+    distance * weight
+}
+
+/// The sector `y_rot` falls in.
+fn sector(y_rot: f32) -> u8 {
+    let per = 360.0 / SECTORS as f32;
+    ((y_rot.rem_euclid(360.0) + per / 2.0) / per) as u8 % SECTORS
+}
+
+/// Keeps a chunk send queue in [`view_score`] order.
+#[derive(Debug, Default)]
+pub struct ViewOrder {
+    /// Centre chunk and sector of the last sort, and the queue length
+    /// since: any other length means something was queued or dropped.
+    sorted: Option<((i32, i32), u8, usize)>,
+}
+
+impl ViewOrder {
+    /// Re-sort `queue` for a player in chunk `center` facing `y_rot`, if
+    /// anything changed since the last sort.
+    pub fn refresh(&mut self, queue: &mut VecDeque<(i32, i32)>, center: (i32, i32), y_rot: f32) {
+        let key = (center, sector(y_rot), queue.len());
+        if self.sorted == Some(key) {
+            return;
+        }
+        queue.make_contiguous().sort_by(|a, b| {
+            let score = |&(x, z): &(i32, i32)| view_score(x - center.0, z - center.1, y_rot);
+            score(a).total_cmp(&score(b))
+        });
+        self.sorted = Some(key);
+    }
+
+    /// Note that the front of `queue` was sent; what remains is in order.
+    pub fn sent(&mut self, queue: &VecDeque<(i32, i32)>) {
+        if let Some((_, _, len)) = &mut self.sorted {
+            *len = queue.len();
+        }
+    }
+}
+
+/// How many queued chunks to send per main-loop iteration.
+#[derive(Debug)]
+pub struct ChunkPacer {
+    current: usize,
+    max: usize,
+}
+
+impl ChunkPacer {
+    /// Start at `configured` chunks per pass (at least one).
+    pub fn new(configured: usize) -> Self {
+        let configured = configured.max(1);
+        Self { current: configured, max: configured * MAX_FACTOR }
+    }
+
+    pub fn per_pass(&self) -> usize {
+        self.current
+    }
+
+    /// Adjust after a pass that sent `chunks` in `took`.
+    pub fn record(&mut self, chunks: usize, took: Duration) {
+        if took >= SLOW_PASS {
+            self.current = (self.current / 2).max(1);
+        } else if took < FAST_PASS && chunks >= self.current {
+            self.current = (self.current + 1).min(self.max);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_in_view_go_first() {
+        // Facing +Z (south).
+        assert!(view_score(0, 4, 0.0) < view_score(4, 0, 0.0), "ahead before beside");
+        assert!(view_score(4, 0, 0.0) < view_score(0, -4, 0.0), "beside before behind");
+        assert!(view_score(0, -1, 0.0) < view_score(0, 3, 0.0), "adjacent chunks first regardless");
+        // Facing -X (west, 90°): the chunk at -X is now ahead.
+        assert!(view_score(-4, 0, 90.0) < view_score(0, 4, 90.0));
+    }
+
+    #[test]
+    fn test_view_order_resorts_on_turn_and_changes() {
+        let mut queue: VecDeque<(i32, i32)> = [(0, -5), (5, 0), (0, 5)].into_iter().collect();
+        let mut order = ViewOrder::default();
+        order.refresh(&mut queue, (0, 0), 0.0);
+        assert_eq!(queue.front(), Some(&(0, 5)));
+
+        order.refresh(&mut queue, (0, 0), 180.0);
+        assert_eq!(queue.front(), Some(&(0, -5)), "turning around re-sorts");
+
+        queue.pop_front();
+        order.sent(&queue);
+        queue.push_back((0, -3));
+        order.refresh(&mut queue, (0, 0), 180.0);
+        assert_eq!(queue.front(), Some(&(0, -3)), "new chunks are placed by score");
+        assert_eq!(sector(-10.0), sector(350.0));
+    }
+
+    #[test]
+    fn test_pacer_backs_off_under_backpressure() {
+        let mut pacer = ChunkPacer::new(4);
+        pacer.record(4, Duration::from_millis(1));
+        assert_eq!(pacer.per_pass(), 5);
+        pacer.record(2, Duration::from_millis(1));
+        assert_eq!(pacer.per_pass(), 5, "a short queue says nothing about the socket");
+        pacer.record(5, Duration::from_millis(80));
+        assert_eq!(pacer.per_pass(), 2);
+        for _ in 0..50 {
+            pacer.record(pacer.per_pass(), Duration::ZERO);
+        }
+        assert_eq!(pacer.per_pass(), 16);
+        for _ in 0..10 {
+            pacer.record(pacer.per_pass(), SLOW_PASS);
+        }
+        assert_eq!(pacer.per_pass(), 1);
+    }
+}
@@ -0,0 +1,189 @@
+//! Shared chunk serialization for every connection.
+//!
+//! Connections used to generate and encode each chunk they sent
+//! themselves, so a crowd around spawn encoded the same chunks once per
+//! player. Now they ask a [`ChunkSender`] for a batch's frames instead.
+//! Requests go to one of [`SHARDS`] tasks by region (32×32 chunks, as in
+//! region files). Each round, a shard takes every request queued since the
+//! last one and groups them by chunk. It then generates and encodes each
+//! chunk once on the blocking pool and hands the same frame to every
+//! requester. Spawn chunks still come straight from the [`ChunkCache`].
+//!
+//! A frame is shared only among requests made before its encode started.
+//! A connection claims a chunk, and so starts taking its bus updates,
+//! before asking for it, so anything written after the encode reaches it
+//! over the bus. A frame kept for later requests could miss what landed
+//! in between. Light is the worst case: the cache can't see it change.
+//! So finished frames are not kept.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{Result, anyhow};
+use tokio::sync::{mpsc, oneshot};
+use ultimate_engine::world::World;
+
+use crate::pools::Pools;
+use crate::worldgen::WorldGen;
+
+use super::chunk_cache::ChunkCache;
+
+/// Encoder tasks; a region always goes to the same one.
+pub const SHARDS: usize = 4;
+
+/// A `LevelChunkWithLight` packet, id onward, unframed.
+pub type Frame = Arc<[u8]>;
+
+type Reply = oneshot::Sender<Result<Frame, String>>;
+
+struct Request {
+    chunk: (i32, i32),
+    reply: Reply,
+}
+
+/// Hands out encoded chunks, encoding each once per round however many
+/// connections want it.
+pub struct ChunkSender {
+    shards: Vec<mpsc::UnboundedSender<Request>>,
+    cache: Arc<ChunkCache>,
+    encoded: Arc<AtomicU64>,
+    requested: AtomicU64,
+}
+
+impl ChunkSender {
+    /// Start the shard tasks. They stop when the sender is dropped.
+    pub fn start(world: Arc<World>, worldgen: Arc<dyn WorldGen>, cache: Arc<ChunkCache>, pools: Arc<Pools>) -> Self {
+        let encoded = Arc::new(AtomicU64::new(0));
+        let shards = (0..SHARDS)
+            .map(|_| {
+                let (tx, rx) = mpsc::unbounded_channel();
+                let shard = Shard {
+                    world: Arc::clone(&world),
+                    worldgen: Arc::clone(&worldgen),
+                    pools: Arc::clone(&pools),
+                    encoded: Arc::clone(&encoded),
+                };
+                tokio::spawn(shard.run(rx));
+                tx
+            })
+            .collect();
+        Self { shards, cache, encoded, requested: AtomicU64::new(0) }
+    }
+
+    /// The frames of `chunks`, in order, generating chunks as needed.
+    pub async fn frames(&self, chunks: &[(i32, i32)]) -> Result<Vec<Frame>> {
+        // Ask for every chunk before waiting on any, so a batch shares the
+        // shards' rounds with everyone else's.
+        let mut pending = Vec::with_capacity(chunks.len());
+        for &(cx, cz) in chunks {
+            if let Some(frame) = self.cache.get(cx, cz) {
+                pending.push(Err(frame));
+                continue;
+            }
+            self.requested.fetch_add(1, Ordering::Relaxed);
+            let (reply, rx) = oneshot::channel();
+            self.shards[shard_of(cx, cz)]
+                .send(Request { chunk: (cx, cz), reply })
+                .map_err(|_| anyhow!("chunk sender stopped"))?;
+            pending.push(Ok(rx));
+        }
+        let mut frames = Vec::with_capacity(pending.len());
+        for p in pending {
+            frames.push(match p {
+                Err(cached) => cached,
+                Ok(rx) => rx.await.map_err(|_| anyhow!("chunk sender stopped"))?.map_err(|e| anyhow!(e))?,
+            });
+        }
+        Ok(frames)
+    }
+
+    /// Chunks asked of the shards, and encodes they ran for them.
+    pub fn stats(&self) -> (u64, u64) {
+        (self.requested.load(Ordering::Relaxed), self.encoded.load(Ordering::Relaxed))
+    }
+}
+
+fn shard_of(cx: i32, cz: i32) -> usize {
+    let (rx, rz) = (cx >> 5, cz >> 5);
+    (rx.wrapping_mul(31).wrapping_add(rz)).rem_euclid(SHARDS as i32) as usize
+}
+
+struct Shard {
+    world: Arc<World>,
+    worldgen: Arc<dyn WorldGen>,
+    pools: Arc<Pools>,
+    encoded: Arc<AtomicU64>,
+}
+
+impl Shard {
+    async fn run(self, mut rx: mpsc::UnboundedReceiver<Request>) {
+        while let Some(first) = rx.recv().await {
+            let mut waiting: HashMap<(i32, i32), Vec<Reply>> = HashMap::new();
+            waiting.entry(first.chunk).or_default().push(first.reply);
+            while let Ok(request) = rx.try_recv() {
+                waiting.entry(request.chunk).or_default().push(request.reply);
+            }
+            let chunks: Vec<(i32, i32)> = waiting.keys().copied().collect();
+            let (world, worldgen) = (Arc::clone(&self.world), Arc::clone(&self.worldgen));
+            let frames = self.pools.run_blocking(move || encode_all(&world, &*worldgen, &chunks)).await;
+            self.encoded.fetch_add(frames.len() as u64, Ordering::Relaxed);
+            for (chunk, frame) in frames {
+                for reply in waiting.remove(&chunk).unwrap_or_default() {
+                    let _ = reply.send(frame.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Generate and encode `chunks`.
+fn encode_all(world: &World, worldgen: &dyn WorldGen, chunks: &[(i32, i32)]) -> Vec<((i32, i32), Result<Frame, String>)> {
+    chunks
+        .iter()
+        .map(|&(cx, cz)| {
+            worldgen.ensure_generated(world, cx, cz);
+            let frame = super::connection::encode_chunk(world, worldgen, cx, cz).map(Frame::from).map_err(|e| format!("{e:#}"));
+            ((cx, cz), frame)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PhysicsConfig;
+    use crate::dashboard::DashboardState;
+    use crate::worldgen::biome::Biome;
+    use crate::worldgen::pipeline::FlatPipeline;
+    use ultimate_engine::world::block::BlockId;
+    use ultimate_engine::world::position::ChunkPos;
+
+    #[tokio::test]
+    async fn test_crowd_shares_one_encode_per_chunk() {
+        let world = Arc::new(World::new());
+        let dashboard = Arc::new(DashboardState::new(Arc::clone(&world)));
+        let cfg = PhysicsConfig { cascade_threads: 1, blocking_threads: 1, ..Default::default() };
+        let pools = Arc::new(Pools::new(&cfg, dashboard).unwrap());
+        let worldgen: Arc<dyn WorldGen> =
+            Arc::new(FlatPipeline { min_y: -64, layers: vec![(BlockId::new(1), 4)], biome: Biome::Plains });
+        let cache = Arc::new(ChunkCache::attach(&world));
+        cache.insert(5, 5, vec![1, 2, 3]);
+        let sender = ChunkSender::start(Arc::clone(&world), worldgen, cache, pools);
+
+        // Three players asking at once: the requests all queue before the
+        // shards run (one runtime thread), so each chunk is encoded once.
+        let batch = [(0, 0), (1, 0), (5, 5)];
+        let (a, b, c) = tokio::join!(sender.frames(&batch), sender.frames(&batch), sender.frames(&batch[..1]));
+        let (a, b, c) = (a.unwrap(), b.unwrap(), c.unwrap());
+        assert!(Arc::ptr_eq(&a[0], &b[0]) && Arc::ptr_eq(&a[0], &c[0]));
+        assert_eq!(&*a[2], &[1, 2, 3][..], "spawn chunks come from the cache");
+        assert_eq!(sender.stats(), (5, 2));
+        assert!(world.has_chunk(ChunkPos::new(1, 0)));
+
+        // A later request encodes afresh.
+        let again = sender.frames(&[(0, 0)]).await.unwrap();
+        assert!(!Arc::ptr_eq(&again[0], &a[0]));
+        assert_eq!(sender.stats(), (6, 3));
+    }
+}
@@ -2,13 +2,15 @@
 //!
 //! Handshake -> Status | Login -> Configuration -> Play
 
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::Cursor;
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{anyhow, Result};
-use azalea_auth::game_profile::GameProfile;
+use azalea_auth::game_profile::{GameProfile, GameProfileProperties, ProfilePropertyValue};
+use azalea_brigadier::context::StringRange;
+use azalea_brigadier::suggestion::{Suggestion, Suggestions};
 use azalea_buf::AzaleaWrite;
 use azalea_chat::FormattedText;
 use azalea_core::bitset::BitSet;
@@ -25,19 +27,27 @@ use azalea_protocol::packets::game::{
     ClientboundPlayerInfoUpdate, ClientboundPlayerInfoRemove,
     ClientboundAddEntity, ClientboundRemoveEntities,
     ClientboundTeleportEntity, ClientboundRotateHead,
+    ClientboundMoveEntityPos, ClientboundMoveEntityPosRot,
     ClientboundForgetLevelChunk,
     ClientboundChunkBatchStart, ClientboundChunkBatchFinished,
-    ClientboundSystemChat,
-    ServerboundGamePacket,
+    ClientboundSystemChat, ClientboundPlayerChat, ClientboundDisconnect,
+    ClientboundCommandSuggestions, ClientboundSetHealth, ClientboundPlayerCombatKill,
+    ClientboundCustomPayload, ClientboundTabList, ClientboundContainerSetContent, ServerboundGamePacket,
+    ClientboundMerchantOffers, ClientboundOpenScreen,
+};
+use azalea_protocol::packets::game::c_player_chat::{
+    ChatTypeBound, FilterMask, PackedLastSeenMessages, PackedSignedMessageBody,
 };
 use azalea_protocol::packets::game::c_game_event::EventType;
+use azalea_protocol::packets::game::s_player_command::Action as PlayerCommandAction;
 use azalea_protocol::packets::game::c_player_info_update::{ActionEnumSet, PlayerInfoEntry};
-use azalea_core::delta::LpVec3;
+use azalea_core::delta::{LpVec3, PositionDelta8};
 use azalea_protocol::packets::status::c_status_response::SamplePlayer;
-use azalea_registry::builtin::EntityKind;
+use azalea_registry::builtin::{EntityKind, ItemKind};
 use azalea_protocol::packets::handshake::ServerboundHandshakePacket;
 use azalea_protocol::packets::login::{
-    ClientboundLoginFinished, ClientboundLoginPacket, ServerboundLoginPacket,
+    ClientboundCustomQuery, ClientboundLoginDisconnect, ClientboundLoginFinished,
+    ClientboundLoginPacket, ServerboundLoginPacket,
 };
 use azalea_protocol::packets::status::{
     ClientboundPongResponse, ClientboundStatusPacket, ClientboundStatusResponse,
@@ -48,24 +58,60 @@ use azalea_protocol::packets::Packet;
 use azalea_protocol::packets::common::CommonPlayerSpawnInfo;
 use azalea_protocol::packets::config::s_select_known_packs::KnownPack;
 use azalea_protocol::read::read_packet;
-use azalea_protocol::write::write_packet;
+use azalea_protocol::simdnbt::owned::{NbtCompound, NbtTag};
 use azalea_core::game_type::{GameMode, OptionalGameType};
 use azalea_core::position::Vec3;
 use azalea_entity::LookDirection;
 use azalea_registry::DataRegistry;
-use azalea_registry::data::DimensionKind;
+use azalea_registry::data::{ChatKind, DimensionKind};
 use azalea_registry::identifier::Identifier;
 use azalea_world::MinecraftEntityId;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
+use tracing::Instrument;
+use ultimate_engine::world::position::ChunkPos;
 use ultimate_engine::world::World;
 use uuid::Uuid;
 
-use crate::config::ServerConfig;
+use crate::access::AccessLists;
+use crate::channels::{self, PluginChannels};
+use crate::config::{ProxyConfig, ProxyMode, ServerConfig};
 use crate::dashboard::DashboardState;
+use crate::dashboard::metrics::{Metrics, Rejection};
+use crate::motd::{ServerStatus, Status};
 use crate::event_bus::{self};
+use crate::persistence::WorldStorage;
 use crate::player_registry::{PlayerEvent, PlayerInfo, PlayerRegistry};
+use crate::pools::Pools;
+use crate::skins::SkinResolver;
 use crate::worldgen::WorldGen;
+use crate::worldgen::biome::Biome;
+
+use super::chunk_cache::ChunkCache;
+use super::chunk_sender::ChunkSender;
+use super::listener::ServerContext;
+use super::chunk_order::{ChunkPacer, ViewOrder};
+use super::proxy;
+
+/// How often a keep-alive goes out, and how long the client has to answer
+/// one before it is disconnected (vanilla's client-side limit too).
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+const KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long the "Timed out" disconnect may take to write: a client that
+/// stopped answering may have stopped reading too, and must not hold the
+/// connection open.
+const DISCONNECT_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Whether the keep-alive still owed (`pending`: its id and when it went
+/// out) has gone unanswered for [`KEEPALIVE_TIMEOUT`] at `now`.
+fn keepalive_expired(pending: Option<(u64, std::time::Instant)>, now: std::time::Instant) -> bool {
+    pending.is_some_and(|(_, sent)| now.saturating_duration_since(sent) >= KEEPALIVE_TIMEOUT)
+}
+
+/// Where every player spawns (block X/Z; Y is the surface there).
+const SPAWN_X: f64 = 8.0;
+const SPAWN_Z: f64 = 8.0;
 
 /// Monotonic connection ID counter for identifying change sources.
 static NEXT_CONN_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
@@ -114,17 +160,51 @@ impl<W: tokio::io::AsyncWrite + Unpin> tokio::io::AsyncWrite for CountingWriter<
     }
 }
 
+/// One connection's socket halves and codec state, handed to each protocol
+/// phase in turn.
+struct Wire<'a, R, W> {
+    read: &'a mut R,
+    write: &'a mut W,
+    buf: &'a mut Cursor<Vec<u8>>,
+    compression: Option<u32>,
+    cipher_enc: &'a mut Option<azalea_crypto::Aes128CfbEnc>,
+    cipher_dec: &'a mut Option<azalea_crypto::Aes128CfbDec>,
+}
+
+impl<R, W> Wire<'_, R, W> {
+    /// A `Wire` borrowing this one, for the next phase.
+    fn reborrow(&mut self) -> Wire<'_, R, W> {
+        Wire {
+            read: self.read,
+            write: self.write,
+            buf: self.buf,
+            compression: self.compression,
+            cipher_enc: self.cipher_enc,
+            cipher_dec: self.cipher_dec,
+        }
+    }
+}
+
 /// Handle a single client connection through all protocol phases.
-pub async fn handle(
-    stream: TcpStream,
-    world: Arc<World>,
-    dashboard: Arc<DashboardState>,
-    spatial: Arc<crate::event_bus::SpatialBus>,
-    registry: Arc<PlayerRegistry>,
-    worldgen: Arc<dyn WorldGen>,
-    config: Arc<ServerConfig>,
-    physics: crate::physics::PhysicsHandle,
-) -> Result<()> {
+pub async fn handle(stream: TcpStream, ctx: ServerContext) -> Result<()> {
+    let ServerContext { registry, status, dashboard, .. } = &ctx;
+    // Pre-1.7 clients open a server-list ping with a bare 0xFE instead of
+    // a length-prefixed handshake; answer in their format rather than
+    // failing to parse it as one.
+    let mut first = [0u8; 2];
+    let peeked = stream.peek(&mut first).await?;
+    if peeked > 0 && first[0] == 0xFE {
+        let mut stream = stream;
+        let reply = legacy_ping_response(
+            peeked > 1 && first[1] == 0x01,
+            &status.current(),
+            registry.player_count(),
+        );
+        tokio::io::AsyncWriteExt::write_all(&mut stream, &reply).await?;
+        tracing::debug!("Answered legacy server list ping");
+        return Ok(());
+    }
+
     let (read, write) = stream.into_split();
     let mut read = read;
     let mut write = CountingWriter { inner: write };
@@ -134,10 +214,14 @@ pub async fn handle(
     let mut cipher_enc: Option<azalea_crypto::Aes128CfbEnc> = None;
     let mut cipher_dec: Option<azalea_crypto::Aes128CfbDec> = None;
     let compression: Option<u32> = None;
+    let mut wire = Wire {
+        read: &mut read, write: &mut write, buf: &mut buf,
+        compression, cipher_enc: &mut cipher_enc, cipher_dec: &mut cipher_dec,
+    };
 
     // ── Phase 1: Handshake ──────────────────────────────────────────────
     let handshake = read_packet::<ServerboundHandshakePacket, _>(
-        &mut read, &mut buf, compression, &mut cipher_dec,
+        wire.read, wire.buf, compression, wire.cipher_dec,
     ).await?;
 
     let intention = match handshake {
@@ -147,21 +231,36 @@ pub async fn handle(
     tracing::info!(
         "Handshake: protocol={}, host={}:{}, intention={:?}",
         intention.protocol_version,
-        intention.hostname,
+        // BungeeCord forwarding rides after the first NUL; keep it out of logs.
+        intention.hostname.split('\0').next().unwrap_or_default(),
         intention.port,
         intention.intention,
     );
 
     match intention.intention {
         ClientIntention::Status => {
-            handle_status(&mut read, &mut write, &mut buf, compression, &mut cipher_enc, &mut cipher_dec, &registry, &config.network).await?;
+            handle_status(wire.reborrow(), &ctx).await?;
         }
         ClientIntention::Login => {
-            let (name, uuid) = handle_login(&mut read, &mut write, &mut buf, compression, &mut cipher_enc, &mut cipher_dec).await?;
-            handle_configuration(&mut read, &mut write, &mut buf, compression, &mut cipher_enc, &mut cipher_dec).await?;
+            // Refuse other protocol versions up front with vanilla's
+            // message; letting them through fails much later in Play on
+            // whatever packet layout changed.
+            if let Some(reason) = version_mismatch(intention.protocol_version) {
+                let refusal: ClientboundLoginPacket = ClientboundLoginDisconnect {
+                    reason: FormattedText::from(reason.clone()),
+                }.into_variant();
+                write_packet(&refusal, wire.write, compression, wire.cipher_enc).await?;
+                tracing::info!(
+                    "Refused login from protocol {}: {}",
+                    intention.protocol_version, reason,
+                );
+                return Ok(());
+            }
+            let profile = handle_login(wire.reborrow(), &intention.hostname, &ctx).await?;
+            handle_configuration(wire.reborrow(), &profile, &ctx.registry.channels).await?;
             dashboard.metrics.player_joined();
             // handle_play registers/deregisters with the player registry internally.
-            let result = handle_play(&mut read, &mut write, &mut buf, compression, &mut cipher_enc, &mut cipher_dec, &world, &name, uuid, &dashboard, &spatial, &registry, &*worldgen, &config, &physics).await;
+            let result = handle_play(wire.reborrow(), &profile, &ctx).await;
             dashboard.metrics.player_left();
             result?;
         }
@@ -173,20 +272,49 @@ pub async fn handle(
     Ok(())
 }
 
+/// Vanilla's refusal for a client on another protocol version, or `None`
+/// when it matches ours.
+fn version_mismatch(client_protocol: i32) -> Option<String> {
+    use azalea_protocol::packets::{PROTOCOL_VERSION, VERSION_NAME};
+    match client_protocol.cmp(&PROTOCOL_VERSION) {
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Less => Some(format!("Outdated client! Please use {}", VERSION_NAME)),
+        std::cmp::Ordering::Greater => Some(format!("Outdated server! I'm still on {}", VERSION_NAME)),
+    }
+}
+
+/// Kick packet (0xFF) answering a legacy server-list ping. 1.4–1.6 clients
+/// (`FE 01`) get the `§1` field format, which carries our protocol so they
+/// show the server as incompatible; older clients (bare `FE`) get
+/// `motd§online§max`, where formatting codes would split the fields. The
+/// string is UTF-16BE, length-prefixed in chars.
+fn legacy_ping_response(v1_4: bool, status: &Status, online: usize) -> Vec<u8> {
+    use azalea_protocol::packets::{PROTOCOL_VERSION, VERSION_NAME};
+    let text = if v1_4 {
+        format!("§1\0{}\0{}\0{}\0{}\0{}", PROTOCOL_VERSION, VERSION_NAME, status.motd, online, status.max_players)
+    } else {
+        format!("{}§{}§{}", status.plain_motd(), online, status.max_players)
+    };
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let mut out = Vec::with_capacity(3 + units.len() * 2);
+    out.push(0xFF);
+    out.extend_from_slice(&(units.len() as u16).to_be_bytes());
+    for unit in units {
+        out.extend_from_slice(&unit.to_be_bytes());
+    }
+    out
+}
+
 // ── Status ──────────────────────────────────────────────────────────────
 
-async fn handle_status<R, W>(
-    read: &mut R, write: &mut W, buf: &mut Cursor<Vec<u8>>,
-    compression: Option<u32>,
-    cipher_enc: &mut Option<azalea_crypto::Aes128CfbEnc>,
-    cipher_dec: &mut Option<azalea_crypto::Aes128CfbDec>,
-    registry: &PlayerRegistry,
-    network: &crate::config::NetworkConfig,
-) -> Result<()>
+async fn handle_status<R, W>(wire: Wire<'_, R, W>, ctx: &ServerContext) -> Result<()>
 where
     R: AsyncRead + Unpin + Send + Sync,
     W: AsyncWrite + Unpin + Send,
 {
+    let Wire { read, write, buf, compression, cipher_enc, cipher_dec } = wire;
+    let registry: &PlayerRegistry = &ctx.registry;
+    let server_status: &ServerStatus = &ctx.status;
     // Client sends status request
     let packet = read_packet::<ServerboundStatusPacket, _>(read, buf, compression, cipher_dec).await?;
     tracing::debug!("Status request: {:?}", packet);
@@ -203,11 +331,12 @@ where
         .collect();
 
     // Respond with server status
+    let status = server_status.current();
     let response: ClientboundStatusPacket = ClientboundStatusResponse {
-        description: FormattedText::from("Ultimate Minecraft - Causal Graph Engine"),
-        favicon: None,
+        description: FormattedText::from(status.motd),
+        favicon: status.favicon,
         players: Players {
-            max: network.max_players as i32,
+            max: status.max_players as i32,
             online: online_players.len() as i32,
             sample,
         },
@@ -233,16 +362,18 @@ where
 
 // ── Login ───────────────────────────────────────────────────────────────
 
-async fn handle_login<R, W>(
-    read: &mut R, write: &mut W, buf: &mut Cursor<Vec<u8>>,
-    compression: Option<u32>,
-    cipher_enc: &mut Option<azalea_crypto::Aes128CfbEnc>,
-    cipher_dec: &mut Option<azalea_crypto::Aes128CfbDec>,
-) -> Result<(String, Uuid)>
+async fn handle_login<R, W>(wire: Wire<'_, R, W>, hostname: &str, ctx: &ServerContext) -> Result<GameProfile>
 where
     R: AsyncRead + Unpin + Send + Sync,
     W: AsyncWrite + Unpin + Send,
 {
+    let Wire { read, write, buf, compression, cipher_enc, cipher_dec } = wire;
+    let access: &AccessLists = &ctx.access;
+    let skins: &SkinResolver = &ctx.skins;
+    let proxy_config: &ProxyConfig = &ctx.config.proxy;
+    let registry: &PlayerRegistry = &ctx.registry;
+    let server_status: &ServerStatus = &ctx.status;
+    let metrics: &Metrics = &ctx.dashboard.metrics;
     // Client sends Login Start
     let packet = read_packet::<ServerboundLoginPacket, _>(read, buf, compression, cipher_dec).await?;
 
@@ -254,16 +385,84 @@ where
         other => return Err(anyhow!("Expected Login Start, got: {:?}", other)),
     };
 
+    // Behind a proxy the player was authenticated there; take their real
+    // identity from what it forwards, and refuse anyone who bypassed it.
+    let forwarded = match proxy_config.mode {
+        ProxyMode::None => None,
+        ProxyMode::Bungeecord => match proxy::parse_bungee_hostname(hostname) {
+            Ok(forwarded) => Some(forwarded),
+            Err(e) => {
+                refuse_login(write, compression, cipher_enc, proxy::BUNGEE_REQUIRED).await?;
+                return Err(anyhow!("{} refused at login: {:#}", name, e));
+            }
+        },
+        ProxyMode::Velocity => {
+            let query: ClientboundLoginPacket = ClientboundCustomQuery {
+                transaction_id: 0,
+                identifier: Identifier::new(proxy::VELOCITY_CHANNEL),
+                data: vec![proxy::VELOCITY_VERSION].into(),
+            }.into_variant();
+            write_packet(&query, write, compression, cipher_enc).await?;
+            let answer = match read_packet::<ServerboundLoginPacket, _>(read, buf, compression, cipher_dec).await? {
+                ServerboundLoginPacket::CustomQueryAnswer(answer) => answer.data,
+                other => return Err(anyhow!("Expected forwarding answer, got: {:?}", other)),
+            };
+            let verified = match answer {
+                _ if proxy_config.velocity_secret.is_empty() => {
+                    Err(anyhow!("proxy.velocity_secret is not set"))
+                }
+                Some(data) => proxy::verify_velocity(&data, proxy_config.velocity_secret.as_bytes()),
+                // Vanilla clients don't know the channel and answer empty.
+                None => Err(anyhow!("connected without Velocity")),
+            };
+            match verified {
+                Ok(forwarded) => Some(forwarded),
+                Err(e) => {
+                    refuse_login(write, compression, cipher_enc, proxy::VELOCITY_REQUIRED).await?;
+                    return Err(anyhow!("{} refused at login: {:#}", name, e));
+                }
+            }
+        }
+    };
+
     // Offline mode: skip encryption, generate UUID from name
-    let uuid = offline_uuid(&name);
+    let (uuid, name) = match &forwarded {
+        Some(forwarded) => {
+            tracing::info!("{} forwarded from {} as {}", name, forwarded.address, forwarded.uuid);
+            (forwarded.uuid, forwarded.name.clone().unwrap_or(name))
+        }
+        None => (offline_uuid(&name), name),
+    };
+
+    // Bans and whitelist are enforced before the client leaves Login.
+    if let Err(reason) = access.check_login(uuid, &name) {
+        refuse_login(write, compression, cipher_enc, &reason).await?;
+        return Err(anyhow!("{} refused at login: {}", name, reason));
+    }
+    // Counted before this player registers, so a burst of simultaneous
+    // logins can overshoot by the few still between here and Play.
+    let max_players = server_status.max_players();
+    if registry.player_count() >= max_players as usize {
+        refuse_login(write, compression, cipher_enc, SERVER_FULL).await?;
+        metrics.record_rejection(Rejection::Full);
+        return Err(anyhow!("{} refused at login: server full ({} players)", name, max_players));
+    }
 
-    // Send Login Success
+    // Send Login Success, carrying the skin so the client draws its own.
+    // A proxy forwards the skin Mojang gave it; otherwise resolve one.
+    let properties = match forwarded {
+        Some(forwarded) if !forwarded.properties.is_empty() => {
+            let mut props = GameProfileProperties::default();
+            for p in forwarded.properties {
+                props.map.insert(p.name, ProfilePropertyValue { value: p.value, signature: p.signature });
+            }
+            Arc::new(props)
+        }
+        _ => skins.properties(&name).await,
+    };
+    let profile = GameProfile { uuid, properties, name };
     let response: ClientboundLoginPacket = ClientboundLoginFinished {
-        game_profile: GameProfile {
-            uuid,
-            name: name.clone(),
-            properties: Default::default(),
-        },
+        game_profile: profile.clone(),
     }.into_variant();
     write_packet(&response, write, compression, cipher_enc).await?;
 
@@ -271,21 +470,44 @@ where
     let ack = read_packet::<ServerboundLoginPacket, _>(read, buf, compression, cipher_dec).await?;
     tracing::debug!("Login ack: {:?}", ack);
 
-    Ok((name, uuid))
+    Ok(profile)
 }
 
-// ── Configuration ───────────────────────────────────────────────────────
+/// Vanilla's refusal when `network.max_players` are online.
+const SERVER_FULL: &str = "The server is full!";
 
-async fn handle_configuration<R, W>(
-    read: &mut R, write: &mut W, buf: &mut Cursor<Vec<u8>>,
+/// Disconnect a client still in Login with `reason`.
+async fn refuse_login<W>(
+    write: &mut W,
     compression: Option<u32>,
     cipher_enc: &mut Option<azalea_crypto::Aes128CfbEnc>,
-    cipher_dec: &mut Option<azalea_crypto::Aes128CfbDec>,
+    reason: &str,
 ) -> Result<()>
+where
+    W: AsyncWrite + Unpin + Send,
+{
+    let refusal: ClientboundLoginPacket = ClientboundLoginDisconnect {
+        reason: FormattedText::from(reason.to_owned()),
+    }.into_variant();
+    write_packet(&refusal, write, compression, cipher_enc).await?;
+    Ok(())
+}
+
+// ── Configuration ───────────────────────────────────────────────────────
+
+async fn handle_configuration<R, W>(wire: Wire<'_, R, W>, profile: &GameProfile, channels: &PluginChannels) -> Result<()>
 where
     R: AsyncRead + Unpin + Send + Sync,
     W: AsyncWrite + Unpin + Send,
 {
+    let Wire { read, write, buf, compression, cipher_enc, cipher_dec } = wire;
+    // Our brand first, as vanilla does; the client shows it on F3.
+    let brand: ClientboundConfigPacket = azalea_protocol::packets::config::ClientboundCustomPayload {
+        identifier: Identifier::new(channels::BRAND),
+        data: channels::encode_string(channels::SERVER_BRAND).into(),
+    }.into_variant();
+    write_packet(&brand, write, compression, cipher_enc).await?;
+
     // Send Known Packs -- tell client we share the vanilla data pack
     let known_packs: ClientboundConfigPacket = ClientboundSelectKnownPacks {
         known_packs: vec![KnownPack {
@@ -305,6 +527,9 @@ where
                 tracing::debug!("Client known packs: {:?}", packet);
                 break;
             }
+            ServerboundConfigPacket::CustomPayload(payload) => {
+                config_payload(write, compression, cipher_enc, profile, channels, payload).await?;
+            }
             other => {
                 tracing::debug!("Config packet (pre-registry): {:?}", other);
             }
@@ -329,6 +554,9 @@ where
                 tracing::debug!("Client finished configuration");
                 break;
             }
+            ServerboundConfigPacket::CustomPayload(payload) => {
+                config_payload(write, compression, cipher_enc, profile, channels, payload).await?;
+            }
             other => {
                 tracing::debug!("Config packet (post-registry): {:?}", other);
             }
@@ -338,6 +566,45 @@ where
     Ok(())
 }
 
+/// Hand a Configuration-phase custom payload to its channel and send back
+/// any reply.
+async fn config_payload<W: AsyncWrite + Unpin + Send>(
+    write: &mut W,
+    compression: Option<u32>,
+    cipher: &mut Option<azalea_crypto::Aes128CfbEnc>,
+    profile: &GameProfile,
+    channels: &PluginChannels,
+    payload: &azalea_protocol::packets::config::ServerboundCustomPayload,
+) -> Result<()> {
+    let channel = payload.identifier.to_string();
+    let received = channels::Received { player: profile.uuid, name: &profile.name, channel: &channel, data: &payload.data[..] };
+    if let Some(reply) = channels.dispatch(&received) {
+        let packet: ClientboundConfigPacket = azalea_protocol::packets::config::ClientboundCustomPayload {
+            identifier: payload.identifier.clone(),
+            data: reply.into(),
+        }.into_variant();
+        write_packet(&packet, write, compression, cipher).await?;
+    }
+    Ok(())
+}
+
+/// `azalea_protocol::write::write_packet` inside a `write_packet` span,
+/// so profiles show time spent compressing, encrypting and flushing.
+async fn write_packet<P, W>(
+    packet: &P,
+    stream: &mut W,
+    compression: Option<u32>,
+    cipher: &mut Option<azalea_crypto::Aes128CfbEnc>,
+) -> std::io::Result<()>
+where
+    P: azalea_protocol::packets::ProtocolPacket + std::fmt::Debug,
+    W: AsyncWrite + Unpin + Send,
+{
+    azalea_protocol::write::write_packet(packet, stream, compression, cipher)
+        .instrument(tracing::trace_span!("write_packet"))
+        .await
+}
+
 /// Send all required registry data packets.
 async fn send_registries<W: AsyncWrite + Unpin + Send>(
     write: &mut W,
@@ -345,15 +612,21 @@ async fn send_registries<W: AsyncWrite + Unpin + Send>(
     cipher: &mut Option<azalea_crypto::Aes128CfbEnc>,
 ) -> Result<()> {
     // Each registry: (registry_id, list of entry identifiers)
-    // With Known Packs, we send None for NBT data -- client fills from local files.
+    // With Known Packs, we send None for NBT data -- client fills from local
+    // files. Biomes our worldgen assigns are the exception: they carry full
+    // NBT so their colours come from the server.
     let registries = registry_entries();
 
     for (registry_id, entries) in registries {
+        let is_biome = registry_id == "minecraft:worldgen/biome";
         let packet: ClientboundConfigPacket = ClientboundRegistryData {
             registry_id: Identifier::new(&registry_id),
             entries: entries
                 .into_iter()
-                .map(|name| (Identifier::new(&name), None))
+                .map(|name| {
+                    let nbt = if is_biome { Biome::from_name(&name).map(biome_nbt) } else { None };
+                    (Identifier::new(&name), nbt)
+                })
                 .collect(),
         }.into_variant();
         write_packet(&packet, write, compression, cipher).await?;
@@ -362,6 +635,30 @@ async fn send_registries<W: AsyncWrite + Unpin + Send>(
     Ok(())
 }
 
+/// Registry NBT for one of our biomes (network codec: climate at the top
+/// level, colours under `effects`).
+fn biome_nbt(biome: Biome) -> NbtCompound {
+    let props = biome.properties();
+    let mut effects = NbtCompound::new();
+    effects.insert("sky_color", props.sky_color());
+    effects.insert("fog_color", props.fog_color);
+    effects.insert("water_color", props.water_color);
+    effects.insert("water_fog_color", props.water_fog_color);
+    if let Some(c) = props.grass_color {
+        effects.insert("grass_color", c);
+    }
+    if let Some(c) = props.foliage_color {
+        effects.insert("foliage_color", c);
+    }
+
+    let mut nbt = NbtCompound::new();
+    nbt.insert("has_precipitation", props.has_precipitation);
+    nbt.insert("temperature", props.temperature);
+    nbt.insert("downfall", props.downfall);
+    nbt.insert("effects", NbtTag::Compound(effects));
+    nbt
+}
+
 /// Send UpdateTags packet. The timeline registry needs tags to bind its entries.
 async fn send_tags<W: AsyncWrite + Unpin + Send>(
     write: &mut W,
@@ -558,39 +855,87 @@ fn registry_entries() -> Vec<(String, Vec<String>)> {
         ("minecraft:zombie_nautilus_variant".into(), vec![
             "minecraft:temperate".into(), "minecraft:warm".into(),
         ]),
+        // Sorted like the vanilla data pack; `minecraft:chat` must stay
+        // first (CHAT_TYPE_CHAT).
+        ("minecraft:chat_type".into(), vec![
+            "minecraft:chat".into(), "minecraft:emote_command".into(),
+            "minecraft:msg_command_incoming".into(), "minecraft:msg_command_outgoing".into(),
+            "minecraft:say_command".into(), "minecraft:team_msg_command_incoming".into(),
+            "minecraft:team_msg_command_outgoing".into(),
+        ]),
         ("minecraft:timeline".into(), vec![
             "minecraft:day".into(), "minecraft:early_game".into(),
             "minecraft:moon".into(), "minecraft:villager_schedule".into(),
         ]),
+        // Enchantment components on items are ids into this registry.
+        ("minecraft:enchantment".into(), crate::items::ENCHANTMENTS.iter().map(|&e| e.into()).collect()),
     ]
 }
 
+/// Registry id of `minecraft:chat` in the `chat_type` registry we send.
+const CHAT_TYPE_CHAT: u32 = 0;
+
+/// An unsigned `minecraft:chat` message from `sender`. `global_index`
+/// counts every player message this recipient has been sent (the client
+/// disconnects on a gap); `index` is the sender's own message count.
+fn player_chat(
+    global_index: u32,
+    sender: uuid::Uuid,
+    index: u32,
+    name: &str,
+    body: PackedSignedMessageBody,
+) -> ClientboundPlayerChat {
+    ClientboundPlayerChat {
+        global_index,
+        sender,
+        index,
+        signature: None,
+        body,
+        unsigned_content: None,
+        filter_mask: FilterMask::PassThrough,
+        chat_type: ChatTypeBound {
+            chat_type: azalea_registry::Holder::Reference(ChatKind::new_raw(CHAT_TYPE_CHAT)),
+            name: FormattedText::from(name),
+            target_name: None,
+        },
+    }
+}
+
 // ── Play ────────────────────────────────────────────────────────────────
 
-async fn handle_play<R, W>(
-    read: &mut R, write: &mut W, buf: &mut Cursor<Vec<u8>>,
-    compression: Option<u32>,
-    cipher_enc: &mut Option<azalea_crypto::Aes128CfbEnc>,
-    cipher_dec: &mut Option<azalea_crypto::Aes128CfbDec>,
-    world: &World,
-    player_name: &str,
-    player_uuid: Uuid,
-    // Cascade metrics moved to the physics service in 6b-1; the slot stays
-    // for future per-connection dashboards (latency, packet rates).
-    _dashboard: &DashboardState,
-    spatial: &Arc<crate::event_bus::SpatialBus>,
-    registry: &PlayerRegistry,
-    worldgen: &dyn WorldGen,
-    config: &ServerConfig,
-    physics: &crate::physics::PhysicsHandle,
-) -> Result<()>
+async fn handle_play<R, W>(wire: Wire<'_, R, W>, profile: &GameProfile, ctx: &ServerContext) -> Result<()>
 where
     R: AsyncRead + Unpin + Send + Sync,
     W: AsyncWrite + Unpin + Send,
 {
+    let Wire { read, write, buf, compression, cipher_enc, cipher_dec } = wire;
+    let world = &ctx.world;
+    // Cascade metrics moved to the physics service in 6b-1; connections
+    // report what they filter out of the bus.
+    let dashboard: &DashboardState = &ctx.dashboard;
+    let spatial = &ctx.spatial;
+    let registry: &PlayerRegistry = &ctx.registry;
+    let config: &ServerConfig = &ctx.config;
+    let physics = &ctx.physics;
+    let storage = &ctx.storage;
+    let access: &AccessLists = &ctx.access;
+    let pools: &Pools = &ctx.pools;
+    let chunks: &ChunkSender = &ctx.chunks;
+    let projectiles: &crate::projectiles::Projectiles = &ctx.projectiles;
+    let border: &crate::worldborder::WorldBorder = &ctx.border;
+    let recipes: &crate::recipes::RecipeBook = &ctx.recipes;
+    let trades: &crate::trading::Trades = &ctx.trades;
+    let shutdown = &ctx.shutdown;
+    // Shared for generating off this task (teleport preloads).
+    let shared_worldgen = &ctx.worldgen;
+    let worldgen: &dyn WorldGen = &*ctx.worldgen;
+    let player_name = profile.name.as_str();
+    let player_uuid = profile.uuid;
     let entity_id = registry.allocate_entity_id();
-    let spawn_x = 8.0_f64;
-    let spawn_z = 8.0_f64;
+    let edit_session = std::sync::Mutex::new(crate::edit::EditSession::default());
+    let mut game_mode = crate::gamemode::DEFAULT;
+    let spawn_x = SPAWN_X;
+    let spawn_z = SPAWN_Z;
     // Pre-generate the spawn column so the surface is sampled from the
     // committed world, not just the noise function — this matters once
     // persistence layers modifications on top of the generator.
@@ -604,25 +949,20 @@ where
         levels: vec![Identifier::new("minecraft:overworld")],
         max_players: config.network.max_players as i32,
         chunk_radius: config.network.view_distance.max(0) as u32,
-        simulation_distance: config.network.simulation_distance.max(0) as u32,
+        // 0 (no limit) means as far as the client can see.
+        simulation_distance: match config.network.simulation_distance {
+            d if d > 0 => d as u32,
+            _ => config.network.view_distance.max(0) as u32,
+        },
         reduced_debug_info: false,
         show_death_screen: true,
         do_limited_crafting: false,
-        common: CommonPlayerSpawnInfo {
-            dimension_type: DimensionKind::new_raw(0), // overworld = 0
-            dimension: Identifier::new("minecraft:overworld"),
-            seed: 0,
-            game_type: GameMode::Creative,
-            previous_game_type: OptionalGameType(None),
-            is_debug: false,
-            is_flat: true,
-            last_death_location: None,
-            portal_cooldown: 0,
-            sea_level: 63,
-        },
+        common: spawn_info(game_mode),
         enforces_secure_chat: false,
     }.into_variant();
     write_packet(&login, write, compression, cipher_enc).await?;
+    let abilities: ClientboundGamePacket = crate::gamemode::abilities(game_mode).into_variant();
+    write_packet(&abilities, write, compression, cipher_enc).await?;
 
     // Send player position (teleport)
     let position: ClientboundGamePacket = ClientboundPlayerPosition {
@@ -644,6 +984,12 @@ where
     }.into_variant();
     write_packet(&position, write, compression, cipher_enc).await?;
 
+    // Command tree for this player's permission level; argument nodes
+    // ask the server for tab completions (`CommandSuggestion` below).
+    let tree: ClientboundGamePacket =
+        crate::commands::command_tree(access.permission_level(player_uuid)).into_variant();
+    write_packet(&tree, write, compression, cipher_enc).await?;
+
     // Wait for client to confirm teleport
     let tp_ack = read_packet::<ServerboundGamePacket, _>(read, buf, compression, cipher_dec).await?;
     tracing::debug!("Teleport ack: {:?}", tp_ack);
@@ -654,6 +1000,12 @@ where
         param: 0.0,
     }.into_variant();
     write_packet(&game_event, write, compression, cipher_enc).await?;
+    if registry.weather.is_raining() {
+        for event in crate::weather::packets(true) {
+            let pkt: ClientboundGamePacket = event.into_variant();
+            write_packet(&pkt, write, compression, cipher_enc).await?;
+        }
+    }
 
     // Set center chunk
     let chunk_x = (spawn_x as i32) >> 4;
@@ -695,27 +1047,31 @@ where
         .clone();
     let mut stream_permit = Arc::clone(&stream_sem).try_acquire_owned().ok();
 
+    let max_loaded = config.network.max_loaded_chunks;
+    let full_view = ((2 * view_distance + 1) * (2 * view_distance + 1)) as usize;
+    if max_loaded != 0 && full_view > max_loaded {
+        tracing::info!(
+            "{}: view distance {} spans {} chunks; capped to the nearest {}",
+            player_name, view_distance, full_view, max_loaded,
+        );
+    }
+
     let mut immediate: Vec<(i32, i32)> = Vec::new();
     let mut deferred: Vec<(i32, i32)> = Vec::new();
-    for cx in (chunk_x - view_distance)..=(chunk_x + view_distance) {
-        for cz in (chunk_z - view_distance)..=(chunk_z + view_distance) {
-            let inner = (cx - chunk_x).abs().max((cz - chunk_z).abs()) <= immediate_radius;
-            if inner && stream_permit.is_some() {
-                immediate.push((cx, cz));
-            } else {
-                deferred.push((cx, cz));
-            }
-            loaded_chunks.insert((cx, cz));
+    for (cx, cz) in desired_chunks(chunk_x, chunk_z, view_distance, max_loaded) {
+        let inner = ChunkPos::new(cx, cz).chebyshev(ChunkPos::new(chunk_x, chunk_z)) <= immediate_radius;
+        if inner && stream_permit.is_some() {
+            immediate.push((cx, cz));
+        } else {
+            deferred.push((cx, cz));
         }
+        loaded_chunks.insert((cx, cz));
     }
 
     if !immediate.is_empty() {
         let batch_start: ClientboundGamePacket = ClientboundChunkBatchStart.into_variant();
         write_packet(&batch_start, write, compression, cipher_enc).await?;
-        for &(cx, cz) in &immediate {
-            worldgen.ensure_generated(world, cx, cz);
-            send_chunk_from_world(write, compression, cipher_enc, world, &*worldgen, cx, cz).await?;
-        }
+        send_chunks(write, compression, cipher_enc, chunks, &immediate).await?;
         let batch_end: ClientboundGamePacket = ClientboundChunkBatchFinished {
             batch_size: immediate.len() as u32,
         }.into_variant();
@@ -723,8 +1079,8 @@ where
     }
 
     // Outer ring (everything, when admission deferred us) streams from the
-    // main loop, nearest first.
-    deferred.sort_by_key(|(cx, cz)| (cx - chunk_x).abs().max((cz - chunk_z).abs()));
+    // main loop, nearest first until it is re-sorted by view there.
+    deferred.sort_by_key(|&(cx, cz)| ChunkPos::new(cx, cz).chebyshev(ChunkPos::new(chunk_x, chunk_z)));
     chunk_send_queue.extend(deferred.iter());
 
     let mut current_chunk_x = chunk_x;
@@ -737,10 +1093,12 @@ where
     // to the shared physics service and acknowledged immediately. All
     // resulting world changes — including our own — come back through the
     // event bus as `ChangeSource::Physics` batches.
-    use azalea_block::BlockState;
-    use azalea_core::direction::Direction;
     use azalea_protocol::packets::game::{
-        ClientboundBlockUpdate, ClientboundBlockChangedAck,
+        ClientboundBlockUpdate, ClientboundBlockChangedAck, ClientboundContainerSetSlot,
+        ClientboundAnimate, ClientboundHurtAnimation, ClientboundRespawn, ClientboundSetEntityMotion,
+        c_animate::AnimationAction,
+        s_client_command::Action as ClientCommand,
+        s_interact::{ActionType, InteractionHand},
         s_player_action::Action,
     };
     use ultimate_engine::world::block::BlockId;
@@ -787,12 +1145,49 @@ where
         n => n,
     };
     let mut tab_listed: HashSet<uuid::Uuid> = HashSet::new();
+    // Player-chat counters: messages this client has been sent, and
+    // messages it has sent.
+    let mut chat_received: u32 = 0;
+    let mut chat_sent: u32 = 0;
     let mut spawned_entities: HashSet<i32> = HashSet::new();
+    // Position/rotation this client last saw for each remote entity, the
+    // base for relative move packets.
+    let mut entity_pos: HashMap<i32, SentPos> = HashMap::new();
+    // The same for mobs spawned on this client. They count against
+    // `spawn_cap` alongside players.
+    let mut mob_pos: HashMap<i32, SentPos> = HashMap::new();
+    // Villagers among them, by entity ID: UUID (which picks their trades)
+    // and position, for reach.
+    let mut villagers: HashMap<i32, (uuid::Uuid, f64, f64, f64)> = HashMap::new();
+    // Window IDs for trade windows, cycling 1-100 as vanilla's do.
+    let mut container_id: i32 = 0;
+    // When the player started drawing a bow, until it is let go.
+    let mut bow_drawn: Option<std::time::Instant> = None;
+    // Outside creative: the block being dug, when digging started, how
+    // many ticks it takes (`items::break_ticks`) and the crack stage
+    // others were last shown (`effects::crack_stage`).
+    let mut digging: Option<(ultimate_engine::world::position::BlockPos, std::time::Instant, u32, u8)> = None;
+    // Combat (see `combat`): our health, and when we last swung, for the
+    // attack cooldown.
+    let mut health = crate::combat::Health::default();
+    let mut last_attack: Option<std::time::Instant> = None;
+    // Interest radius (`network.entity_view_distance`): only players this
+    // close are spawned here and have their movement forwarded.
+    let entity_range = entity_range_blocks(config.network.entity_view_distance, view_distance);
+    // Chunk the entity view was last re-evaluated from.
+    let mut entity_view_chunk = (chunk_x, chunk_z);
 
     // Step 1: Tell this client about every player already online (plus
     // ourselves) in ONE multi-entry tab-list packet — a packet per player
     // made joining O(N) packets and a join storm O(N²) server-wide.
     let existing_players = registry.snapshot();
+    // Who is in spectator mode: their entities are hidden unless we are
+    // spectating too (see `gamemode::visible_to`).
+    let mut spectators: HashSet<uuid::Uuid> = existing_players
+        .iter()
+        .filter(|p| p.game_mode == GameMode::Spectator)
+        .map(|p| p.uuid)
+        .collect();
     let mut tab_entries: Vec<PlayerInfoEntry> = Vec::new();
     for p in existing_players.iter().take(tab_cap) {
         tab_listed.insert(p.uuid);
@@ -800,11 +1195,11 @@ where
             profile: GameProfile {
                 uuid: p.uuid,
                 name: p.name.clone(),
-                properties: Default::default(),
+                properties: Arc::clone(&p.properties),
             },
             listed: true,
-            latency: 0,
-            game_mode: GameMode::Creative,
+            latency: p.latency,
+            game_mode: p.game_mode,
             display_name: None,
             list_order: 0,
             update_hat: false,
@@ -812,14 +1207,10 @@ where
         });
     }
     tab_entries.push(PlayerInfoEntry {
-        profile: GameProfile {
-            uuid: player_uuid,
-            name: player_name.to_owned(),
-            properties: Default::default(),
-        },
+        profile: profile.clone(),
         listed: true,
         latency: 0,
-        game_mode: GameMode::Creative,
+        game_mode,
         display_name: None,
         list_order: 0,
         update_hat: false,
@@ -840,27 +1231,59 @@ where
     }.into_variant();
     write_packet(&info_packet, write, compression, cipher_enc).await?;
 
-    // Spawn each existing player's entity at their current position.
-    for p in existing_players.iter().take(spawn_cap) {
+    // Spawn each nearby player's entity at their current position.
+    let nearby = existing_players
+        .iter()
+        .filter(|p| in_entity_range(p.x - spawn_x, p.z - spawn_z, entity_range))
+        .filter(|p| crate::gamemode::visible_to(p.game_mode, game_mode));
+    for p in nearby.take(spawn_cap) {
         spawned_entities.insert(p.entity_id);
-        let spawn_packet: ClientboundGamePacket = ClientboundAddEntity {
-            id: MinecraftEntityId(p.entity_id),
-            uuid: p.uuid,
-            entity_type: EntityKind::Player,
-            position: Vec3 { x: p.x, y: p.y, z: p.z },
-            movement: LpVec3::Zero,
-            x_rot: degrees_to_byte_angle(p.x_rot),
-            y_rot: degrees_to_byte_angle(p.y_rot),
-            y_head_rot: degrees_to_byte_angle(p.y_rot),
-            data: 0,
-        }.into_variant();
+        entity_pos.insert(p.entity_id, SentPos::new(p.x, p.y, p.z, p.y_rot, p.x_rot));
+        let spawn_packet = add_player_entity(p.entity_id, p.uuid, p.x, p.y, p.z, p.y_rot, p.x_rot);
         write_packet(&spawn_packet, write, compression, cipher_enc).await?;
+        for pkt in p.appearance.spawn_packets(p.entity_id) {
+            write_packet(&pkt, write, compression, cipher_enc).await?;
+        }
     }
     // Without this, the snapshot (up to one PlayerInfo per online player)
     // lives in this stack frame for the connection's whole lifetime —
     // ~0.5 MB × 10k connections was gigabytes in the 10k load test.
     drop(existing_players);
 
+    // Objectives, scores and display slots; changes follow on `score_rx`.
+    let (score_pkts, mut score_rx) = storage.scoreboard.join();
+    for pkt in &score_pkts {
+        write_packet(pkt, write, compression, cipher_enc).await?;
+    }
+    drop(score_pkts);
+    // Boss bars meant for us; likewise followed by `bar_rx`.
+    let (bar_pkts, mut bar_rx) = registry.boss_bars.join(player_uuid);
+    for pkt in &bar_pkts {
+        write_packet(pkt, write, compression, cipher_enc).await?;
+    }
+    drop(bar_pkts);
+    // The tab list header and footer; refreshes follow on `tab_rx`.
+    let (tab_current, mut tab_rx) = registry.tab_list.join();
+    if let Some((header, footer)) = tab_current {
+        let pkt: ClientboundGamePacket = ClientboundTabList {
+            header: FormattedText::from(header),
+            footer: FormattedText::from(footer),
+        }.into_variant();
+        write_packet(&pkt, write, compression, cipher_enc).await?;
+    }
+    // Announce our plugin channels; pushes follow on `channel_rx`.
+    let (channel_names, mut channel_rx) = registry.channels.join();
+    if !channel_names.is_empty() {
+        let pkt: ClientboundGamePacket = ClientboundCustomPayload {
+            identifier: Identifier::new(channels::REGISTER),
+            data: channel_names.into(),
+        }.into_variant();
+        write_packet(&pkt, write, compression, cipher_enc).await?;
+    }
+    // Likewise the world border, and its moves on `border_rx`.
+    let (border_pkt, mut border_rx) = border.join();
+    write_packet(&border_pkt, write, compression, cipher_enc).await?;
+
     // Step 3: Register in the shared registry -- this broadcasts PlayerEvent::Joined
     // to all other connections so they can send the tab-list + entity spawn packets.
     registry.register(PlayerInfo {
@@ -868,12 +1291,16 @@ where
         entity_id,
         uuid: player_uuid,
         name: player_name.to_owned(),
+        properties: Arc::clone(&profile.properties),
         x: spawn_x,
         y: spawn_y,
         z: spawn_z,
         y_rot: 0.0,
         x_rot: 0.0,
         on_ground: false,
+        game_mode,
+        latency: 0,
+        appearance: Default::default(),
     });
 
     // Track player position and rotation for movement relaying.
@@ -882,22 +1309,94 @@ where
     let mut player_z = spawn_z;
     let mut player_y_rot: f32 = 0.0;
     let mut player_x_rot: f32 = 0.0;
-    // Track hotbar contents and selected slot for creative placement.
-    use azalea_inventory::ItemStack;
-    let mut hotbar: [BlockState; 9] = [BlockState::AIR; 9];
-    let mut selected_slot: usize = 0;
+    // Items picked from the creative menu and the selected hotbar slot,
+    // as the player left them last time; saved again however the
+    // connection ends. A villager's trade window open on top (see
+    // `trading`) is closed first, so its payment isn't lost.
+    struct InventoryGuard<'a> {
+        inventory: crate::inventory::Inventory,
+        merchant: Option<crate::trading::Merchant>,
+        recipes: &'a crate::recipes::RecipeBook,
+        world_dir: &'a std::path::Path,
+        uuid: uuid::Uuid,
+    }
+    impl std::ops::Deref for InventoryGuard<'_> {
+        type Target = crate::inventory::Inventory;
+        fn deref(&self) -> &Self::Target {
+            &self.inventory
+        }
+    }
+    impl std::ops::DerefMut for InventoryGuard<'_> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.inventory
+        }
+    }
+    impl Drop for InventoryGuard<'_> {
+        fn drop(&mut self) {
+            // Grid, payment and cursor items go back into the inventory
+            // first, as when the window closes.
+            match self.merchant.take() {
+                Some(merchant) => merchant.close(self.recipes, &mut self.inventory),
+                None => self.inventory.close(self.recipes),
+            }
+            let saved = self.inventory.save();
+            let result = crate::playerdata::load(self.world_dir, self.uuid).and_then(|mut data| {
+                data.inventory = saved;
+                crate::playerdata::save(self.world_dir, self.uuid, &data)
+            });
+            if let Err(e) = result {
+                tracing::warn!("saving inventory of {}: {:#}", self.uuid, e);
+            }
+        }
+    }
+    let mut inventory = InventoryGuard {
+        inventory: crate::inventory::Inventory::default(),
+        merchant: None,
+        recipes,
+        world_dir: &storage.dir,
+        uuid: player_uuid,
+    };
+    let saved = {
+        let dir = storage.dir.clone();
+        tokio::task::spawn_blocking(move || crate::playerdata::load(&dir, player_uuid)).await?
+    };
+    match saved {
+        Ok(data) if !data.inventory.is_empty() => {
+            for lost in inventory.restore(recipes, &data.inventory) {
+                tracing::warn!("{}'s inventory: dropped {}", player_name, lost);
+            }
+            write_packet(&window_content(&inventory), write, compression, cipher_enc).await?;
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("loading {}'s player data: {:#}", player_name, e),
+    }
+    // Others see what we hold (see `appearance`); kept up to date after
+    // every packet, whichever changed it.
+    let mut shown_held = inventory.held().clone();
+    registry.update_appearance(conn_id, |look| look.main_hand = shown_held.clone());
+    if config.crafting.recipe_book && !recipes.is_empty() {
+        for packet in recipe_book_packets(recipes) {
+            write_packet(&packet, write, compression, cipher_enc).await?;
+        }
+    }
 
     // ── Main loop: keep-alive + handle incoming packets + bus ────────────
-    let mut keepalive_timer = tokio::time::interval(Duration::from_secs(15));
+    let mut keepalive_timer = tokio::time::interval(KEEPALIVE_INTERVAL);
+    // Once a second: hurt the player, or push them back, for being
+    // outside the world border.
+    let mut border_timer = tokio::time::interval(std::time::Duration::from_secs(1));
     let mut keepalive_id: u64 = 0;
+    // The keep-alive the client still owes us, and when it was sent.
+    let mut keepalive_pending: Option<(u64, std::time::Instant)> = None;
     // Diagnostics: a keep-alive gap above 25s means this client was one
     // missed packet from a vanilla 30s timeout — log who and how long.
     let mut last_keepalive_sent: Option<std::time::Instant> = None;
     let mut stream_wait_started: Option<std::time::Instant> = None;
 
-    // Max chunks to send per loop iteration. Keeps the loop responsive while
-    // still making rapid progress on the queue.
-    let chunks_per_iter: usize = config.network.chunks_per_iter;
+    // Chunks to send per loop iteration, adapting to how fast the socket
+    // takes them; the queue is kept in view order (see `chunk_order`).
+    let mut chunk_pacer = ChunkPacer::new(config.network.chunks_per_iter);
+    let mut view_order = ViewOrder::default();
 
     // Track chunks physically sent to the client. Deferred chunks are added to
     // `loaded_chunks` optimistically before being sent, so this set lets us
@@ -916,8 +1415,9 @@ where
         // Wrap each drain pass in a ChunkBatchStart/Finished pair so the
         // client renders the chunks (1.20+ requirement).
         if stream_permit.is_some() {
+            view_order.refresh(&mut chunk_send_queue, (current_chunk_x, current_chunk_z), player_y_rot);
             let mut to_send: Vec<(i32, i32)> = Vec::new();
-            while to_send.len() < chunks_per_iter {
+            while to_send.len() < chunk_pacer.per_pass() {
                 let Some((cx, cz)) = chunk_send_queue.pop_front() else { break };
                 if !loaded_chunks.contains(&(cx, cz)) {
                     sent_to_client.remove(&(cx, cz));
@@ -925,21 +1425,21 @@ where
                 }
                 to_send.push((cx, cz));
             }
+            view_order.sent(&chunk_send_queue);
 
             if !to_send.is_empty() {
+                let started = std::time::Instant::now();
                 let batch_start: ClientboundGamePacket = ClientboundChunkBatchStart.into_variant();
                 write_packet(&batch_start, write, compression, cipher_enc).await?;
 
-                for &(cx, cz) in &to_send {
-                    worldgen.ensure_generated(world, cx, cz);
-                    send_chunk_from_world(write, compression, cipher_enc, world, &*worldgen, cx, cz).await?;
-                    sent_to_client.insert((cx, cz));
-                }
+                send_chunks(write, compression, cipher_enc, chunks, &to_send).await?;
+                sent_to_client.extend(to_send.iter().copied());
 
                 let batch_end: ClientboundGamePacket = ClientboundChunkBatchFinished {
                     batch_size: to_send.len() as u32,
                 }.into_variant();
                 write_packet(&batch_end, write, compression, cipher_enc).await?;
+                chunk_pacer.record(to_send.len(), started.elapsed());
             }
         }
 
@@ -953,6 +1453,56 @@ where
             }
         }
 
+        // ── Entity view: after crossing a chunk, spawn players we walked
+        // up to and despawn the ones we left behind. Moves only cover
+        // players who move; this catches the ones standing still.
+        if entity_view_chunk != (current_chunk_x, current_chunk_z) {
+            entity_view_chunk = (current_chunk_x, current_chunk_z);
+            let mut nearby = registry.players_within(player_x, player_z, entity_range);
+            nearby.retain(|p| crate::gamemode::visible_to(p.game_mode, game_mode));
+            let nearby_eids: HashSet<i32> = nearby.iter().map(|p| p.entity_id).collect();
+            let mut gone: Vec<MinecraftEntityId> = spawned_entities
+                .extract_if(|eid| !nearby_eids.contains(eid))
+                .map(|eid| {
+                    entity_pos.remove(&eid);
+                    MinecraftEntityId(eid)
+                })
+                .collect();
+            // Mobs that come into range announce themselves; ones left
+            // behind are dropped here.
+            gone.extend(
+                mob_pos
+                    .extract_if(|_, p| {
+                        let (x, z) = p.block_xz();
+                        !in_entity_range(x - player_x, z - player_z, entity_range)
+                    })
+                    .map(|(eid, _)| MinecraftEntityId(eid)),
+            );
+            if !gone.is_empty() {
+                let remove_pkt: ClientboundGamePacket = ClientboundRemoveEntities {
+                    entity_ids: gone,
+                }.into_variant();
+                write_packet(&remove_pkt, write, compression, cipher_enc).await?;
+            }
+            for p in &nearby {
+                // Only players this client has a tab entry for can be
+                // drawn; the rest arrive with their join event.
+                if p.conn_id == conn_id
+                    || !tab_listed.contains(&p.uuid)
+                    || spawned_entities.len() >= spawn_cap
+                    || !spawned_entities.insert(p.entity_id)
+                {
+                    continue;
+                }
+                entity_pos.insert(p.entity_id, SentPos::new(p.x, p.y, p.z, p.y_rot, p.x_rot));
+                let spawn_pkt = add_player_entity(p.entity_id, p.uuid, p.x, p.y, p.z, p.y_rot, p.x_rot);
+                write_packet(&spawn_pkt, write, compression, cipher_enc).await?;
+                for pkt in p.appearance.spawn_packets(p.entity_id) {
+                    write_packet(&pkt, write, compression, cipher_enc).await?;
+                }
+            }
+        }
+
         // Done streaming: hand the permit to the next waiting connection.
         if chunk_send_queue.is_empty() {
             stream_permit = None;
@@ -978,8 +1528,27 @@ where
                     }
                 }
             }
-            _ = keepalive_timer.tick() => {
-                let now = std::time::Instant::now();
+            tick = keepalive_timer.tick() => {
+                // The scheduled instant, not the wake-up time, so a pending
+                // keep-alive ages exactly one interval per tick.
+                let now = tick.into_std();
+                if let Some((id, _)) = keepalive_pending {
+                    if keepalive_expired(keepalive_pending, now) {
+                        tracing::info!("{} timed out (keep-alive {} unanswered)", player_name, id);
+                        let disconnect: ClientboundGamePacket = ClientboundDisconnect {
+                            reason: FormattedText::from("Timed out"),
+                        }.into_variant();
+                        let goodbye = write_packet(&disconnect, write, compression, cipher_enc);
+                        match tokio::time::timeout(DISCONNECT_WRITE_TIMEOUT, goodbye).await {
+                            Ok(Ok(())) => {}
+                            Ok(Err(e)) => tracing::debug!("{} timeout disconnect failed: {}", player_name, e),
+                            Err(_) => tracing::debug!("{} timeout disconnect not written in time", player_name),
+                        }
+                        break;
+                    }
+                    // Still waiting on the last one; don't stack another.
+                    continue;
+                }
                 if let Some(prev) = last_keepalive_sent {
                     let gap = now.duration_since(prev);
                     if gap > Duration::from_secs(25) {
@@ -989,6 +1558,7 @@ where
                 }
                 last_keepalive_sent = Some(now);
                 keepalive_id += 1;
+                keepalive_pending = Some((keepalive_id, now));
                 let ka: ClientboundGamePacket = azalea_protocol::packets::game::ClientboundKeepAlive {
                     id: keepalive_id,
                 }.into_variant();
@@ -1000,23 +1570,114 @@ where
                         match packet {
                             // ── Block breaking (creative = instant) ──────
                             ServerboundGamePacket::PlayerAction(action) => {
-                                if action.action == Action::StartDestroyBlock {
+                                let creative = game_mode == GameMode::Creative;
+                                if action.action == Action::StartDestroyBlock
+                                    || (action.action == Action::StopDestroyBlock && !creative)
+                                {
                                     let pos = action.pos;
                                     let epos = ultimate_engine::world::position::BlockPos::new(
                                         pos.x as i64, pos.y as i64, pos.z as i64,
                                     );
+                                    let protected = protected_region(storage, access, config, player_uuid, epos);
+                                    if !crate::gamemode::may_edit_blocks(game_mode)
+                                        || !border.contains_block(epos)
+                                        || protected.is_some()
+                                    {
+                                        // Undo the client's prediction: ack,
+                                        // then restore what's really there.
+                                        let ack: ClientboundGamePacket = ClientboundBlockChangedAck {
+                                            seq: action.seq,
+                                        }.into_variant();
+                                        write_packet(&ack, write, compression, cipher_enc).await?;
+                                        let restore: ClientboundGamePacket = ClientboundBlockUpdate {
+                                            pos,
+                                            block_state: engine_block_to_mc(world.get_block(epos)),
+                                        }.into_variant();
+                                        write_packet(&restore, write, compression, cipher_enc).await?;
+                                        if let Some(region) = protected {
+                                            send_protected_notice(write, compression, cipher_enc, &region).await?;
+                                        }
+                                        continue;
+                                    }
 
                                     // Submit to the shared physics service; the
                                     // cascade runs off this task. `old` is our
                                     // observation — physics' stale-precondition
                                     // guard drops the action if another event
                                     // got to the cell first.
+                                    let old = world.get_block(epos);
+                                    if !creative {
+                                        // Digging takes the block's time with
+                                        // the held tool; the client says when
+                                        // it's done, which must be at least
+                                        // 70% of that (vanilla's allowance).
+                                        let ticks = crate::items::break_ticks(engine_block_to_mc(old), inventory.held());
+                                        let done = match (action.action, ticks) {
+                                            (_, None) => false,
+                                            (Action::StartDestroyBlock, Some(0)) => true,
+                                            (Action::StartDestroyBlock, Some(ticks)) => {
+                                                clear_cracks(spatial, conn_id, entity_id, digging.take());
+                                                digging = Some((epos, std::time::Instant::now(), ticks, 0));
+                                                spatial.publish_effects(
+                                                    event_bus::ChangeSource::Player(conn_id),
+                                                    crate::effects::digging(entity_id, epos, old, 0),
+                                                );
+                                                false
+                                            }
+                                            _ => {
+                                                let dug = digging.take();
+                                                clear_cracks(spatial, conn_id, entity_id, dug);
+                                                dug.is_some_and(|(at, since, ticks, _)| {
+                                                    at == epos && since.elapsed() >= Duration::from_millis(35 * ticks as u64)
+                                                })
+                                            }
+                                        };
+                                        if !done {
+                                            let ack: ClientboundGamePacket = ClientboundBlockChangedAck {
+                                                seq: action.seq,
+                                            }.into_variant();
+                                            write_packet(&ack, write, compression, cipher_enc).await?;
+                                            if action.action == Action::StopDestroyBlock {
+                                                let restore: ClientboundGamePacket = ClientboundBlockUpdate {
+                                                    pos,
+                                                    block_state: engine_block_to_mc(old),
+                                                }.into_variant();
+                                                write_packet(&restore, write, compression, cipher_enc).await?;
+                                            }
+                                            continue;
+                                        }
+                                    }
                                     physics.submit_action(BlockAction {
                                         pos: epos,
-                                        old: world.get_block(epos),
+                                        old,
                                         new: BlockId::AIR,
                                         update_stairs: true,
                                     });
+                                    spatial.publish_effects(
+                                        event_bus::ChangeSource::Player(conn_id),
+                                        crate::effects::block_broken(epos, old),
+                                    );
+                                    storage.scoreboard.record(crate::scoreboard::Criterion::BlocksBroken, player_name, 1);
+                                    registry.audit.record(player_uuid, player_name, crate::audit::Action::block(epos, old, BlockId::AIR));
+                                    if !creative {
+                                        // One use of the tool. Unbreaking's
+                                        // odds only need a roll nobody can
+                                        // predict; the clock's nanoseconds do.
+                                        let roll = std::time::SystemTime::now()
+                                            .duration_since(std::time::UNIX_EPOCH)
+                                            .map_or(0, |d| d.subsec_nanos() as u64);
+                                        let worn = crate::items::worn(inventory.held(), roll);
+                                        if worn != *inventory.held() {
+                                            let slot = inventory.set_held(worn);
+                                            let set_slot: ClientboundGamePacket = ClientboundContainerSetSlot {
+                                                container_id: 0,
+                                                state_id: inventory.state_id(),
+                                                slot: slot as u16,
+                                                item_stack: inventory.slot(slot).clone(),
+                                            }.into_variant();
+                                            write_packet(&set_slot, write, compression, cipher_enc).await?;
+                                        }
+                                    }
 
                                     // Acknowledge the sequence immediately; the
                                     // authoritative block updates arrive via the
@@ -1025,30 +1686,97 @@ where
                                         seq: action.seq,
                                     }.into_variant();
                                     write_packet(&ack, write, compression, cipher_enc).await?;
+                                } else if action.action == Action::AbortDestroyBlock {
+                                    clear_cracks(spatial, conn_id, entity_id, digging.take());
+                                } else if action.action == Action::ReleaseUseItem
+                                    && let Some(drawn) = bow_drawn.take()
+                                    && inventory.held().kind() == ItemKind::Bow
+                                    && let Some(speed) = crate::projectiles::bow_speed(drawn.elapsed())
+                                {
+                                    // Creative bows need no arrows.
+                                    let eye = [player_x, player_y + crate::item_use::EYE_HEIGHT, player_z];
+                                    projectiles.launch(
+                                        crate::projectiles::ProjectileKind::Arrow,
+                                        eye, player_y_rot, player_x_rot, speed,
+                                    );
                                 }
                             }
 
                             // ── Block placing ───────────────────────────
                             ServerboundGamePacket::UseItemOn(place) => {
+                                if !crate::gamemode::may_edit_blocks(game_mode) {
+                                    // Adventure and spectator clients don't
+                                    // predict placement; just acknowledge.
+                                    let ack: ClientboundGamePacket = ClientboundBlockChangedAck {
+                                        seq: place.seq,
+                                    }.into_variant();
+                                    write_packet(&ack, write, compression, cipher_enc).await?;
+                                    continue;
+                                }
                                 let hit = &place.block_hit;
-                                // Calculate target position (adjacent to clicked face)
-                                let target = match hit.direction {
-                                    Direction::Down  => azalea_core::position::BlockPos::new(hit.block_pos.x, hit.block_pos.y - 1, hit.block_pos.z),
-                                    Direction::Up    => azalea_core::position::BlockPos::new(hit.block_pos.x, hit.block_pos.y + 1, hit.block_pos.z),
-                                    Direction::North => azalea_core::position::BlockPos::new(hit.block_pos.x, hit.block_pos.y, hit.block_pos.z - 1),
-                                    Direction::South => azalea_core::position::BlockPos::new(hit.block_pos.x, hit.block_pos.y, hit.block_pos.z + 1),
-                                    Direction::West  => azalea_core::position::BlockPos::new(hit.block_pos.x - 1, hit.block_pos.y, hit.block_pos.z),
-                                    Direction::East  => azalea_core::position::BlockPos::new(hit.block_pos.x + 1, hit.block_pos.y, hit.block_pos.z),
-                                };
 
-                                let epos = ultimate_engine::world::position::BlockPos::new(
-                                    target.x as i64, target.y as i64, target.z as i64,
+                                // Items that act on the clicked block itself
+                                // (buckets on cauldrons) change it in place.
+                                let clicked = ultimate_engine::world::position::BlockPos::new(
+                                    hit.block_pos.x as i64, hit.block_pos.y as i64, hit.block_pos.z as i64,
                                 );
+                                if !border.contains_block(clicked) {
+                                    let ack: ClientboundGamePacket = ClientboundBlockChangedAck {
+                                        seq: place.seq,
+                                    }.into_variant();
+                                    write_packet(&ack, write, compression, cipher_enc).await?;
+                                    continue;
+                                }
+                                let clicked_block = world.get_block(clicked);
+                                if let Some(new) = crate::item_use::use_on(inventory.held(), clicked_block) {
+                                    if let Some(region) = protected_region(storage, access, config, player_uuid, clicked) {
+                                        let ack: ClientboundGamePacket = ClientboundBlockChangedAck {
+                                            seq: place.seq,
+                                        }.into_variant();
+                                        write_packet(&ack, write, compression, cipher_enc).await?;
+                                        send_protected_notice(write, compression, cipher_enc, &region).await?;
+                                        continue;
+                                    }
+                                    physics.submit_action(BlockAction {
+                                        pos: clicked,
+                                        old: clicked_block,
+                                        new,
+                                        update_stairs: false,
+                                    });
+                                    registry.audit.record(player_uuid, player_name, crate::audit::Action::block(clicked, clicked_block, new));
+                                    let ack: ClientboundGamePacket = ClientboundBlockChangedAck {
+                                        seq: place.seq,
+                                    }.into_variant();
+                                    write_packet(&ack, write, compression, cipher_enc).await?;
+                                    continue;
+                                }
+
+                                // The block adjacent to the clicked face.
+                                let epos = clicked.relative(crate::placement::engine_direction(hit.direction));
+                                let protected = protected_region(storage, access, config, player_uuid, epos);
+                                if !border.contains_block(epos) || protected.is_some() {
+                                    // Clicked just inside the border, facing
+                                    // out, or into someone's region: take
+                                    // back the predicted block.
+                                    let ack: ClientboundGamePacket = ClientboundBlockChangedAck {
+                                        seq: place.seq,
+                                    }.into_variant();
+                                    write_packet(&ack, write, compression, cipher_enc).await?;
+                                    let restore: ClientboundGamePacket = ClientboundBlockUpdate {
+                                        pos: azalea_core::position::BlockPos::new(epos.x as i32, epos.y as i32, epos.z as i32),
+                                        block_state: engine_block_to_mc(world.get_block(epos)),
+                                    }.into_variant();
+                                    write_packet(&restore, write, compression, cipher_enc).await?;
+                                    if let Some(region) = protected {
+                                        send_protected_notice(write, compression, cipher_enc, &region).await?;
+                                    }
+                                    continue;
+                                }
 
                                 // Place the held block via the causal engine so that
                                 // gravity, fluid spread, etc. trigger on placement.
-                                let held = hotbar[selected_slot];
-                                if held == BlockState::AIR { continue; } // nothing to place
+                                // Only block items place anything.
+                                let Some(held) = inventory.held_block() else { continue };
 
                                 // Orient the block based on player rotation & clicked face.
                                 let cursor_y = (hit.location.y - hit.block_pos.y as f64) as f32;
@@ -1077,6 +1805,12 @@ where
                                     new: new_id,
                                     update_stairs: true,
                                 });
+                                spatial.publish_effects(
+                                    event_bus::ChangeSource::Player(conn_id),
+                                    crate::effects::block_placed(epos, new_id),
+                                );
+                                storage.scoreboard.record(crate::scoreboard::Criterion::BlocksPlaced, player_name, 1);
+                                registry.audit.record(player_uuid, player_name, crate::audit::Action::block(epos, old, new_id));
 
                                 // Acknowledge immediately; authoritative updates
                                 // arrive via the event bus once the cascade settles.
@@ -1086,29 +1820,265 @@ where
                                 write_packet(&ack, write, compression, cipher_enc).await?;
                             }
 
-                            // ── Creative inventory slot update ───────────
-                            ServerboundGamePacket::SetCreativeModeSlot(slot) => {
-                                // Hotbar slots are 36-44 in the inventory window.
-                                let hotbar_idx = slot.slot_num as i32 - 36;
-                                if hotbar_idx >= 0 && hotbar_idx < 9 {
-                                    let bs = match &slot.item_stack {
-                                        ItemStack::Present(data) => {
-                                            item_to_block_kind(data.kind)
-                                                .map(BlockState::from)
-                                                .unwrap_or(BlockState::AIR)
-                                        }
-                                        ItemStack::Empty => BlockState::AIR,
+                            // ── Buckets on fluids, throwables, bows ─────
+                            // Fluids aren't targetable blocks, so the client
+                            // sends a bare "use item" with its look direction
+                            // and we raycast for the source to scoop or the
+                            // face to pour against. Snowballs and eggs fly
+                            // off at once; a bow starts drawing and shoots
+                            // on release (`ReleaseUseItem`, above).
+                            ServerboundGamePacket::UseItem(use_item) => {
+                                let held = inventory.held().kind();
+                                if use_item.hand == InteractionHand::MainHand && game_mode != GameMode::Spectator {
+                                    let eye = [player_x, player_y + crate::item_use::EYE_HEIGHT, player_z];
+                                    if let Some(kind) = crate::projectiles::ProjectileKind::thrown(held) {
+                                        projectiles.launch(
+                                            kind, eye, use_item.y_rot, use_item.x_rot,
+                                            crate::projectiles::THROW_SPEED,
+                                        );
+                                    } else if held == ItemKind::Bow {
+                                        bow_drawn = Some(std::time::Instant::now());
+                                    }
+                                }
+                                let bucket = if use_item.hand == InteractionHand::MainHand
+                                    && crate::gamemode::may_edit_blocks(game_mode)
+                                {
+                                    let eye = [player_x, player_y + crate::item_use::EYE_HEIGHT, player_z];
+                                    crate::item_use::use_bucket(world, held, eye, use_item.y_rot, use_item.x_rot)
+                                        .filter(|bucket| {
+                                            border.contains_block(bucket.pos)
+                                                && protected_region(storage, access, config, player_uuid, bucket.pos).is_none()
+                                        })
+                                } else {
+                                    None
+                                };
+                                if let Some(bucket) = bucket {
+                                    physics.submit_action(BlockAction {
+                                        pos: bucket.pos,
+                                        old: bucket.old,
+                                        new: bucket.new,
+                                        update_stairs: false,
+                                    });
+                                    let effects = if bucket.new == BlockId::AIR {
+                                        crate::effects::block_broken(bucket.pos, bucket.old)
+                                    } else {
+                                        crate::effects::block_placed(bucket.pos, bucket.new)
                                     };
-                                    hotbar[hotbar_idx as usize] = bs;
+                                    spatial.publish_effects(event_bus::ChangeSource::Player(conn_id), effects);
+                                    registry.audit.record(
+                                        player_uuid, player_name,
+                                        crate::audit::Action::block(bucket.pos, bucket.old, bucket.new),
+                                    );
+                                    let item_stack = azalea_inventory::ItemStack::new(bucket.result, 1);
+                                    let slot = inventory.set_held(item_stack.clone());
+                                    let set_slot: ClientboundGamePacket = ClientboundContainerSetSlot {
+                                        container_id: 0,
+                                        state_id: 0,
+                                        slot: slot as u16,
+                                        item_stack,
+                                    }.into_variant();
+                                    write_packet(&set_slot, write, compression, cipher_enc).await?;
+                                }
+                                let ack: ClientboundGamePacket = ClientboundBlockChangedAck {
+                                    seq: use_item.seq,
+                                }.into_variant();
+                                write_packet(&ack, write, compression, cipher_enc).await?;
+                            }
+
+                            // ── Melee attacks (see `combat`) ─────────────
+                            ServerboundGamePacket::Interact(interact) if interact.action == ActionType::Attack => {
+                                let now = std::time::Instant::now();
+                                let since = last_attack.replace(now).map(|at| now.duration_since(at));
+                                if !config.combat.pvp || !crate::combat::fights(game_mode) || health.is_dead() {
+                                    continue;
+                                }
+                                let Some(target) = registry.find_by_entity_id(interact.entity_id.0) else {
+                                    continue;
+                                };
+                                let eye_y = player_y + crate::item_use::EYE_HEIGHT;
+                                let reach = (target.x - player_x).hypot(target.z - player_z).hypot(target.y + 0.9 - eye_y);
+                                if target.conn_id != conn_id && crate::combat::fights(target.game_mode) && reach <= crate::combat::REACH {
+                                    registry.attack(
+                                        target.uuid,
+                                        player_name,
+                                        crate::combat::damage(inventory.held().kind(), since),
+                                        [player_x, player_y, player_z],
+                                    );
+                                }
+                            }
+
+                            // ── Villagers: right-click to trade ──────────
+                            ServerboundGamePacket::Interact(interact)
+                                if matches!(interact.action, ActionType::Interact { .. })
+                                    && game_mode != GameMode::Spectator =>
+                            {
+                                let Some(&(villager, x, y, z)) = villagers.get(&interact.entity_id.0) else {
+                                    continue;
+                                };
+                                let eye_y = player_y + crate::item_use::EYE_HEIGHT;
+                                let reach = (x - player_x).hypot(z - player_z).hypot(y + 0.9 - eye_y);
+                                let Some((profession, _)) = trades.profession(villager) else { continue };
+                                if reach > crate::combat::REACH || health.is_dead() {
+                                    continue;
+                                }
+                                if let Some(open) = inventory.merchant.take() {
+                                    open.close(recipes, &mut inventory.inventory);
+                                }
+                                container_id = container_id % 100 + 1;
+                                let merchant = crate::trading::Merchant::new(container_id, villager);
+                                let open: ClientboundGamePacket = ClientboundOpenScreen {
+                                    container_id,
+                                    menu_type: azalea_registry::builtin::MenuKind::Merchant,
+                                    title: FormattedText::from(profession_title(profession)),
+                                }.into_variant();
+                                write_packet(&open, write, compression, cipher_enc).await?;
+                                write_packet(&merchant_offers(&merchant, trades), write, compression, cipher_enc).await?;
+                                write_packet(&merchant_content(&merchant, trades, &inventory), write, compression, cipher_enc).await?;
+                                inventory.merchant = Some(merchant);
+                            }
+                            ServerboundGamePacket::SelectTrade(select) => {
+                                let guard = &mut inventory;
+                                let Some(merchant) = guard.merchant.as_mut() else { continue };
+                                merchant.select(trades, &mut guard.inventory, select.item as usize);
+                                write_packet(&merchant_content(merchant, trades, &guard.inventory), write, compression, cipher_enc).await?;
+                            }
+                            ServerboundGamePacket::ContainerClick(click)
+                                if inventory.merchant.as_ref().is_some_and(|m| m.container_id == click.container_id) =>
+                            {
+                                let guard = &mut inventory;
+                                let Some(merchant) = guard.merchant.as_mut() else { continue };
+                                let traded = merchant.click(
+                                    trades, recipes, &mut guard.inventory,
+                                    click.slot_num, click.button_num, click.click_type,
+                                );
+                                if traded {
+                                    write_packet(&merchant_offers(merchant, trades), write, compression, cipher_enc).await?;
+                                }
+                                write_packet(&merchant_content(merchant, trades, &guard.inventory), write, compression, cipher_enc).await?;
+                            }
+                            ServerboundGamePacket::ContainerClose(close)
+                                if inventory.merchant.as_ref().is_some_and(|m| m.container_id == close.container_id) =>
+                            {
+                                if let Some(merchant) = inventory.merchant.take() {
+                                    merchant.close(recipes, &mut inventory.inventory);
+                                }
+                                write_packet(&window_content(&inventory), write, compression, cipher_enc).await?;
+                            }
+
+                            // ── Respawn from the death screen ────────────
+                            ServerboundGamePacket::ClientCommand(cmd)
+                                if cmd.action == ClientCommand::PerformRespawn && health.is_dead() =>
+                            {
+                                health = crate::combat::Health::default();
+                                let respawn: ClientboundGamePacket = ClientboundRespawn {
+                                    common: spawn_info(game_mode),
+                                    data_to_keep: 0,
+                                }.into_variant();
+                                write_packet(&respawn, write, compression, cipher_enc).await?;
+                                let abilities: ClientboundGamePacket = crate::gamemode::abilities(game_mode).into_variant();
+                                write_packet(&abilities, write, compression, cipher_enc).await?;
+                                let set_health: ClientboundGamePacket = ClientboundSetHealth {
+                                    health: health.health,
+                                    food: 20,
+                                    saturation: 5.0,
+                                }.into_variant();
+                                write_packet(&set_health, write, compression, cipher_enc).await?;
+                                teleport_to(
+                                    write, compression, cipher_enc, world, shared_worldgen, chunks, pools,
+                                    Vec3 { x: spawn_x, y: spawn_y, z: spawn_z },
+                                    LookDirection::new(player_y_rot, player_x_rot),
+                                    view_distance, immediate_radius, max_loaded,
+                                    &mut current_chunk_x, &mut current_chunk_z,
+                                    &mut loaded_chunks, &mut sent_to_client,
+                                    &mut chunk_send_queue,
+                                ).await?;
+                                player_x = spawn_x;
+                                player_y = spawn_y;
+                                player_z = spawn_z;
+                                // Others dropped our body on `Died`; this
+                                // move spawns us again where they can see.
+                                registry.update_position(
+                                    conn_id, player_x, player_y, player_z,
+                                    player_y_rot, player_x_rot, false,
+                                );
+                                spatial_sub.set_view(current_chunk_x, current_chunk_z, view_distance);
+                            }
+
+                            // ── Creative inventory slot update ───────────
+                            ServerboundGamePacket::SetCreativeModeSlot(slot) if game_mode == GameMode::Creative => {
+                                let slot_num = slot.slot_num as usize;
+                                inventory.set_slot(slot_num, slot.item_stack);
+                                if crate::inventory::CRAFT_GRID.contains(&slot_num) {
+                                    inventory.update_output(recipes);
+                                    let output = crate::inventory::CRAFT_OUTPUT;
+                                    let set_slot: ClientboundGamePacket = ClientboundContainerSetSlot {
+                                        container_id: 0,
+                                        state_id: inventory.state_id(),
+                                        slot: output as u16,
+                                        item_stack: inventory.slot(output).clone(),
+                                    }.into_variant();
+                                    write_packet(&set_slot, write, compression, cipher_enc).await?;
                                 }
                             }
 
+                            // ── Inventory window (see `Inventory::click`) ─
+                            // The server owns the window outside creative,
+                            // so every click is answered with all of it.
+                            ServerboundGamePacket::ContainerClick(click)
+                                if click.container_id == 0 && game_mode != GameMode::Spectator =>
+                            {
+                                inventory.click(recipes, click.slot_num, click.button_num, click.click_type);
+                                write_packet(&window_content(&inventory), write, compression, cipher_enc).await?;
+                            }
+                            ServerboundGamePacket::ContainerClose(close) if close.container_id == 0 => {
+                                inventory.close(recipes);
+                                write_packet(&window_content(&inventory), write, compression, cipher_enc).await?;
+                            }
+
                             // ── Hotbar slot selection ────────────────────
                             ServerboundGamePacket::SetCarriedItem(carried) => {
-                                selected_slot = (carried.slot as usize).min(8);
+                                inventory.select(carried.slot as usize);
+                            }
+
+                            // ── Arm swings, for others to see ────────────
+                            ServerboundGamePacket::Swing(swing) => {
+                                registry.swing(conn_id, swing.hand == InteractionHand::OffHand);
+                                // The client swings every tick while it
+                                // digs; show others each new crack stage.
+                                if let Some((at, since, ticks, shown)) = &mut digging {
+                                    let stage = crate::effects::crack_stage(since.elapsed(), *ticks);
+                                    if stage != *shown {
+                                        *shown = stage;
+                                        spatial.publish_effects(
+                                            event_bus::ChangeSource::Player(conn_id),
+                                            crate::effects::digging(entity_id, *at, world.get_block(*at), stage),
+                                        );
+                                    }
+                                }
+                            }
+
+                            // ── Sneaking and sprinting, for others to see ─
+                            ServerboundGamePacket::PlayerInput(input) => {
+                                registry.update_appearance(conn_id, |look| look.sneaking = input.shift);
+                            }
+                            ServerboundGamePacket::PlayerCommand(command) => {
+                                let sprinting = match command.action {
+                                    PlayerCommandAction::StartSprinting => Some(true),
+                                    PlayerCommandAction::StopSprinting => Some(false),
+                                    _ => None,
+                                };
+                                if let Some(sprinting) = sprinting {
+                                    registry.update_appearance(conn_id, |look| look.sprinting = sprinting);
+                                }
                             }
 
                             // ── Player movement ───────────────────────
+                            // A dead player stays where they fell until
+                            // they respawn.
+                            ServerboundGamePacket::MovePlayerPos(_)
+                            | ServerboundGamePacket::MovePlayerPosRot(_)
+                            | ServerboundGamePacket::MovePlayerRot(_)
+                                if health.is_dead() => {}
                             ServerboundGamePacket::MovePlayerPos(pkt) => {
                                 player_x = pkt.pos.x;
                                 player_y = pkt.pos.y;
@@ -1118,9 +2088,8 @@ where
                                     player_y_rot, player_x_rot, pkt.flags.on_ground,
                                 );
                                 update_loaded_chunks(
-                                    write, compression, cipher_enc, world,
-                                    &*worldgen,
-                                    player_x, player_z, view_distance, immediate_radius,
+                                    write, compression, cipher_enc, chunks,
+                                    player_x, player_z, view_distance, immediate_radius, max_loaded,
                                     &mut current_chunk_x, &mut current_chunk_z,
                                     &mut loaded_chunks, &mut sent_to_client,
                                     &mut chunk_send_queue,
@@ -1138,9 +2107,8 @@ where
                                     player_y_rot, player_x_rot, pkt.flags.on_ground,
                                 );
                                 update_loaded_chunks(
-                                    write, compression, cipher_enc, world,
-                                    &*worldgen,
-                                    player_x, player_z, view_distance, immediate_radius,
+                                    write, compression, cipher_enc, chunks,
+                                    player_x, player_z, view_distance, immediate_radius, max_loaded,
                                     &mut current_chunk_x, &mut current_chunk_z,
                                     &mut loaded_chunks, &mut sent_to_client,
                                     &mut chunk_send_queue,
@@ -1158,18 +2126,104 @@ where
 
                             // ── Chat ────────────────────────────────────
                             ServerboundGamePacket::Chat(chat) => {
+                                if chat.signature.is_none() && !config.chat.accept_unsigned {
+                                    tracing::info!("{} sent unsigned chat, disconnecting", player_name);
+                                    let disconnect: ClientboundGamePacket = ClientboundDisconnect {
+                                        reason: FormattedText::from("Received chat packet with missing or invalid signature."),
+                                    }.into_variant();
+                                    write_packet(&disconnect, write, compression, cipher_enc).await?;
+                                    break;
+                                }
                                 tracing::info!("<{}> {}", player_name, chat.message);
-                                registry.broadcast_chat(conn_id, &player_name, &chat.message);
+                                registry.audit.record(
+                                    player_uuid, player_name,
+                                    crate::audit::Action::Chat { message: chat.message.clone() },
+                                );
+                                registry.broadcast_chat(
+                                    conn_id, player_uuid, &player_name, &chat.message,
+                                    chat.timestamp, chat.salt, chat_sent,
+                                );
+                                chat_sent = chat_sent.wrapping_add(1);
+                            }
+                            ServerboundGamePacket::CommandSuggestion(req) => {
+                                let players: Vec<String> =
+                                    registry.snapshot().into_iter().map(|p| p.name).collect();
+                                let pos = [
+                                    player_x.floor() as i64,
+                                    player_y.floor() as i64,
+                                    player_z.floor() as i64,
+                                ];
+                                let (start, matches) = crate::commands::complete(
+                                    &req.command, access.permission_level(player_uuid), &players, pos,
+                                );
+                                let range = StringRange::between(start, req.command.len());
+                                let reply: ClientboundGamePacket = ClientboundCommandSuggestions {
+                                    id: req.id,
+                                    suggestions: Suggestions::new(
+                                        range,
+                                        matches.iter().map(|m| Suggestion::new(range, m)).collect(),
+                                    ),
+                                }.into_variant();
+                                write_packet(&reply, write, compression, cipher_enc).await?;
                             }
                             ServerboundGamePacket::ChatCommand(cmd) => {
-                                // Ignore slash-commands for now; just swallow the packet.
-                                tracing::debug!("{} sent command: /{}", player_name, cmd.command);
+                                let ctx = crate::commands::CommandContext {
+                                    world,
+                                    storage,
+                                    registry,
+                                    access,
+                                    pools,
+                                    physics,
+                                    config,
+                                    border,
+                                    edit: &edit_session,
+                                    sender: player_name,
+                                    sender_uuid: player_uuid,
+                                    sender_pos: [
+                                        player_x.floor() as i64,
+                                        player_y.floor() as i64,
+                                        player_z.floor() as i64,
+                                    ],
+                                    is_console: false,
+                                };
+                                for line in crate::commands::dispatch(&ctx, &cmd.command).await {
+                                    let reply: ClientboundGamePacket = ClientboundSystemChat {
+                                        content: FormattedText::from(line),
+                                        overlay: false,
+                                    }.into_variant();
+                                    write_packet(&reply, write, compression, cipher_enc).await?;
+                                }
+                            }
+
+                            ServerboundGamePacket::CustomPayload(payload) => {
+                                let channel = payload.identifier.to_string();
+                                let received = channels::Received {
+                                    player: player_uuid, name: player_name, channel: &channel, data: &payload.data[..],
+                                };
+                                if let Some(reply) = registry.channels.dispatch(&received) {
+                                    let pkt: ClientboundGamePacket = ClientboundCustomPayload {
+                                        identifier: payload.identifier,
+                                        data: reply.into(),
+                                    }.into_variant();
+                                    write_packet(&pkt, write, compression, cipher_enc).await?;
+                                }
+                            }
+
+                            ServerboundGamePacket::KeepAlive(ka)
+                                if keepalive_pending.is_some_and(|(id, _)| id == ka.id) =>
+                            {
+                                if let Some((_, sent)) = keepalive_pending.take() {
+                                    registry.set_latency(conn_id, sent.elapsed());
+                                }
                             }
 
                             // ── Ignored packets ─────────────────────────
-                            ServerboundGamePacket::KeepAlive(_) => {}
                             _ => {}
                         }
+                        if *inventory.held() != shown_held {
+                            shown_held = inventory.held().clone();
+                            registry.update_appearance(conn_id, |look| look.main_hand = shown_held.clone());
+                        }
                     }
                     Err(e) => {
                         let msg = format!("{}", e);
@@ -1205,16 +2259,42 @@ where
                 }
                 let mut latest_move: std::collections::HashMap<i32, PlayerEvent> =
                     std::collections::HashMap::new();
+                let mut latest_mob: std::collections::HashMap<i32, event_bus::EntityEvent> =
+                    std::collections::HashMap::new();
 
                 for msg in &burst {
                     match &**msg {
                         event_bus::SpatialMsg::World(batch) => {
+                            // Regions are wider than the view: drop changes
+                            // in chunks the client doesn't have. Chunks
+                            // still queued are encoded from the world when
+                            // sent, so they pick the change up then.
+                            let on_client = |x: i64, z: i64| {
+                                sent_to_client.contains(&((x >> 4) as i32, (z >> 4) as i32))
+                            };
+                            let lights: Vec<event_bus::LightChange> = batch
+                                .light_changes
+                                .iter()
+                                .filter(|lc| on_client(lc.pos.x, lc.pos.z))
+                                .cloned()
+                                .collect();
+                            let changes: Vec<_> = batch
+                                .changes
+                                .iter()
+                                .copied()
+                                .filter(|(pos, _)| on_client(pos.x, pos.z))
+                                .collect();
+                            let filtered = batch.changes.len() - changes.len()
+                                + batch.light_changes.len() - lights.len();
+                            if filtered > 0 {
+                                dashboard.metrics.record_filtered_updates(filtered as u64);
+                            }
                             // Light updates before block updates so the
                             // client re-renders with fresh light data.
-                            if !batch.light_changes.is_empty() {
-                                send_light_updates(write, compression, cipher_enc, world, &batch.light_changes).await?;
+                            if !lights.is_empty() {
+                                send_light_updates(write, compression, cipher_enc, world, &lights).await?;
                             }
-                            for &(pos, new_block) in batch.changes.iter() {
+                            for (pos, new_block) in changes {
                                 let mc_pos = azalea_core::position::BlockPos::new(
                                     pos.x as i32, pos.y as i32, pos.z as i32,
                                 );
@@ -1226,42 +2306,266 @@ where
                                 write_packet(&update, write, compression, cipher_enc).await?;
                             }
                         }
-                        event_bus::SpatialMsg::Move(ev) => {
-                            if let PlayerEvent::Moved { entity_id, .. } = ev {
+                        event_bus::SpatialMsg::Move(ev) => match ev {
+                            PlayerEvent::Moved { entity_id, .. } => {
                                 latest_move.insert(*entity_id, ev.clone());
                             }
+                            // Swings aren't coalesced: each one shows.
+                            PlayerEvent::Swung { conn_id: swung_id, entity_id: eid, off_hand, .. } => {
+                                if *swung_id == conn_id || !spawned_entities.contains(eid) {
+                                    continue;
+                                }
+                                let pkt: ClientboundGamePacket = ClientboundAnimate {
+                                    id: MinecraftEntityId(*eid),
+                                    action: if *off_hand { AnimationAction::SwingOffHand } else { AnimationAction::SwingMainHand },
+                                }.into_variant();
+                                write_packet(&pkt, write, compression, cipher_enc).await?;
+                            }
+                            _ => {}
+                        },
+                        event_bus::SpatialMsg::Entity(ev) => {
+                            latest_mob.insert(ev.entity_id(), ev.clone());
+                        }
+                        // Our own breaks and placements already played
+                        // on our client.
+                        event_bus::SpatialMsg::Effects(batch) => {
+                            if batch.source == event_bus::ChangeSource::Player(conn_id) {
+                                continue;
+                            }
+                            for effect in batch.effects.iter() {
+                                if effect.reaches(player_x, player_y, player_z) {
+                                    write_packet(&effect.to_packet(), write, compression, cipher_enc).await?;
+                                }
+                            }
                         }
                     }
                 }
 
+                let mut out_of_range: Vec<MinecraftEntityId> = Vec::new();
                 for ev in latest_move.into_values() {
-                    let PlayerEvent::Moved { conn_id: moved_id, entity_id: eid, x, y, z, y_rot, x_rot, on_ground } = ev else {
+                    let PlayerEvent::Moved { conn_id: moved_id, entity_id: eid, uuid, x, y, z, y_rot, x_rot, on_ground } = ev else {
                         continue;
                     };
                     if moved_id == conn_id { continue; }
-                    // Fine AOI filter on top of region-granular delivery.
-                    let aoi = ((config.network.view_distance as f64) + 2.0) * 16.0;
-                    if (x - player_x).abs() > aoi || (z - player_z).abs() > aoi {
+                    // Fine interest filter on top of region-granular
+                    // delivery: entering the radius spawns the entity,
+                    // leaving it despawns.
+                    let in_range = in_entity_range(x - player_x, z - player_z, entity_range);
+                    if !spawned_entities.contains(&eid) {
+                        let hidden = spectators.contains(&uuid) && game_mode != GameMode::Spectator;
+                        if in_range && !hidden && tab_listed.contains(&uuid) && spawned_entities.len() < spawn_cap {
+                            spawned_entities.insert(eid);
+                            entity_pos.insert(eid, SentPos::new(x, y, z, y_rot, x_rot));
+                            let spawn_pkt = add_player_entity(eid, uuid, x, y, z, y_rot, x_rot);
+                            write_packet(&spawn_pkt, write, compression, cipher_enc).await?;
+                            let look = registry.find_by_entity_id(eid).map(|p| p.appearance).unwrap_or_default();
+                            for pkt in look.spawn_packets(eid) {
+                                write_packet(&pkt, write, compression, cipher_enc).await?;
+                            }
+                        }
+                        continue;
+                    }
+                    if !in_range {
+                        spawned_entities.remove(&eid);
+                        entity_pos.remove(&eid);
+                        out_of_range.push(MinecraftEntityId(eid));
                         continue;
                     }
 
-                    let tp: ClientboundGamePacket = ClientboundTeleportEntity {
-                        id: MinecraftEntityId(eid),
-                        change: PositionMoveRotation {
-                            pos: Vec3 { x, y, z },
-                            delta: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
-                            look_direction: LookDirection::new(y_rot, x_rot),
-                        },
-                        relative: RelativeMovements::default(),
-                        on_ground,
+                    let now = SentPos::new(x, y, z, y_rot, x_rot);
+                    let last = entity_pos.insert(eid, now);
+                    for pkt in entity_move_packets(eid, last, now, Vec3 { x, y, z }, LookDirection::new(y_rot, x_rot), on_ground) {
+                        write_packet(&pkt, write, compression, cipher_enc).await?;
+                    }
+                }
+                for ev in latest_mob.into_values() {
+                    let event_bus::EntityEvent::Moved { entity_id: eid, uuid, kind, x, y, z, y_rot, x_rot, on_ground } = ev else {
+                        villagers.remove(&ev.entity_id());
+                        if mob_pos.remove(&ev.entity_id()).is_some() {
+                            out_of_range.push(MinecraftEntityId(ev.entity_id()));
+                        }
+                        continue;
+                    };
+                    let in_range = in_entity_range(x - player_x, z - player_z, entity_range);
+                    let now = SentPos::new(x, y, z, y_rot, x_rot);
+                    if kind == EntityKind::Villager {
+                        villagers.insert(eid, (uuid, x, y, z));
+                    }
+                    if !mob_pos.contains_key(&eid) {
+                        if in_range && spawned_entities.len() + mob_pos.len() < spawn_cap {
+                            mob_pos.insert(eid, now);
+                            let spawn_pkt = add_entity(eid, uuid, kind, Vec3 { x, y, z }, y_rot, x_rot);
+                            write_packet(&spawn_pkt, write, compression, cipher_enc).await?;
+                        }
+                        continue;
+                    }
+                    if !in_range {
+                        mob_pos.remove(&eid);
+                        out_of_range.push(MinecraftEntityId(eid));
+                        continue;
+                    }
+                    let last = mob_pos.insert(eid, now);
+                    for pkt in entity_move_packets(eid, last, now, Vec3 { x, y, z }, LookDirection::new(y_rot, x_rot), on_ground) {
+                        write_packet(&pkt, write, compression, cipher_enc).await?;
+                    }
+                }
+                if !out_of_range.is_empty() {
+                    let remove_pkt: ClientboundGamePacket = ClientboundRemoveEntities {
+                        entity_ids: out_of_range,
                     }.into_variant();
-                    write_packet(&tp, write, compression, cipher_enc).await?;
+                    write_packet(&remove_pkt, write, compression, cipher_enc).await?;
+                }
+            }
 
-                    let head: ClientboundGamePacket = ClientboundRotateHead {
-                        entity_id: MinecraftEntityId(eid),
-                        y_head_rot: degrees_to_byte_angle(y_rot),
-                    }.into_variant();
-                    write_packet(&head, write, compression, cipher_enc).await?;
+            // ── Scoreboard changes, already packets ─────────────────────
+            result = score_rx.recv() => {
+                match result {
+                    Ok(pkt) => write_packet(&pkt, write, compression, cipher_enc).await?,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("{} scoreboard bus lagged, skipped {} updates", player_name, n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+
+            // ── World border ────────────────────────────────────────────
+            result = border_rx.recv() => {
+                match result {
+                    Ok(pkt) => write_packet(&pkt, write, compression, cipher_enc).await?,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("{} world border bus lagged, skipped {} updates", player_name, n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            reason = shutdown.triggered() => {
+                let disconnect: ClientboundGamePacket = ClientboundDisconnect {
+                    reason: FormattedText::from(reason),
+                }.into_variant();
+                write_packet(&disconnect, write, compression, cipher_enc).await?;
+                break;
+            }
+            _ = border_timer.tick() => {
+                if game_mode == GameMode::Spectator || health.is_dead() {
+                    continue;
+                }
+                if crate::combat::fights(game_mode) {
+                    let damage = border.damage(player_x, player_z);
+                    if damage <= 0.0 {
+                        continue;
+                    }
+                    let hit = health.hit(damage, std::time::Instant::now());
+                    if hit == crate::combat::Hit::Ignored {
+                        continue;
+                    }
+                    registry.hurt(entity_id, 0.0);
+                    let death = (hit == crate::combat::Hit::Killed)
+                        .then(|| format!("{} left the confines of this world", player_name));
+                    for pkt in health_packets(entity_id, health.health, death.as_deref()) {
+                        write_packet(&pkt, write, compression, cipher_enc).await?;
+                    }
+                    if let Some(message) = death {
+                        tracing::info!("{}", message);
+                        registry.died(entity_id, message);
+                        storage.scoreboard.record(crate::scoreboard::Criterion::Deaths, player_name, 1);
+                    }
+                } else if !border.contains(player_x, player_z) {
+                    // Creative players can't be hurt; put them back inside.
+                    (player_x, player_z) = border.clamp(player_x, player_z);
+                    teleport_to(
+                        write, compression, cipher_enc, world, shared_worldgen, chunks, pools,
+                        Vec3 { x: player_x, y: player_y, z: player_z },
+                        LookDirection::new(player_y_rot, player_x_rot),
+                        view_distance, immediate_radius, max_loaded,
+                        &mut current_chunk_x, &mut current_chunk_z,
+                        &mut loaded_chunks, &mut sent_to_client,
+                        &mut chunk_send_queue,
+                    ).await?;
+                    registry.update_position(
+                        conn_id, player_x, player_y, player_z,
+                        player_y_rot, player_x_rot, false,
+                    );
+                    spatial_sub.set_view(current_chunk_x, current_chunk_z, view_distance);
+                }
+            }
+
+            // ── Boss bars: relay what's addressed to us ─────────────────
+            result = bar_rx.recv() => {
+                match result {
+                    Ok(update) if update.reaches(player_uuid) => {
+                        write_packet(&update.packet, write, compression, cipher_enc).await?;
+                    }
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("{} boss bar bus lagged, skipped {} updates", player_name, n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+
+            // ── Tab list: header/footer and everyone's latency ─────────
+            result = tab_rx.recv() => {
+                match result {
+                    Ok(update) => {
+                        let pkt: ClientboundGamePacket = ClientboundTabList {
+                            header: FormattedText::from(update.header),
+                            footer: FormattedText::from(update.footer),
+                        }.into_variant();
+                        write_packet(&pkt, write, compression, cipher_enc).await?;
+                        let entries: Vec<PlayerInfoEntry> = update
+                            .latencies
+                            .iter()
+                            .filter(|(uuid, _)| *uuid == player_uuid || tab_listed.contains(uuid))
+                            .map(|&(uuid, latency)| PlayerInfoEntry {
+                                profile: GameProfile { uuid, name: String::new(), properties: Default::default() },
+                                listed: true,
+                                latency,
+                                game_mode,
+                                display_name: None,
+                                list_order: 0,
+                                update_hat: false,
+                                chat_session: None,
+                            })
+                            .collect();
+                        if !entries.is_empty() {
+                            let info_pkt: ClientboundGamePacket = ClientboundPlayerInfoUpdate {
+                                actions: ActionEnumSet {
+                                    add_player: false,
+                                    initialize_chat: false,
+                                    update_game_mode: false,
+                                    update_listed: false,
+                                    update_latency: true,
+                                    update_display_name: false,
+                                    update_hat: false,
+                                    update_list_order: false,
+                                },
+                                entries,
+                            }.into_variant();
+                            write_packet(&info_pkt, write, compression, cipher_enc).await?;
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::debug!("{} tab list bus lagged, skipped {} refreshes", player_name, n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+
+            // ── Plugin channels: relay pushes addressed to us ───────────
+            result = channel_rx.recv() => {
+                match result {
+                    Ok(out) if out.reaches(player_uuid) => {
+                        let pkt: ClientboundGamePacket = ClientboundCustomPayload {
+                            identifier: Identifier::new(&out.channel),
+                            data: out.data.to_vec().into(),
+                        }.into_variant();
+                        write_packet(&pkt, write, compression, cipher_enc).await?;
+                    }
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("{} plugin channel bus lagged, skipped {} payloads", player_name, n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
                 }
             }
 
@@ -1300,48 +2604,47 @@ where
                 let mut spawn_pkts: Vec<ClientboundGamePacket> = Vec::new();
                 let mut left_eids: Vec<MinecraftEntityId> = Vec::new();
                 let mut left_uuids = Vec::new();
+                let mut kicked: Option<String> = None;
+                let mut mode_entries: Vec<PlayerInfoEntry> = Vec::new();
+                let mut own_mode_changed = false;
                 for event in events {
                     match event {
-                        PlayerEvent::Joined { conn_id: joined_id, entity_id: eid, uuid, name, x, y, z, y_rot, x_rot } => {
+                        PlayerEvent::Joined { conn_id: joined_id, entity_id: eid, uuid, name, properties, x, y, z, y_rot, x_rot, game_mode: joined_mode } => {
                             // Skip our own join event.
                             if joined_id == conn_id { continue; }
+                            if joined_mode == GameMode::Spectator {
+                                spectators.insert(uuid);
+                            }
                             if tab_listed.len() < tab_cap && tab_listed.insert(uuid) {
                                 join_entries.push(PlayerInfoEntry {
-                                    profile: GameProfile {
-                                        uuid,
-                                        name,
-                                        properties: Default::default(),
-                                    },
+                                    profile: GameProfile { uuid, name, properties },
                                     listed: true,
                                     latency: 0,
-                                    game_mode: GameMode::Creative,
+                                    game_mode: joined_mode,
                                     display_name: None,
                                     list_order: 0,
                                     update_hat: false,
                                     chat_session: None,
                                 });
                             }
-                            if spawned_entities.len() < spawn_cap && spawned_entities.insert(eid) {
-                                spawn_pkts.push(ClientboundAddEntity {
-                                    id: MinecraftEntityId(eid),
-                                    uuid,
-                                    entity_type: EntityKind::Player,
-                                    position: Vec3 { x, y, z },
-                                    movement: LpVec3::Zero,
-                                    x_rot: degrees_to_byte_angle(x_rot),
-                                    y_rot: degrees_to_byte_angle(y_rot),
-                                    y_head_rot: degrees_to_byte_angle(y_rot),
-                                    data: 0,
-                                }.into_variant());
+                            if in_entity_range(x - player_x, z - player_z, entity_range)
+                                && crate::gamemode::visible_to(joined_mode, game_mode)
+                                && spawned_entities.len() < spawn_cap
+                                && spawned_entities.insert(eid)
+                            {
+                                entity_pos.insert(eid, SentPos::new(x, y, z, y_rot, x_rot));
+                                spawn_pkts.push(add_player_entity(eid, uuid, x, y, z, y_rot, x_rot));
                             }
                         }
-                        PlayerEvent::Moved { .. } => {
-                            // Movement is delivered through the spatial
-                            // bus; nothing should arrive here.
+                        PlayerEvent::Moved { .. } | PlayerEvent::Swung { .. } => {
+                            // Movement and swings are delivered through
+                            // the spatial bus; nothing should arrive here.
                         }
                         PlayerEvent::Left { conn_id: left_id, entity_id: eid, uuid } => {
                             if left_id == conn_id { continue; }
+                            spectators.remove(&uuid);
                             // Only retract what this client was actually sent.
+                            entity_pos.remove(&eid);
                             if spawned_entities.remove(&eid) {
                                 left_eids.push(MinecraftEntityId(eid));
                             }
@@ -1349,17 +2652,206 @@ where
                                 left_uuids.push(uuid);
                             }
                         }
-                        PlayerEvent::Chat { name, message, .. } => {
-                            // Send as system chat to all clients (including sender).
-                            let text = format!("<{}> {}", name, message);
-                            let chat_pkt: ClientboundGamePacket = ClientboundSystemChat {
-                                content: FormattedText::from(text),
+                        PlayerEvent::Chat { uuid, name, message, timestamp, salt, index, .. } => {
+                            // Delivered to all clients, including the sender.
+                            // The client drops the connection on player chat
+                            // from someone not in its tab list, so those (and
+                            // everything, if disabled) go out as system chat.
+                            let chat_pkt: ClientboundGamePacket =
+                                if config.chat.player_messages && tab_listed.contains(&uuid) {
+                                    let body = PackedSignedMessageBody {
+                                        content: message,
+                                        timestamp,
+                                        salt,
+                                        last_seen: PackedLastSeenMessages { entries: Vec::new() },
+                                    };
+                                    let pkt = player_chat(chat_received, uuid, index, &name, body);
+                                    chat_received = chat_received.wrapping_add(1);
+                                    pkt.into_variant()
+                                } else {
+                                    ClientboundSystemChat {
+                                        content: FormattedText::from(format!("<{}> {}", name, message)),
+                                        overlay: false,
+                                    }.into_variant()
+                                };
+                            write_packet(&chat_pkt, write, compression, cipher_enc).await?;
+                        }
+                        PlayerEvent::Kicked { uuid, reason } => {
+                            if uuid == player_uuid {
+                                kicked = Some(reason);
+                            }
+                        }
+                        PlayerEvent::Teleport { uuid, to: [x, y, z] } => {
+                            if uuid != player_uuid || health.is_dead() {
+                                continue;
+                            }
+                            teleport_to(
+                                write, compression, cipher_enc, world, shared_worldgen, chunks, pools,
+                                Vec3 { x, y, z },
+                                LookDirection::new(player_y_rot, player_x_rot),
+                                view_distance, immediate_radius, max_loaded,
+                                &mut current_chunk_x, &mut current_chunk_z,
+                                &mut loaded_chunks, &mut sent_to_client,
+                                &mut chunk_send_queue,
+                            ).await?;
+                            (player_x, player_y, player_z) = (x, y, z);
+                            registry.update_position(
+                                conn_id, player_x, player_y, player_z,
+                                player_y_rot, player_x_rot, false,
+                            );
+                            spatial_sub.set_view(current_chunk_x, current_chunk_z, view_distance);
+                        }
+                        PlayerEvent::Message { uuid, message } => {
+                            if uuid != player_uuid {
+                                continue;
+                            }
+                            let pkt: ClientboundGamePacket = ClientboundSystemChat {
+                                content: FormattedText::from(message),
                                 overlay: false,
                             }.into_variant();
-                            write_packet(&chat_pkt, write, compression, cipher_enc).await?;
+                            write_packet(&pkt, write, compression, cipher_enc).await?;
+                        }
+                        PlayerEvent::Announcement { message } => {
+                            let pkt: ClientboundGamePacket = ClientboundSystemChat {
+                                content: FormattedText::from(format!("[Server] {}", message)),
+                                overlay: false,
+                            }.into_variant();
+                            write_packet(&pkt, write, compression, cipher_enc).await?;
+                        }
+                        PlayerEvent::WeatherChanged { raining } => {
+                            for event in crate::weather::packets(raining) {
+                                let pkt: ClientboundGamePacket = event.into_variant();
+                                write_packet(&pkt, write, compression, cipher_enc).await?;
+                            }
+                        }
+                        PlayerEvent::Attacked { target, attacker, damage, from } => {
+                            if target != player_uuid || !crate::combat::fights(game_mode) {
+                                continue;
+                            }
+                            let hit = health.hit(damage, std::time::Instant::now());
+                            if hit == crate::combat::Hit::Ignored {
+                                continue;
+                            }
+                            let on_ground = registry.find_by_entity_id(entity_id).is_some_and(|p| p.on_ground);
+                            let push = crate::combat::knockback(from, [player_x, player_y, player_z], on_ground);
+                            let motion: ClientboundGamePacket = ClientboundSetEntityMotion {
+                                id: MinecraftEntityId(entity_id),
+                                delta: LpVec3::from_vec3(push),
+                            }.into_variant();
+                            write_packet(&motion, write, compression, cipher_enc).await?;
+                            // Direction of the hit, relative to where we face.
+                            let yaw = ((from[2] - player_z).atan2(from[0] - player_x).to_degrees() as f32) - player_y_rot;
+                            registry.hurt(entity_id, yaw);
+                            let death = (hit == crate::combat::Hit::Killed)
+                                .then(|| format!("{} was slain by {}", player_name, attacker));
+                            for pkt in health_packets(entity_id, health.health, death.as_deref()) {
+                                write_packet(&pkt, write, compression, cipher_enc).await?;
+                            }
+                            if let Some(message) = death {
+                                tracing::info!("{}", message);
+                                registry.died(entity_id, message);
+                                storage.scoreboard.record(crate::scoreboard::Criterion::Deaths, player_name, 1);
+                                storage.scoreboard.record(crate::scoreboard::Criterion::PlayerKills, &attacker, 1);
+                            }
+                        }
+                        PlayerEvent::Hurt { entity_id: eid, yaw } => {
+                            if eid == entity_id || spawned_entities.contains(&eid) {
+                                let pkt: ClientboundGamePacket = ClientboundHurtAnimation {
+                                    id: MinecraftEntityId(eid),
+                                    yaw,
+                                }.into_variant();
+                                write_packet(&pkt, write, compression, cipher_enc).await?;
+                            }
+                        }
+                        PlayerEvent::Died { entity_id: eid, message } => {
+                            let pkt: ClientboundGamePacket = ClientboundSystemChat {
+                                content: FormattedText::from(message),
+                                overlay: false,
+                            }.into_variant();
+                            write_packet(&pkt, write, compression, cipher_enc).await?;
+                            // The body is gone until its player respawns.
+                            entity_pos.remove(&eid);
+                            if spawned_entities.remove(&eid) {
+                                left_eids.push(MinecraftEntityId(eid));
+                            }
+                        }
+                        PlayerEvent::AppearanceChanged { conn_id: changed_id, entity_id: eid, appearance, pose, held } => {
+                            // Our own client shows its own.
+                            if changed_id == conn_id || !spawned_entities.contains(&eid) {
+                                continue;
+                            }
+                            if pose {
+                                write_packet(&appearance.data_packet(eid), write, compression, cipher_enc).await?;
+                            }
+                            if held {
+                                write_packet(&appearance.equipment_packet(eid), write, compression, cipher_enc).await?;
+                            }
+                        }
+                        PlayerEvent::GameModeChanged { entity_id: eid, uuid, game_mode: new_mode } => {
+                            if new_mode == GameMode::Spectator {
+                                spectators.insert(uuid);
+                            } else {
+                                spectators.remove(&uuid);
+                            }
+                            if tab_listed.contains(&uuid) {
+                                mode_entries.push(PlayerInfoEntry {
+                                    profile: GameProfile { uuid, name: String::new(), properties: Default::default() },
+                                    listed: true,
+                                    latency: 0,
+                                    game_mode: new_mode,
+                                    display_name: None,
+                                    list_order: 0,
+                                    update_hat: false,
+                                    chat_session: None,
+                                });
+                            }
+                            if uuid == player_uuid {
+                                game_mode = new_mode;
+                                own_mode_changed = true;
+                            } else if !crate::gamemode::visible_to(new_mode, game_mode) && spawned_entities.remove(&eid) {
+                                entity_pos.remove(&eid);
+                                left_eids.push(MinecraftEntityId(eid));
+                            }
+                            // Re-run the entity view at the top of the loop:
+                            // spawns whoever just became visible and, after
+                            // our own change, drops whoever just became hidden.
+                            entity_view_chunk = (i32::MAX, i32::MAX);
                         }
                     }
                 }
+                if own_mode_changed {
+                    let change: ClientboundGamePacket = ClientboundGameEvent {
+                        event: EventType::ChangeGameMode,
+                        param: game_mode.to_id() as f32,
+                    }.into_variant();
+                    write_packet(&change, write, compression, cipher_enc).await?;
+                    let abilities: ClientboundGamePacket = crate::gamemode::abilities(game_mode).into_variant();
+                    write_packet(&abilities, write, compression, cipher_enc).await?;
+                }
+                if !mode_entries.is_empty() {
+                    let info_pkt: ClientboundGamePacket = ClientboundPlayerInfoUpdate {
+                        actions: ActionEnumSet {
+                            add_player: false,
+                            initialize_chat: false,
+                            update_game_mode: true,
+                            update_listed: false,
+                            update_latency: false,
+                            update_display_name: false,
+                            update_hat: false,
+                            update_list_order: false,
+                        },
+                        entries: mode_entries,
+                    }.into_variant();
+                    write_packet(&info_pkt, write, compression, cipher_enc).await?;
+                }
+                if let Some(reason) = kicked {
+                    tracing::info!("{} was kicked: {}", player_name, reason);
+                    let disconnect: ClientboundGamePacket = ClientboundDisconnect {
+                        reason: FormattedText::from(reason),
+                    }.into_variant();
+                    write_packet(&disconnect, write, compression, cipher_enc).await?;
+                    break;
+                }
 
                 if !join_entries.is_empty() {
                     let info_pkt: ClientboundGamePacket = ClientboundPlayerInfoUpdate {
@@ -1404,28 +2896,367 @@ where
 
 /// Convert degrees (f32) to a Minecraft protocol byte angle (i8).
 /// MC encodes angles as 256 = 360 degrees.
+/// The region `player` may not edit `pos` in, if any (see `regions`).
+/// Players at `claims.bypass_level` edit everywhere.
+fn protected_region(
+    storage: &WorldStorage,
+    access: &AccessLists,
+    config: &ServerConfig,
+    player: Uuid,
+    pos: ultimate_engine::world::position::BlockPos,
+) -> Option<String> {
+    if access.permission_level(player) >= config.claims.bypass_level {
+        return None;
+    }
+    storage.regions.denies(player, pos)
+}
+
+/// Action-bar line telling the player why their edit was refused.
+async fn send_protected_notice<W: AsyncWrite + Unpin + Send>(
+    write: &mut W,
+    compression: Option<u32>,
+    cipher_enc: &mut Option<azalea_crypto::Aes128CfbEnc>,
+    region: &str,
+) -> Result<()> {
+    let notice: ClientboundGamePacket = ClientboundSystemChat {
+        content: FormattedText::from(format!("This area is protected (region {})", region)),
+        overlay: true,
+    }.into_variant();
+    write_packet(&notice, write, compression, cipher_enc).await?;
+    Ok(())
+}
+
+/// Clear the cracks others see in what we were `dug`ging, if anything.
+fn clear_cracks(
+    spatial: &event_bus::SpatialBus,
+    conn_id: u64,
+    entity_id: i32,
+    dug: Option<(ultimate_engine::world::position::BlockPos, std::time::Instant, u32, u8)>,
+) {
+    if let Some((pos, ..)) = dug {
+        spatial.publish_effects(
+            event_bus::ChangeSource::Player(conn_id),
+            vec![crate::effects::WorldEffect::Cracks { breaker: entity_id, pos, stage: crate::effects::NO_CRACKS }],
+        );
+    }
+}
+
 fn degrees_to_byte_angle(degrees: f32) -> i8 {
     (degrees / 360.0 * 256.0) as i8
 }
 
-/// Try to convert an ItemKind to its corresponding BlockKind.
-/// Uses string name matching: ItemKind::OakPlanks displays as "minecraft:oak_planks",
-/// and BlockKind::from_str("oak_planks") parses it back.
-/// Special-cases items whose name doesn't match a block (e.g. water_bucket → water).
-fn item_to_block_kind(item: azalea_registry::builtin::ItemKind) -> Option<azalea_registry::builtin::BlockKind> {
-    use azalea_registry::builtin::{BlockKind, ItemKind};
+/// Spawn packet for another player's entity.
+fn add_player_entity(
+    eid: i32, uuid: uuid::Uuid, x: f64, y: f64, z: f64, y_rot: f32, x_rot: f32,
+) -> ClientboundGamePacket {
+    add_entity(eid, uuid, EntityKind::Player, Vec3 { x, y, z }, y_rot, x_rot)
+}
+
+/// Move our player to `pos` (see `teleport`): generate the chunks there
+/// off this task, send them, then the new position, so the client never
+/// stands in unloaded terrain. The caller records the position.
+async fn teleport_to<W: AsyncWrite + Unpin + Send>(
+    write: &mut W,
+    compression: Option<u32>,
+    cipher: &mut Option<azalea_crypto::Aes128CfbEnc>,
+    world: &Arc<World>,
+    worldgen: &Arc<dyn WorldGen>,
+    chunks: &ChunkSender,
+    pools: &Pools,
+    pos: Vec3,
+    look: LookDirection,
+    view_distance: i32,
+    immediate_radius: i32,
+    max_loaded: usize,
+    current_chunk_x: &mut i32,
+    current_chunk_z: &mut i32,
+    loaded_chunks: &mut HashSet<(i32, i32)>,
+    sent_to_client: &mut HashSet<(i32, i32)>,
+    chunk_send_queue: &mut VecDeque<(i32, i32)>,
+) -> Result<()> {
+    crate::teleport::preload(world, worldgen, pools, pos.x, pos.z).await;
+    update_loaded_chunks(
+        write, compression, cipher, chunks,
+        pos.x, pos.z, view_distance, immediate_radius, max_loaded,
+        current_chunk_x, current_chunk_z,
+        loaded_chunks, sent_to_client,
+        chunk_send_queue,
+    ).await?;
+    let position: ClientboundGamePacket = ClientboundPlayerPosition {
+        id: 2,
+        change: PositionMoveRotation {
+            pos,
+            delta: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+            look_direction: look,
+        },
+        relative: RelativeMovements::default(),
+    }.into_variant();
+    write_packet(&position, write, compression, cipher).await?;
+    Ok(())
+}
+
+/// Our health after taking damage, then the death screen if it killed us.
+fn health_packets(entity_id: i32, health: f32, death: Option<&str>) -> Vec<ClientboundGamePacket> {
+    let mut out = vec![ClientboundSetHealth { health, food: 20, saturation: 5.0 }.into_variant()];
+    if let Some(message) = death {
+        out.push(ClientboundPlayerCombatKill {
+            player_id: MinecraftEntityId(entity_id),
+            message: FormattedText::from(message),
+        }.into_variant());
+    }
+    out
+}
+
+/// The whole player window, cursor included, as the server has it.
+fn window_content(inventory: &crate::inventory::Inventory) -> ClientboundGamePacket {
+    ClientboundContainerSetContent {
+        container_id: 0,
+        state_id: inventory.state_id(),
+        items: inventory.slots().to_vec(),
+        carried_item: inventory.carried().clone(),
+    }.into_variant()
+}
+
+/// A profession as a window title: `farmer` → `Farmer`.
+fn profession_title(profession: &str) -> String {
+    let name = profession.replace('_', " ");
+    let mut chars = name.chars();
+    chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+}
+
+/// A villager's offers for its open trade window, with how far each is
+/// used up.
+fn merchant_offers(merchant: &crate::trading::Merchant, trades: &crate::trading::Trades) -> ClientboundGamePacket {
+    use azalea_protocol::packets::game::c_merchant_offers::{DataComponentExactPredicate, ItemCost, MerchantOffer};
+
+    // Trades ask for plain items: no components to match.
+    let cost = |cost: crate::trading::Cost| ItemCost {
+        item: cost.item,
+        count: cost.count,
+        components: DataComponentExactPredicate { expected: Vec::new() },
+    };
+    let int = |n: u32| i32::try_from(n).unwrap_or(i32::MAX);
+    let offers = trades.profession(merchant.villager).map_or(&[][..], |(_, offers)| offers);
+    let uses = trades.uses(merchant.villager);
+    ClientboundMerchantOffers {
+        container_id: merchant.container_id,
+        offers: offers
+            .iter()
+            .zip(uses)
+            .map(|(offer, uses)| MerchantOffer {
+                base_cost_a: cost(offer.buy),
+                result: offer.sell.stack(),
+                cost_b: offer.buy_b.map(cost),
+                out_of_stock: uses >= offer.max_uses,
+                uses: int(uses),
+                max_uses: int(offer.max_uses),
+                xp: int(offer.xp),
+                special_price_diff: 0,
+                price_multiplier: 0.0,
+                demand: 0,
+            })
+            .collect(),
+        villager_level: 1,
+        villager_xp: 0,
+        show_progress: false,
+        can_restock: false,
+    }.into_variant()
+}
+
+/// Every slot of an open trade window.
+fn merchant_content(
+    merchant: &crate::trading::Merchant,
+    trades: &crate::trading::Trades,
+    inventory: &crate::inventory::Inventory,
+) -> ClientboundGamePacket {
+    ClientboundContainerSetContent {
+        container_id: merchant.container_id,
+        state_id: inventory.state_id(),
+        items: merchant.slots(trades, inventory),
+        carried_item: inventory.carried().clone(),
+    }.into_variant()
+}
+
+/// Every recipe for the client's recipe book, shown as crafted at a
+/// crafting table. The book only displays them: clicking one to fill the
+/// grid (`PlaceRecipe`) isn't handled.
+fn recipe_book_packets(recipes: &crate::recipes::RecipeBook) -> Vec<ClientboundGamePacket> {
+    use azalea_inventory::ItemStack;
+    use azalea_protocol::common::recipe::{
+        CompositeSlotDisplay, ItemStackDisplay, ItemStackSlotDisplay, RecipeDisplayData,
+        ShapedCraftingRecipeDisplay, ShapelessCraftingRecipeDisplay, SlotDisplayData,
+    };
+    use azalea_protocol::packets::game::c_recipe_book_add::{ClientboundRecipeBookAdd, Entry, RecipeDisplayEntry};
+    use azalea_protocol::packets::game::c_update_recipes::ClientboundUpdateRecipes;
+    use azalea_registry::builtin::RecipeBookCategory;
+    use crate::recipes::{Ingredient, Shape};
+
+    let slot = |ingredient: Option<&Ingredient>| match ingredient.map(|i| i.0.as_slice()) {
+        None | Some([]) => SlotDisplayData::Empty,
+        Some([item]) => SlotDisplayData::ItemKind(ItemStackDisplay { item: *item }),
+        Some(items) => SlotDisplayData::Composite(CompositeSlotDisplay {
+            contents: items.iter().map(|&item| SlotDisplayData::ItemKind(ItemStackDisplay { item })).collect(),
+        }),
+    };
+    let entries = recipes.recipes().iter().enumerate().map(|(id, recipe)| {
+        let result = SlotDisplayData::ItemStack(ItemStackSlotDisplay { stack: ItemStack::new(recipe.result, recipe.count) });
+        let crafting_station = SlotDisplayData::ItemKind(ItemStackDisplay { item: ItemKind::CraftingTable });
+        let display = match &recipe.shape {
+            Shape::Shaped { width, height, cells } => RecipeDisplayData::Shaped(ShapedCraftingRecipeDisplay {
+                width: *width as u32,
+                height: *height as u32,
+                ingredients: cells.iter().map(|cell| slot(cell.as_ref())).collect(),
+                result,
+                crafting_station,
+            }),
+            Shape::Shapeless(ingredients) => RecipeDisplayData::Shapeless(ShapelessCraftingRecipeDisplay {
+                ingredients: ingredients.iter().map(|i| slot(Some(i))).collect(),
+                result,
+                crafting_station,
+            }),
+        };
+        Entry {
+            contents: RecipeDisplayEntry {
+                id: id as u32,
+                display,
+                // An optional varint on the wire: 0 is "no group".
+                group: 0,
+                category: RecipeBookCategory::CraftingMisc,
+                crafting_requirements: None,
+            },
+            flags: 0,
+        }
+    });
+    vec![
+        ClientboundUpdateRecipes { item_sets: Default::default(), stonecutter_recipes: Vec::new() }.into_variant(),
+        ClientboundRecipeBookAdd { entries: entries.collect(), replace: true }.into_variant(),
+    ]
+}
+
+/// The world a player spawns into, at login and on respawn.
+fn spawn_info(game_mode: GameMode) -> CommonPlayerSpawnInfo {
+    CommonPlayerSpawnInfo {
+        dimension_type: DimensionKind::new_raw(0), // overworld = 0
+        dimension: Identifier::new("minecraft:overworld"),
+        seed: 0,
+        game_type: game_mode,
+        previous_game_type: OptionalGameType(None),
+        is_debug: false,
+        is_flat: true,
+        last_death_location: None,
+        portal_cooldown: 0,
+        sea_level: 63,
+    }
+}
+
+/// Spawn packet for any entity, facing where it looks.
+fn add_entity(
+    eid: i32, uuid: uuid::Uuid, kind: EntityKind, position: Vec3, y_rot: f32, x_rot: f32,
+) -> ClientboundGamePacket {
+    ClientboundAddEntity {
+        id: MinecraftEntityId(eid),
+        uuid,
+        entity_type: kind,
+        position,
+        movement: LpVec3::Zero,
+        x_rot: degrees_to_byte_angle(x_rot),
+        y_rot: degrees_to_byte_angle(y_rot),
+        y_head_rot: degrees_to_byte_angle(y_rot),
+        data: 0,
+    }.into_variant()
+}
+
+/// Packets moving entity `eid` from `last` (what this client last saw)
+/// to `now`. Small steps go out as deltas, which the client interpolates
+/// smoothly; teleports (which snap) only for the first sighting or a jump
+/// of 8+ blocks. A turn also turns the head.
+fn entity_move_packets(
+    eid: i32, last: Option<SentPos>, now: SentPos, pos: Vec3, look: LookDirection, on_ground: bool,
+) -> Vec<ClientboundGamePacket> {
+    let turned = last.is_none_or(|l| (l.y_rot, l.x_rot) != (now.y_rot, now.x_rot));
+    let mv: ClientboundGamePacket = match last.and_then(|l| now.delta_from(&l)) {
+        Some(delta) if turned => ClientboundMoveEntityPosRot {
+            entity_id: MinecraftEntityId(eid),
+            delta,
+            y_rot: now.y_rot,
+            x_rot: now.x_rot,
+            on_ground,
+        }.into_variant(),
+        Some(delta) => ClientboundMoveEntityPos {
+            entity_id: MinecraftEntityId(eid),
+            delta,
+            on_ground,
+        }.into_variant(),
+        None => ClientboundTeleportEntity {
+            id: MinecraftEntityId(eid),
+            change: PositionMoveRotation {
+                pos,
+                delta: Vec3 { x: 0.0, y: 0.0, z: 0.0 },
+                look_direction: look,
+            },
+            relative: RelativeMovements::default(),
+            on_ground,
+        }.into_variant(),
+    };
+    let mut packets = vec![mv];
+    if turned {
+        packets.push(ClientboundRotateHead {
+            entity_id: MinecraftEntityId(eid),
+            y_head_rot: now.y_rot,
+        }.into_variant());
+    }
+    packets
+}
+
+/// Entity interest radius in blocks: `entity_view_distance` chunks,
+/// never beyond the chunks the client actually has loaded.
+fn entity_range_blocks(entity_view_distance: i32, view_distance: i32) -> f64 {
+    (entity_view_distance.clamp(0, view_distance.max(0)) * 16) as f64
+}
+
+/// Whether an entity offset (`dx`, `dz`) blocks from the player is within
+/// the interest square.
+fn in_entity_range(dx: f64, dz: f64, range: f64) -> bool {
+    dx.abs() <= range && dz.abs() <= range
+}
+
+/// A remote entity's position as a client last received it, in the
+/// protocol's fixed-point units (1/4096 block) and byte angles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SentPos {
+    x: i64,
+    y: i64,
+    z: i64,
+    y_rot: i8,
+    x_rot: i8,
+}
+
+impl SentPos {
+    fn new(x: f64, y: f64, z: f64, y_rot: f32, x_rot: f32) -> Self {
+        let fixed = |v: f64| (v * 4096.0).round() as i64;
+        Self {
+            x: fixed(x),
+            y: fixed(y),
+            z: fixed(z),
+            y_rot: degrees_to_byte_angle(y_rot),
+            x_rot: degrees_to_byte_angle(x_rot),
+        }
+    }
 
-    // Items whose name doesn't map to a block name directly.
-    match item {
-        ItemKind::WaterBucket => return Some(BlockKind::Water),
-        ItemKind::LavaBucket => return Some(BlockKind::Lava),
-        _ => {}
+    /// Block coordinates of the horizontal position.
+    fn block_xz(&self) -> (f64, f64) {
+        (self.x as f64 / 4096.0, self.z as f64 / 4096.0)
     }
 
-    // Display gives "minecraft:oak_planks", strip prefix for FromStr which expects "oak_planks"
-    let full = format!("{}", item);
-    let name = full.strip_prefix("minecraft:").unwrap_or(&full);
-    name.parse::<BlockKind>().ok()
+    /// The relative move from `from` to here, or `None` when an axis moved
+    /// too far (8 blocks or more) to fit a move packet's `i16`.
+    fn delta_from(&self, from: &SentPos) -> Option<PositionDelta8> {
+        Some(PositionDelta8 {
+            xa: i16::try_from(self.x - from.x).ok()?,
+            ya: i16::try_from(self.y - from.y).ok()?,
+            za: i16::try_from(self.z - from.z).ok()?,
+        })
+    }
 }
 
 /// Map engine BlockId to MC BlockState for protocol.
@@ -1440,19 +3271,21 @@ fn engine_block_to_mc(id: ultimate_engine::world::block::BlockId) -> azalea_bloc
 /// Check if the player has crossed a chunk boundary, and if so, queue new
 /// chunks for deferred loading and immediately unload old ones.
 ///
-/// New chunks are sorted by Chebyshev distance from the player (nearest first)
-/// and added to `chunk_send_queue`. The main loop drains this queue
-/// progressively so the event loop stays responsive during fast movement.
+/// New chunks are added to `chunk_send_queue`. The main loop drains this
+/// queue progressively, in view order (see [`super::chunk_order`]), so the
+/// event loop stays responsive during fast movement.
+/// With a `max_loaded` cap the desired set is the nearest `max_loaded`
+/// chunks (see [`desired_chunks`]); unloads go out farthest first.
 async fn update_loaded_chunks<W: AsyncWrite + Unpin + Send>(
     write: &mut W,
     compression: Option<u32>,
     cipher: &mut Option<azalea_crypto::Aes128CfbEnc>,
-    world: &World,
-    worldgen: &dyn WorldGen,
+    chunks: &ChunkSender,
     player_x: f64,
     player_z: f64,
     view_distance: i32,
     immediate_radius: i32,
+    max_loaded: usize,
     current_chunk_x: &mut i32,
     current_chunk_z: &mut i32,
     loaded_chunks: &mut HashSet<(i32, i32)>,
@@ -1471,15 +3304,8 @@ async fn update_loaded_chunks<W: AsyncWrite + Unpin + Send>(
     *current_chunk_z = new_cz;
 
     // Compute the desired set of loaded chunks.
-    let desired: HashSet<(i32, i32)> = {
-        let mut s = HashSet::with_capacity(((2 * view_distance + 1) * (2 * view_distance + 1)) as usize);
-        for cx in (new_cx - view_distance)..=(new_cx + view_distance) {
-            for cz in (new_cz - view_distance)..=(new_cz + view_distance) {
-                s.insert((cx, cz));
-            }
-        }
-        s
-    };
+    let desired: HashSet<(i32, i32)> =
+        desired_chunks(new_cx, new_cz, view_distance, max_loaded).into_iter().collect();
 
     // Unload chunks that are no longer in range.
     //
@@ -1491,7 +3317,11 @@ async fn update_loaded_chunks<W: AsyncWrite + Unpin + Send>(
     // cx=-4 reach the client as (-4, -1), and the other chunks stay in the
     // client's cache outside the view distance — interactable but not
     // rendered. Build the packet manually with correct bit handling.
-    let to_unload: Vec<(i32, i32)> = loaded_chunks.difference(&desired).copied().collect();
+    //
+    // Farthest first: everything here goes out before anything new is
+    // loaded, so the client's cache never exceeds the cap in between.
+    let mut to_unload: Vec<(i32, i32)> = loaded_chunks.difference(&desired).copied().collect();
+    to_unload.sort_by_key(|&(cx, cz)| std::cmp::Reverse(dist_sq(cx - new_cx, cz - new_cz)));
     for (cx, cz) in &to_unload {
         send_forget_level_chunk(write, compression, cipher, *cx, *cz).await?;
         loaded_chunks.remove(&(*cx, *cz));
@@ -1531,12 +3361,9 @@ async fn update_loaded_chunks<W: AsyncWrite + Unpin + Send>(
         let batch_start: ClientboundGamePacket = ClientboundChunkBatchStart.into_variant();
         write_packet(&batch_start, write, compression, cipher).await?;
 
-        for (cx, cz) in &immediate {
-            worldgen.ensure_generated(world, *cx, *cz);
-            send_chunk_from_world(write, compression, cipher, world, worldgen, *cx, *cz).await?;
-            loaded_chunks.insert((*cx, *cz));
-            sent_to_client.insert((*cx, *cz));
-        }
+        send_chunks(write, compression, cipher, chunks, &immediate).await?;
+        loaded_chunks.extend(immediate.iter().copied());
+        sent_to_client.extend(immediate.iter().copied());
 
         let batch_end: ClientboundGamePacket = ClientboundChunkBatchFinished {
             batch_size: immediate.len() as u32,
@@ -1559,16 +3386,36 @@ async fn update_loaded_chunks<W: AsyncWrite + Unpin + Send>(
 
     if !immediate.is_empty() || !deferred.is_empty() || !to_unload.is_empty() {
         tracing::debug!(
-            "Chunk update: center ({},{}), {} unloaded, {} immediate, {} deferred, queue={}",
+            "Chunk update: center ({},{}), {} unloaded, {} immediate, {} deferred, queue={}, loaded={}",
             new_cx, new_cz,
             to_unload.len(), immediate.len(), deferred.len(),
-            chunk_send_queue.len(),
+            chunk_send_queue.len(), loaded_chunks.len(),
         );
     }
 
     Ok(())
 }
 
+/// Squared horizontal distance in chunks.
+fn dist_sq(dx: i32, dz: i32) -> i64 {
+    (dx as i64).pow(2) + (dz as i64).pow(2)
+}
+
+/// The chunks a client centred on `(cx, cz)` should hold: the
+/// view-distance square, nearest first, truncated to `max_loaded`
+/// (`0` = no cap). A cap trims the square's corners first, so the kept
+/// set is the largest disc that fits.
+fn desired_chunks(cx: i32, cz: i32, view_distance: i32, max_loaded: usize) -> Vec<(i32, i32)> {
+    let mut chunks: Vec<(i32, i32)> =
+        ChunkPos::new(cx, cz).chunks_in_radius(view_distance).map(|c| (c.x, c.z)).collect();
+    if max_loaded != 0 && chunks.len() > max_loaded {
+        // Tie-break on position so every call keeps the same set.
+        chunks.sort_by_key(|&(x, z)| (dist_sq(x - cx, z - cz), x, z));
+        chunks.truncate(max_loaded);
+    }
+    chunks
+}
+
 // ── Chunk data ──────────────────────────────────────────────────────────
 
 /// Send a `ForgetLevelChunk` packet with correct bit handling, working around
@@ -1615,7 +3462,7 @@ async fn send_forget_level_chunk<W: AsyncWrite + Unpin + Send>(
 /// DashMap acquisition instead of ~100K (one per `set_sky_light`/`get_block`
 /// call). This is the difference between ~30 ms and <1 ms per chunk.
 fn ensure_sky_light(world: &World, cx: i32, cz: i32) {
-    use ultimate_engine::world::position::{ChunkPos, LocalBlockPos};
+    use ultimate_engine::world::position::LocalBlockPos;
 
     let cp = ChunkPos::new(cx, cz);
     if world.is_sky_lit(&cp) {
@@ -1662,24 +3509,50 @@ fn ensure_sky_light(world: &World, cx: i32, cz: i32) {
     world.mark_sky_lit(cp);
 }
 
-/// Send a chunk read from the World in MC 1.21.5+ wire format.
-/// Reads actual block state from the engine World, so edits persist.
-///
-/// `worldgen` supplies the biome registry ID for the chunk (Stage 4b ships
-/// one biome per chunk, encoded as a single-valued biome paletted container
-/// in every section).
-async fn send_chunk_from_world<W: AsyncWrite + Unpin + Send>(
+/// Send `list` in MC 1.21.5+ wire format, generated and encoded by the
+/// shared [`ChunkSender`] (or from its spawn-area cache).
+async fn send_chunks<W: AsyncWrite + Unpin + Send>(
     write: &mut W,
     compression: Option<u32>,
     cipher: &mut Option<azalea_crypto::Aes128CfbEnc>,
+    chunks: &ChunkSender,
+    list: &[(i32, i32)],
+) -> Result<()> {
+    for frame in chunks.frames(list).await? {
+        azalea_protocol::write::write_raw_packet(&frame, write, compression, cipher).await?;
+    }
+    Ok(())
+}
+
+/// Pre-encode the chunks a player spawning at world spawn is sent first,
+/// so the first logins after startup copy them instead of serializing.
+/// Returns how many were cached.
+pub fn warm_spawn_chunks(
     world: &World,
     worldgen: &dyn WorldGen,
-    cx: i32,
-    cz: i32,
-) -> Result<()> {
+    chunk_cache: &ChunkCache,
+    view_distance: i32,
+    max_loaded: usize,
+) -> Result<usize> {
+    let (cx, cz) = ((SPAWN_X as i32) >> 4, (SPAWN_Z as i32) >> 4);
+    let chunks = desired_chunks(cx, cz, view_distance.max(0), max_loaded);
+    for &(x, z) in &chunks {
+        worldgen.ensure_generated(world, x, z);
+        chunk_cache.insert(x, z, encode_chunk(world, worldgen, x, z)?);
+    }
+    Ok(chunks.len())
+}
+
+/// Encode a `LevelChunkWithLight` packet (id onward, unframed) from the
+/// World. Reads actual block state from the engine World, so edits persist.
+///
+/// `worldgen` supplies the biome registry ID for the chunk (Stage 4b ships
+/// one biome per chunk, encoded as a single-valued biome paletted container
+/// in every section).
+pub fn encode_chunk(world: &World, worldgen: &dyn WorldGen, cx: i32, cz: i32) -> Result<Vec<u8>> {
     use ultimate_engine::world::block::BlockId;
-    use ultimate_engine::world::position::ChunkPos;
 
+    let _encode = tracing::trace_span!("encode_chunk", cx, cz).entered();
     let total_sections = 24;
     let min_y: i64 = -64;
     let base_x = cx as i64 * 16;
@@ -1894,7 +3767,7 @@ async fn send_chunk_from_world<W: AsyncWrite + Unpin + Send>(
     empty_sky_y_mask.set(num_light_sections - 1);
     empty_block_y_mask.set(num_light_sections - 1);
 
-    // CRITICAL: release the DashMap read guard BEFORE the awaits below.
+    // CRITICAL: release the DashMap read guard BEFORE the caller's awaits.
     // A guard held across an await parks with its task; under hundreds of
     // concurrent joins all reading the same spawn chunks, the write-side
     // (`ensure_sky_light`'s `get_chunk_mut`) then blocks tokio worker
@@ -1919,10 +3792,7 @@ async fn send_chunk_from_world<W: AsyncWrite + Unpin + Send>(
         raw_packet.extend_from_slice(arr);
     }
 
-    // Write the raw packet with framing
-    azalea_protocol::write::write_raw_packet(&raw_packet, write, compression, cipher).await?;
-
-    Ok(())
+    Ok(raw_packet)
 }
 
 /// Encode a MOTION_BLOCKING / WORLD_SURFACE heightmap as a bit-packed `u64`
@@ -2130,7 +4000,6 @@ async fn send_light_updates<W: AsyncWrite + Unpin + Send>(
     light_changes: &[event_bus::LightChange],
 ) -> Result<()> {
     use std::collections::{HashMap, HashSet};
-    use ultimate_engine::world::position::ChunkPos;
 
     if light_changes.is_empty() {
         return Ok(());
@@ -2200,7 +4069,7 @@ async fn send_light_updates<W: AsyncWrite + Unpin + Send>(
 
         // Release the read guard BEFORE the packet write awaits below —
         // guards held across awaits wedge the runtime under load (see
-        // the matching comment in send_chunk_from_world).
+        // the matching comment in encode_chunk).
         drop(chunk_ref);
 
         // Build the LightUpdate packet manually (azalea's Write impls
@@ -2251,6 +4120,188 @@ async fn send_light_updates<W: AsyncWrite + Unpin + Send>(
 }
 
 /// Generate an offline-mode UUID from a player name.
-fn offline_uuid(name: &str) -> Uuid {
+pub(crate) fn offline_uuid(name: &str) -> Uuid {
     Uuid::new_v3(&Uuid::NAMESPACE_URL, format!("OfflinePlayer:{}", name).as_bytes())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keepalive_expires_after_timeout() {
+        let sent = std::time::Instant::now();
+        let pending = Some((7, sent));
+        assert!(!keepalive_expired(None, sent + KEEPALIVE_TIMEOUT * 2), "nothing owed");
+        assert!(!keepalive_expired(pending, sent));
+        assert!(!keepalive_expired(pending, sent + KEEPALIVE_INTERVAL));
+        assert!(keepalive_expired(pending, sent + KEEPALIVE_TIMEOUT));
+        assert!(keepalive_expired(pending, sent + KEEPALIVE_TIMEOUT + KEEPALIVE_INTERVAL));
+    }
+
+    #[test]
+    fn test_biome_registry_ids_match_sent_order() {
+        let (_, biomes) = registry_entries()
+            .into_iter()
+            .find(|(id, _)| id == "minecraft:worldgen/biome")
+            .unwrap();
+        for biome in Biome::ALL {
+            assert_eq!(biomes[biome.registry_id() as usize], biome.name());
+            let nbt = biome_nbt(biome);
+            assert_eq!(nbt.float("temperature"), Some(biome.properties().temperature));
+            assert!(nbt.compound("effects").unwrap().int("water_color").is_some());
+        }
+    }
+
+    #[test]
+    fn test_player_chat_uses_sent_chat_type() {
+        use azalea_buf::{AzaleaRead, AzaleaWrite};
+
+        let (_, chat_types) = registry_entries()
+            .into_iter()
+            .find(|(id, _)| id == "minecraft:chat_type")
+            .unwrap();
+        assert_eq!(chat_types[CHAT_TYPE_CHAT as usize], "minecraft:chat");
+
+        let body = PackedSignedMessageBody {
+            content: "hello".into(),
+            timestamp: 1,
+            salt: 2,
+            last_seen: PackedLastSeenMessages { entries: Vec::new() },
+        };
+        let pkt = player_chat(3, uuid::Uuid::from_u128(9), 4, "Steve", body);
+        let mut bytes = Vec::new();
+        pkt.azalea_write(&mut bytes).unwrap();
+        let decoded = ClientboundPlayerChat::azalea_read(&mut Cursor::new(&bytes[..])).unwrap();
+        assert_eq!(decoded, pkt);
+        assert_eq!(decoded.body.content, "hello");
+    }
+
+    #[test]
+    fn test_recipe_book_encodes_shaped_and_shapeless() {
+        use azalea_buf::{AzaleaRead, AzaleaWrite};
+        use azalea_protocol::common::recipe::{RecipeDisplayData, SlotDisplayData};
+        use azalea_protocol::packets::game::c_recipe_book_add::ClientboundRecipeBookAdd;
+
+        let mut tags = crate::recipes::Tags::default();
+        tags.insert("planks", r#"{"values": ["minecraft:oak_planks", "minecraft:birch_planks"]}"#).unwrap();
+        let mut book = crate::recipes::RecipeBook::default();
+        let stick = r###"{"type": "minecraft:crafting_shaped", "key": {"#": "#minecraft:planks"},
+            "pattern": ["#", "#"], "result": {"count": 4, "id": "minecraft:stick"}}"###;
+        assert!(book.add("minecraft:stick", stick, &tags).unwrap());
+        let dye = r#"{"type": "minecraft:crafting_shapeless", "ingredients": ["minecraft:blue_dye", "minecraft:red_dye"],
+            "result": {"count": 2, "id": "minecraft:purple_dye"}}"#;
+        assert!(book.add("minecraft:purple_dye", dye, &tags).unwrap());
+
+        let packets = recipe_book_packets(&book);
+        let Some(ClientboundGamePacket::RecipeBookAdd(add)) = packets.last() else {
+            panic!("the recipe book add packet comes last");
+        };
+        let mut bytes = Vec::new();
+        add.azalea_write(&mut bytes).unwrap();
+        let decoded = ClientboundRecipeBookAdd::azalea_read(&mut Cursor::new(&bytes[..])).unwrap();
+        assert_eq!(&decoded, add);
+
+        let RecipeDisplayData::Shaped(shaped) = &decoded.entries[0].contents.display else { panic!("stick is shaped") };
+        assert_eq!((shaped.width, shaped.height), (1, 2));
+        assert!(matches!(&shaped.ingredients[0], SlotDisplayData::Composite(c) if c.contents.len() == 2));
+        let RecipeDisplayData::Shapeless(shapeless) = &decoded.entries[1].contents.display else { panic!("dye is shapeless") };
+        assert_eq!(shapeless.ingredients.len(), 2);
+        assert!(matches!(&shapeless.ingredients[0], SlotDisplayData::ItemKind(d) if d.item == ItemKind::BlueDye));
+        assert_eq!(decoded.entries[1].contents.id, 1);
+    }
+
+    #[test]
+    fn test_sent_pos_delta_and_teleport_threshold() {
+        let from = SentPos::new(10.0, 64.0, -3.5, 90.0, 0.0);
+        let step = SentPos::new(10.25, 64.0, -3.0, 90.0, 0.0);
+        let d = step.delta_from(&from).unwrap();
+        assert_eq!((d.xa, d.ya, d.za), (1024, 0, 2048));
+        // Just under 8 blocks still fits; 8 does not.
+        assert!(SentPos::new(17.99, 64.0, -3.5, 0.0, 0.0).delta_from(&from).is_some());
+        assert!(SentPos::new(18.0, 64.0, -3.5, 0.0, 0.0).delta_from(&from).is_none());
+        assert!(SentPos::new(10.0, 50.0, -3.5, 0.0, 0.0).delta_from(&from).is_none());
+    }
+
+    #[test]
+    fn test_entity_range_is_clamped_to_view() {
+        assert_eq!(entity_range_blocks(6, 8), 96.0);
+        assert_eq!(entity_range_blocks(12, 8), 128.0);
+        assert_eq!(entity_range_blocks(-1, 8), 0.0);
+        let range = entity_range_blocks(4, 8);
+        assert!(in_entity_range(64.0, -64.0, range));
+        assert!(!in_entity_range(0.0, 64.5, range));
+    }
+
+    #[test]
+    fn test_version_mismatch_messages() {
+        use azalea_protocol::packets::PROTOCOL_VERSION;
+        assert_eq!(version_mismatch(PROTOCOL_VERSION), None);
+        assert!(version_mismatch(PROTOCOL_VERSION - 1).unwrap().starts_with("Outdated client!"));
+        assert!(version_mismatch(PROTOCOL_VERSION + 1).unwrap().starts_with("Outdated server!"));
+    }
+
+    #[test]
+    fn test_legacy_ping_response_framing() {
+        let status = Status { motd: "§aA Server".into(), max_players: 20, favicon: None };
+        let reply = legacy_ping_response(true, &status, 3);
+        assert_eq!(reply[0], 0xFF);
+        let len = u16::from_be_bytes([reply[1], reply[2]]) as usize;
+        assert_eq!(reply.len(), 3 + len * 2);
+        let units: Vec<u16> = reply[3..]
+            .chunks(2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .collect();
+        let text = String::from_utf16(&units).unwrap();
+        let fields: Vec<&str> = text.split('\0').collect();
+        assert_eq!(fields[0], "§1");
+        assert_eq!(&fields[3..], ["§aA Server", "3", "20"]);
+
+        let beta = legacy_ping_response(false, &Status { max_players: 8, ..status }, 0);
+        let units: Vec<u16> = beta[3..].chunks(2).map(|b| u16::from_be_bytes([b[0], b[1]])).collect();
+        assert_eq!(String::from_utf16(&units).unwrap(), "A Server§0§8");
+    }
+
+    #[test]
+    fn test_desired_chunks_cap_keeps_nearest() {
+        assert_eq!(desired_chunks(0, 0, 2, 0).len(), 25);
+        assert_eq!(desired_chunks(0, 0, 2, 100).len(), 25, "cap above the square is a no-op");
+
+        let capped = desired_chunks(3, -2, 8, 100);
+        assert_eq!(capped.len(), 100);
+        assert!(capped.contains(&(3, -2)));
+        // Every kept chunk is at least as near as every dropped one.
+        let kept_max = capped.iter().map(|&(x, z)| dist_sq(x - 3, z + 2)).max().unwrap();
+        let dropped_min = desired_chunks(3, -2, 8, 0)
+            .into_iter()
+            .filter(|c| !capped.contains(c))
+            .map(|(x, z)| dist_sq(x - 3, z + 2))
+            .min()
+            .unwrap();
+        assert!(kept_max <= dropped_min);
+        // Deterministic across calls, so moving within a chunk churns nothing.
+        assert_eq!(capped, desired_chunks(3, -2, 8, 100));
+    }
+    #[test]
+    fn test_warm_spawn_chunks_match_fresh_encoding() {
+        use crate::worldgen::pipeline::FlatPipeline;
+        use ultimate_engine::world::block::BlockId;
+        use ultimate_engine::world::position::BlockPos;
+
+        let world = World::new();
+        let worldgen = FlatPipeline {
+            min_y: -64,
+            layers: vec![(BlockId::new(1), 4)],
+            biome: Biome::Plains,
+        };
+        let cache = ChunkCache::attach(&world);
+        assert_eq!(warm_spawn_chunks(&world, &worldgen, &cache, 1, 0).unwrap(), 9);
+        assert_eq!(cache.len(), 9);
+        let cached = cache.get(-1, 1).unwrap();
+        assert_eq!(&cached[..], &encode_chunk(&world, &worldgen, -1, 1).unwrap()[..]);
+
+        // An edit at spawn retires every cached packet it could affect.
+        world.set_block(BlockPos::new(8, -60, 8), BlockId::new(2));
+        assert!(cache.is_empty());
+    }
+}
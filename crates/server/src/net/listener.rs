@@ -1,25 +1,116 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
 use tokio::net::TcpListener;
 use ultimate_engine::world::World;
 
+use crate::access::AccessLists;
 use crate::config::ServerConfig;
 use crate::dashboard::DashboardState;
+use crate::dashboard::metrics::Rejection;
 use crate::event_bus::SpatialBus;
+use crate::motd::ServerStatus;
+use crate::persistence::WorldStorage;
 use crate::player_registry::PlayerRegistry;
+use crate::pools::Pools;
+use crate::projectiles::Projectiles;
+use crate::recipes::RecipeBook;
+use crate::trading::Trades;
+use crate::shutdown::Shutdown;
+use crate::skins::SkinResolver;
+use crate::worldborder::WorldBorder;
 use crate::worldgen::WorldGen;
 
-/// Start the TCP listener and accept Minecraft client connections.
-pub async fn run(
-    world: Arc<World>,
-    dashboard: Arc<DashboardState>,
-    spatial: Arc<SpatialBus>,
-    registry: Arc<PlayerRegistry>,
-    worldgen: Arc<dyn WorldGen>,
-    config: Arc<ServerConfig>,
-    physics: crate::physics::PhysicsHandle,
-) -> anyhow::Result<()> {
+use super::chunk_sender::ChunkSender;
+
+/// Per-IP connection rate limit: at most `max` connections from one
+/// address in any `window`. Loopback addresses, and any passed to
+/// [`ConnectionThrottle::exempting`], are never throttled.
+pub struct ConnectionThrottle {
+    max: usize,
+    window: Duration,
+    exempt: Vec<IpAddr>,
+    recent: HashMap<IpAddr, VecDeque<Instant>>,
+    last_sweep: Instant,
+}
+
+impl ConnectionThrottle {
+    /// `max == 0` admits everything.
+    pub fn new(max: u32, window: Duration) -> Self {
+        Self { max: max as usize, window, exempt: Vec::new(), recent: HashMap::new(), last_sweep: Instant::now() }
+    }
+
+    /// Never throttle `ips`: a proxy every player connects through.
+    pub fn exempting(mut self, ips: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.exempt.extend(ips);
+        self
+    }
+
+    /// Whether to accept a connection from `ip` arriving at `now`.
+    pub fn admit(&mut self, ip: IpAddr, now: Instant) -> bool {
+        if self.max == 0 || ip.is_loopback() || self.exempt.contains(&ip) {
+            return true;
+        }
+        // Forget idle addresses once a window, so scans can't grow the map.
+        if now.duration_since(self.last_sweep) >= self.window {
+            let window = self.window;
+            self.recent.retain(|_, times| times.back().is_some_and(|&t| now.duration_since(t) < window));
+            self.last_sweep = now;
+        }
+        let times = self.recent.entry(ip).or_default();
+        while times.front().is_some_and(|&t| now.duration_since(t) >= self.window) {
+            times.pop_front();
+        }
+        if times.len() >= self.max {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
+
+/// The shared services every connection works with: built once in
+/// `main`, handed to [`run`] and cloned into each connection task.
+#[derive(Clone)]
+pub struct ServerContext {
+    pub world: Arc<World>,
+    pub dashboard: Arc<DashboardState>,
+    pub spatial: Arc<SpatialBus>,
+    pub registry: Arc<PlayerRegistry>,
+    pub worldgen: Arc<dyn WorldGen>,
+    pub config: Arc<ServerConfig>,
+    pub physics: crate::physics::PhysicsHandle,
+    pub storage: Arc<WorldStorage>,
+    pub access: Arc<AccessLists>,
+    pub pools: Arc<Pools>,
+    pub skins: Arc<SkinResolver>,
+    pub chunks: Arc<ChunkSender>,
+    pub projectiles: Arc<Projectiles>,
+    pub border: Arc<WorldBorder>,
+    pub status: Arc<ServerStatus>,
+    pub recipes: Arc<RecipeBook>,
+    pub trades: Arc<Trades>,
+    pub shutdown: Shutdown,
+}
+
+/// Start the TCP listener and accept Minecraft client connections until
+/// `ctx.shutdown`. Connections are counted as running tasks, so shutdown
+/// can wait for them to disconnect their clients.
+pub async fn run(ctx: ServerContext) -> anyhow::Result<()> {
+    let ServerContext { config, dashboard, shutdown, .. } = &ctx;
     let listener = TcpListener::bind(&config.network.bind).await?;
     tracing::info!("Listening on {}", config.network.bind);
+    let proxies = match config.proxy.mode {
+        crate::config::ProxyMode::None => &[][..],
+        _ => &config.proxy.addresses[..],
+    };
+    let mut throttle = ConnectionThrottle::new(
+        config.network.throttle_connections,
+        Duration::from_secs(config.network.throttle_window_secs),
+    )
+    .exempting(proxies.iter().copied());
 
     // Telemetry heartbeat: total socket bytes written, to correlate with
     // process RSS during load tests.
@@ -37,7 +128,15 @@ pub async fn run(
     });
 
     loop {
-        let (stream, addr) = listener.accept().await?;
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = shutdown.triggered() => return Ok(()),
+        };
+        if !throttle.admit(addr.ip(), Instant::now()) {
+            tracing::debug!("Throttled connection from {}", addr);
+            dashboard.metrics.record_rejection(Rejection::Throttled);
+            continue;
+        }
         tracing::info!("Connection from {}", addr);
 
         // Disable Nagle's algorithm. Without this, the kernel batches small
@@ -47,14 +146,8 @@ pub async fn run(
             tracing::warn!("Failed to set TCP_NODELAY on {}: {}", addr, e);
         }
 
-        let world = Arc::clone(&world);
-        let dashboard = Arc::clone(&dashboard);
-        let spatial = Arc::clone(&spatial);
-        let registry = Arc::clone(&registry);
-        let worldgen = Arc::clone(&worldgen);
-        let config = Arc::clone(&config);
-        let physics = physics.clone();
-        let fut = super::connection::handle(stream, world, dashboard, spatial, registry, worldgen, config, physics);
+        let running = shutdown.task();
+        let fut = super::connection::handle(stream, ctx.clone());
         {
             static ONCE: std::sync::Once = std::sync::Once::new();
             ONCE.call_once(|| {
@@ -65,6 +158,46 @@ pub async fn run(
             if let Err(e) = fut.await {
                 tracing::warn!("Connection from {} closed: {}", addr, e);
             }
+            drop(running);
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_limits_each_address_per_window() {
+        let window = Duration::from_secs(10);
+        let mut throttle = ConnectionThrottle::new(2, window);
+        let a: IpAddr = "203.0.113.1".parse().unwrap();
+        let b: IpAddr = "203.0.113.2".parse().unwrap();
+        let t0 = Instant::now();
+
+        assert!(throttle.admit(a, t0));
+        assert!(throttle.admit(a, t0 + Duration::from_secs(1)));
+        assert!(!throttle.admit(a, t0 + Duration::from_secs(2)));
+        assert!(throttle.admit(b, t0 + Duration::from_secs(2)));
+        // The first connection ages out of the window.
+        assert!(throttle.admit(a, t0 + window));
+        assert!(!throttle.admit(a, t0 + window));
+
+        for _ in 0..10 {
+            assert!(throttle.admit("127.0.0.1".parse().unwrap(), t0));
+        }
+        let mut off = ConnectionThrottle::new(0, window);
+        assert!((0..10).all(|_| off.admit(a, t0)));
+    }
+
+    #[test]
+    fn test_throttle_exempts_the_proxy() {
+        let proxy: IpAddr = "198.51.100.7".parse().unwrap();
+        let other: IpAddr = "203.0.113.1".parse().unwrap();
+        let mut throttle = ConnectionThrottle::new(1, Duration::from_secs(10)).exempting([proxy]);
+        let t0 = Instant::now();
+        assert!((0..50).all(|_| throttle.admit(proxy, t0)), "every player joins through it");
+        assert!(throttle.admit(other, t0));
+        assert!(!throttle.admit(other, t0), "anyone else is still limited");
+    }
+}
@@ -1,2 +1,6 @@
+pub mod chunk_cache;
+pub mod chunk_order;
+pub mod chunk_sender;
 pub mod connection;
 pub mod listener;
+pub mod proxy;
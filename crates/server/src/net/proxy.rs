@@ -0,0 +1,240 @@
+//! Player info forwarding from a proxy (BungeeCord, Velocity).
+//!
+//! Behind a proxy every connection comes from the proxy's address and the
+//! proxy has already authenticated the player, so the real UUID, address
+//! and skin have to be handed over:
+//!
+//! - **BungeeCord** (`ip_forward: true`) appends them to the handshake
+//!   hostname: `host\0address\0uuid-without-dashes[\0properties-json]`.
+//! - **Velocity** (modern forwarding) answers a login plugin request on
+//!   [`VELOCITY_CHANNEL`] with a payload signed by HMAC-SHA256 under the
+//!   shared secret: version, address, UUID, name and properties.
+//!
+//! Only the parsing lives here; `connection::handle_login` decides when to
+//! ask and what to refuse. Neither format may name the nil UUID: no Mojang
+//! profile has it, and the console's audit entries use it.
+//!
+//! BungeeCord's data is not authenticated. Anyone who can reach the server
+//! directly can write any hostname, so with it on the server must be
+//! reachable only by the proxy (see [`bind_is_loopback`]).
+
+use std::net::{IpAddr, SocketAddr};
+
+use anyhow::{anyhow, bail, Result};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use uuid::Uuid;
+
+/// Login plugin channel Velocity answers with the forwarded player.
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+/// Highest forwarding version we understand (`MODERN_DEFAULT`); later ones
+/// add chat-signing keys this server does not use. Sent as the request
+/// body so the proxy never answers with a newer one.
+pub const VELOCITY_VERSION: u8 = 1;
+const SIGNATURE_LEN: usize = 32;
+
+/// Refusals shown to players who bypass the proxy, worded like the proxies'
+/// own server-side checks.
+pub const BUNGEE_REQUIRED: &str =
+    "If you wish to use IP forwarding, please enable it in your BungeeCord config as well!";
+pub const VELOCITY_REQUIRED: &str = "This server requires you to connect with Velocity.";
+
+/// One profile property (usually `textures`).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Property {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+/// The player as the proxy saw them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Forwarded {
+    /// The player's real address.
+    pub address: IpAddr,
+    pub uuid: Uuid,
+    /// Velocity forwards the name; BungeeCord leaves the client's.
+    pub name: Option<String>,
+    pub properties: Vec<Property>,
+}
+
+/// Split a BungeeCord handshake hostname. Errors when it carries no
+/// forwarded data, i.e. the client connected without the proxy.
+pub fn parse_bungee_hostname(hostname: &str) -> Result<Forwarded> {
+    let mut parts = hostname.split('\0');
+    let _host = parts.next();
+    let (Some(address), Some(uuid)) = (parts.next(), parts.next()) else {
+        bail!("handshake carries no forwarded player");
+    };
+    let address = address.parse().map_err(|_| anyhow!("bad forwarded address {:?}", address))?;
+    let uuid = player_uuid(Uuid::try_parse(uuid).map_err(|_| anyhow!("bad forwarded uuid {:?}", uuid))?)?;
+    let properties = match parts.next() {
+        Some(json) => serde_json::from_str(json).map_err(|e| anyhow!("bad forwarded properties: {}", e))?,
+        None => Vec::new(),
+    };
+    Ok(Forwarded { address, uuid, name: None, properties })
+}
+
+/// Check the HMAC on a Velocity forwarding answer and decode it.
+pub fn verify_velocity(data: &[u8], secret: &[u8]) -> Result<Forwarded> {
+    if data.len() < SIGNATURE_LEN {
+        bail!("forwarding answer too short");
+    }
+    let (signature, payload) = data.split_at(SIGNATURE_LEN);
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(payload);
+    mac.verify_slice(signature).map_err(|_| anyhow!("forwarding signature mismatch (check velocity_secret)"))?;
+
+    let mut r = Reader(payload);
+    let version = r.var_int()?;
+    if version < 1 || version > VELOCITY_VERSION as i32 {
+        bail!("unsupported forwarding version {}", version);
+    }
+    let address = r.string()?;
+    let address = address.parse().map_err(|_| anyhow!("bad forwarded address {:?}", address))?;
+    let uuid = player_uuid(Uuid::from_bytes(r.take(16)?.try_into().expect("16 bytes")))?;
+    let name = r.string()?;
+    let count = r.var_int()?;
+    let mut properties = Vec::new();
+    for _ in 0..count {
+        let name = r.string()?;
+        let value = r.string()?;
+        let signature = if r.take(1)?[0] != 0 { Some(r.string()?) } else { None };
+        properties.push(Property { name, value, signature });
+    }
+    Ok(Forwarded { address, uuid, name: Some(name), properties })
+}
+
+/// A forwarded UUID, refused if nil.
+fn player_uuid(uuid: Uuid) -> Result<Uuid> {
+    if uuid.is_nil() {
+        bail!("forwarded nil uuid");
+    }
+    Ok(uuid)
+}
+
+/// Whether `network.bind` (`host:port`) only accepts local connections.
+pub fn bind_is_loopback(bind: &str) -> bool {
+    match bind.parse::<SocketAddr>() {
+        Ok(addr) => addr.ip().is_loopback(),
+        Err(_) => bind.rsplit_once(':').is_some_and(|(host, _)| host == "localhost"),
+    }
+}
+
+/// Just enough of the protocol's primitives for the forwarding payload.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        if self.0.len() < n {
+            bail!("forwarding payload truncated");
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn var_int(&mut self) -> Result<i32> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7F) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value as i32);
+            }
+        }
+        bail!("VarInt too long")
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.var_int()?;
+        let bytes = self.take(usize::try_from(len).map_err(|_| anyhow!("negative string length"))?)?;
+        Ok(String::from_utf8(bytes.to_vec())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: &str = "069a79f444e94726a5befca90e38aaf5";
+
+    #[test]
+    fn test_bungee_hostname_split() {
+        let hostname = format!(
+            "play.example.net\x00203.0.113.9\x00{}\x00[{{\"name\":\"textures\",\"value\":\"abc\",\"signature\":\"sig\"}}]",
+            UUID,
+        );
+        let fwd = parse_bungee_hostname(&hostname).unwrap();
+        assert_eq!(fwd.address, "203.0.113.9".parse::<IpAddr>().unwrap());
+        assert_eq!(fwd.uuid, Uuid::try_parse(UUID).unwrap());
+        assert_eq!(fwd.name, None);
+        assert_eq!(
+            fwd.properties,
+            vec![Property { name: "textures".into(), value: "abc".into(), signature: Some("sig".into()) }],
+        );
+
+        let bare = parse_bungee_hostname(&format!("host\x00::1\x00{}", UUID)).unwrap();
+        assert!(bare.properties.is_empty());
+        assert!(parse_bungee_hostname("play.example.net").is_err());
+        assert!(parse_bungee_hostname("host\x00not-an-ip\x00uuid").is_err());
+        let nil = format!("host\x00::1\x00{}", Uuid::nil().simple());
+        assert!(parse_bungee_hostname(&nil).is_err(), "the nil uuid is never a player");
+    }
+
+    #[test]
+    fn test_bind_is_loopback() {
+        assert!(bind_is_loopback("127.0.0.1:25565"));
+        assert!(bind_is_loopback("[::1]:25565"));
+        assert!(bind_is_loopback("localhost:25565"));
+        assert!(!bind_is_loopback("0.0.0.0:25565"));
+        assert!(!bind_is_loopback("192.168.1.10:25565"));
+    }
+
+    fn string(out: &mut Vec<u8>, s: &str) {
+        out.push(s.len() as u8); // short strings: one-byte VarInt
+        out.extend_from_slice(s.as_bytes());
+    }
+
+    fn signed(secret: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+        mac.update(payload);
+        let mut out = mac.finalize().into_bytes().to_vec();
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn test_velocity_answer_verified_and_decoded() {
+        let uuid = Uuid::try_parse(UUID).unwrap();
+        let mut payload = vec![1];
+        string(&mut payload, "198.51.100.4");
+        payload.extend_from_slice(uuid.as_bytes());
+        string(&mut payload, "Notch");
+        payload.push(1);
+        string(&mut payload, "textures");
+        string(&mut payload, "abc");
+        payload.push(0);
+
+        let fwd = verify_velocity(&signed(b"s3cret", &payload), b"s3cret").unwrap();
+        assert_eq!(fwd.address, "198.51.100.4".parse::<IpAddr>().unwrap());
+        assert_eq!(fwd.uuid, uuid);
+        assert_eq!(fwd.name.as_deref(), Some("Notch"));
+        assert_eq!(fwd.properties, vec![Property { name: "textures".into(), value: "abc".into(), signature: None }]);
+
+        assert!(verify_velocity(&signed(b"other", &payload), b"s3cret").is_err());
+        let mut tampered = signed(b"s3cret", &payload);
+        *tampered.last_mut().unwrap() = 1;
+        assert!(verify_velocity(&tampered, b"s3cret").is_err());
+        assert!(verify_velocity(&signed(b"s3cret", &payload[..10]), b"s3cret").is_err());
+
+        let mut nil = vec![1];
+        string(&mut nil, "198.51.100.4");
+        nil.extend_from_slice(Uuid::nil().as_bytes());
+        string(&mut nil, "Notch");
+        nil.push(0);
+        assert!(verify_velocity(&signed(b"s3cret", &nil), b"s3cret").is_err());
+    }
+}
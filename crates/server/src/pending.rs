@@ -0,0 +1,228 @@
+//! Pending events for chunks that can't run them yet.
+//!
+//! A cascade that reaches unloaded terrain (or, with a simulation distance,
+//! terrain no player is near) parks its next writes on the worker's graph
+//! (see `Scheduler::with_unloaded_deferral`). Parked there, they lasted
+//! only as long as the process. Water that stopped at the edge of the
+//! loaded area would never carry on over the edge after a restart.
+//!
+//! [`PendingEvents`] keeps them instead, per chunk. Physics workers move
+//! their graph's deferrals here once they settle. When a chunk loads or
+//! starts simulating, its events are taken out and replayed as roots on
+//! the worker that owns it. The store is saved alongside the world, like
+//! vanilla's per-chunk scheduled ticks, so a flow left waiting at a chunk
+//! border resumes on the next run when that chunk loads.
+//!
+//! Only `BlockSet` and `BlockNotify`, the events the scheduler parks, are
+//! kept. A replayed `BlockSet` keeps the `old` its rule saw, so if the
+//! chunk loads with something else there the stale-precondition guard
+//! drops it.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use ultimate_engine::causal::event::{Event, EventPayload};
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::position::{BlockPos, ChunkPos, Direction};
+
+/// Saved in the world directory.
+pub const PENDING_FILE: &str = "pending_events.json";
+
+/// Events kept per chunk; more are dropped (and counted). Matches the
+/// graph's own deferral cap.
+pub const MAX_PER_CHUNK: usize = 4096;
+
+/// Events parked per chunk, with the priority each inherited.
+pub struct PendingEvents {
+    /// Where [`save`](Self::save) writes; `None` keeps them in memory only.
+    path: Option<PathBuf>,
+    chunks: DashMap<ChunkPos, Vec<(Event, u8)>>,
+    dropped: AtomicU64,
+}
+
+impl Default for PendingEvents {
+    fn default() -> Self {
+        Self::in_memory()
+    }
+}
+
+impl PendingEvents {
+    /// A store that is never saved (tests, benchmarks, replays).
+    pub fn in_memory() -> Self {
+        Self { path: None, chunks: DashMap::new(), dropped: AtomicU64::new(0) }
+    }
+
+    /// No pending events, saved in world directory `dir`.
+    pub fn empty(dir: &Path) -> Self {
+        Self { path: Some(dir.join(PENDING_FILE)), ..Self::in_memory() }
+    }
+
+    /// Read the events saved in world directory `dir`, if any.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let store = Self::empty(dir);
+        let path = dir.join(PENDING_FILE);
+        let saved: Vec<SavedChunk> = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+        };
+        for chunk in saved {
+            let events = chunk.events.iter().map(SavedEvent::to_event).collect();
+            store.park(ChunkPos::new(chunk.x, chunk.z), events);
+        }
+        Ok(store)
+    }
+
+    /// Write the store back to the world directory. An empty store still
+    /// writes, so events replayed since the last save aren't loaded again.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else { return Ok(()) };
+        let mut saved: Vec<SavedChunk> = self
+            .chunks
+            .iter()
+            .map(|entry| SavedChunk {
+                x: entry.key().x,
+                z: entry.key().z,
+                events: entry.value().iter().filter_map(|(e, p)| SavedEvent::of(e, *p)).collect(),
+            })
+            .collect();
+        saved.sort_by_key(|c| (c.x, c.z));
+        let json = serde_json::to_string(&saved)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, json).with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Keep `events` until `chunk` can run them.
+    pub fn park(&self, chunk: ChunkPos, events: Vec<(Event, u8)>) {
+        if events.is_empty() {
+            return;
+        }
+        let mut queue = self.chunks.entry(chunk).or_default();
+        let room = MAX_PER_CHUNK.saturating_sub(queue.len());
+        if events.len() > room {
+            self.dropped.fetch_add((events.len() - room) as u64, Ordering::Relaxed);
+        }
+        queue.extend(events.into_iter().take(room));
+    }
+
+    /// Everything parked on `chunk`, removed for replay.
+    pub fn take(&self, chunk: ChunkPos) -> Vec<(Event, u8)> {
+        self.chunks.remove(&chunk).map(|(_, events)| events).unwrap_or_default()
+    }
+
+    /// Chunks with events parked on them.
+    pub fn chunks(&self) -> Vec<ChunkPos> {
+        self.chunks.iter().map(|entry| *entry.key()).collect()
+    }
+
+    /// Events parked across all chunks.
+    pub fn len(&self) -> usize {
+        self.chunks.iter().map(|entry| entry.value().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Lifetime number of events dropped because a chunk's queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedChunk {
+    x: i32,
+    z: i32,
+    events: Vec<SavedEvent>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum SavedEvent {
+    Set { pos: [i64; 3], old: u16, new: u16, priority: u8 },
+    /// `from` indexes [`Direction::ALL`].
+    Notify { pos: [i64; 3], from: Option<u8>, priority: u8 },
+}
+
+impl SavedEvent {
+    fn of(event: &Event, priority: u8) -> Option<Self> {
+        let at = |p: &BlockPos| [p.x, p.y, p.z];
+        match &event.payload {
+            EventPayload::BlockSet { pos, old, new } => Some(Self::Set { pos: at(pos), old: old.0, new: new.0, priority }),
+            EventPayload::BlockNotify { pos, from } => Some(Self::Notify {
+                pos: at(pos),
+                from: from.and_then(|d| Direction::ALL.iter().position(|&a| a == d)).map(|i| i as u8),
+                priority,
+            }),
+            _ => None,
+        }
+    }
+
+    fn to_event(&self) -> (Event, u8) {
+        let at = |p: &[i64; 3]| BlockPos::new(p[0], p[1], p[2]);
+        match self {
+            Self::Set { pos, old, new, priority } => (
+                Event { payload: EventPayload::BlockSet { pos: at(pos), old: BlockId::new(*old), new: BlockId::new(*new) } },
+                *priority,
+            ),
+            Self::Notify { pos, from, priority } => (
+                Event {
+                    payload: EventPayload::BlockNotify {
+                        pos: at(pos),
+                        from: from.and_then(|i| Direction::ALL.get(i as usize).copied()),
+                    },
+                },
+                *priority,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(x: i64, priority: u8) -> (Event, u8) {
+        let payload = EventPayload::BlockSet { pos: BlockPos::new(x, 5, 0), old: BlockId::AIR, new: BlockId::new(9) };
+        (Event { payload }, priority)
+    }
+
+    #[test]
+    fn test_pending_events_survive_a_save() {
+        let dir = std::env::temp_dir().join("ultimate_mc_test_pending");
+        let store = PendingEvents::empty(&dir);
+        let notify = Event { payload: EventPayload::BlockNotify { pos: BlockPos::new(17, 5, -3), from: Some(Direction::West) } };
+        store.park(ChunkPos::new(1, -1), vec![(notify, 0)]);
+        store.park(ChunkPos::new(1, 0), vec![set(16, 2), set(17, 2)]);
+        store.save().unwrap();
+
+        let loaded = PendingEvents::load(&dir).unwrap();
+        assert_eq!(loaded.len(), 3);
+        let events = loaded.take(ChunkPos::new(1, -1));
+        assert!(matches!(
+            events[0].0.payload,
+            EventPayload::BlockNotify { pos, from: Some(Direction::West) } if pos == BlockPos::new(17, 5, -3)
+        ));
+        let events = loaded.take(ChunkPos::new(1, 0));
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].1, 2);
+        assert!(matches!(events[1].0.payload, EventPayload::BlockSet { new, .. } if new == BlockId::new(9)));
+        assert!(loaded.is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_park_caps_each_chunk() {
+        let store = PendingEvents::in_memory();
+        store.park(ChunkPos::new(0, 0), (0..MAX_PER_CHUNK as i64 + 5).map(|x| set(x % 16, 0)).collect());
+        assert_eq!(store.len(), MAX_PER_CHUNK);
+        assert_eq!(store.dropped(), 5);
+        assert!(store.save().is_ok(), "an in-memory store saves nowhere");
+    }
+}
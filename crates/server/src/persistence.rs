@@ -19,10 +19,12 @@ use ultimate_engine::world::chunk::{Chunk, ChunkSection};
 use ultimate_engine::world::position::{ChunkPos, LocalBlockPos};
 use ultimate_engine::world::World;
 
+use crate::gamerules::GameRule;
+
 // ── MC 1.21.11 data version ─────────────────────────────────────────────────
 
 /// DataVersion tag written into every saved chunk. MC 1.21.11 = 4189.
-const DATA_VERSION: i32 = 4189;
+pub(crate) const DATA_VERSION: i32 = 4189;
 
 // ── Reverse lookup table: (name, properties) → BlockState ID ─────────────────
 
@@ -57,7 +59,7 @@ pub(crate) fn lookup_block_state(name: &str, props: &[(String, String)]) -> Opti
 }
 
 /// Convert a palette entry (name + optional properties) back to a BlockId.
-fn palette_entry_to_block_id(entry: &PaletteEntry) -> BlockId {
+pub(crate) fn palette_entry_to_block_id(entry: &PaletteEntry) -> BlockId {
     let name = entry
         .name
         .strip_prefix("minecraft:")
@@ -82,7 +84,7 @@ fn palette_entry_to_block_id(entry: &PaletteEntry) -> BlockId {
 }
 
 /// Convert a BlockId to a palette entry (name + optional properties).
-fn block_id_to_palette_entry(id: BlockId) -> PaletteEntry {
+pub(crate) fn block_id_to_palette_entry(id: BlockId) -> PaletteEntry {
     if id == BlockId::AIR {
         return PaletteEntry {
             name: "minecraft:air".into(),
@@ -162,11 +164,19 @@ pub fn new_delta_store() -> DeltaStore {
 pub struct DeltaOverlayGen {
     inner: std::sync::Arc<dyn crate::worldgen::WorldGen>,
     deltas: DeltaStore,
+    biomes: Option<std::sync::Arc<crate::vanilla::BiomeOverlay>>,
 }
 
 impl DeltaOverlayGen {
     pub fn new(inner: std::sync::Arc<dyn crate::worldgen::WorldGen>, deltas: DeltaStore) -> Self {
-        Self { inner, deltas }
+        Self { inner, deltas, biomes: None }
+    }
+
+    /// Report imported biomes where the overlay has them (see
+    /// [`crate::vanilla`]), the generator's everywhere else.
+    pub fn with_biomes(mut self, biomes: std::sync::Arc<crate::vanilla::BiomeOverlay>) -> Self {
+        self.biomes = Some(biomes);
+        self
     }
 }
 
@@ -191,10 +201,167 @@ impl crate::worldgen::WorldGen for DeltaOverlayGen {
     }
 
     fn biome_at_cell(&self, x: i64, y: i64, z: i64) -> u32 {
-        self.inner.biome_at_cell(x, y, z)
+        self.biomes
+            .as_ref()
+            .and_then(|b| b.biome_at_cell(x, y, z))
+            .unwrap_or_else(|| self.inner.biome_at_cell(x, y, z))
+    }
+}
+
+/// Everything needed to save or trim a world directory: the save dir, the
+/// **base** generator (never the overlay — see [`save_world`]) and its
+/// fingerprint, plus the live delta store and `level.dat` state. Shared
+/// by autosave, the shutdown save, and maintenance commands.
+pub struct WorldStorage {
+    pub dir: std::path::PathBuf,
+    pub gen_fp: u64,
+    pub base_gen: std::sync::Arc<dyn crate::worldgen::WorldGen>,
+    pub deltas: DeltaStore,
+    level: std::sync::Mutex<LevelInfo>,
+    /// Mobs saved alongside the world, once attached.
+    mobs: std::sync::OnceLock<std::sync::Arc<crate::mobs::Mobs>>,
+    /// Chunks whose entity record on disk lists entities; one that no
+    /// longer has any is rewritten empty on the next save.
+    entity_chunks: std::sync::Mutex<std::collections::HashSet<ChunkPos>>,
+    /// Objectives and scores, saved alongside the world.
+    pub scoreboard: std::sync::Arc<crate::scoreboard::Scoreboard>,
+    /// Protected regions, saved alongside the world.
+    pub regions: std::sync::Arc<crate::regions::Regions>,
+    /// Events waiting on unloaded or inactive chunks, saved alongside the
+    /// world (see [`crate::pending`]).
+    pub pending: std::sync::Arc<crate::pending::PendingEvents>,
+    /// Anchors `level.dat`'s `Time`: ticks are the saved time plus 20 per
+    /// wall-clock second since this process opened the world (or since
+    /// `doDaylightCycle` last changed).
+    opened_at: std::sync::Mutex<Instant>,
+}
+
+impl WorldStorage {
+    /// Open storage for `dir`. An existing `level.dat` is read so time,
+    /// spawn and gamerules carry over between runs; otherwise defaults are
+    /// derived from the generator (spawn on the surface at (8, 8)).
+    pub fn new(
+        dir: std::path::PathBuf,
+        gen_fp: u64,
+        seed: i64,
+        base_gen: std::sync::Arc<dyn crate::worldgen::WorldGen>,
+        deltas: DeltaStore,
+    ) -> Self {
+        let level = match read_level_dat(&dir) {
+            Ok(Some(mut info)) => {
+                info.seed = seed;
+                info
+            }
+            Ok(None) => LevelInfo::new(seed, [8, base_gen.spawn_y(8, 8).ceil() as i32, 8]),
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable level.dat: {:#}", e);
+                LevelInfo::new(seed, [8, base_gen.spawn_y(8, 8).ceil() as i32, 8])
+            }
+        };
+        crate::gamerules::install(&level.game_rules);
+        let scoreboard = crate::scoreboard::Scoreboard::load(&dir).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable scoreboard: {:#}", e);
+            crate::scoreboard::Scoreboard::empty(&dir)
+        });
+        let regions = crate::regions::Regions::load(&dir).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable regions: {:#}", e);
+            crate::regions::Regions::empty(&dir)
+        });
+        let pending = crate::pending::PendingEvents::load(&dir).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable pending events: {:#}", e);
+            crate::pending::PendingEvents::empty(&dir)
+        });
+        Self {
+            dir,
+            gen_fp,
+            base_gen,
+            deltas,
+            level: std::sync::Mutex::new(level),
+            mobs: std::sync::OnceLock::new(),
+            entity_chunks: std::sync::Mutex::default(),
+            scoreboard: std::sync::Arc::new(scoreboard),
+            regions: std::sync::Arc::new(regions),
+            pending: std::sync::Arc::new(pending),
+            opened_at: std::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Save dirty chunks (refreshing the delta store), then the mobs if
+    /// attached, the scoreboard, the regions, pending events, and
+    /// `level.dat`.
+    pub fn save(&self, world: &World) -> Result<usize> {
+        let _save = tracing::debug_span!("save").entered();
+        let n = save_world(world, &self.dir, self.gen_fp, &*self.base_gen, Some(&self.deltas))?;
+        if let Some(mobs) = self.mobs.get() {
+            let mut written = self.entity_chunks.lock().unwrap_or_else(|e| e.into_inner());
+            *written = save_entities(&self.dir, &mobs.saved(), &written)?;
+        }
+        self.scoreboard.save()?;
+        self.regions.save()?;
+        self.pending.save()?;
+        write_level_dat(&self.dir, &self.level_info())?;
+        Ok(n)
+    }
+
+    /// Restore the saved mobs into `mobs` and save them from now on.
+    /// Returns how many were restored.
+    pub fn attach_mobs(&self, mobs: std::sync::Arc<crate::mobs::Mobs>) -> Result<usize> {
+        let saved = load_entities(&self.dir)?;
+        *self.entity_chunks.lock().unwrap_or_else(|e| e.into_inner()) =
+            saved.iter().map(SavedEntity::chunk).collect();
+        let restored = mobs.restore(&saved);
+        if self.mobs.set(mobs).is_err() {
+            anyhow::bail!("mobs already attached");
+        }
+        Ok(restored)
+    }
+
+    /// Save, then [`trim_world`]: flushing first means chunks whose edits
+    /// were reverted since the last save are trimmed too.
+    pub fn trim(&self, world: &World) -> Result<TrimStats> {
+        self.save(world)?;
+        trim_world(&self.dir, self.gen_fp, &*self.base_gen, Some(&self.deltas))
+    }
+
+    /// Current `level.dat` contents, with `time` advanced to now unless
+    /// `doDaylightCycle` is off.
+    pub fn level_info(&self) -> LevelInfo {
+        let mut info = self.level.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        info.time += self.ticks_since_anchor(&info);
+        info
+    }
+
+    fn ticks_since_anchor(&self, info: &LevelInfo) -> i64 {
+        let cycling = info.game_rules.get(GameRule::DoDaylightCycle.name()).is_none_or(|v| v != "false");
+        if !cycling {
+            return 0;
+        }
+        let opened_at = self.opened_at.lock().unwrap_or_else(|e| e.into_inner());
+        (opened_at.elapsed().as_millis() / 50) as i64
+    }
+
+    /// Set gamerule `rule` from `/gamerule` input, make it current (see
+    /// [`crate::gamerules`]) and return the value as stored. Saved with
+    /// the next `level.dat` write.
+    pub fn set_game_rule(&self, rule: GameRule, value: &str) -> Result<String> {
+        let value = rule.normalize(value)?;
+        let mut level = self.level.lock().unwrap_or_else(|e| e.into_inner());
+        if rule == GameRule::DoDaylightCycle {
+            // Bank the time so far, so stopping freezes it where it is and
+            // starting again runs on from there.
+            level.time += self.ticks_since_anchor(&level);
+            *self.opened_at.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+        }
+        level.game_rules.insert(rule.name().to_owned(), value.clone());
+        crate::gamerules::install(&level.game_rules);
+        Ok(value)
     }
 }
 
+/// Serializes region-file rewrites: autosave and trim both rewrite whole
+/// `.mca` files and would otherwise clobber each other's updates.
+static REGION_IO: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
 /// Chunk-local position of a packed delta cell.
 fn delta_local_pos(section_y: i32, cell: usize) -> LocalBlockPos {
     LocalBlockPos {
@@ -225,19 +392,43 @@ struct SectionNbt {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct BlockStatesNbt {
-    palette: Vec<PaletteEntry>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    data: Option<Vec<i64>>,
+pub(crate) struct BlockStatesNbt {
+    pub(crate) palette: Vec<PaletteEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none", deserialize_with = "long_array_or_list")]
+    pub(crate) data: Option<Vec<i64>>,
+}
+
+/// Packed data as vanilla writes it (a `LongArray` tag) or as older saves
+/// of ours did (a list of longs).
+pub(crate) fn long_array_or_list<'de, D>(d: D) -> std::result::Result<Option<Vec<i64>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use fastnbt::Value;
+    use serde::de::Error;
+
+    match Option::<Value>::deserialize(d)? {
+        None => Ok(None),
+        Some(Value::LongArray(a)) => Ok(Some(a.to_vec())),
+        Some(Value::List(items)) => items
+            .into_iter()
+            .map(|v| match v {
+                Value::Long(l) => Ok(l),
+                other => Err(D::Error::custom(format!("expected long, found {:?}", other))),
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map(Some),
+        Some(other) => Err(D::Error::custom(format!("expected long array, found {:?}", other))),
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-struct PaletteEntry {
+pub(crate) struct PaletteEntry {
     #[serde(rename = "Name")]
-    name: String,
+    pub(crate) name: String,
     #[serde(rename = "Properties")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    properties: Option<HashMap<String, String>>,
+    pub(crate) properties: Option<HashMap<String, String>>,
 }
 
 // ── Bit-packing helpers ──────────────────────────────────────────────────────
@@ -246,7 +437,7 @@ struct PaletteEntry {
 ///
 /// `bits_per_entry` = max(4, ceil(log2(palette_len))).
 /// Entries are packed sequentially into i64s with no entry spanning two longs.
-fn pack_indices(indices: &[u16; 4096], palette_len: usize) -> Option<Vec<i64>> {
+pub(crate) fn pack_indices(indices: &[u16; 4096], palette_len: usize) -> Option<Vec<i64>> {
     if palette_len <= 1 {
         return None; // single-block section, no data array needed
     }
@@ -266,7 +457,7 @@ fn pack_indices(indices: &[u16; 4096], palette_len: usize) -> Option<Vec<i64>> {
 }
 
 /// Unpack palette indices from a `Vec<i64>` back into 4096 entries.
-fn unpack_indices(data: &[i64], palette_len: usize) -> [u16; 4096] {
+pub(crate) fn unpack_indices(data: &[i64], palette_len: usize) -> [u16; 4096] {
     let bits = bits_per_entry(palette_len);
     let entries_per_long = 64 / bits;
     let mask = (1u64 << bits) - 1;
@@ -311,6 +502,9 @@ fn bits_per_entry(palette_len: usize) -> usize {
 /// created as needed. Every chunk is stamped with `gen_fp` (the current
 /// generator fingerprint). Returns the number of chunks written.
 ///
+/// With a `deltas` store, a chunk it already has only re-diffs its dirty
+/// sections, and a chunk whose delta comes out unchanged isn't rewritten.
+///
 /// `worldgen` MUST be the **base** generator, never a [`DeltaOverlayGen`]:
 /// the diff has to be computed against the pristine procedural baseline.
 /// Diffing against an overlay would yield edits-since-last-delta, which
@@ -322,25 +516,40 @@ pub fn save_world(
     worldgen: &dyn crate::worldgen::WorldGen,
     deltas: Option<&DeltaStore>,
 ) -> Result<usize> {
-    let dirty = world.take_dirty_chunks();
+    let dirty = world.take_dirty_sections();
     if dirty.is_empty() {
         tracing::info!("World save: nothing to save (no dirty chunks)");
         return Ok(0);
     }
 
     let start = Instant::now();
+    let _io = REGION_IO.lock().unwrap_or_else(|e| e.into_inner());
     let region_dir = dir.join("region");
     fs::create_dir_all(&region_dir)?;
 
     // Serialize dirty chunks and group by region.
-    let mut region_chunks: HashMap<(i32, i32), Vec<(ChunkPos, Vec<u8>)>> = HashMap::new();
-
-    for pos in &dirty {
+    let mut region_chunks: RegionBatch = HashMap::new();
+
+    let mut unchanged = 0usize;
+    for (pos, sections) in &dirty {
+        // Edited chunks are the ones whose palettes carry stale entries;
+        // saving is the occasional pass that trims them.
+        if let Some(mut chunk) = world.get_chunk_mut(pos) {
+            chunk.compact();
+        }
         let Some(chunk_ref) = world.get_chunk(pos) else {
             continue; // Chunk was removed between dirty-mark and save.
         };
-        let nbt = chunk_to_delta_nbt(*pos, &chunk_ref, gen_fp, worldgen);
+        // The stored delta is what's on disk, and the rest of the chunk
+        // is still baseline + that delta, so only the dirty sections need
+        // diffing again.
+        let previous = deltas.and_then(|store| store.get(pos).map(|d| std::sync::Arc::clone(&d)));
+        let nbt = chunk_to_delta_nbt(*pos, &chunk_ref, gen_fp, worldgen, previous.as_deref().map(|p| (p, sections.as_slice())));
         drop(chunk_ref); // Release DashMap ref before region I/O.
+        if previous.is_some() && nbt.delta.as_deref() == previous.as_deref() {
+            unchanged += 1; // Edited and put back: the region already has it.
+            continue;
+        }
 
         // Refresh the live delta store: after this save the chunk is
         // clean AND its regeneration recipe is current → evictable.
@@ -360,9 +569,31 @@ pub fn save_world(
             .push((*pos, nbt_bytes));
     }
 
+    let total_chunks = write_regions(&region_dir, &region_chunks)?;
+
+    let elapsed = start.elapsed();
+    tracing::info!(
+        "World saved: {} dirty chunks across {} regions, {} unchanged ({:.2?})",
+        total_chunks,
+        region_chunks.len(),
+        unchanged,
+        elapsed,
+    );
+    Ok(total_chunks)
+}
+
+/// Serialized chunk NBT grouped by region coordinates.
+type RegionBatch = HashMap<(i32, i32), Vec<(ChunkPos, Vec<u8>)>>;
+
+/// Write serialized chunks into their region files under `region_dir`,
+/// updating existing files in place. Returns the number of chunks written.
+fn write_regions(
+    region_dir: &Path,
+    region_chunks: &RegionBatch,
+) -> Result<usize> {
     let mut total_chunks = 0usize;
 
-    for ((rx, rz), chunks) in &region_chunks {
+    for ((rx, rz), chunks) in region_chunks {
         let path = region_dir.join(format!("r.{}.{}.mca", rx, rz));
 
         // Open existing region file or create a new one.
@@ -391,17 +622,103 @@ pub fn save_world(
         let data = cursor.into_inner();
         fs::write(&path, &data[..len as usize])?;
     }
-
-    let elapsed = start.elapsed();
-    tracing::info!(
-        "World saved: {} dirty chunks across {} regions ({:.2?})",
-        total_chunks,
-        region_chunks.len(),
-        elapsed,
-    );
     Ok(total_chunks)
 }
 
+/// Export EVERY chunk of `world` as legacy full-section Anvil chunks
+/// under `<dir>/region/` — self-contained terrain that needs no generator
+/// to reconstruct (vanilla tools, `umc-convert`). Chunks are stamped with
+/// `gen_fp`; the server loads them verbatim only under that fingerprint.
+///
+/// `progress` is called as `(done, total)` after each chunk is encoded.
+/// Returns the number of chunks written.
+pub fn export_full_chunks(
+    world: &World,
+    dir: &Path,
+    gen_fp: u64,
+    progress: &mut dyn FnMut(usize, usize),
+) -> Result<usize> {
+    let _io = REGION_IO.lock().unwrap_or_else(|e| e.into_inner());
+    let region_dir = dir.join("region");
+    fs::create_dir_all(&region_dir)?;
+
+    let total = world.chunk_count();
+    let mut region_chunks: RegionBatch = HashMap::new();
+    for (done, entry) in world.iter_chunks().enumerate() {
+        let pos = *entry.key();
+        let nbt = chunk_to_nbt(pos, entry.value(), gen_fp);
+        let nbt_bytes = fastnbt::to_bytes(&nbt)
+            .with_context(|| format!("serializing chunk ({}, {})", pos.x, pos.z))?;
+        region_chunks
+            .entry((pos.x.div_euclid(32), pos.z.div_euclid(32)))
+            .or_default()
+            .push((pos, nbt_bytes));
+        progress(done + 1, total);
+    }
+    write_regions(&region_dir, &region_chunks)
+}
+
+/// Write the chunks at `positions` as full-section chunks, like
+/// [`export_full_chunks`] but for a chosen set (`--pregen` output).
+/// Positions `world` doesn't have are skipped. Returns the number of
+/// chunks written.
+pub fn save_full_chunks(world: &World, dir: &Path, gen_fp: u64, positions: &[ChunkPos]) -> Result<usize> {
+    let _io = REGION_IO.lock().unwrap_or_else(|e| e.into_inner());
+    let region_dir = dir.join("region");
+    fs::create_dir_all(&region_dir)?;
+
+    let mut region_chunks: RegionBatch = HashMap::new();
+    for pos in positions {
+        let Some(chunk) = world.get_chunk(pos) else {
+            continue;
+        };
+        let nbt = chunk_to_nbt(*pos, &chunk, gen_fp);
+        drop(chunk);
+        let nbt_bytes = fastnbt::to_bytes(&nbt)
+            .with_context(|| format!("serializing chunk ({}, {})", pos.x, pos.z))?;
+        region_chunks
+            .entry((pos.x.div_euclid(32), pos.z.div_euclid(32)))
+            .or_default()
+            .push((*pos, nbt_bytes));
+    }
+    write_regions(&region_dir, &region_chunks)
+}
+
+/// Export EVERY chunk of `world` delta-encoded against `worldgen` (the
+/// **base** generator, as for [`save_world`]) — for bringing foreign
+/// terrain into a save. Unlike [`export_full_chunks`] the result stays
+/// evictable and survives generator upgrades, at the cost of large deltas
+/// where the terrain differs from the baseline.
+///
+/// `progress` is called as `(done, total)` after each chunk is encoded.
+/// Returns the number of chunks written.
+pub fn export_delta_chunks(
+    world: &World,
+    dir: &Path,
+    gen_fp: u64,
+    worldgen: &dyn crate::worldgen::WorldGen,
+    progress: &mut dyn FnMut(usize, usize),
+) -> Result<usize> {
+    let _io = REGION_IO.lock().unwrap_or_else(|e| e.into_inner());
+    let region_dir = dir.join("region");
+    fs::create_dir_all(&region_dir)?;
+
+    let total = world.chunk_count();
+    let mut region_chunks: RegionBatch = HashMap::new();
+    for (done, entry) in world.iter_chunks().enumerate() {
+        let pos = *entry.key();
+        let nbt = chunk_to_delta_nbt(pos, entry.value(), gen_fp, worldgen, None);
+        let nbt_bytes = fastnbt::to_bytes(&nbt)
+            .with_context(|| format!("serializing chunk ({}, {})", pos.x, pos.z))?;
+        region_chunks
+            .entry((pos.x.div_euclid(32), pos.z.div_euclid(32)))
+            .or_default()
+            .push((pos, nbt_bytes));
+        progress(done + 1, total);
+    }
+    write_regions(&region_dir, &region_chunks)
+}
+
 /// Build the delta NBT for a chunk: regenerate the baseline from the
 /// worldgen pipeline and record only the differing cells.
 ///
@@ -414,9 +731,28 @@ fn chunk_to_delta_nbt(
     chunk: &Chunk,
     gen_fp: u64,
     worldgen: &dyn crate::worldgen::WorldGen,
+    previous: Option<(&[i64], &[i32])>,
 ) -> ChunkNbt {
     let baseline = worldgen.generate_chunk(pos.x, pos.z, &World::new());
+    let delta = match previous {
+        Some((previous, sections)) => update_delta(previous, chunk, &baseline, sections),
+        None => diff_cells(chunk, &baseline),
+    };
+
+    ChunkNbt {
+        data_version: DATA_VERSION,
+        x_pos: pos.x,
+        z_pos: pos.z,
+        y_pos: 0,
+        sections: Vec::new(),
+        status: "minecraft:full".into(),
+        gen_fp: Some(gen_fp as i64),
+        delta: Some(delta),
+    }
+}
 
+/// Packed delta cells where `chunk` differs from `baseline`.
+pub(crate) fn diff_cells(chunk: &Chunk, baseline: &Chunk) -> Vec<i64> {
     // Union of section indices present on either side: a section missing
     // entirely on one side still diffs cell-by-cell against air.
     let mut section_indices: Vec<i32> = chunk
@@ -429,33 +765,44 @@ fn chunk_to_delta_nbt(
 
     let mut delta = Vec::new();
     for si in section_indices {
-        let live = chunk.section(si);
-        let base = baseline.section(si);
-        for cell in 0..4096usize {
-            let live_block = live.map_or(BlockId::AIR, |s| s.get_by_index(cell));
-            let base_block = base.map_or(BlockId::AIR, |s| s.get_by_index(cell));
-            if live_block != base_block {
-                delta.push(pack_delta(si, cell, live_block));
-            }
-        }
+        diff_section(chunk, baseline, si, &mut delta);
     }
+    delta
+}
 
-    ChunkNbt {
-        data_version: DATA_VERSION,
-        x_pos: pos.x,
-        z_pos: pos.z,
-        y_pos: 0,
-        sections: Vec::new(),
-        status: "minecraft:full".into(),
-        gen_fp: Some(gen_fp as i64),
-        delta: Some(delta),
+/// `previous` (a delta from [`diff_cells`]) with `sections` (sorted)
+/// re-diffed, for a chunk whose other sections haven't changed since.
+/// Same result as a full [`diff_cells`], for a fraction of the cells.
+pub(crate) fn update_delta(previous: &[i64], chunk: &Chunk, baseline: &Chunk, sections: &[i32]) -> Vec<i64> {
+    let mut delta: Vec<i64> = previous
+        .iter()
+        .copied()
+        .filter(|&packed| sections.binary_search(&unpack_delta(packed).0).is_err())
+        .collect();
+    for &si in sections {
+        diff_section(chunk, baseline, si, &mut delta);
+    }
+    // Back into `diff_cells` order: by section, then cell.
+    delta.sort_unstable_by_key(|&packed| packed >> 16);
+    delta
+}
+
+fn diff_section(chunk: &Chunk, baseline: &Chunk, si: i32, delta: &mut Vec<i64>) {
+    let live = chunk.section(si);
+    let base = baseline.section(si);
+    for cell in 0..4096usize {
+        let live_block = live.map_or(BlockId::AIR, |s| s.get_by_index(cell));
+        let base_block = base.map_or(BlockId::AIR, |s| s.get_by_index(cell));
+        if live_block != base_block {
+            delta.push(pack_delta(si, cell, live_block));
+        }
     }
 }
 
 /// Convert an engine `Chunk` to the full-section Anvil NBT representation.
 /// Legacy format — current saves are delta-encoded; this is kept for
-/// vanilla-tool export and for tests exercising the legacy load path.
-#[cfg_attr(not(test), allow(dead_code))]
+/// vanilla-tool export ([`export_full_chunks`]) and for tests exercising
+/// the legacy load path.
 fn chunk_to_nbt(pos: ChunkPos, chunk: &Chunk, gen_fp: u64) -> ChunkNbt {
     let mut sections = Vec::new();
 
@@ -684,24 +1031,29 @@ pub fn load_into(
 /// Convert Anvil NBT chunk data back into an engine `Chunk`.
 fn nbt_to_chunk(nbt: &ChunkNbt) -> Chunk {
     let mut chunk = Chunk::new();
-
     for section_nbt in &nbt.sections {
-        let section_idx = section_nbt.y as i32;
-        let palette = &section_nbt.block_states.palette;
+        set_section_blocks(&mut chunk, section_nbt.y as i32, &section_nbt.block_states);
+    }
+    chunk
+}
 
-        if palette.is_empty() {
-            continue;
-        }
+/// Decode one section's block states into `chunk`. Palette entries the
+/// block table doesn't know become air.
+pub(crate) fn set_section_blocks(chunk: &mut Chunk, section_idx: i32, states: &BlockStatesNbt) {
+    let palette = &states.palette;
+    if palette.is_empty() {
+        return;
+    }
 
-        // Resolve palette to BlockIds.
-        let resolved_palette: Vec<BlockId> =
-            palette.iter().map(palette_entry_to_block_id).collect();
+    // Resolve palette to BlockIds.
+    let resolved_palette: Vec<BlockId> =
+        palette.iter().map(palette_entry_to_block_id).collect();
 
-        // If single-block section (palette length 1, no data array), fill uniformly.
-        let block_ids: [BlockId; 4096] = if palette.len() == 1 || section_nbt.block_states.data.is_none() {
-            [resolved_palette[0]; 4096]
-        } else {
-            let data = section_nbt.block_states.data.as_ref().unwrap();
+    // If single-block section (palette length 1, no data array), fill uniformly.
+    let block_ids: [BlockId; 4096] = match &states.data {
+        None => [resolved_palette[0]; 4096],
+        Some(_) if palette.len() == 1 => [resolved_palette[0]; 4096],
+        Some(data) => {
             let indices = unpack_indices(data, palette.len());
             let mut ids = [BlockId::AIR; 4096];
             for (i, &idx) in indices.iter().enumerate() {
@@ -711,36 +1063,605 @@ fn nbt_to_chunk(nbt: &ChunkNbt) -> Chunk {
                     .unwrap_or(BlockId::AIR);
             }
             ids
+        }
+    };
+
+    // Skip all-air sections.
+    if block_ids.iter().all(|&b| b == BlockId::AIR) {
+        return;
+    }
+
+    // Write blocks into the chunk using set_block.
+    let y_base = (section_idx as i64) * 16;
+    for y in 0..16u8 {
+        for z in 0..16u8 {
+            for x in 0..16u8 {
+                let idx = (y as usize) * 256 + (z as usize) * 16 + (x as usize);
+                let block = block_ids[idx];
+                if block != BlockId::AIR {
+                    chunk.set_block(
+                        LocalBlockPos {
+                            x,
+                            y: y_base + y as i64,
+                            z,
+                        },
+                        block,
+                    );
+                }
+            }
+        }
+    }
+}
+
+// ── Entities ─────────────────────────────────────────────────────────────────
+
+/// An entity as saved: vanilla's entity `id` (`minecraft:pig`) and where
+/// it stands and faces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SavedEntity {
+    pub id: String,
+    pub uuid: uuid::Uuid,
+    pub pos: [f64; 3],
+    /// Yaw, pitch in degrees.
+    pub rotation: [f32; 2],
+}
+
+impl SavedEntity {
+    /// The chunk it is saved with: the one it stands in.
+    pub fn chunk(&self) -> ChunkPos {
+        ChunkPos::new((self.pos[0].floor() as i32).div_euclid(16), (self.pos[2].floor() as i32).div_euclid(16))
+    }
+}
+
+/// One chunk of vanilla's `entities/` region files (1.17+ layout).
+#[derive(Serialize, Deserialize, Debug)]
+struct EntityChunkNbt {
+    #[serde(rename = "DataVersion")]
+    data_version: i32,
+    #[serde(rename = "Position")]
+    position: fastnbt::IntArray,
+    #[serde(rename = "Entities")]
+    entities: Vec<EntityNbt>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct EntityNbt {
+    id: String,
+    #[serde(rename = "UUID")]
+    uuid: fastnbt::IntArray,
+    #[serde(rename = "Pos")]
+    pos: Vec<f64>,
+    #[serde(rename = "Rotation")]
+    rotation: Vec<f32>,
+}
+
+impl From<&SavedEntity> for EntityNbt {
+    fn from(e: &SavedEntity) -> Self {
+        // Vanilla stores UUIDs as four big-endian ints.
+        let bits = e.uuid.as_u128();
+        let uuid = (0..4).rev().map(|i| (bits >> (i * 32)) as u32 as i32).collect();
+        Self {
+            id: e.id.clone(),
+            uuid: fastnbt::IntArray::new(uuid),
+            pos: e.pos.to_vec(),
+            rotation: e.rotation.to_vec(),
+        }
+    }
+}
+
+impl EntityNbt {
+    fn to_saved(&self) -> Option<SavedEntity> {
+        let words = <[i32; 4]>::try_from(&self.uuid[..]).ok()?;
+        let bits = words.iter().fold(0u128, |acc, &w| acc << 32 | w as u32 as u128);
+        Some(SavedEntity {
+            id: self.id.clone(),
+            uuid: uuid::Uuid::from_u128(bits),
+            pos: <[f64; 3]>::try_from(self.pos.as_slice()).ok()?,
+            rotation: <[f32; 2]>::try_from(self.rotation.as_slice()).unwrap_or_default(),
+        })
+    }
+}
+
+/// Write `entities` into `<dir>/entities/`, each in the record of the
+/// chunk it stands in. Chunks in `previous` (those that held entities at
+/// the last save) that now hold none are rewritten empty. Returns the
+/// chunks now holding entities, the next call's `previous`.
+pub fn save_entities(
+    dir: &Path,
+    entities: &[SavedEntity],
+    previous: &std::collections::HashSet<ChunkPos>,
+) -> Result<std::collections::HashSet<ChunkPos>> {
+    let mut by_chunk: HashMap<ChunkPos, Vec<EntityNbt>> = HashMap::new();
+    for entity in entities {
+        by_chunk.entry(entity.chunk()).or_default().push(entity.into());
+    }
+    let holding: std::collections::HashSet<ChunkPos> = by_chunk.keys().copied().collect();
+    for &pos in previous.difference(&holding) {
+        by_chunk.insert(pos, Vec::new());
+    }
+    if by_chunk.is_empty() {
+        return Ok(holding);
+    }
+
+    let _io = REGION_IO.lock().unwrap_or_else(|e| e.into_inner());
+    let region_dir = dir.join("entities");
+    fs::create_dir_all(&region_dir)?;
+    let mut region_chunks: RegionBatch = HashMap::new();
+    for (pos, entities) in by_chunk {
+        let nbt = EntityChunkNbt {
+            data_version: DATA_VERSION,
+            position: fastnbt::IntArray::new(vec![pos.x, pos.z]),
+            entities,
         };
+        let nbt_bytes = fastnbt::to_bytes(&nbt)
+            .with_context(|| format!("serializing entities of chunk ({}, {})", pos.x, pos.z))?;
+        region_chunks
+            .entry((pos.x.div_euclid(32), pos.z.div_euclid(32)))
+            .or_default()
+            .push((pos, nbt_bytes));
+    }
+    write_regions(&region_dir, &region_chunks)?;
+    Ok(holding)
+}
+
+/// Every entity saved under `<dir>/entities/` (none if there is no such
+/// directory). Entities with a malformed UUID or position are skipped.
+pub fn load_entities(dir: &Path) -> Result<Vec<SavedEntity>> {
+    let region_dir = dir.join("entities");
+    if !region_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut entities = Vec::new();
+    for entry in fs::read_dir(&region_dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "mca") {
+            continue;
+        }
+        let file = fs::File::open(&path)
+            .with_context(|| format!("opening entity region {}", path.display()))?;
+        let mut region = fastanvil::Region::from_stream(file)
+            .with_context(|| format!("parsing entity region {}", path.display()))?;
+        for x in 0..32usize {
+            for z in 0..32usize {
+                let Some(nbt_bytes) = region
+                    .read_chunk(x, z)
+                    .with_context(|| format!("reading entities ({}, {}) from {}", x, z, path.display()))?
+                else {
+                    continue;
+                };
+                let chunk: EntityChunkNbt = fastnbt::from_bytes(&nbt_bytes)
+                    .with_context(|| format!("deserializing entities ({}, {}) from {}", x, z, path.display()))?;
+                entities.extend(chunk.entities.iter().filter_map(EntityNbt::to_saved));
+            }
+        }
+    }
+    Ok(entities)
+}
+
+// ── level.dat ────────────────────────────────────────────────────────────────
+
+/// The subset of `level.dat` the server owns. Everything else vanilla
+/// expects (version block, world-gen settings, difficulty) is filled in
+/// with fixed values at write time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelInfo {
+    pub level_name: String,
+    pub seed: i64,
+    /// World spawn, block coordinates.
+    pub spawn: [i32; 3],
+    /// Game time in ticks.
+    pub time: i64,
+    /// Gamerule name → value, stored as strings like vanilla.
+    pub game_rules: std::collections::BTreeMap<String, String>,
+}
+
+impl LevelInfo {
+    pub fn new(seed: i64, spawn: [i32; 3]) -> Self {
+        let mut game_rules = crate::gamerules::defaults();
+        // Players appear exactly at the spawn point.
+        game_rules.insert(GameRule::SpawnRadius.name().to_owned(), "0".into());
+        Self {
+            level_name: "world".into(),
+            seed,
+            spawn,
+            time: 0,
+            game_rules,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LevelDatNbt {
+    #[serde(rename = "Data")]
+    data: LevelDataNbt,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct LevelDataNbt {
+    data_version: i32,
+    level_name: String,
+    #[serde(rename = "version")]
+    version: LevelVersionNbt,
+    world_gen_settings: WorldGenSettingsNbt,
+    spawn_x: i32,
+    spawn_y: i32,
+    spawn_z: i32,
+    spawn_angle: f32,
+    time: i64,
+    day_time: i64,
+    last_played: i64,
+    game_type: i32,
+    difficulty: i8,
+    #[serde(rename = "hardcore")]
+    hardcore: bool,
+    #[serde(rename = "allowCommands")]
+    allow_commands: bool,
+    #[serde(rename = "initialized")]
+    initialized: bool,
+    game_rules: std::collections::BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct LevelVersionNbt {
+    id: i32,
+    name: String,
+    series: String,
+    snapshot: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct WorldGenSettingsNbt {
+    seed: i64,
+    generate_features: bool,
+    bonus_chest: bool,
+    dimensions: HashMap<String, DimensionNbt>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DimensionNbt {
+    #[serde(rename = "type")]
+    kind: String,
+    generator: DimensionGeneratorNbt,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct DimensionGeneratorNbt {
+    #[serde(rename = "type")]
+    kind: String,
+    settings: String,
+    biome_source: BiomeSourceNbt,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct BiomeSourceNbt {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    preset: Option<String>,
+}
+
+/// Vanilla's default noise dimensions. Our terrain comes from the preset
+/// pipeline, not these — they exist so vanilla can open the world at all
+/// (it rejects a `level.dat` without a `dimensions` compound).
+fn vanilla_dimensions() -> HashMap<String, DimensionNbt> {
+    [("overworld", "overworld"), ("the_nether", "nether"), ("the_end", "end")]
+        .into_iter()
+        .map(|(dim, settings)| {
+            let biome_source = if dim == "the_end" {
+                BiomeSourceNbt { kind: "minecraft:the_end".into(), preset: None }
+            } else {
+                BiomeSourceNbt {
+                    kind: "minecraft:multi_noise".into(),
+                    preset: Some(format!("minecraft:{}", settings)),
+                }
+            };
+            (
+                format!("minecraft:{}", dim),
+                DimensionNbt {
+                    kind: format!("minecraft:{}", dim),
+                    generator: DimensionGeneratorNbt {
+                        kind: "minecraft:noise".into(),
+                        settings: format!("minecraft:{}", settings),
+                        biome_source,
+                    },
+                },
+            )
+        })
+        .collect()
+}
+
+/// Write `<dir>/level.dat` (gzipped NBT) so vanilla tools and clients can
+/// open the save. The previous file is kept as `level.dat_old` and the new
+/// one is renamed into place, so a crash mid-write never leaves the world
+/// without a readable `level.dat`.
+pub fn write_level_dat(dir: &Path, info: &LevelInfo) -> Result<()> {
+    use std::io::Write;
+
+    let nbt = LevelDatNbt {
+        data: LevelDataNbt {
+            data_version: DATA_VERSION,
+            level_name: info.level_name.clone(),
+            version: LevelVersionNbt {
+                id: DATA_VERSION,
+                name: "1.21.11".into(),
+                series: "main".into(),
+                snapshot: false,
+            },
+            world_gen_settings: WorldGenSettingsNbt {
+                seed: info.seed,
+                generate_features: true,
+                bonus_chest: false,
+                dimensions: vanilla_dimensions(),
+            },
+            spawn_x: info.spawn[0],
+            spawn_y: info.spawn[1],
+            spawn_z: info.spawn[2],
+            spawn_angle: 0.0,
+            time: info.time,
+            day_time: info.time,
+            last_played: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as i64),
+            game_type: 1, // creative
+            difficulty: 2,
+            hardcore: false,
+            allow_commands: true,
+            initialized: true,
+            game_rules: info.game_rules.clone(),
+        },
+    };
+    let raw = fastnbt::to_bytes(&nbt).context("serializing level.dat")?;
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gz.write_all(&raw)?;
+    let bytes = gz.finish()?;
+
+    fs::create_dir_all(dir)?;
+    let path = dir.join("level.dat");
+    let tmp = dir.join("level.dat_new");
+    fs::write(&tmp, &bytes).with_context(|| format!("writing {}", tmp.display()))?;
+    if path.exists() {
+        fs::copy(&path, dir.join("level.dat_old")).context("backing up level.dat")?;
+    }
+    fs::rename(&tmp, &path).with_context(|| format!("replacing {}", path.display()))?;
+    Ok(())
+}
+
+/// Read `<dir>/level.dat`. Returns `Ok(None)` when the world has none yet.
+pub fn read_level_dat(dir: &Path) -> Result<Option<LevelInfo>> {
+    use std::io::Read;
+
+    let path = dir.join("level.dat");
+    // `exists` reads an unreadable directory as "no level.dat".
+    if !path.try_exists().with_context(|| format!("checking {}", path.display()))? {
+        return Ok(None);
+    }
+    let file = fs::File::open(&path).with_context(|| format!("opening {}", path.display()))?;
+    let mut raw = Vec::new();
+    flate2::read::GzDecoder::new(file)
+        .read_to_end(&mut raw)
+        .with_context(|| format!("decompressing {}", path.display()))?;
+    let nbt: LevelDatNbt = fastnbt::from_bytes(&raw)
+        .with_context(|| format!("parsing {}", path.display()))?;
+    let d = nbt.data;
+    Ok(Some(LevelInfo {
+        level_name: d.level_name,
+        seed: d.world_gen_settings.seed,
+        spawn: [d.spawn_x, d.spawn_y, d.spawn_z],
+        time: d.time,
+        game_rules: d.game_rules,
+    }))
+}
+
+/// The seed to generate `dir` with. A world that already has a `level.dat`
+/// keeps the seed recorded there, so its terrain regenerates the same
+/// whatever the config says now, unless `forced` (an explicit `--seed`).
+/// New worlds take `configured`, which the first save records.
+///
+/// A `level.dat` that is there but can't be read is an error unless
+/// `forced`: falling back to `configured` would have the next save
+/// overwrite the world's seed for good.
+///
+/// The generator takes 32-bit seeds. A saved seed outside that range (a
+/// vanilla world's 64-bit seed) can't be reproduced, so it is an error
+/// rather than silently truncated, unless `forced` picks another.
+pub fn resolve_seed(dir: &Path, configured: u32, forced: bool) -> Result<u32> {
+    let saved = match read_level_dat(dir) {
+        Ok(Some(info)) => info.seed,
+        Ok(None) => return Ok(configured),
+        Err(e) if forced => {
+            tracing::warn!("Can't read the saved seed, --seed {:#x} replaces it: {:#}", configured, e);
+            return Ok(configured);
+        }
+        Err(e) => {
+            return Err(e.context("reading the world's saved seed; start with --seed to choose one"));
+        }
+    };
+    let Ok(saved) = u32::try_from(saved) else {
+        if forced {
+            tracing::warn!("--seed {:#x} replaces the world's saved 64-bit seed {}", configured, saved);
+            return Ok(configured);
+        }
+        anyhow::bail!(
+            "the world's saved seed {} doesn't fit the 32-bit generator; start with --seed to choose one",
+            saved,
+        );
+    };
+    if saved == configured {
+        return Ok(saved);
+    }
+    if forced {
+        tracing::warn!("--seed {:#x} replaces the world's saved seed {:#x}", configured, saved);
+        Ok(configured)
+    } else {
+        tracing::info!("Using the world's saved seed {:#x} (config has {:#x})", saved, configured);
+        Ok(saved)
+    }
+}
+
+// ── Trim ─────────────────────────────────────────────────────────────────────
+
+/// Outcome of a [`trim_world`] pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrimStats {
+    /// Chunks read from region files.
+    pub scanned: usize,
+    /// Chunks removed because they regenerate identically.
+    pub removed: usize,
+    /// Region files rewritten with their remaining chunks.
+    pub regions_rewritten: usize,
+    /// Region files deleted because every chunk in them was removed.
+    pub regions_deleted: usize,
+    /// Total region bytes before and after the pass.
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Delete every saved chunk that is identical to what `worldgen` produces
+/// for it, shrinking world folders that accumulated untouched terrain.
+///
+/// Each candidate is verified by regeneration + comparison, never by
+/// heuristics: a **delta** chunk is removed when every recorded cell
+/// already equals the regenerated baseline (including empty deltas left
+/// behind by edits that were later reverted); a **legacy full-section**
+/// chunk is removed when it matches the baseline cell-for-cell under the
+/// current fingerprint. Legacy chunks from an older generator are kept —
+/// the loader skips them, but they may still hold edits worth migrating.
+///
+/// Affected region files are rebuilt from their surviving chunks (so
+/// freed sectors are actually reclaimed) or deleted when nothing is
+/// left. Trimmed positions are dropped from `deltas` too.
+///
+/// `worldgen` MUST be the **base** generator, as for [`save_world`].
+pub fn trim_world(
+    dir: &Path,
+    gen_fp: u64,
+    worldgen: &dyn crate::worldgen::WorldGen,
+    deltas: Option<&DeltaStore>,
+) -> Result<TrimStats> {
+    let mut stats = TrimStats::default();
+    let region_dir = dir.join("region");
+    if !region_dir.is_dir() {
+        return Ok(stats);
+    }
+
+    let start = Instant::now();
+    let _io = REGION_IO.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = &*BLOCK_LOOKUP;
 
-        // Skip all-air sections.
-        if block_ids.iter().all(|&b| b == BlockId::AIR) {
+    for entry in fs::read_dir(&region_dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !name.ends_with(".mca") {
             continue;
         }
 
-        // Write blocks into the chunk using set_block.
-        let y_base = (section_idx as i64) * 16;
-        for y in 0..16u8 {
-            for z in 0..16u8 {
-                for x in 0..16u8 {
-                    let idx = (y as usize) * 256 + (z as usize) * 16 + (x as usize);
-                    let block = block_ids[idx];
-                    if block != BlockId::AIR {
-                        chunk.set_block(
-                            LocalBlockPos {
-                                x,
-                                y: y_base + y as i64,
-                                z,
-                            },
-                            block,
-                        );
+        let file_bytes = fs::read(&path)
+            .with_context(|| format!("reading region file {}", path.display()))?;
+        stats.bytes_before += file_bytes.len() as u64;
+        let mut region = fastanvil::Region::from_stream(Cursor::new(file_bytes))
+            .with_context(|| format!("parsing region file {}", path.display()))?;
+
+        let mut kept: Vec<(usize, usize, Vec<u8>)> = Vec::new();
+        let mut removed: Vec<ChunkPos> = Vec::new();
+        for x in 0..32usize {
+            for z in 0..32usize {
+                let Some(nbt_bytes) = region
+                    .read_chunk(x, z)
+                    .with_context(|| format!("reading chunk ({}, {}) from {}", x, z, name))?
+                else {
+                    continue;
+                };
+                stats.scanned += 1;
+
+                // Unparseable chunks are never ours to judge: keep them.
+                let pristine = match fastnbt::from_bytes::<ChunkNbt>(&nbt_bytes) {
+                    Ok(nbt) => regenerates_identically(&nbt, gen_fp, worldgen)
+                        .then(|| ChunkPos::new(nbt.x_pos, nbt.z_pos)),
+                    Err(e) => {
+                        tracing::warn!("Trim: keeping unreadable chunk ({}, {}) in {}: {}", x, z, name, e);
+                        None
                     }
+                };
+                match pristine {
+                    Some(pos) => removed.push(pos),
+                    None => kept.push((x, z, nbt_bytes)),
                 }
             }
         }
+        drop(region);
+
+        if removed.is_empty() {
+            stats.bytes_after += fs::metadata(&path)?.len();
+            continue;
+        }
+        stats.removed += removed.len();
+        if let Some(store) = deltas {
+            for pos in &removed {
+                store.remove(pos);
+            }
+        }
+
+        if kept.is_empty() {
+            fs::remove_file(&path)
+                .with_context(|| format!("deleting region file {}", path.display()))?;
+            stats.regions_deleted += 1;
+            continue;
+        }
+
+        let mut rebuilt = fastanvil::Region::new(Cursor::new(Vec::new()))
+            .with_context(|| format!("creating region {}", name))?;
+        for (x, z, nbt_bytes) in &kept {
+            rebuilt
+                .write_chunk(*x, *z, nbt_bytes)
+                .with_context(|| format!("rewriting chunk ({}, {}) in {}", x, z, name))?;
+        }
+        let mut cursor = rebuilt.into_inner()?;
+        let len = cursor.stream_position()?;
+        let data = cursor.into_inner();
+        fs::write(&path, &data[..len as usize])?;
+        stats.bytes_after += len;
+        stats.regions_rewritten += 1;
     }
 
-    chunk
+    tracing::info!(
+        "World trimmed: {}/{} chunks removed, {} regions rewritten, {} deleted, \
+         {:.1} MB → {:.1} MB ({:.2?})",
+        stats.removed,
+        stats.scanned,
+        stats.regions_rewritten,
+        stats.regions_deleted,
+        stats.bytes_before as f64 / 1e6,
+        stats.bytes_after as f64 / 1e6,
+        start.elapsed(),
+    );
+    Ok(stats)
+}
+
+/// Whether a saved chunk is indistinguishable from its regenerated baseline.
+fn regenerates_identically(
+    nbt: &ChunkNbt,
+    gen_fp: u64,
+    worldgen: &dyn crate::worldgen::WorldGen,
+) -> bool {
+    if let Some(delta) = &nbt.delta {
+        if delta.is_empty() {
+            return true;
+        }
+        let baseline = worldgen.generate_chunk(nbt.x_pos, nbt.z_pos, &World::new());
+        return delta.iter().all(|&packed| {
+            let (sy, cell, block) = unpack_delta(packed);
+            baseline.section(sy).map_or(BlockId::AIR, |s| s.get_by_index(cell)) == block
+        });
+    }
+    if nbt.gen_fp != Some(gen_fp as i64) {
+        return false;
+    }
+    let baseline = worldgen.generate_chunk(nbt.x_pos, nbt.z_pos, &World::new());
+    diff_cells(&nbt_to_chunk(nbt), &baseline).is_empty()
 }
 
 // ── Tests ────────────────────────────────────────────────────────────────────
@@ -1055,7 +1976,7 @@ mod tests {
         world.set_block(BlockPos::new(7, 2, 7), crate::block::SAND);
 
         let chunk_ref = world.get_chunk(&ChunkPos::new(0, 0)).unwrap();
-        let nbt = chunk_to_delta_nbt(ChunkPos::new(0, 0), &chunk_ref, 1, &generator);
+        let nbt = chunk_to_delta_nbt(ChunkPos::new(0, 0), &chunk_ref, 1, &generator, None);
         let delta = nbt.delta.expect("delta format");
         assert_eq!(delta.len(), 1, "one edit → one delta cell, got {}", delta.len());
         let (sy, cell, block) = unpack_delta(delta[0]);
@@ -1099,6 +2020,47 @@ mod tests {
         let _ = fs::remove_dir_all(&tmp);
     }
 
+    #[test]
+    fn test_resave_rediffs_only_dirty_sections() {
+        use ultimate_engine::world::position::BlockPos;
+        use crate::worldgen::WorldGen as _;
+
+        let base = FillGen(crate::block::STONE);
+        let store = new_delta_store();
+        let world = World::new();
+        base.ensure_generated(&world, 0, 0);
+        world.set_block(BlockPos::new(1, 2, 1), crate::block::SAND);
+        world.set_block(BlockPos::new(1, 40, 1), crate::block::DIRT);
+
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_section_resave");
+        let _ = fs::remove_dir_all(&tmp);
+        assert_eq!(save_world(&world, &tmp, 7, &base, Some(&store)).unwrap(), 1);
+
+        // A second edit in section 0 only: the section-2 cell is carried
+        // over from the stored delta, and the result matches a full diff.
+        world.set_block(BlockPos::new(9, 3, 9), crate::block::DIRT);
+        assert_eq!(world.dirty_sections(ChunkPos::new(0, 0)), vec![0]);
+        assert_eq!(save_world(&world, &tmp, 7, &base, Some(&store)).unwrap(), 1);
+        let stored = store.get(&ChunkPos::new(0, 0)).unwrap().to_vec();
+        let chunk = world.get_chunk(&ChunkPos::new(0, 0)).unwrap();
+        let baseline = base.generate_chunk(0, 0, &World::new());
+        assert_eq!(stored, diff_cells(&chunk, &baseline));
+        assert_eq!(stored.len(), 3);
+        drop(chunk);
+
+        // Edited and put back: nothing to write.
+        world.set_block(BlockPos::new(9, 3, 9), BlockId::AIR);
+        world.set_block(BlockPos::new(9, 3, 9), crate::block::DIRT);
+        assert_eq!(save_world(&world, &tmp, 7, &base, Some(&store)).unwrap(), 0);
+
+        let loaded = World::new();
+        load_into(&loaded, &tmp, 7, &base, None).unwrap();
+        assert_eq!(loaded.get_block(BlockPos::new(1, 40, 1)), crate::block::DIRT);
+        assert_eq!(loaded.get_block(BlockPos::new(9, 3, 9)), crate::block::DIRT);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
     #[test]
     fn test_legacy_full_chunks_skip_on_fingerprint_mismatch() {
         use ultimate_engine::world::position::BlockPos;
@@ -1138,4 +2100,145 @@ mod tests {
 
         let _ = fs::remove_dir_all(&tmp);
     }
+
+    #[test]
+    fn test_trim_removes_only_pristine_chunks() {
+        use ultimate_engine::world::position::BlockPos;
+
+        // Three saved chunks: one genuinely edited, two edited-then-
+        // reverted (empty deltas). Trim must drop exactly the reverted
+        // ones — deleting a region file that ends up empty — and the
+        // surviving edit must still load.
+        let generator = FillGen(crate::block::STONE);
+        let world = World::new();
+        for cx in [0, 1, 40] {
+            generator.ensure_generated(&world, cx, 0);
+        }
+        world.set_block(BlockPos::new(5, 10, 5), crate::block::SAND);
+        for x in [16, 640] {
+            world.set_block(BlockPos::new(x, 2, 0), crate::block::DIRT);
+            world.set_block(BlockPos::new(x, 2, 0), crate::block::STONE);
+        }
+
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_trim");
+        let _ = fs::remove_dir_all(&tmp);
+        let store = new_delta_store();
+        assert_eq!(save_world(&world, &tmp, 9, &generator, Some(&store)).unwrap(), 3);
+        assert!(tmp.join("region/r.1.0.mca").exists());
+
+        let stats = trim_world(&tmp, 9, &generator, Some(&store)).unwrap();
+        assert_eq!((stats.scanned, stats.removed), (3, 2));
+        assert_eq!((stats.regions_rewritten, stats.regions_deleted), (1, 1));
+        assert!(!tmp.join("region/r.1.0.mca").exists());
+        assert!(store.contains_key(&ChunkPos::new(0, 0)));
+        assert!(!store.contains_key(&ChunkPos::new(1, 0)));
+
+        let loaded = World::new();
+        assert_eq!(load_into(&loaded, &tmp, 9, &generator, None).unwrap(), 1);
+        assert_eq!(loaded.get_block(BlockPos::new(5, 10, 5)), crate::block::SAND);
+
+        // A second pass finds nothing left to trim.
+        let again = trim_world(&tmp, 9, &generator, None).unwrap();
+        assert_eq!((again.scanned, again.removed), (1, 0));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_level_dat_roundtrip() {
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_level_dat");
+        let _ = fs::remove_dir_all(&tmp);
+        assert_eq!(read_level_dat(&tmp).unwrap(), None);
+
+        let mut info = LevelInfo::new(-42, [8, 65, 8]);
+        info.time = 24_000;
+        write_level_dat(&tmp, &info).unwrap();
+        let bytes = fs::read(tmp.join("level.dat")).unwrap();
+        assert_eq!(&bytes[..2], &[0x1f, 0x8b], "level.dat must be gzipped");
+        assert_eq!(read_level_dat(&tmp).unwrap(), Some(info.clone()));
+
+        // Rewrites keep the previous file as level.dat_old.
+        info.time = 48_000;
+        write_level_dat(&tmp, &info).unwrap();
+        assert!(tmp.join("level.dat_old").exists());
+        assert_eq!(read_level_dat(&tmp).unwrap().unwrap().time, 48_000);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_storage_save_writes_level_dat_and_resumes_time() {
+        use ultimate_engine::world::position::BlockPos;
+
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_storage_level");
+        let _ = fs::remove_dir_all(&tmp);
+        let base: std::sync::Arc<dyn crate::worldgen::WorldGen> =
+            std::sync::Arc::new(FillGen(crate::block::STONE));
+
+        let mut seeded = LevelInfo::new(7, [0, 70, 0]);
+        seeded.time = 1_000;
+        write_level_dat(&tmp, &seeded).unwrap();
+
+        let storage = WorldStorage::new(tmp.clone(), 1, 7, base, new_delta_store());
+        let world = World::new();
+        world.set_block(BlockPos::new(1, 1, 1), crate::block::SAND);
+        assert_eq!(storage.save(&world).unwrap(), 1);
+
+        let info = read_level_dat(&tmp).unwrap().unwrap();
+        assert_eq!((info.seed, info.spawn), (7, [0, 70, 0]));
+        assert!(info.time >= 1_000, "time carries over from the previous level.dat");
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_saved_seed_wins_unless_forced() {
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_storage_seed");
+        let _ = fs::remove_dir_all(&tmp);
+        assert_eq!(resolve_seed(&tmp, 5, false).unwrap(), 5, "a new world takes the configured seed");
+
+        write_level_dat(&tmp, &LevelInfo::new(0xC0FFEE, [0, 70, 0])).unwrap();
+        assert_eq!(resolve_seed(&tmp, 5, false).unwrap(), 0xC0FFEE);
+        assert_eq!(resolve_seed(&tmp, 5, true).unwrap(), 5);
+
+        // A vanilla 64-bit seed isn't truncated into a different world.
+        let mut vanilla = LevelInfo::new(0, [0, 70, 0]);
+        vanilla.seed = u32::MAX as i64 + 0xC0FFEE;
+        write_level_dat(&tmp, &vanilla).unwrap();
+        assert!(resolve_seed(&tmp, 0xC0FFEE, false).is_err());
+        vanilla.seed = -42;
+        write_level_dat(&tmp, &vanilla).unwrap();
+        assert!(resolve_seed(&tmp, 5, false).is_err());
+        assert_eq!(resolve_seed(&tmp, 5, true).unwrap(), 5, "--seed still opens it");
+
+        // A level.dat that can't be read keeps its seed out of reach, not
+        // replaced by the configured one.
+        fs::write(tmp.join("level.dat"), b"not gzip").unwrap();
+        assert!(resolve_seed(&tmp, 5, false).is_err());
+        assert_eq!(resolve_seed(&tmp, 5, true).unwrap(), 5);
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
+
+    #[test]
+    fn test_gamerules_save_and_stop_time() {
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_storage_gamerules");
+        let _ = fs::remove_dir_all(&tmp);
+        let base: std::sync::Arc<dyn crate::worldgen::WorldGen> =
+            std::sync::Arc::new(FillGen(crate::block::STONE));
+        let storage = WorldStorage::new(tmp.clone(), 1, 7, base, new_delta_store());
+
+        assert_eq!(storage.set_game_rule(GameRule::DoDaylightCycle, "false").unwrap(), "false");
+        let frozen = storage.level_info().time;
+        std::thread::sleep(std::time::Duration::from_millis(120));
+        assert_eq!(storage.level_info().time, frozen, "time stands still without the daylight cycle");
+        assert!(storage.set_game_rule(GameRule::RandomTickSpeed, "lots").is_err());
+        assert_eq!(storage.set_game_rule(GameRule::RandomTickSpeed, "10").unwrap(), "10");
+
+        storage.save(&World::new()).unwrap();
+        let rules = read_level_dat(&tmp).unwrap().unwrap().game_rules;
+        assert_eq!((rules["doDaylightCycle"].as_str(), rules["randomTickSpeed"].as_str()), ("false", "10"));
+
+        let _ = fs::remove_dir_all(&tmp);
+    }
 }
@@ -62,15 +62,19 @@ use std::time::{Duration, Instant};
 use dashmap::DashMap;
 
 use ultimate_engine::causal::event::{Event, EventPayload};
-use ultimate_engine::causal::graph::CausalGraph;
+use ultimate_engine::causal::graph::{CausalGraph, RecentWindow};
 use ultimate_engine::causal::scheduler::Scheduler;
 use ultimate_engine::rules::RuleSet;
 use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::observer::WorldObserver;
 use ultimate_engine::world::position::{BlockPos, ChunkPos};
 use ultimate_engine::world::World;
 
-use crate::dashboard::DashboardState;
+use crate::dashboard::{CascadeKind, DashboardState, GraphSource};
 use crate::event_bus::{self, ChangeSource, SpatialBus};
+use crate::pending::PendingEvents;
+use crate::tickets::SimulationTickets;
+use crate::watchdog::{CascadeBudget, Watch};
 
 /// Regions are 2^REGION_BITS × 2^REGION_BITS chunks.
 const REGION_BITS: i32 = 2;
@@ -108,8 +112,32 @@ pub struct PhysicsOptions {
     /// [`cluster::owner_node`](crate::cluster::owner_node) isn't this
     /// node route over the peer link instead of to local workers.
     pub cluster: Option<ClusterCtx>,
+    /// Dedicated pool for parallel steps (see [`crate::pools`]). `None`
+    /// falls back to rayon's global pool.
+    pub cascade_pool: Option<Arc<rayon::ThreadPool>>,
+    /// Events a worker executes per step before it publishes changes and
+    /// checks its inbox again (minimum 1).
+    pub step_budget: usize,
+    /// Record every root submission for `--replay` (see [`crate::journal`]).
+    pub journal: Option<Arc<crate::journal::Recorder>>,
+    /// Recent events each worker's graph keeps for dashboard snapshots.
+    pub recent_window: RecentWindow,
+    /// Budgets past which a batch is aborted (see [`crate::watchdog`]).
+    pub watchdog: CascadeBudget,
+    /// Simulation distance (see [`crate::tickets`]). `None` simulates
+    /// every loaded chunk.
+    pub simulation: Option<Arc<SimulationTickets>>,
+    /// Where events waiting on unloaded or inactive chunks are kept (see
+    /// [`crate::pending`]). Those already runnable replay at startup.
+    pub parked: Arc<PendingEvents>,
 }
 
+/// Default [`PhysicsOptions::step_budget`]: small enough that a huge
+/// cascade streams to clients in many small batches, large enough that
+/// per-step overhead (publish, inbox drain, assignment refresh) stays
+/// negligible.
+pub const DEFAULT_STEP_BUDGET: usize = 2048;
+
 /// Cluster membership for this physics service: the full N-node mesh.
 #[derive(Clone)]
 pub struct ClusterCtx {
@@ -118,7 +146,19 @@ pub struct ClusterCtx {
 
 impl Default for PhysicsOptions {
     fn default() -> Self {
-        Self { workers: 0, pin_workers: false, rebalance: true, cluster: None }
+        Self {
+            workers: 0,
+            pin_workers: false,
+            rebalance: true,
+            cluster: None,
+            cascade_pool: None,
+            step_budget: DEFAULT_STEP_BUDGET,
+            journal: None,
+            recent_window: RecentWindow::default(),
+            watchdog: CascadeBudget::default(),
+            simulation: None,
+            parked: Arc::default(),
+        }
     }
 }
 
@@ -143,6 +183,7 @@ enum WorkerMsg {
     /// Cross-partition consequents with their inherited priorities.
     /// Inserted as roots: the causal parents live (executed) in the
     /// sender's graph; the channel carries the happens-before edge.
+    /// Also how parked events replay once their chunk can run them.
     Forward(Vec<(Event, u8)>),
 }
 
@@ -220,6 +261,7 @@ pub struct PhysicsHandle {
     pending: Arc<AtomicI64>,
     executed: Arc<AtomicU64>,
     cluster: Option<ClusterCtx>,
+    journal: Option<Arc<crate::journal::Recorder>>,
 }
 
 impl PhysicsHandle {
@@ -239,6 +281,9 @@ impl PhysicsHandle {
     }
 
     pub fn submit_action(&self, action: BlockAction) {
+        if let Some(journal) = &self.journal {
+            journal.record_action(&action);
+        }
         if let Some(node) = self.foreign_node(action.pos.chunk()) {
             // The owning node ingests it (fan-out, priority, stair hook)
             // and mirrors the results back via WriteSync.
@@ -262,6 +307,9 @@ impl PhysicsHandle {
         if events.is_empty() {
             return;
         }
+        if let Some(journal) = &self.journal {
+            journal.record_events(&events);
+        }
         let table = self.assignment.snapshot();
         let workers = self.txs.len();
         let mut per_worker: Vec<Vec<Event>> = vec![Vec::new(); workers];
@@ -358,7 +406,9 @@ pub fn start(
         let ctx = WorkerCtx {
             id,
             world: Arc::clone(&world),
-            rules: rules_factory(),
+            rules_generation: crate::rules::config::generation(),
+            rules: profiled(rules_factory(), &dashboard),
+            rules_factory,
             peers: txs.clone(),
             assignment: Arc::clone(&assignment),
             region_loads: Arc::clone(&region_loads),
@@ -367,6 +417,12 @@ pub fn start(
             pending: Arc::clone(&pending),
             executed: Arc::clone(&executed),
             cluster: opts.cluster.clone(),
+            cascade_pool: opts.cascade_pool.clone(),
+            parked: Arc::clone(&opts.parked),
+            step_budget: opts.step_budget.max(1),
+            recent_window: opts.recent_window,
+            watchdog: opts.watchdog,
+            simulation: opts.simulation.clone(),
         };
         let pin = if core_ids.is_empty() { None } else { Some(core_ids[id % core_ids.len()]) };
         std::thread::Builder::new()
@@ -382,6 +438,25 @@ pub fn start(
             .expect("spawning physics worker");
     }
 
+    let wake = BorderWake {
+        txs: txs.clone(),
+        assignment: Arc::clone(&assignment),
+        pending: Arc::clone(&pending),
+        parked: Arc::clone(&opts.parked),
+    };
+    if let Some(tickets) = &opts.simulation {
+        let wake = wake.clone();
+        tickets.on_activate(move |pos| wake.wake(pos));
+    }
+    // Events saved by the last run on chunks that can already run them.
+    let active = |c: ChunkPos| opts.simulation.as_ref().is_none_or(|t| t.is_active(c));
+    for chunk in opts.parked.chunks() {
+        if world.has_chunk(chunk) && active(chunk) {
+            wake.wake(chunk);
+        }
+    }
+    world.add_observer(Box::new(wake));
+
     if opts.rebalance && workers > 1 {
         let weak_assignment = Arc::downgrade(&assignment);
         let weak_loads = Arc::downgrade(&region_loads);
@@ -398,7 +473,7 @@ pub fn start(
         opts.cluster.as_ref().map(|c| format!("{}/{}", c.mesh.node_id, c.mesh.total_nodes))
             .unwrap_or_else(|| "single".into()),
     );
-    PhysicsHandle { txs, assignment, pending, executed, cluster: opts.cluster }
+    PhysicsHandle { txs, assignment, pending, executed, cluster: opts.cluster, journal: opts.journal }
 }
 
 // ── Worker ──────────────────────────────────────────────────────────────────
@@ -407,6 +482,10 @@ struct WorkerCtx {
     id: usize,
     world: Arc<World>,
     rules: RuleSet,
+    /// Rebuilds `rules` when the rule configuration changes.
+    rules_factory: fn() -> RuleSet,
+    /// `rules::config::generation()` that `rules` was built under.
+    rules_generation: u64,
     peers: Vec<mpsc::Sender<WorkerMsg>>,
     assignment: Arc<Assignment>,
     region_loads: Arc<DashMap<Region, u64>>,
@@ -415,25 +494,127 @@ struct WorkerCtx {
     pending: Arc<AtomicI64>,
     executed: Arc<AtomicU64>,
     cluster: Option<ClusterCtx>,
+    cascade_pool: Option<Arc<rayon::ThreadPool>>,
+    /// Events waiting on chunks that can't run them yet.
+    parked: Arc<PendingEvents>,
+    step_budget: usize,
+    recent_window: RecentWindow,
+    watchdog: CascadeBudget,
+    simulation: Option<Arc<SimulationTickets>>,
+}
+
+impl WorkerCtx {
+    /// Whether consequents aimed at `chunk` may run: it is loaded and
+    /// within some player's simulation distance.
+    fn runnable(&self, chunk: ChunkPos) -> bool {
+        self.world.has_chunk(chunk) && self.simulation.as_ref().is_none_or(|t| t.is_active(chunk))
+    }
+}
+
+/// Attach the dashboard's per-rule profile, if there is a dashboard.
+fn profiled(rules: RuleSet, dashboard: &Option<Arc<DashboardState>>) -> RuleSet {
+    match dashboard {
+        Some(dash) => rules.with_profile(dash.metrics.rule_profile()),
+        None => rules,
+    }
+}
+
+/// Replays the events parked on a chunk, on the worker that owns it, once
+/// the chunk loads or comes into simulation distance.
+#[derive(Clone)]
+struct BorderWake {
+    txs: Vec<mpsc::Sender<WorkerMsg>>,
+    assignment: Arc<Assignment>,
+    pending: Arc<AtomicI64>,
+    parked: Arc<PendingEvents>,
+}
+
+impl BorderWake {
+    fn wake(&self, pos: ChunkPos) {
+        let events = self.parked.take(pos);
+        if events.is_empty() {
+            return;
+        }
+        // Still not runnable (loaded but out of range, say)? The owner
+        // parks them again.
+        let worker = owner_of(pos, &self.assignment.snapshot(), self.txs.len());
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        if self.txs[worker].send(WorkerMsg::Forward(events)).is_err() {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl WorldObserver for BorderWake {
+    fn chunk_inserted(&self, pos: ChunkPos) {
+        self.wake(pos);
+    }
+}
+
+/// Move the graph's deferred events to the shared store, for
+/// [`BorderWake`] to replay. Any whose chunk is already runnable — its
+/// load or activation may have raced the move — go straight back in as
+/// roots, and `true` tells the caller to step again.
+fn park_deferred(ctx: &WorkerCtx, graph: &mut CausalGraph) -> bool {
+    let mut ready = false;
+    for (chunk, events) in graph.take_deferred() {
+        ctx.parked.park(chunk, events);
+        if ctx.runnable(chunk) {
+            for (event, priority) in ctx.parked.take(chunk) {
+                graph.insert_root_with_priority(event, priority);
+            }
+            ready = true;
+        }
+    }
+    ready
 }
 
-fn worker_loop(ctx: WorkerCtx, rx: mpsc::Receiver<WorkerMsg>) {
-    let mut graph = CausalGraph::with_pruning();
-    let scheduler = Scheduler::new();
+fn worker_loop(mut ctx: WorkerCtx, rx: mpsc::Receiver<WorkerMsg>) {
+    let mut graph = CausalGraph::with_pruning().with_recent_window(ctx.recent_window);
+    // Cascades reaching past the loaded area wait at the border instead of
+    // flowing into air and materializing empty chunks ahead of worldgen.
+    let mut scheduler = Scheduler::new().with_unloaded_deferral();
+    scheduler.max_events_per_step = ctx.step_budget;
+    // Outside every player's simulation distance they wait the same way.
+    if let Some(tickets) = &ctx.simulation {
+        let tickets = Arc::clone(tickets);
+        scheduler = scheduler.with_activity(Arc::new(move |chunk| tickets.is_active(chunk)));
+    }
+    if let Some(pool) = &ctx.cascade_pool {
+        scheduler = scheduler.with_pool(Arc::clone(pool));
+    }
+    if let Some(dash) = &ctx.dashboard {
+        scheduler = scheduler
+            .with_busy_counter(dash.metrics.cascade_busy_counter())
+            .with_conflict_counter(dash.metrics.block_conflict_counter());
+    }
     let workers = ctx.peers.len();
     let mut outbox: Vec<(usize, Event, u8)> = Vec::new();
     // Consequents owned by peer NODES (6f): shipped over the mesh after
     // each step, same post-execution ordering as local forwards —
     // happens-before rides the socket.
     let mut remote_outbox: Vec<(u32, Event, u8)> = Vec::new();
+    let rule_profile = ctx.dashboard.as_ref().map(|dash| dash.metrics.rule_profile());
 
     while let Ok(first) = rx.recv() {
+        // `/reloadrules`: cascades starting from here use the new rules.
+        let generation = crate::rules::config::generation();
+        if generation != ctx.rules_generation {
+            ctx.rules = profiled((ctx.rules_factory)(), &ctx.dashboard);
+            ctx.rules_generation = generation;
+        }
         let mut consumed: i64 = 0;
         let mut stair_hooks: Vec<BlockPos> = Vec::new();
         let executed_before = graph.executed_total();
+        let snapshot_cursor = graph.recent_cursor();
         let started = Instant::now();
+        let kind = root_kind(&ctx.world, &first);
+        let source = root_source(&first);
+        let root = root_event(&first);
+        let mut watch = Watch::new(ctx.watchdog);
+        let _cascade = tracing::debug_span!("cascade", worker = ctx.id, kind = kind.label()).entered();
 
-        ingest(&mut graph, first, &mut stair_hooks);
+        ingest(&ctx, &mut graph, first, &mut stair_hooks);
         consumed += 1;
 
         // Run to local quiescence: drain the inbox between steps, refresh
@@ -441,7 +622,7 @@ fn worker_loop(ctx: WorkerCtx, rx: mpsc::Receiver<WorkerMsg>) {
         // cascades reach clients while long background cascades continue.
         loop {
             while let Ok(msg) = rx.try_recv() {
-                ingest(&mut graph, msg, &mut stair_hooks);
+                ingest(&ctx, &mut graph, msg, &mut stair_hooks);
                 consumed += 1;
             }
 
@@ -504,10 +685,24 @@ fn worker_loop(ctx: WorkerCtx, rx: mpsc::Receiver<WorkerMsg>) {
                 }
             }
 
+            let executed = graph.executed_total() - executed_before;
+            if let Some(overrun) = watch.check(executed, rule_profile.as_deref()) {
+                let dropped = graph.abandon_pending();
+                crate::watchdog::report(ctx.id, root.as_ref(), &overrun, dropped);
+                if let Some(dash) = &ctx.dashboard {
+                    dash.metrics.record_aborted_cascade();
+                }
+                park_deferred(&ctx, &mut graph);
+                break;
+            }
+
             if n == 0 {
+                if park_deferred(&ctx, &mut graph) {
+                    continue;
+                }
                 match rx.try_recv() {
                     Ok(msg) => {
-                        ingest(&mut graph, msg, &mut stair_hooks);
+                        ingest(&ctx, &mut graph, msg, &mut stair_hooks);
                         consumed += 1;
                     }
                     Err(_) => break,
@@ -518,10 +713,9 @@ fn worker_loop(ctx: WorkerCtx, rx: mpsc::Receiver<WorkerMsg>) {
         // Post-batch: stair rewrites (read the settled world), final publish.
         let mut extra_changes: Vec<(BlockPos, BlockId)> = Vec::new();
         for pos in stair_hooks {
-            for (npos, new_id) in crate::placement::update_adjacent_stair_shapes(&ctx.world, pos) {
-                ctx.world.set_block(npos, new_id);
-                extra_changes.push((npos, new_id));
-            }
+            let updates = crate::placement::update_adjacent_stair_shapes(&ctx.world, pos);
+            ctx.world.apply_batch(&updates);
+            extra_changes.extend(updates);
         }
         publish_writes(&ctx, &mut graph, &mut extra_changes);
 
@@ -530,8 +724,8 @@ fn worker_loop(ctx: WorkerCtx, rx: mpsc::Receiver<WorkerMsg>) {
         ctx.executed.fetch_add(executed_delta, Ordering::Relaxed);
 
         if let Some(dash) = &ctx.dashboard {
-            dash.metrics.record_cascade(executed_delta, elapsed);
-            dash.publish_graph(crate::dashboard::snapshot_graph(&graph));
+            dash.metrics.record_cascade(kind, executed_delta, elapsed);
+            dash.publish_graph(source, ctx.id, crate::dashboard::snapshot_since(&graph, snapshot_cursor));
         }
         if executed_delta > 0 {
             tracing::debug!(
@@ -585,6 +779,7 @@ fn publish_writes(ctx: &WorkerCtx, graph: &mut CausalGraph, extra: &mut Vec<(Blo
     // Spatial delivery (6f): each change reaches only the connections
     // subscribed near it — O(nearby players), not O(all players).
     ctx.bus.publish_world(ChangeSource::Physics, changes, light_changes);
+    ctx.bus.publish_effects(ChangeSource::Physics, crate::effects::from_writes(&log));
 
     // 6f: mirror this node's executed writes to every peer so their
     // replica worlds (and their connected clients) see physics computed
@@ -596,21 +791,56 @@ fn publish_writes(ctx: &WorkerCtx, graph: &mut CausalGraph, extra: &mut Vec<(Blo
     }
 }
 
-fn ingest(graph: &mut CausalGraph, msg: WorkerMsg, stair_hooks: &mut Vec<BlockPos>) {
+/// Dashboard bucket for a cascade, from the message that started it.
+/// What started the batch led by `msg`, for the dashboard's graph view.
+fn root_source(msg: &WorkerMsg) -> GraphSource {
+    match msg {
+        WorkerMsg::Action(_) => GraphSource::Player,
+        WorkerMsg::Events(_) => GraphSource::Simulation,
+        WorkerMsg::Forward(_) => GraphSource::Forwarded,
+    }
+}
+
+/// The event that started the batch led by `msg`, for the watchdog.
+fn root_event(msg: &WorkerMsg) -> Option<EventPayload> {
+    match msg {
+        WorkerMsg::Action(a) => Some(EventPayload::BlockSet { pos: a.pos, old: a.old, new: a.new }),
+        WorkerMsg::Events(events) => events.first().map(|e| e.payload.clone()),
+        WorkerMsg::Forward(events) => events.first().map(|(e, _)| e.payload.clone()),
+    }
+}
+
+fn root_kind(world: &World, msg: &WorkerMsg) -> CascadeKind {
+    let first = match msg {
+        WorkerMsg::Action(a) => return CascadeKind::of(a.new),
+        WorkerMsg::Events(events) => events.first(),
+        WorkerMsg::Forward(events) => events.first().map(|(e, _)| e),
+    };
+    match first.map(|e| &e.payload) {
+        Some(EventPayload::BlockSet { new, .. }) => CascadeKind::of(*new),
+        Some(EventPayload::BlockNotify { pos, .. }) => CascadeKind::of(world.get_block(*pos)),
+        _ => CascadeKind::Other,
+    }
+}
+
+/// Insert a player action's roots. Player actions ride the priority lane;
+/// the notify fan-out (each told which side the change is on) and the
+/// whole cascade inherit it.
+pub(crate) fn insert_action(graph: &mut CausalGraph, a: &BlockAction) {
+    let root = graph.insert_root_with_priority(
+        Event { payload: EventPayload::BlockSet { pos: a.pos, old: a.old, new: a.new } },
+        PRIO_PLAYER,
+    );
+    for (dir, neighbor) in a.pos.directions() {
+        let from = Some(dir.opposite());
+        graph.insert(Event { payload: EventPayload::BlockNotify { pos: neighbor, from } }, vec![root]);
+    }
+}
+
+fn ingest(ctx: &WorkerCtx, graph: &mut CausalGraph, msg: WorkerMsg, stair_hooks: &mut Vec<BlockPos>) {
     match msg {
         WorkerMsg::Action(a) => {
-            // Player actions ride the priority lane; the notify fan-out
-            // and the whole cascade inherit it.
-            let root = graph.insert_root_with_priority(
-                Event { payload: EventPayload::BlockSet { pos: a.pos, old: a.old, new: a.new } },
-                PRIO_PLAYER,
-            );
-            for neighbor in a.pos.neighbors() {
-                graph.insert(
-                    Event { payload: EventPayload::BlockNotify { pos: neighbor } },
-                    vec![root],
-                );
-            }
+            insert_action(graph, &a);
             if a.update_stairs {
                 stair_hooks.push(a.pos);
             }
@@ -621,8 +851,19 @@ fn ingest(graph: &mut CausalGraph, msg: WorkerMsg, stair_hooks: &mut Vec<BlockPo
             }
         }
         WorkerMsg::Forward(events) => {
+            // A forwarded consequent is still a consequent: if it crossed
+            // into a chunk that isn't loaded or simulating, park it here on
+            // its owner. The same goes for replayed events.
             for (event, prio) in events {
-                graph.insert_root_with_priority(event, prio);
+                let block = matches!(
+                    event.payload,
+                    EventPayload::BlockSet { .. } | EventPayload::BlockNotify { .. }
+                );
+                if block && !ctx.runnable(event.chunk()) {
+                    graph.defer(event, prio);
+                } else {
+                    graph.insert_root_with_priority(event, prio);
+                }
             }
         }
     }
@@ -9,13 +9,25 @@ use azalea_block::{BlockState, BlockTrait};
 use azalea_core::direction::Direction;
 
 use ultimate_engine::world::block::BlockId;
-use ultimate_engine::world::position::BlockPos as EngineBlockPos;
+use ultimate_engine::world::position::{BlockPos as EngineBlockPos, Direction as EngineDirection};
 use ultimate_engine::world::World;
 
 use crate::persistence::lookup_block_state;
 
 // ── Public API ──────────────────────────────────────────────────────────────
 
+/// The engine's [`EngineDirection`] for a protocol direction.
+pub fn engine_direction(dir: Direction) -> EngineDirection {
+    match dir {
+        Direction::Down => EngineDirection::Down,
+        Direction::Up => EngineDirection::Up,
+        Direction::North => EngineDirection::North,
+        Direction::South => EngineDirection::South,
+        Direction::West => EngineDirection::West,
+        Direction::East => EngineDirection::East,
+    }
+}
+
 /// Compute the correctly-oriented block state for a placed block.
 ///
 /// * `default_state` – the default `BlockState` for this `BlockKind`
@@ -576,6 +588,15 @@ mod tests {
         assert_eq!(cardinal_opposite_of_yaw(-90.0), "west"); // -90 == 270
     }
 
+    #[test]
+    fn test_engine_direction_matches_protocol_normals() {
+        for dir in [Direction::Down, Direction::Up, Direction::North, Direction::South, Direction::West, Direction::East] {
+            let n = dir.normal();
+            assert_eq!(engine_direction(dir).offset(), [n.x as i64, n.y as i64, n.z as i64], "{dir:?}");
+            assert_eq!(engine_direction(dir.opposite()), engine_direction(dir).opposite());
+        }
+    }
+
     #[test]
     fn test_axis_from_face() {
         assert_eq!(axis_from_hit_face(Direction::Up), "y");
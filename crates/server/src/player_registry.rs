@@ -5,11 +5,16 @@
 
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicI32, Ordering};
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
+use azalea_auth::game_profile::GameProfileProperties;
+use azalea_core::game_type::GameMode;
 use tokio::sync::broadcast;
 use uuid::Uuid;
 
+use crate::appearance::Appearance;
+
 /// Information about a connected player, stored in the registry.
 #[derive(Clone, Debug)]
 pub struct PlayerInfo {
@@ -17,12 +22,19 @@ pub struct PlayerInfo {
     pub entity_id: i32,
     pub uuid: Uuid,
     pub name: String,
+    /// Profile properties (skin textures) sent in tab-list entries.
+    pub properties: Arc<GameProfileProperties>,
     pub x: f64,
     pub y: f64,
     pub z: f64,
     pub y_rot: f32,
     pub x_rot: f32,
     pub on_ground: bool,
+    pub game_mode: GameMode,
+    /// Keep-alive round trip in milliseconds, shown in the tab list.
+    pub latency: i32,
+    /// Sneaking, sprinting and the held item (see `appearance`).
+    pub appearance: Appearance,
 }
 
 /// Lifecycle events broadcast to all connections.
@@ -33,11 +45,13 @@ pub enum PlayerEvent {
         entity_id: i32,
         uuid: Uuid,
         name: String,
+        properties: Arc<GameProfileProperties>,
         x: f64,
         y: f64,
         z: f64,
         y_rot: f32,
         x_rot: f32,
+        game_mode: GameMode,
     },
     Left {
         conn_id: u64,
@@ -48,6 +62,7 @@ pub enum PlayerEvent {
     Moved {
         conn_id: u64,
         entity_id: i32,
+        uuid: Uuid,
         x: f64,
         y: f64,
         z: f64,
@@ -55,11 +70,87 @@ pub enum PlayerEvent {
         x_rot: f32,
         on_ground: bool,
     },
+    /// A player swung an arm. Routed like moves, to connections nearby.
+    Swung {
+        conn_id: u64,
+        entity_id: i32,
+        x: f64,
+        z: f64,
+        off_hand: bool,
+    },
     /// A player sent a chat message.
     Chat {
         conn_id: u64,
+        uuid: Uuid,
         name: String,
         message: String,
+        /// Client timestamp (epoch millis) and salt, echoed in the
+        /// player-chat body.
+        timestamp: u64,
+        salt: u64,
+        /// The sender's running message count this session.
+        index: u32,
+    },
+    /// A player started or stopped sneaking or sprinting (`pose`), or
+    /// changed what they hold (`held`).
+    AppearanceChanged {
+        conn_id: u64,
+        entity_id: i32,
+        appearance: Appearance,
+        pose: bool,
+        held: bool,
+    },
+    /// A player's game mode changed (see `gamemode`).
+    GameModeChanged {
+        entity_id: i32,
+        uuid: Uuid,
+        game_mode: GameMode,
+    },
+    /// A player must be disconnected (banned, kicked). Only the
+    /// connection owning `uuid` acts on it.
+    Kicked {
+        uuid: Uuid,
+        reason: String,
+    },
+    /// A player must be moved (see `teleport`). Only the connection
+    /// owning `uuid` acts on it.
+    Teleport {
+        uuid: Uuid,
+        to: [f64; 3],
+    },
+    /// A system message for one player. Only the connection owning
+    /// `uuid` shows it.
+    Message {
+        uuid: Uuid,
+        message: String,
+    },
+    /// A server-wide system message (admin broadcast).
+    Announcement {
+        message: String,
+    },
+    /// Rain started or stopped (see `weather`).
+    WeatherChanged {
+        raining: bool,
+    },
+    /// A player or hostile mob landed a melee hit (see `combat`, `mobs`).
+    /// Only the connection owning `target` acts on it.
+    Attacked {
+        target: Uuid,
+        attacker: String,
+        damage: f32,
+        /// Where the attacker stood, for knockback.
+        from: [f64; 3],
+    },
+    /// A player took damage; `yaw` is where the hit came from, relative to
+    /// where they face.
+    Hurt {
+        entity_id: i32,
+        yaw: f32,
+    },
+    /// A player died; `message` is the death message.
+    Died {
+        entity_id: i32,
+        message: String,
     },
 }
 
@@ -75,6 +166,23 @@ pub struct PlayerRegistry {
     /// Movement goes SPATIAL (Phase 6f): delivered only to connections
     /// subscribed near the mover — O(nearby), not O(all players).
     spatial: std::sync::Arc<crate::event_bus::SpatialBus>,
+    /// Status bars shown to online players.
+    pub boss_bars: crate::bossbar::BossBarManager,
+    /// Pending `/tpa` requests.
+    pub teleports: crate::teleport::TeleportRequests,
+    /// Custom-payload channels online players are served on.
+    pub channels: crate::channels::PluginChannels,
+    /// Tab list header, footer and latencies.
+    pub tab_list: crate::tablist::TabList,
+    /// Per-player action log; off until opened at startup.
+    pub audit: crate::audit::AuditLog,
+    /// TPS and MSPT, kept up by `ticks::start`.
+    pub ticks: crate::ticks::TickMeter,
+    /// Rain or clear, run by `weather::WeatherLayer`.
+    pub weather: crate::weather::Weather,
+    /// Simulation tickets kept centred on each player, if physics is
+    /// gated on simulation distance.
+    simulation: Option<Arc<crate::tickets::SimulationTickets>>,
 }
 
 impl PlayerRegistry {
@@ -89,9 +197,23 @@ impl PlayerRegistry {
             next_entity_id: AtomicI32::new(1),
             event_tx,
             spatial,
+            boss_bars: crate::bossbar::BossBarManager::new(),
+            teleports: crate::teleport::TeleportRequests::default(),
+            channels: crate::channels::PluginChannels::new(),
+            tab_list: crate::tablist::TabList::new(),
+            audit: crate::audit::AuditLog::default(),
+            ticks: crate::ticks::TickMeter::default(),
+            weather: crate::weather::Weather::default(),
+            simulation: None,
         }
     }
 
+    /// Keep `tickets` centred on every registered player.
+    pub fn with_simulation(mut self, tickets: Arc<crate::tickets::SimulationTickets>) -> Self {
+        self.simulation = Some(tickets);
+        self
+    }
+
     /// Allocate a unique entity ID for a new player.
     pub fn allocate_entity_id(&self) -> i32 {
         self.next_entity_id.fetch_add(1, Ordering::Relaxed)
@@ -102,16 +224,23 @@ impl PlayerRegistry {
     /// Call this *after* you have already sent existing-player info to the
     /// newcomer, so the newcomer doesn't receive its own join event.
     pub fn register(&self, info: PlayerInfo) {
+        let pos = [info.x.floor() as i64, info.y.floor() as i64, info.z.floor() as i64];
+        self.audit.record(info.uuid, &info.name, crate::audit::Action::Login { pos });
+        if let Some(tickets) = &self.simulation {
+            tickets.set_player(info.conn_id, info.x, info.z);
+        }
         let event = PlayerEvent::Joined {
             conn_id: info.conn_id,
             entity_id: info.entity_id,
             uuid: info.uuid,
             name: info.name.clone(),
+            properties: Arc::clone(&info.properties),
             x: info.x,
             y: info.y,
             z: info.z,
             y_rot: info.y_rot,
             x_rot: info.x_rot,
+            game_mode: info.game_mode,
         };
         self.players
             .write()
@@ -132,7 +261,7 @@ impl PlayerRegistry {
         x_rot: f32,
         on_ground: bool,
     ) {
-        let entity_id = {
+        let (entity_id, uuid) = {
             let mut players = self.players.write().expect("player registry poisoned");
             let Some(info) = players.get_mut(&conn_id) else {
                 return;
@@ -143,11 +272,15 @@ impl PlayerRegistry {
             info.y_rot = y_rot;
             info.x_rot = x_rot;
             info.on_ground = on_ground;
-            info.entity_id
+            (info.entity_id, info.uuid)
         };
+        if let Some(tickets) = &self.simulation {
+            tickets.set_player(conn_id, x, z);
+        }
         self.spatial.publish_move(PlayerEvent::Moved {
             conn_id,
             entity_id,
+            uuid,
             x,
             y,
             z,
@@ -157,15 +290,151 @@ impl PlayerRegistry {
         });
     }
 
+    /// Show the connection `conn_id`'s player swinging an arm to players
+    /// nearby, broadcasting `PlayerEvent::Swung`.
+    pub fn swing(&self, conn_id: u64, off_hand: bool) {
+        let Some((entity_id, x, z)) = self
+            .players
+            .read()
+            .expect("player registry poisoned")
+            .get(&conn_id)
+            .map(|info| (info.entity_id, info.x, info.z))
+        else {
+            return;
+        };
+        self.spatial.publish_move(PlayerEvent::Swung { conn_id, entity_id, x, z, off_hand });
+    }
+
     /// Broadcast a chat message from a player.
-    pub fn broadcast_chat(&self, conn_id: u64, name: &str, message: &str) {
+    pub fn broadcast_chat(&self, conn_id: u64, uuid: Uuid, name: &str, message: &str, timestamp: u64, salt: u64, index: u32) {
         let _ = self.event_tx.send(PlayerEvent::Chat {
             conn_id,
+            uuid,
             name: name.to_owned(),
             message: message.to_owned(),
+            timestamp,
+            salt,
+            index,
         });
     }
 
+    /// Set an online player's game mode, broadcasting
+    /// `PlayerEvent::GameModeChanged`. Returns `false` if they're offline.
+    pub fn set_game_mode(&self, uuid: Uuid, game_mode: GameMode) -> bool {
+        let entity_id = {
+            let mut players = self.players.write().expect("player registry poisoned");
+            let Some(info) = players.values_mut().find(|p| p.uuid == uuid) else {
+                return false;
+            };
+            info.game_mode = game_mode;
+            info.entity_id
+        };
+        let _ = self.event_tx.send(PlayerEvent::GameModeChanged { entity_id, uuid, game_mode });
+        true
+    }
+
+    /// Change how the connection `conn_id`'s player looks, broadcasting
+    /// `PlayerEvent::AppearanceChanged` if anything did.
+    pub fn update_appearance(&self, conn_id: u64, change: impl FnOnce(&mut Appearance)) {
+        let (entity_id, appearance, pose, held) = {
+            let mut players = self.players.write().expect("player registry poisoned");
+            let Some(info) = players.get_mut(&conn_id) else {
+                return;
+            };
+            let before = info.appearance.clone();
+            change(&mut info.appearance);
+            let pose = info.appearance.pose_differs(&before);
+            let held = info.appearance.main_hand != before.main_hand;
+            if !pose && !held {
+                return;
+            }
+            (info.entity_id, info.appearance.clone(), pose, held)
+        };
+        let _ = self.event_tx.send(PlayerEvent::AppearanceChanged { conn_id, entity_id, appearance, pose, held });
+    }
+
+    /// Record a keep-alive round trip for the connection `conn_id`; the
+    /// tab list shows it from its next refresh.
+    pub fn set_latency(&self, conn_id: u64, latency: Duration) {
+        let mut players = self.players.write().expect("player registry poisoned");
+        if let Some(info) = players.get_mut(&conn_id) {
+            info.latency = latency.as_millis().min(i32::MAX as u128) as i32;
+        }
+    }
+
+    /// Move the player with `uuid`, if online, to `to`.
+    pub fn teleport(&self, uuid: Uuid, to: [f64; 3]) {
+        let _ = self.event_tx.send(PlayerEvent::Teleport { uuid, to });
+    }
+
+    /// Show `message` to the player with `uuid`, if online, as system chat.
+    pub fn tell(&self, uuid: Uuid, message: &str) {
+        let _ = self.event_tx.send(PlayerEvent::Message {
+            uuid,
+            message: message.to_owned(),
+        });
+    }
+
+    /// Disconnect the player with `uuid`, if online, with `reason`.
+    pub fn kick(&self, uuid: Uuid, reason: &str) {
+        let _ = self.event_tx.send(PlayerEvent::Kicked {
+            uuid,
+            reason: reason.to_owned(),
+        });
+    }
+
+    /// Show `message` to every connected player as system chat.
+    pub fn announce(&self, message: &str) {
+        let _ = self.event_tx.send(PlayerEvent::Announcement {
+            message: message.to_owned(),
+        });
+    }
+
+    /// Tell every connected player that rain started or stopped.
+    pub fn announce_weather(&self, raining: bool) {
+        let _ = self.event_tx.send(PlayerEvent::WeatherChanged { raining });
+    }
+
+    /// Send a melee hit to the player with `target`, if online.
+    pub fn attack(&self, target: Uuid, attacker: &str, damage: f32, from: [f64; 3]) {
+        let _ = self.event_tx.send(PlayerEvent::Attacked {
+            target,
+            attacker: attacker.to_owned(),
+            damage,
+            from,
+        });
+    }
+
+    /// Tell everyone the player with `entity_id` was hurt.
+    pub fn hurt(&self, entity_id: i32, yaw: f32) {
+        let _ = self.event_tx.send(PlayerEvent::Hurt { entity_id, yaw });
+    }
+
+    /// Tell everyone the player with `entity_id` died.
+    pub fn died(&self, entity_id: i32, message: String) {
+        let _ = self.event_tx.send(PlayerEvent::Died { entity_id, message });
+    }
+
+    /// Look up an online player by entity ID.
+    pub fn find_by_entity_id(&self, entity_id: i32) -> Option<PlayerInfo> {
+        self.players
+            .read()
+            .expect("player registry poisoned")
+            .values()
+            .find(|p| p.entity_id == entity_id)
+            .cloned()
+    }
+
+    /// Look up an online player by name (case-insensitive, like vanilla).
+    pub fn find_by_name(&self, name: &str) -> Option<PlayerInfo> {
+        self.players
+            .read()
+            .expect("player registry poisoned")
+            .values()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
     /// Remove a player and broadcast `PlayerEvent::Left`.
     pub fn deregister(&self, conn_id: u64) {
         let info = self
@@ -173,7 +442,11 @@ impl PlayerRegistry {
             .write()
             .expect("player registry poisoned")
             .remove(&conn_id);
+        if let Some(tickets) = &self.simulation {
+            tickets.remove_player(conn_id);
+        }
         if let Some(info) = info {
+            self.audit.record(info.uuid, &info.name, crate::audit::Action::Logout);
             let _ = self.event_tx.send(PlayerEvent::Left {
                 conn_id: info.conn_id,
                 entity_id: info.entity_id,
@@ -192,6 +465,18 @@ impl PlayerRegistry {
             .collect()
     }
 
+    /// Players whose horizontal position is within `radius` blocks of
+    /// (`x`, `z`) on both axes.
+    pub fn players_within(&self, x: f64, z: f64, radius: f64) -> Vec<PlayerInfo> {
+        self.players
+            .read()
+            .expect("player registry poisoned")
+            .values()
+            .filter(|p| (p.x - x).abs() <= radius && (p.z - z).abs() <= radius)
+            .cloned()
+            .collect()
+    }
+
     /// Number of currently connected players.
     pub fn player_count(&self) -> usize {
         self.players
@@ -0,0 +1,50 @@
+//! Per-player data kept with the world: `playerdata/<uuid>.json` under
+//! the world directory, one small file per player who has any.
+//!
+//! Today that is the `/sethome` position and the inventory, saved when
+//! the player leaves. Vanilla's `playerdata/*.dat` files are left alone,
+//! so an imported world keeps them for when positions are persisted too.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::items::SavedItem;
+
+/// Directory of player files, relative to the world directory.
+pub const PLAYERDATA_DIR: &str = "playerdata";
+
+/// Everything saved for one player.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerData {
+    /// Where `/home` goes.
+    #[serde(default)]
+    pub home: Option<[f64; 3]>,
+    /// Inventory window slots with an item in them, by slot number.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inventory: BTreeMap<usize, SavedItem>,
+}
+
+fn path(world_dir: &Path, uuid: Uuid) -> PathBuf {
+    world_dir.join(PLAYERDATA_DIR).join(format!("{}.json", uuid))
+}
+
+/// Load `uuid`'s data (defaults if they have none).
+pub fn load(world_dir: &Path, uuid: Uuid) -> Result<PlayerData> {
+    let path = path(world_dir, uuid);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(PlayerData::default()),
+        Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+pub fn save(world_dir: &Path, uuid: Uuid, data: &PlayerData) -> Result<()> {
+    let path = path(world_dir, uuid);
+    std::fs::create_dir_all(world_dir.join(PLAYERDATA_DIR))?;
+    let json = serde_json::to_string_pretty(data)?;
+    std::fs::write(&path, json).with_context(|| format!("writing {}", path.display()))
+}
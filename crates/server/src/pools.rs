@@ -0,0 +1,107 @@
+//! Dedicated thread pools, isolated from the tokio runtime.
+//!
+//! Tokio's worker threads drive every connection's keep-alives; anything
+//! that holds one for milliseconds (a save, a whole-world trim, a
+//! simulation layer scanning chunks) shows up as latency spikes for every
+//! player. Rayon's global pool is no better a home: it sizes itself to
+//! all cores and competes with tokio for them. The server owns two sized
+//! pools instead:
+//!
+//! - **cascade** — parallel causal steps (`Scheduler::with_pool`).
+//! - **blocking** — simulation ticks and persistence (autosave, `/trim`),
+//!   awaited from async code through [`Pools::run_blocking`].
+//!
+//! Both report busy time to the dashboard metrics so utilization is
+//! visible next to cascade latency.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::config::PhysicsConfig;
+use crate::dashboard::DashboardState;
+
+/// The server's cascade and blocking pools.
+pub struct Pools {
+    cascade: Arc<ThreadPool>,
+    blocking: ThreadPool,
+    dashboard: Arc<DashboardState>,
+}
+
+impl Pools {
+    /// Build both pools sized from `cfg` (`0` = auto) and publish their
+    /// sizes to the dashboard.
+    pub fn new(cfg: &PhysicsConfig, dashboard: Arc<DashboardState>) -> Result<Self> {
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let cascade_threads = if cfg.cascade_threads == 0 { (cores / 2).max(1) } else { cfg.cascade_threads };
+        let blocking_threads = if cfg.blocking_threads == 0 { 2 } else { cfg.blocking_threads };
+
+        let cascade = ThreadPoolBuilder::new()
+            .num_threads(cascade_threads)
+            .thread_name(|i| format!("cascade-{i}"))
+            .build()
+            .context("building cascade pool")?;
+        let blocking = ThreadPoolBuilder::new()
+            .num_threads(blocking_threads)
+            .thread_name(|i| format!("blocking-{i}"))
+            .build()
+            .context("building blocking pool")?;
+
+        dashboard.metrics.set_pool_threads(cascade_threads, blocking_threads);
+        tracing::info!(
+            "Thread pools: {} cascade, {} blocking",
+            cascade_threads, blocking_threads,
+        );
+        Ok(Self { cascade: Arc::new(cascade), blocking, dashboard })
+    }
+
+    /// The pool parallel cascades run on.
+    pub fn cascade(&self) -> Arc<ThreadPool> {
+        Arc::clone(&self.cascade)
+    }
+
+    /// Run `f` on the blocking pool and await its result without holding
+    /// a tokio worker. A panic in `f` is resumed in the caller.
+    pub async fn run_blocking<T, F>(&self, f: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let dashboard = Arc::clone(&self.dashboard);
+        self.blocking.spawn(move || {
+            let started = Instant::now();
+            let out = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+            dashboard.metrics.record_blocking_job(started.elapsed());
+            let _ = tx.send(out);
+        });
+        match rx.await.expect("blocking pool dropped a job") {
+            Ok(v) => v,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultimate_engine::world::World;
+
+    #[tokio::test]
+    async fn test_run_blocking_off_runtime_and_metered() {
+        let dashboard = Arc::new(DashboardState::new(Arc::new(World::new())));
+        let cfg = PhysicsConfig { cascade_threads: 1, blocking_threads: 1, ..Default::default() };
+        let pools = Pools::new(&cfg, Arc::clone(&dashboard)).unwrap();
+
+        let name = pools
+            .run_blocking(|| std::thread::current().name().map(str::to_owned))
+            .await;
+        assert_eq!(name.as_deref(), Some("blocking-0"));
+
+        let snap = dashboard.metrics.snapshot(0);
+        assert_eq!((snap.cascade_threads, snap.blocking_threads), (1, 1));
+        assert_eq!(snap.blocking_jobs, 1);
+    }
+}
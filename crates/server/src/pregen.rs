@@ -0,0 +1,83 @@
+//! Chunk pre-generation (`--pregen <radius>`): generate every chunk in a
+//! square around spawn on all cores, so players exploring it later don't
+//! wait on the generator.
+//!
+//! Terrain is normally saved only where players changed it and
+//! regenerated otherwise (see `persistence::save_world`); pre-generated
+//! chunks are written in full under the current generator fingerprint
+//! instead ([`crate::persistence::save_full_chunks`]), so later starts load
+//! them rather than generate them. `--trim` removes them again, and a
+//! preset or seed change makes them stale like any full-section chunk.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::prelude::*;
+use ultimate_engine::world::World;
+use ultimate_engine::world::position::ChunkPos;
+
+use crate::worldgen::WorldGen;
+
+/// Generate every chunk within `radius` chunks of `center` that `world`
+/// doesn't have yet, nearest first, on rayon's global pool (all cores).
+///
+/// `progress` is called as `(done, total)` after each chunk, from
+/// whichever thread generated it. Returns the chunks generated.
+pub fn pregenerate(
+    world: &World,
+    worldgen: &dyn WorldGen,
+    center: ChunkPos,
+    radius: i32,
+    progress: &(dyn Fn(usize, usize) + Sync),
+) -> Vec<ChunkPos> {
+    let mut missing: Vec<ChunkPos> = center.chunks_in_radius(radius).filter(|pos| !world.has_chunk(*pos)).collect();
+    missing.sort_by_key(|pos| pos.chebyshev(center));
+
+    let total = missing.len();
+    let done = AtomicUsize::new(0);
+    missing.par_iter().for_each(|pos| {
+        worldgen.ensure_generated(world, pos.x, pos.z);
+        progress(done.fetch_add(1, Ordering::Relaxed) + 1, total);
+    });
+    missing
+}
+
+/// Whether `done` of `total` is the first count at or past a new tenth,
+/// for logging progress at 10%, 20%, ... from many threads at once.
+pub fn crossed_tenth(done: usize, total: usize) -> bool {
+    total > 0 && done * 10 / total != (done - 1) * 10 / total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worldgen::biome::Biome;
+    use crate::worldgen::pipeline::FlatPipeline;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_pregenerate_fills_radius_and_saves() {
+        let worldgen = FlatPipeline { min_y: 0, layers: vec![(crate::block::STONE, 4)], biome: Biome::Plains };
+        let world = World::new();
+        worldgen.ensure_generated(&world, 10, 10);
+
+        let seen = Mutex::new(Vec::new());
+        let generated = pregenerate(&world, &worldgen, ChunkPos::new(10, 10), 2, &|done, total| {
+            seen.lock().unwrap().push((done, total));
+        });
+        assert_eq!(generated.len(), 24, "a 5x5 square minus the chunk already there");
+        assert_eq!(world.chunk_count(), 25);
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort_unstable();
+        assert_eq!(seen.last(), Some(&(24, 24)));
+        assert_eq!(seen.iter().filter(|(done, total)| crossed_tenth(*done, *total)).count(), 10);
+
+        let tmp = std::env::temp_dir().join("ultimate_mc_test_pregen");
+        let _ = std::fs::remove_dir_all(&tmp);
+        let saved = crate::persistence::save_full_chunks(&world, &tmp, 0xFEED, &generated).unwrap();
+        assert_eq!(saved, 24);
+        let loaded = World::new();
+        assert_eq!(crate::persistence::load_into(&loaded, &tmp, 0xFEED, &worldgen, None).unwrap(), 24);
+        assert!(loaded.has_chunk(ChunkPos::new(8, 12)) && !loaded.has_chunk(ChunkPos::new(10, 10)));
+        let _ = std::fs::remove_dir_all(&tmp);
+    }
+}
@@ -0,0 +1,274 @@
+//! On-demand profiling for lag spikes (`--profile`).
+//!
+//! The hot paths run inside `tracing` spans: `cascade` (a physics worker
+//! running a cascade to quiescence), `encode_chunk`, `save` (persistence)
+//! and `write_packet`. Started with `--profile`, the server installs a
+//! [`ProfileLayer`] that can record them. `/profile start` (from the
+//! console or an operator) begins a recording and `/profile stop` ends it,
+//! writing two files to `profiles/`:
+//!
+//! - `<stamp>.trace.json`, in Chrome's trace event format: open it in
+//!   `chrome://tracing` or [Perfetto](https://ui.perfetto.dev);
+//! - `<stamp>.folded`, collapsed stacks weighted by self time in
+//!   microseconds, which `flamegraph.pl` or `inferno-flamegraph` turn
+//!   into a flame graph.
+//!
+//! Without `--profile`, nothing is interested in the spans, so they are
+//! skipped where they are created. With it, the layer only checks a flag
+//! until a recording starts.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use tracing::span::Id;
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Spans kept per recording; a forgotten `/profile start` stops growing
+/// here.
+pub const MAX_RECORDS: usize = 1_000_000;
+
+/// One span, entered and exited once.
+#[derive(Debug, Clone)]
+struct Record {
+    name: &'static str,
+    target: &'static str,
+    thread: u64,
+    start: Instant,
+    took: Duration,
+    /// Span names from the root down to this one, `;`-separated.
+    stack: String,
+}
+
+/// The recording state behind `/profile`.
+pub struct Profiler {
+    dir: PathBuf,
+    recording: AtomicBool,
+    started: Mutex<Option<Instant>>,
+    records: Mutex<Vec<Record>>,
+    dropped: AtomicU64,
+}
+
+/// What `/profile stop` wrote.
+#[derive(Debug)]
+pub struct Report {
+    pub spans: usize,
+    /// Spans beyond [`MAX_RECORDS`], not written.
+    pub dropped: u64,
+    pub trace: PathBuf,
+    pub folded: PathBuf,
+}
+
+static PROFILER: OnceLock<Profiler> = OnceLock::new();
+
+/// Set up profiling into `dir` and return the layer to install. Call once,
+/// at startup.
+pub fn install(dir: impl Into<PathBuf>) -> ProfileLayer {
+    let profiler = PROFILER.get_or_init(|| Profiler {
+        dir: dir.into(),
+        recording: AtomicBool::new(false),
+        started: Mutex::new(None),
+        records: Mutex::new(Vec::new()),
+        dropped: AtomicU64::new(0),
+    });
+    ProfileLayer(profiler)
+}
+
+/// The profiler, if the server runs with `--profile`.
+pub fn profiler() -> Option<&'static Profiler> {
+    PROFILER.get()
+}
+
+impl Profiler {
+    fn recording(&self) -> bool {
+        self.recording.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, record: Record) {
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        if records.len() < MAX_RECORDS {
+            records.push(record);
+        } else {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Start recording. False if already recording.
+    pub fn start(&self) -> bool {
+        let mut started = self.started.lock().unwrap_or_else(|e| e.into_inner());
+        if started.is_some() {
+            return false;
+        }
+        self.records.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        self.dropped.store(0, Ordering::Relaxed);
+        *started = Some(Instant::now());
+        self.recording.store(true, Ordering::Relaxed);
+        true
+    }
+
+    /// Stop recording and write the trace and folded stacks. `None` if
+    /// not recording. Blocks on file I/O.
+    pub fn stop(&self) -> Result<Option<Report>> {
+        let Some(origin) = self.started.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            return Ok(None);
+        };
+        self.recording.store(false, Ordering::Relaxed);
+        let records = std::mem::take(&mut *self.records.lock().unwrap_or_else(|e| e.into_inner()));
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        std::fs::create_dir_all(&self.dir).with_context(|| format!("creating {}", self.dir.display()))?;
+        let trace = self.dir.join(format!("{stamp}.trace.json"));
+        let folded = self.dir.join(format!("{stamp}.folded"));
+        write(&trace, &chrome_trace(&records, origin))?;
+        write(&folded, &folded_stacks(&records))?;
+        Ok(Some(Report { spans: records.len(), dropped: self.dropped.load(Ordering::Relaxed), trace, folded }))
+    }
+}
+
+fn write(path: &Path, contents: &str) -> Result<()> {
+    std::fs::write(path, contents).with_context(|| format!("writing {}", path.display()))
+}
+
+/// When a span was last entered, kept in its extensions.
+struct Entered(Instant);
+
+/// Records spans into the [`Profiler`] while it is recording.
+pub struct ProfileLayer(&'static Profiler);
+
+impl<S> Layer<S> for ProfileLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if !self.0.recording() {
+            return;
+        }
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().replace(Entered(Instant::now()));
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else { return };
+        let Some(Entered(start)) = span.extensions_mut().remove::<Entered>() else { return };
+        if !self.0.recording() {
+            return;
+        }
+        let stack = span.scope().from_root().map(|s| s.name()).collect::<Vec<_>>().join(";");
+        self.0.record(Record {
+            name: span.name(),
+            target: span.metadata().target(),
+            thread: thread_id(),
+            start,
+            took: start.elapsed(),
+            stack,
+        });
+    }
+}
+
+/// A small, stable number for the current thread, for trace lanes.
+fn thread_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static ID: u64 = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+    ID.with(|id| *id)
+}
+
+/// A "complete" event in Chrome's trace format; times in microseconds.
+#[derive(Serialize)]
+struct TraceEvent<'a> {
+    name: &'a str,
+    cat: &'a str,
+    ph: &'static str,
+    ts: f64,
+    dur: f64,
+    pid: u32,
+    tid: u64,
+}
+
+/// The records as a Chrome trace, timed from `origin`.
+fn chrome_trace(records: &[Record], origin: Instant) -> String {
+    let events: Vec<TraceEvent<'_>> = records
+        .iter()
+        .map(|r| TraceEvent {
+            name: r.name,
+            cat: r.target,
+            ph: "X",
+            ts: r.start.saturating_duration_since(origin).as_secs_f64() * 1e6,
+            dur: r.took.as_secs_f64() * 1e6,
+            pid: 1,
+            tid: r.thread,
+        })
+        .collect();
+    serde_json::json!({ "traceEvents": events }).to_string()
+}
+
+/// The records as collapsed stacks, one `a;b;c <µs>` line per stack,
+/// weighted by self time: a span's time less its children's.
+fn folded_stacks(records: &[Record]) -> String {
+    let mut total: BTreeMap<&str, f64> = BTreeMap::new();
+    for r in records {
+        *total.entry(&r.stack).or_default() += r.took.as_secs_f64() * 1e6;
+    }
+    let mut out = String::new();
+    for (&stack, &time) in &total {
+        let children: f64 = total
+            .range::<str, _>((std::ops::Bound::Excluded(stack), std::ops::Bound::Unbounded))
+            .take_while(|(s, _)| s.starts_with(stack))
+            .filter(|(s, _)| s.as_bytes().get(stack.len()) == Some(&b';') && !s[stack.len() + 1..].contains(';'))
+            .map(|(_, t)| t)
+            .sum();
+        let own = (time - children).max(0.0).round() as u64;
+        if own > 0 {
+            out.push_str(&format!("{stack} {own}\n"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(stack: &str, start_us: u64, took_us: u64, origin: Instant) -> Record {
+        Record {
+            name: stack.rsplit(';').next().unwrap().to_owned().leak(),
+            target: "test",
+            thread: 1,
+            start: origin + Duration::from_micros(start_us),
+            took: Duration::from_micros(took_us),
+            stack: stack.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_folded_stacks_use_self_time() {
+        let origin = Instant::now();
+        let records = [
+            record("cascade", 0, 100, origin),
+            record("cascade;save", 10, 30, origin),
+            record("cascade;save;encode", 15, 20, origin),
+            record("cascade2", 200, 5, origin),
+        ];
+        // Lines come out in byte order, so `cascade2` sorts before `cascade;`.
+        assert_eq!(folded_stacks(&records), "cascade 70\ncascade2 5\ncascade;save 10\ncascade;save;encode 20\n");
+    }
+
+    #[test]
+    fn test_chrome_trace_events() {
+        let origin = Instant::now();
+        let trace = chrome_trace(&[record("write_packet", 250, 40, origin)], origin);
+        let json: serde_json::Value = serde_json::from_str(&trace).unwrap();
+        let event = &json["traceEvents"][0];
+        assert_eq!(event["name"], "write_packet");
+        assert_eq!(event["ph"], "X");
+        assert!((event["ts"].as_f64().unwrap() - 250.0).abs() < 1e-6);
+        assert!((event["dur"].as_f64().unwrap() - 40.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,399 @@
+//! Thrown and shot projectiles: snowballs, eggs and arrows.
+//!
+//! A connection [`launch`](Projectiles::launch)es one when its player
+//! throws a snowball or egg (`UseItem`) or lets go of a drawn bow
+//! (`ReleaseUseItem`). [`start`] flies them all on vanilla's 20 Hz tick:
+//! each tick a projectile casts a ray along its velocity for the first
+//! block in the way, then moves, slows by drag and falls by gravity.
+//! Like mobs, they reach clients as [`EntityEvent`]s on the spatial bus.
+//!
+//! An impact is submitted to physics as a [`PROJECTILE_HIT`] custom event
+//! at the block hit, so what it does to the world is a rule
+//! ([`projectile_hit`](crate::rules::projectiles::projectile_hit)) in the
+//! causal graph like any other change. Snowballs and eggs break on impact
+//! in a puff of their particles; arrows stick where they hit until the
+//! block is gone (then they drop) or [`STUCK_TICKS`] pass.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use azalea_entity::particle::{ItemParticle, Particle};
+use azalea_registry::builtin::{EntityKind, ItemKind, SoundEvent};
+
+use ultimate_engine::causal::event::{Event, EventPayload};
+use ultimate_engine::world::World;
+use ultimate_engine::world::position::BlockPos;
+
+use crate::block;
+use crate::effects::WorldEffect;
+use crate::event_bus::{ChangeSource, EntityEvent, SpatialBus};
+use crate::item_use::raycast;
+use crate::physics::PhysicsHandle;
+use crate::player_registry::PlayerRegistry;
+use crate::pools::Pools;
+
+/// Custom event kind of an impact; `data` is `[ProjectileKind::code]`.
+pub const PROJECTILE_HIT: &str = "projectile_hit";
+
+/// One projectile tick, as vanilla's.
+pub const TICK: Duration = Duration::from_millis(50);
+
+/// An arrow stays stuck this many ticks (a minute).
+pub const STUCK_TICKS: u32 = 1200;
+
+/// Launch speed of a snowball or egg, in blocks per tick.
+pub const THROW_SPEED: f64 = 1.5;
+
+/// Launch speed of an arrow from a fully drawn bow.
+pub const BOW_SPEED: f64 = 3.0;
+
+/// Velocity kept per tick (air drag).
+const DRAG: f64 = 0.99;
+
+/// Projectiles below this are gone.
+const MIN_Y: f64 = -128.0;
+
+/// Thrown items spawn this far below the eye, as in vanilla.
+const LAUNCH_DROP: f64 = 0.1;
+
+/// High half of every projectile's UUID ("umc-proj").
+const UUID_PREFIX: u64 = 0x756d_632d_7072_6f6a;
+
+/// The kinds of projectile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectileKind {
+    Snowball,
+    Egg,
+    Arrow,
+}
+
+impl ProjectileKind {
+    /// What throwing `item` launches, if it is throwable.
+    pub fn thrown(item: ItemKind) -> Option<Self> {
+        match item {
+            ItemKind::Snowball => Some(ProjectileKind::Snowball),
+            ItemKind::Egg => Some(ProjectileKind::Egg),
+            _ => None,
+        }
+    }
+
+    pub fn entity_kind(self) -> EntityKind {
+        match self {
+            ProjectileKind::Snowball => EntityKind::Snowball,
+            ProjectileKind::Egg => EntityKind::Egg,
+            ProjectileKind::Arrow => EntityKind::Arrow,
+        }
+    }
+
+    /// Its byte in a [`PROJECTILE_HIT`] event.
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Downward acceleration, in blocks per tick².
+    fn gravity(self) -> f64 {
+        match self {
+            ProjectileKind::Snowball | ProjectileKind::Egg => 0.03,
+            ProjectileKind::Arrow => 0.05,
+        }
+    }
+
+    fn launch_sound(self) -> SoundEvent {
+        match self {
+            ProjectileKind::Snowball => SoundEvent::EntitySnowballThrow,
+            ProjectileKind::Egg => SoundEvent::EntityEggThrow,
+            ProjectileKind::Arrow => SoundEvent::EntityArrowShoot,
+        }
+    }
+}
+
+/// How fast a bow drawn for `drawn` shoots, in blocks per tick: vanilla's
+/// power curve, full after a second. `None` if barely drawn.
+pub fn bow_speed(drawn: Duration) -> Option<f64> {
+    let seconds = (drawn.as_millis() / TICK.as_millis()) as f64 / 20.0;
+    let power = ((seconds * seconds + seconds * 2.0) / 3.0).min(1.0);
+    (power >= 0.1).then_some(power * BOW_SPEED)
+}
+
+/// One projectile in flight or stuck.
+#[derive(Debug, Clone)]
+pub struct Projectile {
+    pub entity_id: i32,
+    pub uuid: uuid::Uuid,
+    pub kind: ProjectileKind,
+    pub pos: [f64; 3],
+    /// Blocks per tick.
+    pub vel: [f64; 3],
+    /// An arrow in a block: the block, and ticks since it hit.
+    stuck: Option<(BlockPos, u32)>,
+}
+
+impl Projectile {
+    fn event(&self) -> EntityEvent {
+        // Vanilla faces projectiles along their flight.
+        let [vx, vy, vz] = self.vel;
+        EntityEvent::Moved {
+            entity_id: self.entity_id,
+            uuid: self.uuid,
+            kind: self.kind.entity_kind(),
+            x: self.pos[0],
+            y: self.pos[1],
+            z: self.pos[2],
+            y_rot: vx.atan2(vz).to_degrees() as f32,
+            x_rot: vy.atan2(vx.hypot(vz)).to_degrees() as f32,
+            on_ground: self.stuck.is_some(),
+        }
+    }
+
+    fn removed(&self) -> EntityEvent {
+        EntityEvent::Removed { entity_id: self.entity_id, x: self.pos[0], z: self.pos[2] }
+    }
+
+    fn cell(&self) -> BlockPos {
+        BlockPos::new(self.pos[0].floor() as i64, self.pos[1].floor() as i64, self.pos[2].floor() as i64)
+    }
+}
+
+/// What a tick did to a projectile.
+#[derive(Debug, Clone, PartialEq)]
+enum Flight {
+    Flying,
+    /// Hangs in its block.
+    Stuck,
+    /// Hit `pos`; broke if not an arrow.
+    Hit(BlockPos),
+    /// Left the world, or its time is up.
+    Gone,
+}
+
+/// All projectiles, shared by the connections that launch them and the
+/// flight task.
+pub struct Projectiles {
+    flying: Mutex<Vec<Projectile>>,
+    registry: Arc<PlayerRegistry>,
+    bus: Arc<SpatialBus>,
+}
+
+impl Projectiles {
+    pub fn new(registry: Arc<PlayerRegistry>, bus: Arc<SpatialBus>) -> Arc<Self> {
+        Arc::new(Self { flying: Mutex::new(Vec::new()), registry, bus })
+    }
+
+    /// How many projectiles exist.
+    pub fn len(&self) -> usize {
+        self.flying.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Launch a `kind` from a player's `eye`, looking along (`y_rot`,
+    /// `x_rot`), at `speed` blocks per tick.
+    pub fn launch(&self, kind: ProjectileKind, eye: [f64; 3], y_rot: f32, x_rot: f32, speed: f64) {
+        let dir = crate::item_use::look_direction(y_rot, x_rot);
+        let entity_id = self.registry.allocate_entity_id();
+        let projectile = Projectile {
+            entity_id,
+            // Projectiles aren't saved, so unique within the run will do.
+            uuid: uuid::Uuid::from_u64_pair(UUID_PREFIX, entity_id as u64),
+            kind,
+            pos: [eye[0], eye[1] - LAUNCH_DROP, eye[2]],
+            vel: dir.map(|d| d * speed),
+            stuck: None,
+        };
+        let sound = WorldEffect::Sound { sound: kind.launch_sound(), pos: projectile.cell(), volume: 0.5, pitch: 0.4 };
+        self.bus.publish_effects(ChangeSource::Simulation("projectiles"), vec![sound]);
+        self.bus.publish_entity(projectile.event());
+        self.flying.lock().unwrap().push(projectile);
+    }
+
+    /// One tick for every projectile. Returns the impact events for
+    /// physics.
+    pub fn tick(&self, world: &World) -> Vec<Event> {
+        let mut impacts = Vec::new();
+        let mut effects = Vec::new();
+        self.flying.lock().unwrap().retain_mut(|p| {
+            match fly(world, p) {
+                Flight::Flying => self.bus.publish_entity(p.event()),
+                Flight::Stuck => {}
+                Flight::Gone => {
+                    self.bus.publish_entity(p.removed());
+                    return false;
+                }
+                Flight::Hit(pos) => {
+                    impacts.push(Event {
+                        payload: EventPayload::Custom {
+                            kind: PROJECTILE_HIT,
+                            pos,
+                            data: Arc::from([p.kind.code()]),
+                        },
+                    });
+                    let cell = p.cell();
+                    match p.kind {
+                        ProjectileKind::Arrow => {
+                            effects.push(WorldEffect::Sound { sound: SoundEvent::EntityArrowHit, pos: cell, volume: 1.0, pitch: 1.0 });
+                            self.bus.publish_entity(p.event());
+                        }
+                        ProjectileKind::Snowball | ProjectileKind::Egg => {
+                            let particle = match p.kind {
+                                ProjectileKind::Egg => Particle::Item(ItemParticle {
+                                    item: azalea_inventory::ItemStack::new(ItemKind::Egg, 1),
+                                }),
+                                _ => Particle::ItemSnowball,
+                            };
+                            effects.push(WorldEffect::Particle { particle, pos: cell, count: 8 });
+                            self.bus.publish_entity(p.removed());
+                            return false;
+                        }
+                    }
+                }
+            }
+            true
+        });
+        if !effects.is_empty() {
+            self.bus.publish_effects(ChangeSource::Simulation("projectiles"), effects);
+        }
+        impacts
+    }
+}
+
+/// Fly every projectile each [`TICK`] on the blocking pool, submitting
+/// impacts to physics, until `shutdown`.
+pub fn start(
+    projectiles: Arc<Projectiles>,
+    world: Arc<World>,
+    physics: PhysicsHandle,
+    pools: Arc<Pools>,
+    shutdown: crate::shutdown::Shutdown,
+) {
+    tokio::spawn(async move {
+        let _running = shutdown.task();
+        let mut interval = tokio::time::interval(TICK);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.triggered() => break,
+            }
+            if projectiles.is_empty() {
+                continue;
+            }
+            let (projectiles, world) = (Arc::clone(&projectiles), Arc::clone(&world));
+            let impacts = pools.run_blocking(move || projectiles.tick(&world)).await;
+            if !impacts.is_empty() {
+                physics.submit_events(impacts);
+            }
+        }
+    });
+}
+
+/// Advance one projectile by a tick.
+fn fly(world: &World, p: &mut Projectile) -> Flight {
+    if let Some((pos, ticks)) = &mut p.stuck {
+        if block::is_passable(world.get_block(*pos)) {
+            // The block it was in is gone: drop.
+            p.stuck = None;
+            p.vel = [0.0; 3];
+        } else {
+            *ticks += 1;
+            return if *ticks >= STUCK_TICKS { Flight::Gone } else { Flight::Stuck };
+        }
+    }
+    if p.pos[1] < MIN_Y || !world.has_chunk(p.cell().chunk()) {
+        return Flight::Gone;
+    }
+
+    let speed = p.vel.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if speed > 0.0 {
+        let dir = p.vel.map(|v| v / speed);
+        let solid = |b| !block::is_passable(b) && block::fluid_kind(b).is_none();
+        if let Some(hit) = raycast(world, p.pos, dir, speed, solid) {
+            for (coord, d) in p.pos.iter_mut().zip(dir) {
+                *coord += d * hit.distance;
+            }
+            if p.kind == ProjectileKind::Arrow {
+                p.stuck = Some((hit.pos, 0));
+            }
+            return Flight::Hit(hit.pos);
+        }
+    }
+    for axis in 0..3 {
+        p.pos[axis] += p.vel[axis];
+        p.vel[axis] *= DRAG;
+    }
+    p.vel[1] -= p.kind.gravity();
+    Flight::Flying
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultimate_engine::world::chunk::Chunk;
+    use ultimate_engine::world::position::ChunkPos;
+
+    /// One chunk with a stone wall at z=10.
+    fn range() -> World {
+        let world = World::new();
+        world.insert_chunk(ChunkPos::new(0, 0), Chunk::new());
+        for x in 0..16 {
+            for y in 0..16 {
+                world.set_block(BlockPos::new(x, y, 10), block::STONE);
+            }
+        }
+        world
+    }
+
+    fn projectiles() -> Arc<Projectiles> {
+        let bus = SpatialBus::new();
+        Projectiles::new(Arc::new(PlayerRegistry::new(Arc::clone(&bus))), bus)
+    }
+
+    fn hits(world: &World, projectiles: &Projectiles) -> Vec<BlockPos> {
+        let mut hits = Vec::new();
+        for _ in 0..100 {
+            for event in projectiles.tick(world) {
+                if let EventPayload::Custom { kind: PROJECTILE_HIT, pos, .. } = event.payload {
+                    hits.push(pos);
+                }
+            }
+        }
+        hits
+    }
+
+    #[test]
+    fn test_snowball_breaks_on_the_wall() {
+        let world = range();
+        let projectiles = projectiles();
+        // Facing +z (yaw 0), level.
+        projectiles.launch(ProjectileKind::Snowball, [8.5, 8.0, 2.5], 0.0, 0.0, THROW_SPEED);
+        let hits = hits(&world, &projectiles);
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].z, hits[0].x), (10, 8));
+        assert!(hits[0].y < 8, "it dropped on the way");
+        assert!(projectiles.is_empty(), "broke on impact");
+    }
+
+    #[test]
+    fn test_arrow_sticks_then_drops_when_its_block_goes() {
+        let world = range();
+        let projectiles = projectiles();
+        projectiles.launch(ProjectileKind::Arrow, [8.5, 8.0, 2.5], 0.0, 0.0, BOW_SPEED);
+        let hits = hits(&world, &projectiles);
+        assert_eq!(hits.len(), 1);
+        let arrow = projectiles.flying.lock().unwrap()[0].clone();
+        assert_eq!(arrow.stuck.map(|(pos, _)| pos), Some(hits[0]));
+        assert!((arrow.pos[2] - 10.0).abs() < 1e-9, "stuck in the wall's face");
+
+        world.set_block(hits[0], block::AIR);
+        projectiles.tick(&world);
+        assert!(projectiles.flying.lock().unwrap()[0].stuck.is_none(), "falls once its block is gone");
+    }
+
+    #[test]
+    fn test_bow_power() {
+        assert_eq!(bow_speed(Duration::from_millis(50)), None, "a tap doesn't shoot");
+        assert_eq!(bow_speed(Duration::from_secs(1)), Some(BOW_SPEED));
+        assert!(bow_speed(Duration::from_millis(500)).unwrap() < BOW_SPEED);
+    }
+}
@@ -0,0 +1,264 @@
+//! Query protocol (GameSpy 4 over UDP, vanilla's `enable-query`), so
+//! server-list sites and monitoring tools can read the MOTD, player list
+//! and plugin string.
+//!
+//! Every request starts with `FE FD`, a type byte and a session id. A
+//! client first asks for a challenge ([`HANDSHAKE`]), then sends a
+//! [`STAT`] request carrying it: four payload bytes get the basic stat,
+//! eight (the challenge plus padding) the full stat with every player
+//! name. Challenges are derived from the client's IP and a time window
+//! rather than stored, so spoofed handshakes can't grow any table; one
+//! stays valid for one to two [`CHALLENGE_WINDOW`]s.
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::net::UdpSocket;
+
+use crate::config::ServerConfig;
+use crate::motd::ServerStatus;
+use crate::player_registry::PlayerRegistry;
+use crate::shutdown::Shutdown;
+
+const MAGIC: [u8; 2] = [0xFE, 0xFD];
+pub const HANDSHAKE: u8 = 9;
+pub const STAT: u8 = 0;
+
+pub const CHALLENGE_WINDOW: Duration = Duration::from_secs(30);
+
+/// Reported in the full stat's `plugins` field (`ServerMod: plugins`).
+const PLUGINS: &str = "ultimate-minecraft";
+
+/// Padding vanilla writes before the full stat's key/value section and
+/// before its player list.
+const KV_HEADER: &[u8] = b"splitnum\0\x80\0";
+const PLAYERS_HEADER: &[u8] = b"\x01player_\0\0";
+
+/// What a stat reply reports.
+#[derive(Debug, Clone)]
+pub struct ServerInfo {
+    pub motd: String,
+    pub version: String,
+    pub map: String,
+    pub players: Vec<String>,
+    pub max_players: u32,
+    pub host_ip: String,
+    pub host_port: u16,
+}
+
+/// Issues and checks challenge tokens.
+pub struct Challenges {
+    secret: RandomState,
+    started: Instant,
+}
+
+impl Default for Challenges {
+    fn default() -> Self {
+        Self { secret: RandomState::new(), started: Instant::now() }
+    }
+}
+
+impl Challenges {
+    fn window(&self, now: Instant) -> u64 {
+        (now - self.started).as_secs() / CHALLENGE_WINDOW.as_secs()
+    }
+
+    fn token(&self, ip: IpAddr, window: u64) -> i32 {
+        (self.secret.hash_one((ip, window)) & 0x7FFF_FFFF) as i32
+    }
+
+    pub fn issue(&self, ip: IpAddr, now: Instant) -> i32 {
+        self.token(ip, self.window(now))
+    }
+
+    /// Whether `token` was issued to `ip` in this window or the last.
+    pub fn check(&self, ip: IpAddr, token: i32, now: Instant) -> bool {
+        let window = self.window(now);
+        token == self.token(ip, window) || (window > 0 && token == self.token(ip, window - 1))
+    }
+}
+
+/// The reply to one request datagram, or `None` for anything malformed,
+/// unknown, or carrying a bad challenge. `info` is only built for stat
+/// requests that pass the challenge.
+pub fn respond(
+    packet: &[u8],
+    ip: IpAddr,
+    challenges: &Challenges,
+    now: Instant,
+    info: impl FnOnce() -> ServerInfo,
+) -> Option<Vec<u8>> {
+    if packet.len() < 7 || packet[..2] != MAGIC {
+        return None;
+    }
+    let kind = packet[2];
+    let session = &packet[3..7];
+    let payload = &packet[7..];
+    match kind {
+        HANDSHAKE => {
+            let mut out = vec![HANDSHAKE];
+            out.extend_from_slice(session);
+            out.extend_from_slice(challenges.issue(ip, now).to_string().as_bytes());
+            out.push(0);
+            Some(out)
+        }
+        STAT if payload.len() >= 4 => {
+            let token = i32::from_be_bytes(payload[..4].try_into().expect("4 bytes"));
+            if !challenges.check(ip, token, now) {
+                return None;
+            }
+            let info = info();
+            Some(if payload.len() >= 8 { full_stat(session, &info) } else { basic_stat(session, &info) })
+        }
+        _ => None,
+    }
+}
+
+fn push_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+}
+
+fn basic_stat(session: &[u8], info: &ServerInfo) -> Vec<u8> {
+    let mut out = vec![STAT];
+    out.extend_from_slice(session);
+    push_str(&mut out, &info.motd);
+    push_str(&mut out, "SMP");
+    push_str(&mut out, &info.map);
+    push_str(&mut out, &info.players.len().to_string());
+    push_str(&mut out, &info.max_players.to_string());
+    out.extend_from_slice(&info.host_port.to_le_bytes());
+    push_str(&mut out, &info.host_ip);
+    out
+}
+
+fn full_stat(session: &[u8], info: &ServerInfo) -> Vec<u8> {
+    let mut out = vec![STAT];
+    out.extend_from_slice(session);
+    out.extend_from_slice(KV_HEADER);
+    let fields = [
+        ("hostname", info.motd.clone()),
+        ("gametype", "SMP".into()),
+        ("game_id", "MINECRAFT".into()),
+        ("version", info.version.clone()),
+        ("plugins", PLUGINS.into()),
+        ("map", info.map.clone()),
+        ("numplayers", info.players.len().to_string()),
+        ("maxplayers", info.max_players.to_string()),
+        ("hostport", info.host_port.to_string()),
+        ("hostip", info.host_ip.clone()),
+    ];
+    for (key, value) in &fields {
+        push_str(&mut out, key);
+        push_str(&mut out, value);
+    }
+    out.push(0);
+    out.extend_from_slice(PLAYERS_HEADER);
+    for name in &info.players {
+        push_str(&mut out, name);
+    }
+    out.push(0);
+    out
+}
+
+/// Answer query requests on `config.query.bind` until `shutdown`. Returns
+/// at once when query is disabled.
+pub async fn run(
+    config: Arc<ServerConfig>,
+    registry: Arc<PlayerRegistry>,
+    status: Arc<ServerStatus>,
+    shutdown: Shutdown,
+) -> anyhow::Result<()> {
+    if !config.query.enabled {
+        return Ok(());
+    }
+    let socket = UdpSocket::bind(&config.query.bind).await?;
+    tracing::info!("Query listening on {} (udp)", config.query.bind);
+    let game: Option<SocketAddr> = config.network.bind.parse().ok();
+    let challenges = Challenges::default();
+    let mut buf = [0u8; 1500];
+    loop {
+        let (len, from) = tokio::select! {
+            received = socket.recv_from(&mut buf) => received?,
+            _ = shutdown.triggered() => return Ok(()),
+        };
+        let reply = respond(&buf[..len], from.ip(), &challenges, Instant::now(), || ServerInfo {
+            motd: status.current().plain_motd(),
+            version: azalea_protocol::packets::VERSION_NAME.to_owned(),
+            map: config.world.dir.file_name().map_or("world".into(), |n| n.to_string_lossy().into_owned()),
+            players: registry.snapshot().into_iter().map(|p| p.name).collect(),
+            max_players: status.max_players(),
+            host_ip: game.map_or("0.0.0.0".into(), |a| a.ip().to_string()),
+            host_port: game.map_or(25565, |a| a.port()),
+        });
+        if let Some(reply) = reply
+            && let Err(e) = socket.send_to(&reply, from).await
+        {
+            tracing::debug!("Query reply to {} failed: {}", from, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info() -> ServerInfo {
+        ServerInfo {
+            motd: "A Server".into(),
+            version: "1.21.11".into(),
+            map: "world".into(),
+            players: vec!["Alice".into(), "Bob".into()],
+            max_players: 20,
+            host_ip: "127.0.0.1".into(),
+            host_port: 25565,
+        }
+    }
+
+    fn request(kind: u8, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xFE, 0xFD, kind, 0, 0, 0, 1];
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn test_handshake_then_basic_and_full_stat() {
+        let challenges = Challenges::default();
+        let ip: IpAddr = "10.0.0.2".parse().unwrap();
+        let now = Instant::now();
+
+        let hello = respond(&request(HANDSHAKE, &[]), ip, &challenges, now, info).unwrap();
+        assert_eq!(&hello[..5], &[HANDSHAKE, 0, 0, 0, 1]);
+        let token: i32 = std::str::from_utf8(&hello[5..hello.len() - 1]).unwrap().parse().unwrap();
+
+        let basic = respond(&request(STAT, &token.to_be_bytes()), ip, &challenges, now, info).unwrap();
+        assert_eq!(&basic[..5], &[STAT, 0, 0, 0, 1]);
+        assert_eq!(&basic[5..], b"A Server\0SMP\0world\x002\x0020\0\xDD\x63127.0.0.1\0");
+
+        let mut full_payload = token.to_be_bytes().to_vec();
+        full_payload.extend_from_slice(&[0; 4]);
+        let full = respond(&request(STAT, &full_payload), ip, &challenges, now, info).unwrap();
+        let text = String::from_utf8_lossy(&full);
+        assert!(text.contains("plugins\0ultimate-minecraft\0"));
+        assert!(text.contains("numplayers\x002\0maxplayers\x0020\0"));
+        assert!(full.ends_with(b"\x01player_\0\0Alice\0Bob\0\0"));
+    }
+
+    #[test]
+    fn test_challenge_checked_per_ip_and_window() {
+        let challenges = Challenges::default();
+        let ip: IpAddr = "10.0.0.2".parse().unwrap();
+        let now = Instant::now();
+        let token = challenges.issue(ip, now);
+
+        assert!(challenges.check(ip, token, now + CHALLENGE_WINDOW));
+        assert!(!challenges.check(ip, token, now + CHALLENGE_WINDOW * 2));
+        assert!(!challenges.check("10.0.0.3".parse().unwrap(), token, now));
+        let stat = request(STAT, &token.wrapping_add(1).to_be_bytes());
+        assert!(respond(&stat, ip, &challenges, now, || unreachable!()).is_none());
+        assert!(respond(&[0xFE, 0xFD, HANDSHAKE], ip, &challenges, now, info).is_none());
+    }
+}
@@ -0,0 +1,202 @@
+//! Remote console: the standard Minecraft RCON protocol, so hosting panels
+//! and admin scripts can run commands without joining.
+//!
+//! Every packet is `length: i32 LE` followed by `request id: i32 LE`,
+//! `type: i32 LE` and a NUL-terminated ASCII body plus one padding NUL.
+//! A client logs in with a [`LOGIN`] packet carrying the password (a
+//! failed login is answered with request id `-1` and closes the
+//! connection), then sends [`COMMAND`] packets; each is run through the
+//! command dispatcher as the console ([`crate::commands::Console`]) and
+//! answered with [`RESPONSE`] packets, split at [`MAX_FRAGMENT`] bytes.
+
+use std::sync::Arc;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::commands::Console;
+use crate::config::RconConfig;
+use crate::shutdown::Shutdown;
+
+/// Packet types. `COMMAND` and the login reply share a value, as in the
+/// protocol.
+pub const RESPONSE: i32 = 0;
+pub const COMMAND: i32 = 2;
+pub const LOGIN_REPLY: i32 = 2;
+pub const LOGIN: i32 = 3;
+
+/// Largest response body per packet; longer output spans several packets
+/// with the same request id.
+pub const MAX_FRAGMENT: usize = 4096;
+/// Largest packet (after the length field) accepted from a client.
+const MAX_PACKET: usize = 4096 + 10;
+/// Request id, type and the two trailing NULs.
+const MIN_PACKET: usize = 10;
+
+/// Sender name commands run under, as in vanilla's logs.
+const SENDER: &str = "Rcon";
+
+/// One decoded packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+    pub id: i32,
+    pub kind: i32,
+    pub body: String,
+}
+
+/// Encode a packet, length prefix included.
+pub fn encode(id: i32, kind: i32, body: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len() + 14);
+    out.extend_from_slice(&((body.len() + MIN_PACKET) as i32).to_le_bytes());
+    out.extend_from_slice(&id.to_le_bytes());
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(body.as_bytes());
+    out.extend_from_slice(&[0, 0]);
+    out
+}
+
+/// Read one packet, or `None` if the client closed the connection between
+/// packets.
+pub async fn read_packet<R: AsyncRead + Unpin>(read: &mut R) -> anyhow::Result<Option<Packet>> {
+    let mut len = [0u8; 4];
+    match read.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = i32::from_le_bytes(len);
+    if !(MIN_PACKET as i32..=MAX_PACKET as i32).contains(&len) {
+        anyhow::bail!("bad RCON packet length {}", len);
+    }
+    let mut buf = vec![0u8; len as usize];
+    read.read_exact(&mut buf).await?;
+    let id = i32::from_le_bytes(buf[0..4].try_into().expect("4 bytes"));
+    let kind = i32::from_le_bytes(buf[4..8].try_into().expect("4 bytes"));
+    // The body ends at the first NUL; some clients omit the padding byte.
+    let body = &buf[8..];
+    let end = body.iter().position(|&b| b == 0).unwrap_or(body.len());
+    let body = String::from_utf8_lossy(&body[..end]).into_owned();
+    Ok(Some(Packet { id, kind, body }))
+}
+
+/// Split `text` into response bodies of at most [`MAX_FRAGMENT`] bytes,
+/// on character boundaries. Empty output is still one (empty) body.
+pub fn fragments(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut rest = text;
+    while rest.len() > MAX_FRAGMENT {
+        let mut cut = MAX_FRAGMENT;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        let (head, tail) = rest.split_at(cut);
+        out.push(head);
+        rest = tail;
+    }
+    out.push(rest);
+    out
+}
+
+/// Accept RCON clients on `config.bind` until `shutdown`. Returns at once
+/// (with a warning) when disabled or no password is set.
+pub async fn run(config: RconConfig, console: Arc<Console>, shutdown: Shutdown) -> anyhow::Result<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+    if config.password.is_empty() {
+        tracing::warn!("RCON is enabled but rcon.password is empty; not starting it");
+        return Ok(());
+    }
+    let listener = TcpListener::bind(&config.bind).await?;
+    tracing::info!("RCON listening on {}", config.bind);
+    let password: Arc<str> = config.password.into();
+
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = shutdown.triggered() => return Ok(()),
+        };
+        tracing::info!("RCON connection from {}", addr);
+        let console = Arc::clone(&console);
+        let password = Arc::clone(&password);
+        let running = shutdown.task();
+        let shutdown = shutdown.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                result = serve(stream, &console, &password) => {
+                    if let Err(e) = result {
+                        tracing::info!("RCON connection from {} closed: {:#}", addr, e);
+                    }
+                }
+                _ = shutdown.triggered() => {}
+            }
+            drop(running);
+        });
+    }
+}
+
+/// One client: log in, then run commands until it disconnects.
+async fn serve(mut stream: TcpStream, console: &Console, password: &str) -> anyhow::Result<()> {
+    let (mut read, mut write) = stream.split();
+    let mut authenticated = false;
+    while let Some(packet) = read_packet(&mut read).await? {
+        if !authenticated {
+            let ok = packet.kind == LOGIN && crate::dashboard::admin::token_matches(&packet.body, password);
+            let id = if ok { packet.id } else { -1 };
+            write.write_all(&encode(id, LOGIN_REPLY, "")).await?;
+            if !ok {
+                anyhow::bail!("login failed");
+            }
+            authenticated = true;
+            continue;
+        }
+        if packet.kind != COMMAND {
+            write.write_all(&encode(packet.id, RESPONSE, &format!("Unknown request {:#x}", packet.kind))).await?;
+            continue;
+        }
+        let reply = console.execute(SENDER, &packet.body).await.join("\n");
+        send_response(&mut write, packet.id, &reply).await?;
+    }
+    Ok(())
+}
+
+async fn send_response<W: AsyncWrite + Unpin>(write: &mut W, id: i32, text: &str) -> std::io::Result<()> {
+    for body in fragments(text) {
+        write.write_all(&encode(id, RESPONSE, body)).await?;
+    }
+    write.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_packets_round_trip() {
+        let mut bytes = encode(7, COMMAND, "list");
+        bytes.extend(encode(-1, LOGIN_REPLY, ""));
+        assert_eq!(&bytes[..4], &14i32.to_le_bytes());
+
+        let mut read = bytes.as_slice();
+        assert_eq!(
+            read_packet(&mut read).await.unwrap(),
+            Some(Packet { id: 7, kind: COMMAND, body: "list".into() }),
+        );
+        assert_eq!(read_packet(&mut read).await.unwrap(), Some(Packet { id: -1, kind: LOGIN_REPLY, body: String::new() }));
+        assert_eq!(read_packet(&mut read).await.unwrap(), None);
+
+        let mut oversized = ((MAX_PACKET + 1) as i32).to_le_bytes().to_vec();
+        oversized.resize(MAX_PACKET + 5, 0);
+        assert!(read_packet(&mut oversized.as_slice()).await.is_err());
+    }
+
+    #[test]
+    fn test_fragments_split_on_char_boundaries() {
+        assert_eq!(fragments(""), vec![""]);
+        let long = "é".repeat(MAX_FRAGMENT); // two bytes each
+        let parts = fragments(&long);
+        assert_eq!(parts.len(), 2);
+        assert!(parts.iter().all(|p| p.len() <= MAX_FRAGMENT));
+        assert_eq!(parts.concat(), long);
+    }
+}
@@ -0,0 +1,411 @@
+//! Crafting recipes, loaded from vanilla's data-pack JSON.
+//!
+//! `crafting.data_dir` is a copy of `data/minecraft` from the vanilla
+//! server jar: every `recipe/*.json` of type `crafting_shaped` or
+//! `crafting_shapeless` is loaded, with `#tag` ingredients resolved
+//! through `tags/item/*.json`. Other recipe types (smelting,
+//! stonecutting, the `crafting_special_*` ones) are skipped. Both the
+//! current format (`"#": "minecraft:stick"`, `"result": {"id": ...}`)
+//! and the pre-1.21.2 one (`{"item": ...}`, `{"tag": ...}`) are read.
+//!
+//! [`RecipeBook::find`] matches a crafting grid the way vanilla does:
+//! empty rows and columns around the items don't matter, shaped recipes
+//! also match mirrored, and shapeless ones in any arrangement. The
+//! player's 2×2 grid is driven by [`crate::inventory::Inventory::click`];
+//! connections send every recipe to the client's recipe book on join.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+use azalea_registry::builtin::ItemKind;
+use serde_json::Value;
+
+/// Tags nest (`#logs` holds `#oak_logs`); deeper than this is a cycle.
+const MAX_TAG_DEPTH: usize = 16;
+
+/// One ingredient slot: any of these items.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ingredient(pub Vec<ItemKind>);
+
+impl Ingredient {
+    pub fn matches(&self, item: ItemKind) -> bool {
+        self.0.contains(&item)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Shape {
+    /// Row-major cells, trimmed of empty rows and columns.
+    Shaped { width: usize, height: usize, cells: Vec<Option<Ingredient>> },
+    Shapeless(Vec<Ingredient>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipe {
+    /// `minecraft:<file stem>`.
+    pub id: String,
+    pub shape: Shape,
+    pub result: ItemKind,
+    pub count: i32,
+}
+
+impl Recipe {
+    /// Whether `grid` (`width` wide, row-major, `None` for empty) crafts
+    /// this recipe.
+    pub fn matches(&self, grid: &[Option<ItemKind>], width: usize) -> bool {
+        match &self.shape {
+            Shape::Shaped { width: w, height: h, cells } => {
+                let Some((x0, y0, x1, y1)) = bounds(grid, width) else { return false };
+                if x1 - x0 != *w || y1 - y0 != *h {
+                    return false;
+                }
+                let fits = |mirror: bool| {
+                    (0..*h).all(|y| {
+                        (0..*w).all(|x| {
+                            let cell = &cells[y * w + if mirror { w - 1 - x } else { x }];
+                            match (cell, grid[(y0 + y) * width + x0 + x]) {
+                                (None, None) => true,
+                                (Some(ingredient), Some(item)) => ingredient.matches(item),
+                                _ => false,
+                            }
+                        })
+                    })
+                };
+                fits(false) || fits(true)
+            }
+            Shape::Shapeless(ingredients) => {
+                let items: Vec<ItemKind> = grid.iter().flatten().copied().collect();
+                items.len() == ingredients.len() && assign(&items, ingredients, &mut vec![false; ingredients.len()])
+            }
+        }
+    }
+}
+
+/// Bounding box of the non-empty cells, as `(x0, y0, x1, y1)` exclusive.
+fn bounds(grid: &[Option<ItemKind>], width: usize) -> Option<(usize, usize, usize, usize)> {
+    let filled = || grid.iter().enumerate().filter(|(_, cell)| cell.is_some()).map(|(i, _)| (i % width, i / width));
+    let x0 = filled().map(|(x, _)| x).min()?;
+    let x1 = filled().map(|(x, _)| x).max()? + 1;
+    let y0 = filled().map(|(_, y)| y).min()?;
+    let y1 = filled().map(|(_, y)| y).max()? + 1;
+    Some((x0, y0, x1, y1))
+}
+
+/// Whether every item can take a distinct ingredient. At most nine of
+/// each, so plain backtracking does.
+fn assign(items: &[ItemKind], ingredients: &[Ingredient], used: &mut [bool]) -> bool {
+    let Some((&item, rest)) = items.split_first() else { return true };
+    for (i, ingredient) in ingredients.iter().enumerate() {
+        if !used[i] && ingredient.matches(item) {
+            used[i] = true;
+            if assign(rest, ingredients, used) {
+                return true;
+            }
+            used[i] = false;
+        }
+    }
+    false
+}
+
+/// What an ingredient leaves in the grid when crafted: the bucket of a
+/// milk bucket, the bottle of honey.
+pub fn remainder(item: ItemKind) -> Option<ItemKind> {
+    match item {
+        ItemKind::MilkBucket | ItemKind::WaterBucket | ItemKind::LavaBucket | ItemKind::PowderSnowBucket => {
+            Some(ItemKind::Bucket)
+        }
+        ItemKind::HoneyBottle | ItemKind::DragonBreath => Some(ItemKind::GlassBottle),
+        _ => None,
+    }
+}
+
+/// An item by namespaced or bare name.
+pub(crate) fn item(name: &str) -> Result<ItemKind> {
+    let bare = name.strip_prefix("minecraft:").unwrap_or(name);
+    ItemKind::from_str(bare).map_err(|_| anyhow::anyhow!("unknown item {:?}", name))
+}
+
+/// Item tags by name (`minecraft:planks`), unresolved.
+#[derive(Debug, Default)]
+pub struct Tags(BTreeMap<String, Vec<Value>>);
+
+impl Tags {
+    /// Read `tags/item/**/*.json` under `dir`. None if it doesn't exist.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let mut tags = Self::default();
+        let root = dir.join("tags/item");
+        for (name, json) in read_json_tree(&root)? {
+            tags.insert(&name, &json).with_context(|| format!("in {}", root.join(&name).display()))?;
+        }
+        Ok(tags)
+    }
+
+    /// Add tag `name` (without namespace) from its JSON.
+    pub fn insert(&mut self, name: &str, json: &str) -> Result<()> {
+        let value: Value = serde_json::from_str(json)?;
+        let values = value.get("values").and_then(Value::as_array).context("no \"values\" list")?;
+        self.0.insert(format!("minecraft:{}", name), values.clone());
+        Ok(())
+    }
+
+    /// Every item in tag `name`, nested tags included. Entries marked
+    /// `"required": false` may name items this version lacks.
+    fn resolve(&self, name: &str, depth: usize, out: &mut Vec<ItemKind>) -> Result<()> {
+        let name = if name.contains(':') { name.to_owned() } else { format!("minecraft:{}", name) };
+        if depth > MAX_TAG_DEPTH {
+            bail!("tag #{} nests too deep", name);
+        }
+        let values = self.0.get(&name).with_context(|| format!("unknown tag #{}", name))?;
+        for value in values {
+            let (id, required) = match value {
+                Value::String(id) => (id.as_str(), true),
+                Value::Object(entry) => (
+                    entry.get("id").and_then(Value::as_str).context("tag entry without \"id\"")?,
+                    entry.get("required").and_then(Value::as_bool).unwrap_or(true),
+                ),
+                _ => bail!("bad entry in tag #{}", name),
+            };
+            let found = match id.strip_prefix('#') {
+                Some(tag) => self.resolve(tag, depth + 1, out),
+                None => item(id).map(|kind| out.push(kind)),
+            };
+            if required {
+                found?;
+            }
+        }
+        Ok(())
+    }
+
+    fn ingredient(&self, value: &Value) -> Result<Ingredient> {
+        let mut items = Vec::new();
+        let mut add = |value: &Value| -> Result<()> {
+            let (name, tag) = match value {
+                Value::String(s) => match s.strip_prefix('#') {
+                    Some(tag) => (tag, true),
+                    None => (s.as_str(), false),
+                },
+                Value::Object(o) => match (o.get("item").and_then(Value::as_str), o.get("tag").and_then(Value::as_str)) {
+                    (Some(id), _) => (id, false),
+                    (None, Some(tag)) => (tag, true),
+                    _ => bail!("ingredient needs \"item\" or \"tag\""),
+                },
+                _ => bail!("bad ingredient {}", value),
+            };
+            if tag {
+                self.resolve(name, 0, &mut items)
+            } else {
+                items.push(item(name)?);
+                Ok(())
+            }
+        };
+        match value {
+            Value::Array(alternatives) => alternatives.iter().try_for_each(&mut add)?,
+            single => add(single)?,
+        }
+        if items.is_empty() {
+            bail!("ingredient {} matches no items", value);
+        }
+        let mut unique = Vec::with_capacity(items.len());
+        for kind in items {
+            if !unique.contains(&kind) {
+                unique.push(kind);
+            }
+        }
+        Ok(Ingredient(unique))
+    }
+}
+
+/// `*.json` files under `root` and its subdirectories, as (path relative
+/// to `root` without `.json`, contents). A missing `root` has none.
+fn read_json_tree(root: &Path) -> Result<Vec<(String, String)>> {
+    let mut out = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("listing {}", dir.display())),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                let name = path.strip_prefix(root).unwrap_or(&path).with_extension("");
+                let text = std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+                out.push((name.to_string_lossy().replace('\\', "/"), text));
+            }
+        }
+    }
+    out.sort();
+    Ok(out)
+}
+
+/// Every crafting recipe.
+#[derive(Debug, Default)]
+pub struct RecipeBook {
+    recipes: Vec<Recipe>,
+}
+
+impl RecipeBook {
+    /// Load the recipes in data directory `dir` (see the module docs).
+    /// Recipes that don't parse are logged and skipped; a missing
+    /// directory gives an empty book.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let tags = Tags::load(dir)?;
+        let mut book = Self::default();
+        let (mut skipped, mut broken) = (0, 0);
+        for (name, json) in read_json_tree(&dir.join("recipe"))? {
+            match book.add(&format!("minecraft:{}", name), &json, &tags) {
+                Ok(true) => {}
+                Ok(false) => skipped += 1,
+                Err(e) => {
+                    tracing::warn!("Skipping recipe {}: {:#}", name, e);
+                    broken += 1;
+                }
+            }
+        }
+        tracing::info!(
+            "Loaded {} crafting recipes from {} ({} of other types skipped, {} unreadable)",
+            book.len(), dir.display(), skipped, broken,
+        );
+        Ok(book)
+    }
+
+    /// Parse and add recipe `id`. `Ok(false)` for recipe types that
+    /// aren't grid crafting.
+    pub fn add(&mut self, id: &str, json: &str, tags: &Tags) -> Result<bool> {
+        let value: Value = serde_json::from_str(json)?;
+        let shape = match value.get("type").and_then(Value::as_str) {
+            Some("minecraft:crafting_shaped") => shaped(&value, tags)?,
+            Some("minecraft:crafting_shapeless") => {
+                let list = value.get("ingredients").and_then(Value::as_array).context("no \"ingredients\"")?;
+                if list.is_empty() || list.len() > 9 {
+                    bail!("{} ingredients; a grid holds 1 to 9", list.len());
+                }
+                Shape::Shapeless(list.iter().map(|i| tags.ingredient(i)).collect::<Result<_>>()?)
+            }
+            _ => return Ok(false),
+        };
+        let result = value.get("result").context("no \"result\"")?;
+        let name = result.get("id").or_else(|| result.get("item")).and_then(Value::as_str).context("result without \"id\"")?;
+        let count = result.get("count").and_then(Value::as_i64).unwrap_or(1).clamp(1, 99) as i32;
+        self.recipes.push(Recipe { id: id.to_owned(), shape, result: item(name)?, count });
+        Ok(true)
+    }
+
+    pub fn len(&self) -> usize {
+        self.recipes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_empty()
+    }
+
+    pub fn recipes(&self) -> &[Recipe] {
+        &self.recipes
+    }
+
+    /// The recipe `grid` (`width` wide, row-major) crafts, if any.
+    pub fn find(&self, grid: &[Option<ItemKind>], width: usize) -> Option<&Recipe> {
+        if grid.iter().all(Option::is_none) {
+            return None;
+        }
+        self.recipes.iter().find(|recipe| recipe.matches(grid, width))
+    }
+}
+
+/// A shaped recipe's pattern and key, trimmed to the cells in use.
+fn shaped(value: &Value, tags: &Tags) -> Result<Shape> {
+    let rows: Vec<Vec<char>> = value
+        .get("pattern")
+        .and_then(Value::as_array)
+        .context("no \"pattern\"")?
+        .iter()
+        .map(|row| row.as_str().map(|s| s.chars().collect()).context("pattern rows are strings"))
+        .collect::<Result<_>>()?;
+    let width = rows.first().map_or(0, Vec::len);
+    if rows.is_empty() || rows.len() > 3 || width == 0 || width > 3 || rows.iter().any(|r| r.len() != width) {
+        bail!("pattern must be 1-3 rows of 1-3 equal-length strings");
+    }
+    let key = value.get("key").and_then(Value::as_object).context("no \"key\"")?;
+    let mut cells = Vec::new();
+    for &symbol in rows.iter().flatten() {
+        cells.push(match symbol {
+            ' ' => None,
+            _ => {
+                let ingredient = key.get(&symbol.to_string()).with_context(|| format!("{:?} isn't in the key", symbol))?;
+                Some(tags.ingredient(ingredient)?)
+            }
+        });
+    }
+
+    // Drop empty outer rows and columns, as vanilla does, so a pattern
+    // written with padding still matches anywhere in the grid.
+    let grid: Vec<Option<ItemKind>> = cells.iter().map(|c| c.as_ref().map(|i| i.0[0])).collect();
+    let (x0, y0, x1, y1) = bounds(&grid, width).context("pattern is empty")?;
+    let trimmed = (y0..y1).flat_map(|y| (x0..x1).map(move |x| (x, y))).map(|(x, y)| cells[y * width + x].clone()).collect();
+    Ok(Shape::Shaped { width: x1 - x0, height: y1 - y0, cells: trimmed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book() -> RecipeBook {
+        let mut tags = Tags::default();
+        tags.insert("planks", r###"{"values": ["minecraft:oak_planks", "#minecraft:dark_planks"]}"###).unwrap();
+        tags.insert("dark_planks", r#"{"values": ["minecraft:dark_oak_planks", {"id": "minecraft:not_an_item", "required": false}]}"#).unwrap();
+        let mut book = RecipeBook::default();
+        let stick = r###"{"type": "minecraft:crafting_shaped", "key": {"#": "#minecraft:planks"},
+            "pattern": ["# ", "# "], "result": {"count": 4, "id": "minecraft:stick"}}"###;
+        assert!(book.add("minecraft:stick", stick, &tags).unwrap());
+        let boat = r###"{"type": "minecraft:crafting_shaped", "key": {"#": {"item": "minecraft:oak_planks"}, "S": "minecraft:stick"},
+            "pattern": ["S  ", "## "], "result": {"item": "minecraft:oak_boat"}}"###;
+        assert!(book.add("minecraft:test_boat", boat, &tags).unwrap());
+        let dye = r#"{"type": "minecraft:crafting_shapeless", "ingredients": ["minecraft:blue_dye", ["minecraft:red_dye", "minecraft:pink_dye"]],
+            "result": {"count": 2, "id": "minecraft:purple_dye"}}"#;
+        assert!(book.add("minecraft:purple_dye", dye, &tags).unwrap());
+        let smelt = r#"{"type": "minecraft:smelting", "ingredient": "minecraft:sand", "result": {"id": "minecraft:glass"}}"#;
+        assert!(!book.add("minecraft:glass", smelt, &tags).unwrap());
+        book
+    }
+
+    #[test]
+    fn test_grids_match_anywhere_mirrored_and_unordered() {
+        use ItemKind::*;
+        let book = book();
+        assert_eq!(book.len(), 3);
+        let found = |grid: &[Option<ItemKind>], width| book.find(grid, width).map(|r| (r.result, r.count));
+
+        // Padding in the pattern is trimmed: a vertical pair anywhere.
+        assert_eq!(found(&[None, Some(OakPlanks), None, Some(DarkOakPlanks)], 2), Some((Stick, 4)));
+        assert_eq!(found(&[None, None, None, None, None, Some(OakPlanks), None, None, Some(OakPlanks)], 3), Some((Stick, 4)));
+        assert_eq!(found(&[Some(OakPlanks), Some(OakPlanks), None, None], 2), None, "side by side isn't a stick");
+        assert_eq!(found(&[Some(Stone), None, Some(OakPlanks), None], 2), None);
+
+        // Shaped recipes also match mirrored.
+        assert_eq!(found(&[Some(Stick), None, Some(OakPlanks), Some(OakPlanks)], 2), Some((OakBoat, 1)));
+        assert_eq!(found(&[None, Some(Stick), Some(OakPlanks), Some(OakPlanks)], 2), Some((OakBoat, 1)));
+
+        assert_eq!(found(&[Some(PinkDye), None, None, Some(BlueDye)], 2), Some((PurpleDye, 2)));
+        assert_eq!(found(&[Some(PinkDye), Some(RedDye), None, Some(BlueDye)], 2), None, "one too many");
+        assert_eq!(found(&[None; 4], 2), None);
+    }
+
+    #[test]
+    fn test_bad_recipes_are_rejected() {
+        let tags = Tags::default();
+        let mut book = RecipeBook::default();
+        let unknown_tag = r###"{"type": "minecraft:crafting_shapeless", "ingredients": ["#minecraft:logs"], "result": {"id": "minecraft:stick"}}"###;
+        assert!(book.add("a", unknown_tag, &tags).is_err());
+        let missing_key = r#"{"type": "minecraft:crafting_shaped", "key": {}, "pattern": ["X"], "result": {"id": "minecraft:stick"}}"#;
+        assert!(book.add("b", missing_key, &tags).is_err());
+        let ragged = r#"{"type": "minecraft:crafting_shaped", "key": {"X": "minecraft:stone"}, "pattern": ["XX", "X"], "result": {"id": "minecraft:stick"}}"#;
+        assert!(book.add("c", ragged, &tags).is_err());
+        assert!(book.is_empty());
+        assert!(RecipeBook::load(Path::new("/nonexistent")).unwrap().is_empty());
+    }
+}
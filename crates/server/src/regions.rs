@@ -0,0 +1,229 @@
+//! Protected regions: named cuboids where only their owner and members
+//! may place and break blocks.
+//!
+//! Players claim the chunk they stand in with `/claim <name>` (up to
+//! `claims.per_player` each); ops define arbitrary cuboids from their
+//! `//pos1` `//pos2` selection with `/region define`. Owners (and ops)
+//! manage members and remove regions with `/region` (see
+//! [`crate::commands`]).
+//!
+//! Connections ask [`Regions::denies`] before submitting a player's block
+//! action to physics; a denied root is never submitted, and the client's
+//! predicted change is taken back by resending the real block, as at the
+//! world border. Players at `claims.bypass_level` or above aren't asked.
+//! Only player actions are checked: cascades (water flowing in, sand
+//! falling) and op tools (`//set`, schematic pastes) are not.
+//!
+//! Regions are saved with the world (`data/regions.json`, see
+//! [`crate::persistence::WorldStorage::save`]) and by every command that
+//! changes them. Lookups scan every region,
+//! which is fine for the handful a server has.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use ultimate_engine::world::position::{BlockPos, ChunkPos};
+use uuid::Uuid;
+
+/// Where regions are saved, relative to the world directory.
+pub const REGIONS_FILE: &str = "data/regions.json";
+
+/// Vertical extent of a `/claim`: the whole build height.
+pub const CLAIM_MIN_Y: i64 = -64;
+pub const CLAIM_MAX_Y: i64 = 319;
+
+/// One protected cuboid.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claim {
+    /// Inclusive corners.
+    pub min: [i64; 3],
+    pub max: [i64; 3],
+    pub owner: Uuid,
+    pub owner_name: String,
+    /// Players who may build besides the owner, with their names.
+    #[serde(default)]
+    pub members: BTreeMap<Uuid, String>,
+}
+
+impl Claim {
+    /// The cuboid spanned by `a` and `b`, in any order.
+    pub fn new(a: BlockPos, b: BlockPos, owner: Uuid, owner_name: &str) -> Self {
+        Self {
+            min: [a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)],
+            max: [a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)],
+            owner,
+            owner_name: owner_name.to_owned(),
+            members: BTreeMap::new(),
+        }
+    }
+
+    /// The whole height of chunk `chunk`.
+    pub fn chunk(chunk: ChunkPos, owner: Uuid, owner_name: &str) -> Self {
+        let (x, z) = (chunk.x as i64 * 16, chunk.z as i64 * 16);
+        Self::new(BlockPos::new(x, CLAIM_MIN_Y, z), BlockPos::new(x + 15, CLAIM_MAX_Y, z + 15), owner, owner_name)
+    }
+
+    pub fn contains(&self, pos: BlockPos) -> bool {
+        (0..3).all(|i| (self.min[i]..=self.max[i]).contains(&[pos.x, pos.y, pos.z][i]))
+    }
+
+    pub fn overlaps(&self, other: &Claim) -> bool {
+        (0..3).all(|i| self.min[i] <= other.max[i] && other.min[i] <= self.max[i])
+    }
+
+    /// Whether `player` may build here.
+    pub fn allows(&self, player: Uuid) -> bool {
+        player == self.owner || self.members.contains_key(&player)
+    }
+}
+
+/// The saved form: regions by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct State {
+    #[serde(default)]
+    regions: BTreeMap<String, Claim>,
+}
+
+/// Every protected region.
+pub struct Regions {
+    path: PathBuf,
+    state: RwLock<State>,
+}
+
+impl Regions {
+    /// Load the regions saved in world directory `dir` (none if no file).
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(REGIONS_FILE);
+        let state = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => State::default(),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+        };
+        Ok(Self { path, state: RwLock::new(state) })
+    }
+
+    /// No regions, saved in world directory `dir`.
+    pub fn empty(dir: &Path) -> Self {
+        Self { path: dir.join(REGIONS_FILE), state: RwLock::default() }
+    }
+
+    /// Write the regions back to the world directory.
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&*self.state.read().expect("regions poisoned"))?;
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, json).with_context(|| format!("writing {}", self.path.display()))
+    }
+
+    /// The region `player` may not build at `pos` in, if any. Callers
+    /// skip this for players at `claims.bypass_level`.
+    pub fn denies(&self, player: Uuid, pos: BlockPos) -> Option<String> {
+        let state = self.state.read().expect("regions poisoned");
+        state.regions.iter().find(|(_, claim)| claim.contains(pos) && !claim.allows(player)).map(|(name, _)| name.clone())
+    }
+
+    pub fn get(&self, name: &str) -> Option<Claim> {
+        self.state.read().expect("regions poisoned").regions.get(name).cloned()
+    }
+
+    /// Every region, by name.
+    pub fn list(&self) -> Vec<(String, Claim)> {
+        let state = self.state.read().expect("regions poisoned");
+        state.regions.iter().map(|(name, claim)| (name.clone(), claim.clone())).collect()
+    }
+
+    /// Regions containing `pos`.
+    pub fn at(&self, pos: BlockPos) -> Vec<(String, Claim)> {
+        self.list().into_iter().filter(|(_, claim)| claim.contains(pos)).collect()
+    }
+
+    /// How many regions `owner` has.
+    pub fn owned_by(&self, owner: Uuid) -> usize {
+        self.state.read().expect("regions poisoned").regions.values().filter(|c| c.owner == owner).count()
+    }
+
+    /// The first region `claim` would overlap, if any.
+    pub fn overlapping(&self, claim: &Claim) -> Option<String> {
+        let state = self.state.read().expect("regions poisoned");
+        state.regions.iter().find(|(_, other)| other.overlaps(claim)).map(|(name, _)| name.clone())
+    }
+
+    /// Add `claim` as `name`. False if the name is taken.
+    pub fn define(&self, name: &str, claim: Claim) -> bool {
+        let mut state = self.state.write().expect("regions poisoned");
+        if state.regions.contains_key(name) {
+            return false;
+        }
+        state.regions.insert(name.to_owned(), claim);
+        true
+    }
+
+    pub fn remove(&self, name: &str) -> Option<Claim> {
+        self.state.write().expect("regions poisoned").regions.remove(name)
+    }
+
+    /// Add or remove a member of region `name`. False if there is no such
+    /// region or nothing changed.
+    pub fn set_member(&self, name: &str, player: Uuid, player_name: &str, member: bool) -> bool {
+        let mut state = self.state.write().expect("regions poisoned");
+        let Some(claim) = state.regions.get_mut(name) else { return false };
+        if member {
+            claim.members.insert(player, player_name.to_owned()).is_none()
+        } else {
+            claim.members.remove(&player).is_some()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regions_deny_outsiders_only() {
+        let regions = Regions::empty(Path::new("unused"));
+        let (alice, bob, carol) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        let home = Claim::chunk(ChunkPos::new(-1, 0), alice, "Alice");
+        assert_eq!((home.min, home.max), ([-16, CLAIM_MIN_Y, 0], [-1, CLAIM_MAX_Y, 15]));
+        assert!(regions.define("home", home));
+        assert!(!regions.define("home", Claim::chunk(ChunkPos::new(5, 5), bob, "Bob")));
+
+        let inside = BlockPos::new(-3, 70, 4);
+        let outside = BlockPos::new(0, 70, 4);
+        assert_eq!(regions.denies(alice, inside), None);
+        assert_eq!(regions.denies(bob, inside).as_deref(), Some("home"));
+        assert_eq!(regions.denies(bob, outside), None);
+
+        assert!(regions.set_member("home", bob, "Bob", true));
+        assert!(!regions.set_member("home", bob, "Bob", true));
+        assert_eq!(regions.denies(bob, inside), None);
+        assert_eq!(regions.denies(carol, inside).as_deref(), Some("home"));
+        assert!(regions.set_member("home", bob, "Bob", false));
+        assert!(regions.denies(bob, inside).is_some());
+
+        let next_door = Claim::new(BlockPos::new(-1, 0, 15), BlockPos::new(3, 10, 20), bob, "Bob");
+        assert_eq!(regions.overlapping(&next_door).as_deref(), Some("home"));
+        assert_eq!(regions.at(inside).len(), 1);
+        assert_eq!(regions.owned_by(alice), 1);
+    }
+
+    #[test]
+    fn test_regions_survive_save_and_load() {
+        let dir = std::env::temp_dir().join("ultimate_mc_test_regions_save");
+        let _ = std::fs::remove_dir_all(&dir);
+        let regions = Regions::empty(&dir);
+        let mut claim = Claim::new(BlockPos::new(5, 60, 5), BlockPos::new(0, 70, 0), Uuid::from_u128(1), "Alice");
+        claim.members.insert(Uuid::from_u128(2), "Bob".into());
+        regions.define("farm", claim.clone());
+        regions.save().unwrap();
+
+        let loaded = Regions::load(&dir).unwrap();
+        assert_eq!(loaded.get("farm"), Some(claim));
+        assert!(Regions::load(&dir.join("missing")).unwrap().list().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
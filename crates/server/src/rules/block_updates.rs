@@ -1,36 +1,45 @@
-//! Block-update rules: gravity, fluid spread, and fluid drainage.
+//! Block-update rules: gravity, fluid spread and drainage, and lava
+//! hardening where it meets water.
 //!
-//! Each public function has the signature `fn(&World, &EventPayload) -> Vec<Event>`
+//! Each public function has the signature `fn(&WorldView, &EventPayload) -> Vec<Event>`
 //! so it can be registered directly as a `RuleFn`.
 
-use crate::block::{self, FluidKind};
+use crate::block::{self, FluidKind, Gravity};
+use super::config;
 use super::helpers::{block_set, notify_vertical, notify_neighbors, horizontal_neighbors};
 use ultimate_engine::causal::event::{Event, EventPayload};
+use ultimate_engine::world::block::BlockId;
 use ultimate_engine::world::position::BlockPos;
-use ultimate_engine::world::World;
+use ultimate_engine::world::WorldView;
 
 // ── Gravity ──────────────────────────────────────────────────────────────
 
-/// Gravity rule: if a gravity-affected block (sand, gravel) has a replaceable
-/// block below it, swap them and notify above + below.
-pub fn gravity(world: &World, payload: &EventPayload) -> Vec<Event> {
+/// Gravity rule: if a gravity-affected block (sand, gravel, concrete
+/// powder, anvils) has a replaceable block below it, swap them and notify
+/// above + below. Concrete powder that lands in water arrives as concrete
+/// (the water goes up into the cell it left, as for any swap), which ends
+/// its fall.
+pub fn gravity(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
     let pos = match payload {
-        EventPayload::BlockSet { pos, .. } | EventPayload::BlockNotify { pos } => *pos,
+        EventPayload::BlockSet { pos, .. } | EventPayload::BlockNotify { pos, .. } => *pos,
         _ => return Vec::new(),
     };
 
     let block_id = world.get_block(pos);
-    if !block::has_gravity(block_id) {
-        return Vec::new();
-    }
+    let landed_as = match config::with_active(|rules| rules.gravity(block_id)) {
+        Gravity::Floats => return Vec::new(),
+        Gravity::Falls => block_id,
+        Gravity::Hardens(hardened) => hardened,
+    };
 
     let below = BlockPos::new(pos.x, pos.y - 1, pos.z);
     let below_id = world.get_block(below);
 
     if block::is_replaceable(below_id) {
+        let arrives = if FluidKind::Water.is_match(below_id) { landed_as } else { block_id };
         let mut events = vec![
             block_set(pos, block_id, below_id),
-            block_set(below, below_id, block_id),
+            block_set(below, below_id, arrives),
         ];
         // Notify below (continued falling) and above (pillar cascade).
         events.extend(notify_vertical(pos));
@@ -42,30 +51,64 @@ pub fn gravity(world: &World, payload: &EventPayload) -> Vec<Event> {
 
 // ── Generic fluid logic ──────────────────────────────────────────────────
 
-/// The level a flowing fluid cell *should* have given its neighbors, or
-/// `None` if nothing supports it:
-///   - Fluid of the same kind directly above feeds it at level 1 (falling).
-///   - Otherwise `min(horizontal neighbor levels) + 1`.
+/// How far `kind` spreads under the active `rules.toml`.
+fn max_spread(kind: FluidKind) -> u8 {
+    config::with_active(|rules| rules.max_spread(kind))
+}
+
+/// The state a non-source fluid cell *should* have given its neighbors,
+/// or `None` if nothing supports it:
+///   - Between two sources over solid ground it becomes a source (see
+///     [`forms_source`]).
+///   - Fluid of the same kind directly above feeds it as falling fluid.
+///   - Otherwise flowing at `min(horizontal neighbor distances) + 1`, as
+///     long as that is within [`max_spread`]. Sources and falling
+///     fluid count as distance 0, so a column that lands restarts the
+///     spread at level 1, as in vanilla.
 ///
 /// This is the unique fixed point of fluid flow — every flowing cell's
-/// level equals its shortest-path distance from a source. Re-levelling
-/// toward it on notify makes fluid **confluent**: the final state is
-/// independent of event execution order, which spacelike-parallel and
-/// partitioned scheduling require. (Previously a cell kept whichever
-/// level arrived first, so two interacting fronts settled differently
-/// depending on arrival order.)
-fn desired_fluid_level(world: &World, pos: BlockPos, kind: FluidKind) -> Option<u8> {
-    // Fluid from above always feeds at level 1.
+/// level equals its shortest-path distance from a source or falling
+/// column. Re-levelling toward it on notify makes fluid **confluent**:
+/// the final state is independent of event execution order, which
+/// spacelike-parallel and partitioned scheduling require. (Previously a
+/// cell kept whichever level arrived first, so two interacting fronts
+/// settled differently depending on arrival order.)
+fn desired_fluid_state(world: &WorldView, pos: BlockPos, kind: FluidKind) -> Option<BlockId> {
+    if forms_source(world, pos, kind) {
+        return Some(kind.source());
+    }
+
     let above = BlockPos::new(pos.x, pos.y + 1, pos.z);
     if kind.is_match(world.get_block(above)) {
-        return Some(1);
+        return Some(kind.falling());
     }
 
     horizontal_neighbors(pos)
         .into_iter()
-        .filter_map(|n| kind.level(world.get_block(n)))
+        .filter_map(|n| kind.distance(world.get_block(n)))
         .min()
-        .map(|min_level| min_level.saturating_add(1))
+        .map(|min_distance| min_distance.saturating_add(1))
+        .filter(|&d| d <= max_spread(kind))
+        .map(|d| kind.flowing(d))
+}
+
+/// Whether the cell at `pos` turns into a new source, as vanilla's
+/// infinite water: the fluid forms sources, at least two horizontal
+/// neighbors are its sources, and below is solid or another source.
+/// Flow from a single direction never qualifies.
+fn forms_source(world: &WorldView, pos: BlockPos, kind: FluidKind) -> bool {
+    if !kind.forms_sources() {
+        return false;
+    }
+    let sources = horizontal_neighbors(pos)
+        .into_iter()
+        .filter(|n| world.get_block(*n) == kind.source())
+        .count();
+    if sources < 2 {
+        return false;
+    }
+    let below = world.get_block(BlockPos::new(pos.x, pos.y - 1, pos.z));
+    below == kind.source() || !block::is_replaceable(below)
 }
 
 /// Core fluid rule, parameterized by `FluidKind`.
@@ -73,29 +116,32 @@ fn desired_fluid_level(world: &World, pos: BlockPos, kind: FluidKind) -> Option<
 /// Handles **spread**, **drainage**, and **removal notification**:
 ///   - Removal: when a `BlockSet` replaces this fluid with a non-fluid block,
 ///     notify all 6 neighbors so drainage can cascade through the rules alone.
-///   - Spread: source (level 0) spreads to level 1; flowing (level N) to N+1,
-///     up to `kind.max_spread()`. Fluid above air falls down as level 1.
-///   - Drain: on `BlockNotify`, flowing fluid (level > 0) without support
-///     drains to air and notifies horizontal neighbors.
-fn generic_fluid(world: &World, payload: &EventPayload, kind: FluidKind) -> Vec<Event> {
+///   - Source formation: flowing water between two sources over solid
+///     ground becomes a source, so pools are infinite.
+///   - Spread: sources and falling fluid spread to level 1; flowing
+///     (level N) to N+1, up to [`max_spread`]. Fluid above air falls
+///     down as falling fluid (level 8).
+///   - Drain: on `BlockNotify`, non-source fluid without support drains to
+///     air and notifies horizontal neighbors.
+fn generic_fluid(world: &WorldView, payload: &EventPayload, kind: FluidKind) -> Vec<Event> {
     // ── Removal: fluid replaced by non-fluid → notify neighbors for drainage ─
     if let EventPayload::BlockSet { pos, old, new } = payload {
         if kind.is_match(*old) && !kind.is_match(*new) {
             return notify_neighbors(*pos);
         }
-        // Re-level: same-kind fluid changed level. Horizontal neighbors'
-        // levels may now be wrong (their min-neighbor changed) — notify
-        // them so the relaxation propagates. The spread logic below also
-        // runs for the new level via the normal BlockSet path.
-        if let (Some(old_l), Some(new_l)) = (kind.level(*old), kind.level(*new)) {
-            if old_l != new_l {
-                let mut events: Vec<Event> = horizontal_neighbors(*pos)
-                    .into_iter()
-                    .map(|n| Event { payload: EventPayload::BlockNotify { pos: n } })
-                    .collect();
-                events.extend(spread_events(world, *pos, new_l, kind));
-                return events;
-            }
+        // Re-level: same-kind fluid changed state (level or falling bit).
+        // Horizontal neighbors' levels may now be wrong (their
+        // min-neighbor changed) — notify them so the relaxation
+        // propagates. The spread logic below also runs for the new state
+        // via the normal BlockSet path.
+        if kind.is_match(*old) && kind.is_match(*new) && old != new {
+            let distance = kind.distance(*new).expect("is_match implies distance");
+            let mut events: Vec<Event> = horizontal_neighbors(*pos)
+                .into_iter()
+                .map(|n| Event { payload: EventPayload::BlockNotify { pos: n, from: None } })
+                .collect();
+            events.extend(spread_events(world, *pos, distance, kind));
+            return events;
         }
         // Appearance: a fluid cell came into existence (old was not this
         // kind). Besides spreading, wake any ADJACENT same-kind fluid so
@@ -106,13 +152,18 @@ fn generic_fluid(world: &World, payload: &EventPayload, kind: FluidKind) -> Vec<
         // re-evaluated by this notify, which is emitted *after* our write
         // and therefore observes it. Without it, spread only targets AIR
         // and a wrongly-drained fluid cell is never revisited.
-        if kind.level(*old).is_none() && kind.is_match(*new) {
-            let level = kind.level(*new).expect("is_match implies level");
-            let mut events = spread_events(world, *pos, level, kind);
+        if !kind.is_match(*old) && kind.is_match(*new) {
+            let distance = kind.distance(*new).expect("is_match implies distance");
+            let mut events = spread_events(world, *pos, distance, kind);
+            // Flowing fluid that lands between two sources is already a
+            // source; nothing would notify it otherwise.
+            if *new != kind.source() && forms_source(world, *pos, kind) {
+                events.push(block_set(*pos, *new, kind.source()));
+            }
             let below = BlockPos::new(pos.x, pos.y - 1, pos.z);
             for n in horizontal_neighbors(*pos).into_iter().chain([below]) {
                 if kind.is_match(world.get_block(n)) {
-                    events.push(Event { payload: EventPayload::BlockNotify { pos: n } });
+                    events.push(Event { payload: EventPayload::BlockNotify { pos: n, from: None } });
                 }
             }
             return events;
@@ -123,52 +174,52 @@ fn generic_fluid(world: &World, payload: &EventPayload, kind: FluidKind) -> Vec<
 
     let pos = match payload {
         EventPayload::BlockSet { pos, new, .. } if kind.is_match(*new) => *pos,
-        EventPayload::BlockNotify { pos } if kind.is_match(world.get_block(*pos)) => *pos,
+        EventPayload::BlockNotify { pos, .. } if kind.is_match(world.get_block(*pos)) => *pos,
         _ => return Vec::new(),
     };
 
     let block_id = world.get_block(pos);
-    let level = match kind.level(block_id) {
-        Some(l) => l,
+    let distance = match kind.distance(block_id) {
+        Some(d) => d,
         None => return Vec::new(),
     };
 
-    // ── Re-level / drain (flowing only, on BlockNotify) ───────────────
-    // Relax toward the unique fixed point `level = desired`:
-    //   - no feed at all, or desired beyond the spread cap → drain to air
-    //     (the removal trigger above then notifies neighbors);
-    //   - wrong level → set the correct one (the level-change trigger
+    // ── Re-level / drain (non-source only, on BlockNotify) ────────────
+    // Relax toward the unique fixed point `state = desired`:
+    //   - no support within the spread cap → drain to air (the removal
+    //     trigger above then notifies neighbors);
+    //   - wrong state → set the correct one (the state-change trigger
     //     above then notifies neighbors, continuing the relaxation);
-    //   - correct level → nothing. No re-spread from notify (that caused
+    //   - correct state → nothing. No re-spread from notify (that caused
     //     feedback loops); spreading cascades via BlockSet events only.
-    if level > 0 && is_notify {
-        return match desired_fluid_level(world, pos, kind) {
+    if block_id != kind.source() && is_notify {
+        return match desired_fluid_state(world, pos, kind) {
             None => vec![block_set(pos, block_id, block::AIR)],
-            Some(d) if d > kind.max_spread() => vec![block_set(pos, block_id, block::AIR)],
-            Some(d) if d != level => vec![block_set(pos, block_id, kind.at_level(d))],
+            Some(d) if d != block_id => vec![block_set(pos, block_id, d)],
             Some(_) => Vec::new(),
         };
     }
 
     // ── Spread (BlockSet, or source on BlockNotify) ──────────────────
-    spread_events(world, pos, level, kind)
+    spread_events(world, pos, distance, kind)
 }
 
-/// Spread from a fluid cell at `level`: fall into air below as level 1,
-/// otherwise flow horizontally into air at `level + 1` (capped).
-fn spread_events(world: &World, pos: BlockPos, level: u8, kind: FluidKind) -> Vec<Event> {
-    // Falls down first (gravity-like). Falling fluid becomes level 1.
+/// Spread from a fluid cell `distance` from its feed: fall into air below
+/// as falling fluid, otherwise flow horizontally into air at
+/// `distance + 1` (capped).
+fn spread_events(world: &WorldView, pos: BlockPos, distance: u8, kind: FluidKind) -> Vec<Event> {
+    // Falls down first (gravity-like).
     let below = BlockPos::new(pos.x, pos.y - 1, pos.z);
     let below_id = world.get_block(below);
     if below_id == block::AIR {
-        return vec![block_set(below, below_id, kind.at_level(1))];
+        return vec![block_set(below, below_id, kind.falling())];
     }
 
-    // Horizontal spread: level increases by 1 each step, capped at max.
-    if level >= kind.max_spread() {
+    // Horizontal spread: distance increases by 1 each step, capped at max.
+    if distance >= max_spread(kind) {
         return Vec::new();
     }
-    let next = kind.at_level(level + 1);
+    let next = kind.flowing(distance + 1);
 
     horizontal_neighbors(pos)
         .into_iter()
@@ -177,14 +228,74 @@ fn spread_events(world: &World, pos: BlockPos, level: u8, kind: FluidKind) -> Ve
         .collect()
 }
 
+// ── Lava meeting water ───────────────────────────────────────────────────
+
+/// What the lava at `pos` hardens into given the water around it, as the
+/// event replacing it, or `None` if it stays lava:
+///   - a source with water directly above → stone;
+///   - a source with water beside it → obsidian;
+///   - flowing or falling lava with water beside or above it → cobblestone.
+///
+/// Water below doesn't count: lava resting on water stays lava.
+fn harden_lava(world: &WorldView, pos: BlockPos) -> Option<Event> {
+    let id = world.get_block(pos);
+    if !FluidKind::Lava.is_match(id) {
+        return None;
+    }
+    let is_water = |p: BlockPos| FluidKind::Water.is_match(world.get_block(p));
+    let above = is_water(BlockPos::new(pos.x, pos.y + 1, pos.z));
+    let beside = horizontal_neighbors(pos).into_iter().any(is_water);
+
+    let new = if id == FluidKind::Lava.source() {
+        if above {
+            block::STONE
+        } else if beside {
+            block::OBSIDIAN
+        } else {
+            return None;
+        }
+    } else if above || beside {
+        block::COBBLESTONE
+    } else {
+        return None;
+    };
+    Some(block_set(pos, id, new))
+}
+
+/// Lava–water interaction rule.
+///
+/// Checked from both sides, so it doesn't matter which fluid arrives
+/// second: lava that is placed, flows or is notified hardens against the
+/// water already around it, and water that appears hardens the lava beside
+/// and below it. The hardened block replaces the lava through an ordinary
+/// `BlockSet`, so the lava rule's removal trigger drains whatever it fed.
+pub fn lava_water_interaction(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
+    match payload {
+        EventPayload::BlockSet { pos, old, new }
+            if FluidKind::Water.is_match(*new) && !FluidKind::Water.is_match(*old) =>
+        {
+            let below = BlockPos::new(pos.x, pos.y - 1, pos.z);
+            horizontal_neighbors(*pos)
+                .into_iter()
+                .chain([below])
+                .filter_map(|n| harden_lava(world, n))
+                .collect()
+        }
+        EventPayload::BlockSet { pos, .. } | EventPayload::BlockNotify { pos, .. } => {
+            harden_lava(world, *pos).into_iter().collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
 // ── Public rule wrappers ─────────────────────────────────────────────────
 
 /// Water spread and drainage rule.
-pub fn water_spread(world: &World, payload: &EventPayload) -> Vec<Event> {
+pub fn water_spread(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
     generic_fluid(world, payload, FluidKind::Water)
 }
 
 /// Lava spread and drainage rule.
-pub fn lava_spread(world: &World, payload: &EventPayload) -> Vec<Event> {
+pub fn lava_spread(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
     generic_fluid(world, payload, FluidKind::Lava)
 }
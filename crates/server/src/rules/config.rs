@@ -0,0 +1,304 @@
+//! Rule configuration (`rules.toml`): which standard rules run, how far
+//! fluids spread, and which blocks fall.
+//!
+//! Rules are plain `fn` pointers, so their settings live here as one
+//! process-wide [`ActiveRules`] snapshot. [`install`] swaps it and bumps a
+//! generation counter; physics workers rebuild their `RuleSet` from
+//! [`super::standard`] when they start their next cascade after a bump,
+//! and rule code reads parameters through [`with_active`], which caches
+//! the snapshot per thread so the hot path never takes the lock.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+
+use anyhow::{Context, bail};
+use azalea_registry::builtin::BlockKind;
+use serde::{Deserialize, Serialize};
+use ultimate_engine::world::block::BlockId;
+
+use crate::block::{self, FluidKind, Gravity};
+
+/// `rules.toml` as written by an operator.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleConfig {
+    pub gravity: GravityRules,
+    pub water: FluidRules,
+    pub lava: FluidRules,
+    pub lava_water: Toggle,
+    pub light: Toggle,
+    pub projectiles: Toggle,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GravityRules {
+    pub enabled: bool,
+    /// Blocks that fall.
+    pub falling: Vec<String>,
+    /// Blocks that fall and turn into another block when they land in
+    /// water (concrete powder).
+    pub hardens_in_water: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FluidRules {
+    pub enabled: bool,
+    /// Horizontal spread distance, 1-7. Unset means vanilla (water 7,
+    /// lava 3).
+    pub max_spread: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Toggle {
+    pub enabled: bool,
+}
+
+impl Default for GravityRules {
+    fn default() -> Self {
+        let mut falling = Vec::new();
+        let mut hardens_in_water = BTreeMap::new();
+        for (name, hardened) in block::vanilla_falling_blocks() {
+            match hardened {
+                None => falling.push(name),
+                Some(hardened) => {
+                    hardens_in_water.insert(name, hardened);
+                }
+            }
+        }
+        Self { enabled: true, falling, hardens_in_water }
+    }
+}
+
+impl Default for FluidRules {
+    fn default() -> Self {
+        Self { enabled: true, max_spread: None }
+    }
+}
+
+impl Default for Toggle {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Written on first run. Mirrors [`RuleConfig::default`] (asserted in
+/// tests) but keeps the comments.
+pub const DEFAULT_RULES_TOML: &str = r#"# Ultimate Minecraft -- block rule configuration.
+# Reload without restarting with /reloadrules; new cascades pick it up.
+
+[gravity]
+enabled = true
+falling = ["sand", "red_sand", "gravel", "suspicious_sand", "suspicious_gravel", "anvil", "chipped_anvil", "damaged_anvil"]
+
+# Fall, and turn into the second block on landing in water.
+[gravity.hardens_in_water]
+white_concrete_powder = "white_concrete"
+orange_concrete_powder = "orange_concrete"
+magenta_concrete_powder = "magenta_concrete"
+light_blue_concrete_powder = "light_blue_concrete"
+yellow_concrete_powder = "yellow_concrete"
+lime_concrete_powder = "lime_concrete"
+pink_concrete_powder = "pink_concrete"
+gray_concrete_powder = "gray_concrete"
+light_gray_concrete_powder = "light_gray_concrete"
+cyan_concrete_powder = "cyan_concrete"
+purple_concrete_powder = "purple_concrete"
+blue_concrete_powder = "blue_concrete"
+brown_concrete_powder = "brown_concrete"
+green_concrete_powder = "green_concrete"
+red_concrete_powder = "red_concrete"
+black_concrete_powder = "black_concrete"
+
+# max_spread: horizontal reach in blocks, 1-7. Omit for vanilla
+# (water 7, lava 3).
+[water]
+enabled = true
+
+[lava]
+enabled = true
+
+# Lava touching water becomes obsidian, cobblestone or stone.
+[lava_water]
+enabled = true
+
+[light]
+enabled = true
+
+# Arrows and other projectiles hitting blocks.
+[projectiles]
+enabled = true
+"#;
+
+/// A validated [`RuleConfig`], ready for the rules to read.
+pub struct ActiveRules {
+    pub config: RuleConfig,
+    water_spread: u8,
+    lava_spread: u8,
+    gravity: Box<[Gravity]>,
+}
+
+impl ActiveRules {
+    /// Resolve block names and check ranges.
+    pub fn compile(config: RuleConfig) -> anyhow::Result<Self> {
+        let kind = |name: &str| {
+            let bare = name.strip_prefix("minecraft:").unwrap_or(name);
+            BlockKind::from_str(bare).map_err(|_| anyhow::anyhow!("unknown block {name:?}"))
+        };
+        let mut falling = Vec::new();
+        for name in &config.gravity.falling {
+            falling.push((kind(name).context("gravity.falling")?, None));
+        }
+        for (name, hardened) in &config.gravity.hardens_in_water {
+            let hardened = kind(hardened).context("gravity.hardens_in_water")?;
+            falling.push((kind(name).context("gravity.hardens_in_water")?, Some(hardened)));
+        }
+
+        let spread = |fluid: &FluidRules, kind: FluidKind, section: &str| match fluid.max_spread {
+            None => Ok(kind.max_spread()),
+            Some(n @ 1..=7) => Ok(n),
+            Some(n) => bail!("{section}.max_spread must be 1-7, got {n}"),
+        };
+        Ok(Self {
+            water_spread: spread(&config.water, FluidKind::Water, "water")?,
+            lava_spread: spread(&config.lava, FluidKind::Lava, "lava")?,
+            gravity: block::gravity_table(&falling),
+            config,
+        })
+    }
+
+    /// How far `kind` spreads horizontally.
+    pub fn max_spread(&self, kind: FluidKind) -> u8 {
+        match kind {
+            FluidKind::Water => self.water_spread,
+            FluidKind::Lava => self.lava_spread,
+        }
+    }
+
+    /// How this block responds to gravity under this configuration.
+    pub fn gravity(&self, id: BlockId) -> Gravity {
+        self.gravity.get(id.0 as usize).copied().unwrap_or(Gravity::Floats)
+    }
+
+    /// One-line description for logs and `/reloadrules`.
+    pub fn summary(&self) -> String {
+        let c = &self.config;
+        let on = [c.gravity.enabled, c.water.enabled, c.lava.enabled, c.lava_water.enabled, c.light.enabled, c.projectiles.enabled];
+        format!(
+            "{} of {} rules enabled, water spreads {}, lava {}, {} falling blocks",
+            on.iter().filter(|&&b| b).count(),
+            on.len(),
+            self.water_spread,
+            self.lava_spread,
+            c.gravity.falling.len() + c.gravity.hardens_in_water.len(),
+        )
+    }
+}
+
+static ACTIVE: LazyLock<RwLock<Arc<ActiveRules>>> = LazyLock::new(|| {
+    RwLock::new(Arc::new(ActiveRules::compile(RuleConfig::default()).expect("default rules compile")))
+});
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Where [`load`] read the rules from, for [`reload`].
+static PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+thread_local! {
+    static CACHED: RefCell<Option<(u64, Arc<ActiveRules>)>> = const { RefCell::new(None) };
+}
+
+/// Bumped by every [`install`].
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Acquire)
+}
+
+/// Make `rules` the active configuration.
+pub fn install(rules: ActiveRules) {
+    *ACTIVE.write().unwrap() = Arc::new(rules);
+    invalidate();
+}
+
+/// Have physics workers rebuild their rule sets without changing the
+/// configuration, as when [`super::scripts`] change.
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+}
+
+/// Run `f` with the active configuration.
+pub fn with_active<R>(f: impl FnOnce(&ActiveRules) -> R) -> R {
+    CACHED.with(|cached| {
+        let mut cached = cached.borrow_mut();
+        let generation = generation();
+        if cached.as_ref().is_none_or(|(seen, _)| *seen != generation) {
+            *cached = Some((generation, Arc::clone(&ACTIVE.read().unwrap())));
+        }
+        f(&cached.as_ref().unwrap().1)
+    })
+}
+
+/// Load `path` (writing the defaults there first if it doesn't exist),
+/// install it, and remember the path for [`reload`].
+pub fn load(path: &Path) -> anyhow::Result<String> {
+    if !path.exists() {
+        std::fs::write(path, DEFAULT_RULES_TOML)
+            .with_context(|| format!("writing default rules to {}", path.display()))?;
+        tracing::info!("Wrote default rules to {}", path.display());
+    }
+    let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let config: RuleConfig = toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+    let rules = ActiveRules::compile(config).with_context(|| format!("in {}", path.display()))?;
+    let summary = rules.summary();
+    install(rules);
+    *PATH.lock().unwrap() = Some(path.to_owned());
+    Ok(summary)
+}
+
+/// Re-read the file [`load`] last read. On error the active rules stay.
+pub fn reload() -> anyhow::Result<String> {
+    let Some(path) = PATH.lock().unwrap().clone() else {
+        bail!("no rules file was loaded at startup");
+    };
+    load(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_toml_matches_defaults() {
+        let parsed: RuleConfig = toml::from_str(DEFAULT_RULES_TOML).unwrap();
+        assert_eq!(parsed, RuleConfig::default());
+        let rules = ActiveRules::compile(parsed).unwrap();
+        assert_eq!(rules.max_spread(FluidKind::Water), 7);
+        assert_eq!(rules.max_spread(FluidKind::Lava), 3);
+        for raw in [block::SAND.0, block::STONE.0, block::WATER.0] {
+            assert_eq!(rules.gravity(BlockId(raw)), block::gravity(BlockId(raw)));
+        }
+    }
+
+    #[test]
+    fn compile_applies_overrides_and_rejects_bad_values() {
+        let config: RuleConfig = toml::from_str(
+            "[gravity]\nfalling = [\"minecraft:stone\"]\n\n[lava]\nmax_spread = 6\n\n[light]\nenabled = false\n",
+        )
+        .unwrap();
+        assert!(config.water.enabled && !config.light.enabled);
+        let rules = ActiveRules::compile(config).unwrap();
+        assert_eq!(rules.max_spread(FluidKind::Lava), 6);
+        assert_eq!(rules.gravity(block::STONE), Gravity::Falls);
+        assert_eq!(rules.gravity(block::SAND), Gravity::Floats);
+        assert!(rules.summary().starts_with("5 of 6 rules enabled"));
+
+        assert!(toml::from_str::<RuleConfig>("[water]\nspread = 3\n").is_err());
+        let bad_spread: RuleConfig = toml::from_str("[water]\nmax_spread = 8\n").unwrap();
+        assert!(ActiveRules::compile(bad_spread).is_err());
+        let bad_block: RuleConfig = toml::from_str("[gravity]\nfalling = [\"sandd\"]\n").unwrap();
+        assert!(ActiveRules::compile(bad_block).is_err());
+    }
+}
@@ -2,18 +2,13 @@
 
 use ultimate_engine::causal::event::{Event, EventPayload};
 use ultimate_engine::world::block::BlockId;
-use ultimate_engine::world::position::BlockPos;
+use ultimate_engine::world::position::{BlockPos, Direction};
 
 // ── Position helpers ─────────────────────────────────────────────────────
 
 /// The four horizontal neighbor positions (±X, ±Z).
 pub fn horizontal_neighbors(pos: BlockPos) -> [BlockPos; 4] {
-    [
-        BlockPos::new(pos.x + 1, pos.y, pos.z),
-        BlockPos::new(pos.x - 1, pos.y, pos.z),
-        BlockPos::new(pos.x, pos.y, pos.z + 1),
-        BlockPos::new(pos.x, pos.y, pos.z - 1),
-    ]
+    pos.horizontal_neighbors()
 }
 
 // ── Event constructors ───────────────────────────────────────────────────
@@ -28,7 +23,15 @@ pub fn block_set(pos: BlockPos, old: BlockId, new: BlockId) -> Event {
 /// Create a `BlockNotify` event.
 pub fn notify(pos: BlockPos) -> Event {
     Event {
-        payload: EventPayload::BlockNotify { pos },
+        payload: EventPayload::BlockNotify { pos, from: None },
+    }
+}
+
+/// Create a `BlockNotify` event telling `pos` that the block on its `from`
+/// side changed.
+pub fn notify_from(pos: BlockPos, from: Direction) -> Event {
+    Event {
+        payload: EventPayload::BlockNotify { pos, from: Some(from) },
     }
 }
 
@@ -39,6 +42,13 @@ pub fn notify_neighbors(pos: BlockPos) -> Vec<Event> {
     pos.neighbors().into_iter().map(notify).collect()
 }
 
+/// Notify all 6 cardinal neighbors, each told which side `pos` is on.
+/// Unlike [`notify_neighbors`], these only coalesce with notifies from
+/// the same side, so use them where a rule reads the direction.
+pub fn notify_neighbors_directed(pos: BlockPos) -> Vec<Event> {
+    pos.directions().into_iter().map(|(dir, n)| notify_from(n, dir.opposite())).collect()
+}
+
 /// Notify the 4 horizontal neighbors (±X, ±Z).
 pub fn notify_horizontal(pos: BlockPos) -> Vec<Event> {
     horizontal_neighbors(pos).into_iter().map(notify).collect()
@@ -46,8 +56,5 @@ pub fn notify_horizontal(pos: BlockPos) -> Vec<Event> {
 
 /// Notify the 2 vertical neighbors (above and below).
 pub fn notify_vertical(pos: BlockPos) -> Vec<Event> {
-    vec![
-        notify(BlockPos::new(pos.x, pos.y + 1, pos.z)),
-        notify(BlockPos::new(pos.x, pos.y - 1, pos.z)),
-    ]
+    vec![notify(pos.relative(Direction::Up)), notify(pos.relative(Direction::Down))]
 }
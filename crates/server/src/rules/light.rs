@@ -18,19 +18,19 @@ use crate::block;
 use ultimate_engine::causal::event::{Event, EventPayload, LightType};
 use ultimate_engine::world::block::BlockId;
 use ultimate_engine::world::position::BlockPos;
-use ultimate_engine::world::World;
+use ultimate_engine::world::{LightChunk, WorldView};
 
 const MIN_Y: i64 = -64;
 const MAX_Y: i64 = 319;
 
-pub fn light_propagation(world: &World, payload: &EventPayload) -> Vec<Event> {
+pub fn light_propagation(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
     match payload {
         EventPayload::BlockSet { pos, old, new } => update_light(world, *pos, *old, *new),
         _ => Vec::new(),
     }
 }
 
-fn update_light(world: &World, pos: BlockPos, old: BlockId, new: BlockId) -> Vec<Event> {
+fn update_light(world: &WorldView, pos: BlockPos, old: BlockId, new: BlockId) -> Vec<Event> {
     let old_emit = block::light_emission(old);
     let new_emit = block::light_emission(new);
     let old_opacity = block::light_opacity(old);
@@ -61,28 +61,24 @@ fn update_light(world: &World, pos: BlockPos, old: BlockId, new: BlockId) -> Vec
 /// acquired — so two workers flooding light near a shared border cannot
 /// deadlock on opposite acquisition orders.
 struct CachedWorld<'w> {
-    world: &'w World,
-    current: Option<(
-        ultimate_engine::world::position::ChunkPos,
-        dashmap::mapref::one::RefMut<'w, ultimate_engine::world::position::ChunkPos,
-            ultimate_engine::world::chunk::Chunk>,
-    )>,
+    world: WorldView<'w>,
+    current: Option<(ultimate_engine::world::position::ChunkPos, LightChunk<'w>)>,
 }
 
 impl<'w> CachedWorld<'w> {
-    fn new(world: &'w World) -> Self {
-        Self { world, current: None }
+    fn new(world: &WorldView<'w>) -> Self {
+        Self { world: *world, current: None }
     }
 
     #[inline]
-    fn chunk(&mut self, pos: BlockPos) -> Option<&mut ultimate_engine::world::chunk::Chunk> {
+    fn chunk(&mut self, pos: BlockPos) -> Option<&mut LightChunk<'w>> {
         let cp = pos.chunk();
         let hit = matches!(&self.current, Some((c, _)) if *c == cp);
         if !hit {
             self.current = None; // release the old guard BEFORE acquiring
-            self.current = self.world.get_chunk_mut(&cp).map(|r| (cp, r));
+            self.current = self.world.light_chunk(&cp).map(|c| (cp, c));
         }
-        self.current.as_mut().map(|(_, c)| &mut **c)
+        self.current.as_mut().map(|(_, c)| c)
     }
 
     #[inline]
@@ -135,7 +131,7 @@ impl<'w> CachedWorld<'w> {
 /// that re-propagates from every independent source encountered during the
 /// first phase.
 fn update_block_light(
-    world: &World,
+    world: &WorldView,
     pos: BlockPos,
     new_emit: u8,
     new_opacity: u8,
@@ -232,7 +228,7 @@ fn update_block_light(
 /// re-evaluation (block inserted at the top of a tall open shaft) is not yet
 /// handled — the BFS radius is 15, which is fine for most placements.
 fn update_sky_light(
-    world: &World,
+    world: &WorldView,
     pos: BlockPos,
     new_opacity: u8,
     events: &mut Vec<Event>,
@@ -302,7 +298,7 @@ fn update_sky_light(
 
 /// Compute what sky-light should be at `pos` given its opacity, honoring the
 /// direct-column rule for transparent cells under an unobstructed sky.
-fn compute_sky_at(world: &World, pos: BlockPos, opacity: u8) -> u8 {
+fn compute_sky_at(world: &WorldView, pos: BlockPos, opacity: u8) -> u8 {
     if opacity == 0 {
         let above = BlockPos::new(pos.x, pos.y + 1, pos.z);
         if above.y <= MAX_Y && world.get_sky_light(above) == 15 {
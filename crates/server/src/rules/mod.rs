@@ -1,15 +1,39 @@
 pub mod block_updates;
+pub mod config;
 pub mod helpers;
 pub mod light;
+pub mod projectiles;
+pub mod scripts;
 
 use ultimate_engine::rules::RuleSet;
 
-/// The standard Minecraft rule set: gravity + water + lava + light.
+/// The standard Minecraft rule set: gravity + water + lava (and their
+/// interaction) + light + projectile impacts, minus any `rules.toml`
+/// disables (see [`config`]), then one rule per loaded script (see
+/// [`scripts`]).
 pub fn standard() -> RuleSet {
+    let enabled = config::with_active(|active| active.config.clone());
     let mut rules = RuleSet::new();
-    rules.add(block_updates::gravity);
-    rules.add(block_updates::water_spread);
-    rules.add(block_updates::lava_spread);
-    rules.add(light::light_propagation);
+    if enabled.gravity.enabled {
+        rules.add_named("gravity", block_updates::gravity);
+    }
+    if enabled.water.enabled {
+        rules.add_named("water_spread", block_updates::water_spread);
+    }
+    if enabled.lava.enabled {
+        rules.add_named("lava_spread", block_updates::lava_spread);
+    }
+    if enabled.lava_water.enabled {
+        rules.add_named("lava_water_interaction", block_updates::lava_water_interaction);
+    }
+    if enabled.light.enabled {
+        rules.add_named("light_propagation", light::light_propagation);
+    }
+    if enabled.projectiles.enabled {
+        rules.add_named("projectile_hit", projectiles::projectile_hit);
+    }
+    for (name, slot) in scripts::active().names().zip(scripts::SLOTS) {
+        rules.add_named(name, slot);
+    }
     rules
 }
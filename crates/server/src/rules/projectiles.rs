@@ -0,0 +1,34 @@
+//! Projectile impacts: what a snowball, egg or arrow does to the block it
+//! hits. Impacts arrive as [`PROJECTILE_HIT`] custom events from
+//! [`crate::projectiles`].
+//!
+//! Fragile blocks shatter, as in vanilla: chorus flowers, decorated pots
+//! and pointed dripstone break into air.
+
+use azalea_block::BlockState;
+use azalea_registry::builtin::BlockKind;
+
+use super::helpers::block_set;
+use crate::projectiles::PROJECTILE_HIT;
+use ultimate_engine::causal::event::{Event, EventPayload};
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::WorldView;
+
+pub fn projectile_hit(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
+    let EventPayload::Custom { kind: PROJECTILE_HIT, pos, .. } = payload else {
+        return Vec::new();
+    };
+    let hit = world.get_block(*pos);
+    if shatters(hit) {
+        vec![block_set(*pos, hit, BlockId::AIR)]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Does a projectile break this block?
+fn shatters(id: BlockId) -> bool {
+    BlockState::try_from(id.0 as u32).is_ok_and(|state| {
+        matches!(BlockKind::from(state), BlockKind::ChorusFlower | BlockKind::DecoratedPot | BlockKind::PointedDripstone)
+    })
+}
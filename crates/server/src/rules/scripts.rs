@@ -0,0 +1,455 @@
+//! Scripted rules: small block rules written in [Rhai](https://rhai.rs)
+//! and loaded from `scripts.dir` (`*.rhai`), for customisation that
+//! doesn't warrant a recompile.
+//!
+//! A script defines either or both hooks, each handed an `area` — the
+//! 3×3×3 blocks around the event, offsets `-1..=1` from its centre:
+//!
+//! ```text
+//! // scripts/moss.rhai: cobblestone placed touching water turns mossy.
+//! fn on_set(area, before, after) {
+//!     if after == "minecraft:cobblestone" && area.touching("minecraft:water") {
+//!         area.set(0, 0, 0, "minecraft:mossy_cobblestone");
+//!     }
+//! }
+//!
+//! // fn on_notify(area) { ... }   // a neighbour changed
+//! ```
+//!
+//! `area.get(dx, dy, dz)` names a block, `area.is(dx, dy, dz, name)`,
+//! `area.touching(name)` (any of the six faces) and `area.count(name)`
+//! (all 26 around) test for one, `area.x`/`y`/`z` give the centre, and
+//! `area.set(dx, dy, dz, name)` changes a block. Names are namespaced
+//! (`minecraft:` may be left off) and properties are ignored. Sets become
+//! ordinary `BlockSet` consequences plus neighbour notifies, so scripts
+//! cascade with the built-in rules; keeping to the area is what keeps them
+//! local, as every rule must be.
+//!
+//! Every script is one entry in the [`RuleSet`](ultimate_engine::rules::RuleSet)
+//! [`super::standard`] builds (`script:<file stem>` in the rule profile). Each
+//! call gets `scripts.max_operations` Rhai operations and
+//! `scripts.budget_us` of wall time; a script that runs over, or errors,
+//! contributes nothing for that event and is logged once per load.
+//!
+//! The directory is polled every `scripts.reload_interval_secs` and by
+//! `/reloadrules`; when a file is added, changed or removed every script
+//! is recompiled and installed like a `rules.toml` reload (new cascades
+//! pick them up). A script that doesn't compile keeps the previous set.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result, bail};
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, Scope};
+use ultimate_engine::causal::event::{Event, EventPayload};
+use ultimate_engine::rules::RuleFn;
+use ultimate_engine::world::WorldView;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::position::BlockPos;
+
+use super::helpers::{block_set, notify_neighbors};
+use crate::block;
+use crate::config::ScriptsConfig;
+
+/// Check the wall-clock budget every this many operations; reading the
+/// clock on every one would cost more than most scripts.
+const CLOCK_EVERY: u64 = 256;
+
+/// [`Area::index`]es of the six blocks sharing a face with the centre.
+const FACES: [usize; 6] = [4, 10, 12, 14, 16, 22];
+
+/// The blocks around an event, as scripts see them.
+#[derive(Clone)]
+struct Area {
+    center: BlockPos,
+    /// Indexed by [`Area::index`].
+    blocks: [BlockId; 27],
+}
+
+impl Area {
+    fn read(world: &WorldView, center: BlockPos) -> Self {
+        let mut blocks = [BlockId::AIR; 27];
+        for (i, slot) in blocks.iter_mut().enumerate() {
+            *slot = world.get_block(Self::at(center, i));
+        }
+        Self { center, blocks }
+    }
+
+    /// The position of block `index` of an area around `center`.
+    fn at(center: BlockPos, index: usize) -> BlockPos {
+        let i = index as i64;
+        BlockPos::new(center.x + i % 3 - 1, center.y + i / 3 % 3 - 1, center.z + i / 9 - 1)
+    }
+
+    fn index(dx: i64, dy: i64, dz: i64) -> Result<usize, Box<EvalAltResult>> {
+        if [dx, dy, dz].iter().any(|d| !(-1..=1).contains(d)) {
+            return Err(format!("offset ({}, {}, {}) is outside the area", dx, dy, dz).into());
+        }
+        Ok(((dx + 1) + (dy + 1) * 3 + (dz + 1) * 9) as usize)
+    }
+
+    fn get(&mut self, dx: i64, dy: i64, dz: i64) -> Result<String, Box<EvalAltResult>> {
+        Ok(block::kind_name(self.blocks[Self::index(dx, dy, dz)?]))
+    }
+
+    fn is(&mut self, dx: i64, dy: i64, dz: i64, name: &str) -> Result<bool, Box<EvalAltResult>> {
+        let wanted = lookup(name)?;
+        Ok(block::same_block(self.blocks[Self::index(dx, dy, dz)?], wanted))
+    }
+
+    fn touching(&mut self, name: &str) -> Result<bool, Box<EvalAltResult>> {
+        let wanted = lookup(name)?;
+        Ok(FACES.iter().any(|&i| block::same_block(self.blocks[i], wanted)))
+    }
+
+    fn count(&mut self, name: &str) -> Result<i64, Box<EvalAltResult>> {
+        let wanted = lookup(name)?;
+        let around = self.blocks.iter().enumerate().filter(|&(i, _)| i != 13);
+        Ok(around.filter(|&(_, &id)| block::same_block(id, wanted)).count() as i64)
+    }
+
+    fn set(&mut self, dx: i64, dy: i64, dz: i64, name: &str) -> Result<(), Box<EvalAltResult>> {
+        let index = Self::index(dx, dy, dz)?;
+        let new = lookup(name)?;
+        SETS.with(|sets| sets.borrow_mut().push((index, new)));
+        Ok(())
+    }
+}
+
+fn lookup(name: &str) -> Result<BlockId, Box<EvalAltResult>> {
+    block::block_id_from_name(name).ok_or_else(|| format!("unknown block {:?}", name).into())
+}
+
+thread_local! {
+    /// `area.set` calls made by the script running on this thread.
+    static SETS: RefCell<Vec<(usize, BlockId)>> = const { RefCell::new(Vec::new()) };
+    /// When the script running on this thread runs out of time.
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// One compiled file.
+struct Script {
+    /// `script:<file stem>`, interned (see [`rule_name`]).
+    name: &'static str,
+    ast: AST,
+    on_set: bool,
+    on_notify: bool,
+    /// Set once a failure has been logged, to keep a broken script on a
+    /// busy path from flooding the log.
+    warned: AtomicBool,
+}
+
+/// Every script in the directory, compiled against one engine.
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<Script>,
+    budget: Duration,
+}
+
+impl Scripts {
+    fn engine(config: &ScriptsConfig) -> Engine {
+        let mut engine = Engine::new();
+        engine.set_max_operations(config.max_operations);
+        engine.set_max_call_levels(16);
+        engine.set_max_string_size(1024);
+        engine.set_max_array_size(1024);
+        engine.set_max_map_size(256);
+        engine.on_progress(|ops| {
+            if ops % CLOCK_EVERY == 0 && DEADLINE.get().is_some_and(|deadline| Instant::now() > deadline) {
+                return Some(Dynamic::from("time budget exceeded"));
+            }
+            None
+        });
+        engine.on_print(|text| tracing::info!("[script] {}", text));
+        engine
+            .register_type_with_name::<Area>("Area")
+            .register_get("x", |area: &mut Area| area.center.x)
+            .register_get("y", |area: &mut Area| area.center.y)
+            .register_get("z", |area: &mut Area| area.center.z)
+            .register_fn("get", Area::get)
+            .register_fn("is", Area::is)
+            .register_fn("touching", Area::touching)
+            .register_fn("count", Area::count)
+            .register_fn("set", Area::set);
+        engine
+    }
+
+    /// Compile `sources` (file name, text) under `config`'s limits.
+    pub fn compile(config: &ScriptsConfig, sources: &[(String, String)]) -> Result<Self> {
+        let engine = Self::engine(config);
+        let mut scripts = Vec::new();
+        for (name, text) in sources {
+            let ast = engine.compile(text).map_err(|e| anyhow::anyhow!("{}: {}", name, e))?;
+            let defines = |hook: &str, params: usize| {
+                ast.iter_functions().any(|f| f.name == hook && f.params.len() == params)
+            };
+            let (on_set, on_notify) = (defines("on_set", 3), defines("on_notify", 1));
+            if !on_set && !on_notify {
+                bail!("{}: defines neither on_set(area, before, after) nor on_notify(area)", name);
+            }
+            let name = rule_name(name);
+            scripts.push(Script { name, ast, on_set, on_notify, warned: AtomicBool::new(false) });
+        }
+        Ok(Self { engine, scripts, budget: Duration::from_micros(config.budget_us) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.scripts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    /// Run script `index`'s hook for `payload`.
+    pub fn evaluate(&self, index: usize, world: &WorldView, payload: &EventPayload) -> Vec<Event> {
+        let script = &self.scripts[index];
+        let (pos, hook, args): (_, _, Vec<Dynamic>) = match *payload {
+            EventPayload::BlockSet { pos, old, new } if script.on_set => {
+                (pos, "on_set", vec![block::kind_name(old).into(), block::kind_name(new).into()])
+            }
+            EventPayload::BlockNotify { pos, .. } if script.on_notify => (pos, "on_notify", Vec::new()),
+            _ => return Vec::new(),
+        };
+        let area = Area::read(world, pos);
+        let mut call = vec![Dynamic::from(area.clone())];
+        call.extend(args);
+
+        SETS.with(|sets| sets.borrow_mut().clear());
+        DEADLINE.set(Some(Instant::now() + self.budget));
+        let options = CallFnOptions::new().eval_ast(false);
+        let result = self.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &script.ast, hook, call);
+        DEADLINE.set(None);
+        let sets = SETS.with(|sets| std::mem::take(&mut *sets.borrow_mut()));
+        if let Err(e) = result {
+            if !script.warned.swap(true, Ordering::Relaxed) {
+                tracing::warn!("{} failed at {:?} (further failures not logged): {}", script.name, pos, e);
+            }
+            return Vec::new();
+        }
+
+        // Last set of a block wins; setting what's already there is a no-op.
+        let changes: BTreeMap<usize, BlockId> = sets.into_iter().collect();
+        let mut events = Vec::new();
+        for (index, new) in changes {
+            let old = area.blocks[index];
+            if old == new {
+                continue;
+            }
+            let at = Area::at(pos, index);
+            events.push(block_set(at, old, new));
+            events.extend(notify_neighbors(at));
+        }
+        events
+    }
+
+    /// Rule-profile names, in order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.scripts.iter().map(|s| s.name)
+    }
+}
+
+/// The rule name for the script in file `name`. Rule names are
+/// `&'static`, so each distinct one is allocated once and reused by every
+/// later compile: reloads don't grow memory.
+fn rule_name(name: &str) -> &'static str {
+    static NAMES: LazyLock<Mutex<HashSet<&'static str>>> = LazyLock::new(Default::default);
+    let name = format!("script:{}", name);
+    let mut names = NAMES.lock().expect("script names poisoned");
+    if let Some(&interned) = names.get(name.as_str()) {
+        return interned;
+    }
+    let interned: &'static str = Box::leak(name.into_boxed_str());
+    names.insert(interned);
+    interned
+}
+
+static ACTIVE: LazyLock<RwLock<Arc<Scripts>>> = LazyLock::new(|| {
+    RwLock::new(Arc::new(Scripts::compile(&ScriptsConfig::default(), &[]).expect("no scripts compile")))
+});
+/// What [`load`] was given and the files it saw, for [`reload_if_changed`].
+static SOURCE: Mutex<Option<(ScriptsConfig, BTreeMap<PathBuf, Option<SystemTime>>)>> = Mutex::new(None);
+
+thread_local! {
+    static CACHED: RefCell<Option<(u64, Arc<Scripts>)>> = const { RefCell::new(None) };
+}
+
+/// The installed scripts. Cached per thread until the rules generation
+/// moves, like [`super::config::with_active`].
+pub fn active() -> Arc<Scripts> {
+    CACHED.with(|cached| {
+        let mut cached = cached.borrow_mut();
+        let generation = super::config::generation();
+        if cached.as_ref().is_none_or(|(seen, _)| *seen != generation) {
+            *cached = Some((generation, Arc::clone(&ACTIVE.read().unwrap())));
+        }
+        Arc::clone(&cached.as_ref().unwrap().1)
+    })
+}
+
+/// Rule `N` of the rule set runs script `N`: rules are plain `fn`s, so
+/// each script gets one of these slots, and files past the last slot are
+/// refused at load.
+fn slot<const N: usize>(world: &WorldView, payload: &EventPayload) -> Vec<Event> {
+    let scripts = active();
+    if N < scripts.len() { scripts.evaluate(N, world, payload) } else { Vec::new() }
+}
+
+pub const SLOTS: [RuleFn; 16] = [
+    slot::<0>, slot::<1>, slot::<2>, slot::<3>, slot::<4>, slot::<5>, slot::<6>, slot::<7>,
+    slot::<8>, slot::<9>, slot::<10>, slot::<11>, slot::<12>, slot::<13>, slot::<14>, slot::<15>,
+];
+
+/// `*.rhai` files in `dir` with their mtimes, by path. A missing
+/// directory has none.
+fn scan(dir: &Path) -> Result<BTreeMap<PathBuf, Option<SystemTime>>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e).with_context(|| format!("listing {}", dir.display())),
+    };
+    Ok(entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+        .map(|path| {
+            let mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, mtime)
+        })
+        .collect())
+}
+
+fn compile_files(config: &ScriptsConfig, files: &BTreeMap<PathBuf, Option<SystemTime>>) -> Result<Scripts> {
+    if files.len() > SLOTS.len() {
+        bail!("{} scripts in {}; at most {} are supported", files.len(), config.dir.display(), SLOTS.len());
+    }
+    let mut sources = Vec::new();
+    for path in files.keys() {
+        let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        sources.push((name, text));
+    }
+    Scripts::compile(config, &sources)
+}
+
+fn install(scripts: Scripts) -> String {
+    let summary = match scripts.len() {
+        0 => "no scripts".to_owned(),
+        _ => format!("{} script(s): {}", scripts.len(), scripts.names().collect::<Vec<_>>().join(", ")),
+    };
+    *ACTIVE.write().unwrap() = Arc::new(scripts);
+    super::config::invalidate();
+    summary
+}
+
+/// Compile and install every script in `config.dir`, and remember it for
+/// [`reload_if_changed`]. Does nothing while scripts are disabled.
+pub fn load(config: &ScriptsConfig) -> Result<String> {
+    if !config.enabled {
+        return Ok("scripts disabled".into());
+    }
+    let files = scan(&config.dir)?;
+    let scripts = compile_files(config, &files)?;
+    *SOURCE.lock().unwrap() = Some((config.clone(), files));
+    Ok(install(scripts))
+}
+
+/// Recompile if a script was added, edited or removed since the last
+/// load. `Ok(None)` when nothing changed; on error the running scripts
+/// stay (and the same files aren't retried until they change again).
+pub fn reload_if_changed() -> Result<Option<String>> {
+    let mut source = SOURCE.lock().unwrap();
+    let Some((config, seen)) = source.as_mut() else { return Ok(None) };
+    let files = scan(&config.dir)?;
+    if files == *seen {
+        return Ok(None);
+    }
+    *seen = files.clone();
+    let scripts = compile_files(config, &files)?;
+    Ok(Some(install(scripts)))
+}
+
+/// Poll `config.dir` for edits every `config.reload_interval_secs`, until
+/// `shutdown`.
+pub fn start_reloader(config: &ScriptsConfig, shutdown: crate::shutdown::Shutdown) {
+    if !config.enabled || config.reload_interval_secs == 0 {
+        return;
+    }
+    let interval_secs = config.reload_interval_secs;
+    tokio::spawn(async move {
+        let _running = shutdown.task();
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.triggered() => break,
+            }
+            match reload_if_changed() {
+                Ok(Some(summary)) => tracing::info!("Scripts reloaded: {}", summary),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Script reload failed (keeping previous scripts): {:#}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultimate_engine::world::World;
+
+    fn compile(text: &str) -> Result<Scripts> {
+        Scripts::compile(&ScriptsConfig::default(), &[("test".into(), text.into())])
+    }
+
+    #[test]
+    fn test_script_turns_placed_block_by_its_neighbours() {
+        let scripts = compile(
+            r#"fn on_set(area, before, after) {
+                if after == "minecraft:cobblestone" && area.touching("water") {
+                    area.set(0, 0, 0, "minecraft:mossy_cobblestone");
+                }
+            }"#,
+        )
+        .unwrap();
+        let world = World::new();
+        let (pos, next_to) = (BlockPos::new(3, 10, 3), BlockPos::new(4, 10, 3));
+        let cobble = block::block_id_from_name("cobblestone").unwrap();
+        world.set_block(pos, cobble);
+        let placed = EventPayload::BlockSet { pos, old: BlockId::AIR, new: cobble };
+        assert!(scripts.evaluate(0, &world.view(), &placed).is_empty(), "no water yet");
+
+        world.set_block(next_to, block::WATER);
+        let events = scripts.evaluate(0, &world.view(), &placed);
+        let mossy = block::block_id_from_name("mossy_cobblestone").unwrap();
+        assert!(matches!(events[0].payload, EventPayload::BlockSet { pos: p, old, new } if p == pos && old == cobble && new == mossy));
+        assert_eq!(events.len(), 7, "the set and its six notifies");
+        let notify = EventPayload::BlockNotify { pos, from: None };
+        assert!(scripts.evaluate(0, &world.view(), &notify).is_empty(), "no on_notify hook");
+    }
+
+    #[test]
+    fn test_scripts_are_checked_and_budgeted() {
+        assert!(compile("fn on_set(area, before, after) {").is_err());
+        assert!(compile("fn unrelated() {}").is_err());
+
+        let world = World::new();
+        let notify = EventPayload::BlockNotify { pos: BlockPos::new(0, 10, 0), from: None };
+        let spinning = compile("fn on_notify(area) { loop { area.set(0, 0, 0, \"stone\"); } }").unwrap();
+        assert!(spinning.evaluate(0, &world.view(), &notify).is_empty());
+        let reaching = compile("fn on_notify(area) { area.set(0, 2, 0, \"stone\"); }").unwrap();
+        assert!(reaching.evaluate(0, &world.view(), &notify).is_empty());
+    }
+
+    #[test]
+    fn test_recompiling_reuses_rule_names() {
+        let first = compile("fn on_notify(area) {}").unwrap();
+        let again = compile("fn on_notify(area) {}").unwrap();
+        let (a, b) = (first.names().next().unwrap(), again.names().next().unwrap());
+        assert_eq!(a, "script:test");
+        assert!(std::ptr::eq(a, b), "a reload doesn't allocate the name again");
+    }
+}
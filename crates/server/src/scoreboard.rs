@@ -0,0 +1,376 @@
+//! Scoreboard: named objectives holding a score per player, shown on the
+//! sidebar, in the tab list or below name tags.
+//!
+//! Objectives are created by server code or `/scoreboard`. Each has a
+//! [`Criterion`]: `dummy` objectives only change when set explicitly, the
+//! others count what players do as the server reports it ([`Scoreboard::record`]
+//! from the connection: deaths and kills from combat, blocks placed and
+//! broken from block edits).
+//!
+//! Every change is broadcast as ready-to-send packets, which connections
+//! relay after the snapshot from [`Scoreboard::join`]. The scoreboard is
+//! saved with the world (`data/scoreboard.json`, see
+//! [`crate::persistence::WorldStorage::save`]).
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{Context, Result};
+use azalea_chat::FormattedText;
+use azalea_chat::numbers::NumberFormat;
+use azalea_core::objectives::ObjectiveCriteria;
+use azalea_protocol::packets::game::c_set_display_objective::{ClientboundSetDisplayObjective, DisplaySlot};
+use azalea_protocol::packets::game::c_set_objective::{ClientboundSetObjective, Method};
+use azalea_protocol::packets::game::{
+    ClientboundGamePacket, ClientboundResetScore, ClientboundSetScore,
+};
+use azalea_protocol::packets::Packet;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Where the scoreboard is saved, relative to the world directory.
+pub const SCOREBOARD_FILE: &str = "data/scoreboard.json";
+
+/// What an objective counts. Names follow vanilla's criteria where one
+/// exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Criterion {
+    #[serde(rename = "dummy")]
+    Dummy,
+    #[serde(rename = "deathCount")]
+    Deaths,
+    #[serde(rename = "playerKillCount")]
+    PlayerKills,
+    #[serde(rename = "blocksPlaced")]
+    BlocksPlaced,
+    #[serde(rename = "blocksBroken")]
+    BlocksBroken,
+}
+
+impl Criterion {
+    pub const ALL: [Criterion; 5] =
+        [Criterion::Dummy, Criterion::Deaths, Criterion::PlayerKills, Criterion::BlocksPlaced, Criterion::BlocksBroken];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Criterion::Dummy => "dummy",
+            Criterion::Deaths => "deathCount",
+            Criterion::PlayerKills => "playerKillCount",
+            Criterion::BlocksPlaced => "blocksPlaced",
+            Criterion::BlocksBroken => "blocksBroken",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Criterion> {
+        Criterion::ALL.into_iter().find(|c| c.name() == name)
+    }
+}
+
+/// Where an objective can be displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Slot {
+    Sidebar,
+    List,
+    BelowName,
+}
+
+impl Slot {
+    pub const ALL: [Slot; 3] = [Slot::Sidebar, Slot::List, Slot::BelowName];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Slot::Sidebar => "sidebar",
+            Slot::List => "list",
+            Slot::BelowName => "below_name",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Slot> {
+        Slot::ALL.into_iter().find(|s| s.name() == name)
+    }
+
+    fn protocol(self) -> DisplaySlot {
+        match self {
+            Slot::Sidebar => DisplaySlot::Sidebar,
+            Slot::List => DisplaySlot::List,
+            Slot::BelowName => DisplaySlot::BelowName,
+        }
+    }
+}
+
+/// One objective and its scores, by player name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Objective {
+    pub criterion: Criterion,
+    pub display_name: String,
+    #[serde(default)]
+    pub scores: BTreeMap<String, i32>,
+}
+
+/// The saved form: objectives by name and what each slot shows.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct State {
+    #[serde(default)]
+    objectives: BTreeMap<String, Objective>,
+    #[serde(default)]
+    display: BTreeMap<Slot, String>,
+}
+
+/// Shared scoreboard. Mutations update the state and broadcast the
+/// matching packets under one lock, so a joining client's snapshot and
+/// the updates after it never disagree.
+pub struct Scoreboard {
+    path: PathBuf,
+    state: RwLock<State>,
+    tx: broadcast::Sender<ClientboundGamePacket>,
+}
+
+impl Scoreboard {
+    /// Load the scoreboard saved in world directory `dir` (empty if none).
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(SCOREBOARD_FILE);
+        let state = match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).with_context(|| format!("parsing {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => State::default(),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+        };
+        Ok(Self::with_state(path, state))
+    }
+
+    /// A scoreboard with nothing on it, saved in world directory `dir`.
+    pub fn empty(dir: &Path) -> Self {
+        Self::with_state(dir.join(SCOREBOARD_FILE), State::default())
+    }
+
+    fn with_state(path: PathBuf, state: State) -> Self {
+        let (tx, _) = broadcast::channel(1024);
+        Self { path, state: RwLock::new(state), tx }
+    }
+
+    /// Write the scoreboard back to the world directory.
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&*self.state.read().expect("scoreboard poisoned"))?;
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, json).with_context(|| format!("writing {}", self.path.display()))
+    }
+
+    /// Everything a client joining now needs (objectives, scores, display
+    /// slots), and every change after it. Taken under one lock: a client
+    /// that saw an objective added twice would disconnect.
+    pub fn join(&self) -> (Vec<ClientboundGamePacket>, broadcast::Receiver<ClientboundGamePacket>) {
+        let state = self.state.read().expect("scoreboard poisoned");
+        let mut out = Vec::new();
+        for (name, objective) in &state.objectives {
+            out.push(objective_packet(name, Some(objective)));
+            for (owner, &score) in &objective.scores {
+                out.push(score_packet(owner, name, score));
+            }
+        }
+        for (slot, name) in &state.display {
+            out.push(display_packet(*slot, name));
+        }
+        (out, self.tx.subscribe())
+    }
+
+    /// Objectives as (name, criterion, display name), sorted by name.
+    pub fn objectives(&self) -> Vec<(String, Criterion, String)> {
+        let state = self.state.read().expect("scoreboard poisoned");
+        state.objectives.iter().map(|(n, o)| (n.clone(), o.criterion, o.display_name.clone())).collect()
+    }
+
+    /// `owner`'s scores as (objective, score).
+    pub fn scores_of(&self, owner: &str) -> Vec<(String, i32)> {
+        let state = self.state.read().expect("scoreboard poisoned");
+        state
+            .objectives
+            .iter()
+            .filter_map(|(n, o)| o.scores.get(owner).map(|&s| (n.clone(), s)))
+            .collect()
+    }
+
+    pub fn score(&self, objective: &str, owner: &str) -> Option<i32> {
+        let state = self.state.read().expect("scoreboard poisoned");
+        state.objectives.get(objective)?.scores.get(owner).copied()
+    }
+
+    /// Create an objective. False if the name is taken.
+    pub fn add_objective(&self, name: &str, criterion: Criterion, display_name: &str) -> bool {
+        let mut state = self.state.write().expect("scoreboard poisoned");
+        if state.objectives.contains_key(name) {
+            return false;
+        }
+        let objective = Objective { criterion, display_name: display_name.to_owned(), scores: BTreeMap::new() };
+        self.send(objective_packet(name, Some(&objective)));
+        state.objectives.insert(name.to_owned(), objective);
+        true
+    }
+
+    /// Delete an objective, its scores and its display slots. False if
+    /// there is no such objective.
+    pub fn remove_objective(&self, name: &str) -> bool {
+        let mut state = self.state.write().expect("scoreboard poisoned");
+        if state.objectives.remove(name).is_none() {
+            return false;
+        }
+        state.display.retain(|_, shown| shown != name);
+        self.send(objective_packet(name, None));
+        true
+    }
+
+    /// Show `objective` in `slot`, or clear the slot with `None`. False if
+    /// there is no such objective.
+    pub fn set_display(&self, slot: Slot, objective: Option<&str>) -> bool {
+        let mut state = self.state.write().expect("scoreboard poisoned");
+        match objective {
+            Some(name) if !state.objectives.contains_key(name) => return false,
+            Some(name) => state.display.insert(slot, name.to_owned()),
+            None => state.display.remove(&slot),
+        };
+        self.send(display_packet(slot, objective.unwrap_or("")));
+        true
+    }
+
+    /// Set `owner`'s score. False if there is no such objective.
+    pub fn set_score(&self, objective: &str, owner: &str, score: i32) -> bool {
+        self.update(objective, owner, |_| score).is_some()
+    }
+
+    /// Add `amount` to `owner`'s score (from zero if unset); the new score,
+    /// or `None` if there is no such objective.
+    pub fn add_score(&self, objective: &str, owner: &str, amount: i32) -> Option<i32> {
+        self.update(objective, owner, |old| old.unwrap_or(0).saturating_add(amount))
+    }
+
+    /// Remove `owner`'s score in `objective`, or in every objective with
+    /// `None`. False if there was nothing to remove.
+    pub fn reset(&self, owner: &str, objective: Option<&str>) -> bool {
+        let mut state = self.state.write().expect("scoreboard poisoned");
+        let mut removed = false;
+        for (name, o) in &mut state.objectives {
+            if objective.is_none_or(|only| only == name) {
+                removed |= o.scores.remove(owner).is_some();
+            }
+        }
+        if removed {
+            self.send(ClientboundResetScore {
+                owner: owner.to_owned(),
+                objective_name: objective.map(str::to_owned),
+            }.into_variant());
+        }
+        removed
+    }
+
+    /// Count `amount` of something `owner` did towards every objective
+    /// with `criterion`.
+    pub fn record(&self, criterion: Criterion, owner: &str, amount: i32) {
+        let mut state = self.state.write().expect("scoreboard poisoned");
+        for (name, o) in &mut state.objectives {
+            if o.criterion == criterion {
+                let score = o.scores.entry(owner.to_owned()).or_insert(0);
+                *score = score.saturating_add(amount);
+                self.send(score_packet(owner, name, *score));
+            }
+        }
+    }
+
+    fn update(&self, objective: &str, owner: &str, f: impl FnOnce(Option<i32>) -> i32) -> Option<i32> {
+        let mut state = self.state.write().expect("scoreboard poisoned");
+        let o = state.objectives.get_mut(objective)?;
+        let score = f(o.scores.get(owner).copied());
+        o.scores.insert(owner.to_owned(), score);
+        self.send(score_packet(owner, objective, score));
+        Some(score)
+    }
+
+    fn send(&self, packet: ClientboundGamePacket) {
+        // No receivers (nobody online) is fine.
+        let _ = self.tx.send(packet);
+    }
+}
+
+/// Add an objective; `None` removes it.
+fn objective_packet(name: &str, objective: Option<&Objective>) -> ClientboundGamePacket {
+    let method = match objective {
+        None => Method::Remove,
+        Some(o) => Method::Add {
+            display_name: FormattedText::from(o.display_name.clone()),
+            render_type: ObjectiveCriteria::Integer,
+            number_format: NumberFormat::Blank,
+        },
+    };
+    ClientboundSetObjective { objective_name: name.to_owned(), method }.into_variant()
+}
+
+fn score_packet(owner: &str, objective: &str, score: i32) -> ClientboundGamePacket {
+    ClientboundSetScore {
+        owner: owner.to_owned(),
+        objective_name: objective.to_owned(),
+        // A VarInt on the wire, so negative scores survive the cast.
+        score: score as u32,
+        display: None,
+        number_format: None,
+    }.into_variant()
+}
+
+/// Show `objective` in `slot`; an empty name clears it.
+fn display_packet(slot: Slot, objective: &str) -> ClientboundGamePacket {
+    ClientboundSetDisplayObjective { slot: slot.protocol(), objective_name: objective.to_owned() }.into_variant()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_counts_matching_objectives() {
+        let dir = std::env::temp_dir().join("ultimate_mc_test_scoreboard_record");
+        let _ = std::fs::remove_dir_all(&dir);
+        let board = Scoreboard::load(&dir).unwrap();
+        let (_, mut rx) = board.join();
+        assert!(board.add_objective("deaths", Criterion::Deaths, "Deaths"));
+        assert!(!board.add_objective("deaths", Criterion::Dummy, "Again"));
+        assert!(board.add_objective("notes", Criterion::Dummy, "Notes"));
+
+        board.record(Criterion::Deaths, "Steve", 1);
+        board.record(Criterion::Deaths, "Steve", 1);
+        assert_eq!(board.score("deaths", "Steve"), Some(2));
+        assert_eq!(board.score("notes", "Steve"), None, "dummy objectives don't count");
+        assert_eq!(board.add_score("notes", "Alex", -3), Some(-3));
+        assert_eq!(board.add_score("missing", "Alex", 1), None);
+
+        // add, add, score, score, score
+        let mut sent = 0;
+        while rx.try_recv().is_ok() {
+            sent += 1;
+        }
+        assert_eq!(sent, 5);
+
+        assert!(board.set_display(Slot::Sidebar, Some("deaths")));
+        assert!(!board.set_display(Slot::List, Some("missing")));
+        assert!(board.remove_objective("deaths"));
+        // Objective, the one score left, no display.
+        assert_eq!(board.join().0.len(), 2);
+    }
+
+    #[test]
+    fn test_scoreboard_survives_a_save() {
+        let dir = std::env::temp_dir().join("ultimate_mc_test_scoreboard_save");
+        let _ = std::fs::remove_dir_all(&dir);
+        let board = Scoreboard::load(&dir).unwrap();
+        board.add_objective("placed", Criterion::BlocksPlaced, "Blocks placed");
+        board.set_display(Slot::Sidebar, Some("placed"));
+        board.record(Criterion::BlocksPlaced, "Steve", 7);
+        board.save().unwrap();
+
+        let loaded = Scoreboard::load(&dir).unwrap();
+        assert_eq!(loaded.score("placed", "Steve"), Some(7));
+        assert_eq!(loaded.objectives(), board.objectives());
+        assert_eq!(loaded.join().0, board.join().0);
+        assert!(loaded.reset("Steve", None));
+        assert!(!loaded.reset("Steve", Some("placed")));
+    }
+}
@@ -0,0 +1,122 @@
+//! Graceful shutdown: one token every long-running task watches.
+//!
+//! `main` triggers it on Ctrl+C. The listener stops accepting,
+//! connections send their client a disconnect with the reason, simulation
+//! layers and the autosave loop finish the tick they're in, and the
+//! dashboard stops serving. Each of those holds a [`TaskGuard`] while it
+//! runs, so `main` can wait (bounded, see [`Shutdown::wait_for_tasks`])
+//! for them before the final save.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+
+/// Cloneable handle to the server's shutdown state.
+#[derive(Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// The reason, once triggered.
+    reason: watch::Sender<Option<String>>,
+    /// Tasks holding a [`TaskGuard`].
+    tasks: watch::Sender<usize>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self { inner: Arc::new(Inner { reason: watch::channel(None).0, tasks: watch::channel(0).0 }) }
+    }
+
+    /// Start shutting down. Later calls keep the first reason.
+    pub fn trigger(&self, reason: &str) {
+        self.inner.reason.send_if_modified(|current| {
+            if current.is_some() {
+                return false;
+            }
+            *current = Some(reason.to_owned());
+            true
+        });
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.inner.reason.borrow().is_some()
+    }
+
+    /// Wait until shutdown is triggered; returns the reason.
+    pub async fn triggered(&self) -> String {
+        let mut rx = self.inner.reason.subscribe();
+        let reason = rx.wait_for(Option::is_some).await.map(|reason| reason.clone().unwrap_or_default());
+        match reason {
+            Ok(reason) => reason,
+            // The sender lives in `self`, so this can't close.
+            Err(_) => std::future::pending().await,
+        }
+    }
+
+    /// Count a task as running until the guard drops.
+    pub fn task(&self) -> TaskGuard {
+        self.inner.tasks.send_modify(|n| *n += 1);
+        TaskGuard(self.clone())
+    }
+
+    /// Tasks currently holding a guard.
+    pub fn running(&self) -> usize {
+        *self.inner.tasks.borrow()
+    }
+
+    /// Wait up to `timeout` for every guarded task to finish. Returns how
+    /// many were still running when it gave up (0 if all finished).
+    pub async fn wait_for_tasks(&self, timeout: Duration) -> usize {
+        let mut rx = self.inner.tasks.subscribe();
+        let _ = tokio::time::timeout(timeout, rx.wait_for(|&n| n == 0)).await;
+        self.running()
+    }
+}
+
+/// Marks a task as running for [`Shutdown::wait_for_tasks`].
+pub struct TaskGuard(Shutdown);
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.0.inner.tasks.send_modify(|n| *n -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_trigger_wakes_tasks_and_waits_for_them() {
+        let shutdown = Shutdown::new();
+        let task = {
+            let shutdown = shutdown.clone();
+            let guard = shutdown.task();
+            tokio::spawn(async move {
+                let reason = shutdown.triggered().await;
+                drop(guard);
+                reason
+            })
+        };
+        let stuck = shutdown.task();
+        assert_eq!(shutdown.running(), 2);
+        assert!(!shutdown.is_triggered());
+
+        shutdown.trigger("Server closed");
+        shutdown.trigger("ignored");
+        assert_eq!(task.await.unwrap(), "Server closed");
+        assert_eq!(shutdown.wait_for_tasks(Duration::from_millis(20)).await, 1, "one task never finishes");
+        drop(stuck);
+        assert_eq!(shutdown.wait_for_tasks(Duration::from_secs(1)).await, 0);
+        assert_eq!(shutdown.triggered().await, "Server closed", "resolves at once after the fact");
+    }
+}
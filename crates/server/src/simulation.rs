@@ -1,7 +1,9 @@
 //! Ambient simulation framework.
 //!
 //! Each [`SimulationLayer`] runs on its own tokio task, periodically
-//! generating root causal events. Since Phase 6b-1 the layers are pure
+//! generating root causal events. The generation itself runs on the
+//! blocking pool ([`crate::pools`]) so a slow world scan never stalls the
+//! runtime's connection tasks. Since Phase 6b-1 the layers are pure
 //! event *sources*: generated events are submitted to the shared physics
 //! service, which runs the cascade on the server-wide causal graph and
 //! broadcasts the resulting changes on the event bus.
@@ -18,6 +20,8 @@ use ultimate_engine::causal::event::Event;
 use ultimate_engine::world::World;
 
 use crate::physics::PhysicsHandle;
+use crate::pools::Pools;
+use crate::shutdown::Shutdown;
 
 /// A pluggable simulation layer that generates root causal events on a timer.
 ///
@@ -40,16 +44,23 @@ pub trait SimulationLayer: Send + Sync + 'static {
 ///
 /// Each task loops on `layer.interval()` and submits generated events to
 /// the shared physics service; the service runs the cascade and publishes
-/// the resulting changes to the event bus.
+/// the resulting changes to the event bus. At `shutdown` each task
+/// finishes the tick it is in and stops.
 pub fn start(
     world: Arc<World>,
     layers: Vec<Box<dyn SimulationLayer>>,
     physics: PhysicsHandle,
+    pools: Arc<Pools>,
+    shutdown: Shutdown,
 ) {
     for layer in layers {
+        let layer: Arc<dyn SimulationLayer> = Arc::from(layer);
         let world = Arc::clone(&world);
         let physics = physics.clone();
+        let pools = Arc::clone(&pools);
+        let shutdown = shutdown.clone();
         tokio::spawn(async move {
+            let _running = shutdown.task();
             let name = layer.name();
             let mut interval = tokio::time::interval(layer.interval());
             // The first tick fires immediately; skip it so the world has time to initialize.
@@ -58,9 +69,13 @@ pub fn start(
             tracing::info!("Simulation layer '{}' started (interval {:?})", name, layer.interval());
 
             loop {
-                interval.tick().await;
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = shutdown.triggered() => break,
+                }
 
-                let events = layer.generate_events(&world);
+                let (layer, world) = (Arc::clone(&layer), Arc::clone(&world));
+                let events = pools.run_blocking(move || layer.generate_events(&world)).await;
                 if events.is_empty() {
                     continue;
                 }
@@ -68,6 +83,7 @@ pub fn start(
                 tracing::debug!("Simulation '{}': submitting {} root events", name, events.len());
                 physics.submit_events(events);
             }
+            tracing::info!("Simulation layer '{}' stopped", name);
         });
     }
 }
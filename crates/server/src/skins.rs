@@ -0,0 +1,217 @@
+//! Player skins for offline mode.
+//!
+//! Offline-mode profiles carry no properties, so the client draws every
+//! player with a default skin and blank tab-list heads. The resolver looks
+//! up the signed `textures` property by player name, in order:
+//!
+//! 1. `<dir>/<name>.json` in the operator's local skins directory;
+//! 2. `<cache_dir>/<name>.json`, if younger than the cache TTL;
+//! 3. Mojang (name → UUID → session profile), when enabled; the answer,
+//!    including "no such account", is written to the cache.
+//!
+//! Files hold the property exactly as the session server returns it:
+//! `{"value": "<base64>", "signature": "<base64>"}` (`signature` may be
+//! omitted). The client only fetches textures from Mojang's own hosts, so
+//! a local entry must still point at a texture uploaded there.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use azalea_auth::game_profile::{GameProfileProperties, ProfilePropertyValue};
+use serde::{Deserialize, Serialize};
+
+use crate::config::SkinsConfig;
+
+const PROFILE_URL: &str = "https://api.mojang.com/users/profiles/minecraft/";
+const SESSION_URL: &str = "https://sessionserver.mojang.com/session/minecraft/profile/";
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A `textures` profile property as stored on disk.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Textures {
+    value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+/// Resolves player names to profile properties. Cheap to share.
+pub struct SkinResolver {
+    dir: Option<PathBuf>,
+    mojang: Option<Mojang>,
+}
+
+struct Mojang {
+    client: reqwest::Client,
+    cache_dir: PathBuf,
+    ttl: Duration,
+}
+
+impl SkinResolver {
+    pub fn new(cfg: &SkinsConfig) -> Result<Self> {
+        let mojang = if cfg.mojang {
+            std::fs::create_dir_all(&cfg.cache_dir)
+                .with_context(|| format!("creating skin cache {}", cfg.cache_dir.display()))?;
+            let client = reqwest::Client::builder()
+                .timeout(HTTP_TIMEOUT)
+                .build()
+                .context("building skin HTTP client")?;
+            Some(Mojang {
+                client,
+                cache_dir: cfg.cache_dir.clone(),
+                ttl: Duration::from_secs(cfg.cache_ttl_secs),
+            })
+        } else {
+            None
+        };
+        Ok(Self { dir: cfg.dir.clone(), mojang })
+    }
+
+    /// A resolver that never finds anything.
+    pub fn disabled() -> Self {
+        Self { dir: None, mojang: None }
+    }
+
+    /// Profile properties for `name`: its `textures` if one was found,
+    /// otherwise empty. Lookup failures are logged, never fatal — a
+    /// missing skin must not keep anyone from joining.
+    pub async fn properties(&self, name: &str) -> Arc<GameProfileProperties> {
+        let mut props = GameProfileProperties::default();
+        match self.textures(name).await {
+            Ok(Some(t)) => {
+                props.map.insert(
+                    "textures".to_owned(),
+                    ProfilePropertyValue { value: t.value, signature: t.signature },
+                );
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Skin lookup for {} failed: {:#}", name, e),
+        }
+        Arc::new(props)
+    }
+
+    async fn textures(&self, name: &str) -> Result<Option<Textures>> {
+        // Names become file names; anything outside the vanilla charset
+        // can't be a real account anyway.
+        if !valid_name(name) {
+            return Ok(None);
+        }
+        let file = format!("{}.json", name.to_ascii_lowercase());
+        if let Some(dir) = &self.dir {
+            let path = dir.join(&file);
+            if path.exists() {
+                return read_entry(&path).await;
+            }
+        }
+        let Some(mojang) = &self.mojang else {
+            return Ok(None);
+        };
+        let cached = mojang.cache_dir.join(&file);
+        if is_fresh(&cached, mojang.ttl) {
+            return read_entry(&cached).await;
+        }
+        let textures = mojang.fetch(name).await?;
+        let json = serde_json::to_vec(&textures)?;
+        if let Err(e) = tokio::fs::write(&cached, json).await {
+            tracing::warn!("Writing skin cache {}: {}", cached.display(), e);
+        }
+        Ok(textures)
+    }
+}
+
+impl Mojang {
+    /// Name → UUID → signed textures. `None` when no account has the name.
+    async fn fetch(&self, name: &str) -> Result<Option<Textures>> {
+        #[derive(Deserialize)]
+        struct Profile {
+            id: String,
+        }
+        #[derive(Deserialize)]
+        struct Session {
+            properties: Vec<Property>,
+        }
+        #[derive(Deserialize)]
+        struct Property {
+            name: String,
+            value: String,
+            signature: Option<String>,
+        }
+
+        let resp = self.client.get(format!("{}{}", PROFILE_URL, name)).send().await?;
+        if matches!(resp.status().as_u16(), 204 | 404) {
+            return Ok(None);
+        }
+        let profile: Profile = resp.error_for_status()?.json().await?;
+        let session: Session = self
+            .client
+            .get(format!("{}{}?unsigned=false", SESSION_URL, profile.id))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(session
+            .properties
+            .into_iter()
+            .find(|p| p.name == "textures")
+            .map(|p| Textures { value: p.value, signature: p.signature }))
+    }
+}
+
+/// Vanilla usernames: 1–16 of `[A-Za-z0-9_]`.
+fn valid_name(name: &str) -> bool {
+    (1..=16).contains(&name.len()) && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// Whether a cache file exists and was written within `ttl`.
+fn is_fresh(path: &Path, ttl: Duration) -> bool {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .is_ok_and(|t| t.elapsed().is_ok_and(|age| age < ttl))
+}
+
+/// Read a skin file. A cache file holding `null` records a name with no
+/// account behind it.
+async fn read_entry(path: &Path) -> Result<Option<Textures>> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .with_context(|| format!("reading {}", path.display()))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("parsing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_dir_and_cache_lookup() {
+        let root = std::env::temp_dir().join(format!("ultimate_mc_test_skins_{}", std::process::id()));
+        let local = root.join("local");
+        let cache = root.join("cache");
+        std::fs::create_dir_all(&local).unwrap();
+        std::fs::write(local.join("alice.json"), r#"{"value":"QQ==","signature":"c2ln"}"#).unwrap();
+
+        let cfg = SkinsConfig {
+            dir: Some(local),
+            mojang: true,
+            cache_dir: cache.clone(),
+            cache_ttl_secs: 3600,
+        };
+        let skins = SkinResolver::new(&cfg).unwrap();
+        // Fresh cache entries answer without touching the network,
+        // including remembered misses.
+        std::fs::write(cache.join("bob.json"), r#"{"value":"Qg=="}"#).unwrap();
+        std::fs::write(cache.join("nobody.json"), "null").unwrap();
+
+        let alice = skins.properties("Alice").await;
+        let t = &alice.map["textures"];
+        assert_eq!((t.value.as_str(), t.signature.as_deref()), ("QQ==", Some("c2ln")));
+        assert_eq!(skins.properties("bob").await.map["textures"].signature, None);
+        assert!(skins.properties("nobody").await.map.is_empty());
+        assert!(skins.properties("../alice").await.map.is_empty(), "names are not paths");
+        assert!(SkinResolver::disabled().properties("Alice").await.map.is_empty());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,268 @@
+//! Compact binary world snapshots.
+//!
+//! A self-contained dump of every chunk's block data — no generator, no
+//! NBT, no region sectors — meant for test fixtures and benchmarks that
+//! want a known world in milliseconds. `umc-convert` translates between
+//! this format and Anvil region saves.
+//!
+//! Layout (little-endian, whole stream zlib-compressed):
+//!
+//! ```text
+//! magic "UMCSNAP\0" · version u32 · chunk_count u32
+//! per chunk:   cx i32 · cz i32 · section_count u16
+//! per section: y i32 · palette_len u16 · palette [u16; len]
+//!              · (palette_len > 1) packed indices, MC long packing
+//! ```
+//!
+//! Indices reuse the Anvil bit-packing from `persistence`, so a section
+//! costs the same bytes here as in a region file minus the NBT framing.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use ultimate_engine::world::World;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::chunk::Chunk;
+use ultimate_engine::world::position::{ChunkPos, LocalBlockPos};
+
+use crate::persistence::{diff_cells, pack_indices, unpack_indices};
+
+const MAGIC: &[u8; 8] = b"UMCSNAP\0";
+const VERSION: u32 = 1;
+
+/// Write every chunk of `world` to `path`. `progress` is called as
+/// `(done, total)` after each chunk. Returns the number of chunks written.
+pub fn write(world: &World, path: &Path, progress: &mut dyn FnMut(usize, usize)) -> Result<usize> {
+    let total = world.chunk_count();
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&(total as u32).to_le_bytes());
+
+    let mut written = 0usize;
+    for entry in world.iter_chunks() {
+        encode_chunk(&mut out, *entry.key(), entry.value());
+        written += 1;
+        progress(written, total);
+    }
+
+    let mut z = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    z.write_all(&out)?;
+    let bytes = z.finish()?;
+    fs::write(path, bytes).with_context(|| format!("writing snapshot {}", path.display()))?;
+    Ok(written)
+}
+
+/// Read a snapshot into `world` (chunks inserted clean, not dirty).
+/// Returns the number of chunks loaded.
+pub fn read_into(world: &World, path: &Path) -> Result<usize> {
+    let file = fs::File::open(path).with_context(|| format!("opening snapshot {}", path.display()))?;
+    let mut raw = Vec::new();
+    flate2::read::ZlibDecoder::new(file)
+        .read_to_end(&mut raw)
+        .with_context(|| format!("decompressing snapshot {}", path.display()))?;
+    let mut r = Reader { buf: &raw, at: 0 };
+
+    if r.take(8)? != MAGIC {
+        bail!("{} is not a world snapshot", path.display());
+    }
+    let version = r.u32()?;
+    if version != VERSION {
+        bail!("unsupported snapshot version {} (expected {})", version, VERSION);
+    }
+    let count = r.u32()? as usize;
+    for _ in 0..count {
+        let (pos, chunk) = decode_chunk(&mut r)?;
+        world.insert_chunk(pos, chunk);
+    }
+    if r.at != raw.len() {
+        bail!("{} trailing bytes after {} chunks", raw.len() - r.at, count);
+    }
+    Ok(count)
+}
+
+/// Append one chunk's record (position, then its non-empty sections).
+fn encode_chunk(out: &mut Vec<u8>, pos: ChunkPos, chunk: &Chunk) {
+    let mut sections: Vec<(i32, Vec<BlockId>)> = chunk
+        .sections()
+        .filter(|(_, s)| !s.is_empty())
+        .map(|(&y, s)| (y, (0..4096).map(|i| s.get_by_index(i)).collect()))
+        .collect();
+    sections.sort_unstable_by_key(|(y, _)| *y);
+
+    out.extend_from_slice(&pos.x.to_le_bytes());
+    out.extend_from_slice(&pos.z.to_le_bytes());
+    out.extend_from_slice(&(sections.len() as u16).to_le_bytes());
+    for (y, cells) in &sections {
+        let mut palette: Vec<BlockId> = Vec::new();
+        let mut indices = [0u16; 4096];
+        for (i, &block) in cells.iter().enumerate() {
+            indices[i] = match palette.iter().position(|&b| b == block) {
+                Some(idx) => idx as u16,
+                None => {
+                    palette.push(block);
+                    (palette.len() - 1) as u16
+                }
+            };
+        }
+        out.extend_from_slice(&y.to_le_bytes());
+        out.extend_from_slice(&(palette.len() as u16).to_le_bytes());
+        for block in &palette {
+            out.extend_from_slice(&block.0.to_le_bytes());
+        }
+        if let Some(longs) = pack_indices(&indices, palette.len()) {
+            for long in longs {
+                out.extend_from_slice(&long.to_le_bytes());
+            }
+        }
+    }
+}
+
+/// Read one chunk record written by [`encode_chunk`].
+fn decode_chunk(r: &mut Reader) -> Result<(ChunkPos, Chunk)> {
+    let pos = ChunkPos::new(r.i32()?, r.i32()?);
+    let mut chunk = Chunk::new();
+    for _ in 0..r.u16()? {
+        let y = r.i32()?;
+        let palette_len = r.u16()? as usize;
+        if palette_len == 0 {
+            bail!("empty palette in chunk ({}, {}) section {}", pos.x, pos.z, y);
+        }
+        let palette: Vec<BlockId> =
+            (0..palette_len).map(|_| r.u16().map(BlockId)).collect::<Result<_>>()?;
+        let indices = if palette_len > 1 {
+            let bits = (usize::BITS - (palette_len - 1).leading_zeros()).max(4) as usize;
+            let longs = 4096usize.div_ceil(64 / bits);
+            let data: Vec<i64> = (0..longs).map(|_| r.i64()).collect::<Result<_>>()?;
+            unpack_indices(&data, palette_len)
+        } else {
+            [0u16; 4096]
+        };
+        for (cell, &idx) in indices.iter().enumerate() {
+            let block = *palette.get(idx as usize).with_context(|| {
+                format!("palette index {} out of range in chunk ({}, {})", idx, pos.x, pos.z)
+            })?;
+            if block != BlockId::AIR {
+                let local = LocalBlockPos {
+                    x: (cell & 15) as u8,
+                    y: y as i64 * 16 + (cell >> 8) as i64,
+                    z: ((cell >> 4) & 15) as u8,
+                };
+                chunk.set_block(local, block);
+            }
+        }
+    }
+    Ok((pos, chunk))
+}
+
+/// One chunk in the snapshot record format, uncompressed — for embedding
+/// chunks in other streams (the replay journal).
+pub(crate) fn chunk_to_bytes(pos: ChunkPos, chunk: &Chunk) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_chunk(&mut out, pos, chunk);
+    out
+}
+
+/// Inverse of [`chunk_to_bytes`].
+pub(crate) fn chunk_from_bytes(bytes: &[u8]) -> Result<(ChunkPos, Chunk)> {
+    let mut r = Reader { buf: bytes, at: 0 };
+    let chunk = decode_chunk(&mut r)?;
+    if r.at != bytes.len() {
+        bail!("{} trailing bytes after chunk record", bytes.len() - r.at);
+    }
+    Ok(chunk)
+}
+
+/// Chunk positions whose blocks differ between `a` and `b`, including
+/// chunks present in only one of them. Empty means the worlds match.
+pub fn diff_worlds(a: &World, b: &World) -> Vec<ChunkPos> {
+    let empty = Chunk::new();
+    let mut mismatched: Vec<ChunkPos> = a
+        .iter_chunks()
+        .filter(|entry| match b.get_chunk(entry.key()) {
+            Some(other) => !diff_cells(entry.value(), &other).is_empty(),
+            None => !diff_cells(entry.value(), &empty).is_empty(),
+        })
+        .map(|entry| *entry.key())
+        .collect();
+    mismatched.extend(
+        b.iter_chunks()
+            .filter(|entry| !a.has_chunk(*entry.key()) && !diff_cells(entry.value(), &empty).is_empty())
+            .map(|entry| *entry.key()),
+    );
+    mismatched
+}
+
+/// Little-endian cursor over the decompressed snapshot.
+struct Reader<'a> {
+    buf: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let Some(bytes) = self.buf.get(self.at..self.at + n) else {
+            bail!("snapshot truncated at byte {}", self.at);
+        };
+        self.at += n;
+        Ok(bytes)
+    }
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into()?))
+    }
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultimate_engine::world::position::BlockPos;
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let world = World::new();
+        for x in -20..20i64 {
+            world.set_block(BlockPos::new(x, -3, x * 2), crate::block::STONE);
+            world.set_block(BlockPos::new(x, 70, 5), crate::block::SAND);
+        }
+        // A section with a large palette exercises wide index packing.
+        for i in 0..40u16 {
+            world.set_block(BlockPos::new(i as i64 % 16, 100, i as i64 / 16), BlockId(i + 1));
+        }
+
+        let path = std::env::temp_dir().join("ultimate_mc_test_snapshot.umcs");
+        let mut calls = 0;
+        let n = write(&world, &path, &mut |_, _| calls += 1).unwrap();
+        assert_eq!(n, world.chunk_count());
+        assert_eq!(calls, n);
+
+        let loaded = World::new();
+        assert_eq!(read_into(&loaded, &path).unwrap(), n);
+        assert!(diff_worlds(&world, &loaded).is_empty());
+        assert_eq!(loaded.dirty_count(), 0, "snapshot loads must not dirty");
+
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_diff_worlds_reports_mismatch() {
+        let a = World::new();
+        let b = World::new();
+        a.set_block(BlockPos::new(1, 1, 1), crate::block::STONE);
+        b.set_block(BlockPos::new(1, 1, 1), crate::block::STONE);
+        assert!(diff_worlds(&a, &b).is_empty());
+        b.set_block(BlockPos::new(40, 1, 1), crate::block::DIRT);
+        assert_eq!(diff_worlds(&a, &b), vec![ChunkPos::new(2, 0)]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Tab list header and footer, with live placeholders, and the latency
+//! column.
+//!
+//! The header and footer are templates ([`TabList::set_format`], seeded
+//! from `tab_list` in the config). Every `refresh_secs` the service
+//! ([`start`]) renders them and broadcasts a [`TabListUpdate`] carrying the
+//! text plus every player's latency, as measured from keep-alive round
+//! trips (`PlayerRegistry::set_latency`); connections relay it like boss
+//! bar updates. Placeholders:
+//!
+//! | placeholder | value                                        |
+//! |-------------|----------------------------------------------|
+//! | `{online}`  | players online                               |
+//! | `{max}`     | `network.max_players` (live, see `motd`)     |
+//! | `{tps}`     | ticks per second (≤ 20, see `ticks`)         |
+//! | `{time}`    | world time of day, `HH:MM`                   |
+//! | `{day}`     | world day, counting from 1                   |
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::config::TabListConfig;
+use crate::motd::ServerStatus;
+use crate::persistence::WorldStorage;
+use crate::player_registry::PlayerRegistry;
+use crate::shutdown::Shutdown;
+
+/// What placeholders expand to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Values {
+    pub online: usize,
+    pub max: u32,
+    pub tps: f64,
+    /// World game time in ticks.
+    pub time: i64,
+}
+
+/// Expand the placeholders in `template`. Unknown `{...}` is left as is.
+pub fn render(template: &str, v: &Values) -> String {
+    // Tick 0 is 06:00; a day is 24000 ticks.
+    let of_day = v.time.rem_euclid(24_000);
+    let minutes = (of_day * 1440 / 24_000 + 360) % 1440;
+    template
+        .replace("{online}", &v.online.to_string())
+        .replace("{max}", &v.max.to_string())
+        .replace("{tps}", &format!("{:.1}", v.tps))
+        .replace("{time}", &format!("{:02}:{:02}", minutes / 60, minutes % 60))
+        .replace("{day}", &(v.time.div_euclid(24_000) + 1).to_string())
+}
+
+/// One refresh for every connection.
+#[derive(Debug, Clone)]
+pub struct TabListUpdate {
+    pub header: String,
+    pub footer: String,
+    /// Latency in milliseconds per online player.
+    pub latencies: Arc<[(Uuid, i32)]>,
+}
+
+/// The header/footer templates and the last rendering.
+pub struct TabList {
+    format: RwLock<(String, String)>,
+    current: RwLock<Option<(String, String)>>,
+    tx: broadcast::Sender<TabListUpdate>,
+}
+
+impl Default for TabList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TabList {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(16);
+        Self { format: RwLock::new((String::new(), String::new())), current: RwLock::new(None), tx }
+    }
+
+    /// Replace the header and footer templates; shown from the next
+    /// refresh on.
+    pub fn set_format(&self, header: &str, footer: &str) {
+        *self.format.write().expect("tab list poisoned") = (header.to_owned(), footer.to_owned());
+    }
+
+    pub fn format(&self) -> (String, String) {
+        self.format.read().expect("tab list poisoned").clone()
+    }
+
+    /// The last rendered header and footer (`None` before the first
+    /// refresh), and every update after.
+    pub fn join(&self) -> (Option<(String, String)>, broadcast::Receiver<TabListUpdate>) {
+        let current = self.current.read().expect("tab list poisoned");
+        (current.clone(), self.tx.subscribe())
+    }
+
+    /// Render the templates with `values` and send them with `latencies`.
+    pub fn publish(&self, values: &Values, latencies: Arc<[(Uuid, i32)]>) {
+        let (header, footer) = self.format();
+        let (header, footer) = (render(&header, values), render(&footer, values));
+        let mut current = self.current.write().expect("tab list poisoned");
+        // No receivers (nobody online) is fine.
+        let _ = self.tx.send(TabListUpdate { header: header.clone(), footer: footer.clone(), latencies });
+        *current = Some((header, footer));
+    }
+}
+
+/// Refresh the tab list every `config.refresh_secs` until `shutdown`.
+pub fn start(
+    config: &TabListConfig,
+    status: Arc<ServerStatus>,
+    registry: Arc<PlayerRegistry>,
+    storage: Arc<WorldStorage>,
+    shutdown: Shutdown,
+) {
+    registry.tab_list.set_format(&config.header, &config.footer);
+    let refresh = Duration::from_secs(config.refresh_secs.max(1));
+    tokio::spawn(async move {
+        let _running = shutdown.task();
+        let mut interval = tokio::time::interval(refresh);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.triggered() => break,
+            }
+            let tps = registry.ticks.stats().tps;
+            let players = registry.snapshot();
+            let values = Values { online: players.len(), max: status.max_players(), tps, time: storage.level_info().time };
+            let latencies = players.iter().map(|p| (p.uuid, p.latency)).collect();
+            registry.tab_list.publish(&values, latencies);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholders_render() {
+        let v = Values { online: 3, max: 20, tps: 19.96, time: 24_000 + 6_000 };
+        assert_eq!(render("{online}/{max} · TPS {tps}", &v), "3/20 · TPS 20.0");
+        assert_eq!(render("Day {day}, {time} {unknown}", &v), "Day 2, 12:00 {unknown}");
+        let night = Values { time: 18_000, ..v };
+        assert_eq!(render("{time}", &night), "00:00");
+        assert_eq!(render("{time}", &Values { time: 23_999, ..v }), "05:59");
+    }
+
+    #[test]
+    fn test_publish_reaches_subscribers_and_joiners() {
+        let tab = TabList::new();
+        tab.set_format("Hello", "{online} online");
+        let (current, mut rx) = tab.join();
+        assert!(current.is_none());
+
+        let v = Values { online: 2, max: 20, tps: 20.0, time: 0 };
+        tab.publish(&v, Arc::from(vec![(Uuid::nil(), 42)]));
+        let update = rx.try_recv().unwrap();
+        assert_eq!((update.header.as_str(), update.footer.as_str()), ("Hello", "2 online"));
+        assert_eq!(&*update.latencies, &[(Uuid::nil(), 42)]);
+        assert_eq!(tab.join().0, Some(("Hello".into(), "2 online".into())));
+    }
+}
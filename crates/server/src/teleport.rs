@@ -0,0 +1,98 @@
+//! Player teleports: `/spawn`, `/home`, and `/tpa` requests.
+//!
+//! Commands decide where a player goes and hand the move to the player's
+//! own connection (`PlayerRegistry::teleport`), which owns their position
+//! and loaded chunks. The connection generates the destination's chunks
+//! off its task first ([`preload`]), sends them, and only then moves the
+//! client, so a long jump lands on terrain rather than in the void.
+//!
+//! A `/tpa` request waits for its target's `/tpaccept` for
+//! [`REQUEST_TIMEOUT`]; pending requests live in [`TeleportRequests`].
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use ultimate_engine::world::World;
+use ultimate_engine::world::position::ChunkPos;
+use uuid::Uuid;
+
+use crate::pools::Pools;
+use crate::worldgen::WorldGen;
+
+/// How long a `/tpa` request can be accepted.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Chunks generated around a destination before the move: the ring the
+/// client needs to stand on.
+pub const PRELOAD_RADIUS: i32 = 2;
+
+/// Pending `/tpa` requests: for each target, who asked and when.
+#[derive(Default)]
+pub struct TeleportRequests {
+    pending: Mutex<HashMap<Uuid, HashMap<Uuid, Instant>>>,
+}
+
+impl TeleportRequests {
+    /// Record that `from` asks to teleport to `to`. False if the same
+    /// request is already pending.
+    pub fn request(&self, from: Uuid, to: Uuid, now: Instant) -> bool {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let asks = pending.entry(to).or_default();
+        asks.retain(|_, at| now.duration_since(*at) < REQUEST_TIMEOUT);
+        asks.insert(from, now).is_none()
+    }
+
+    /// Take `to`'s pending request from `from`, or their latest one with
+    /// `None`. Returns who asked.
+    pub fn accept(&self, to: Uuid, from: Option<Uuid>, now: Instant) -> Option<Uuid> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let asks = pending.get_mut(&to)?;
+        asks.retain(|_, at| now.duration_since(*at) < REQUEST_TIMEOUT);
+        let from = match from {
+            Some(from) => from,
+            None => *asks.iter().max_by_key(|&(_, at)| *at)?.0,
+        };
+        let taken = asks.remove(&from).map(|_| from);
+        if asks.is_empty() {
+            pending.remove(&to);
+        }
+        taken
+    }
+}
+
+/// Generate the chunks within [`PRELOAD_RADIUS`] of `(x, z)` on the
+/// blocking pool.
+pub async fn preload(world: &Arc<World>, worldgen: &Arc<dyn WorldGen>, pools: &Pools, x: f64, z: f64) {
+    let (world, worldgen) = (Arc::clone(world), Arc::clone(worldgen));
+    let (cx, cz) = ((x.floor() as i32) >> 4, (z.floor() as i32) >> 4);
+    pools
+        .run_blocking(move || {
+            for chunk in ChunkPos::new(cx, cz).chunks_in_radius(PRELOAD_RADIUS) {
+                worldgen.ensure_generated(&world, chunk.x, chunk.z);
+            }
+        })
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_accept_latest_and_expire() {
+        let requests = TeleportRequests::default();
+        let (alice, bob, carol) = (Uuid::from_u128(1), Uuid::from_u128(2), Uuid::from_u128(3));
+        let start = Instant::now();
+
+        assert!(requests.request(bob, alice, start));
+        assert!(!requests.request(bob, alice, start), "already pending");
+        assert!(requests.request(carol, alice, start + Duration::from_secs(1)));
+        assert_eq!(requests.accept(alice, None, start + Duration::from_secs(2)), Some(carol), "latest first");
+        assert_eq!(requests.accept(alice, Some(carol), start + Duration::from_secs(2)), None);
+        assert_eq!(requests.accept(bob, None, start), None, "requests are per target");
+
+        let late = start + REQUEST_TIMEOUT + Duration::from_secs(1);
+        assert_eq!(requests.accept(alice, Some(bob), late), None, "expired");
+    }
+}
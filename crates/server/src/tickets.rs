@@ -0,0 +1,149 @@
+//! Simulation distance: which chunks run physics.
+//!
+//! Loaded is not the same as simulated. A player keeps chunks loaded out to
+//! `network.view_distance`, but, as with vanilla's entity-ticking chunk
+//! tickets, only chunks within `network.simulation_distance` of some player
+//! simulate. [`SimulationTickets`] counts, per chunk, the players whose
+//! simulation square covers it. The player registry keeps it up to date as
+//! players join, move and leave. Physics workers gate their schedulers on
+//! [`SimulationTickets::is_active`]
+//! (`Scheduler::with_activity`), so a rule's write into an inactive chunk
+//! is parked on the graph instead of running. When a chunk gains its first
+//! ticket the activation hooks run; the physics service uses one to wake
+//! the worker holding that chunk's parked events.
+//!
+//! Root events always run, whoever submits them. Only cascades stop at the
+//! edge of the simulated area, and they carry on when a player comes near.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+
+use dashmap::DashMap;
+use ultimate_engine::world::position::ChunkPos;
+
+type Hook = Box<dyn Fn(ChunkPos) + Send + Sync>;
+
+/// Per-chunk simulation tickets held by online players.
+pub struct SimulationTickets {
+    /// Chebyshev radius, in chunks, of each player's simulation square.
+    distance: i32,
+    /// The chunk each player's tickets are centred on.
+    players: Mutex<HashMap<u64, ChunkPos>>,
+    tickets: DashMap<ChunkPos, u32>,
+    on_activate: RwLock<Vec<Hook>>,
+}
+
+impl SimulationTickets {
+    pub fn new(distance: i32) -> Self {
+        Self {
+            distance: distance.max(0),
+            players: Mutex::new(HashMap::new()),
+            tickets: DashMap::new(),
+            on_activate: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Whether some player is within simulation distance of `chunk`.
+    pub fn is_active(&self, chunk: ChunkPos) -> bool {
+        self.tickets.contains_key(&chunk)
+    }
+
+    /// Chunks currently simulating.
+    pub fn active_len(&self) -> usize {
+        self.tickets.len()
+    }
+
+    /// Run `hook` for each chunk that gains its first ticket from now on.
+    pub fn on_activate(&self, hook: impl Fn(ChunkPos) + Send + Sync + 'static) {
+        self.on_activate.write().expect("ticket hooks poisoned").push(Box::new(hook));
+    }
+
+    /// Centre `conn_id`'s tickets on the chunk holding block `(x, z)`.
+    pub fn set_player(&self, conn_id: u64, x: f64, z: f64) {
+        let center = ChunkPos::new((x.floor() as i32) >> 4, (z.floor() as i32) >> 4);
+        let mut activated = Vec::new();
+        {
+            let mut players = self.players.lock().expect("ticket players poisoned");
+            let old = players.insert(conn_id, center);
+            if old == Some(center) {
+                return;
+            }
+            // Add before removing, so chunks both squares cover never
+            // drop to zero in between.
+            for chunk in center.chunks_in_radius(self.distance) {
+                let mut count = self.tickets.entry(chunk).or_insert(0);
+                *count += 1;
+                if *count == 1 {
+                    activated.push(chunk);
+                }
+            }
+            if let Some(old) = old {
+                self.release(old);
+            }
+        }
+        self.activated(&activated);
+    }
+
+    /// Drop `conn_id`'s tickets.
+    pub fn remove_player(&self, conn_id: u64) {
+        let mut players = self.players.lock().expect("ticket players poisoned");
+        if let Some(old) = players.remove(&conn_id) {
+            self.release(old);
+        }
+    }
+
+    fn release(&self, center: ChunkPos) {
+        for chunk in center.chunks_in_radius(self.distance) {
+            self.tickets.remove_if_mut(&chunk, |_, count| {
+                *count -= 1;
+                *count == 0
+            });
+        }
+    }
+
+    fn activated(&self, chunks: &[ChunkPos]) {
+        if chunks.is_empty() {
+            return;
+        }
+        let hooks = self.on_activate.read().expect("ticket hooks poisoned");
+        for &chunk in chunks {
+            for hook in hooks.iter() {
+                hook(chunk);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_tickets_follow_players() {
+        let tickets = SimulationTickets::new(1);
+        let woken = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&woken);
+        tickets.on_activate(move |c| log.lock().unwrap().push(c));
+
+        tickets.set_player(1, 8.0, 8.0);
+        assert_eq!(tickets.active_len(), 9);
+        assert!(tickets.is_active(ChunkPos::new(-1, 1)));
+        assert!(!tickets.is_active(ChunkPos::new(2, 0)));
+
+        // A second player overlapping the first only activates new chunks.
+        woken.lock().unwrap().clear();
+        tickets.set_player(2, 24.0, 8.0);
+        assert_eq!(woken.lock().unwrap().len(), 3);
+        assert!(tickets.is_active(ChunkPos::new(2, 0)));
+
+        // Moving within a chunk changes nothing; leaving keeps shared chunks.
+        tickets.set_player(2, 30.0, 1.0);
+        tickets.remove_player(1);
+        assert!(tickets.is_active(ChunkPos::new(0, 0)));
+        assert!(!tickets.is_active(ChunkPos::new(-1, 0)));
+        assert_eq!(tickets.active_len(), 9);
+        tickets.remove_player(2);
+        assert_eq!(tickets.active_len(), 0);
+    }
+}
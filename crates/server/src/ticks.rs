@@ -0,0 +1,156 @@
+//! Server tick rate: TPS and MSPT, measured in one place so the tab list,
+//! `/tps`, the dashboard and the debug channel agree.
+//!
+//! [`start`] runs a heartbeat at vanilla's 20 ticks a second. Each tick
+//! runs a probe on the blocking pool, where world work (mob AI,
+//! simulation layers, saves) runs, and counts the time from when the tick
+//! was due until the probe is done: that is MSPT, and it climbs as soon
+//! as the runtime or the pool falls behind. Ticks that come too late are
+//! skipped, which is what brings TPS under 20. Every [`WINDOW`] the
+//! averages go to the registry's [`TickMeter`] and the dashboard.
+//!
+//! A client mod or proxy asks on [`DEBUG_CHANNEL`]: any payload is
+//! answered with the current numbers as a JSON protocol string,
+//! `{"tps":19.98,"mspt":0.42}`.
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::dashboard::DashboardState;
+use crate::player_registry::PlayerRegistry;
+use crate::pools::Pools;
+use crate::shutdown::Shutdown;
+
+/// One game tick (vanilla's 20 per second).
+pub const TICK: Duration = Duration::from_millis(50);
+
+/// How long the averages run over.
+pub const WINDOW: Duration = Duration::from_secs(1);
+
+/// The plugin channel the numbers are served on.
+pub const DEBUG_CHANNEL: &str = "ultimate:debug";
+
+/// Tick rate over the last [`WINDOW`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TickStats {
+    /// Ticks per second, at most 20.
+    pub tps: f64,
+    /// Milliseconds per tick, from due to done.
+    pub mspt: f64,
+}
+
+impl Default for TickStats {
+    /// A server that hasn't measured yet is assumed to keep up.
+    fn default() -> Self {
+        Self { tps: 20.0, mspt: 0.0 }
+    }
+}
+
+/// The latest [`TickStats`], for everyone who shows them.
+#[derive(Default)]
+pub struct TickMeter {
+    stats: RwLock<TickStats>,
+}
+
+impl TickMeter {
+    pub fn stats(&self) -> TickStats {
+        *self.stats.read().expect("tick meter poisoned")
+    }
+
+    fn set(&self, stats: TickStats) {
+        *self.stats.write().expect("tick meter poisoned") = stats;
+    }
+
+    /// The [`DEBUG_CHANNEL`] payload.
+    pub fn payload(&self) -> Vec<u8> {
+        let json = serde_json::to_string(&self.stats()).expect("tick stats serialize");
+        crate::channels::encode_string(&json)
+    }
+}
+
+/// Ticks counted since a window opened.
+#[derive(Debug, Default)]
+struct Window {
+    ticks: u32,
+    busy: Duration,
+}
+
+impl Window {
+    fn record(&mut self, took: Duration) {
+        self.ticks += 1;
+        self.busy += took;
+    }
+
+    /// The stats of the window, `elapsed` long, and a fresh one.
+    fn finish(&mut self, elapsed: Duration) -> TickStats {
+        let window = std::mem::take(self);
+        if window.ticks == 0 {
+            return TickStats { tps: 0.0, mspt: elapsed.as_secs_f64() * 1000.0 };
+        }
+        TickStats {
+            tps: (window.ticks as f64 / elapsed.as_secs_f64()).min(20.0),
+            mspt: window.busy.as_secs_f64() * 1000.0 / window.ticks as f64,
+        }
+    }
+}
+
+/// Serve [`DEBUG_CHANNEL`] and measure ticks until `shutdown`.
+pub fn start(registry: Arc<PlayerRegistry>, dashboard: Arc<DashboardState>, pools: Arc<Pools>, shutdown: Shutdown) {
+    let debug = Arc::clone(&registry);
+    registry.channels.register(DEBUG_CHANNEL, move |_| Some(debug.ticks.payload()));
+    tokio::spawn(async move {
+        let _running = shutdown.task();
+        let mut interval = tokio::time::interval(TICK);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let (mut window, mut since) = (Window::default(), Instant::now());
+        loop {
+            let due = tokio::select! {
+                due = interval.tick() => due.into_std(),
+                _ = shutdown.triggered() => break,
+            };
+            pools.run_blocking(|| ()).await;
+            window.record(due.elapsed());
+            let elapsed = since.elapsed();
+            if elapsed < WINDOW {
+                continue;
+            }
+            since = Instant::now();
+            let stats = window.finish(elapsed);
+            registry.ticks.set(stats);
+            dashboard.metrics.set_ticks(stats);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_averages() {
+        let mut window = Window::default();
+        for ms in [1, 2, 3, 2] {
+            window.record(Duration::from_millis(ms));
+        }
+        let stats = window.finish(Duration::from_millis(200));
+        assert_eq!(stats, TickStats { tps: 20.0, mspt: 2.0 });
+
+        for _ in 0..10 {
+            window.record(Duration::from_millis(60));
+        }
+        let stats = window.finish(Duration::from_secs(1));
+        assert_eq!(stats.tps, 10.0, "only the counted ticks, from a fresh window");
+        assert!((stats.mspt - 60.0).abs() < 1e-9);
+        assert_eq!(window.finish(Duration::from_secs(1)).tps, 0.0, "a stalled heartbeat");
+    }
+
+    #[test]
+    fn test_debug_payload() {
+        let meter = TickMeter::default();
+        meter.set(TickStats { tps: 19.5, mspt: 4.25 });
+        let json = crate::channels::decode_string(&meter.payload()).unwrap();
+        assert_eq!(json, r#"{"tps":19.5,"mspt":4.25}"#);
+    }
+}
@@ -0,0 +1,420 @@
+//! Villager trading: trade lists loaded from JSON, and the merchant
+//! window a villager opens when a player right-clicks it.
+//!
+//! `trades.json` (see `trading.file`; written with the defaults below
+//! when missing) maps professions to their offers:
+//!
+//! ```json
+//! { "farmer": [ { "buy": { "id": "minecraft:wheat", "count": 20 },
+//!                 "sell": { "id": "minecraft:emerald" },
+//!                 "max_uses": 16, "xp": 2 } ] }
+//! ```
+//!
+//! `buy_b` adds a second price; `count` defaults to 1 and `max_uses` to
+//! 12. Each villager (see [`crate::mobs`]) has one profession, picked by
+//! its UUID, so it offers the same trades every time. Uses are counted per
+//! villager until the server restarts, when every villager restocks.
+//!
+//! [`Merchant`] is the open window. Its slots are the two payment slots,
+//! the result, and the player's inventory (window slots 3-38 are
+//! inventory slots 9-44), so clicks below the trade go straight to
+//! [`Inventory::click`]. Selecting a trade fills the payment slots from
+//! the inventory, as vanilla does; taking the result pays for it.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use azalea_inventory::ItemStack;
+use azalea_inventory::operations::ClickType;
+use azalea_registry::builtin::ItemKind;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::inventory::{self, Inventory};
+use crate::recipes::{self, RecipeBook};
+
+/// Written to `trading.file` when it doesn't exist.
+pub const DEFAULT_TRADES: &str = r#"{
+  "armorer": [
+    { "buy": { "id": "minecraft:coal", "count": 15 }, "sell": { "id": "minecraft:emerald" }, "max_uses": 16, "xp": 2 },
+    { "buy": { "id": "minecraft:emerald", "count": 5 }, "sell": { "id": "minecraft:iron_helmet" }, "xp": 1 },
+    { "buy": { "id": "minecraft:emerald", "count": 9 }, "sell": { "id": "minecraft:iron_chestplate" }, "xp": 1 }
+  ],
+  "farmer": [
+    { "buy": { "id": "minecraft:wheat", "count": 20 }, "sell": { "id": "minecraft:emerald" }, "max_uses": 16, "xp": 2 },
+    { "buy": { "id": "minecraft:potato", "count": 26 }, "sell": { "id": "minecraft:emerald" }, "max_uses": 16, "xp": 2 },
+    { "buy": { "id": "minecraft:emerald" }, "sell": { "id": "minecraft:bread", "count": 6 }, "max_uses": 16, "xp": 1 }
+  ],
+  "fletcher": [
+    { "buy": { "id": "minecraft:stick", "count": 32 }, "sell": { "id": "minecraft:emerald" }, "max_uses": 16, "xp": 2 },
+    { "buy": { "id": "minecraft:emerald" }, "sell": { "id": "minecraft:arrow", "count": 16 }, "xp": 1 },
+    { "buy": { "id": "minecraft:emerald" }, "buy_b": { "id": "minecraft:gravel", "count": 10 },
+      "sell": { "id": "minecraft:flint", "count": 10 }, "xp": 1 }
+  ],
+  "toolsmith": [
+    { "buy": { "id": "minecraft:coal", "count": 15 }, "sell": { "id": "minecraft:emerald" }, "max_uses": 16, "xp": 2 },
+    { "buy": { "id": "minecraft:emerald" }, "sell": { "id": "minecraft:stone_pickaxe" }, "xp": 1 },
+    { "buy": { "id": "minecraft:emerald", "count": 3 }, "sell": { "id": "minecraft:iron_shovel" }, "xp": 1 }
+  ]
+}
+"#;
+
+/// Uses of an offer without `max_uses`, as for most vanilla trades.
+const DEFAULT_MAX_USES: u32 = 12;
+
+/// An item and how many of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cost {
+    pub item: ItemKind,
+    pub count: i32,
+}
+
+impl Cost {
+    pub fn stack(self) -> ItemStack {
+        ItemStack::new(self.item, self.count)
+    }
+
+    /// Whether `payment` covers this cost.
+    fn paid_by(self, payment: &ItemStack) -> bool {
+        payment.kind() == self.item && payment.count() >= self.count
+    }
+}
+
+/// One trade a villager offers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Offer {
+    pub buy: Cost,
+    pub buy_b: Option<Cost>,
+    pub sell: Cost,
+    pub max_uses: u32,
+    /// Villager experience; sent to the client, not otherwise used.
+    pub xp: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawCost {
+    id: String,
+    #[serde(default = "one")]
+    count: i32,
+}
+
+fn one() -> i32 {
+    1
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawOffer {
+    buy: RawCost,
+    #[serde(default)]
+    buy_b: Option<RawCost>,
+    sell: RawCost,
+    #[serde(default)]
+    max_uses: Option<u32>,
+    #[serde(default)]
+    xp: u32,
+}
+
+impl RawOffer {
+    fn resolve(&self) -> Result<Offer> {
+        Ok(Offer {
+            buy: self.buy.resolve()?,
+            buy_b: self.buy_b.as_ref().map(RawCost::resolve).transpose()?,
+            sell: self.sell.resolve()?,
+            max_uses: self.max_uses.unwrap_or(DEFAULT_MAX_USES),
+            xp: self.xp,
+        })
+    }
+}
+
+impl RawCost {
+    fn resolve(&self) -> Result<Cost> {
+        let item = recipes::item(&self.id)?;
+        let max = inventory::max_stack(item);
+        anyhow::ensure!((1..=max).contains(&self.count), "{} count {} is outside 1..={}", self.id, self.count, max);
+        Ok(Cost { item, count: self.count })
+    }
+}
+
+/// Every profession's offers, and how often each villager's have been
+/// used.
+#[derive(Debug, Default)]
+pub struct Trades {
+    professions: BTreeMap<String, Vec<Offer>>,
+    uses: Mutex<HashMap<Uuid, Vec<u32>>>,
+}
+
+impl Trades {
+    /// Read `path`, writing [`DEFAULT_TRADES`] there first if it doesn't
+    /// exist.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            std::fs::write(path, DEFAULT_TRADES).with_context(|| format!("writing {}", path.display()))?;
+        }
+        let json = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&json).with_context(|| format!("in {}", path.display()))
+    }
+
+    pub fn parse(json: &str) -> Result<Self> {
+        let raw: BTreeMap<String, Vec<RawOffer>> = serde_json::from_str(json)?;
+        let mut professions = BTreeMap::new();
+        for (profession, offers) in raw {
+            let offers = offers
+                .iter()
+                .enumerate()
+                .map(|(i, offer)| offer.resolve().with_context(|| format!("{} offer {}", profession, i + 1)))
+                .collect::<Result<Vec<_>>>()?;
+            if !offers.is_empty() {
+                professions.insert(profession, offers);
+            }
+        }
+        Ok(Self { professions, uses: Mutex::default() })
+    }
+
+    /// How many professions have offers.
+    pub fn len(&self) -> usize {
+        self.professions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.professions.is_empty()
+    }
+
+    /// Villager `uuid`'s profession and offers; `None` with no trades.
+    pub fn profession(&self, uuid: Uuid) -> Option<(&str, &[Offer])> {
+        let nth = (uuid.as_u128() % self.professions.len().max(1) as u128) as usize;
+        self.professions.iter().nth(nth).map(|(name, offers)| (name.as_str(), offers.as_slice()))
+    }
+
+    /// Times villager `uuid` has made each of its offers.
+    pub fn uses(&self, uuid: Uuid) -> Vec<u32> {
+        let count = self.profession(uuid).map_or(0, |(_, offers)| offers.len());
+        let mut uses = self.uses.lock().unwrap().get(&uuid).cloned().unwrap_or_default();
+        uses.resize(count, 0);
+        uses
+    }
+
+    /// Offer `index` of villager `uuid`, if it isn't used up.
+    fn available(&self, uuid: Uuid, index: usize) -> Option<Offer> {
+        let offer = self.profession(uuid)?.1.get(index)?;
+        (self.uses(uuid)[index] < offer.max_uses).then(|| offer.clone())
+    }
+
+    fn record_use(&self, uuid: Uuid, index: usize) {
+        let mut all = self.uses.lock().unwrap();
+        let uses = all.entry(uuid).or_default();
+        if uses.len() <= index {
+            uses.resize(index + 1, 0);
+        }
+        uses[index] += 1;
+    }
+}
+
+/// Slots of a merchant window before the player's inventory.
+pub const MERCHANT_SLOTS: usize = 3;
+
+/// Window slot of the result.
+const RESULT: usize = 2;
+
+/// Inventory slot minus merchant window slot, below the trade.
+const INVENTORY_OFFSET: usize = 9 - MERCHANT_SLOTS;
+
+/// A merchant window open on one villager.
+#[derive(Debug)]
+pub struct Merchant {
+    pub container_id: i32,
+    pub villager: Uuid,
+    payment: [ItemStack; 2],
+    /// The offer the player last selected.
+    selected: usize,
+}
+
+impl Merchant {
+    pub fn new(container_id: i32, villager: Uuid) -> Self {
+        Self { container_id, villager, payment: [ItemStack::Empty, ItemStack::Empty], selected: 0 }
+    }
+
+    /// What the result slot shows: the selected offer's goods, if the
+    /// payment slots cover it and it isn't used up.
+    pub fn result(&self, trades: &Trades) -> ItemStack {
+        match trades.available(self.villager, self.selected) {
+            Some(offer) if self.covers(&offer) => offer.sell.stack(),
+            _ => ItemStack::Empty,
+        }
+    }
+
+    fn covers(&self, offer: &Offer) -> bool {
+        offer.buy.paid_by(&self.payment[0]) && offer.buy_b.is_none_or(|b| b.paid_by(&self.payment[1]))
+    }
+
+    /// Every window slot, in order.
+    pub fn slots(&self, trades: &Trades, inventory: &Inventory) -> Vec<ItemStack> {
+        let mut slots = self.payment.to_vec();
+        slots.push(self.result(trades));
+        slots.extend_from_slice(&inventory.slots()[MERCHANT_SLOTS + INVENTORY_OFFSET..inventory::OFFHAND]);
+        slots
+    }
+
+    /// The player picked offer `index` from the list: return what's in
+    /// the payment slots, then fill them from the inventory with as much
+    /// of the prices as there is (up to a stack), ready to trade.
+    pub fn select(&mut self, trades: &Trades, inventory: &mut Inventory, index: usize) {
+        self.return_payment(inventory);
+        self.selected = index;
+        let Some(offer) = trades.profession(self.villager).and_then(|(_, offers)| offers.get(index).cloned()) else {
+            return;
+        };
+        for (slot, cost) in [Some(offer.buy), offer.buy_b].into_iter().enumerate() {
+            let Some(cost) = cost else { continue };
+            if self.payment[slot].is_empty() {
+                let taken = inventory.take(cost.item, inventory::max_stack(cost.item));
+                self.payment[slot] = inventory::with_count(&ItemStack::new(cost.item, 1), taken);
+            }
+        }
+    }
+
+    /// Apply a `ContainerClick` on this window (see
+    /// [`Inventory::click`]). Returns whether a trade was made, so the
+    /// offers (their uses) should be sent again.
+    pub fn click(
+        &mut self,
+        trades: &Trades,
+        recipes: &RecipeBook,
+        inventory: &mut Inventory,
+        slot: i16,
+        button: u8,
+        kind: ClickType,
+    ) -> bool {
+        let Ok(slot) = usize::try_from(slot) else { return false };
+        match (kind, slot) {
+            (_, MERCHANT_SLOTS..) => {
+                if slot + INVENTORY_OFFSET < inventory::OFFHAND {
+                    inventory.click(recipes, (slot + INVENTORY_OFFSET) as i16, button, kind);
+                }
+                false
+            }
+            (ClickType::Pickup, RESULT) => {
+                let result = self.result(trades);
+                if result.is_empty() || !inventory.carry(&result) {
+                    return false;
+                }
+                self.pay(trades);
+                true
+            }
+            (ClickType::QuickMove, RESULT) => {
+                // Trade until the payment runs out or the inventory is full.
+                let mut traded = false;
+                loop {
+                    let result = self.result(trades);
+                    if result.is_empty() || !inventory.has_room(&result) {
+                        return traded;
+                    }
+                    inventory.give(result);
+                    self.pay(trades);
+                    traded = true;
+                }
+            }
+            (ClickType::Pickup, _) => {
+                inventory.pickup_with(&mut self.payment[slot], button == 1);
+                false
+            }
+            (ClickType::QuickMove, _) => {
+                let item = std::mem::replace(&mut self.payment[slot], ItemStack::Empty);
+                self.payment[slot] = inventory.give(item);
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Take the selected offer's price out of the payment slots and
+    /// count the use.
+    fn pay(&mut self, trades: &Trades) {
+        let Some(offer) = trades.available(self.villager, self.selected) else { return };
+        for (slot, cost) in [Some(offer.buy), offer.buy_b].into_iter().enumerate() {
+            if let Some(cost) = cost {
+                let left = self.payment[slot].count() - cost.count;
+                self.payment[slot] = inventory::with_count(&self.payment[slot], left);
+            }
+        }
+        trades.record_use(self.villager, self.selected);
+    }
+
+    /// The window closed: the cursor goes back into the inventory (see
+    /// [`Inventory::close`]), then the payment. What doesn't fit is lost,
+    /// where vanilla would drop it.
+    pub fn close(mut self, recipes: &RecipeBook, inventory: &mut Inventory) {
+        inventory.close(recipes);
+        self.return_payment(inventory);
+    }
+
+    fn return_payment(&mut self, inventory: &mut Inventory) {
+        for payment in &mut self.payment {
+            let item = std::mem::replace(payment, ItemStack::Empty);
+            *payment = inventory.give(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn farmer() -> (Trades, Uuid) {
+        let trades = Trades::parse(
+            r#"{ "farmer": [
+                { "buy": { "id": "minecraft:wheat", "count": 20 }, "sell": { "id": "minecraft:emerald" }, "max_uses": 2 },
+                { "buy": { "id": "emerald" }, "buy_b": { "id": "minecraft:gravel", "count": 10 },
+                  "sell": { "id": "minecraft:flint", "count": 10 } }
+            ] }"#,
+        )
+        .unwrap();
+        (trades, Uuid::from_u128(7))
+    }
+
+    #[test]
+    fn test_trade_lists_parse() {
+        let (trades, villager) = farmer();
+        let (name, offers) = trades.profession(villager).unwrap();
+        assert_eq!((name, offers.len()), ("farmer", 2));
+        assert_eq!(offers[1].buy, Cost { item: ItemKind::Emerald, count: 1 });
+        assert_eq!(offers[1].max_uses, DEFAULT_MAX_USES);
+        assert_eq!(Trades::parse(DEFAULT_TRADES).unwrap().len(), 4);
+
+        assert!(Trades::parse(r#"{"farmer": [{"buy": {"id": "wheat"}, "sell": {"id": "no_such_item"}}]}"#).is_err());
+        assert!(Trades::parse(r#"{"farmer": [{"buy": {"id": "wheat", "count": 65}, "sell": {"id": "emerald"}}]}"#).is_err());
+        assert!(Trades::parse("{}").unwrap().profession(villager).is_none());
+    }
+
+    #[test]
+    fn test_trading_through_the_merchant_window() {
+        let (trades, villager) = farmer();
+        let recipes = RecipeBook::default();
+        let mut inv = Inventory::default();
+        inv.set_slot(9, ItemStack::new(ItemKind::Wheat, 45));
+        let mut merchant = Merchant::new(1, villager);
+
+        // Selecting the wheat trade moves the wheat into the payment slot.
+        merchant.select(&trades, &mut inv, 0);
+        assert!(inv.slot(9).is_empty());
+        assert_eq!(merchant.slots(&trades, &inv)[0].count(), 45);
+        assert_eq!(merchant.result(&trades).kind(), ItemKind::Emerald);
+
+        // Shift-clicking the result trades until the offer is used up.
+        assert!(merchant.click(&trades, &recipes, &mut inv, RESULT as i16, 0, ClickType::QuickMove));
+        assert_eq!(trades.uses(villager), vec![2, 0]);
+        assert_eq!((inv.slot(9).kind(), inv.slot(9).count()), (ItemKind::Emerald, 2));
+        assert!(merchant.result(&trades).is_empty(), "used up");
+
+        // Window slot 3 is inventory slot 9; the leftover wheat comes back
+        // on close.
+        assert!(!merchant.click(&trades, &recipes, &mut inv, 3, 0, ClickType::Pickup));
+        assert_eq!(inv.carried().kind(), ItemKind::Emerald);
+        merchant.close(&recipes, &mut inv);
+        assert_eq!(inv.slot(9).count(), 2);
+        assert_eq!((inv.slot(10).kind(), inv.slot(10).count()), (ItemKind::Wheat, 5));
+    }
+}
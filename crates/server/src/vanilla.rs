@@ -0,0 +1,566 @@
+//! Importing vanilla 1.21 Anvil worlds.
+//!
+//! [`persistence::load_into`](crate::persistence::load_into) only reads
+//! what this server writes: delta chunks, and full-section chunks stamped
+//! with the current generator fingerprint. A vanilla save has neither, and
+//! its chunks carry plenty we don't model (heightmaps, light, structures,
+//! block entities, light-only sections above and below the world).
+//!
+//! [`import_world`] reads such a save tolerantly — unknown tags are
+//! ignored, sections without block states are skipped, chunks vanilla
+//! hadn't finished generating are left out — into a [`World`] plus a
+//! [`BiomeOverlay`]. `umc-convert from-vanilla` then writes the terrain as
+//! delta chunks and the overlay as `biomes.dat`, which the server layers
+//! over its generator's biomes at startup.
+//!
+//! Not carried over: block entity contents (chest inventories, sign text —
+//! the blocks themselves import), entities, and light (recomputed).
+//! Vanilla biomes map onto the nearest of the biomes this server
+//! registers; unmapped names become plains.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+
+use ultimate_engine::world::World;
+use ultimate_engine::world::chunk::Chunk;
+use ultimate_engine::world::position::ChunkPos;
+
+use crate::persistence::{set_section_blocks, BlockStatesNbt};
+use crate::worldgen::biome::Biome;
+
+/// First data version with the 1.18 chunk layout (no `Level` wrapper,
+/// `sections[].block_states`). Older chunks need vanilla's own upgrader.
+const MIN_DATA_VERSION: i32 = 2860;
+
+/// Imported biome sidecar, next to `level.dat`.
+const BIOMES_FILE: &str = "biomes.dat";
+
+// ── Vanilla chunk NBT (tolerant subset) ─────────────────────────────────────
+
+#[derive(Deserialize)]
+struct VanillaChunkNbt {
+    #[serde(rename = "DataVersion", default)]
+    data_version: i32,
+    #[serde(rename = "xPos")]
+    x_pos: Option<i32>,
+    #[serde(rename = "zPos")]
+    z_pos: Option<i32>,
+    #[serde(rename = "Status", default)]
+    status: String,
+    #[serde(default)]
+    sections: Vec<VanillaSectionNbt>,
+    #[serde(default)]
+    block_entities: Vec<IgnoredAny>,
+}
+
+#[derive(Deserialize)]
+struct VanillaSectionNbt {
+    #[serde(rename = "Y")]
+    y: i8,
+    /// Absent on the light-only sections at either end of the world.
+    block_states: Option<BlockStatesNbt>,
+    biomes: Option<BiomesNbt>,
+}
+
+#[derive(Deserialize)]
+struct BiomesNbt {
+    palette: Vec<String>,
+    #[serde(default, deserialize_with = "crate::persistence::long_array_or_list")]
+    data: Option<Vec<i64>>,
+}
+
+#[derive(Deserialize)]
+struct VanillaLevelDatNbt {
+    #[serde(rename = "Data")]
+    data: VanillaLevelDataNbt,
+}
+
+/// Spawn moved from `SpawnX/Y/Z` into a `spawn` compound in 1.21.9; read
+/// whichever is present.
+#[derive(Deserialize)]
+struct VanillaLevelDataNbt {
+    #[serde(rename = "LevelName")]
+    level_name: Option<String>,
+    #[serde(rename = "SpawnX")]
+    spawn_x: Option<i32>,
+    #[serde(rename = "SpawnY")]
+    spawn_y: Option<i32>,
+    #[serde(rename = "SpawnZ")]
+    spawn_z: Option<i32>,
+    spawn: Option<VanillaSpawnNbt>,
+}
+
+#[derive(Deserialize)]
+struct VanillaSpawnNbt {
+    pos: Vec<i32>,
+}
+
+// ── Import ──────────────────────────────────────────────────────────────────
+
+/// Outcome of an [`import_world`] pass.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportStats {
+    /// Chunks imported.
+    pub chunks: usize,
+    /// Chunks vanilla hadn't finished generating (status other than
+    /// `minecraft:full`); they regenerate from our own generator.
+    pub incomplete: usize,
+    /// Pre-1.18 chunks, skipped: open the world in a current vanilla
+    /// client once to upgrade them.
+    pub legacy: usize,
+    /// Block entities dropped (their blocks import without contents).
+    pub block_entities: usize,
+    /// Biome palette entries with no equivalent here, imported as plains.
+    pub unmapped_biomes: usize,
+    pub level_name: Option<String>,
+    pub spawn: Option<[i32; 3]>,
+}
+
+/// Read every complete chunk of the vanilla world at `dir` into `world`
+/// (without marking it dirty) and collect its biomes.
+pub fn import_world(world: &World, dir: &Path) -> Result<(BiomeOverlay, ImportStats)> {
+    let mut stats = ImportStats::default();
+    let mut biomes = BiomeOverlay::default();
+
+    if let Some(level) = read_vanilla_level_dat(dir)? {
+        stats.level_name = level.level_name;
+        stats.spawn = match (level.spawn, level.spawn_x, level.spawn_y, level.spawn_z) {
+            (Some(s), _, _, _) if s.pos.len() == 3 => Some([s.pos[0], s.pos[1], s.pos[2]]),
+            (_, Some(x), Some(y), Some(z)) => Some([x, y, z]),
+            _ => None,
+        };
+    }
+
+    let region_dir = dir.join("region");
+    let entries = fs::read_dir(&region_dir)
+        .with_context(|| format!("reading {}", region_dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        // Vanilla leaves zero-length region files behind.
+        if path.extension().and_then(|e| e.to_str()) != Some("mca") || fs::metadata(&path)?.len() == 0 {
+            continue;
+        }
+        let file = fs::File::open(&path)
+            .with_context(|| format!("opening region file {}", path.display()))?;
+        let mut region = match fastanvil::Region::from_stream(file) {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::warn!("Skipping unreadable region {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        for x in 0..32usize {
+            for z in 0..32usize {
+                let nbt_bytes = match region.read_chunk(x, z) {
+                    Ok(Some(b)) => b,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        tracing::warn!("Skipping chunk ({}, {}) in {}: {}", x, z, path.display(), e);
+                        continue;
+                    }
+                };
+                let nbt: VanillaChunkNbt = match fastnbt::from_bytes(&nbt_bytes) {
+                    Ok(n) => n,
+                    Err(e) => {
+                        tracing::warn!("Skipping chunk ({}, {}) in {}: {}", x, z, path.display(), e);
+                        continue;
+                    }
+                };
+                import_chunk(world, &mut biomes, &mut stats, nbt);
+            }
+        }
+    }
+    Ok((biomes, stats))
+}
+
+fn import_chunk(world: &World, biomes: &mut BiomeOverlay, stats: &mut ImportStats, nbt: VanillaChunkNbt) {
+    let (Some(cx), Some(cz)) = (nbt.x_pos, nbt.z_pos) else {
+        stats.legacy += 1;
+        return;
+    };
+    if nbt.data_version < MIN_DATA_VERSION {
+        stats.legacy += 1;
+        return;
+    }
+    if nbt.status != "minecraft:full" && nbt.status != "full" {
+        stats.incomplete += 1;
+        return;
+    }
+
+    let mut chunk = Chunk::new();
+    let mut sections: Vec<(i32, [Biome; 64])> = Vec::new();
+    for section in &nbt.sections {
+        let Some(states) = &section.block_states else { continue };
+        set_section_blocks(&mut chunk, section.y as i32, states);
+        if let Some(b) = &section.biomes {
+            sections.push((section.y as i32, section_biomes(b, &mut stats.unmapped_biomes)));
+        }
+    }
+    stats.block_entities += nbt.block_entities.len();
+    stats.chunks += 1;
+
+    let pos = ChunkPos::new(cx, cz);
+    if let Some(cells) = ChunkBiomes::from_sections(sections) {
+        biomes.chunks.insert(pos, cells);
+    }
+    world.insert_chunk(pos, chunk);
+}
+
+/// Decode one section's 4×4×4 biome cells.
+fn section_biomes(nbt: &BiomesNbt, unmapped: &mut usize) -> [Biome; 64] {
+    let palette: Vec<Biome> = nbt
+        .palette
+        .iter()
+        .map(|name| {
+            map_biome(name).unwrap_or_else(|| {
+                *unmapped += 1;
+                Biome::Plains
+            })
+        })
+        .collect();
+    let Some(&first) = palette.first() else {
+        return [Biome::Plains; 64];
+    };
+    let Some(data) = nbt.data.as_ref().filter(|_| palette.len() > 1) else {
+        return [first; 64];
+    };
+    // Biome containers have no 4-bit minimum: ceil(log2(len)) bits, and
+    // like block states no entry spans two longs.
+    let bits = (usize::BITS - (palette.len() - 1).leading_zeros()) as usize;
+    let per_long = 64 / bits;
+    let mask = (1u64 << bits) - 1;
+    std::array::from_fn(|i| {
+        let idx = data.get(i / per_long).map_or(0, |&l| (l as u64 >> ((i % per_long) * bits)) & mask);
+        palette.get(idx as usize).copied().unwrap_or(first)
+    })
+}
+
+/// Nearest biome this server registers for a vanilla biome name.
+pub fn map_biome(name: &str) -> Option<Biome> {
+    if let Some(b) = Biome::from_name(name) {
+        return Some(b);
+    }
+    let name = name.strip_prefix("minecraft:")?;
+    Some(match name {
+        n if n.contains("ocean") => Biome::Ocean,
+        n if n.contains("river") => Biome::River,
+        "snowy_beach" | "stony_shore" => Biome::Beach,
+        n if n.starts_with("snowy") || n.contains("frozen") || n == "ice_spikes" || n == "grove" => {
+            Biome::SnowyPlains
+        }
+        n if n.contains("peaks") || n.starts_with("windswept") && n != "windswept_forest" => Biome::StonyPeaks,
+        n if n == "desert" || n.contains("badlands") => Biome::Desert,
+        n if n.contains("forest") || n.contains("taiga") || n.contains("jungle") || n.contains("swamp")
+            || n == "cherry_grove" || n.contains("bamboo") => Biome::Forest,
+        n if n.contains("plains") || n.contains("savanna") || n == "meadow" || n == "mushroom_fields" => {
+            Biome::Plains
+        }
+        _ => return None,
+    })
+}
+
+fn read_vanilla_level_dat(dir: &Path) -> Result<Option<VanillaLevelDataNbt>> {
+    use std::io::Read;
+
+    let path = dir.join("level.dat");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let file = fs::File::open(&path).with_context(|| format!("opening {}", path.display()))?;
+    let mut raw = Vec::new();
+    flate2::read::GzDecoder::new(file)
+        .read_to_end(&mut raw)
+        .with_context(|| format!("decompressing {}", path.display()))?;
+    let nbt: VanillaLevelDatNbt =
+        fastnbt::from_bytes(&raw).with_context(|| format!("parsing {}", path.display()))?;
+    Ok(Some(nbt.data))
+}
+
+// ── Biome overlay ───────────────────────────────────────────────────────────
+
+/// Biomes of one imported chunk: 64 cells per section, sections
+/// contiguous from `min_section`.
+#[derive(Debug, Clone, PartialEq)]
+struct ChunkBiomes {
+    min_section: i32,
+    cells: Vec<Biome>,
+}
+
+impl ChunkBiomes {
+    fn from_sections(mut sections: Vec<(i32, [Biome; 64])>) -> Option<Self> {
+        sections.sort_unstable_by_key(|&(y, _)| y);
+        let min_section = sections.first()?.0;
+        let max_section = sections.last()?.0;
+        let mut cells = vec![Biome::Plains; (max_section - min_section + 1) as usize * 64];
+        for (y, section) in sections {
+            let at = (y - min_section) as usize * 64;
+            cells[at..at + 64].copy_from_slice(&section);
+        }
+        Some(Self { min_section, cells })
+    }
+}
+
+/// Per-cell biomes of imported chunks, consulted before the generator's
+/// (see `DeltaOverlayGen::with_biomes`). Written once by the importer;
+/// the server never changes biomes, so it isn't part of regular saves.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BiomeOverlay {
+    chunks: HashMap<ChunkPos, ChunkBiomes>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BiomesDatNbt {
+    #[serde(rename = "Palette")]
+    palette: Vec<String>,
+    #[serde(rename = "Chunks")]
+    chunks: Vec<ChunkBiomesNbt>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChunkBiomesNbt {
+    #[serde(rename = "xPos")]
+    x_pos: i32,
+    #[serde(rename = "zPos")]
+    z_pos: i32,
+    #[serde(rename = "MinSection")]
+    min_section: i32,
+    /// Indices into `Palette`.
+    #[serde(rename = "Cells")]
+    cells: fastnbt::ByteArray,
+}
+
+impl BiomeOverlay {
+    /// Number of chunks with imported biomes.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Wire ID of the imported biome at block `(x, y, z)`, or `None`
+    /// outside imported chunks and sections.
+    pub fn biome_at_cell(&self, x: i64, y: i64, z: i64) -> Option<u32> {
+        let chunk = self.chunks.get(&ChunkPos::new(x.div_euclid(16) as i32, z.div_euclid(16) as i32))?;
+        let section = y.div_euclid(16) - chunk.min_section as i64;
+        if section < 0 {
+            return None;
+        }
+        let cell = ((y.rem_euclid(16) / 4) * 16 + (z.rem_euclid(16) / 4) * 4 + x.rem_euclid(16) / 4) as usize;
+        chunk.cells.get(section as usize * 64 + cell).map(|b| b.registry_id())
+    }
+
+    /// Write `<dir>/biomes.dat` (gzipped NBT).
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        use std::io::Write;
+
+        let palette: Vec<Biome> = Biome::ALL.to_vec();
+        let mut chunks: Vec<ChunkBiomesNbt> = self
+            .chunks
+            .iter()
+            .map(|(pos, c)| ChunkBiomesNbt {
+                x_pos: pos.x,
+                z_pos: pos.z,
+                min_section: c.min_section,
+                cells: fastnbt::ByteArray::new(
+                    c.cells.iter().map(|b| palette.iter().position(|p| p == b).unwrap() as i8).collect(),
+                ),
+            })
+            .collect();
+        chunks.sort_unstable_by_key(|c| (c.x_pos, c.z_pos));
+        let nbt = BiomesDatNbt { palette: palette.iter().map(|b| b.name().to_string()).collect(), chunks };
+
+        let raw = fastnbt::to_bytes(&nbt).context("serializing biomes.dat")?;
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&raw)?;
+        fs::create_dir_all(dir)?;
+        let path = dir.join(BIOMES_FILE);
+        fs::write(&path, gz.finish()?).with_context(|| format!("writing {}", path.display()))
+    }
+
+    /// Read `<dir>/biomes.dat`. `Ok(None)` when the world has none (it
+    /// wasn't imported).
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        use std::io::Read;
+
+        let path = dir.join(BIOMES_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = fs::File::open(&path).with_context(|| format!("opening {}", path.display()))?;
+        let mut raw = Vec::new();
+        flate2::read::GzDecoder::new(file)
+            .read_to_end(&mut raw)
+            .with_context(|| format!("decompressing {}", path.display()))?;
+        let nbt: BiomesDatNbt =
+            fastnbt::from_bytes(&raw).with_context(|| format!("parsing {}", path.display()))?;
+
+        let palette: Vec<Biome> = nbt
+            .palette
+            .iter()
+            .map(|name| Biome::from_name(name).unwrap_or(Biome::Plains))
+            .collect();
+        let chunks = nbt
+            .chunks
+            .into_iter()
+            .map(|c| {
+                let cells = c
+                    .cells
+                    .iter()
+                    .map(|&i| palette.get(i as usize).copied().unwrap_or(Biome::Plains))
+                    .collect();
+                (ChunkPos::new(c.x_pos, c.z_pos), ChunkBiomes { min_section: c.min_section, cells })
+            })
+            .collect();
+        Ok(Some(Self { chunks }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultimate_engine::world::position::BlockPos;
+
+    #[derive(Serialize)]
+    struct ChunkOut {
+        #[serde(rename = "DataVersion")]
+        data_version: i32,
+        #[serde(rename = "xPos")]
+        x_pos: i32,
+        #[serde(rename = "zPos")]
+        z_pos: i32,
+        #[serde(rename = "Status")]
+        status: &'static str,
+        sections: Vec<fastnbt::Value>,
+        block_entities: Vec<fastnbt::Value>,
+        #[serde(rename = "Heightmaps")]
+        heightmaps: fastnbt::Value,
+    }
+
+    fn compound(entries: Vec<(&str, fastnbt::Value)>) -> fastnbt::Value {
+        fastnbt::Value::Compound(entries.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+
+    fn block(name: &str, props: &[(&str, &str)]) -> fastnbt::Value {
+        let mut entries = vec![("Name", fastnbt::Value::String(name.to_string()))];
+        if !props.is_empty() {
+            let props = props.iter().map(|&(k, v)| (k, fastnbt::Value::String(v.to_string()))).collect();
+            entries.push(("Properties", compound(props)));
+        }
+        compound(entries)
+    }
+
+    /// A vanilla-shaped chunk: a light-only section below the world, a
+    /// stone floor with a chest, two biomes split at x = 8.
+    fn vanilla_chunk(x_pos: i32, z_pos: i32, status: &'static str) -> Vec<u8> {
+        let light_only = compound(vec![
+            ("Y", fastnbt::Value::Byte(-5)),
+            ("SkyLight", fastnbt::Value::ByteArray(fastnbt::ByteArray::new(vec![0; 2048]))),
+        ]);
+        // Palette [air, stone, chest]: y = 0 stone, chest at (3, 1, 3).
+        let mut indices = [0u16; 4096];
+        indices[..256].fill(1);
+        indices[256 + 3 * 16 + 3] = 2;
+        let data = crate::persistence::pack_indices(&indices, 3).unwrap();
+        // Biome palette [plains, desert] packed 1 bit per cell: x >= 8 → desert.
+        let mut biome_bits = 0i64;
+        for i in 0..64 {
+            if i % 4 >= 2 {
+                biome_bits |= 1 << i;
+            }
+        }
+        let section = compound(vec![
+            ("Y", fastnbt::Value::Byte(0)),
+            (
+                "block_states",
+                compound(vec![
+                    (
+                        "palette",
+                        fastnbt::Value::List(vec![
+                            block("minecraft:air", &[]),
+                            block("minecraft:stone", &[]),
+                            block(
+                                "minecraft:chest",
+                                &[("facing", "north"), ("type", "single"), ("waterlogged", "false")],
+                            ),
+                        ]),
+                    ),
+                    ("data", fastnbt::Value::LongArray(fastnbt::LongArray::new(data))),
+                ]),
+            ),
+            (
+                "biomes",
+                compound(vec![
+                    (
+                        "palette",
+                        fastnbt::Value::List(vec![
+                            fastnbt::Value::String("minecraft:sunflower_plains".into()),
+                            fastnbt::Value::String("minecraft:badlands".into()),
+                        ]),
+                    ),
+                    ("data", fastnbt::Value::LongArray(fastnbt::LongArray::new(vec![biome_bits]))),
+                ]),
+            ),
+        ]);
+        let chest = compound(vec![("id", fastnbt::Value::String("minecraft:chest".into()))]);
+        fastnbt::to_bytes(&ChunkOut {
+            data_version: 4189,
+            x_pos,
+            z_pos,
+            status,
+            sections: vec![light_only, section],
+            block_entities: vec![chest],
+            heightmaps: compound(vec![]),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_import_vanilla_chunks_and_biomes() {
+        let dir = std::env::temp_dir().join("ultimate_mc_test_vanilla_import");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("region")).unwrap();
+        let mut region = fastanvil::Region::new(std::io::Cursor::new(Vec::new())).unwrap();
+        region.write_chunk(0, 0, &vanilla_chunk(0, 0, "minecraft:full")).unwrap();
+        region.write_chunk(1, 0, &vanilla_chunk(1, 0, "minecraft:features")).unwrap();
+        let bytes = region.into_inner().unwrap().into_inner();
+        fs::write(dir.join("region/r.0.0.mca"), bytes).unwrap();
+        // An empty region file, as vanilla sometimes leaves.
+        fs::write(dir.join("region/r.1.0.mca"), []).unwrap();
+
+        let world = World::new();
+        let (biomes, stats) = import_world(&world, &dir).unwrap();
+        assert_eq!((stats.chunks, stats.incomplete, stats.block_entities), (1, 1, 1));
+        assert_eq!(stats.unmapped_biomes, 0);
+        assert_eq!(world.chunk_count(), 1);
+        assert_eq!(world.dirty_count(), 0, "import must not dirty");
+        assert_eq!(world.get_block(BlockPos::new(5, 0, 5)), crate::block::STONE);
+        assert_ne!(world.get_block(BlockPos::new(3, 1, 3)), crate::block::AIR, "chest block imports");
+
+        assert_eq!(biomes.biome_at_cell(1, 2, 1), Some(Biome::Plains.registry_id()));
+        assert_eq!(biomes.biome_at_cell(12, 2, 1), Some(Biome::Desert.registry_id()));
+        assert_eq!(biomes.biome_at_cell(1, 40, 1), None, "no section there");
+        assert_eq!(biomes.biome_at_cell(20, 2, 1), None, "incomplete chunk left to the generator");
+
+        biomes.save(&dir).unwrap();
+        assert_eq!(BiomeOverlay::load(&dir).unwrap(), Some(biomes));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_map_biome_covers_vanilla_names() {
+        assert_eq!(map_biome("minecraft:deep_frozen_ocean"), Some(Biome::Ocean));
+        assert_eq!(map_biome("minecraft:frozen_river"), Some(Biome::River));
+        assert_eq!(map_biome("minecraft:ice_spikes"), Some(Biome::SnowyPlains));
+        assert_eq!(map_biome("minecraft:jagged_peaks"), Some(Biome::StonyPeaks));
+        assert_eq!(map_biome("minecraft:windswept_forest"), Some(Biome::Forest));
+        assert_eq!(map_biome("minecraft:eroded_badlands"), Some(Biome::Desert));
+        assert_eq!(map_biome("minecraft:the_void"), None);
+    }
+}
@@ -0,0 +1,195 @@
+//! Watchdog for stuck cascades and runaway rules.
+//!
+//! A physics worker runs each batch until its graph settles. A rule that
+//! never reaches quiescence (two rules undoing each other, a spread with
+//! no bound) would keep the worker busy forever and starve every region
+//! it owns. So each batch runs under a [`Watch`]: past half of either
+//! [`CascadeBudget`] it snapshots the per-rule timings, and past the full
+//! budget it reports an [`Overrun`]. The worker then drops the batch's
+//! unexecuted events (`CausalGraph::abandon_pending`), logs the root
+//! event with the rules that ran since the snapshot, and counts the abort
+//! on the dashboard. Writes that already executed were published step by
+//! step and stay; only what never ran is rolled back.
+//!
+//! The watch is checked between steps, so a single rule evaluation that
+//! never returns is out of its reach.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use ultimate_engine::causal::event::EventPayload;
+use ultimate_engine::rules::{RuleProfile, RuleSample};
+
+use crate::config::WatchdogConfig;
+
+/// Rules listed in an [`Overrun`], costliest first.
+const TOP_RULES: usize = 5;
+
+/// How far one batch may run before it is aborted. Zero turns a limit off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CascadeBudget {
+    pub max_events: u64,
+    pub max_time: Duration,
+}
+
+impl From<&WatchdogConfig> for CascadeBudget {
+    fn from(cfg: &WatchdogConfig) -> Self {
+        Self { max_events: cfg.max_events, max_time: Duration::from_millis(cfg.max_millis) }
+    }
+}
+
+impl Default for CascadeBudget {
+    fn default() -> Self {
+        Self::from(&WatchdogConfig::default())
+    }
+}
+
+impl CascadeBudget {
+    /// The larger share of either limit used so far.
+    fn used(&self, events: u64, elapsed: Duration) -> f64 {
+        let events = if self.max_events == 0 { 0.0 } else { events as f64 / self.max_events as f64 };
+        let time = if self.max_time.is_zero() { 0.0 } else { elapsed.as_secs_f64() / self.max_time.as_secs_f64() };
+        events.max(time)
+    }
+}
+
+/// One rule's sampled cost during an overrun.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleTiming {
+    pub name: &'static str,
+    /// Timed evaluations.
+    pub samples: u64,
+    /// Consequents those evaluations produced.
+    pub events: u64,
+    pub time: Duration,
+}
+
+/// A batch that ran out of budget.
+#[derive(Debug, Clone)]
+pub struct Overrun {
+    pub events: u64,
+    pub elapsed: Duration,
+    /// Sampled rule costs from half the budget on, costliest first.
+    /// Empty when rule sampling is off.
+    pub rules: Vec<RuleTiming>,
+}
+
+impl fmt::Display for Overrun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} events in {:.1?}", self.events, self.elapsed)?;
+        if self.rules.is_empty() {
+            return write!(f, "; no rule timings (set dashboard.rule_sample_every)");
+        }
+        write!(f, "; sampled rule time since half budget:")?;
+        for (i, rule) in self.rules.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(f, "{sep}{} {:.1?} over {} evaluations ({} events)", rule.name, rule.time, rule.samples, rule.events)?;
+        }
+        Ok(())
+    }
+}
+
+/// Budget tracking for one batch.
+pub struct Watch {
+    budget: CascadeBudget,
+    started: Instant,
+    /// Rule counters at half budget; `None` until then.
+    baseline: Option<Vec<RuleSample>>,
+}
+
+impl Watch {
+    pub fn new(budget: CascadeBudget) -> Self {
+        Self { budget, started: Instant::now(), baseline: None }
+    }
+
+    /// Check after a step, `events` into the batch.
+    pub fn check(&mut self, events: u64, profile: Option<&RuleProfile>) -> Option<Overrun> {
+        self.check_at(events, self.started.elapsed(), profile)
+    }
+
+    fn check_at(&mut self, events: u64, elapsed: Duration, profile: Option<&RuleProfile>) -> Option<Overrun> {
+        let used = self.budget.used(events, elapsed);
+        if used >= 0.5 && self.baseline.is_none() {
+            self.baseline = Some(sampled(profile));
+        }
+        if used < 1.0 {
+            return None;
+        }
+        let baseline = self.baseline.take().unwrap_or_default();
+        Some(Overrun { events, elapsed, rules: rule_delta(&baseline, &sampled(profile)) })
+    }
+}
+
+/// The profile's counters, or none if it isn't sampling.
+fn sampled(profile: Option<&RuleProfile>) -> Vec<RuleSample> {
+    profile.filter(|p| p.sample_every() > 0).map(RuleProfile::snapshot).unwrap_or_default()
+}
+
+/// What each rule added between `before` and `after`, costliest first.
+fn rule_delta(before: &[RuleSample], after: &[RuleSample]) -> Vec<RuleTiming> {
+    let mut timings: Vec<RuleTiming> = after
+        .iter()
+        .map(|now| {
+            let then = before.iter().find(|b| b.name == now.name);
+            let since = |f: fn(&RuleSample) -> u64| f(now) - then.map_or(0, f);
+            RuleTiming {
+                name: now.name,
+                samples: since(|s| s.samples),
+                events: since(|s| s.events),
+                time: Duration::from_nanos(since(|s| s.ns_sum)),
+            }
+        })
+        .filter(|t| t.samples > 0)
+        .collect();
+    timings.sort_by(|a, b| b.time.cmp(&a.time));
+    timings.truncate(TOP_RULES);
+    timings
+}
+
+/// Log an aborted batch started by `root` on `worker`, which dropped
+/// `dropped` unexecuted events.
+pub fn report(worker: usize, root: Option<&EventPayload>, overrun: &Overrun, dropped: usize) {
+    tracing::error!(
+        "physics-{worker}: cascade aborted by the watchdog after {overrun}; \
+         dropped {dropped} unexecuted events; root event: {:?}",
+        root,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(name: &'static str, samples: u64, ns_sum: u64) -> RuleSample {
+        RuleSample { name, samples, events: samples * 2, ns_sum, hist: [0; 5] }
+    }
+
+    #[test]
+    fn test_budget_trips_on_either_limit() {
+        let budget = CascadeBudget { max_events: 1_000, max_time: Duration::from_secs(10) };
+        let mut watch = Watch::new(budget);
+        assert!(watch.check_at(400, Duration::from_secs(1), None).is_none());
+        assert!(watch.baseline.is_none());
+        assert!(watch.check_at(600, Duration::from_secs(1), None).is_none());
+        assert!(watch.baseline.is_some(), "half the event budget takes the baseline");
+        let overrun = watch.check_at(1_000, Duration::from_secs(1), None).unwrap();
+        assert_eq!(overrun.events, 1_000);
+
+        let mut watch = Watch::new(budget);
+        assert!(watch.check_at(10, Duration::from_secs(10), None).is_some(), "time alone trips it");
+
+        let off = CascadeBudget { max_events: 0, max_time: Duration::ZERO };
+        assert!(Watch::new(off).check_at(u64::MAX, Duration::from_secs(3600), None).is_none());
+    }
+
+    #[test]
+    fn test_rule_delta_since_baseline() {
+        let before = [sample("fluid", 10, 1_000), sample("gravity", 5, 500)];
+        let after = [sample("fluid", 10, 1_000), sample("gravity", 9, 4_500), sample("light", 2, 9_000)];
+        let delta = rule_delta(&before, &after);
+        let names: Vec<_> = delta.iter().map(|t| t.name).collect();
+        assert_eq!(names, ["light", "gravity"], "idle rules left out, costliest first");
+        assert_eq!(delta[1], RuleTiming { name: "gravity", samples: 4, events: 8, time: Duration::from_nanos(4_000) });
+        assert!(Overrun { events: 1, elapsed: Duration::ZERO, rules: delta }.to_string().contains("light"));
+    }
+}
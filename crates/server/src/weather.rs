@@ -0,0 +1,200 @@
+//! Weather: vanilla's clear/rain cycle, and rain filling cauldrons.
+//!
+//! [`Weather`] counts down the current spell like vanilla's `rainTime`:
+//! rain lasts [`RAIN_TICKS`], clear skies [`CLEAR_TICKS`], and the world
+//! starts clear. The cycle only runs while `doWeatherCycle` is on. The
+//! state lives in memory: a restart starts a fresh clear spell rather than
+//! resuming the one in `level.dat`. There is no thunder, and no biomes to
+//! keep deserts dry or turn rain into snow.
+//!
+//! [`WeatherLayer`] advances the cycle once a second, tells players when
+//! it turns ([`crate::player_registry::PlayerEvent::WeatherChanged`]), and
+//! while it rains sends a drop into one random column of each loaded chunk
+//! with vanilla's [`FILL_CHANCE`]. A cauldron on top of its column gains a
+//! level ([`crate::item_use::rain_fill`]).
+
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use azalea_protocol::packets::game::c_game_event::{ClientboundGameEvent, EventType};
+use ultimate_engine::causal::event::{Event, EventPayload};
+use ultimate_engine::world::World;
+use ultimate_engine::world::block::BlockId;
+use ultimate_engine::world::position::BlockPos;
+
+use crate::gamerules::{self, GameRule};
+use crate::player_registry::PlayerRegistry;
+use crate::simulation::SimulationLayer;
+use crate::worldgen::decorator::SplitMix64;
+
+/// How long rain lasts, in ticks (vanilla: half to a whole day).
+pub const RAIN_TICKS: RangeInclusive<i64> = 12_000..=24_000;
+
+/// How long clear skies last, in ticks (vanilla: half a day to 7.5 days).
+pub const CLEAR_TICKS: RangeInclusive<i64> = 12_000..=180_000;
+
+/// How often [`WeatherLayer`] runs.
+pub const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Chance that a drop landing on a cauldron fills it (vanilla's 0.05).
+pub const FILL_CHANCE: u32 = 20;
+
+/// Ticks per [`INTERVAL`].
+const TICKS_PER_INTERVAL: i64 = 20;
+
+const MIN_Y: i64 = -64;
+const MAX_Y: i64 = 319;
+
+struct State {
+    raining: bool,
+    /// Ticks left in the current spell.
+    left: i64,
+    rng: SplitMix64,
+}
+
+/// The current weather and how long it lasts.
+pub struct Weather {
+    state: Mutex<State>,
+}
+
+impl Weather {
+    /// Clear skies, for a random clear spell.
+    pub fn new(seed: u64) -> Self {
+        let mut rng = SplitMix64::new(seed);
+        let left = rng.range_i64(*CLEAR_TICKS.start(), *CLEAR_TICKS.end());
+        Self { state: Mutex::new(State { raining: false, left, rng }) }
+    }
+
+    pub fn is_raining(&self) -> bool {
+        self.state.lock().expect("weather poisoned").raining
+    }
+
+    /// Run the cycle for `ticks`. Returns whether it rains now if the
+    /// weather turned.
+    pub fn advance(&self, ticks: i64) -> Option<bool> {
+        let mut state = self.state.lock().expect("weather poisoned");
+        state.left -= ticks;
+        if state.left > 0 {
+            return None;
+        }
+        state.raining = !state.raining;
+        let spell = if state.raining { RAIN_TICKS } else { CLEAR_TICKS };
+        state.left = state.rng.range_i64(*spell.start(), *spell.end());
+        Some(state.raining)
+    }
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+        Self::new(seed)
+    }
+}
+
+/// The game events that show `raining` to a client, on join or when the
+/// weather turns.
+pub fn packets(raining: bool) -> [ClientboundGameEvent; 2] {
+    let (event, level) = match raining {
+        true => (EventType::StartRaining, 1.0),
+        false => (EventType::StopRaining, 0.0),
+    };
+    [
+        ClientboundGameEvent { event, param: 0.0 },
+        ClientboundGameEvent { event: EventType::RainLevelChange, param: level },
+    ]
+}
+
+/// The simulation layer that runs the weather (see the module docs).
+pub struct WeatherLayer {
+    registry: Arc<PlayerRegistry>,
+    rng: Mutex<SplitMix64>,
+}
+
+impl WeatherLayer {
+    pub fn new(registry: Arc<PlayerRegistry>, seed: u64) -> Self {
+        Self { registry, rng: Mutex::new(SplitMix64::new(seed)) }
+    }
+}
+
+impl SimulationLayer for WeatherLayer {
+    fn name(&self) -> &'static str {
+        "weather"
+    }
+
+    fn interval(&self) -> Duration {
+        INTERVAL
+    }
+
+    fn generate_events(&self, world: &World) -> Vec<Event> {
+        let weather = &self.registry.weather;
+        if gamerules::enabled(GameRule::DoWeatherCycle)
+            && let Some(raining) = weather.advance(TICKS_PER_INTERVAL)
+        {
+            tracing::info!("Weather: {}", if raining { "rain" } else { "clear" });
+            self.registry.announce_weather(raining);
+        }
+        if !weather.is_raining() {
+            return Vec::new();
+        }
+        let mut rng = self.rng.lock().expect("weather rng poisoned");
+        let chunks: Vec<_> = world.iter_chunks().map(|entry| *entry.key()).collect();
+        let mut events = Vec::new();
+        for chunk in chunks {
+            if rng.range_u32(FILL_CHANCE) != 0 {
+                continue;
+            }
+            let x = chunk.x as i64 * 16 + rng.range_u32(16) as i64;
+            let z = chunk.z as i64 * 16 + rng.range_u32(16) as i64;
+            let Some(pos) = top_block(world, x, z) else { continue };
+            let old = world.get_block(pos);
+            if let Some(new) = crate::item_use::rain_fill(old) {
+                events.push(Event { payload: EventPayload::BlockSet { pos, old, new } });
+            }
+        }
+        events
+    }
+}
+
+/// The highest non-air block in the column at `x`, `z`: where rain lands.
+fn top_block(world: &World, x: i64, z: i64) -> Option<BlockPos> {
+    (MIN_Y..=MAX_Y)
+        .rev()
+        .map(|y| BlockPos::new(x, y, z))
+        .find(|&pos| world.get_block(pos) != BlockId::AIR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cycle_alternates_within_vanilla_spells() {
+        let weather = Weather::new(7);
+        assert!(!weather.is_raining(), "the world starts clear");
+        let mut raining = false;
+        for _ in 0..6 {
+            let mut ticks = 0;
+            let turned = loop {
+                ticks += TICKS_PER_INTERVAL;
+                if let Some(now) = weather.advance(TICKS_PER_INTERVAL) {
+                    break now;
+                }
+            };
+            let spell = if raining { RAIN_TICKS } else { CLEAR_TICKS };
+            assert!(ticks >= *spell.start() && ticks <= *spell.end() + TICKS_PER_INTERVAL, "{ticks} ticks");
+            assert_eq!(turned, !raining);
+            assert_eq!(weather.is_raining(), turned);
+            raining = turned;
+        }
+    }
+
+    #[test]
+    fn test_rain_lands_on_the_top_block() {
+        let world = World::new();
+        world.set_block(BlockPos::new(3, 10, 5), crate::block::STONE);
+        world.set_block(BlockPos::new(3, 64, 5), crate::block::STONE);
+        assert_eq!(top_block(&world, 3, 5), Some(BlockPos::new(3, 64, 5)));
+        assert_eq!(top_block(&world, 4, 5), None);
+    }
+}
@@ -0,0 +1,233 @@
+//! The world border: a square around a center point that players can't
+//! build beyond and are hurt (or pushed back) for leaving.
+//!
+//! The border starts where `border` in the config puts it; `/worldborder`
+//! moves the center or grows and shrinks it, at once or over time (the
+//! side length is interpolated, as the client does for the moving wall).
+//! Changes are broadcast as ready-to-send packets; connections relay them
+//! after the snapshot from [`WorldBorder::join`], check edits against
+//! [`WorldBorder::contains_block`] and once a second apply
+//! [`WorldBorder::damage`] or [`WorldBorder::clamp`] to their player.
+
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use azalea_protocol::packets::game::{
+    ClientboundGamePacket, ClientboundInitializeBorder, ClientboundSetBorderCenter, ClientboundSetBorderLerpSize,
+    ClientboundSetBorderSize,
+};
+use azalea_protocol::packets::Packet;
+use tokio::sync::broadcast;
+use ultimate_engine::world::position::BlockPos;
+
+use crate::config::BorderConfig;
+
+/// Largest side length the client accepts (vanilla's default border).
+pub const MAX_DIAMETER: f64 = 59_999_968.0;
+
+struct State {
+    center: [f64; 2],
+    /// Side length moving from `from` to `to` over `duration`, starting
+    /// at `started`.
+    from: f64,
+    to: f64,
+    started: Instant,
+    duration: Duration,
+}
+
+impl State {
+    fn diameter(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed >= self.duration {
+            return self.to;
+        }
+        let t = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+        self.from + (self.to - self.from) * t
+    }
+
+    /// Signed distance from `(x, z)` to the nearest edge: positive
+    /// inside, negative outside.
+    fn distance_inside(&self, x: f64, z: f64, now: Instant) -> f64 {
+        let half = self.diameter(now) / 2.0;
+        (half - (x - self.center[0]).abs()).min(half - (z - self.center[1]).abs())
+    }
+}
+
+/// Shared border state.
+pub struct WorldBorder {
+    state: RwLock<State>,
+    damage_per_block: f64,
+    safe_zone: f64,
+    warning_blocks: u32,
+    warning_time: u32,
+    tx: broadcast::Sender<ClientboundGamePacket>,
+}
+
+impl WorldBorder {
+    pub fn new(config: &BorderConfig) -> Self {
+        let diameter = config.diameter.clamp(1.0, MAX_DIAMETER);
+        let (tx, _) = broadcast::channel(64);
+        Self {
+            state: RwLock::new(State {
+                center: [config.center_x, config.center_z],
+                from: diameter,
+                to: diameter,
+                started: Instant::now(),
+                duration: Duration::ZERO,
+            }),
+            damage_per_block: config.damage_per_block,
+            safe_zone: config.safe_zone,
+            warning_blocks: config.warning_blocks,
+            warning_time: config.warning_time,
+            tx,
+        }
+    }
+
+    /// The border as a joining client needs it, and every change after.
+    pub fn join(&self) -> (ClientboundGamePacket, broadcast::Receiver<ClientboundGamePacket>) {
+        let state = self.state.read().expect("world border poisoned");
+        let now = Instant::now();
+        let packet = ClientboundInitializeBorder {
+            new_center_x: state.center[0],
+            new_center_z: state.center[1],
+            old_size: state.diameter(now),
+            new_size: state.to,
+            lerp_time: remaining(&state, now).as_millis() as u64,
+            new_absolute_max_size: MAX_DIAMETER as u32,
+            warning_blocks: self.warning_blocks,
+            warning_time: self.warning_time,
+        }
+        .into_variant();
+        (packet, self.tx.subscribe())
+    }
+
+    pub fn center(&self) -> [f64; 2] {
+        self.state.read().expect("world border poisoned").center
+    }
+
+    /// Side length now.
+    pub fn diameter(&self) -> f64 {
+        self.state.read().expect("world border poisoned").diameter(Instant::now())
+    }
+
+    /// Where a moving border is headed and how long until it gets there.
+    pub fn target(&self) -> (f64, Duration) {
+        let state = self.state.read().expect("world border poisoned");
+        (state.to, remaining(&state, Instant::now()))
+    }
+
+    pub fn set_center(&self, x: f64, z: f64) {
+        let mut state = self.state.write().expect("world border poisoned");
+        state.center = [x, z];
+        self.send(ClientboundSetBorderCenter { new_center_x: x, new_center_z: z }.into_variant());
+    }
+
+    /// Grow or shrink to `diameter`, at once or over `over`. Returns the
+    /// side length actually set (clamped to what clients accept).
+    pub fn set_diameter(&self, diameter: f64, over: Duration) -> f64 {
+        let diameter = diameter.clamp(1.0, MAX_DIAMETER);
+        let mut state = self.state.write().expect("world border poisoned");
+        let now = Instant::now();
+        let from = state.diameter(now);
+        *state = State { center: state.center, from, to: diameter, started: now, duration: over };
+        let packet = if over.is_zero() {
+            ClientboundSetBorderSize { size: diameter }.into_variant()
+        } else {
+            ClientboundSetBorderLerpSize { old_size: from, new_size: diameter, lerp_time: over.as_millis() as u64 }
+                .into_variant()
+        };
+        self.send(packet);
+        diameter
+    }
+
+    pub fn contains(&self, x: f64, z: f64) -> bool {
+        let state = self.state.read().expect("world border poisoned");
+        state.distance_inside(x, z, Instant::now()) >= 0.0
+    }
+
+    /// Whether all of the block at `pos` is inside, so it may be edited.
+    pub fn contains_block(&self, pos: BlockPos) -> bool {
+        let state = self.state.read().expect("world border poisoned");
+        let now = Instant::now();
+        let (x, z) = (pos.x as f64, pos.z as f64);
+        state.distance_inside(x, z, now) >= 0.0 && state.distance_inside(x + 1.0, z + 1.0, now) >= 0.0
+    }
+
+    /// Damage for a second spent at `(x, z)`: nothing inside the border or
+    /// within the safe zone past it, otherwise at least one point.
+    pub fn damage(&self, x: f64, z: f64) -> f32 {
+        let state = self.state.read().expect("world border poisoned");
+        let beyond = -state.distance_inside(x, z, Instant::now()) - self.safe_zone;
+        if beyond <= 0.0 || self.damage_per_block <= 0.0 {
+            return 0.0;
+        }
+        (beyond * self.damage_per_block).floor().max(1.0) as f32
+    }
+
+    /// The nearest point to `(x, z)` half a block inside the border (the
+    /// point itself if it is already inside).
+    pub fn clamp(&self, x: f64, z: f64) -> (f64, f64) {
+        let state = self.state.read().expect("world border poisoned");
+        let half = (state.diameter(Instant::now()) / 2.0 - 0.5).max(0.0);
+        let [cx, cz] = state.center;
+        (x.clamp(cx - half, cx + half), z.clamp(cz - half, cz + half))
+    }
+
+    fn send(&self, packet: ClientboundGamePacket) {
+        // No receivers (nobody online) is fine.
+        let _ = self.tx.send(packet);
+    }
+}
+
+fn remaining(state: &State, now: Instant) -> Duration {
+    (state.started + state.duration).saturating_duration_since(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn border(diameter: f64) -> WorldBorder {
+        WorldBorder::new(&BorderConfig { center_x: 100.0, center_z: -50.0, diameter, ..BorderConfig::default() })
+    }
+
+    #[test]
+    fn test_inside_outside_and_damage() {
+        let border = border(20.0);
+        assert!(border.contains(109.0, -59.0));
+        assert!(!border.contains(111.0, -50.0));
+        assert!(border.contains_block(BlockPos::new(109, 64, -60)));
+        assert!(!border.contains_block(BlockPos::new(110, 64, -50)), "straddles the edge at 110");
+        assert!(!border.contains_block(BlockPos::new(100, 64, -61)));
+
+        assert_eq!(border.damage(105.0, -50.0), 0.0);
+        assert_eq!(border.damage(114.0, -50.0), 0.0, "within the safe zone");
+        assert_eq!(border.damage(125.0, -50.0), 2.0, "10 blocks past the safe zone at 0.2");
+        assert_eq!(border.damage(116.0, -50.0), 1.0, "never less than a point");
+        assert_eq!(border.clamp(130.0, -51.0), (109.5, -51.0));
+        assert_eq!(border.clamp(101.0, -51.0), (101.0, -51.0));
+    }
+
+    #[test]
+    fn test_resizing_over_time() {
+        let border = border(100.0);
+        let (_, mut rx) = border.join();
+        assert_eq!(border.set_diameter(1e12, Duration::ZERO), MAX_DIAMETER);
+        assert_eq!(border.diameter(), MAX_DIAMETER);
+        border.set_diameter(10.0, Duration::from_secs(600));
+        let now = border.diameter();
+        assert!(now < MAX_DIAMETER && now > 10.0, "moving: {now}");
+        let (to, left) = border.target();
+        assert_eq!(to, 10.0);
+        assert!(left > Duration::from_secs(590));
+        border.set_center(0.0, 0.0);
+        assert_eq!(border.center(), [0.0, 0.0]);
+
+        let sent: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+        assert!(matches!(sent[..], [
+            ClientboundGamePacket::SetBorderSize(_),
+            ClientboundGamePacket::SetBorderLerpSize(_),
+            ClientboundGamePacket::SetBorderCenter(_),
+        ]));
+    }
+}
@@ -12,6 +12,11 @@
 //! The registry list itself isn't pruned — vanilla shipping all ~65 names
 //! means the client expects them all, so we keep all 65 registered and
 //! just pick a small subset to actually *assign* during worldgen.
+//!
+//! The biomes we assign also carry their climate and colours
+//! ([`BiomeProperties`]); those entries go out with full registry NBT
+//! instead of leaning on the client's Known Packs copy, so tints follow
+//! what the server says rather than whatever the client bundles.
 
 use serde::{Deserialize, Serialize};
 
@@ -29,7 +34,92 @@ pub enum Biome {
     River,
 }
 
+/// Climate and colour data the client renders a biome with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiomeProperties {
+    pub temperature: f32,
+    pub downfall: f32,
+    pub has_precipitation: bool,
+    pub water_color: u32,
+    pub water_fog_color: u32,
+    pub fog_color: u32,
+    /// Fixed grass / foliage tints. `None` lets the client sample its
+    /// colour maps at (temperature, downfall), as vanilla biomes do.
+    pub grass_color: Option<u32>,
+    pub foliage_color: Option<u32>,
+}
+
+impl BiomeProperties {
+    /// Vanilla's temperate defaults; biomes override climate fields.
+    const TEMPERATE: Self = Self {
+        temperature: 0.8,
+        downfall: 0.4,
+        has_precipitation: true,
+        water_color: 0x3F76E4,
+        water_fog_color: 0x050533,
+        fog_color: 0xC0D8FF,
+        grass_color: None,
+        foliage_color: None,
+    };
+
+    /// Sky colour derived from temperature, as vanilla's overworld biomes
+    /// compute it (hotter → paler, cooler → bluer).
+    pub fn sky_color(&self) -> u32 {
+        let t = (self.temperature / 3.0).clamp(-1.0, 1.0);
+        hsv_to_rgb(0.622_222_24 - t * 0.05, 0.5 + t * 0.1, 1.0)
+    }
+}
+
+/// `Mth.hsvToRgb`: truncating float→byte conversion included, so derived
+/// colours match vanilla's bit for bit.
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> u32 {
+    let sector = (h * 6.0) as i32 % 6;
+    let f = h * 6.0 - sector as f32;
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - f * s);
+    let t = v * (1.0 - (1.0 - f) * s);
+    let (r, g, b) = match sector {
+        0 => (v, t, p),
+        1 => (q, v, p),
+        2 => (p, v, t),
+        3 => (p, q, v),
+        4 => (t, p, v),
+        _ => (v, p, q),
+    };
+    let byte = |c: f32| ((c * 255.0) as i32).clamp(0, 255) as u32;
+    (byte(r) << 16) | (byte(g) << 8) | byte(b)
+}
+
 impl Biome {
+    /// Every biome worldgen can assign.
+    pub const ALL: [Biome; 8] = [
+        Self::Plains, Self::Forest, Self::Desert, Self::SnowyPlains,
+        Self::StonyPeaks, Self::Beach, Self::Ocean, Self::River,
+    ];
+
+    /// Look up a biome by its namespaced registry name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|b| b.name() == name)
+    }
+
+    /// Climate and colours, matching the vanilla data pack.
+    pub const fn properties(self) -> BiomeProperties {
+        let base = BiomeProperties::TEMPERATE;
+        match self {
+            Self::Plains | Self::Beach => base,
+            Self::Forest => BiomeProperties { temperature: 0.7, downfall: 0.8, ..base },
+            Self::Desert => BiomeProperties {
+                temperature: 2.0,
+                downfall: 0.0,
+                has_precipitation: false,
+                ..base
+            },
+            Self::SnowyPlains => BiomeProperties { temperature: 0.0, downfall: 0.5, ..base },
+            Self::StonyPeaks => BiomeProperties { temperature: 1.0, downfall: 0.3, ..base },
+            Self::Ocean | Self::River => BiomeProperties { temperature: 0.5, downfall: 0.5, ..base },
+        }
+    }
+
     /// Wire ID for the worldgen/biome registry sent during configuration.
     /// MUST stay in sync with the alphabetical list in
     /// `connection.rs::registry_data` — changing the order there breaks
@@ -88,6 +178,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sky_color_matches_vanilla() {
+        // Values from the vanilla data pack.
+        assert_eq!(Biome::Plains.properties().sky_color(), 0x78A7FF);
+        assert_eq!(Biome::Desert.properties().sky_color(), 0x6EB1FF);
+        assert_eq!(Biome::SnowyPlains.properties().sky_color(), 0x7FA1FF);
+        assert_eq!(Biome::Ocean.properties().sky_color(), 0x7BA4FF);
+    }
+
+    #[test]
+    fn from_name_covers_all() {
+        for b in Biome::ALL {
+            assert_eq!(Biome::from_name(b.name()), Some(b));
+        }
+        assert_eq!(Biome::from_name("minecraft:the_void"), None);
+    }
+
     #[test]
     fn roundtrips_through_json() {
         let json = serde_json::to_string(&Biome::SnowyPlains).unwrap();
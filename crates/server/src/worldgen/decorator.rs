@@ -19,8 +19,15 @@
 //!   "attempts_per_chunk": 20,
 //!   "vein_size": 8,
 //!   "min_y": 0,
-//!   "max_y": 100 }
+//!   "max_y": 100,
+//!   "height": "triangle" }
 //! ```
+//!
+//! `height` picks where in `[min_y, max_y]` veins start: `"uniform"`
+//! (the default) or `"triangle"`, which peaks at the middle of the band
+//! like vanilla's `trapezoid` height provider with no plateau. Iron and
+//! gold are most common at their own depth this way, rather than evenly
+//! spread across it.
 
 use std::sync::Arc;
 
@@ -180,6 +187,25 @@ impl SplitMix64 {
         let span = (hi - lo + 1) as u64;
         lo + (self.next_u64() % span) as i64
     }
+
+    /// Inclusive `[lo, hi]` integer, most likely at the midpoint and
+    /// falling off linearly to the ends (sum of two uniform draws).
+    pub fn triangle_i64(&mut self, lo: i64, hi: i64) -> i64 {
+        if hi <= lo { return lo; }
+        let half = (hi - lo) / 2;
+        self.range_i64(0, half) + self.range_i64(lo, hi - half)
+    }
+}
+
+/// How an [`OreDecorator`] spreads vein starts over its Y band.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeightDistribution {
+    /// Every height equally likely.
+    #[default]
+    Uniform,
+    /// Peaks at the middle of the band, tapering to none at the ends.
+    Triangle,
 }
 
 /// Mix four `u32`s into one `u64` seed for [`SplitMix64`].
@@ -194,7 +220,8 @@ pub fn chunk_decorator_seed(world_seed: u32, cx: i32, cz: i32, decorator_index:
 // ── OreDecorator ────────────────────────────────────────────────────────────
 
 /// Scatters ore veins through a chunk. For each of `attempts_per_chunk`
-/// attempts, picks a random `(x, y, z)` within `[min_y, max_y]` and grows
+/// attempts, picks a random `(x, y, z)` within `[min_y, max_y]` (y drawn
+/// from `height`) and grows
 /// a `vein_size`-block random-walk vein, replacing cells whose current
 /// block is in `replaces` (typically just stone).
 ///
@@ -208,6 +235,7 @@ pub struct OreDecorator {
     pub vein_size: u32,
     pub min_y: i64,
     pub max_y: i64,
+    pub height: HeightDistribution,
     /// If `Some`, only place veins in columns whose biome is in the list.
     /// `None` = place anywhere.
     pub in_biomes: Option<Vec<Biome>>,
@@ -220,7 +248,10 @@ impl Decorator for OreDecorator {
         for _ in 0..self.attempts_per_chunk {
             let mut x = rng.range_u32(16) as u8;
             let mut z = rng.range_u32(16) as u8;
-            let mut y = rng.range_i64(self.min_y, self.max_y);
+            let mut y = match self.height {
+                HeightDistribution::Uniform => rng.range_i64(self.min_y, self.max_y),
+                HeightDistribution::Triangle => rng.triangle_i64(self.min_y, self.max_y),
+            };
 
             // Biome filter at the attempt's starting column. Veins drift
             // a few blocks during the walk; checking the start is enough
@@ -440,6 +471,9 @@ pub struct OreDecoratorSchema {
     pub vein_size: u32,
     pub min_y: i64,
     pub max_y: i64,
+    /// Vein start distribution over `[min_y, max_y]`. Omitted = uniform.
+    #[serde(default)]
+    pub height: HeightDistribution,
     /// Optional biome whitelist. `None` / omitted = place in any biome.
     #[serde(default)]
     pub in_biomes: Option<Vec<Biome>>,
@@ -527,6 +561,7 @@ impl DecoratorSchema {
                     vein_size: o.vein_size,
                     min_y: o.min_y,
                     max_y: o.max_y,
+                    height: o.height,
                     in_biomes: o.in_biomes.clone(),
                 }))
             }
@@ -664,6 +699,7 @@ mod tests {
             vein_size: 8,
             min_y: 0,
             max_y: 100,
+            height: HeightDistribution::Uniform,
             in_biomes: None,
         }
     }
@@ -694,6 +730,25 @@ mod tests {
         assert!(count_block(&chunk, coal_ore(), 20..=40) > 0);
     }
 
+    #[test]
+    fn triangle_height_concentrates_veins_mid_band() {
+        let dec = OreDecorator {
+            attempts_per_chunk: 40,
+            vein_size: 1,
+            height: HeightDistribution::Triangle,
+            ..unfiltered_ore()
+        };
+        let (mut middle, mut ends) = (0, 0);
+        for cx in 0..8 {
+            let chunk = run_decorator(&dec, stone_chunk(), cx, 0, 7, 0, Biome::Plains, 70);
+            middle += count_block(&chunk, coal_ore(), 25..=75);
+            ends += count_block(&chunk, coal_ore(), 0..=24) + count_block(&chunk, coal_ore(), 76..=100);
+        }
+        // Uniform would split these roughly evenly; a triangle puts ~3/4
+        // of the starts in the middle half of the band.
+        assert!(middle > 2 * ends, "middle {} vs ends {}", middle, ends);
+    }
+
     #[test]
     fn ore_decorator_only_replaces_listed_blocks() {
         let dec = OreDecorator {
@@ -765,6 +820,7 @@ mod tests {
             vein_size: 8,
             min_y: 0,
             max_y: 100,
+            height: HeightDistribution::Triangle,
             in_biomes: None,
         });
         let json = serde_json::to_string(&schema).unwrap();
@@ -1119,6 +1175,7 @@ mod tests {
             vein_size: 1,
             min_y: 0,
             max_y: 10,
+            height: HeightDistribution::Uniform,
             in_biomes: None,
         });
         assert!(bad.build().is_err());
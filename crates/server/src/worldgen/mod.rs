@@ -65,26 +65,26 @@ pub trait WorldGen: Send + Sync + 'static {
     }
 
     /// Pre-generate every chunk inside a radius around the world origin.
-    /// Used at server startup so the spawn region is immediate.
+    /// The server runs it in the background after startup, so the spawn
+    /// region is ready before most players reach it.
     fn pregenerate_radius(&self, world: &World, chunk_radius: i32) {
         for cx in -chunk_radius..chunk_radius {
             for cz in -chunk_radius..chunk_radius {
-                if !world.has_chunk(ChunkPos::new(cx, cz)) {
-                    let chunk = self.generate_chunk(cx, cz, world);
-                    world.insert_chunk(ChunkPos::new(cx, cz), chunk);
-                }
+                self.ensure_generated(world, cx, cz);
             }
         }
     }
 
     /// Idempotent on-demand generation: if the chunk doesn't exist, generate
     /// and insert it. Called from chunk-loading code paths so the player can
-    /// walk past the pre-generated radius without falling into void.
+    /// walk past the pre-generated radius without falling into void. Safe
+    /// to race: a chunk that appears while this one generates (from disk,
+    /// or another caller, perhaps already edited) is kept.
     fn ensure_generated(&self, world: &World, cx: i32, cz: i32) {
         let pos = ChunkPos::new(cx, cz);
         if !world.has_chunk(pos) {
             let chunk = self.generate_chunk(cx, cz, world);
-            world.insert_chunk(pos, chunk);
+            world.insert_chunk_if_absent(pos, chunk);
         }
     }
 }
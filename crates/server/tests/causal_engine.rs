@@ -37,11 +37,6 @@ fn flat_world(chunk_radius: i32) -> World {
     world
 }
 
-/// Read a vertical column of block IDs from the world.
-fn column(world: &World, x: i64, z: i64, y_range: std::ops::RangeInclusive<i64>) -> Vec<BlockId> {
-    y_range.map(|y| world.get_block(BlockPos::new(x, y, z))).collect()
-}
-
 /// Execute the causal graph to quiescence with a custom frontier ordering.
 /// `order_fn` receives the frontier and returns it reordered.
 fn run_with_order<F>(
@@ -88,12 +83,13 @@ where
                 EventPayload::LightNotify { .. } => true,
                 // Reporting-only: the light rule already wrote storage.
                 EventPayload::LightBatch { .. } => true,
+                EventPayload::Custom { .. } => true,
             };
             graph.mark_executed(id);
             total += 1;
 
             if effective {
-                let consequents = rules.evaluate(world, &event.payload);
+                let consequents = rules.evaluate(&world.view(), &event.payload);
                 for new_event in consequents {
                     graph.insert(new_event, vec![id]);
                 }
@@ -200,6 +196,63 @@ fn sand_on_bedrock_stays() {
     assert_eq!(world.get_block(BlockPos::new(4, 3, 4)), block::AIR);
 }
 
+#[test]
+fn gravity_table_covers_gravel_anvils_and_powder() {
+    let id = |name| block::block_id_from_name(name).unwrap();
+    assert_eq!(block::gravity(id("gravel")), block::Gravity::Falls);
+    assert_eq!(block::gravity(id("damaged_anvil")), block::Gravity::Falls);
+    assert_eq!(block::gravity(id("lime_concrete_powder")), block::Gravity::Hardens(id("lime_concrete")));
+    assert_eq!(block::gravity(id("lime_concrete")), block::Gravity::Floats);
+    assert!(!block::has_gravity(block::STONE));
+
+    // Every anvil facing falls, not just the default state.
+    let anvil = id("anvil");
+    assert!(block::has_gravity(BlockId(anvil.0 + 1)));
+}
+
+#[test]
+fn gravel_and_anvil_fall() {
+    let world = flat_world(2);
+    let mut graph = CausalGraph::new();
+    let rules = ultimate_server::rules::standard();
+    let gravel = block::block_id_from_name("gravel").unwrap();
+    let anvil = block::block_id_from_name("anvil").unwrap();
+
+    for (x, id) in [(4, gravel), (6, anvil)] {
+        graph.insert_root(Event {
+            payload: EventPayload::BlockSet { pos: BlockPos::new(x, 9, 8), old: block::AIR, new: id },
+        });
+    }
+    Scheduler::new().run_until_quiet(&world, &mut graph, &rules, 1000);
+
+    assert_eq!(world.get_block(BlockPos::new(4, 5, 8)), gravel);
+    assert_eq!(world.get_block(BlockPos::new(6, 5, 8)), anvil);
+    assert_eq!(world.get_block(BlockPos::new(4, 9, 8)), block::AIR);
+}
+
+#[test]
+fn concrete_powder_hardens_when_it_lands_in_water() {
+    let world = flat_world(2);
+    let rules = ultimate_server::rules::standard();
+    let powder = block::block_id_from_name("cyan_concrete_powder").unwrap();
+    let concrete = block::block_id_from_name("cyan_concrete").unwrap();
+
+    // A water source walled into a one-block pit at y=5.
+    for (x, z) in [(7, 8), (9, 8), (8, 7), (8, 9)] {
+        world.set_block(BlockPos::new(x, 5, z), block::STONE);
+    }
+    world.set_block(BlockPos::new(8, 5, 8), block::WATER);
+
+    let mut graph = CausalGraph::new();
+    for pos in [BlockPos::new(8, 8, 8), BlockPos::new(4, 8, 4)] {
+        graph.insert_root(Event { payload: EventPayload::BlockSet { pos, old: block::AIR, new: powder } });
+    }
+    Scheduler::new().run_until_quiet(&world, &mut graph, &rules, 5000);
+
+    assert_eq!(world.get_block(BlockPos::new(8, 5, 8)), concrete, "landed in the water");
+    assert_eq!(world.get_block(BlockPos::new(4, 5, 4)), powder, "landed on dry ground");
+}
+
 #[test]
 fn water_spreads_horizontally_on_surface() {
     let world = flat_world(2);
@@ -265,11 +318,12 @@ fn water_falls_before_spreading() {
     scheduler.step(&world, &mut graph, &rules);
     assert_eq!(world.get_block(BlockPos::new(4, 5, 4)), block::WATER);
 
-    // Step 2: fall event places flowing water (level 1) at y=4.
+    // Step 2: fall event places falling water (level 8) at y=4.
     scheduler.step(&world, &mut graph, &rules);
-    assert!(
-        block::is_fluid(world.get_block(BlockPos::new(4, 4, 4))),
-        "fallen water should be a fluid"
+    assert_eq!(
+        world.get_block(BlockPos::new(4, 4, 4)),
+        block::FluidKind::Water.falling(),
+        "fallen water should be in the falling state"
     );
 
     // Horizontal neighbors at y=5 should still be air -- the fluid rule
@@ -278,6 +332,34 @@ fn water_falls_before_spreading() {
     assert_eq!(world.get_block(BlockPos::new(3, 5, 4)), block::AIR);
 }
 
+#[test]
+fn falling_column_lands_and_spreads_from_level_one() {
+    // A source two blocks above the floor: the column below it is falling
+    // water, and where it lands the spread restarts at level 1 — what a
+    // vanilla client expects to draw.
+    let world = flat_world(2);
+    let mut graph = CausalGraph::new();
+    let rules = ultimate_server::rules::standard();
+    let scheduler = Scheduler::new();
+
+    graph.insert_root(Event {
+        payload: EventPayload::BlockSet {
+            pos: BlockPos::new(8, 7, 8),
+            old: block::AIR,
+            new: block::WATER,
+        },
+    });
+    scheduler.run_until_quiet(&world, &mut graph, &rules, 50_000);
+
+    let water = block::FluidKind::Water;
+    assert_eq!(world.get_block(BlockPos::new(8, 7, 8)), block::WATER);
+    assert_eq!(world.get_block(BlockPos::new(8, 6, 8)), water.falling());
+    assert_eq!(world.get_block(BlockPos::new(8, 5, 8)), water.falling(), "the landing cell keeps falling");
+    assert_eq!(world.get_block(BlockPos::new(9, 5, 8)), water.flowing(1));
+    assert_eq!(world.get_block(BlockPos::new(11, 5, 8)), water.flowing(3));
+    assert_eq!(world.get_block(BlockPos::new(9, 7, 8)), block::AIR, "a source over air only falls");
+}
+
 #[test]
 fn no_events_on_inert_block() {
     let world = flat_world(1);
@@ -313,7 +395,7 @@ fn no_events_on_inert_block() {
 // The core property: if events A and B are on the same frontier (spacelike-
 // separated), processing them in order A,B or B,A must yield an identical
 // world state. We test this by running the same scenario with different
-// frontier orderings and comparing the resulting block columns.
+// frontier orderings and diffing the resulting worlds.
 
 #[test]
 fn invariance_two_independent_sand_columns() {
@@ -355,9 +437,8 @@ fn invariance_two_independent_sand_columns() {
         1000,
     );
 
-    // Both worlds must be identical at the relevant columns.
-    assert_eq!(column(&world_a, 4, 4, 0..=12), column(&world_b, 4, 4, 0..=12));
-    assert_eq!(column(&world_a, 20, 20, 0..=12), column(&world_b, 20, 20, 0..=12));
+    // Both worlds must be identical, block for block.
+    assert_eq!(world_a.diff(&world_b), []);
 
     // And both should have sand at y=5.
     assert_eq!(world_a.get_block(BlockPos::new(4, 5, 4)), block::SAND);
@@ -422,22 +503,9 @@ fn invariance_sand_and_water_independent() {
         1000,
     );
 
-    // Sand column must match.
-    assert_eq!(column(&world_a, 4, 4, 0..=12), column(&world_b, 4, 4, 0..=12));
+    // Sand column and water pit must match, as must everything else.
+    assert_eq!(world_a.diff(&world_b), []);
     assert_eq!(world_a.get_block(BlockPos::new(4, 5, 4)), block::SAND);
-
-    // Water region must match.
-    for dx in -2i64..=2 {
-        for dz in -2i64..=2 {
-            let x = 40 + dx;
-            let z = 40 + dz;
-            assert_eq!(
-                column(&world_a, x, z, 4..=6),
-                column(&world_b, x, z, 4..=6),
-                "mismatch at ({x}, {z})"
-            );
-        }
-    }
 }
 
 #[test]
@@ -519,19 +587,12 @@ fn invariance_many_sand_columns_shuffled() {
         assert_eq!(world_a.get_block(landed), block::SAND);
         assert_eq!(world_b.get_block(landed), block::SAND);
         assert_eq!(world_c.get_block(landed), block::SAND);
-
-        // Full column comparison between all orderings.
-        assert_eq!(
-            column(&world_a, pos.x, pos.z, 0..=14),
-            column(&world_b, pos.x, pos.z, 0..=14),
-            "column mismatch (natural vs reversed) at ({}, {})", pos.x, pos.z,
-        );
-        assert_eq!(
-            column(&world_a, pos.x, pos.z, 0..=14),
-            column(&world_c, pos.x, pos.z, 0..=14),
-            "column mismatch (natural vs interleaved) at ({}, {})", pos.x, pos.z,
-        );
     }
+
+    // Full-world comparison between all orderings.
+    assert_eq!(world_a.diff(&world_b), [], "natural vs reversed");
+    assert_eq!(world_a.diff(&world_c), [], "natural vs interleaved");
+    assert_eq!(world_a.content_hash(), world_c.content_hash());
 }
 
 // ---------------------------------------------------------------------------
@@ -597,7 +658,7 @@ fn torch_lights_surrounding_area() {
     // Notify neighbors (same as connection handler does).
     for nb in pos.neighbors() {
         graph.insert(
-            Event { payload: EventPayload::BlockNotify { pos: nb } },
+            Event { payload: EventPayload::BlockNotify { pos: nb, from: None } },
             vec![root],
         );
     }
@@ -669,7 +730,7 @@ fn parallel_sand_falls_identically() {
     let world_seq = flat_world(2);
     let world_par = flat_world(2);
     let rules = ultimate_server::rules::standard();
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new().with_deterministic_order();
 
     let make_graph = || {
         let mut g = CausalGraph::new();
@@ -689,17 +750,14 @@ fn parallel_sand_falls_identically() {
     scheduler.run_until_quiet(&world_seq, &mut graph_seq, &rules, 100);
     scheduler.run_until_quiet_parallel(&world_par, &mut graph_par, &rules, 100);
 
-    assert_eq!(
-        column(&world_seq, 8, 8, 0..=12),
-        column(&world_par, 8, 8, 0..=12),
-    );
+    assert_eq!(world_seq.diff(&world_par), []);
     assert_eq!(world_par.get_block(BlockPos::new(8, 5, 8)), block::SAND);
 }
 
 #[test]
 fn parallel_many_independent_columns() {
     let rules = ultimate_server::rules::standard();
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new().with_deterministic_order();
 
     let positions: Vec<BlockPos> = vec![
         BlockPos::new(4, 12, 4),
@@ -734,19 +792,13 @@ fn parallel_many_independent_columns() {
     setup(&mut graph_par);
     scheduler.run_until_quiet_parallel(&world_par, &mut graph_par, &rules, 5000);
 
-    for &pos in &positions {
-        assert_eq!(
-            column(&world_seq, pos.x, pos.z, 0..=14),
-            column(&world_par, pos.x, pos.z, 0..=14),
-            "seq vs par mismatch at ({}, {})", pos.x, pos.z,
-        );
-    }
+    assert_eq!(world_seq.diff(&world_par), [], "seq vs par");
 }
 
 #[test]
 fn parallel_water_and_sand_independent() {
     let rules = ultimate_server::rules::standard();
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new().with_deterministic_order();
 
     let build_world = || {
         let world = flat_world(4);
@@ -791,23 +843,8 @@ fn parallel_water_and_sand_independent() {
     setup(&mut graph_par);
     scheduler.run_until_quiet_parallel(&world_par, &mut graph_par, &rules, 1000);
 
-    // Sand column must match.
-    assert_eq!(
-        column(&world_seq, 4, 4, 0..=12),
-        column(&world_par, 4, 4, 0..=12),
-    );
-    // Water region must match.
-    for dx in -2i64..=2 {
-        for dz in -2i64..=2 {
-            let x = 40 + dx;
-            let z = 40 + dz;
-            assert_eq!(
-                column(&world_seq, x, z, 4..=6),
-                column(&world_par, x, z, 4..=6),
-                "seq vs par mismatch at ({x}, {z})"
-            );
-        }
-    }
+    // Sand column and water region must match.
+    assert_eq!(world_seq.diff(&world_par), [], "seq vs par");
 }
 
 // ---------------------------------------------------------------------------
@@ -856,7 +893,7 @@ fn flowing_water_drains_when_source_removed() {
     for neighbor in source_pos.neighbors() {
         graph2.insert(
             Event {
-                payload: EventPayload::BlockNotify { pos: neighbor },
+                payload: EventPayload::BlockNotify { pos: neighbor, from: None },
             },
             vec![root],
         );
@@ -903,7 +940,7 @@ fn source_block_does_not_drain() {
     // Now notify the source as if a neighbor changed.
     let mut graph2 = CausalGraph::new();
     graph2.insert_root(Event {
-        payload: EventPayload::BlockNotify { pos: source_pos },
+        payload: EventPayload::BlockNotify { pos: source_pos, from: None },
     });
     scheduler.run_until_quiet(&world, &mut graph2, &rules, 100);
 
@@ -956,7 +993,7 @@ fn water_drains_behind_wall() {
         for neighbor in wall_pos.neighbors() {
             wall_graph.insert(
                 Event {
-                    payload: EventPayload::BlockNotify { pos: neighbor },
+                    payload: EventPayload::BlockNotify { pos: neighbor, from: None },
                 },
                 vec![root],
             );
@@ -1141,7 +1178,7 @@ fn flowing_lava_drains_when_source_removed() {
     for neighbor in source_pos.neighbors() {
         graph2.insert(
             Event {
-                payload: EventPayload::BlockNotify { pos: neighbor },
+                payload: EventPayload::BlockNotify { pos: neighbor, from: None },
             },
             vec![root],
         );
@@ -1184,7 +1221,7 @@ fn lava_source_does_not_drain() {
     // Notify the source as if a neighbor changed.
     let mut graph2 = CausalGraph::new();
     graph2.insert_root(Event {
-        payload: EventPayload::BlockNotify { pos: source_pos },
+        payload: EventPayload::BlockNotify { pos: source_pos, from: None },
     });
     scheduler.run_until_quiet(&world, &mut graph2, &rules, 100);
 
@@ -1192,6 +1229,181 @@ fn lava_source_does_not_drain() {
     assert_eq!(world.get_block(source_pos), block::LAVA);
 }
 
+// ---------------------------------------------------------------------------
+// Lava meeting water: sources harden to obsidian (or stone under water),
+// flowing lava to cobblestone, whichever fluid arrives second.
+// ---------------------------------------------------------------------------
+
+/// A flat world with solid stone from y=5 to y=7, so fluids only go where
+/// a test carves air.
+fn sealed_world() -> World {
+    let world = flat_world(1);
+    for x in 0..16 {
+        for z in 0..16 {
+            for y in 5..=7 {
+                world.set_block(BlockPos::new(x, y, z), block::STONE);
+            }
+        }
+    }
+    world
+}
+
+/// Lay out the three interactions in sealed pockets, then place all the
+/// water as one batch of roots and run it under `order_fn`. Returns the
+/// world and a snapshot of the pockets.
+fn run_lava_water<F>(order_fn: F) -> (World, Vec<BlockId>)
+where
+    F: Fn(Vec<EventId>) -> Vec<EventId>,
+{
+    let world = sealed_world();
+    let rules = ultimate_server::rules::standard();
+
+    // A settled lava channel x=2..=5 at z=2; water arrives at x=6.
+    world.set_block(BlockPos::new(2, 5, 2), block::LAVA);
+    for d in 1..=3 {
+        world.set_block(BlockPos::new(2 + d, 5, 2), block::lava_flowing(d as u8));
+    }
+    // A lava source with water arriving beside it, and one with water
+    // arriving above it.
+    world.set_block(BlockPos::new(2, 5, 6), block::LAVA);
+    world.set_block(BlockPos::new(2, 5, 10), block::LAVA);
+
+    let mut graph = CausalGraph::new();
+    for pos in [BlockPos::new(6, 5, 2), BlockPos::new(3, 5, 6), BlockPos::new(2, 6, 10)] {
+        graph.insert_root(Event {
+            payload: EventPayload::BlockSet { pos, old: block::STONE, new: block::WATER },
+        });
+    }
+    run_with_order(&world, &mut graph, &rules, order_fn, 10_000);
+
+    let mut snap = Vec::new();
+    for x in 0..8i64 {
+        for y in 5..=6i64 {
+            for z in 0..12i64 {
+                snap.push(world.get_block(BlockPos::new(x, y, z)));
+            }
+        }
+    }
+    (world, snap)
+}
+
+#[test]
+fn lava_hardens_where_water_meets_it() {
+    let (world, _) = run_lava_water(|f| f);
+
+    // Flowing lava touching water → cobblestone; the rest of the channel
+    // is still fed by its source and stays.
+    assert_eq!(world.get_block(BlockPos::new(5, 5, 2)), block::COBBLESTONE);
+    assert_eq!(world.get_block(BlockPos::new(4, 5, 2)), block::lava_flowing(2));
+    assert_eq!(world.get_block(BlockPos::new(2, 5, 2)), block::LAVA);
+    assert_eq!(world.get_block(BlockPos::new(6, 5, 2)), block::WATER);
+
+    assert_eq!(world.get_block(BlockPos::new(2, 5, 6)), block::OBSIDIAN, "source beside water");
+    assert_eq!(world.get_block(BlockPos::new(2, 5, 10)), block::STONE, "source under water");
+}
+
+#[test]
+fn lava_placed_beside_water_hardens() {
+    let world = sealed_world();
+    let rules = ultimate_server::rules::standard();
+    let scheduler = Scheduler::new();
+    world.set_block(BlockPos::new(4, 5, 4), block::WATER);
+    world.set_block(BlockPos::new(4, 5, 5), block::AIR);
+
+    let mut graph = CausalGraph::new();
+    graph.insert_root(Event {
+        payload: EventPayload::BlockSet { pos: BlockPos::new(4, 5, 5), old: block::AIR, new: block::LAVA },
+    });
+    scheduler.run_until_quiet(&world, &mut graph, &rules, 1000);
+
+    assert_eq!(world.get_block(BlockPos::new(4, 5, 5)), block::OBSIDIAN);
+    assert_eq!(world.get_block(BlockPos::new(4, 5, 4)), block::WATER);
+}
+
+#[test]
+fn lava_water_interaction_is_order_invariant() {
+    let (_, natural) = run_lava_water(|f| f);
+    let (_, reversed) = run_lava_water(|mut f: Vec<EventId>| { f.reverse(); f });
+    assert_eq!(natural, reversed, "reversed frontier order must harden the same blocks");
+
+    for seed in [3u64, 7, 11] {
+        let (_, shuffled) = run_lava_water(move |mut f: Vec<EventId>| {
+            let mut state = seed;
+            for i in (1..f.len()).rev() {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let j = (state >> 33) as usize % (i + 1);
+                f.swap(i, j);
+            }
+            f
+        });
+        assert_eq!(natural, shuffled, "shuffled order (seed {seed}) must harden the same blocks");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Infinite water: flowing water between two sources over solid ground
+// becomes a source.
+// ---------------------------------------------------------------------------
+
+/// Carve `cells` out of a sealed world, place water sources at `sources`
+/// as one batch, and run it to quiescence.
+fn run_water_in(cells: &[BlockPos], sources: &[BlockPos]) -> World {
+    let world = sealed_world();
+    for &pos in cells {
+        world.set_block(pos, block::AIR);
+    }
+    let mut graph = CausalGraph::new();
+    for &pos in sources {
+        graph.insert_root(Event {
+            payload: EventPayload::BlockSet { pos, old: block::AIR, new: block::WATER },
+        });
+    }
+    Scheduler::new().run_until_quiet(&world, &mut graph, &ultimate_server::rules::standard(), 10_000);
+    world
+}
+
+#[test]
+fn two_by_two_pool_becomes_infinite() {
+    let cells: Vec<BlockPos> =
+        [(4, 4), (5, 4), (4, 5), (5, 5)].into_iter().map(|(x, z)| BlockPos::new(x, 5, z)).collect();
+    // Diagonal sources: each flowing corner sits between both of them.
+    let world = run_water_in(&cells, &[cells[0], cells[3]]);
+    for &pos in &cells {
+        assert_eq!(world.get_block(pos), block::WATER, "{pos:?} should be a source");
+    }
+
+    // A source taken out of the pool is refilled by the rest.
+    let mut graph = CausalGraph::new();
+    graph.insert_root(Event {
+        payload: EventPayload::BlockSet { pos: cells[0], old: block::WATER, new: block::AIR },
+    });
+    Scheduler::new().run_until_quiet(&world, &mut graph, &ultimate_server::rules::standard(), 10_000);
+    assert_eq!(world.get_block(cells[0]), block::WATER, "scooped cell refills as a source");
+}
+
+#[test]
+fn single_direction_flow_forms_no_source() {
+    // A channel fed from one end: every cell has at most one source
+    // neighbor.
+    let channel: Vec<BlockPos> = (2..=6).map(|x| BlockPos::new(x, 5, 2)).collect();
+    let world = run_water_in(&channel, &[channel[0]]);
+    assert_eq!(world.get_block(channel[1]), block::water_flowing(1));
+    assert_eq!(world.get_block(channel[4]), block::water_flowing(4));
+
+    // Sources two apart: each gap cell touches only one of them.
+    let gapped: Vec<BlockPos> = (2..=5).map(|x| BlockPos::new(x, 5, 6)).collect();
+    let world = run_water_in(&gapped, &[gapped[0], gapped[3]]);
+    assert_eq!(world.get_block(gapped[1]), block::water_flowing(1));
+    assert_eq!(world.get_block(gapped[2]), block::water_flowing(1));
+
+    // Between two sources but over a drop: the water falls instead.
+    let mut ledge: Vec<BlockPos> = (2..=4).map(|x| BlockPos::new(x, 6, 10)).collect();
+    ledge.push(BlockPos::new(3, 5, 10));
+    let world = run_water_in(&ledge, &[ledge[0], ledge[2]]);
+    assert_eq!(world.get_block(ledge[1]), block::water_flowing(1));
+    assert_eq!(world.get_block(ledge[3]), block::FluidKind::Water.falling());
+}
+
 // ---------------------------------------------------------------------------
 // Elevated water source drainage test
 // ---------------------------------------------------------------------------
@@ -1263,7 +1475,7 @@ fn elevated_water_source_drains_when_removed() {
     for neighbor in source_pos.neighbors() {
         graph2.insert(
             Event {
-                payload: EventPayload::BlockNotify { pos: neighbor },
+                payload: EventPayload::BlockNotify { pos: neighbor, from: None },
             },
             vec![root],
         );
@@ -1365,7 +1577,7 @@ fn interacting_water_fronts_are_confluent() {
     let world_par = flat_world(3);
     let mut graph_par = CausalGraph::with_pruning();
     let rules = ultimate_server::rules::standard();
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new().with_deterministic_order();
     for s in sources {
         graph_par.insert_root(Event {
             payload: EventPayload::BlockSet { pos: s, old: block::AIR, new: block::WATER },
@@ -1450,3 +1662,30 @@ fn pruned_cascade_matches_unpruned() {
         ultimate_server::event_bus::collect_block_changes(pruned.write_log()),
     );
 }
+
+#[test]
+fn projectile_hit_shatters_fragile_blocks_only() {
+    use ultimate_server::projectiles::{PROJECTILE_HIT, ProjectileKind};
+
+    let world = flat_world(1);
+    let rules = ultimate_server::rules::standard();
+    let scheduler = Scheduler::new();
+    let pot = block::block_id_from_name("decorated_pot").unwrap();
+    world.set_block(BlockPos::new(3, 5, 3), pot);
+    world.set_block(BlockPos::new(6, 5, 6), block::STONE);
+
+    let mut graph = CausalGraph::new();
+    for pos in [BlockPos::new(3, 5, 3), BlockPos::new(6, 5, 6)] {
+        graph.insert_root(Event {
+            payload: EventPayload::Custom {
+                kind: PROJECTILE_HIT,
+                pos,
+                data: std::sync::Arc::from([ProjectileKind::Arrow.code()]),
+            },
+        });
+    }
+    scheduler.run_until_quiet(&world, &mut graph, &rules, 1000);
+
+    assert_eq!(world.get_block(BlockPos::new(3, 5, 3)), block::AIR, "the pot shatters");
+    assert_eq!(world.get_block(BlockPos::new(6, 5, 6)), block::STONE);
+}
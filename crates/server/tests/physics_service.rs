@@ -18,23 +18,29 @@ use ultimate_engine::world::World;
 
 use ultimate_server::block;
 use ultimate_server::event_bus::ChangeSource;
+use ultimate_server::pending::PendingEvents;
 use ultimate_server::physics::{self, BlockAction};
 
-/// Flat world: stone y=0..=3, dirt at y=4, across a few chunks.
+/// Stone y=0..=3, dirt at y=4.
+fn flat_chunk() -> Chunk {
+    let mut chunk = Chunk::new();
+    for x in 0..16u8 {
+        for z in 0..16u8 {
+            for y in 0..4i64 {
+                chunk.set_block(LocalBlockPos { x, y, z }, BlockId::new(1));
+            }
+            chunk.set_block(LocalBlockPos { x, y: 4, z }, block::DIRT);
+        }
+    }
+    chunk
+}
+
+/// Flat world across a few chunks.
 fn flat_world(radius: i32) -> Arc<World> {
     let world = World::new();
     for cx in -radius..radius {
         for cz in -radius..radius {
-            let mut chunk = Chunk::new();
-            for x in 0..16u8 {
-                for z in 0..16u8 {
-                    for y in 0..4i64 {
-                        chunk.set_block(LocalBlockPos { x, y, z }, BlockId::new(1));
-                    }
-                    chunk.set_block(LocalBlockPos { x, y: 4, z }, block::DIRT);
-                }
-            }
-            world.insert_chunk(ChunkPos::new(cx, cz), chunk);
+            world.insert_chunk(ChunkPos::new(cx, cz), flat_chunk());
         }
     }
     Arc::new(world)
@@ -364,3 +370,196 @@ fn raw_event_submission_runs_cascades() {
         "raw-event sand should cascade to the surface",
     );
 }
+
+#[test]
+fn step_budget_streams_a_cascade_in_slices() {
+    let world = flat_world(1);
+    let bus = ultimate_server::event_bus::SpatialBus::new();
+    let (mut sub, mut rx) = bus.subscribe();
+    sub.set_view(0, 0, 4);
+    let handle = physics::start(
+        Arc::clone(&world),
+        ultimate_server::rules::standard,
+        Arc::clone(&bus),
+        None,
+        physics::PhysicsOptions { workers: 1, step_budget: 1, ..Default::default() },
+    );
+
+    // Four sand blocks in one submission: one cascade, many slices.
+    handle.submit_events(
+        (0..4)
+            .map(|x| Event {
+                payload: EventPayload::BlockSet { pos: BlockPos::new(x, 12, 3), old: block::AIR, new: block::SAND },
+            })
+            .collect(),
+    );
+    assert!(wait_quiet(&handle));
+    for x in 0..4 {
+        assert_eq!(world.get_block(BlockPos::new(x, 5, 3)), block::SAND);
+    }
+
+    // Each step executes one event, so every published batch is at most
+    // one change, and the fall arrives as many batches rather than one.
+    let mut batches = 0;
+    while let Ok(msg) = rx.try_recv() {
+        if let ultimate_server::event_bus::SpatialMsg::World(batch) = &*msg {
+            assert!(batch.changes.len() <= 1, "step published {} changes", batch.changes.len());
+            batches += 1;
+        }
+    }
+    assert!(batches >= 4 * 7, "one batch per sand write, got {batches}");
+}
+
+#[test]
+fn border_water_resumes_when_chunk_loads() {
+    // Loaded area is x in -16..16. Water spreading east must stop at the
+    // edge rather than pour into the unloaded chunk, then carry on by
+    // itself once that chunk is inserted — no further submissions.
+    let world = flat_world(1);
+    let bus_tx = ultimate_server::event_bus::SpatialBus::new();
+    let handle = physics::start(
+        Arc::clone(&world),
+        ultimate_server::rules::standard,
+        bus_tx,
+        None,
+        physics::PhysicsOptions { workers: 4, ..Default::default() },
+    );
+
+    handle.submit_action(BlockAction {
+        pos: BlockPos::new(13, 8, 0),
+        old: block::AIR,
+        new: block::WATER,
+        update_stairs: false,
+    });
+    assert!(wait_quiet(&handle));
+    assert_ne!(world.get_block(BlockPos::new(15, 5, 0)), block::AIR, "water reaches the edge");
+    assert!(!world.has_chunk(ChunkPos::new(1, 0)), "no empty chunk created past the edge");
+
+    world.insert_chunk(ChunkPos::new(1, 0), flat_chunk());
+    assert!(
+        wait_for(|| world.get_block(BlockPos::new(16, 5, 0)) != block::AIR),
+        "parked spread should resume into the loaded chunk",
+    );
+}
+
+#[test]
+fn parked_water_resumes_after_a_restart() {
+    // Water stops at the edge of the loaded area and its next writes are
+    // saved with the world. On the next run, with the chunk past the edge
+    // already loaded, they replay at startup and the flow carries on.
+    let dir = std::env::temp_dir().join("ultimate_mc_test_pending_restart");
+    let _ = std::fs::remove_dir_all(&dir);
+    let world = flat_world(1);
+    let parked = Arc::new(PendingEvents::empty(&dir));
+    let handle = physics::start(
+        Arc::clone(&world),
+        ultimate_server::rules::standard,
+        ultimate_server::event_bus::SpatialBus::new(),
+        None,
+        physics::PhysicsOptions { workers: 4, parked: Arc::clone(&parked), ..Default::default() },
+    );
+    handle.submit_action(BlockAction {
+        pos: BlockPos::new(13, 8, 0),
+        old: block::AIR,
+        new: block::WATER,
+        update_stairs: false,
+    });
+    assert!(wait_quiet(&handle));
+    assert!(!parked.is_empty(), "the flow waits in the store");
+    parked.save().unwrap();
+
+    // The restarted world: the saved edits, and the next chunk loaded.
+    let restarted = flat_world(1);
+    let edited = world.get_chunk(&ChunkPos::new(0, 0)).unwrap().clone();
+    restarted.insert_chunk(ChunkPos::new(0, 0), edited);
+    restarted.insert_chunk(ChunkPos::new(1, 0), flat_chunk());
+    let _handle = physics::start(
+        Arc::clone(&restarted),
+        ultimate_server::rules::standard,
+        ultimate_server::event_bus::SpatialBus::new(),
+        None,
+        physics::PhysicsOptions { workers: 4, parked: Arc::new(PendingEvents::load(&dir).unwrap()), ..Default::default() },
+    );
+    assert!(
+        wait_for(|| restarted.get_block(BlockPos::new(16, 5, 0)) != block::AIR),
+        "saved events should replay into the loaded chunk",
+    );
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn water_waits_outside_simulation_distance() {
+    // Everything is loaded, but only the player's chunk simulates: water
+    // spreading east stops at x = 15 until a player comes near x = 16.
+    let world = flat_world(2);
+    let tickets = Arc::new(ultimate_server::tickets::SimulationTickets::new(0));
+    tickets.set_player(1, 8.0, 8.0);
+    let handle = physics::start(
+        Arc::clone(&world),
+        ultimate_server::rules::standard,
+        ultimate_server::event_bus::SpatialBus::new(),
+        None,
+        physics::PhysicsOptions { workers: 4, simulation: Some(Arc::clone(&tickets)), ..Default::default() },
+    );
+
+    handle.submit_action(BlockAction {
+        pos: BlockPos::new(13, 8, 0),
+        old: block::AIR,
+        new: block::WATER,
+        update_stairs: false,
+    });
+    assert!(wait_quiet(&handle));
+    assert_ne!(world.get_block(BlockPos::new(15, 5, 0)), block::AIR, "water reaches the edge");
+    assert_eq!(world.get_block(BlockPos::new(16, 5, 0)), block::AIR, "but not past it");
+
+    tickets.set_player(2, 24.0, 8.0);
+    assert!(
+        wait_for(|| world.get_block(BlockPos::new(16, 5, 0)) != block::AIR),
+        "parked spread should resume once the chunk simulates",
+    );
+}
+
+/// A runaway rule: a block at y = 10 flips between two ids forever.
+fn flip_flop(_world: &ultimate_engine::world::WorldView, payload: &EventPayload) -> Vec<Event> {
+    match payload {
+        EventPayload::BlockSet { pos, new, .. } if pos.y == 10 && (*new == block::SAND || *new == block::DIRT) => {
+            let next = if *new == block::SAND { block::DIRT } else { block::SAND };
+            vec![Event { payload: EventPayload::BlockSet { pos: *pos, old: *new, new: next } }]
+        }
+        _ => vec![],
+    }
+}
+
+fn runaway_rules() -> ultimate_engine::rules::RuleSet {
+    let mut rules = ultimate_engine::rules::RuleSet::new();
+    rules.add(flip_flop);
+    rules
+}
+
+#[test]
+fn watchdog_aborts_a_cascade_that_never_settles() {
+    let world = flat_world(1);
+    let dashboard = Arc::new(ultimate_server::dashboard::DashboardState::new(Arc::clone(&world)));
+    let handle = physics::start(
+        Arc::clone(&world),
+        runaway_rules,
+        ultimate_server::event_bus::SpatialBus::new(),
+        Some(Arc::clone(&dashboard)),
+        physics::PhysicsOptions {
+            workers: 1,
+            rebalance: false,
+            watchdog: ultimate_server::watchdog::CascadeBudget { max_events: 500, max_time: Duration::ZERO },
+            ..Default::default()
+        },
+    );
+    let flip = BlockPos::new(3, 10, 3);
+    handle.submit_events(vec![Event { payload: EventPayload::BlockSet { pos: flip, old: block::AIR, new: block::SAND } }]);
+    assert!(wait_quiet(&handle), "the watchdog ends the cascade");
+    assert!((500..600).contains(&handle.executed_total()));
+    assert_eq!(dashboard.metrics_snapshot().cascades_aborted, 1);
+
+    // The worker carries on with the next batch.
+    let after = BlockPos::new(5, 10, 5);
+    handle.submit_events(vec![Event { payload: EventPayload::BlockSet { pos: after, old: block::AIR, new: block::STONE } }]);
+    assert!(wait_for(|| world.get_block(after) == block::STONE));
+}
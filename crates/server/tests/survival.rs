@@ -0,0 +1,454 @@
+//! Survival gameplay through real connections: a server on a loopback
+//! port with a superflat world, and headless clients that join it the
+//! way `examples/load_test.rs` does, then send the packets a survival
+//! player would.
+//!
+//! Clients read everything the server sends and skip what a test doesn't
+//! ask for; waiting for one packet gives up after a few seconds.
+
+use std::io::Cursor;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use azalea_block::BlockState;
+use azalea_core::direction::Direction;
+use azalea_core::position::BlockPos;
+use azalea_protocol::packets::game::s_interact::{ActionType, InteractionHand};
+use azalea_inventory::ItemStack;
+use azalea_protocol::packets::config::{
+    ClientboundConfigPacket, ServerboundFinishConfiguration, ServerboundSelectKnownPacks,
+};
+use azalea_protocol::packets::game::c_game_event::EventType;
+use azalea_protocol::packets::game::s_player_action::{Action, ServerboundPlayerAction};
+use azalea_protocol::packets::game::{
+    ClientboundGamePacket, ServerboundAcceptTeleportation, ServerboundChatCommand, ServerboundCommandSuggestion,
+    ServerboundGamePacket, ServerboundInteract, ServerboundSetCreativeModeSlot, ServerboundSwing,
+};
+use azalea_protocol::packets::handshake::{ServerboundHandshakePacket, ServerboundIntention};
+use azalea_protocol::packets::login::{
+    ClientboundLoginPacket, ServerboundHello, ServerboundLoginAcknowledged, ServerboundLoginPacket,
+};
+use azalea_protocol::packets::{ClientIntention, Packet, PROTOCOL_VERSION};
+use azalea_protocol::read::read_packet;
+use azalea_protocol::write::write_packet;
+use azalea_registry::builtin::{BlockKind, ItemKind};
+use azalea_world::MinecraftEntityId;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use ultimate_engine::world::World;
+
+use ultimate_server::access::AccessLists;
+use ultimate_server::block;
+use ultimate_server::combat;
+use ultimate_server::config::ServerConfig;
+use ultimate_server::dashboard::DashboardState;
+use ultimate_server::effects::NO_CRACKS;
+use ultimate_server::event_bus::SpatialBus;
+use ultimate_server::motd::ServerStatus;
+use ultimate_server::net::chunk_cache::ChunkCache;
+use ultimate_server::net::chunk_sender::ChunkSender;
+use ultimate_server::net::listener::ServerContext;
+use ultimate_server::persistence::{self, WorldStorage};
+use ultimate_server::physics::{self, PhysicsOptions};
+use ultimate_server::player_registry::PlayerRegistry;
+use ultimate_server::pools::Pools;
+use ultimate_server::projectiles::Projectiles;
+use ultimate_server::recipes::RecipeBook;
+use ultimate_server::shutdown::Shutdown;
+use ultimate_server::skins::SkinResolver;
+use ultimate_server::trading::Trades;
+use ultimate_server::worldborder::WorldBorder;
+use ultimate_server::worldgen::WorldGen;
+use ultimate_server::worldgen::biome::Biome;
+use ultimate_server::worldgen::pipeline::FlatPipeline;
+
+/// How long a client waits for the packet a test expects.
+const PATIENCE: Duration = Duration::from_secs(5);
+
+/// The stone under the spawn point: the flat world is stone from y=60 to
+/// y=63, and players spawn at (8, 64, 8).
+const UNDERFOOT: BlockPos = BlockPos { x: 8, y: 63, z: 8 };
+
+/// Hotbar slot 0 in the player's inventory window.
+const HOTBAR_0: u16 = 36;
+
+struct Server {
+    ctx: ServerContext,
+    addr: SocketAddr,
+}
+
+impl Server {
+    /// Every service a connection uses, on a fresh world in a temp dir
+    /// named after the test, and a listener handing connections to them.
+    async fn start(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("ultimate_mc_test_{name}"));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut config = ServerConfig::default();
+        config.network.view_distance = 2;
+        config.world.dir = dir.join("world");
+        config.access.dir = dir.clone();
+        config.crafting.data_dir = dir.join("data");
+        config.trading.file = dir.join("trades.json");
+        config.skins.mojang = false;
+
+        let world = Arc::new(World::new());
+        let worldgen: Arc<dyn WorldGen> = Arc::new(FlatPipeline {
+            min_y: 60,
+            layers: vec![(block::STONE, 4)],
+            biome: Biome::Plains,
+        });
+        let dashboard = Arc::new(DashboardState::new(Arc::clone(&world)));
+        let spatial = SpatialBus::new();
+        let pools = Arc::new(Pools::new(&config.physics, Arc::clone(&dashboard)).unwrap());
+        let physics = physics::start(
+            Arc::clone(&world),
+            ultimate_server::rules::standard,
+            Arc::clone(&spatial),
+            None,
+            PhysicsOptions { workers: 1, ..Default::default() },
+        );
+        let registry = Arc::new(PlayerRegistry::new(Arc::clone(&spatial)));
+        let storage = Arc::new(WorldStorage::new(
+            config.world.dir.clone(),
+            0,
+            0,
+            Arc::clone(&worldgen),
+            persistence::new_delta_store(),
+        ));
+        let chunks = Arc::new(ChunkSender::start(
+            Arc::clone(&world),
+            Arc::clone(&worldgen),
+            Arc::new(ChunkCache::attach(&world)),
+            Arc::clone(&pools),
+        ));
+        let ctx = ServerContext {
+            dashboard,
+            spatial: Arc::clone(&spatial),
+            worldgen,
+            physics,
+            storage,
+            access: Arc::new(AccessLists::load(&config.access.dir, false).unwrap()),
+            pools,
+            skins: Arc::new(SkinResolver::new(&config.skins).unwrap()),
+            chunks,
+            projectiles: Projectiles::new(Arc::clone(&registry), spatial),
+            border: Arc::new(WorldBorder::new(&config.border)),
+            status: Arc::new(ServerStatus::new(&config, &dir.join("config.yaml"))),
+            recipes: Arc::new(RecipeBook::default()),
+            trades: Arc::new(Trades::load(&config.trading.file).unwrap()),
+            shutdown: Shutdown::new(),
+            world,
+            registry,
+            config: Arc::new(config),
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepting = ctx.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(ultimate_server::net::connection::handle(stream, accepting.clone()));
+            }
+        });
+        Self { ctx, addr }
+    }
+
+    /// Let `name` run `/gamemode`.
+    fn op(&self, name: &str) {
+        let player = self.ctx.registry.find_by_name(name).expect("player is online");
+        self.ctx.access.op(player.uuid, name, 2).unwrap();
+    }
+
+    fn block(&self, pos: BlockPos) -> ultimate_engine::world::block::BlockId {
+        let pos = ultimate_engine::world::position::BlockPos::new(pos.x as i64, pos.y as i64, pos.z as i64);
+        self.ctx.world.get_block(pos)
+    }
+
+    /// Wait up to [`PATIENCE`] for `pos` to hold `expected`; physics
+    /// applies breaks on its own threads.
+    async fn settles(&self, pos: BlockPos, expected: ultimate_engine::world::block::BlockId) -> bool {
+        let deadline = Instant::now() + PATIENCE;
+        while Instant::now() < deadline {
+            if self.block(pos) == expected {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        false
+    }
+}
+
+struct Client {
+    read: OwnedReadHalf,
+    write: OwnedWriteHalf,
+    buf: Cursor<Vec<u8>>,
+    next_seq: u32,
+    entity_id: i32,
+}
+
+impl Client {
+    /// Handshake, offline login and configuration, then into play until
+    /// the server is taking packets from its main loop.
+    async fn join(addr: SocketAddr, name: &str) -> Self {
+        let (read, write) = TcpStream::connect(addr).await.unwrap().into_split();
+        let mut client = Self { read, write, buf: Cursor::new(Vec::new()), next_seq: 1, entity_id: 0 };
+
+        let intent: ServerboundHandshakePacket = ServerboundIntention {
+            protocol_version: PROTOCOL_VERSION,
+            hostname: "localhost".into(),
+            port: addr.port(),
+            intention: ClientIntention::Login,
+        }
+        .into_variant();
+        client.write(&intent).await;
+        let hello: ServerboundLoginPacket =
+            ServerboundHello { name: name.into(), profile_id: uuid::Uuid::nil() }.into_variant();
+        client.write(&hello).await;
+        loop {
+            match client.read::<ClientboundLoginPacket>().await {
+                ClientboundLoginPacket::LoginFinished(_) => break,
+                ClientboundLoginPacket::LoginDisconnect(d) => panic!("refused: {:?}", d.reason),
+                _ => {}
+            }
+        }
+        let ack: ServerboundLoginPacket = ServerboundLoginAcknowledged.into_variant();
+        client.write(&ack).await;
+
+        loop {
+            match client.read::<ClientboundConfigPacket>().await {
+                ClientboundConfigPacket::SelectKnownPacks(p) => {
+                    let known = ServerboundSelectKnownPacks { known_packs: p.known_packs };
+                    client.write(&known.into_variant()).await;
+                }
+                ClientboundConfigPacket::FinishConfiguration(_) => {
+                    client.write(&ServerboundFinishConfiguration.into_variant()).await;
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let mut entity_id = 0;
+        let teleport = client
+            .expect(|packet| match packet {
+                ClientboundGamePacket::Login(login) => {
+                    entity_id = login.player_id.0;
+                    None
+                }
+                ClientboundGamePacket::PlayerPosition(p) => Some(p.id),
+                _ => None,
+            })
+            .await;
+        client.entity_id = entity_id;
+        client.send(ServerboundAcceptTeleportation { id: teleport }).await;
+        client.sync().await;
+        client
+    }
+
+    async fn write<P: azalea_protocol::packets::ProtocolPacket + std::fmt::Debug>(&mut self, packet: &P) {
+        write_packet(packet, &mut self.write, None, &mut None).await.unwrap();
+    }
+
+    async fn read<P: azalea_protocol::packets::ProtocolPacket + std::fmt::Debug>(&mut self) -> P {
+        let mut cipher = None;
+        let read = read_packet::<P, _>(&mut self.read, &mut self.buf, None, &mut cipher);
+        tokio::time::timeout(PATIENCE, read).await.expect("server went quiet").unwrap()
+    }
+
+    async fn send(&mut self, packet: impl Packet<ServerboundGamePacket>) {
+        self.write(&packet.into_variant()).await;
+    }
+
+    /// Read until `pick` takes a packet; panics after [`PATIENCE`].
+    async fn expect<T>(&mut self, mut pick: impl FnMut(ClientboundGamePacket) -> Option<T>) -> T {
+        let deadline = Instant::now() + PATIENCE;
+        while Instant::now() < deadline {
+            if let Some(found) = pick(self.read::<ClientboundGamePacket>().await) {
+                return found;
+            }
+        }
+        panic!("the expected packet never came");
+    }
+
+    /// Wait until the server has handled everything sent so far: it
+    /// answers tab completions in order with the rest.
+    async fn sync(&mut self) {
+        let id = self.next_seq;
+        self.next_seq += 1;
+        self.send(ServerboundCommandSuggestion { id, command: "/".into() }).await;
+        self.expect(|packet| match packet {
+            ClientboundGamePacket::CommandSuggestions(s) if s.id == id => Some(()),
+            _ => None,
+        })
+        .await;
+    }
+
+    /// `/gamemode <mode>`, waiting for the switch.
+    async fn gamemode(&mut self, mode: &str) {
+        self.send(ServerboundChatCommand { command: format!("gamemode {mode}") }).await;
+        self.expect(|packet| match packet {
+            ClientboundGamePacket::GameEvent(e) if e.event == EventType::ChangeGameMode => Some(()),
+            _ => None,
+        })
+        .await;
+    }
+
+    /// Creative players set their own slots; this is how a tool gets
+    /// into a hand before switching to survival.
+    async fn hold(&mut self, kind: ItemKind) {
+        self.send(ServerboundSetCreativeModeSlot { slot_num: HOTBAR_0, item_stack: ItemStack::new(kind, 1) })
+            .await;
+        self.sync().await;
+    }
+
+    async fn dig(&mut self, action: Action, pos: BlockPos) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.send(ServerboundPlayerAction { action, pos, direction: Direction::Up, seq }).await;
+    }
+}
+
+fn is_stone(state: BlockState) -> bool {
+    BlockKind::from(state) == BlockKind::Stone
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_survival_digging_takes_the_tools_time_and_wears_it() {
+    let server = Server::start("survival_digging").await;
+    let mut digger = Client::join(server.addr, "Digger").await;
+    digger.hold(ItemKind::IronPickaxe).await;
+    server.op("Digger");
+    digger.gamemode("survival").await;
+
+    // An iron pickaxe takes 8 ticks on stone, and the client may finish
+    // at 70% of that: 280 ms. Stopping at once is too early, so the
+    // server puts the block back.
+    digger.dig(Action::StartDestroyBlock, UNDERFOOT).await;
+    digger.dig(Action::StopDestroyBlock, UNDERFOOT).await;
+    let restored = digger
+        .expect(|packet| match packet {
+            ClientboundGamePacket::BlockUpdate(u) if u.pos == UNDERFOOT => Some(u.block_state),
+            _ => None,
+        })
+        .await;
+    assert!(is_stone(restored));
+    digger.sync().await;
+    assert_eq!(server.block(UNDERFOOT), block::STONE);
+
+    // Waiting the dig out breaks it, and costs the pickaxe one use.
+    digger.dig(Action::StartDestroyBlock, UNDERFOOT).await;
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    digger.dig(Action::StopDestroyBlock, UNDERFOOT).await;
+    let worn = digger
+        .expect(|packet| match packet {
+            ClientboundGamePacket::ContainerSetSlot(s) if s.slot == HOTBAR_0 => Some(s.item_stack),
+            _ => None,
+        })
+        .await;
+    assert_eq!(worn.kind(), ItemKind::IronPickaxe);
+    assert_eq!(ultimate_server::items::damage(&worn), 1);
+    assert!(server.settles(UNDERFOOT, block::AIR).await);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_survival_digging_without_the_right_tool_is_slower() {
+    let server = Server::start("survival_digging_by_hand").await;
+    let mut digger = Client::join(server.addr, "Digger").await;
+    server.op("Digger");
+    digger.gamemode("survival").await;
+
+    // Bare hands take stone 150 ticks (5.25 s at 70%): the pickaxe's
+    // 400 ms isn't nearly enough, and nothing wears.
+    digger.dig(Action::StartDestroyBlock, UNDERFOOT).await;
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    digger.dig(Action::StopDestroyBlock, UNDERFOOT).await;
+    digger
+        .expect(|packet| match packet {
+            ClientboundGamePacket::BlockUpdate(u) if u.pos == UNDERFOOT => Some(()),
+            ClientboundGamePacket::ContainerSetSlot(s) => panic!("bare hands wore: {s:?}"),
+            _ => None,
+        })
+        .await;
+    digger.sync().await;
+    assert_eq!(server.block(UNDERFOOT), block::STONE);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_others_see_survival_digging_crack_the_block() {
+    let server = Server::start("survival_cracks").await;
+    let mut digger = Client::join(server.addr, "Digger").await;
+    let mut watcher = Client::join(server.addr, "Watcher").await;
+    digger.hold(ItemKind::IronPickaxe).await;
+    server.op("Digger");
+    digger.gamemode("survival").await;
+
+    let cracks = |breaker: i32| {
+        move |packet| match packet {
+            ClientboundGamePacket::BlockDestruction(d) if d.id.0 == breaker && d.pos == UNDERFOOT => Some(d.progress),
+            _ => None,
+        }
+    };
+    digger.dig(Action::StartDestroyBlock, UNDERFOOT).await;
+    assert_eq!(watcher.expect(cracks(digger.entity_id)).await, 0);
+
+    // The digger's client swings every tick; halfway through, the cracks
+    // have grown.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    digger.send(ServerboundSwing { hand: InteractionHand::MainHand }).await;
+    let stage = watcher.expect(cracks(digger.entity_id)).await;
+    assert!((1..NO_CRACKS).contains(&stage), "stage {stage}");
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    digger.dig(Action::StopDestroyBlock, UNDERFOOT).await;
+    assert_eq!(watcher.expect(cracks(digger.entity_id)).await, NO_CRACKS);
+    assert!(server.settles(UNDERFOOT, block::AIR).await);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_survival_players_fight_and_creative_ones_dont() {
+    let server = Server::start("survival_combat").await;
+    let mut attacker = Client::join(server.addr, "Attacker").await;
+    let mut victim = Client::join(server.addr, "Victim").await;
+    for (client, name) in [(&mut attacker, "Attacker"), (&mut victim, "Victim")] {
+        server.op(name);
+        client.gamemode("survival").await;
+    }
+    let health = |packet| match packet {
+        ClientboundGamePacket::SetHealth(h) => Some(h.health),
+        _ => None,
+    };
+
+    // Both spawn at the same spot, well within reach; a fist deals 1.
+    attacker.send(attack(victim.entity_id)).await;
+    assert_eq!(victim.expect(health).await, 19.0);
+
+    // A creative player takes no damage. Player events reach the victim
+    // in order, so a hit that landed would show before the switch back.
+    victim.gamemode("creative").await;
+    tokio::time::sleep(combat::INVULNERABILITY).await;
+    attacker.send(attack(victim.entity_id)).await;
+    attacker.sync().await;
+    victim.send(ServerboundChatCommand { command: "gamemode survival".into() }).await;
+    victim
+        .expect(|packet| match packet {
+            ClientboundGamePacket::SetHealth(h) => panic!("a creative player was hurt: {h:?}"),
+            ClientboundGamePacket::GameEvent(e) if e.event == EventType::ChangeGameMode => Some(()),
+            _ => None,
+        })
+        .await;
+
+    // Back in survival, the next full swing lands.
+    tokio::time::sleep(combat::INVULNERABILITY).await;
+    attacker.send(attack(victim.entity_id)).await;
+    assert_eq!(victim.expect(health).await, 18.0);
+}
+
+fn attack(target: i32) -> ServerboundInteract {
+    ServerboundInteract {
+        entity_id: MinecraftEntityId(target),
+        action: ActionType::Attack,
+        using_secondary_action: false,
+    }
+}